@@ -0,0 +1,124 @@
+/*
+ * C API for the Memetendo Unsafe Boy Advance emulator core.
+ *
+ * Link against the libmemetendo static or shared library built from the
+ * libmemetendo-capi crate (`cargo build -p libmemetendo-capi --release`).
+ *
+ * Instances are not thread-safe; each must only be used from the thread that
+ * created it. Different instances may be used from different threads.
+ */
+
+#ifndef MEMETENDO_H
+#define MEMETENDO_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Version of this API; compare with memetendo_api_version(). */
+#define MEMETENDO_API_VERSION 2
+
+#define MEMETENDO_SCREEN_WIDTH 240
+#define MEMETENDO_SCREEN_HEIGHT 160
+
+/* Key bits for memetendo_set_keys(); these match the order of KEYINPUT. */
+#define MEMETENDO_KEY_A (1 << 0)
+#define MEMETENDO_KEY_B (1 << 1)
+#define MEMETENDO_KEY_SELECT (1 << 2)
+#define MEMETENDO_KEY_START (1 << 3)
+#define MEMETENDO_KEY_RIGHT (1 << 4)
+#define MEMETENDO_KEY_LEFT (1 << 5)
+#define MEMETENDO_KEY_UP (1 << 6)
+#define MEMETENDO_KEY_DOWN (1 << 7)
+#define MEMETENDO_KEY_R (1 << 8)
+#define MEMETENDO_KEY_L (1 << 9)
+
+typedef enum MemetendoResult {
+    MEMETENDO_OK = 0,
+    /* A required pointer was null, or an argument was otherwise invalid. */
+    MEMETENDO_INVALID_ARGUMENT = -1,
+    MEMETENDO_INVALID_ROM_SIZE = -2,
+    /* memetendo_power_on() hasn't been successfully called yet. */
+    MEMETENDO_NOT_POWERED_ON = -3,
+    /* The save state is malformed or from an unsupported version. */
+    MEMETENDO_INVALID_STATE = -4,
+    MEMETENDO_BUFFER_TOO_SMALL = -5,
+    /* The size of the cartridge backup doesn't match any save type. */
+    MEMETENDO_INVALID_BACKUP = -6,
+} MemetendoResult;
+
+typedef struct MemetendoGba MemetendoGba;
+
+/*
+ * Called by memetendo_run_frame() with the frame's audio: `frames` pairs of
+ * interleaved left and right samples, at memetendo_audio_sample_rate() Hz.
+ * Not called if no audio was produced (e.g: if the game disabled sound).
+ * `samples` is only valid until the callback returns.
+ */
+typedef void (*MemetendoAudioCallback)(void *userdata, const int16_t *samples,
+                                       size_t frames);
+
+uint32_t memetendo_api_version(void);
+
+/* Creates an instance, which must be destroyed with memetendo_destroy(). */
+MemetendoGba *memetendo_create(void);
+/* Destroys an instance; does nothing if `gba` is null. */
+void memetendo_destroy(MemetendoGba *gba);
+
+/*
+ * Loads ROMs, copying the data. Takes effect from the next call to
+ * memetendo_power_on(). The BIOS ROM must be 16 KiB, and the cartridge ROM
+ * 32 MiB at most.
+ */
+MemetendoResult memetendo_load_bios(MemetendoGba *gba, const uint8_t *data,
+                                    size_t len);
+MemetendoResult memetendo_load_rom(MemetendoGba *gba, const uint8_t *data,
+                                   size_t len);
+
+/*
+ * Powers on the system with the loaded ROMs. `backup_data` is the initial
+ * contents of the cartridge's save memory (e.g: from a .sav file), or null to
+ * detect the save type from the ROM instead.
+ */
+MemetendoResult memetendo_power_on(MemetendoGba *gba,
+                                   const uint8_t *backup_data,
+                                   size_t backup_len, bool skip_bios);
+MemetendoResult memetendo_reset(MemetendoGba *gba, bool skip_bios);
+
+/* Emulates until the next frame has been drawn. */
+MemetendoResult memetendo_run_frame(MemetendoGba *gba);
+
+/*
+ * Returns the last frame as MEMETENDO_SCREEN_WIDTH * MEMETENDO_SCREEN_HEIGHT
+ * RGBA pixels (4 bytes each). The pointer is valid until `gba` is destroyed.
+ */
+const uint8_t *memetendo_framebuffer(const MemetendoGba *gba);
+
+/* Sets the audio callback, or disables audio output if `callback` is null. */
+void memetendo_set_audio_callback(MemetendoGba *gba,
+                                  MemetendoAudioCallback callback,
+                                  void *userdata);
+uint32_t memetendo_audio_sample_rate(void);
+
+/* Sets the pressed keys (MEMETENDO_KEY_* bits) for the next frames. */
+void memetendo_set_keys(MemetendoGba *gba, uint16_t keys);
+
+/*
+ * Saves the system's state (excluding ROMs) into `buf`. The state's size is
+ * written to `out_len` (if not null), even if `buf` is too small; pass a null
+ * `buf` to query the size.
+ */
+MemetendoResult memetendo_save_state(const MemetendoGba *gba, uint8_t *buf,
+                                     size_t len, size_t *out_len);
+MemetendoResult memetendo_load_state(MemetendoGba *gba, const uint8_t *data,
+                                     size_t len);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* MEMETENDO_H */
@@ -0,0 +1,384 @@
+//! C API for the emulator core; see `include/memetendo.h` for documentation.
+//!
+//! Any changes to the exported functions or types must be reflected in the header, and must keep
+//! compatibility with existing callers (bump `API_VERSION` when adding to the API).
+
+#![warn(clippy::pedantic)]
+
+use std::{ffi::c_void, mem::take, ptr, rc::Rc, slice};
+
+use libmemetendo::{
+    audio, bios,
+    cart::{self, Cartridge},
+    gba::Gba,
+    keypad::KeyState,
+    util::video::FrameBuffer,
+    video::{self, Dot},
+};
+
+const API_VERSION: u32 = 2;
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum MemetendoResult {
+    Ok = 0,
+    InvalidArgument = -1,
+    InvalidRomSize = -2,
+    NotPoweredOn = -3,
+    InvalidState = -4,
+    BufferTooSmall = -5,
+    InvalidBackup = -6,
+}
+
+pub type MemetendoAudioCallback =
+    Option<unsafe extern "C" fn(userdata: *mut c_void, samples: *const i16, frames: usize)>;
+
+struct VideoCallback {
+    buf: FrameBuffer<4>,
+    new_frame: bool,
+}
+
+impl video::Callback for VideoCallback {
+    fn put_dot(&mut self, x: u8, y: u8, dot: Dot) {
+        self.buf.put_dot(x, y, dot);
+    }
+
+    fn end_frame(&mut self, green_swap: bool) {
+        if green_swap {
+            self.buf.green_swap();
+        }
+        self.new_frame = true;
+    }
+
+    fn is_frame_skipping(&self) -> bool {
+        false
+    }
+}
+
+struct AudioCallback {
+    callback: MemetendoAudioCallback,
+    userdata: *mut c_void,
+    /// Interleaved stereo samples.
+    samples: Vec<i16>,
+}
+
+impl audio::Callback for AudioCallback {
+    fn push_sample(&mut self, sample: (i16, i16)) {
+        if self.callback.is_some() {
+            self.samples.extend([sample.0, sample.1]);
+        }
+    }
+
+    fn push_samples(&mut self, samples: &[(i16, i16)]) {
+        if self.callback.is_some() {
+            self.samples
+                .extend(samples.iter().flat_map(|&(l, r)| [l, r]));
+        }
+    }
+}
+
+impl AudioCallback {
+    fn flush(&mut self) {
+        if let (Some(callback), false) = (self.callback, self.samples.is_empty()) {
+            // SAFETY: the caller of memetendo_set_audio_callback guarantees the callback is safe to
+            // call with its userdata.
+            unsafe { callback(self.userdata, self.samples.as_ptr(), self.samples.len() / 2) };
+        }
+        self.samples.clear();
+    }
+}
+
+pub struct MemetendoGba {
+    gba: Option<Gba>,
+    bios_rom: Option<bios::Rom>,
+    cart_rom: Option<cart::Rom>,
+    video_cb: VideoCallback,
+    audio_cb: AudioCallback,
+    keys: u16,
+}
+
+/// Converts a possibly-null pointer and length from C into a slice.
+///
+/// # Safety
+///
+/// If `data` is not null, it must be valid for reads of `len` bytes.
+unsafe fn slice_from_raw<'a>(data: *const u8, len: usize) -> Option<&'a [u8]> {
+    if data.is_null() {
+        None
+    } else {
+        // SAFETY: guaranteed by the caller.
+        Some(unsafe { slice::from_raw_parts(data, len) })
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn memetendo_api_version() -> u32 {
+    API_VERSION
+}
+
+#[no_mangle]
+pub extern "C" fn memetendo_create() -> *mut MemetendoGba {
+    Box::into_raw(Box::new(MemetendoGba {
+        gba: None,
+        bios_rom: None,
+        cart_rom: None,
+        video_cb: VideoCallback {
+            buf: FrameBuffer::new(0xff),
+            new_frame: false,
+        },
+        audio_cb: AudioCallback {
+            callback: None,
+            userdata: ptr::null_mut(),
+            samples: Vec::new(),
+        },
+        keys: 0,
+    }))
+}
+
+/// # Safety
+///
+/// `gba` must be null or a pointer returned by `memetendo_create` that has not been destroyed.
+#[no_mangle]
+pub unsafe extern "C" fn memetendo_destroy(gba: *mut MemetendoGba) {
+    if !gba.is_null() {
+        // SAFETY: guaranteed by the caller.
+        drop(unsafe { Box::from_raw(gba) });
+    }
+}
+
+/// # Safety
+///
+/// `gba` must be a valid instance, and `data` must be valid for reads of `len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn memetendo_load_bios(
+    gba: *mut MemetendoGba,
+    data: *const u8,
+    len: usize,
+) -> MemetendoResult {
+    // SAFETY: guaranteed by the caller.
+    let Some(gba) = (unsafe { gba.as_mut() }) else {
+        return MemetendoResult::InvalidArgument;
+    };
+    // SAFETY: guaranteed by the caller.
+    let Some(data) = (unsafe { slice_from_raw(data, len) }) else {
+        return MemetendoResult::InvalidArgument;
+    };
+    let Ok(rom) = bios::Rom::new(Rc::from(data)) else {
+        return MemetendoResult::InvalidRomSize;
+    };
+
+    gba.bios_rom = Some(rom);
+    MemetendoResult::Ok
+}
+
+/// # Safety
+///
+/// `gba` must be a valid instance, and `data` must be valid for reads of `len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn memetendo_load_rom(
+    gba: *mut MemetendoGba,
+    data: *const u8,
+    len: usize,
+) -> MemetendoResult {
+    // SAFETY: guaranteed by the caller.
+    let Some(gba) = (unsafe { gba.as_mut() }) else {
+        return MemetendoResult::InvalidArgument;
+    };
+    // SAFETY: guaranteed by the caller.
+    let Some(data) = (unsafe { slice_from_raw(data, len) }) else {
+        return MemetendoResult::InvalidArgument;
+    };
+    let Ok(rom) = cart::Rom::new(Rc::from(data)) else {
+        return MemetendoResult::InvalidRomSize;
+    };
+
+    gba.cart_rom = Some(rom);
+    MemetendoResult::Ok
+}
+
+/// # Safety
+///
+/// `gba` must be a valid instance. `backup_data` must be null, or valid for reads of `backup_len`
+/// bytes.
+#[no_mangle]
+pub unsafe extern "C" fn memetendo_power_on(
+    gba: *mut MemetendoGba,
+    backup_data: *const u8,
+    backup_len: usize,
+    skip_bios: bool,
+) -> MemetendoResult {
+    // SAFETY: guaranteed by the caller.
+    let Some(gba) = (unsafe { gba.as_mut() }) else {
+        return MemetendoResult::InvalidArgument;
+    };
+    let (Some(bios_rom), Some(cart_rom)) = (&gba.bios_rom, &gba.cart_rom) else {
+        return MemetendoResult::InvalidArgument;
+    };
+
+    // SAFETY: guaranteed by the caller.
+    let cart = if let Some(backup) = unsafe { slice_from_raw(backup_data, backup_len) } {
+        let Ok(cart) = Cartridge::try_from_backup(cart_rom, Some(backup.into())) else {
+            return MemetendoResult::InvalidBackup;
+        };
+        cart
+    } else {
+        Cartridge::from(cart_rom.clone())
+    };
+
+    let mut new_gba = Gba::new(bios_rom.clone(), cart);
+    new_gba.reset(skip_bios);
+    gba.gba = Some(new_gba);
+
+    MemetendoResult::Ok
+}
+
+/// # Safety
+///
+/// `gba` must be a valid instance.
+#[no_mangle]
+pub unsafe extern "C" fn memetendo_reset(
+    gba: *mut MemetendoGba,
+    skip_bios: bool,
+) -> MemetendoResult {
+    // SAFETY: guaranteed by the caller.
+    let Some(gba) = (unsafe { gba.as_mut() }) else {
+        return MemetendoResult::InvalidArgument;
+    };
+    let Some(ref mut inner) = gba.gba else {
+        return MemetendoResult::NotPoweredOn;
+    };
+
+    inner.reset(skip_bios);
+    MemetendoResult::Ok
+}
+
+/// # Safety
+///
+/// `gba` must be a valid instance. The audio callback (if set) must be safe to call.
+#[no_mangle]
+pub unsafe extern "C" fn memetendo_run_frame(gba: *mut MemetendoGba) -> MemetendoResult {
+    // SAFETY: guaranteed by the caller.
+    let Some(gba) = (unsafe { gba.as_mut() }) else {
+        return MemetendoResult::InvalidArgument;
+    };
+    let MemetendoGba {
+        gba: Some(ref mut inner),
+        ref mut video_cb,
+        ref mut audio_cb,
+        keys,
+        ..
+    } = *gba
+    else {
+        return MemetendoResult::NotPoweredOn;
+    };
+
+    inner.keypad.set_state(KeyState::from_bits_truncate(keys));
+    while !take(&mut video_cb.new_frame) {
+        inner.step(video_cb, audio_cb);
+    }
+    audio_cb.flush();
+
+    MemetendoResult::Ok
+}
+
+/// # Safety
+///
+/// `gba` must be a valid instance.
+#[no_mangle]
+pub unsafe extern "C" fn memetendo_framebuffer(gba: *const MemetendoGba) -> *const u8 {
+    // SAFETY: guaranteed by the caller.
+    unsafe { gba.as_ref() }.map_or(ptr::null(), |gba| gba.video_cb.buf.0.as_ptr())
+}
+
+/// # Safety
+///
+/// `gba` must be a valid instance. `callback` must be safe to call with `userdata` from
+/// `memetendo_run_frame` for as long as it is set.
+#[no_mangle]
+pub unsafe extern "C" fn memetendo_set_audio_callback(
+    gba: *mut MemetendoGba,
+    callback: MemetendoAudioCallback,
+    userdata: *mut c_void,
+) {
+    // SAFETY: guaranteed by the caller.
+    if let Some(gba) = unsafe { gba.as_mut() } {
+        gba.audio_cb.callback = callback;
+        gba.audio_cb.userdata = userdata;
+        gba.audio_cb.samples.clear();
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn memetendo_audio_sample_rate() -> u32 {
+    audio::SAMPLE_FREQUENCY
+}
+
+/// # Safety
+///
+/// `gba` must be a valid instance.
+#[no_mangle]
+pub unsafe extern "C" fn memetendo_set_keys(gba: *mut MemetendoGba, keys: u16) {
+    // SAFETY: guaranteed by the caller.
+    if let Some(gba) = unsafe { gba.as_mut() } {
+        gba.keys = keys;
+    }
+}
+
+/// # Safety
+///
+/// `gba` must be a valid instance. `buf` must be null, or valid for writes of `len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn memetendo_save_state(
+    gba: *const MemetendoGba,
+    buf: *mut u8,
+    len: usize,
+    out_len: *mut usize,
+) -> MemetendoResult {
+    // SAFETY: guaranteed by the caller.
+    let Some(gba) = (unsafe { gba.as_ref() }) else {
+        return MemetendoResult::InvalidArgument;
+    };
+    let Some(ref inner) = gba.gba else {
+        return MemetendoResult::NotPoweredOn;
+    };
+
+    let state = inner.save_state();
+    // SAFETY: guaranteed by the caller.
+    if let Some(out_len) = unsafe { out_len.as_mut() } {
+        *out_len = state.len();
+    }
+    if buf.is_null() || len < state.len() {
+        return MemetendoResult::BufferTooSmall;
+    }
+
+    // SAFETY: guaranteed by the caller, and we checked the buffer is large enough.
+    unsafe { ptr::copy_nonoverlapping(state.as_ptr(), buf, state.len()) };
+    MemetendoResult::Ok
+}
+
+/// # Safety
+///
+/// `gba` must be a valid instance, and `data` must be valid for reads of `len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn memetendo_load_state(
+    gba: *mut MemetendoGba,
+    data: *const u8,
+    len: usize,
+) -> MemetendoResult {
+    // SAFETY: guaranteed by the caller.
+    let Some(gba) = (unsafe { gba.as_mut() }) else {
+        return MemetendoResult::InvalidArgument;
+    };
+    // SAFETY: guaranteed by the caller.
+    let Some(data) = (unsafe { slice_from_raw(data, len) }) else {
+        return MemetendoResult::InvalidArgument;
+    };
+    let Some(ref mut inner) = gba.gba else {
+        return MemetendoResult::NotPoweredOn;
+    };
+
+    match inner.load_state(data) {
+        Ok(()) => MemetendoResult::Ok,
+        Err(_) => MemetendoResult::InvalidState,
+    }
+}
@@ -0,0 +1,183 @@
+//! Benchmarks for the hot paths of the emulator core.
+//!
+//! Run with `cargo bench -p libmemetendo`; pass a filter (e.g: `cargo bench -p libmemetendo cpu`)
+//! to only run some of them.
+
+use std::{hint::black_box, rc::Rc};
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use libmemetendo::{
+    arm7tdmi::Cpu,
+    bios,
+    cart::{self, Cartridge},
+    gba::Gba,
+    util,
+    video::HBLANK_DOT,
+};
+
+/// Number of instructions stepped per iteration of the CPU benchmarks.
+const CPU_STEPS: u64 = 10_000;
+
+/// Dots (including those in HBlank) per scanline; each dot takes 4 cycles.
+const SCANLINE_DOTS: u32 = 308;
+
+/// Cycles per video frame (228 scanlines).
+const FRAME_CYCLES: u32 = 4 * SCANLINE_DOTS * 228;
+
+/// ```text
+/// loop:
+///     add  r0, r0, #1
+///     subs r1, r1, #1
+///     orr  r2, r0, r1, lsl #3
+///     b    loop
+/// ```
+const ARM_LOOP: [u32; 4] = [0xe280_0001, 0xe251_1001, 0xe180_2181, 0xeaff_fffb];
+
+/// ```text
+///     add r0, pc, #1 @ ARM
+///     bx  r0
+/// loop:              @ Thumb
+///     adds r0, #1
+///     subs r1, #1
+///     lsls r2, r0, #3
+///     b    loop
+/// ```
+const THUMB_LOOP: ([u32; 2], [u16; 4]) =
+    ([0xe28f_0001, 0xe12f_ff10], [0x3001, 0x3901, 0x00c2, 0xe7fb]);
+
+fn cpu_mem(arm: &[u32], thumb: &[u16]) -> Vec<u8> {
+    let mut mem = Vec::with_capacity(0x100);
+    mem.extend(arm.iter().flat_map(|instr| instr.to_le_bytes()));
+    mem.extend(thumb.iter().flat_map(|instr| instr.to_le_bytes()));
+    mem.resize(0x100, 0);
+
+    mem
+}
+
+fn bench_cpu(c: &mut Criterion) {
+    let mut group = c.benchmark_group("cpu");
+    group.throughput(Throughput::Elements(CPU_STEPS));
+
+    for (name, mem) in [
+        ("arm_loop", cpu_mem(&ARM_LOOP, &[])),
+        ("thumb_loop", cpu_mem(&THUMB_LOOP.0, &THUMB_LOOP.1)),
+    ] {
+        let mut bus = &mem[..];
+        let mut cpu = Cpu::new();
+        cpu.reset(&mut bus, false);
+
+        group.bench_function(name, |b| {
+            b.iter(|| {
+                for _ in 0..CPU_STEPS {
+                    cpu.step(&mut bus);
+                }
+                black_box(&cpu);
+            });
+        });
+    }
+
+    group.finish();
+}
+
+/// Returns a `Gba` executing an infinite loop from the cartridge ROM, with the BIOS skipped.
+fn new_gba() -> Gba {
+    let bios_rom = bios::Rom::new(Rc::from(vec![0; 0x4000])).unwrap();
+    let cart_rom = cart::Rom::new(Rc::from(cpu_mem(&ARM_LOOP, &[]))).unwrap();
+
+    let mut gba = Gba::new(bios_rom, Cartridge::from(cart_rom));
+    gba.reset(true);
+
+    gba
+}
+
+/// Returns a `Gba` with all tile mode BGs and OBJs enabled, and video memory filled with junk.
+fn new_gba_with_video() -> Gba {
+    let mut gba = new_gba();
+    let junk = |i: u32| u16::try_from(i.wrapping_mul(0x9e37_79b9) >> 16).unwrap();
+    for addr in (0x0500_0000..0x0500_0400).step_by(2) {
+        gba.write_hword(addr, junk(addr));
+    }
+    for addr in (0x0600_0000..0x0601_8000).step_by(2) {
+        gba.write_hword(addr, junk(addr));
+    }
+    for addr in (0x0700_0000..0x0700_0400).step_by(2) {
+        gba.write_hword(addr, junk(addr));
+    }
+    gba.write_hword(0x0400_0000, 0x1f40); // DISPCNT: mode 0, BG0-3 & OBJ on, 1D OBJ mapping
+
+    gba
+}
+
+fn bench_video(c: &mut Criterion) {
+    let mut group = c.benchmark_group("video");
+    group.throughput(Throughput::Elements(u64::from(HBLANK_DOT)));
+
+    let mut gba = new_gba_with_video();
+    group.bench_function("scanline_mode0", |b| {
+        b.iter(|| {
+            for _ in 0..SCANLINE_DOTS {
+                gba.video.step(
+                    &mut util::video::NullCallback,
+                    &mut gba.irq,
+                    &mut gba.dma,
+                    4,
+                );
+            }
+        });
+    });
+
+    group.finish();
+}
+
+fn bench_audio(c: &mut Criterion) {
+    let mut group = c.benchmark_group("audio");
+    group.throughput(Throughput::Elements(FRAME_CYCLES.into()));
+
+    let mut gba = new_gba();
+    gba.write_hword(0x0400_0084, 0x80); // SOUNDCNT_X: master enable
+    gba.write_hword(0x0400_0080, 0xff77); // SOUNDCNT_L: max volume, all channels on both sides
+    gba.write_hword(0x0400_0082, 0x0002); // SOUNDCNT_H: 100% PSG volume
+    gba.write_hword(0x0400_0062, 0xf080); // SOUND1CNT_H: max volume, 50% duty
+    gba.write_hword(0x0400_0064, 0x8400); // SOUND1CNT_X: restart
+    gba.write_hword(0x0400_0068, 0xf040); // SOUND2CNT_L: max volume, 25% duty
+    gba.write_hword(0x0400_006c, 0x8500); // SOUND2CNT_H: restart
+    gba.write_hword(0x0400_0070, 0x0080); // SOUND3CNT_L: playback on
+    gba.write_hword(0x0400_0072, 0x2000); // SOUND3CNT_H: 100% volume
+    gba.write_hword(0x0400_0074, 0x8600); // SOUND3CNT_X: restart
+    gba.write_hword(0x0400_0078, 0xf000); // SOUND4CNT_L: max volume
+    gba.write_hword(0x0400_007c, 0x8011); // SOUND4CNT_H: restart
+
+    group.bench_function("psg_frame", |b| {
+        b.iter(|| {
+            for _ in 0..FRAME_CYCLES / 4 {
+                gba.audio
+                    .step(&mut util::audio::NullCallback, &mut gba.dma, 4);
+            }
+        });
+    });
+
+    group.finish();
+}
+
+fn bench_gba(c: &mut Criterion) {
+    let mut group = c.benchmark_group("gba");
+    group.sample_size(20);
+
+    let mut gba = new_gba_with_video();
+    group.bench_function("frame", |b| {
+        b.iter(|| {
+            // Gba::step currently always steps 3 cycles.
+            for _ in 0..FRAME_CYCLES / 3 {
+                gba.step(
+                    &mut util::video::NullCallback,
+                    &mut util::audio::NullCallback,
+                );
+            }
+        });
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_cpu, bench_video, bench_audio, bench_gba);
+criterion_main!(benches);
@@ -0,0 +1,69 @@
+//! Minimal headless embedder: runs a ROM without a window or sound, saving some of its frames as
+//! PNG images. A starting point for tools like automated testers or video encoders.
+//!
+//! Run with `cargo run -p libmemetendo --example headless -- BIOS ROM [FRAMES] [INTERVAL]`, which
+//! runs `FRAMES` frames (default 300), saving every `INTERVAL`th one (default 60) to the current
+//! directory.
+
+use std::{env, error::Error, fs, rc::Rc};
+
+use image::RgbImage;
+use libmemetendo::{
+    bios,
+    cart::{self, Cartridge},
+    gba::{self, Event},
+    util::{audio::NullCallback, video::FrameBuffer},
+    video::{self, Dot, HBLANK_DOT, VBLANK_DOT},
+};
+
+/// Receives the dots drawn by the video unit.
+#[derive(Default)]
+struct Screen(FrameBuffer);
+
+impl video::Callback for Screen {
+    fn put_dot(&mut self, x: u8, y: u8, dot: Dot) {
+        self.0.put_dot(x, y, dot);
+    }
+
+    fn end_frame(&mut self, green_swap: bool) {
+        if green_swap {
+            self.0.green_swap();
+        }
+    }
+
+    fn is_frame_skipping(&self) -> bool {
+        false
+    }
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let mut args = env::args().skip(1);
+    let (Some(bios_path), Some(rom_path)) = (args.next(), args.next()) else {
+        return Err("usage: headless BIOS ROM [FRAMES] [INTERVAL]".into());
+    };
+    let frames: u32 = args.next().map_or(Ok(300), |s| s.parse())?;
+    let interval: u32 = args.next().map_or(Ok(60), |s| s.parse())?;
+
+    let bios_rom = bios::Rom::new(Rc::from(fs::read(bios_path)?))?;
+    let cart_rom = cart::Rom::new(Rc::from(fs::read(rom_path)?))?;
+    let mut gba = gba::Builder::new(bios_rom, Cartridge::from(cart_rom))
+        .skip_bios(true)
+        .build()?;
+
+    let mut screen = Screen::default();
+    for frame in 1..=frames {
+        // A frame is complete once the last scanline is drawn, at the start of VBlank.
+        if !gba.step_until(Event::VBlank, &mut screen, &mut NullCallback) {
+            return Err("the system stopped without a way to wake up".into());
+        }
+        if frame % interval.max(1) == 0 {
+            let path = format!("frame_{frame:05}.png");
+            RgbImage::from_raw(HBLANK_DOT.into(), VBLANK_DOT.into(), screen.0 .0.to_vec())
+                .unwrap()
+                .save(&path)?;
+            println!("saved {path}");
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,92 @@
+//! Minimal windowed embedder using `minifb`: shows the screen and maps the keyboard to the keypad,
+//! without sound. A starting point for frontends simpler than `memetendo` or `web-memetendo`.
+//!
+//! Run with `cargo run -p libmemetendo --example window -- BIOS ROM`. The controls are the arrow
+//! keys, X (A), Z (B), A (L), S (R), Enter (Start) and Backspace (Select); Escape quits.
+
+use std::{env, error::Error, fs, rc::Rc};
+
+use libmemetendo::{
+    bios,
+    cart::{self, Cartridge},
+    gba::{self, Event},
+    keypad,
+    util::audio::NullCallback,
+    video::{self, Dot, HBLANK_DOT, VBLANK_DOT},
+};
+use minifb::{Key, Scale, Window, WindowOptions};
+
+const KEYMAP: [(Key, keypad::Key); 10] = [
+    (Key::X, keypad::Key::A),
+    (Key::Z, keypad::Key::B),
+    (Key::Backspace, keypad::Key::Select),
+    (Key::Enter, keypad::Key::Start),
+    (Key::Right, keypad::Key::Right),
+    (Key::Left, keypad::Key::Left),
+    (Key::Up, keypad::Key::Up),
+    (Key::Down, keypad::Key::Down),
+    (Key::S, keypad::Key::R),
+    (Key::A, keypad::Key::L),
+];
+
+/// Receives the dots drawn by the video unit, in the 0RGB format `minifb` expects.
+struct Screen(Vec<u32>);
+
+impl video::Callback for Screen {
+    fn put_dot(&mut self, x: u8, y: u8, dot: Dot) {
+        let [r, g, b] = [dot.red(), dot.green(), dot.blue()].map(|c| u32::from(c) * 8);
+        self.0[usize::from(y) * usize::from(HBLANK_DOT) + usize::from(x)] =
+            (r << 16) | (g << 8) | b;
+    }
+
+    fn end_frame(&mut self, green_swap: bool) {
+        if green_swap {
+            for pair in self.0.chunks_exact_mut(2) {
+                let (g0, g1) = (pair[0] & 0xff00, pair[1] & 0xff00);
+                pair[0] = (pair[0] & !0xff00) | g1;
+                pair[1] = (pair[1] & !0xff00) | g0;
+            }
+        }
+    }
+
+    fn is_frame_skipping(&self) -> bool {
+        false
+    }
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let mut args = env::args().skip(1);
+    let (Some(bios_path), Some(rom_path)) = (args.next(), args.next()) else {
+        return Err("usage: window BIOS ROM".into());
+    };
+
+    let bios_rom = bios::Rom::new(Rc::from(fs::read(bios_path)?))?;
+    let cart_rom = cart::Rom::new(Rc::from(fs::read(rom_path)?))?;
+    let mut gba = gba::Builder::new(bios_rom, Cartridge::from(cart_rom)).build()?;
+
+    let (width, height) = (usize::from(HBLANK_DOT), usize::from(VBLANK_DOT));
+    let mut window = Window::new(
+        "Memetendo Unsafe Boy Advance",
+        width,
+        height,
+        WindowOptions {
+            scale: Scale::X4,
+            ..WindowOptions::default()
+        },
+    )?;
+    // Close enough to the GBA's ~59.73 FPS for an example.
+    window.set_target_fps(60);
+
+    let mut screen = Screen(vec![0; width * height]);
+    while window.is_open() && !window.is_key_down(Key::Escape) {
+        for (key, gba_key) in KEYMAP {
+            gba.keypad.set_pressed(gba_key, window.is_key_down(key));
+        }
+        // Returns false if the system is stopped without a way to wake up; keep updating the
+        // window anyway, so it can still be closed.
+        gba.step_until(Event::VBlank, &mut screen, &mut NullCallback);
+        window.update_with_buffer(&screen.0, width, height)?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,23 @@
+#![no_main]
+
+use std::rc::Rc;
+
+use libfuzzer_sys::fuzz_target;
+use libmemetendo::{
+    bus::Bus,
+    cart::{Cartridge, Rom},
+};
+
+fuzz_target!(|data: &[u8]| {
+    let rom = Rom::new(Rc::from(vec![0; 0xc0])).unwrap();
+    let Ok(mut cart) = Cartridge::try_from_backup(&rom, Some(data.into())) else {
+        return;
+    };
+    assert_eq!(cart.backup_buffer().is_some(), !data.is_empty());
+
+    // Read the SRAM/Flash region, and the EEPROM.
+    for addr in 0x600_0000..0x601_0000 {
+        cart.read_byte(addr);
+    }
+    cart.read_hword(0x1ff_ff00);
+});
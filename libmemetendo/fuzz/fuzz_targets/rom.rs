@@ -0,0 +1,22 @@
+#![no_main]
+
+use std::rc::Rc;
+
+use libfuzzer_sys::fuzz_target;
+use libmemetendo::{
+    bus::Bus,
+    cart::{Cartridge, Rom},
+};
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(rom) = Rom::new(Rc::from(data)) else {
+        return;
+    };
+    let _ = rom.header().map(|header| header.to_string());
+    let _ = rom.game_code();
+
+    let mut cart = Cartridge::from(rom);
+    for addr in (0..0x800_0000).step_by(0x10_0000) {
+        cart.read_word(addr);
+    }
+});
@@ -0,0 +1,54 @@
+#![no_main]
+
+use std::rc::Rc;
+
+use libfuzzer_sys::fuzz_target;
+use libmemetendo::{
+    bios,
+    cart::{self, Cartridge},
+    gba::Gba,
+    util,
+};
+
+fuzz_target!(|data: &[u8]| {
+    // The data is both the ROM to run and the memory accesses to make while running it.
+    let bios_rom = bios::Rom::new(Rc::from(vec![0; 0x4000])).unwrap();
+    let Ok(cart_rom) = cart::Rom::new(Rc::from(data)) else {
+        return;
+    };
+    let mut gba = Gba::new(bios_rom, Cartridge::from(cart_rom));
+    gba.reset(true);
+
+    // Each access is a byte for its kind and steps to run beforehand, then a word for its address
+    // and another for its value.
+    for access in data.chunks_exact(9) {
+        let kind = access[0];
+        let addr = u32::from_le_bytes(access[1..5].try_into().unwrap());
+        let value = u32::from_le_bytes(access[5..9].try_into().unwrap());
+
+        for _ in 0..u32::from(kind >> 3) * 64 {
+            gba.step(
+                &mut util::video::NullCallback,
+                &mut util::audio::NullCallback,
+            );
+        }
+        match kind & 7 {
+            0 => gba.write_byte(addr, value.to_le_bytes()[0]),
+            1 => gba.write_hword(addr, value as u16),
+            2 => gba.write_word(addr, value),
+            3 => _ = gba.read_byte(addr),
+            4 => _ = gba.read_hword(addr),
+            _ => _ = gba.read_word(addr),
+        }
+    }
+
+    // Whatever state the ROM left the system in should be loadable, and keep running from there.
+    let state = gba.save_state();
+    gba.load_state(&state).unwrap();
+    for _ in 0..1000 {
+        gba.step(
+            &mut util::video::NullCallback,
+            &mut util::audio::NullCallback,
+        );
+    }
+});
@@ -0,0 +1,31 @@
+#![no_main]
+
+use std::rc::Rc;
+
+use libfuzzer_sys::fuzz_target;
+use libmemetendo::{
+    bios,
+    cart::{self, Cartridge},
+    gba::Gba,
+    util,
+};
+
+fuzz_target!(|data: &[u8]| {
+    let bios_rom = bios::Rom::new(Rc::from(vec![0; 0x4000])).unwrap();
+    let cart_rom = cart::Rom::new(Rc::from(vec![0; 0xc0])).unwrap();
+    let mut gba = Gba::new(bios_rom, Cartridge::from(cart_rom));
+    if gba.load_state(data).is_err() {
+        return;
+    }
+
+    // Whatever was loaded shouldn't cause a panic later, either, and should still be loadable once
+    // saved again.
+    for _ in 0..10_000 {
+        gba.step(
+            &mut util::video::NullCallback,
+            &mut util::audio::NullCallback,
+        );
+    }
+    let state = gba.save_state();
+    gba.load_state(&state).unwrap();
+});
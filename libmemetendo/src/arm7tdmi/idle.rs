@@ -0,0 +1,287 @@
+//! Detection of idle loops: tight loops that wait for something to change, such as a loop polling
+//! a flag in RAM that's set by an interrupt handler. Games often busy-wait like this instead of
+//! halting, which wastes time emulating instructions that can't change anything.
+//!
+//! A loop is considered idle once an iteration of it completes without writing to memory or
+//! reading memory that can change by itself (see `Bus::is_volatile`), and leaves the registers as
+//! they were at the start of the iteration. Every later iteration would then do exactly the same
+//! thing, so the CPU can stop executing it until something else changes memory or an interrupt is
+//! requested, at which point `Detector::wake` must be called.
+
+use crate::bus::Bus;
+
+use super::reg::{Registers, PC_INDEX};
+
+/// Maximum size of an idle loop's body in bytes; 8 ARM or 16 Thumb instructions.
+const MAX_LOOP_BYTES: u32 = 32;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+struct LoopState {
+    r: [u32; 16],
+    cpsr: u32,
+    spsr: u32,
+}
+
+impl LoopState {
+    fn new(reg: &Registers) -> Self {
+        Self {
+            r: reg.r,
+            cpsr: reg.cpsr.bits(),
+            spsr: reg.spsr(),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+struct Candidate {
+    addr: u32,
+    state: LoopState,
+}
+
+#[derive(Default, Copy, Clone, Debug)]
+pub struct Detector {
+    /// Whether to detect idle loops. Disabled by default.
+    pub enabled: bool,
+    candidate: Option<Candidate>,
+    /// Whether the current iteration of the candidate loop accessed memory in a way that may make
+    /// the next iteration behave differently.
+    impure: bool,
+    idle: bool,
+}
+
+impl Detector {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns true if the CPU is in an idle loop, and thus isn't executing instructions.
+    #[must_use]
+    pub fn is_idle(&self) -> bool {
+        self.idle
+    }
+
+    /// Resumes executing the idle loop, if any, such as after memory that it may read changed.
+    pub fn wake(&mut self) {
+        self.idle = false;
+        self.candidate = None;
+    }
+
+    fn record_instr(&mut self, instr_addr: u32, reg: &Registers, branched: bool, impure: bool) {
+        if let Some(candidate) = self.candidate {
+            if instr_addr.wrapping_sub(candidate.addr) >= MAX_LOOP_BYTES {
+                self.candidate = None; // Left the loop.
+            }
+        }
+        self.impure |= impure;
+
+        let target_addr = reg.r[PC_INDEX].wrapping_sub(2 * reg.cpsr.state.instr_size());
+        if !branched || instr_addr.wrapping_sub(target_addr) >= MAX_LOOP_BYTES {
+            return;
+        }
+
+        // Branched backwards to the start of a (possibly new) loop iteration.
+        let state = LoopState::new(reg);
+        match self.candidate {
+            Some(candidate) if candidate.addr == target_addr => {
+                self.idle = !self.impure && candidate.state == state;
+            }
+            _ => {}
+        }
+        self.candidate = Some(Candidate {
+            addr: target_addr,
+            state,
+        });
+        self.impure = false;
+    }
+}
+
+/// Forwards accesses to another bus, noting whether any may make an idle loop not idle.
+pub(super) struct MonitorBus<'a, B: ?Sized> {
+    bus: &'a mut B,
+    impure: bool,
+}
+
+impl<'a, B: Bus + ?Sized> MonitorBus<'a, B> {
+    pub fn new(bus: &'a mut B) -> Self {
+        Self { bus, impure: false }
+    }
+
+    fn read(&mut self, addr: u32) {
+        self.impure |= self.bus.is_volatile(addr);
+    }
+}
+
+impl<B: Bus + ?Sized> Bus for MonitorBus<'_, B> {
+    fn read_byte(&mut self, addr: u32) -> u8 {
+        self.read(addr);
+        self.bus.read_byte(addr)
+    }
+
+    fn read_hword(&mut self, addr: u32) -> u16 {
+        self.read(addr);
+        self.bus.read_hword(addr)
+    }
+
+    fn read_word(&mut self, addr: u32) -> u32 {
+        self.read(addr);
+        self.bus.read_word(addr)
+    }
+
+    fn write_byte(&mut self, addr: u32, value: u8) {
+        self.impure = true;
+        self.bus.write_byte(addr, value);
+    }
+
+    fn write_hword(&mut self, addr: u32, value: u16) {
+        self.impure = true;
+        self.bus.write_hword(addr, value);
+    }
+
+    fn write_word(&mut self, addr: u32, value: u32) {
+        self.impure = true;
+        self.bus.write_word(addr, value);
+    }
+
+    fn prefetch_instr(&mut self, addr: u32) {
+        self.bus.prefetch_instr(addr);
+    }
+
+    fn notify_prefetched(&mut self, addr: u32, pipeline: [u32; 2], thumb: bool) {
+        self.bus.notify_prefetched(addr, pipeline, thumb);
+    }
+
+    fn is_volatile(&self, addr: u32) -> bool {
+        self.bus.is_volatile(addr)
+    }
+}
+
+impl super::Cpu {
+    /// Steps the CPU like `Self::step`, while detecting idle loops.
+    pub(super) fn step_detecting_idle(&mut self, bus: &mut impl Bus) {
+        if self.idle_loop.is_idle() {
+            if !self.pending_exceptions.contains(&true) {
+                return;
+            }
+            self.idle_loop.wake();
+        }
+
+        let instr_addr = self.next_instr_addr();
+        let mut bus = MonitorBus::new(bus);
+        if self.step_inner(&mut bus) {
+            self.idle_loop.wake(); // Serviced an exception.
+        } else {
+            let (branched, impure) = (self.pipeline_reloaded, bus.impure);
+            self.idle_loop
+                .record_instr(instr_addr, &self.reg, branched, impure);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        arm7tdmi::{Cpu, Exception},
+        bus::tests::VecBus,
+    };
+
+    use super::*;
+
+    /// Creates a CPU executing `program` (ARM instructions) at address 0, with idle loop detection.
+    fn new_cpu(bus: &mut VecBus, program: &[u32]) -> Cpu {
+        for (i, &instr) in program.iter().enumerate() {
+            bus.write_word(4 * u32::try_from(i).unwrap(), instr);
+        }
+
+        let mut cpu = Cpu::new();
+        cpu.idle_loop.enabled = true;
+        cpu.reset(bus, false);
+        cpu
+    }
+
+    #[test]
+    fn polling_loop_is_idle() {
+        // loop:
+        //     ldr r0, [r1]
+        //     cmp r0, #0
+        //     beq loop
+        //     mov r2, #1
+        //     b   .
+        let mut bus = VecBus::new(0x100);
+        let mut cpu = new_cpu(
+            &mut bus,
+            &[
+                0xe591_0000,
+                0xe350_0000,
+                0x0aff_fffc,
+                0xe3a0_2001,
+                0xeaff_fffe,
+            ],
+        );
+        cpu.reg.r[1] = 0x80;
+
+        for _ in 0..6 {
+            cpu.step(&mut bus);
+        }
+        assert!(cpu.idle_loop.is_idle());
+        let reg = cpu.reg;
+        for _ in 0..100 {
+            cpu.step(&mut bus);
+        }
+        assert_eq!(cpu.reg.r, reg.r);
+
+        // Once woken, the CPU should see the new value and exit the loop.
+        bus.write_word(0x80, 1);
+        cpu.idle_loop.wake();
+        for _ in 0..4 {
+            cpu.step(&mut bus);
+        }
+        assert!(!cpu.idle_loop.is_idle());
+        assert_eq!(cpu.reg.r[2], 1);
+    }
+
+    #[test]
+    fn pending_exception_wakes() {
+        // loop:
+        //     b loop
+        let mut bus = VecBus::new(0x100);
+        let mut cpu = new_cpu(&mut bus, &[0xeaff_fffe]);
+        cpu.reg.cpsr.fiq_disabled = false;
+        for _ in 0..3 {
+            cpu.step(&mut bus);
+        }
+        assert!(cpu.idle_loop.is_idle());
+
+        cpu.raise_exception(Exception::FastInterrupt);
+        cpu.step(&mut bus);
+        assert!(!cpu.idle_loop.is_idle());
+        assert_eq!(
+            cpu.reg.r[PC_INDEX],
+            Exception::FastInterrupt.vector_addr() + 8
+        );
+    }
+
+    #[test]
+    fn busy_loops_are_not_idle() {
+        // loop:
+        //     subs r0, r0, #1
+        //     bne  loop
+        let mut bus = VecBus::new(0x100);
+        let mut cpu = new_cpu(&mut bus, &[0xe250_0001, 0x1aff_fffd]);
+        cpu.reg.r[0] = 100;
+        for _ in 0..50 {
+            cpu.step(&mut bus);
+            assert!(!cpu.idle_loop.is_idle());
+        }
+
+        // loop:
+        //     str r0, [r1]
+        //     b   loop
+        let mut cpu = new_cpu(&mut bus, &[0xe581_0000, 0xeaff_fffd]);
+        cpu.reg.r[1] = 0x80;
+        for _ in 0..50 {
+            cpu.step(&mut bus);
+            assert!(!cpu.idle_loop.is_idle());
+        }
+    }
+}
@@ -45,10 +45,8 @@ impl Cpu {
             "101?_????_????_????_????_????_????" => self.execute_arm_b_bl(bus, instr),
             "00??_????_????_????_????_????_????" => self.execute_arm_data_processing(bus, instr),
             "01??_????_????_????_????_????_????" => self.execute_arm_single_transfer(bus, instr),
-            "1100_010?_????_????_????_???0_????" => {} // N/A Coprocessor double register transfer
-            "1110_????_????_????_????_???0_????" => {} // N/A Coprocessor data operations
-            "1110_????_????_????_????_???1_????" => {} // N/A Coprocessor register transfer
-            "110?_????_????_????_????_????_????" => {} // N/A Coprocessor data transfer
+            // Also includes coprocessor instructions (e.g: MRC/MCR for CP14 and CP15), as the GBA has
+            // no coprocessors to accept them.
             _ => {
                 self.enter_exception(bus, Exception::UndefinedInstr);
             }
@@ -523,6 +521,24 @@ mod tests {
             .assert_r(LR_INDEX, 8 - 4)
             .assert_r(PC_INDEX, 0x04 + 8)
             .run();
+
+        // Coprocessor instructions are undefined
+        for instr in [
+            0b1110_1110_0001_0001_0000_1111_0001_0000, // AL MRC p15,0,R0,c1,c0,0
+            0b1110_1110_0000_0001_0000_1110_0001_0000, // AL MCR p14,0,R0,c1,c0,0
+            0b1110_1110_0001_0001_0000_1111_0000_0000, // AL CDP p15,1,c0,c1,c0,0
+            0b1110_1101_1001_0000_0000_1111_0000_0001, // AL LDC p15,c0,[R0,#4]
+            0b1110_1100_0100_0001_0000_1111_0000_0000, // AL MCRR p15,0,R0,R1,c0 (ARMv5)
+        ] {
+            let cpu = InstrTest::new_arm(instr)
+                .setup(&|cpu| cpu.reg.r[0] = 0x1234)
+                .assert_r(0, 0x1234)
+                .assert_r(LR_INDEX, 8 - 4)
+                .assert_r(PC_INDEX, 0x04 + 8)
+                .run();
+
+            assert_eq!(cpu.reg.cpsr.mode(), OperationMode::UndefinedInstr);
+        }
     }
 
     #[test]
@@ -1862,4 +1878,159 @@ mod tests {
 
         assert_eq!(bus.read_byte(4), 4);
     }
+
+    /// Returns a bus for testing misaligned accesses, which don't fault on the ARM7TDMI. Instead,
+    /// the address is forced to be aligned, and for LDR, LDRH and SWP the value read is rotated
+    /// right by the misalignment in bytes. A misaligned LDRSH loads a sign-extended byte.
+    fn new_misaligned_bus() -> VecBus {
+        let mut bus = VecBus::new(0x40);
+        bus.write_word(0x20, 0x1122_3344);
+        bus.write_word(0x24, 0x8877_66f5);
+        bus
+    }
+
+    #[test]
+    fn misaligned_ldr_rotates() {
+        let mut bus = new_misaligned_bus();
+        for (offset, value) in [
+            (0, 0x1122_3344),
+            (1, 0x4411_2233),
+            (2, 0x3344_1122),
+            (3, 0x2233_4411),
+        ] {
+            // LDR R1,[R0]
+            InstrTest::new_arm(0xe590_1000)
+                .setup(&|cpu| cpu.reg.r[0] = 0x20 + offset)
+                .assert_r(0, 0x20 + offset)
+                .assert_r(1, value)
+                .run_with_bus(&mut bus);
+        }
+    }
+
+    #[test]
+    fn misaligned_str_forces_alignment() {
+        for offset in 0..4 {
+            let mut bus = new_misaligned_bus();
+            // STR R1,[R0]
+            InstrTest::new_arm(0xe580_1000)
+                .setup(&|cpu| {
+                    cpu.reg.r[0] = 0x20 + offset;
+                    cpu.reg.r[1] = 0xdead_beef;
+                })
+                .assert_r(0, 0x20 + offset)
+                .assert_r(1, 0xdead_beef)
+                .run_with_bus(&mut bus);
+
+            assert_eq!(bus.read_word(0x20), 0xdead_beef);
+            assert_eq!(bus.read_word(0x24), 0x8877_66f5);
+        }
+    }
+
+    #[test]
+    fn misaligned_hword_and_signed_transfers() {
+        let mut bus = new_misaligned_bus();
+
+        // LDRH R1,[R0]
+        for (addr, value) in [
+            (0x20, 0x3344),
+            (0x21, 0x4400_0033),
+            (0x26, 0x8877),
+            (0x27, 0x7700_0088),
+        ] {
+            InstrTest::new_arm(0xe1d0_10b0)
+                .setup(&|cpu| cpu.reg.r[0] = addr)
+                .assert_r(0, addr)
+                .assert_r(1, value)
+                .run_with_bus(&mut bus);
+        }
+
+        // LDRSH R1,[R0]; when misaligned, only the byte at the address is loaded and sign-extended.
+        for (addr, value) in [
+            (0x20, 0x3344),
+            (0x21, 0x33),
+            (0x24, 0x66f5),
+            (0x25, 0x66),
+            (0x26, 0xffff_8877),
+            (0x27, 0xffff_ff88),
+        ] {
+            InstrTest::new_arm(0xe1d0_10f0)
+                .setup(&|cpu| cpu.reg.r[0] = addr)
+                .assert_r(0, addr)
+                .assert_r(1, value)
+                .run_with_bus(&mut bus);
+        }
+
+        // STRH R1,[R0]
+        for addr in [0x22, 0x23] {
+            let mut bus = new_misaligned_bus();
+            InstrTest::new_arm(0xe1c0_10b0)
+                .setup(&|cpu| {
+                    cpu.reg.r[0] = addr;
+                    cpu.reg.r[1] = 0xdead_beef;
+                })
+                .assert_r(0, addr)
+                .assert_r(1, 0xdead_beef)
+                .run_with_bus(&mut bus);
+
+            assert_eq!(bus.read_word(0x20), 0xbeef_3344);
+        }
+    }
+
+    #[test]
+    fn misaligned_swap() {
+        // SWP R1,R2,[R0]; the old value is read like LDR, and the new value is written like STR.
+        let mut bus = new_misaligned_bus();
+        InstrTest::new_arm(0xe100_1092)
+            .setup(&|cpu| {
+                cpu.reg.r[0] = 0x22;
+                cpu.reg.r[2] = 0xdead_beef;
+            })
+            .assert_r(0, 0x22)
+            .assert_r(1, 0x3344_1122)
+            .assert_r(2, 0xdead_beef)
+            .run_with_bus(&mut bus);
+
+        assert_eq!(bus.read_word(0x20), 0xdead_beef);
+
+        // SWPB R1,R2,[R0]; byte accesses are never misaligned.
+        let mut bus = new_misaligned_bus();
+        InstrTest::new_arm(0xe140_1092)
+            .setup(&|cpu| {
+                cpu.reg.r[0] = 0x23;
+                cpu.reg.r[2] = 0xdead_beef;
+            })
+            .assert_r(0, 0x23)
+            .assert_r(1, 0x11)
+            .assert_r(2, 0xdead_beef)
+            .run_with_bus(&mut bus);
+
+        assert_eq!(bus.read_word(0x20), 0xef22_3344);
+    }
+
+    #[test]
+    fn misaligned_block_transfers() {
+        // LDMIA R0!,{R1,R2}; the words aren't rotated, and the written-back base stays misaligned.
+        let mut bus = new_misaligned_bus();
+        InstrTest::new_arm(0xe8b0_0006)
+            .setup(&|cpu| cpu.reg.r[0] = 0x23)
+            .assert_r(0, 0x2b)
+            .assert_r(1, 0x1122_3344)
+            .assert_r(2, 0x8877_66f5)
+            .run_with_bus(&mut bus);
+
+        // STMIA R0!,{R1,R2}
+        InstrTest::new_arm(0xe8a0_0006)
+            .setup(&|cpu| {
+                cpu.reg.r[0] = 0x21;
+                cpu.reg.r[1] = 0xdead_beef;
+                cpu.reg.r[2] = 0xcafe_babe;
+            })
+            .assert_r(0, 0x29)
+            .assert_r(1, 0xdead_beef)
+            .assert_r(2, 0xcafe_babe)
+            .run_with_bus(&mut bus);
+
+        assert_eq!(bus.read_word(0x20), 0xdead_beef);
+        assert_eq!(bus.read_word(0x24), 0xcafe_babe);
+    }
 }
@@ -28,6 +28,10 @@ impl Cpu {
             "1101_1111" => {
                 self.enter_exception(bus, Exception::SoftwareInterrupt);
             }
+            // Thumb.16 with the AL condition is undefined, rather than an unconditional branch.
+            "1101_1110" => {
+                self.enter_exception(bus, Exception::UndefinedInstr);
+            }
             "0100_00??" => self.execute_thumb4(instr),
             "0100_01??" => self.execute_thumb5(bus, instr),
             "0001_1???" => self.execute_thumb2(instr),
@@ -44,7 +48,11 @@ impl Cpu {
             "000?_????" => self.execute_thumb1(instr),
             "001?_????" => self.execute_thumb3(instr),
             "011?_????" => self.execute_thumb9(bus, instr),
-            _ => {}
+            // Only "1110_1???" is left, which is BLX (the low part of Thumb.19 that switches to ARM)
+            // on ARMv5, but undefined on the ARM7TDMI.
+            _ => {
+                self.enter_exception(bus, Exception::UndefinedInstr);
+            }
         }
     }
 
@@ -378,7 +386,7 @@ impl Cpu {
 
             self.op_branch(bus, self.reg.r[LR_INDEX], addr_offset_part << 1);
             self.reg.r[LR_INDEX] = return_addr | 1; // bit 0 set indicates Thumb
-        };
+        }
     }
 }
 
@@ -1952,6 +1960,16 @@ mod tests {
 
         InstrTest::new_thumb(0b1101_1101_00000011) // #6
             .run();
+
+        // B{AL} label is undefined
+        InstrTest::new_thumb(0b1101_1110_00010100) // #40
+            .setup(&|cpu| {
+                cpu.reg.r[PC_INDEX] = 200;
+                cpu.reg.cpsr.irq_disabled = false;
+            })
+            .assert_r(LR_INDEX, 198)
+            .assert_r(PC_INDEX, 0x04 + 8)
+            .run();
     }
 
     #[test]
@@ -2017,5 +2035,103 @@ mod tests {
             .assert_r(LR_INDEX, 3)
             .assert_r(PC_INDEX, 0xffff_f004 + 0x802 + 4)
             .run();
+
+        // BLX label (lo part) is undefined
+        let cpu = InstrTest::new_thumb(0b11101_00000000010)
+            .setup(&|cpu| {
+                cpu.reg.r[PC_INDEX] = 200;
+                cpu.reg.r[LR_INDEX] = 0x14004;
+                cpu.reg.cpsr.irq_disabled = false;
+            })
+            .assert_r(LR_INDEX, 198)
+            .assert_r(PC_INDEX, 0x04 + 8)
+            .run();
+
+        assert_eq!(cpu.reg.cpsr.state, OperationState::Arm);
+    }
+
+    /// Returns a bus for testing misaligned accesses; see the ARM tests' `new_misaligned_bus`.
+    fn new_misaligned_bus() -> VecBus {
+        let mut bus = VecBus::new(0x40);
+        bus.write_word(0x20, 0x1122_3344);
+        bus.write_word(0x24, 0x8877_66f5);
+        bus
+    }
+
+    #[test]
+    fn misaligned_word_transfers() {
+        let mut bus = new_misaligned_bus();
+
+        // LDR R1,[R0,#4]
+        InstrTest::new_thumb(0x6841)
+            .setup(&|cpu| cpu.reg.r[0] = 0x1d)
+            .assert_r(0, 0x1d)
+            .assert_r(1, 0x4411_2233)
+            .run_with_bus(&mut bus);
+
+        // LDR R1,[R0,R2]
+        InstrTest::new_thumb(0x5881)
+            .setup(&|cpu| {
+                cpu.reg.r[0] = 0x20;
+                cpu.reg.r[2] = 3;
+            })
+            .assert_r(0, 0x20)
+            .assert_r(1, 0x2233_4411)
+            .assert_r(2, 3)
+            .run_with_bus(&mut bus);
+
+        // LDR R1,[SP,#4]
+        InstrTest::new_thumb(0x9901)
+            .setup(&|cpu| cpu.reg.r[SP_INDEX] = 0x1e)
+            .assert_r(SP_INDEX, 0x1e)
+            .assert_r(1, 0x3344_1122)
+            .run_with_bus(&mut bus);
+
+        // STR R1,[R0,#0]
+        InstrTest::new_thumb(0x6001)
+            .setup(&|cpu| {
+                cpu.reg.r[0] = 0x27;
+                cpu.reg.r[1] = 0xdead_beef;
+            })
+            .assert_r(0, 0x27)
+            .assert_r(1, 0xdead_beef)
+            .run_with_bus(&mut bus);
+
+        assert_eq!(bus.read_word(0x24), 0xdead_beef);
+    }
+
+    #[test]
+    fn misaligned_hword_transfers() {
+        let mut bus = new_misaligned_bus();
+
+        // LDRH R1,[R0,#2]
+        InstrTest::new_thumb(0x8841)
+            .setup(&|cpu| cpu.reg.r[0] = 0x1f)
+            .assert_r(0, 0x1f)
+            .assert_r(1, 0x4400_0033)
+            .run_with_bus(&mut bus);
+
+        // LDSH R1,[R0,R2]
+        InstrTest::new_thumb(0x5e81)
+            .setup(&|cpu| {
+                cpu.reg.r[0] = 0x20;
+                cpu.reg.r[2] = 7;
+            })
+            .assert_r(0, 0x20)
+            .assert_r(1, 0xffff_ff88)
+            .assert_r(2, 7)
+            .run_with_bus(&mut bus);
+
+        // STRH R1,[R0,#0]
+        InstrTest::new_thumb(0x8001)
+            .setup(&|cpu| {
+                cpu.reg.r[0] = 0x25;
+                cpu.reg.r[1] = 0xdead_beef;
+            })
+            .assert_r(0, 0x25)
+            .assert_r(1, 0xdead_beef)
+            .run_with_bus(&mut bus);
+
+        assert_eq!(bus.read_word(0x24), 0x8877_beef);
     }
 }
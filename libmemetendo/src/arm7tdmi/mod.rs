@@ -1,3 +1,4 @@
+pub mod idle;
 mod isa;
 pub mod reg;
 
@@ -5,6 +6,7 @@ use std::mem::take;
 
 use intbits::Bits;
 use log::trace;
+use serde::{Deserialize, Serialize};
 use strum::EnumCount;
 use strum_macros::{EnumCount, EnumIter, FromRepr};
 
@@ -82,12 +84,21 @@ impl Exception {
     }
 }
 
-#[derive(Default, Copy, Clone, Debug)]
+/// An instruction for `Cpu::execute_one`, in the state (ARM or Thumb) it's executed in.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Instr {
+    Arm(u32),
+    Thumb(u16),
+}
+
+#[derive(Default, Copy, Clone, Debug, Serialize, Deserialize)]
 pub struct Cpu {
     pub reg: Registers,
     pipeline_instrs: [u32; 2],
     pipeline_reloaded: bool,
     pending_exceptions: [bool; Exception::COUNT],
+    #[serde(skip)]
+    pub idle_loop: idle::Detector,
 }
 
 impl Cpu {
@@ -101,46 +112,77 @@ impl Cpu {
         self.enter_exception(bus, Exception::Reset);
 
         if skip_bios {
-            self.reg.r[..=12].fill(0);
-            self.reg.cpsr.irq_disabled = false;
-            self.reg.cpsr.fiq_disabled = false;
-
-            self.reg.change_mode(OperationMode::Supervisor);
-            self.reg.r[SP_INDEX] = 0x0300_7fe0;
-            self.reg.r[LR_INDEX] = 0;
-            self.reg.set_spsr(0);
-
-            self.reg.change_mode(OperationMode::Interrupt);
-            self.reg.r[SP_INDEX] = 0x0300_7fa0;
-            self.reg.r[LR_INDEX] = 0;
-            self.reg.set_spsr(0);
-
-            self.reg.change_mode(OperationMode::System);
-            self.reg.r[SP_INDEX] = 0x0300_7f00;
-            self.reg.r[PC_INDEX] = 0x0800_0000;
-            self.reload_pipeline(bus);
+            self.soft_reset(bus, 0x0800_0000);
         }
     }
 
+    /// Sets up the registers and jumps to `entry_addr` in ARM state, like the GBA BIOS's
+    /// `SoftReset` function (SWI 0x00), which is also how it starts the game after booting.
+    pub fn soft_reset(&mut self, bus: &mut impl Bus, entry_addr: u32) {
+        self.reg.cpsr.irq_disabled = false;
+        self.reg.cpsr.fiq_disabled = false;
+        self.reg.cpsr.state = OperationState::Arm;
+
+        self.reg.change_mode(OperationMode::Supervisor);
+        self.reg.r[SP_INDEX] = 0x0300_7fe0;
+        self.reg.r[LR_INDEX] = 0;
+        self.reg.set_spsr(0);
+
+        self.reg.change_mode(OperationMode::Interrupt);
+        self.reg.r[SP_INDEX] = 0x0300_7fa0;
+        self.reg.r[LR_INDEX] = 0;
+        self.reg.set_spsr(0);
+
+        self.reg.change_mode(OperationMode::System);
+        self.reg.r[..=12].fill(0);
+        self.reg.r[SP_INDEX] = 0x0300_7f00;
+        self.reg.r[PC_INDEX] = entry_addr;
+        self.reload_pipeline(bus);
+    }
+
+    /// Services a pending exception or executes the next instruction. If idle loop detection is
+    /// enabled and the CPU is in an idle loop, does nothing unless an exception is pending.
+    ///
+    /// Like the ARM7TDMI, exceptions are only serviced between instructions, in priority order;
+    /// long instructions like LDM and STM always complete first.
+    pub fn step(&mut self, bus: &mut impl Bus) {
+        if self.idle_loop.enabled {
+            self.step_detecting_idle(bus);
+        } else {
+            self.step_inner(bus);
+        }
+    }
+
+    /// Returns true if an exception was serviced instead of executing an instruction.
     // We only panic if the priority number of a pending exception does not map to an exception,
     // which should be impossible.
-    #[expect(clippy::missing_panics_doc)]
-    pub fn step(&mut self, bus: &mut impl Bus) {
+    fn step_inner(&mut self, bus: &mut impl Bus) -> bool {
         for priority in 0..self.pending_exceptions.len() {
             let raised = take(&mut self.pending_exceptions[priority]);
             let exception = Exception::from_priority(priority).unwrap();
             if raised && self.enter_exception(bus, exception) {
-                return; // We serviced this exception.
+                return true; // We serviced this exception.
             }
         }
 
+        self.execute(bus, self.pipeline_instrs[0]);
+
+        false
+    }
+
+    /// Executes `instr` in place of the next instruction in the pipeline, in the CPU's current
+    /// state, without servicing pending exceptions first.
+    fn execute(&mut self, bus: &mut impl Bus, instr: u32) {
         // NOTE: emulated pipelining will have the PC 2 instructions ahead of this executing
         // instruction, so the actual address of this instruction was PC -4 or -8.
         // The following two instructions should already be prefetched at this point.
-        let instr = self.pipeline_instrs[0];
+        let instr_addr = self.next_instr_addr();
         self.pipeline_instrs[0] = self.pipeline_instrs[1];
         self.pipeline_instrs[1] = self.prefetch_instr(bus);
         self.pipeline_reloaded = false;
+        let thumb = self.reg.cpsr.state == OperationState::Thumb;
+        bus.notify_prefetched(self.reg.r[PC_INDEX], self.pipeline_instrs, thumb);
+        bus.notify_execute(instr_addr, thumb);
 
         trace!("next instr: {instr:08x}\n{}", self.reg);
         match self.reg.cpsr.state {
@@ -155,6 +197,36 @@ impl Cpu {
         }
     }
 
+    /// Executes `instr` as if it were the next instruction in the pipeline (at
+    /// `Self::next_instr_addr`), rather than the one fetched from the bus; useful for tools that
+    /// drive the CPU directly, like assembler test harnesses and fuzzers. The CPU switches to the
+    /// instruction's state first if needed, refilling the pipeline from that address. Pending
+    /// exceptions aren't serviced, and idle loops aren't detected.
+    ///
+    /// Registers (including PC, which reads 2 instructions ahead as usual) can be inspected or set
+    /// up via `Self::reg`.
+    pub fn execute_one(&mut self, bus: &mut impl Bus, instr: Instr) {
+        let (state, instr) = match instr {
+            Instr::Arm(instr) => (OperationState::Arm, instr),
+            Instr::Thumb(instr) => (OperationState::Thumb, instr.into()),
+        };
+        if self.reg.cpsr.state != state {
+            self.reg.r[PC_INDEX] = self.next_instr_addr();
+            self.reg.cpsr.state = state;
+            self.reload_pipeline(bus);
+        }
+
+        self.execute(bus, instr);
+    }
+
+    /// Address of the instruction to be executed by the next call to `step`, assuming no
+    /// exception is serviced first.
+    #[must_use]
+    pub fn next_instr_addr(&self) -> u32 {
+        // The PC is 2 instructions ahead due to pipelining.
+        self.reg.r[PC_INDEX].wrapping_sub(2 * self.reg.cpsr.state.instr_size())
+    }
+
     fn prefetch_instr(&mut self, bus: &mut impl Bus) -> u32 {
         bus.prefetch_instr(self.reg.r[PC_INDEX]);
 
@@ -171,12 +243,27 @@ impl Cpu {
         self.pipeline_instrs[1] = self.prefetch_instr(bus);
         self.reg.advance_pc();
         self.pipeline_reloaded = true;
+        self.notify_prefetched(bus);
+    }
+
+    /// Calls `Bus::notify_prefetched` for the instructions in the pipeline, such as after loading a
+    /// save state, which doesn't include the bus's copy of them.
+    pub fn notify_prefetched(&self, bus: &mut impl Bus) {
+        let state = self.reg.cpsr.state;
+        let addr = self.reg.r[PC_INDEX].wrapping_sub(state.instr_size());
+        bus.notify_prefetched(addr, self.pipeline_instrs, state == OperationState::Thumb);
     }
 
     pub fn raise_exception(&mut self, exception: Exception) {
         self.pending_exceptions[exception.priority()] = true;
     }
 
+    /// Cancels a raised exception if it wasn't serviced yet, such as when an interrupt request is
+    /// withdrawn before the CPU gets to execute another instruction.
+    pub fn cancel_exception(&mut self, exception: Exception) {
+        self.pending_exceptions[exception.priority()] = false;
+    }
+
     fn enter_exception(&mut self, bus: &mut impl Bus, exception: Exception) -> bool {
         if (self.reg.cpsr.irq_disabled && exception == Exception::Interrupt)
             || (self.reg.cpsr.fiq_disabled && exception == Exception::FastInterrupt)
@@ -324,6 +411,22 @@ mod tests {
         assert_no_pending_exceptions(&cpu);
     }
 
+    #[test]
+    fn cancel_exception_works() {
+        let mut cpu = Cpu::new();
+        cpu.reset(&mut NullBus, false);
+        cpu.reg.cpsr.irq_disabled = false;
+
+        cpu.raise_exception(Exception::Interrupt);
+        cpu.raise_exception(Exception::UndefinedInstr);
+        cpu.cancel_exception(Exception::Interrupt);
+
+        let old_reg = cpu.reg;
+        cpu.step(&mut NullBus);
+        assert_exception_result(&mut cpu, Exception::UndefinedInstr, old_reg);
+        assert_eq!(cpu.pending_exceptions, [false; 7]);
+    }
+
     #[expect(clippy::unusual_byte_groupings)]
     #[test]
     fn step_works() {
@@ -1,9 +1,10 @@
 use std::fmt::Display;
 
 use intbits::Bits;
+use serde::{Deserialize, Serialize};
 use strum_macros::FromRepr;
 
-#[derive(Default, Copy, Clone, PartialEq, Eq, FromRepr, Debug)]
+#[derive(Default, Copy, Clone, PartialEq, Eq, FromRepr, Debug, Serialize, Deserialize)]
 pub enum OperationMode {
     User = 0b10000,
     FastInterrupt = 0b10001,
@@ -38,7 +39,7 @@ pub const SP_INDEX: usize = 13;
 pub const LR_INDEX: usize = 14;
 pub const PC_INDEX: usize = 15;
 
-#[derive(Default, Copy, Clone, Debug)]
+#[derive(Default, Copy, Clone, Debug, Serialize, Deserialize)]
 pub struct Registers {
     pub r: [u32; 16],
     pub cpsr: StatusRegister,
@@ -64,7 +65,7 @@ impl Display for Registers {
     }
 }
 
-#[derive(Default, Copy, Clone, Debug)]
+#[derive(Default, Copy, Clone, Debug, Serialize, Deserialize)]
 struct Bank {
     sp: u32,
     lr: u32,
@@ -147,7 +148,7 @@ impl Registers {
     }
 }
 
-#[derive(Default, Copy, Clone, PartialEq, Eq, FromRepr, Debug)]
+#[derive(Default, Copy, Clone, PartialEq, Eq, FromRepr, Debug, Serialize, Deserialize)]
 pub enum OperationState {
     #[default]
     Arm = 0,
@@ -175,7 +176,7 @@ impl OperationState {
 }
 
 #[expect(clippy::struct_excessive_bools)]
-#[derive(Default, Copy, Clone, PartialEq, Eq, Debug)]
+#[derive(Default, Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
 pub struct StatusRegister {
     pub signed: bool,
     pub zero: bool,
@@ -194,6 +195,11 @@ impl StatusRegister {
         self.mode
     }
 
+    #[must_use]
+    pub fn state(self) -> OperationState {
+        self.state
+    }
+
     #[must_use]
     pub fn bits(self) -> u32 {
         0.with_bit(31, self.signed)
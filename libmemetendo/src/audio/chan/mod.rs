@@ -1,4 +1,7 @@
 use intbits::Bits;
+use serde::{Deserialize, Serialize};
+
+use super::inspect;
 
 pub mod noise;
 pub mod tone;
@@ -6,7 +9,7 @@ pub mod wave;
 
 const MAX_VOLUME: u8 = 15;
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct Length<const MAX_COUNTER: u16> {
     channel_enabled: bool,
     enabled: bool,
@@ -16,7 +19,8 @@ pub struct Length<const MAX_COUNTER: u16> {
 
 impl<const MAX_COUNTER: u16> Length<MAX_COUNTER> {
     pub fn step(&mut self) {
-        if !self.enabled {
+        // Once expired, the counter stays at zero until the channel is restarted.
+        if !self.enabled || self.counter == 0 {
             return;
         }
 
@@ -30,6 +34,14 @@ impl<const MAX_COUNTER: u16> Length<MAX_COUNTER> {
         self.channel_enabled
     }
 
+    pub fn remaining(&self) -> Option<u16> {
+        self.enabled.then_some(self.counter)
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.counter <= MAX_COUNTER && self.initial < MAX_COUNTER
+    }
+
     fn set_ctrl_byte(&mut self, idx: usize, value: u8) {
         match idx {
             0 => self.initial = u16::from(value) % MAX_COUNTER,
@@ -50,7 +62,7 @@ impl<const MAX_COUNTER: u16> Length<MAX_COUNTER> {
     }
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct LengthAndEnvelope {
     pub length: Length<64>,
     envelope_enabled: bool,
@@ -88,6 +100,26 @@ impl LengthAndEnvelope {
         }
     }
 
+    pub fn inspect_envelope(&self) -> inspect::Envelope {
+        inspect::Envelope {
+            volume: self.envelope_volume,
+            increase: self.envelope_increase,
+            period: if self.envelope_enabled {
+                self.envelope_period
+            } else {
+                0
+            },
+        }
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.length.is_valid()
+            && self.envelope_volume <= MAX_VOLUME
+            && self.envelope_initial_volume <= MAX_VOLUME
+            && self.envelope_period < 8
+            && self.envelope_clocks < 8
+    }
+
     fn volume(&self) -> u8 {
         if self.length.channel_enabled {
             self.envelope_volume
@@ -1,8 +1,11 @@
 use intbits::Bits;
+use serde::{Deserialize, Serialize};
+
+use crate::audio::inspect;
 
 use super::LengthAndEnvelope;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Noise {
     pub length_and_envelope: LengthAndEnvelope,
     lfsr: u16,
@@ -30,8 +33,9 @@ impl Default for Noise {
 impl Noise {
     pub fn step_noise(&mut self) {
         self.clocks += 1;
-        let period = [8, 16, 32, 48, 64, 80, 96, 112][usize::from(self.period)];
-        if self.clocks < period >> self.period_shift {
+        let period: u8 = [8, 16, 32, 48, 64, 80, 96, 112][usize::from(self.period)];
+        // Shifts can be up to 15, which is too large to shift a u8 by.
+        if self.clocks < period.checked_shr(self.period_shift.into()).unwrap_or(0) {
             return;
         }
         self.clocks = 0;
@@ -44,6 +48,13 @@ impl Noise {
         }
     }
 
+    pub fn is_valid(&self) -> bool {
+        self.length_and_envelope.is_valid()
+            && self.period < 8
+            && self.period_shift < 16
+            && self.clocks < 112
+    }
+
     pub fn volume(&self) -> u8 {
         if self.lfsr.bit(0) {
             0
@@ -86,4 +97,16 @@ impl Noise {
     pub fn ctrl_bits(&self) -> u64 {
         self.cached_bits
     }
+
+    pub fn inspect(&self) -> inspect::Noise {
+        inspect::Noise {
+            enabled: self.length_and_envelope.length.is_channel_enabled(),
+            ratio: self.period,
+            shift: self.period_shift,
+            half_width: self.half_width,
+            lfsr: self.lfsr,
+            envelope: self.length_and_envelope.inspect_envelope(),
+            length_remaining: self.length_and_envelope.length.remaining(),
+        }
+    }
 }
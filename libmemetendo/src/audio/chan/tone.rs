@@ -1,8 +1,11 @@
 use intbits::Bits;
+use serde::{Deserialize, Serialize};
+
+use crate::audio::inspect;
 
 use super::LengthAndEnvelope;
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct Tone {
     pub length_and_envelope: LengthAndEnvelope,
     frequency: u16,
@@ -26,6 +29,14 @@ impl Tone {
         self.duty_step %= 8;
     }
 
+    pub fn is_valid(&self) -> bool {
+        self.length_and_envelope.is_valid()
+            && self.frequency <= MAX_FREQUENCY
+            && self.duty_mode < 4
+            && self.duty_step < 8
+            && self.duty_step_clocks < 2 * (MAX_FREQUENCY + 1)
+    }
+
     pub fn volume(&self) -> u8 {
         // 8 total steps per duty cycle.
         if self.duty_step < [1, 2, 4, 6][usize::from(self.duty_mode)] {
@@ -74,10 +85,20 @@ impl Tone {
     pub fn ctrl_bits(&self) -> u64 {
         self.cached_bits
     }
+
+    pub fn inspect(&self) -> inspect::Tone {
+        inspect::Tone {
+            enabled: self.length_and_envelope.length.is_channel_enabled(),
+            frequency: self.frequency,
+            duty: self.duty_mode,
+            envelope: self.length_and_envelope.inspect_envelope(),
+            length_remaining: self.length_and_envelope.length.remaining(),
+        }
+    }
 }
 
 #[expect(clippy::module_name_repetitions)]
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct ToneAndSweep {
     tone: Tone,
     sweep_enabled: bool,
@@ -130,6 +151,14 @@ impl ToneAndSweep {
         self.tone.step_duty();
     }
 
+    pub fn is_valid(&self) -> bool {
+        self.tone.is_valid()
+            && self.sweep_shadow_frequency <= MAX_FREQUENCY
+            && self.sweep_shift < 8
+            && self.sweep_period < 8
+            && self.sweep_clocks < 8
+    }
+
     pub fn volume(&self) -> u8 {
         self.tone.volume()
     }
@@ -186,4 +215,8 @@ impl ToneAndSweep {
     pub fn ctrl_bits(&self) -> u64 {
         self.cached_bits
     }
+
+    pub fn inspect(&self) -> inspect::Tone {
+        self.tone.inspect()
+    }
 }
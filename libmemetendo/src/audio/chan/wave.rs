@@ -1,6 +1,8 @@
 use intbits::Bits;
+use serde::{Deserialize, Serialize};
 
 use crate::{
+    audio::inspect,
     bus::Bus,
     dma::{Dma, Event},
 };
@@ -8,13 +10,16 @@ use crate::{
 use super::Length;
 
 const WAVE_RAM_BANK_LEN: usize = 16;
+/// Each byte of wave RAM holds two 4-bit samples.
+const BANK_SAMPLES: usize = 2 * WAVE_RAM_BANK_LEN;
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct Wave {
     pub length: Length<256>,
     ram_banks: [[u8; WAVE_RAM_BANK_LEN]; 2],
     two_banks: bool,
     bank_idx: usize,
+    /// Bank selected by `SOUND3CNT_L`; the other bank is the one mapped to IO.
     bank_initial_idx: usize,
     play: bool,
     sample_rate: u16,
@@ -28,15 +33,18 @@ pub struct Wave {
 #[expect(clippy::module_name_repetitions)]
 pub struct WaveRam<'a>(&'a mut Wave);
 
+// IO accesses the bank that isn't selected, even while it's being played in two bank mode, which
+// lets music engines stream samples into the half of the wave that isn't playing.
 // TODO: As the wave RAM is basically one giant shift register, reads and writes may be shifted,
 //       but is this worth implementing?
 impl Bus for WaveRam<'_> {
     fn read_byte(&mut self, addr: u32) -> u8 {
-        self.0.ram_banks[(self.0.bank_idx + 1) % 2][usize::try_from(addr).unwrap()]
+        self.0.ram_banks[self.0.io_bank_idx()][usize::try_from(addr).unwrap()]
     }
 
     fn write_byte(&mut self, addr: u32, value: u8) {
-        self.0.ram_banks[(self.0.bank_idx + 1) % 2][usize::try_from(addr).unwrap()] = value;
+        let bank_idx = self.0.io_bank_idx();
+        self.0.ram_banks[bank_idx][usize::try_from(addr).unwrap()] = value;
     }
 }
 
@@ -53,8 +61,8 @@ impl Wave {
         self.clocks = 0;
 
         self.sample_idx += 1;
-        if self.sample_idx >= WAVE_RAM_BANK_LEN {
-            if !self.two_banks {
+        if self.sample_idx >= BANK_SAMPLES {
+            if self.two_banks {
                 self.bank_idx += 1;
                 self.bank_idx %= 2;
             }
@@ -62,9 +70,24 @@ impl Wave {
         }
     }
 
+    pub fn is_valid(&self) -> bool {
+        self.length.is_valid()
+            && self.bank_idx < 2
+            && self.bank_initial_idx < 2
+            && self.sample_rate < 2048
+            && self.sample_idx < BANK_SAMPLES
+            && self.volume < 4
+            && self.clocks < 2048
+    }
+
+    fn io_bank_idx(&self) -> usize {
+        (self.bank_initial_idx + 1) % 2
+    }
+
     pub fn volume(&self) -> u8 {
         if self.length.channel_enabled && self.play {
-            let bit_idx = 4 * (self.sample_idx % 2);
+            // The upper 4 bits of each byte are played first.
+            let bit_idx = 4 * (1 - self.sample_idx % 2);
             let sample =
                 self.ram_banks[self.bank_idx][self.sample_idx / 2].bits(bit_idx..bit_idx + 4);
 
@@ -89,9 +112,11 @@ impl Wave {
                 self.bank_initial_idx = value.bits(6..7).into();
                 self.play = value.bit(7);
 
-                // TODO: is this correct?
-                self.bank_idx = self.bank_initial_idx;
-                self.sample_idx = 0;
+                // Playback only restarts via SOUND3CNT_X, but a single bank wave plays whichever
+                // bank is selected.
+                if !self.two_banks {
+                    self.bank_idx = self.bank_initial_idx;
+                }
             }
             1 => self.cached_bits.set_bits(8..16, value.into()),
             // SOUND3CNT_H
@@ -129,12 +154,30 @@ impl Wave {
         self.cached_bits
     }
 
-    pub fn wave_ram(&mut self) -> WaveRam {
+    pub fn wave_ram(&mut self) -> WaveRam<'_> {
         WaveRam(self)
     }
+
+    pub fn inspect(&self) -> inspect::Wave {
+        inspect::Wave {
+            enabled: self.length.channel_enabled,
+            playing: self.play,
+            sample_rate: self.sample_rate,
+            volume_percent: if self.force_75_volume {
+                75
+            } else {
+                [0, 100, 50, 25][usize::from(self.volume)]
+            },
+            bank: self.bank_idx,
+            selected_bank: self.bank_initial_idx,
+            two_banks: self.two_banks,
+            sample_idx: self.sample_idx,
+            length_remaining: self.length.remaining(),
+        }
+    }
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct Fifo<const FIFO_A: bool> {
     sample: i8,
     samples: [i8; 32],
@@ -177,10 +220,16 @@ impl<const FIFO_A: bool> Fifo<FIFO_A> {
         self.sample
     }
 
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.start_idx < self.samples.len() && self.len <= self.samples.len()
+    }
+
     pub fn reset(&mut self) {
-        self.sample = 0;
-        self.start_idx = 0;
-        self.len = 0;
+        *self = Self::default();
     }
 }
 
@@ -192,16 +241,12 @@ impl<const FIFO_A: bool> Bus for Fifo<FIFO_A> {
     fn write_byte(&mut self, _addr: u32, value: u8) {
         #[expect(clippy::cast_possible_wrap)]
         let sample = value as i8;
+        // NOTE: Writes to a full FIFO are dropped, so the queued samples keep playing in order.
+        // Real hardware resets the FIFO instead, but our timings aren't exact enough for that;
+        // some games would hang, as their DMA keeps overflowing the FIFO after each reset.
         if self.len < self.samples.len() {
             self.samples[(self.start_idx + self.len) % self.samples.len()] = sample;
             self.len += 1;
-        } else {
-            // Overwrite the oldest sample.
-            // TODO: real hardware resets the FIFO here, but our timings aren't perfect and this
-            // causes some games to hang (e.g: fifo overflow -> fifo clear -> fifo dma -> repeat)
-            self.samples[self.start_idx] = sample;
-            self.start_idx += 1;
-            self.start_idx %= self.samples.len();
         }
     }
 }
@@ -0,0 +1,113 @@
+use std::f32::consts::TAU;
+
+use super::SAMPLE_FREQUENCY;
+
+/// Cutoff of the high-pass filter, which models the capacitor that blocks the DC offset (from
+/// `SOUNDBIAS`) of the output.
+const HIGH_PASS_CUTOFF_HZ: f32 = 20.0;
+
+/// Cutoff of the low-pass filter, which models the analog output smoothing the PWM output of the
+/// DAC.
+const LOW_PASS_CUTOFF_HZ: f32 = 12_000.0;
+
+/// Filters the mixed output to sound closer to the audio output of real hardware, rather than the
+/// raw output of the DAC. Applied to each stereo channel with a first-order high-pass filter, then
+/// a first-order low-pass filter.
+///
+/// Disabled by default, as this is slower and the resulting samples depend on the history of
+/// samples before them.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct OutputFilter {
+    pub enabled: bool,
+    channels: [ChannelState; 2],
+}
+
+#[derive(Debug, Default, Copy, Clone)]
+struct ChannelState {
+    prev_input: f32,
+    high_pass: f32,
+    low_pass: f32,
+}
+
+impl ChannelState {
+    #[inline]
+    fn apply(&mut self, input: i16, high_pass_alpha: f32, low_pass_alpha: f32) -> i16 {
+        let input = f32::from(input);
+        self.high_pass = high_pass_alpha * (self.high_pass + input - self.prev_input);
+        self.prev_input = input;
+        self.low_pass += low_pass_alpha * (self.high_pass - self.low_pass);
+
+        #[expect(clippy::cast_possible_truncation)] // Saturates.
+        {
+            self.low_pass.round() as i16
+        }
+    }
+}
+
+impl OutputFilter {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Forgets the history of samples, without changing whether the filter is enabled.
+    pub fn reset(&mut self) {
+        self.channels = Default::default();
+    }
+
+    #[inline]
+    pub(super) fn apply(&mut self, sample: (i16, i16)) -> (i16, i16) {
+        if !self.enabled {
+            return sample;
+        }
+
+        #[expect(clippy::cast_precision_loss)] // The sample rate is exactly representable.
+        let dt = 1.0 / SAMPLE_FREQUENCY as f32;
+        let high_pass_rc = 1.0 / (TAU * HIGH_PASS_CUTOFF_HZ);
+        let low_pass_rc = 1.0 / (TAU * LOW_PASS_CUTOFF_HZ);
+        let high_pass_alpha = high_pass_rc / (high_pass_rc + dt);
+        let low_pass_alpha = dt / (low_pass_rc + dt);
+
+        (
+            self.channels[0].apply(sample.0, high_pass_alpha, low_pass_alpha),
+            self.channels[1].apply(sample.1, high_pass_alpha, low_pass_alpha),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn disabled_is_passthrough() {
+        let mut filter = OutputFilter::new();
+        assert_eq!(filter.apply((1234, -5678)), (1234, -5678));
+    }
+
+    #[test]
+    fn blocks_dc_and_smooths_steps() {
+        let mut filter = OutputFilter::new();
+        filter.enabled = true;
+
+        // The low-pass filter should stop the output from jumping straight to the new level.
+        let (first, _) = filter.apply((10_000, 0));
+        assert!(first > 0 && first < 1_000, "{first}");
+
+        // After a short while, the output should be close to the input...
+        let mut sample = (0, 0);
+        for _ in 0..SAMPLE_FREQUENCY / 5000 {
+            sample = filter.apply((10_000, -10_000));
+        }
+        assert!(sample.0 > 9_000 && sample.1 < -9_000, "{sample:?}");
+
+        // ...but a constant offset should eventually decay away.
+        for _ in 0..SAMPLE_FREQUENCY / 2 {
+            sample = filter.apply((10_000, -10_000));
+        }
+        assert!(sample.0.abs() < 100 && sample.1.abs() < 100, "{sample:?}");
+
+        filter.reset();
+        assert!(filter.enabled);
+    }
+}
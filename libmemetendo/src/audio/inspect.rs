@@ -0,0 +1,124 @@
+//! Read-only snapshots of the sound channels' state, for debuggers; see `Audio::inspect`.
+//!
+//! [`Audio::inspect`]: super::Audio::inspect
+
+/// State of every sound channel.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Channels {
+    /// Channels 1 (which also has a frequency sweep) and 2.
+    pub tones: [Tone; 2],
+    /// Channel 3.
+    pub wave: Wave,
+    /// Channel 4.
+    pub noise: Noise,
+    /// Direct Sound channels A and B.
+    pub fifos: [Fifo; 2],
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Envelope {
+    /// Current volume, from 0 to 15.
+    pub volume: u8,
+    pub increase: bool,
+    /// Frame sequencer steps (at 64 Hz) between volume changes; 0 if the envelope is stopped.
+    pub period: u8,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Tone {
+    pub enabled: bool,
+    /// The 11-bit frequency from the channel's control register; see `Self::frequency_hz`.
+    pub frequency: u16,
+    /// Duty cycle: 0 = 12.5%, 1 = 25%, 2 = 50% and 3 = 75%.
+    pub duty: u8,
+    pub envelope: Envelope,
+    /// Length counter steps (at 256 Hz) until the channel is disabled, if the length is enabled.
+    pub length_remaining: Option<u16>,
+}
+
+impl Tone {
+    /// The frequency of the tone being played, in Hz.
+    #[must_use]
+    pub fn frequency_hz(&self) -> f32 {
+        131_072.0 / f32::from(2048 - self.frequency)
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Wave {
+    pub enabled: bool,
+    /// Whether the channel's DAC is on (bit 7 of `SOUND3CNT_L`).
+    pub playing: bool,
+    /// The 11-bit sample rate from the channel's control register; see `Self::sample_rate_hz`.
+    pub sample_rate: u16,
+    /// Output volume as a percentage: 0, 25, 50, 75 or 100.
+    pub volume_percent: u8,
+    /// Index of the wave RAM bank being played.
+    pub bank: usize,
+    /// Index of the wave RAM bank selected by `SOUND3CNT_L`; the other bank is the one mapped to
+    /// IO, even while it's being played.
+    pub selected_bank: usize,
+    /// Whether both banks are played as one 64-sample wave.
+    pub two_banks: bool,
+    /// Index of the 4-bit sample being played within the bank, from 0 to 31.
+    pub sample_idx: usize,
+    /// Length counter steps (at 256 Hz) until the channel is disabled, if the length is enabled.
+    pub length_remaining: Option<u16>,
+}
+
+impl Wave {
+    /// The rate that samples are played at, in Hz.
+    #[must_use]
+    pub fn sample_rate_hz(&self) -> f32 {
+        2_097_152.0 / f32::from(2048 - self.sample_rate)
+    }
+
+    /// Index of the sample being played within the whole wave, which starts at the selected bank
+    /// and is 32 samples long, or 64 if `Self::two_banks`.
+    #[must_use]
+    pub fn position(&self) -> usize {
+        if self.bank == self.selected_bank {
+            self.sample_idx
+        } else {
+            32 + self.sample_idx
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Noise {
+    pub enabled: bool,
+    /// Dividing ratio of the frequency, from 0 to 7; see `Self::frequency_hz`.
+    pub ratio: u8,
+    /// Shift of the frequency, from 0 to 15; see `Self::frequency_hz`.
+    pub shift: u8,
+    /// Whether the LFSR is 7 bits wide, rather than 15.
+    pub half_width: bool,
+    pub lfsr: u16,
+    pub envelope: Envelope,
+    /// Length counter steps (at 256 Hz) until the channel is disabled, if the length is enabled.
+    pub length_remaining: Option<u16>,
+}
+
+impl Noise {
+    /// The frequency that the LFSR is clocked at, in Hz.
+    #[must_use]
+    pub fn frequency_hz(&self) -> f32 {
+        let ratio = if self.ratio == 0 {
+            0.5
+        } else {
+            f32::from(self.ratio)
+        };
+        524_288.0 / ratio / 2.0_f32.powi(i32::from(self.shift) + 1)
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Fifo {
+    /// Number of samples queued, from 0 to 32.
+    pub len: usize,
+    /// The sample being played.
+    pub sample: i8,
+    /// Index of the timer whose overflows play the next sample.
+    pub timer_idx: usize,
+}
@@ -1,22 +1,41 @@
 use std::mem::{replace, take};
 
 use intbits::Bits;
+use serde::{Deserialize, Serialize};
+use tinyvec::ArrayVec;
 
 use crate::{arm7tdmi::CYCLES_PER_SECOND, bus::Bus, dma::Dma};
 
-use self::chan::{
-    noise::Noise,
-    tone::{Tone, ToneAndSweep},
-    wave::{Fifo, Wave},
+use self::{
+    chan::{
+        noise::Noise,
+        tone::{Tone, ToneAndSweep},
+        wave::{Fifo, Wave},
+    },
+    filter::OutputFilter,
 };
 
 mod chan;
+pub mod filter;
+pub mod inspect;
 
 pub trait Callback {
     fn push_sample(&mut self, sample: (i16, i16));
+
+    /// Pushes a batch of samples, in order. Samples are usually pushed via this method, in batches
+    /// of up to `SAMPLE_BATCH_LEN` samples (fewer at the end of a frame, or when flushed via
+    /// `Audio::flush_samples`); implement it to avoid the overhead of handling samples one by one.
+    fn push_samples(&mut self, samples: &[(i16, i16)]) {
+        for &sample in samples {
+            self.push_sample(sample);
+        }
+    }
 }
 
-#[derive(Debug, Default)]
+/// Maximum number of samples accumulated by `Audio` before they're pushed to its callback.
+pub const SAMPLE_BATCH_LEN: usize = 256;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Audio {
     channels: (ToneAndSweep, Tone, Wave, Noise, Fifo<true>, Fifo<false>),
     frame_seq_step: u8,
@@ -31,8 +50,16 @@ pub struct Audio {
     fifo_full_volume: [bool; 2],
     fifo_timer_idx: [usize; 2],
     bias: i16,
+    /// `SOUNDBIAS`'s amplitude resolution (sampling cycle) setting; the DAC's output has
+    /// `9 - sampling_cycle` bits of depth.
     sampling_cycle: u8,
+    #[serde(skip)]
     mix_cache: cache::Mix,
+    #[serde(skip)]
+    pub output_filter: OutputFilter,
+    /// Samples yet to be pushed to the callback.
+    #[serde(skip)]
+    pending_samples: ArrayVec<[(i16, i16); SAMPLE_BATCH_LEN]>,
 
     cached_soundcnt_bits: u64,
     cached_soundbias_bits: u64,
@@ -40,7 +67,7 @@ pub struct Audio {
 
 mod cache {
     /// Cache for certain values computed by `mixed_sample`, to be potentially reused.
-    #[derive(Debug, Default)]
+    #[derive(Debug, Clone, Default)]
     pub struct Mix {
         dmg: Option<([u8; 4], (i16, i16))>,
         fifo: Option<([i8; 2], (i16, i16))>,
@@ -78,6 +105,10 @@ pub const CYCLES_PER_SAMPLE: u16 = CYCLES_PER_FREQ_TIMER_CLOCK;
 // Frequency timer runs at 2,097,152 Hz.
 const CYCLES_PER_FREQ_TIMER_CLOCK: u16 = (CYCLES_PER_SECOND / 2_097_152) as _;
 
+// Frame sequencer runs at 512 Hz.
+#[expect(clippy::cast_possible_truncation)] // it's fine clippy, gosh
+const CYCLES_PER_FRAME_SEQ_CLOCK: u16 = (CYCLES_PER_SECOND / 512) as _;
+
 impl Audio {
     #[must_use]
     pub fn new() -> Self {
@@ -86,6 +117,7 @@ impl Audio {
 
     pub fn reset(&mut self, skip_bios: bool) {
         self.mix_cache = cache::Mix::default();
+        self.output_filter.reset();
 
         // TODO: proper resetting; for now, just reset SOUNDBIAS so audio doesn't suck
         if skip_bios {
@@ -93,11 +125,77 @@ impl Audio {
         }
     }
 
-    pub fn step(&mut self, cb: &mut impl Callback, dma: &mut Dma, cycles: u8) {
-        // Frame sequencer runs at 512 Hz.
-        #[expect(clippy::cast_possible_truncation)] // it's fine clippy, gosh
-        const CYCLES_PER_FRAME_SEQ_CLOCK: u16 = (CYCLES_PER_SECOND / 512) as _;
+    /// Migrates state loaded from version 1 of its save state format, which stored the bias level
+    /// halved.
+    pub(crate) fn migrate_v1(mut self) -> Self {
+        // Out of range levels are rejected by `Self::is_valid` after migrating.
+        self.bias = self.bias.saturating_mul(2);
+        self
+    }
+
+    /// Whether the state is in the range the registers allow, which is always the case unless it
+    /// was loaded from a malformed save state; such state can panic when stepped.
+    pub(crate) fn is_valid(&self) -> bool {
+        let (tone_and_sweep, tone, wave, noise, fifo_a, fifo_b) = &self.channels;
+        tone_and_sweep.is_valid()
+            && tone.is_valid()
+            && wave.is_valid()
+            && noise.is_valid()
+            && fifo_a.is_valid()
+            && fifo_b.is_valid()
+            && self.frame_seq_step < 8
+            && self.frame_seq_cycle_accum < CYCLES_PER_FRAME_SEQ_CLOCK
+            && self.freq_timer_cycles_accum < CYCLES_PER_FREQ_TIMER_CLOCK
+            && self.out_dmg_volume.0 < 8
+            && self.out_dmg_volume.1 < 8
+            && self.dmg_volume_ratio <= 2
+            && self.fifo_timer_idx.iter().all(|&idx| idx < 2)
+            && (0..0x400).contains(&self.bias)
+            && self.sampling_cycle < 4
+    }
+
+    #[cfg(test)]
+    pub(crate) fn to_v1(&self) -> Self {
+        let mut audio = self.clone();
+        audio.bias /= 2;
+        audio
+    }
 
+    /// Whether the sound circuits are enabled via `SOUNDCNT_X`'s master enable bit.
+    #[must_use]
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Returns a snapshot of the state of every channel, for debuggers.
+    #[must_use]
+    pub fn inspect(&self) -> inspect::Channels {
+        let fifo = |len, sample, timer_idx| inspect::Fifo {
+            len,
+            sample,
+            timer_idx,
+        };
+
+        inspect::Channels {
+            tones: [self.channels.0.inspect(), self.channels.1.inspect()],
+            wave: self.channels.2.inspect(),
+            noise: self.channels.3.inspect(),
+            fifos: [
+                fifo(
+                    self.channels.4.len(),
+                    self.channels.4.sample(),
+                    self.fifo_timer_idx[0],
+                ),
+                fifo(
+                    self.channels.5.len(),
+                    self.channels.5.sample(),
+                    self.fifo_timer_idx[1],
+                ),
+            ],
+        }
+    }
+
+    pub fn step(&mut self, cb: &mut impl Callback, dma: &mut Dma, cycles: u8) {
         if !self.enabled {
             return;
         }
@@ -140,7 +238,20 @@ impl Audio {
             self.channels.2.step_wave();
             self.channels.3.step_noise();
 
-            cb.push_sample(self.mix_sample());
+            let sample = self.mix_sample();
+            self.pending_samples.push(self.output_filter.apply(sample));
+            if self.pending_samples.len() == SAMPLE_BATCH_LEN {
+                self.flush_samples(cb);
+            }
+        }
+    }
+
+    /// Pushes the samples accumulated since the last batch to `cb`. `Gba::step` does this at the end
+    /// of each frame.
+    pub fn flush_samples(&mut self, cb: &mut impl Callback) {
+        if !self.pending_samples.is_empty() {
+            cb.push_samples(&self.pending_samples);
+            self.pending_samples.clear();
         }
     }
 
@@ -224,14 +335,13 @@ impl Audio {
             mixed_dmg_sample.0 + mixed_fifo_sample.0,
             mixed_dmg_sample.1 + mixed_fifo_sample.1,
         );
-        // Apply the bias, which is used to ensure the sample is clipped within the signed 10-bit
-        // range for the DAC.
-        mixed_sample.0 += self.bias;
-        mixed_sample.0 = mixed_sample.0.clamp(0, 0x3ff);
-        mixed_sample.0 -= self.bias;
-        mixed_sample.1 += self.bias;
-        mixed_sample.1 = mixed_sample.1.clamp(0, 0x3ff);
-        mixed_sample.1 -= self.bias;
+        // Apply the bias, which is used to ensure the sample is clipped within the unsigned 10-bit
+        // range for the DAC. The DAC then drops the low bits not within the amplitude resolution
+        // chosen by SOUNDBIAS, as higher sampling rates of its PWM output have less bit depth.
+        let resolution_mask = !0 << (1 + self.sampling_cycle);
+        let apply_bias =
+            |sample: i16| ((sample + self.bias).clamp(0, 0x3ff) & resolution_mask) - self.bias;
+        mixed_sample = (apply_bias(mixed_sample.0), apply_bias(mixed_sample.1));
 
         // Scale to the i16 range.
         mixed_sample.0 = mixed_sample.0.saturating_mul(i16::MAX / 0x200);
@@ -243,18 +353,30 @@ impl Audio {
 
     pub fn notify_timer_overflow(&mut self, timer_idx: usize, count: u8) {
         if self.fifo_timer_idx[0] == timer_idx {
-            self.fifo_pending_steps[0] += count;
+            self.fifo_pending_steps[0] = self.fifo_pending_steps[0].saturating_add(count);
         }
         if self.fifo_timer_idx[1] == timer_idx {
-            self.fifo_pending_steps[1] += count;
+            self.fifo_pending_steps[1] = self.fifo_pending_steps[1].saturating_add(count);
         }
     }
 }
 
+/// Readable bits of each sound control register from `SOUND1CNT_L` to `SOUNDBIAS` (and unused
+/// halfwords in-between), indexed by (address - 0x60) / 2. Other bits are write-only or unused, and
+/// read as zero.
+const READ_MASKS: [u16; 24] = [
+    0x007f, 0xffc0, 0x4000, 0x0000, // SOUND1CNT_L, SOUND1CNT_H, SOUND1CNT_X
+    0xffc0, 0x0000, 0x4000, 0x0000, // SOUND2CNT_L, SOUND2CNT_H
+    0x00e0, 0xe000, 0x4000, 0x0000, // SOUND3CNT_L, SOUND3CNT_H, SOUND3CNT_X
+    0xff00, 0x0000, 0x40ff, 0x0000, // SOUND4CNT_L, SOUND4CNT_H
+    0xff77, 0x770f, 0x008f, 0x0000, // SOUNDCNT_L, SOUNDCNT_H, SOUNDCNT_X
+    0xc3fe, 0x0000, 0x0000, 0x0000, // SOUNDBIAS
+];
+
 impl Bus for Audio {
     fn read_byte(&mut self, addr: u32) -> u8 {
         let ctrl_offset = 8 * usize::try_from(addr & 7).unwrap();
-        match addr {
+        let value = match addr {
             // SOUND1CNT
             0x60..=0x67 => self
                 .channels
@@ -332,8 +454,14 @@ impl Bus for Audio {
                 .unwrap(),
             // WAVE_RAM
             0x90..=0x9f => self.channels.2.wave_ram().read_byte(addr & 0xf),
-            0x00..=0x5f | 0xa8.. => panic!("IO register address OOB"),
             _ => 0,
+        };
+
+        if let Some(&mask) = READ_MASKS.get(usize::try_from(addr.wrapping_sub(0x60) / 2).unwrap()) {
+            let mask_offset = 8 * usize::try_from(addr & 1).unwrap();
+            value & u8::try_from(mask.bits(mask_offset..mask_offset + 8)).unwrap()
+        } else {
+            value
         }
     }
 
@@ -431,10 +559,10 @@ impl Bus for Audio {
                     .set_bits(ctrl_offset..ctrl_offset + 8, value.into());
 
                 match addr & 7 {
-                    0 => self.bias.set_bits(..7, value.bits(1..).into()),
+                    0 => self.bias.set_bits(1..8, value.bits(1..).into()),
                     1 => {
-                        self.bias.set_bits(7.., value.bits(..2).into());
-                        self.sampling_cycle = value.bits(6..); // TODO: implement this?
+                        self.bias.set_bits(8..10, value.bits(..2).into());
+                        self.sampling_cycle = value.bits(6..);
                     }
                     _ => {}
                 }
@@ -447,7 +575,279 @@ impl Bus for Audio {
             0xa0..=0xa3 => self.channels.4.write_byte(addr & 3, value),
             // FIFO_B
             0xa4..=0xa7 => self.channels.5.write_byte(addr & 3, value),
-            0x00..=0x5f | 0xa8.. => panic!("IO register address OOB"),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn new_enabled_audio() -> Audio {
+        let mut audio = Audio::new();
+        audio.write_hword(0x84, 0x0080);
+        audio
+    }
+
+    #[test]
+    fn read_masks() {
+        let mut audio = new_enabled_audio();
+        for (addr, &mask) in (0x60..).step_by(2).zip(READ_MASKS.iter()) {
+            if addr == 0x84 {
+                // Contains the read-only channel status bits; tested separately below.
+                continue;
+            }
+
+            audio.write_hword(addr, 0xffff);
+            assert_eq!(
+                audio.read_hword(addr),
+                mask,
+                "register at {addr:#x} should read back the masked value"
+            );
+            audio.write_hword(addr, 0);
+            assert_eq!(audio.read_hword(addr), 0, "register at {addr:#x}");
+        }
+
+        // SOUNDCNT_X: only the master enable and channel status bits are readable.
+        audio.write_hword(0x84, 0xffff);
+        assert_eq!(audio.read_hword(0x84) & !0xf, 0x0080);
+    }
+
+    #[test]
+    fn soundcnt_h_hides_fifo_reset_bits() {
+        let mut audio = new_enabled_audio();
+        audio.write_hword(0x82, 0x8800);
+        assert_eq!(audio.read_hword(0x82), 0);
+        audio.write_hword(0x82, 0xffff);
+        assert_eq!(audio.read_hword(0x82), 0x770f);
+    }
+
+    #[test]
+    fn fifo_reset_clears_samples() {
+        let mut audio = new_enabled_audio();
+        let mut dma = Dma::new();
+        audio.write_word(0xa0, 0x0403_0201);
+        audio.write_word(0xa4, 0x0403_0201);
+
+        audio.write_hword(0x82, 0x0800); // Reset FIFO A
+        audio.channels.4.step(&mut dma, 1);
+        audio.channels.5.step(&mut dma, 1);
+        assert_eq!(audio.channels.4.sample(), 0);
+        assert_eq!(audio.channels.5.sample(), 1);
+
+        audio.write_hword(0x82, 0x8000); // Reset FIFO B
+        audio.channels.5.step(&mut dma, 1);
+        assert_eq!(audio.channels.5.sample(), 0);
+
+        // New samples should be queued from the start.
+        audio.write_byte(0xa0, 42);
+        audio.channels.4.step(&mut dma, 1);
+        assert_eq!(audio.channels.4.sample(), 42);
+    }
+
+    #[test]
+    fn fifo_drops_writes_when_full() {
+        let mut audio = new_enabled_audio();
+        let mut dma = Dma::new();
+        for i in 0..8 {
+            audio.write_word(
+                0xa0,
+                u32::from_le_bytes([4 * i, 4 * i + 1, 4 * i + 2, 4 * i + 3]),
+            );
+        }
+        audio.write_word(0xa0, 0x7f7f_7f7f);
+        assert_eq!(audio.channels.4.len(), 32);
+
+        // Once there's room, writes are queued after the samples that were kept.
+        audio.channels.4.step(&mut dma, 1);
+        assert_eq!(audio.channels.4.sample(), 0);
+        audio.write_byte(0xa0, 42);
+        for i in 1..32 {
+            audio.channels.4.step(&mut dma, 1);
+            assert_eq!(audio.channels.4.sample(), i);
         }
+        audio.channels.4.step(&mut dma, 1);
+        assert_eq!(audio.channels.4.sample(), 42);
+        audio.channels.4.step(&mut dma, 1);
+        assert_eq!(audio.channels.4.sample(), 0);
+    }
+
+    #[test]
+    fn soundbias_resolution_truncates_samples() {
+        let mut audio = new_enabled_audio();
+        let mut dma = Dma::new();
+        audio.write_hword(0x82, 0x210c); // SOUNDCNT_H: full volume FIFO A right, FIFO B left
+        audio.write_byte(0xa0, 31);
+        audio.write_byte(0xa4, 0xe1); // -31
+        audio.channels.4.step(&mut dma, 1);
+        audio.channels.5.step(&mut dma, 1);
+
+        // Samples are biased by 0x200 to (388, 636) for the DAC, then truncated to the resolution.
+        for (resolution, expected) in [
+            (0, (-124, 124)),
+            (1, (-124, 124)),
+            (2, (-128, 120)),
+            (3, (-128, 112)),
+        ] {
+            audio.write_hword(0x88, 0x200 | (resolution << 14));
+            let expected = (
+                expected.0 * (i16::MAX / 0x200),
+                expected.1 * (i16::MAX / 0x200),
+            );
+            assert_eq!(audio.mix_sample(), expected, "resolution {resolution}");
+        }
+
+        // The bias level is applied in full, moving the clipping point.
+        audio.write_hword(0x88, 0x0300);
+        assert_eq!(audio.mix_sample().1, 124 * (i16::MAX / 0x200));
+        // Clipped at 0x3ff, then truncated to 9 bits.
+        audio.write_hword(0x88, 0x03c0);
+        assert_eq!(audio.mix_sample().1, (0x3fe - 0x3c0) * (i16::MAX / 0x200));
+    }
+
+    #[test]
+    fn expired_length_stays_expired() {
+        let mut audio = new_enabled_audio();
+        audio.write_hword(0x68, 0x003f); // SOUND2CNT_L: length 63, so 1 step remains
+        audio.write_hword(0x6c, 0xc000); // SOUND2CNT_H: restart, length enabled
+
+        for _ in 0..3 {
+            audio.channels.1.length_and_envelope.length.step();
+        }
+        assert!(!audio
+            .channels
+            .1
+            .length_and_envelope
+            .length
+            .is_channel_enabled());
+        assert_eq!(
+            audio.channels.1.length_and_envelope.length.remaining(),
+            Some(0)
+        );
+    }
+
+    #[test]
+    fn noise_steps_with_any_shift() {
+        let mut audio = new_enabled_audio();
+        for shift in 0..16 {
+            audio.write_hword(0x7c, shift << 4); // SOUND4CNT_H
+            for _ in 0..256 {
+                audio.channels.3.step_noise();
+            }
+        }
+    }
+
+    #[test]
+    fn fifo_pending_steps_saturate() {
+        let mut audio = new_enabled_audio();
+        audio.notify_timer_overflow(0, 200);
+        audio.notify_timer_overflow(0, 200);
+        assert_eq!(audio.fifo_pending_steps, [u8::MAX; 2]);
+    }
+
+    #[test]
+    fn inspect_channels() {
+        let mut audio = new_enabled_audio();
+        audio.write_hword(0x62, 0xab80); // SOUND1CNT_H: volume 10, increase every 3 steps, 50% duty
+        audio.write_hword(0x64, 0xc780); // SOUND1CNT_X: restart, length enabled, frequency 0x780
+        audio.write_hword(0x70, 0x00c0); // SOUND3CNT_L: play bank 1
+        audio.write_hword(0x72, 0x4000); // SOUND3CNT_H: 50% volume
+        audio.write_hword(0x7c, 0x0052); // SOUND4CNT_H: ratio 2, shift 5
+        audio.write_hword(0x82, 0x4000); // SOUNDCNT_H: FIFO B uses timer 1
+        audio.write_word(0xa4, 0x0403_0201);
+
+        let channels = audio.inspect();
+        let tone = channels.tones[0];
+        assert!(tone.enabled);
+        assert_eq!(tone.frequency, 0x780);
+        assert!((tone.frequency_hz() - 1024.0).abs() < f32::EPSILON);
+        assert_eq!(tone.duty, 2);
+        assert_eq!(tone.envelope.volume, 10);
+        assert!(tone.envelope.increase);
+        assert_eq!(tone.envelope.period, 3);
+        assert_eq!(tone.length_remaining, Some(64));
+        assert!(!channels.tones[1].enabled);
+
+        assert!(channels.wave.playing);
+        assert_eq!(channels.wave.bank, 1);
+        assert_eq!(channels.wave.volume_percent, 50);
+        assert_eq!(channels.wave.length_remaining, None);
+
+        assert_eq!((channels.noise.ratio, channels.noise.shift), (2, 5));
+        assert!((channels.noise.frequency_hz() - 4096.0).abs() < f32::EPSILON);
+
+        assert_eq!(channels.fifos[0].len, 0);
+        assert_eq!(channels.fifos[1].len, 4);
+        assert_eq!(channels.fifos[1].timer_idx, 1);
+    }
+
+    /// Returns the samples played by channel 3 over the next `count` steps of its timer, which
+    /// each play the next sample at the sample rate set by `restart_wave`.
+    fn wave_samples(audio: &mut Audio, count: usize) -> Vec<u8> {
+        (0..count)
+            .map(|_| {
+                let sample = audio.channels.2.volume();
+                audio.channels.2.step_wave();
+                sample
+            })
+            .collect()
+    }
+
+    /// Restarts channel 3 at full volume and the highest sample rate.
+    fn restart_wave(audio: &mut Audio, cnt_l: u16) {
+        audio.write_hword(0x70, cnt_l); // SOUND3CNT_L
+        audio.write_hword(0x72, 0x2000); // SOUND3CNT_H: 100% volume
+        audio.write_hword(0x74, 0x87ff); // SOUND3CNT_X: restart, sample rate 2047
+    }
+
+    #[test]
+    fn wave_plays_whole_banks_upper_samples_first() {
+        let mut audio = new_enabled_audio();
+        audio.write_hword(0x70, 0x0040); // SOUND3CNT_L: select bank 1, so bank 0 is mapped to IO
+        for i in 0..16 {
+            audio.write_byte(0x90 + u32::from(i), (i << 4) | (15 - i));
+        }
+        audio.write_hword(0x70, 0x0000); // SOUND3CNT_L: select bank 0, so bank 1 is mapped to IO
+        for addr in 0x90..0xa0 {
+            audio.write_byte(addr, 0x77);
+        }
+
+        restart_wave(&mut audio, 0x0080); // SOUND3CNT_L: play bank 0
+        let bank_0: Vec<_> = (0..16).flat_map(|i| [i, 15 - i]).collect();
+        // A single bank loops.
+        assert_eq!(wave_samples(&mut audio, 32), bank_0);
+        assert_eq!(wave_samples(&mut audio, 32), bank_0);
+
+        restart_wave(&mut audio, 0x00a0); // SOUND3CNT_L: play banks 0 and 1
+        assert_eq!(wave_samples(&mut audio, 32), bank_0);
+        assert_eq!(wave_samples(&mut audio, 32), [7; 32]);
+        assert_eq!(wave_samples(&mut audio, 32), bank_0);
+    }
+
+    #[test]
+    fn wave_ram_writes_while_playing() {
+        let mut audio = new_enabled_audio();
+        restart_wave(&mut audio, 0x00a0); // SOUND3CNT_L: play banks 0 and 1, starting at 0
+        wave_samples(&mut audio, 32 + 4);
+        let wave = audio.inspect().wave;
+        assert_eq!((wave.bank, wave.selected_bank), (1, 0));
+        assert_eq!((wave.sample_idx, wave.position()), (4, 36));
+
+        // IO maps to the unselected bank 1, even though it's being played.
+        audio.write_byte(0x92, 0xab);
+        assert_eq!(audio.read_byte(0x92), 0xab);
+        assert_eq!(wave_samples(&mut audio, 2), [0xa, 0xb]);
+
+        // A single bank wave switches to the newly selected bank without restarting, so the bank
+        // being written to is never the one being played.
+        audio.write_hword(0x70, 0x00c0); // SOUND3CNT_L: play bank 1
+        let wave = audio.inspect().wave;
+        assert_eq!((wave.bank, wave.sample_idx, wave.position()), (1, 6, 6));
+        audio.write_byte(0x94, 0xcd);
+        assert_eq!(wave_samples(&mut audio, 2), [0, 0]);
+        audio.write_hword(0x70, 0x0080); // SOUND3CNT_L: play bank 0
+        assert_eq!(wave_samples(&mut audio, 2), [0xc, 0xd]);
+        assert_eq!(audio.read_byte(0x92), 0xab);
     }
 }
@@ -1,6 +1,46 @@
-use std::rc::Rc;
+use std::{
+    fmt::{self, Display, Formatter},
+    rc::Rc,
+};
 
-use crate::{bus::Bus, InvalidRomSize};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bus::Bus,
+    cart::hash::{self, Hashes},
+    InvalidRomSize,
+};
+
+/// SHA-1 of the official GBA BIOS (CRC32 81977335).
+const OFFICIAL_SHA1: [u8; 20] = [
+    0x30, 0x0c, 0x20, 0xdf, 0x67, 0x31, 0xa3, 0x39, 0x52, 0xde, 0xd8, 0xc4, 0x36, 0xf7, 0xf1, 0x86,
+    0xd2, 0x5d, 0x34, 0x92,
+];
+
+/// Result of `Rom::verify`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Verification {
+    /// A dump of the official BIOS.
+    Official,
+    /// Not the official BIOS: either a replacement BIOS (which may not run every game
+    /// correctly), or a corrupt dump.
+    Unknown,
+    /// Every byte is the same (e.g: all 0s), so it's not a BIOS at all.
+    Blank,
+}
+
+impl Display for Verification {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Official => write!(f, "official BIOS"),
+            Self::Unknown => write!(
+                f,
+                "unknown BIOS; it may be a replacement BIOS or a corrupt dump of the official one"
+            ),
+            Self::Blank => write!(f, "blank BIOS; the file is likely corrupt"),
+        }
+    }
+}
 
 #[derive(Clone)]
 pub struct Rom(Rc<[u8]>);
@@ -25,11 +65,34 @@ impl Rom {
     pub fn new(buf: Rc<[u8]>) -> Result<Self, InvalidRomSize> {
         Self::try_from(buf)
     }
+
+    #[must_use]
+    pub fn hashes(&self) -> Hashes {
+        Hashes::new(&self.0)
+    }
+
+    /// Checks whether the ROM is the official BIOS. Bad BIOS dumps are a common cause of games
+    /// failing to boot, so frontends should warn users if it isn't.
+    #[must_use]
+    pub fn verify(&self) -> Verification {
+        if self.0.iter().all(|&b| b == self.0[0]) {
+            Verification::Blank
+        } else if hash::sha1(&self.0) == OFFICIAL_SHA1 {
+            Verification::Official
+        } else {
+            Verification::Unknown
+        }
+    }
 }
 
 #[derive(Clone)]
 pub struct Bios {
     rom: Rom,
+    pub(crate) protection: Protection,
+}
+
+#[derive(Debug, Default, Copy, Clone, Serialize, Deserialize)]
+pub(crate) struct Protection {
     readable: bool,
     prefetch_addr: u32,
 }
@@ -39,30 +102,44 @@ impl Bios {
     pub fn new(rom: Rom) -> Self {
         Self {
             rom,
-            readable: false,
-            prefetch_addr: 0,
+            protection: Protection::default(),
         }
     }
 
     pub fn reset(&mut self) {
-        self.readable = false;
-        self.prefetch_addr = 0;
+        self.protection = Protection::default();
     }
 
     pub fn update_protection(&mut self, prefetch_addr: u32) {
-        self.readable = prefetch_addr < 0x4000;
-        if self.readable {
-            self.prefetch_addr = prefetch_addr & !0b11;
+        self.protection.readable = prefetch_addr < 0x4000;
+        if self.protection.readable {
+            self.protection.prefetch_addr = prefetch_addr & !0b11;
         }
     }
 }
 
 impl Bus for Bios {
     fn read_byte(&mut self, addr: u32) -> u8 {
-        self.rom.0.as_ref().read_byte(if self.readable {
+        self.rom.0.as_ref().read_byte(if self.protection.readable {
             addr
         } else {
-            self.prefetch_addr | (addr & 0b11)
+            self.protection.prefetch_addr | (addr & 0b11)
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verifies_rom() {
+        let rom = |buf: Vec<u8>| Rom::new(Rc::from(buf)).unwrap();
+        assert_eq!(rom(vec![0; 0x4000]).verify(), Verification::Blank);
+        assert_eq!(rom(vec![0xff; 0x4000]).verify(), Verification::Blank);
+
+        let mut buf = vec![0; 0x4000];
+        buf[0xdc] = 1;
+        assert_eq!(rom(buf).verify(), Verification::Unknown);
+    }
+}
@@ -0,0 +1,384 @@
+//! Building blocks for composing buses declaratively, such as for tests and tools: `Router` maps
+//! buses to address ranges, `Mirror` repeats a bus over the address space, and `Logged` records
+//! the accesses made to a bus. Buses of different types can be combined as `Box<dyn Bus>`.
+//!
+//! The system's own bus (`gba::Bus`) is wired by hand instead, as it's performance-critical.
+
+use std::ops::RangeInclusive;
+
+use crate::debug::trace::AccessKind;
+
+use super::{
+    read_hword_as_bytes, read_word_as_hwords, write_hword_as_bytes, write_word_as_hwords, Bus,
+};
+
+/// Routes accesses to the buses mapped to their addresses, which receive addresses relative to the
+/// start of their range. Unmapped addresses read `Self::with_open_bus`'s value and ignore writes.
+///
+/// Hword and word accesses are passed whole to a bus if they fit within its range; otherwise,
+/// they're split into smaller accesses.
+#[derive(Default)]
+pub struct Router<'a> {
+    routes: Vec<(RangeInclusive<u32>, Box<dyn Bus + 'a>)>,
+    open_bus: u8,
+}
+
+impl<'a> Router<'a> {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Maps `bus` to `range`, returning the router for chaining. Where ranges overlap, the bus
+    /// that was mapped first takes precedence.
+    #[must_use]
+    pub fn with(mut self, range: RangeInclusive<u32>, bus: impl Bus + 'a) -> Self {
+        self.map(range, bus);
+        self
+    }
+
+    /// Sets the value read from each byte of unmapped addresses (0 by default).
+    #[must_use]
+    pub fn with_open_bus(mut self, value: u8) -> Self {
+        self.open_bus = value;
+        self
+    }
+
+    /// See `Self::with`.
+    pub fn map(&mut self, range: RangeInclusive<u32>, bus: impl Bus + 'a) {
+        self.routes.push((range, Box::new(bus)));
+    }
+
+    /// Returns the index of the route that `len` bytes at `addr` fit within, and the address
+    /// relative to its start.
+    fn find(&self, addr: u32, len: u32) -> Option<(usize, u32)> {
+        let end_addr = addr.checked_add(len - 1)?;
+        self.routes
+            .iter()
+            .position(|(range, _)| range.contains(&addr) && range.contains(&end_addr))
+            .map(|i| (i, addr - self.routes[i].0.start()))
+    }
+
+    fn route(&mut self, addr: u32, len: u32) -> Option<(&mut (dyn Bus + 'a), u32)> {
+        let (i, offset) = self.find(addr, len)?;
+        Some((self.routes[i].1.as_mut(), offset))
+    }
+}
+
+impl Bus for Router<'_> {
+    fn read_byte(&mut self, addr: u32) -> u8 {
+        let open_bus = self.open_bus;
+        self.route(addr, 1)
+            .map_or(open_bus, |(bus, offset)| bus.read_byte(offset))
+    }
+
+    fn read_hword(&mut self, addr: u32) -> u16 {
+        match self.route(addr, 2) {
+            Some((bus, offset)) => bus.read_hword(offset),
+            None => read_hword_as_bytes(self, addr),
+        }
+    }
+
+    fn read_word(&mut self, addr: u32) -> u32 {
+        match self.route(addr, 4) {
+            Some((bus, offset)) => bus.read_word(offset),
+            None => read_word_as_hwords(self, addr),
+        }
+    }
+
+    fn write_byte(&mut self, addr: u32, value: u8) {
+        if let Some((bus, offset)) = self.route(addr, 1) {
+            bus.write_byte(offset, value);
+        }
+    }
+
+    fn write_hword(&mut self, addr: u32, value: u16) {
+        match self.route(addr, 2) {
+            Some((bus, offset)) => bus.write_hword(offset, value),
+            None => write_hword_as_bytes(self, addr, value),
+        }
+    }
+
+    fn write_word(&mut self, addr: u32, value: u32) {
+        match self.route(addr, 4) {
+            Some((bus, offset)) => bus.write_word(offset, value),
+            None => write_word_as_hwords(self, addr, value),
+        }
+    }
+
+    fn prefetch_instr(&mut self, addr: u32) {
+        if let Some((bus, offset)) = self.route(addr, 1) {
+            bus.prefetch_instr(offset);
+        }
+    }
+
+    fn notify_prefetched(&mut self, addr: u32, pipeline: [u32; 2], thumb: bool) {
+        if let Some((bus, offset)) = self.route(addr, 1) {
+            bus.notify_prefetched(offset, pipeline, thumb);
+        }
+    }
+
+    fn notify_execute(&mut self, addr: u32, thumb: bool) {
+        if let Some((bus, offset)) = self.route(addr, 1) {
+            bus.notify_execute(offset, thumb);
+        }
+    }
+
+    fn is_volatile(&self, addr: u32) -> bool {
+        self.find(addr, 1)
+            .is_some_and(|(i, offset)| self.routes[i].1.is_volatile(offset))
+    }
+}
+
+/// Repeats a bus of `len` bytes over the whole address space, like how the GBA's memory regions
+/// are mirrored.
+#[derive(Debug, Clone)]
+pub struct Mirror<B> {
+    pub bus: B,
+    mask: u32,
+}
+
+impl<B: Bus> Mirror<B> {
+    /// # Panics
+    /// Panics if `len` isn't a power of 2.
+    #[must_use]
+    pub fn new(bus: B, len: u32) -> Self {
+        assert!(
+            len.is_power_of_two(),
+            "mirrored length must be a power of 2"
+        );
+        Self { bus, mask: len - 1 }
+    }
+
+    /// Returns the address within the bus that `len` bytes at `addr` are mirrored from, if they
+    /// don't wrap around the end of the bus.
+    fn mirror(&self, addr: u32, len: u32) -> Option<u32> {
+        let offset = addr & self.mask;
+        (offset + (len - 1) <= self.mask).then_some(offset)
+    }
+}
+
+impl<B: Bus> Bus for Mirror<B> {
+    fn read_byte(&mut self, addr: u32) -> u8 {
+        self.bus.read_byte(addr & self.mask)
+    }
+
+    fn read_hword(&mut self, addr: u32) -> u16 {
+        match self.mirror(addr, 2) {
+            Some(offset) => self.bus.read_hword(offset),
+            None => read_hword_as_bytes(self, addr),
+        }
+    }
+
+    fn read_word(&mut self, addr: u32) -> u32 {
+        match self.mirror(addr, 4) {
+            Some(offset) => self.bus.read_word(offset),
+            None => read_word_as_hwords(self, addr),
+        }
+    }
+
+    fn write_byte(&mut self, addr: u32, value: u8) {
+        self.bus.write_byte(addr & self.mask, value);
+    }
+
+    fn write_hword(&mut self, addr: u32, value: u16) {
+        match self.mirror(addr, 2) {
+            Some(offset) => self.bus.write_hword(offset, value),
+            None => write_hword_as_bytes(self, addr, value),
+        }
+    }
+
+    fn write_word(&mut self, addr: u32, value: u32) {
+        match self.mirror(addr, 4) {
+            Some(offset) => self.bus.write_word(offset, value),
+            None => write_word_as_hwords(self, addr, value),
+        }
+    }
+
+    fn prefetch_instr(&mut self, addr: u32) {
+        self.bus.prefetch_instr(addr & self.mask);
+    }
+
+    fn notify_prefetched(&mut self, addr: u32, pipeline: [u32; 2], thumb: bool) {
+        self.bus
+            .notify_prefetched(addr & self.mask, pipeline, thumb);
+    }
+
+    fn notify_execute(&mut self, addr: u32, thumb: bool) {
+        self.bus.notify_execute(addr & self.mask, thumb);
+    }
+
+    fn is_volatile(&self, addr: u32) -> bool {
+        self.bus.is_volatile(addr & self.mask)
+    }
+}
+
+/// An access recorded by `Logged`.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Access {
+    pub kind: AccessKind,
+    pub addr: u32,
+    /// Size of the access in bytes (1, 2 or 4).
+    pub len: u8,
+    /// Value that was read or written.
+    pub value: u32,
+}
+
+/// Records the reads and writes made to a bus, passing them through unchanged.
+#[derive(Debug, Clone)]
+pub struct Logged<B> {
+    pub bus: B,
+    accesses: Vec<Access>,
+}
+
+impl<B: Bus> Logged<B> {
+    #[must_use]
+    pub fn new(bus: B) -> Self {
+        Self {
+            bus,
+            accesses: Vec::new(),
+        }
+    }
+
+    /// Accesses recorded since the last call to `Self::take_accesses`, in order.
+    #[must_use]
+    pub fn accesses(&self) -> &[Access] {
+        &self.accesses
+    }
+
+    pub fn take_accesses(&mut self) -> Vec<Access> {
+        std::mem::take(&mut self.accesses)
+    }
+
+    fn log(&mut self, kind: AccessKind, addr: u32, len: u8, value: u32) {
+        self.accesses.push(Access {
+            kind,
+            addr,
+            len,
+            value,
+        });
+    }
+}
+
+impl<B: Bus> Bus for Logged<B> {
+    fn read_byte(&mut self, addr: u32) -> u8 {
+        let value = self.bus.read_byte(addr);
+        self.log(AccessKind::Read, addr, 1, value.into());
+        value
+    }
+
+    fn read_hword(&mut self, addr: u32) -> u16 {
+        let value = self.bus.read_hword(addr);
+        self.log(AccessKind::Read, addr, 2, value.into());
+        value
+    }
+
+    fn read_word(&mut self, addr: u32) -> u32 {
+        let value = self.bus.read_word(addr);
+        self.log(AccessKind::Read, addr, 4, value);
+        value
+    }
+
+    fn write_byte(&mut self, addr: u32, value: u8) {
+        self.log(AccessKind::Write, addr, 1, value.into());
+        self.bus.write_byte(addr, value);
+    }
+
+    fn write_hword(&mut self, addr: u32, value: u16) {
+        self.log(AccessKind::Write, addr, 2, value.into());
+        self.bus.write_hword(addr, value);
+    }
+
+    fn write_word(&mut self, addr: u32, value: u32) {
+        self.log(AccessKind::Write, addr, 4, value);
+        self.bus.write_word(addr, value);
+    }
+
+    fn prefetch_instr(&mut self, addr: u32) {
+        self.bus.prefetch_instr(addr);
+    }
+
+    fn notify_prefetched(&mut self, addr: u32, pipeline: [u32; 2], thumb: bool) {
+        self.bus.notify_prefetched(addr, pipeline, thumb);
+    }
+
+    fn notify_execute(&mut self, addr: u32, thumb: bool) {
+        self.bus.notify_execute(addr, thumb);
+    }
+
+    fn is_volatile(&self, addr: u32) -> bool {
+        self.bus.is_volatile(addr)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ram(len: usize) -> Box<[u8]> {
+        vec![0; len].into_boxed_slice()
+    }
+
+    #[test]
+    fn router_maps_ranges() {
+        let mut bus = Router::new()
+            .with(0x100..=0x10f, ram(0x10))
+            .with(0x200..=0x3ff, Mirror::new(ram(0x10), 0x10))
+            .with_open_bus(0xaa);
+
+        bus.write_word(0x104, 0x1234_5678);
+        assert_eq!(bus.read_word(0x104), 0x1234_5678);
+        assert_eq!(bus.read_hword(0x106), 0x1234);
+        // Mirrored every 16 bytes.
+        bus.write_hword(0x200, 0xbeef);
+        assert_eq!(bus.read_hword(0x3f0), 0xbeef);
+        // Unmapped.
+        bus.write_byte(0x50, 1);
+        assert_eq!(bus.read_byte(0x50), 0xaa);
+        // Straddles the end of a range, so it's split.
+        assert_eq!(bus.read_word(0x10e), 0xaaaa_0000);
+    }
+
+    #[test]
+    fn mirror_splits_wrapping_accesses() {
+        let mut bus = Mirror::new(ram(4), 4);
+        bus.write_word(0x8, 0x4433_2211);
+        assert_eq!(bus.read_word(0x1_0000), 0x4433_2211);
+        assert_eq!(bus.read_hword(0x3), 0x1144);
+    }
+
+    #[test]
+    fn logged_records_accesses() {
+        let mut bus = Logged::new(Router::new().with(0..=0xf, ram(0x10)));
+        bus.write_hword(2, 0xcafe);
+        assert_eq!(bus.read_byte(3), 0xca);
+
+        let access = |kind, addr, len, value| Access {
+            kind,
+            addr,
+            len,
+            value,
+        };
+        assert_eq!(
+            bus.take_accesses(),
+            [
+                access(AccessKind::Write, 2, 2, 0xcafe),
+                access(AccessKind::Read, 3, 1, 0xca),
+            ]
+        );
+        assert!(bus.accesses().is_empty());
+    }
+
+    #[test]
+    fn dyn_buses_compose() {
+        let mut buses: Vec<Box<dyn Bus>> = vec![
+            Box::new(ram(4)),
+            Box::new(Logged::new(ram(4))),
+            Box::new(Mirror::new(ram(2), 2)),
+        ];
+        for bus in &mut buses {
+            bus.write_hword(0, 0x1234);
+            assert_eq!(bus.read_hword(0), 0x1234);
+        }
+    }
+}
@@ -1,5 +1,23 @@
 use intbits::Bits;
 
+pub mod compose;
+
+#[inline]
+pub fn read_hword_as_bytes<T: Bus + ?Sized>(bus: &mut T, addr: u32) -> u16 {
+    let lo = bus.read_byte(addr);
+    let hi = bus.read_byte(addr.wrapping_add(1));
+
+    u16::from_le_bytes([lo, hi])
+}
+
+#[inline]
+pub fn read_word_as_hwords<T: Bus + ?Sized>(bus: &mut T, addr: u32) -> u32 {
+    let lo = bus.read_hword(addr);
+    let hi = bus.read_hword(addr.wrapping_add(2));
+
+    u32::from(lo).with_bits(16.., hi.into())
+}
+
 // Panic is impossible as the first 8 bits of value always fits a u8.
 #[expect(clippy::missing_panics_doc)]
 #[inline]
@@ -8,23 +26,28 @@ pub fn write_hword_as_bytes<T: Bus + ?Sized>(bus: &mut T, addr: u32, value: u16)
     bus.write_byte(addr.wrapping_add(1), value.bits(8..).try_into().unwrap());
 }
 
+// Panic is impossible as the first 16 bits of value always fits a u16.
+#[expect(clippy::missing_panics_doc)]
+#[inline]
+pub fn write_word_as_hwords<T: Bus + ?Sized>(bus: &mut T, addr: u32, value: u32) {
+    bus.write_hword(addr, value.bits(..16).try_into().unwrap());
+    bus.write_hword(addr.wrapping_add(2), value.bits(16..).try_into().unwrap());
+}
+
+/// Accesses never fail, as the GBA doesn't use the ARM7TDMI's abort signal; unused addresses read
+/// open bus values (see `gba::OpenBus`) and ignore writes, rather than panicking. Devices read 0
+/// from addresses they don't map, as the GBA's bus decides which addresses read open bus instead.
 pub trait Bus {
     fn read_byte(&mut self, addr: u32) -> u8;
 
     #[inline]
     fn read_hword(&mut self, addr: u32) -> u16 {
-        let lo = self.read_byte(addr);
-        let hi = self.read_byte(addr.wrapping_add(1));
-
-        u16::from_le_bytes([lo, hi])
+        read_hword_as_bytes(self, addr)
     }
 
     #[inline]
     fn read_word(&mut self, addr: u32) -> u32 {
-        let lo = self.read_hword(addr);
-        let hi = self.read_hword(addr.wrapping_add(2));
-
-        u32::from(lo).with_bits(16.., hi.into())
+        read_word_as_hwords(self, addr)
     }
 
     #[inline]
@@ -37,12 +60,92 @@ pub trait Bus {
 
     #[inline]
     fn write_word(&mut self, addr: u32, value: u32) {
-        self.write_hword(addr, value.bits(..16).try_into().unwrap());
-        self.write_hword(addr.wrapping_add(2), value.bits(16..).try_into().unwrap());
+        write_word_as_hwords(self, addr, value);
     }
 
     #[inline]
     fn prefetch_instr(&mut self, _addr: u32) {}
+
+    /// Called after the CPU prefetches the instruction at `addr`, with the 2 instructions now in
+    /// its pipeline, oldest first (e.g: for open bus reads, which return them).
+    #[inline]
+    fn notify_prefetched(&mut self, _addr: u32, _pipeline: [u32; 2], _thumb: bool) {}
+
+    /// Called after the instruction at `addr` is fetched from the pipeline, just before the CPU
+    /// executes it (e.g: for code/data logging).
+    #[inline]
+    fn notify_execute(&mut self, _addr: u32, _thumb: bool) {}
+
+    /// Returns true if reading `addr` may return a different value without it being written to or
+    /// an interrupt being requested, like a timer's counter. Used for idle loop detection.
+    #[inline]
+    fn is_volatile(&self, _addr: u32) -> bool {
+        false
+    }
+}
+
+/// Forwards every access to the dereferenced bus, so wider accesses aren't split.
+macro_rules! forward_bus {
+    () => {
+        #[inline]
+        fn read_byte(&mut self, addr: u32) -> u8 {
+            (**self).read_byte(addr)
+        }
+
+        #[inline]
+        fn read_hword(&mut self, addr: u32) -> u16 {
+            (**self).read_hword(addr)
+        }
+
+        #[inline]
+        fn read_word(&mut self, addr: u32) -> u32 {
+            (**self).read_word(addr)
+        }
+
+        #[inline]
+        fn write_byte(&mut self, addr: u32, value: u8) {
+            (**self).write_byte(addr, value);
+        }
+
+        #[inline]
+        fn write_hword(&mut self, addr: u32, value: u16) {
+            (**self).write_hword(addr, value);
+        }
+
+        #[inline]
+        fn write_word(&mut self, addr: u32, value: u32) {
+            (**self).write_word(addr, value);
+        }
+
+        #[inline]
+        fn prefetch_instr(&mut self, addr: u32) {
+            (**self).prefetch_instr(addr);
+        }
+
+        #[inline]
+        fn notify_prefetched(&mut self, addr: u32, pipeline: [u32; 2], thumb: bool) {
+            (**self).notify_prefetched(addr, pipeline, thumb);
+        }
+
+        #[inline]
+        fn notify_execute(&mut self, addr: u32, thumb: bool) {
+            (**self).notify_execute(addr, thumb);
+        }
+
+        #[inline]
+        fn is_volatile(&self, addr: u32) -> bool {
+            (**self).is_volatile(addr)
+        }
+    };
+}
+
+impl<B: Bus + ?Sized> Bus for &mut B {
+    forward_bus!();
+}
+
+/// Allows buses to be composed dynamically as `Box<dyn Bus>`.
+impl<B: Bus + ?Sized> Bus for Box<B> {
+    forward_bus!();
 }
 
 impl Bus for &[u8] {
@@ -0,0 +1,350 @@
+//! Identifying cartridge ROMs by their hashes, using dat files in the Logiqx XML format (as
+//! published by No-Intro), so the canonical names of games can be shown, and bad dumps and
+//! overdumps can be detected.
+//!
+//! No dat is bundled; users can supply one (e.g: No-Intro's GBA set) to frontends.
+
+use std::{
+    error::Error,
+    fmt::{self, Display, Formatter},
+};
+
+use super::{
+    hash::{self, Hashes},
+    Rom,
+};
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct InvalidDat(&'static str);
+
+impl Display for InvalidDat {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid dat file: {}", self.0)
+    }
+}
+
+impl Error for InvalidDat {}
+
+/// A ROM listed in a dat.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    /// Canonical name of the game (e.g: "Pokemon - Emerald Version (USA, Europe)").
+    pub name: String,
+    pub size: usize,
+    pub crc32: u32,
+    /// Not listed by some dats.
+    pub sha1: Option<[u8; 20]>,
+    /// Whether the dat lists the ROM as a known bad dump of the game.
+    pub bad_dump: bool,
+}
+
+impl Entry {
+    fn matches(&self, size: usize, hashes: &Hashes) -> bool {
+        self.size == size
+            && self.crc32 == hashes.crc32
+            && self.sha1.map_or(true, |sha1| sha1 == hashes.sha1)
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Status {
+    Good,
+    /// Matches a ROM listed as a bad dump.
+    BadDump,
+    /// Only the start of the ROM matches, as it has extra data (e.g: padding) after the game.
+    Overdump,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Identity<'a> {
+    pub entry: &'a Entry,
+    pub status: Status,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct Database {
+    entries: Vec<Entry>,
+}
+
+impl Database {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parses a dat in the Logiqx XML format. Only the names of games and the sizes, hashes and
+    /// statuses of their ROMs are read; ROMs that weren't dumped (or lack a CRC32) are skipped.
+    ///
+    /// # Errors
+    /// Returns an error if the dat is malformed.
+    pub fn parse(text: &str) -> Result<Self, InvalidDat> {
+        let mut entries = Vec::new();
+        let mut game_name = None;
+        let mut rest = text;
+
+        while let Some(start) = rest.find('<') {
+            rest = &rest[start..];
+            if let Some(comment) = rest.strip_prefix("<!--") {
+                let end = comment
+                    .find("-->")
+                    .ok_or(InvalidDat("unterminated comment"))?;
+                rest = &comment[end + 3..];
+                continue;
+            }
+
+            let end = rest.find('>').ok_or(InvalidDat("unterminated tag"))?;
+            let tag = &rest[1..end];
+            rest = &rest[end + 1..];
+            // Skip declarations (e.g: <?xml ...?> and <!DOCTYPE ...>).
+            if tag.starts_with(['?', '!']) {
+                continue;
+            }
+
+            let (name, attrs) = tag
+                .split_once(|c: char| c.is_ascii_whitespace())
+                .unwrap_or((tag, ""));
+            match name.trim_end_matches('/') {
+                "game" | "machine" if !tag.ends_with('/') => {
+                    game_name = Some(attr(attrs, "name")?.ok_or(InvalidDat("unnamed game"))?);
+                }
+                "/game" | "/machine" => game_name = None,
+                "rom" => {
+                    let name = game_name
+                        .as_ref()
+                        .ok_or(InvalidDat("rom outside of a game"))?;
+                    entries.extend(parse_rom(name, attrs)?);
+                }
+                _ => {}
+            }
+        }
+
+        Ok(Self { entries })
+    }
+
+    #[must_use]
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    /// Looks up the ROM by its hashes, returning `None` if it's not in the dat.
+    #[must_use]
+    pub fn identify(&self, rom: &Rom) -> Option<Identity<'_>> {
+        let bytes = rom.bytes();
+        if let Some(entry) = self
+            .entries
+            .iter()
+            .find(|entry| entry.matches(bytes.len(), rom.hashes()))
+        {
+            let status = if entry.bad_dump {
+                Status::BadDump
+            } else {
+                Status::Good
+            };
+
+            return Some(Identity { entry, status });
+        }
+
+        // Check if the start of the ROM matches any smaller ROMs, hashing each prefix once.
+        let mut sizes: Vec<_> = self
+            .entries
+            .iter()
+            .map(|entry| entry.size)
+            .filter(|&size| size < bytes.len())
+            .collect();
+        sizes.sort_unstable();
+        sizes.dedup();
+
+        sizes.into_iter().find_map(|size| {
+            let prefix = &bytes[..size];
+            let crc32 = hash::crc32(prefix);
+            let mut candidates = self
+                .entries
+                .iter()
+                .filter(|entry| entry.size == size && entry.crc32 == crc32 && !entry.bad_dump)
+                .peekable();
+            candidates.peek()?;
+
+            let hashes = Hashes {
+                crc32,
+                sha1: hash::sha1(prefix),
+            };
+            candidates
+                .find(|entry| entry.matches(size, &hashes))
+                .map(|entry| Identity {
+                    entry,
+                    status: Status::Overdump,
+                })
+        })
+    }
+}
+
+fn parse_rom(game_name: &str, attrs: &str) -> Result<Option<Entry>, InvalidDat> {
+    let status = attr(attrs, "status")?;
+    if status.as_deref() == Some("nodump") {
+        return Ok(None);
+    }
+    let Some(crc32) = attr(attrs, "crc")? else {
+        return Ok(None);
+    };
+
+    let size = attr(attrs, "size")?
+        .ok_or(InvalidDat("rom without a size"))?
+        .parse()
+        .map_err(|_| InvalidDat("invalid rom size"))?;
+    let crc32 = u32::from_str_radix(&crc32, 16).map_err(|_| InvalidDat("invalid CRC32"))?;
+    let sha1 = attr(attrs, "sha1")?
+        .map(|sha1| parse_sha1(&sha1).ok_or(InvalidDat("invalid SHA-1")))
+        .transpose()?;
+
+    Ok(Some(Entry {
+        name: game_name.to_string(),
+        size,
+        crc32,
+        sha1,
+        bad_dump: status.as_deref() == Some("baddump"),
+    }))
+}
+
+fn parse_sha1(hex: &str) -> Option<[u8; 20]> {
+    if hex.len() != 40 || !hex.is_ascii() {
+        return None;
+    }
+
+    let mut sha1 = [0; 20];
+    for (i, byte) in sha1.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[2 * i..2 * i + 2], 16).ok()?;
+    }
+
+    Some(sha1)
+}
+
+/// Returns the unescaped value of the `key` attribute from a tag's attributes, if present.
+fn attr(attrs: &str, key: &str) -> Result<Option<String>, InvalidDat> {
+    let mut rest = attrs;
+    while let Some((name, value)) = rest.split_once('=') {
+        let value = value.trim_start();
+        let quote = value
+            .chars()
+            .next()
+            .filter(|&c| c == '"' || c == '\'')
+            .ok_or(InvalidDat("unquoted attribute value"))?;
+        let (value, after) = value[1..]
+            .split_once(quote)
+            .ok_or(InvalidDat("unterminated attribute value"))?;
+
+        if name.trim() == key {
+            return unescape(value).map(Some);
+        }
+        rest = after;
+    }
+
+    Ok(None)
+}
+
+fn unescape(text: &str) -> Result<String, InvalidDat> {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        unescaped.push_str(&rest[..start]);
+        let (entity, after) = rest[start + 1..]
+            .split_once(';')
+            .ok_or(InvalidDat("unterminated entity reference"))?;
+
+        let c = match entity {
+            "amp" => '&',
+            "lt" => '<',
+            "gt" => '>',
+            "quot" => '"',
+            "apos" => '\'',
+            _ => entity
+                .strip_prefix("#x")
+                .map(|hex| u32::from_str_radix(hex, 16))
+                .or_else(|| entity.strip_prefix('#').map(str::parse))
+                .and_then(Result::ok)
+                .and_then(char::from_u32)
+                .ok_or(InvalidDat("invalid entity reference"))?,
+        };
+        unescaped.push(c);
+        rest = after;
+    }
+    unescaped.push_str(rest);
+
+    Ok(unescaped)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fmt::Write, rc::Rc};
+
+    use super::*;
+
+    fn rom(buf: &[u8]) -> Rom {
+        Rom::new(Rc::from(buf)).unwrap()
+    }
+
+    fn sha1_hex(buf: &[u8]) -> String {
+        hash::sha1(buf).iter().fold(String::new(), |mut s, byte| {
+            write!(s, "{byte:02x}").unwrap();
+            s
+        })
+    }
+
+    #[test]
+    fn identifies_roms() {
+        let good = [0x11; 0x100];
+        let bad = [0x22; 0x100];
+        let dat = format!(
+            r#"<?xml version="1.0"?>
+<!DOCTYPE datafile PUBLIC "-//Logiqx//DTD ROM Management Datafile//EN" "">
+<datafile>
+    <header><name>Test</name></header>
+    <!-- <game name="Commented Out"> -->
+    <game name="Meme Game (USA) &amp; &#x41;">
+        <description>Meme Game</description>
+        <rom name="a.gba" size="256" crc="{:08X}" sha1="{}" status="verified"/>
+    </game>
+    <game name='Meme Game (Bad)'>
+        <rom name="b.gba" size="256" crc="{:08x}" status="baddump"/>
+    </game>
+    <game name="Not Dumped">
+        <rom name="c.gba" size="256" status="nodump"/>
+    </game>
+</datafile>"#,
+            hash::crc32(&good),
+            sha1_hex(&good),
+            hash::crc32(&bad),
+        );
+        let db = Database::parse(&dat).unwrap();
+        assert_eq!(db.entries().len(), 2);
+        assert_eq!(db.entries()[0].name, "Meme Game (USA) & A");
+
+        let identity = db.identify(&rom(&good)).unwrap();
+        assert_eq!(identity.entry, &db.entries()[0]);
+        assert_eq!(identity.status, Status::Good);
+
+        let identity = db.identify(&rom(&bad)).unwrap();
+        assert_eq!(identity.entry.name, "Meme Game (Bad)");
+        assert_eq!(identity.status, Status::BadDump);
+
+        let mut overdump = good.to_vec();
+        overdump.resize(0x200, 0xff);
+        let identity = db.identify(&rom(&overdump)).unwrap();
+        assert_eq!(identity.entry, &db.entries()[0]);
+        assert_eq!(identity.status, Status::Overdump);
+
+        let mut modified = good;
+        modified[0x80] ^= 1;
+        assert!(db.identify(&rom(&modified)).is_none());
+    }
+
+    #[test]
+    fn rejects_malformed_dats() {
+        assert!(Database::parse("").unwrap().entries().is_empty());
+        assert!(Database::parse("<game name=\"A\"><rom size=\"1\" crc=\"zz\"/>").is_err());
+        assert!(Database::parse("<game name=\"A\"><rom crc=\"0\"/>").is_err());
+        assert!(Database::parse("<rom size=\"1\" crc=\"0\"/>").is_err());
+        assert!(Database::parse("<game name=A>").is_err());
+        assert!(Database::parse("<game name=\"&bad;\">").is_err());
+        assert!(Database::parse("<!-- <game>").is_err());
+    }
+}
@@ -1,8 +1,9 @@
 use intbits::Bits;
+use serde::{Deserialize, Serialize};
 
 use crate::bus::Bus;
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Eeprom {
     buf: Box<[u8]>,
     state: State,
@@ -37,9 +38,34 @@ impl Eeprom {
     pub fn buffer(&self) -> &[u8] {
         &self.buf
     }
+
+    pub(super) fn is_valid(&self) -> bool {
+        if self.buf.len() != 8 * BLOCK_LEN && self.buf.len() != 128 * BLOCK_LEN {
+            return false;
+        }
+
+        match self.state {
+            State::None | State::Type | State::ReadAddress { .. } | State::WriteBlock { .. } => {
+                true
+            }
+            State::ReadBlock {
+                start_bit_idx,
+                rem_len,
+            } => start_bit_idx < (1 << 14) * BLOCK_LEN && (1..=BLOCK_LEN + 4).contains(&rem_len),
+            State::WriteAddress { bit_idx, .. } => bit_idx < self.block_idx_bits(),
+        }
+    }
+
+    fn block_idx_bits(&self) -> usize {
+        if self.buf.len() / BLOCK_LEN > 64 {
+            14
+        } else {
+            6
+        }
+    }
 }
 
-#[derive(Default, Copy, Clone)]
+#[derive(Default, Copy, Clone, Serialize, Deserialize)]
 enum State {
     #[default]
     None,
@@ -113,11 +139,7 @@ impl Bus for Eeprom {
             return;
         }
 
-        let block_idx_bits = if self.buf.len() / BLOCK_LEN > 64 {
-            14
-        } else {
-            6
-        };
+        let block_idx_bits = self.block_idx_bits();
         match (&mut self.state, value.bit(0)) {
             (State::None, true) => self.state = State::Type,
             (State::None, false) | (State::ReadBlock { .. }, _) => {}
@@ -1,6 +1,8 @@
+use serde::{Deserialize, Serialize};
+
 use crate::bus::Bus;
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Flash {
     buf: Box<[u8]>,
     bank_idx: usize,
@@ -8,7 +10,7 @@ pub struct Flash {
     next_cmd_state: NextCommandState,
 }
 
-#[derive(Default, Copy, Clone, Eq, PartialEq)]
+#[derive(Default, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
 enum State {
     #[default]
     None,
@@ -18,7 +20,7 @@ enum State {
     SwitchBank,
 }
 
-#[derive(Default, Copy, Clone, Eq, PartialEq)]
+#[derive(Default, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
 enum NextCommandState {
     #[default]
     None,
@@ -56,6 +58,11 @@ impl Flash {
         self.bank_idx * BANK_LEN + usize::try_from(addr).unwrap()
     }
 
+    pub(super) fn is_valid(&self) -> bool {
+        (self.buf.len() == BANK_LEN || self.buf.len() == 2 * BANK_LEN)
+            && self.bank_idx < self.buf.len() / BANK_LEN
+    }
+
     fn is_dual_bank(&self) -> bool {
         self.buf.len() > BANK_LEN
     }
@@ -0,0 +1,158 @@
+//! Hashes of cartridge ROMs, for identifying them (e.g: via `cart::dat`).
+
+use std::fmt::{self, Display, Formatter};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Hashes {
+    pub crc32: u32,
+    pub sha1: [u8; 20],
+}
+
+impl Hashes {
+    #[must_use]
+    pub fn new(buf: &[u8]) -> Self {
+        Self {
+            crc32: crc32(buf),
+            sha1: sha1(buf),
+        }
+    }
+}
+
+impl Display for Hashes {
+    /// Formats as "CRC32 ..., SHA-1 ..." in lowercase hexadecimal, as used by dat files.
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "CRC32 {:08x}, SHA-1 ", self.crc32)?;
+        for byte in self.sha1 {
+            write!(f, "{byte:02x}")?;
+        }
+
+        Ok(())
+    }
+}
+
+const CRC32_TABLE: [u32; 256] = {
+    let mut table = [0; 256];
+    let mut i = 0;
+    while i < 256 {
+        #[expect(clippy::cast_possible_truncation)]
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+
+    table
+};
+
+/// CRC-32 (as used by zip and PNG).
+#[must_use]
+pub fn crc32(buf: &[u8]) -> u32 {
+    !buf.iter().fold(u32::MAX, |crc, &byte| {
+        CRC32_TABLE[usize::from(crc.to_le_bytes()[0] ^ byte)] ^ (crc >> 8)
+    })
+}
+
+/// SHA-1, which is broken for security purposes, but still fine (and widely used) for identifying
+/// ROMs.
+///
+/// # Panics
+/// Panics if `buf` is over 2 EiB long, which isn't possible in practice.
+#[must_use]
+pub fn sha1(buf: &[u8]) -> [u8; 20] {
+    let mut state = [
+        0x6745_2301,
+        0xefcd_ab89,
+        0x98ba_dcfe,
+        0x1032_5476,
+        0xc3d2_e1f0,
+    ];
+    let mut blocks = buf.chunks_exact(64);
+    for block in &mut blocks {
+        sha1_compress(&mut state, block);
+    }
+
+    // Pad with a 1 bit, then 0s, then the message length in bits, to a multiple of the block size.
+    let rest = blocks.remainder();
+    let mut tail = [0; 128];
+    tail[..rest.len()].copy_from_slice(rest);
+    tail[rest.len()] = 0x80;
+    let tail_len = if rest.len() < 56 { 64 } else { 128 };
+    let bit_len = u64::try_from(buf.len()).unwrap().checked_mul(8).unwrap();
+    tail[tail_len - 8..tail_len].copy_from_slice(&bit_len.to_be_bytes());
+    for block in tail[..tail_len].chunks_exact(64) {
+        sha1_compress(&mut state, block);
+    }
+
+    let mut digest = [0; 20];
+    for (bytes, word) in digest.chunks_exact_mut(4).zip(state) {
+        bytes.copy_from_slice(&word.to_be_bytes());
+    }
+
+    digest
+}
+
+// Names follow FIPS 180.
+#[expect(clippy::many_single_char_names)]
+fn sha1_compress(state: &mut [u32; 5], block: &[u8]) {
+    let mut w = [0; 80];
+    for (word, bytes) in w.iter_mut().zip(block.chunks_exact(4)) {
+        *word = u32::from_be_bytes(bytes.try_into().unwrap());
+    }
+    for i in 16..80 {
+        w[i] = (w[i - 3] ^ w[i - 8] ^ w[i - 14] ^ w[i - 16]).rotate_left(1);
+    }
+
+    let [mut a, mut b, mut c, mut d, mut e] = *state;
+    for (i, word) in w.into_iter().enumerate() {
+        let (f, k) = match i {
+            0..20 => ((b & c) | (!b & d), 0x5a82_7999),
+            20..40 => (b ^ c ^ d, 0x6ed9_eba1),
+            40..60 => ((b & c) | (b & d) | (c & d), 0x8f1b_bcdc),
+            _ => (b ^ c ^ d, 0xca62_c1d6),
+        };
+        let temp = a
+            .rotate_left(5)
+            .wrapping_add(f)
+            .wrapping_add(e)
+            .wrapping_add(k)
+            .wrapping_add(word);
+        (a, b, c, d, e) = (temp, a, b.rotate_left(30), c, d);
+    }
+
+    for (word, value) in state.iter_mut().zip([a, b, c, d, e]) {
+        *word = word.wrapping_add(value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hashes() {
+        assert_eq!(crc32(b""), 0);
+        assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+
+        // Test vectors from FIPS 180, including one that needs an extra padding block.
+        let sha1_str = |buf: &[u8]| Hashes::new(buf).to_string();
+        assert!(sha1_str(b"").ends_with("da39a3ee5e6b4b0d3255bfef95601890afd80709"));
+        assert!(sha1_str(b"abc").ends_with("a9993e364706816aba3e25717850c26c9cd0d89d"));
+        assert!(
+            sha1_str(b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq")
+                .ends_with("84983e441c3bd26ebaae4aa1f95129e5e54670f1")
+        );
+        assert!(sha1_str(&[b'a'; 1000]).ends_with("291e9a6c66994949b57ba5e650361e98fc36b1ba"));
+        assert_eq!(
+            Hashes::new(b"123456789").to_string(),
+            "CRC32 cbf43926, SHA-1 f7c3bc1d808e04732adf679965ccc34ca7ae3441"
+        );
+    }
+}
@@ -1,28 +1,78 @@
-use std::rc::Rc;
+use std::{
+    cell::OnceCell,
+    error::Error,
+    fmt::{self, Display, Formatter},
+    rc::Rc,
+};
 
-use log::{info, warn};
+use serde::{Deserialize, Serialize};
 
-use crate::{bus::Bus, InvalidRomSize};
+use crate::{
+    bus::{self, Bus},
+    gba::Notice,
+    InvalidRomSize,
+};
 
-use self::{eeprom::Eeprom, flash::Flash};
+use self::{eeprom::Eeprom, flash::Flash, hash::Hashes, sram_clock::SramClock};
 
+pub mod dat;
 mod eeprom;
 mod flash;
+pub mod hash;
+pub mod overrides;
+pub mod sram_clock;
 
-#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
 pub enum BackupType {
     #[default]
     None,
+    #[serde(rename = "eeprom-unknown")]
     EepromUnknownSize,
+    #[serde(rename = "eeprom-512")]
     Eeprom512B,
+    #[serde(rename = "eeprom-8k")]
     Eeprom8KiB,
+    #[serde(rename = "sram-32k")]
     Sram32KiB,
+    #[serde(rename = "flash-64k")]
     Flash64KiB,
+    #[serde(rename = "flash-128k")]
     Flash128KiB,
 }
 
+/// Information from a cartridge ROM's header.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Header {
+    /// Game title (e.g: "POKEMON FIRE"), with padding and non-printable characters removed.
+    pub title: String,
+    /// See `Rom::game_code`.
+    pub game_code: Option<String>,
+    /// 2 character maker code (e.g: "01" for Nintendo), if alphanumeric.
+    pub maker_code: Option<String>,
+    pub version: u8,
+    /// Whether the header's complement check byte is correct. The BIOS refuses to boot the
+    /// cartridge if it isn't.
+    pub checksum_valid: bool,
+}
+
+impl Display for Header {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.title)?;
+        if let Some(ref game_code) = self.game_code {
+            write!(f, " ({game_code})")?;
+        }
+
+        Ok(())
+    }
+}
+
 #[derive(Clone)]
-pub struct Rom(Rc<[u8]>);
+pub struct Rom {
+    buf: Rc<[u8]>,
+    /// Computed on first use by `Self::hashes`, as hashing a large ROM takes a while.
+    hashes: Rc<OnceCell<Hashes>>,
+}
 
 impl TryFrom<Rc<[u8]>> for Rom {
     type Error = InvalidRomSize;
@@ -34,7 +84,10 @@ impl TryFrom<Rc<[u8]>> for Rom {
             return Err(InvalidRomSize);
         }
 
-        Ok(Self(buf))
+        Ok(Self {
+            buf,
+            hashes: Rc::default(),
+        })
     }
 }
 
@@ -47,63 +100,184 @@ impl Rom {
 
     #[must_use]
     pub fn parse_backup_type(&self) -> BackupType {
-        // Search for valid IDs in the format "{id_prefix}_Vnnn".
-        // They are word-aligned (4 bytes) and 0-padded.
-        for i in (0..self.0.len()).step_by(4) {
-            let has_id = |id_prefix: &[u8]| {
+        if self.parse_unsupported_backup().is_some() {
+            return BackupType::None;
+        }
+
+        self.backup_ids()
+            .find_map(|id| match id {
+                // Impossible to detect the EEPROM's size from inspecting the ROM.
+                // Try and detect it at runtime.
+                b"EEPROM" => Some(BackupType::EepromUnknownSize),
+                b"FLASH" | b"FLASH512" => Some(BackupType::Flash64KiB),
+                b"FLASH1M" => Some(BackupType::Flash128KiB),
+                b"SRAM" | b"SRAM_F" => Some(BackupType::Sram32KiB),
+                _ => None,
+            })
+            .unwrap_or(BackupType::None)
+    }
+
+    /// Returns the name of the backup hardware that isn't emulated (like DACS) if the ROM has an
+    /// ID for it, in which case `Self::parse_backup_type` treats the cartridge as having no
+    /// backup, as such carts may also have IDs for other backup types that would otherwise be
+    /// misdetected.
+    #[must_use]
+    pub fn parse_unsupported_backup(&self) -> Option<&'static str> {
+        self.backup_ids().find_map(|id| match id {
+            b"DACS" => Some("DACS"),
+            _ => None,
+        })
+    }
+
+    /// Returns the IDs of backup hardware found in the ROM, in order. They are in the format
+    /// "{id}_Vnnn", word-aligned (4 bytes) and 0-padded.
+    fn backup_ids(&self) -> impl Iterator<Item = &'static [u8]> + '_ {
+        const IDS: [&[u8]; 7] = [
+            b"EEPROM",
+            b"FLASH",
+            b"FLASH512",
+            b"FLASH1M",
+            b"SRAM",
+            b"SRAM_F",
+            b"DACS",
+        ];
+
+        (0..self.buf.len()).step_by(4).filter_map(|i| {
+            let slice = &self.buf[i..];
+            IDS.into_iter().find(|id_prefix| {
                 let version_fmt = b"_Vnnn";
                 let id_len = id_prefix.len() + version_fmt.len();
                 let padding_len = if id_len % 4 > 0 { 4 - id_len % 4 } else { 0 };
-                let slice = &self.0[i..];
 
                 slice.len() >= id_len + padding_len
                     && slice.starts_with(id_prefix)
                     && slice[id_len..id_len + padding_len].iter().all(|&b| b == 0)
-            };
+            })
+        })
+    }
 
-            if has_id(b"EEPROM") {
-                // Impossible to detect the EEPROM's size from inspecting the ROM.
-                // Try and detect it at runtime.
-                return BackupType::EepromUnknownSize;
-            } else if has_id(b"FLASH") || has_id(b"FLASH512") {
-                return BackupType::Flash64KiB;
-            } else if has_id(b"FLASH1M") {
-                return BackupType::Flash128KiB;
-            } else if has_id(b"SRAM") || has_id(b"SRAM_F") {
-                return BackupType::Sram32KiB;
-            }
+    /// Returns the 4 character game code from the ROM header (e.g: "AXVE"), if it's present and
+    /// alphanumeric.
+    #[must_use]
+    pub fn game_code(&self) -> Option<&str> {
+        let code = self.buf.get(0xac..0xb0)?;
+        if code.iter().all(u8::is_ascii_alphanumeric) {
+            std::str::from_utf8(code).ok()
+        } else {
+            None
         }
+    }
 
-        BackupType::None
+    /// Parses the ROM header, if the ROM is large enough to contain one.
+    #[must_use]
+    pub fn header(&self) -> Option<Header> {
+        let header = self.buf.get(0xa0..0xbe)?;
+        let alphanumeric = |bytes: &[u8]| {
+            bytes
+                .iter()
+                .all(u8::is_ascii_alphanumeric)
+                .then(|| String::from_utf8_lossy(bytes).into_owned())
+        };
+
+        let title = header[..12]
+            .iter()
+            .take_while(|&&b| b != 0)
+            .filter(|&&b| b == b' ' || b.is_ascii_graphic())
+            .map(|&b| char::from(b))
+            .collect::<String>();
+        let checksum = header[..0x1d]
+            .iter()
+            .fold(0u8, |acc, &b| acc.wrapping_sub(b))
+            .wrapping_sub(0x19);
+
+        Some(Header {
+            title: title.trim().to_string(),
+            game_code: self.game_code().map(str::to_string),
+            maker_code: alphanumeric(&header[0x10..0x12]),
+            version: header[0x1c],
+            checksum_valid: checksum == header[0x1d],
+        })
     }
 
     #[must_use]
     pub fn bytes(&self) -> &[u8] {
-        self.0.as_ref()
+        self.buf.as_ref()
+    }
+
+    /// CRC32 and SHA-1 hashes of the ROM, for identifying it (e.g: via `dat::Database`). They're
+    /// computed on first use, then cached (also for clones of this `Rom`).
+    #[must_use]
+    pub fn hashes(&self) -> &Hashes {
+        self.hashes.get_or_init(|| Hashes::new(&self.buf))
     }
 }
 
 #[derive(Clone)]
 pub struct Cartridge {
     rom: Rom,
-    backup: Option<Backup>,
+    pub(crate) backup: Option<Backup>,
+    /// Clock kept in save memory for ROM hacks that expect one; see `sram_clock`.
+    pub sram_clock: Option<SramClock>,
+    /// Reported by the `Gba` when it's next stepped, as the cartridge can't reach its sink.
+    pub(crate) notices: Vec<Notice>,
 }
 
+/// Creates a cartridge with the backup type guessed by `Rom::parse_backup_type`, reporting it as
+/// `Notice::BackupTypeGuessed` (or `Notice::Unimplemented` for unsupported backup hardware).
 impl From<Rom> for Cartridge {
     fn from(rom: Rom) -> Self {
         let backup_type = rom.parse_backup_type();
-        Self::new(rom, backup_type)
+        let notice = match rom.parse_unsupported_backup() {
+            Some(name) => Notice::Unimplemented(name),
+            None => Notice::BackupTypeGuessed(backup_type),
+        };
+        let mut cart = Self::new(rom, backup_type);
+        cart.notices.push(notice);
+
+        cart
     }
 }
 
-#[derive(Clone)]
-enum Backup {
+/// The size of a cartridge backup (save file) doesn't match any backup type.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct InvalidBackup {
+    pub len: usize,
+}
+
+impl Display for InvalidBackup {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Invalid cartridge backup size ({} bytes); expected 512 bytes or 8KiB (EEPROM), 32KiB \
+            (SRAM), or 64KiB or 128KiB (Flash)",
+            self.len
+        )
+    }
+}
+
+impl Error for InvalidBackup {}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub(crate) enum Backup {
     EepromUnknownSize,
     Eeprom(Eeprom),
     Flash(Flash),
     Sram(Box<[u8]>),
 }
 
+impl Backup {
+    /// Whether the state is in range, which is always the case unless it was loaded from a
+    /// malformed save state; such state can panic when accessed.
+    pub(crate) fn is_valid(&self) -> bool {
+        match self {
+            Self::EepromUnknownSize => true,
+            Self::Eeprom(eeprom) => eeprom.is_valid(),
+            Self::Flash(flash) => flash.is_valid(),
+            Self::Sram(buf) => buf.len() == 32 * 1024,
+        }
+    }
+}
+
 impl Cartridge {
     #[must_use]
     pub fn new(rom: Rom, backup_type: BackupType) -> Self {
@@ -118,29 +292,41 @@ impl Cartridge {
                 BackupType::Flash128KiB => Some(Backup::Flash(Flash::new(true))),
                 BackupType::Sram32KiB => Some(Backup::Sram(vec![0xff; 32 * 1024].into())),
             },
+            sram_clock: None,
+            notices: Vec::new(),
         }
     }
 
-    #[must_use]
-    pub fn try_from_backup(rom: &Rom, mut backup_buf: Option<Box<[u8]>>) -> Option<Self> {
+    /// Creates a cartridge with the backup type matching the size of `backup_buf`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the size of `backup_buf` doesn't match any backup type.
+    pub fn try_from_backup(
+        rom: &Rom,
+        mut backup_buf: Option<Box<[u8]>>,
+    ) -> Result<Self, InvalidBackup> {
         let backup = match backup_buf {
             Some(buf) if buf.is_empty() => None,
             Some(buf) if buf.len() == 32 * 1024 => Some(Backup::Sram(buf)),
-            Some(_) => {
+            Some(ref buf) => {
+                let len = buf.len();
                 if let Ok(eeprom) = Eeprom::try_from(&mut backup_buf) {
                     Some(Backup::Eeprom(eeprom))
                 } else if let Ok(flash) = Flash::try_from(&mut backup_buf) {
                     Some(Backup::Flash(flash))
                 } else {
-                    return None;
+                    return Err(InvalidBackup { len });
                 }
             }
             None => None,
         };
 
-        Some(Self {
+        Ok(Self {
             rom: rom.clone(),
             backup,
+            sram_clock: None,
+            notices: Vec::new(),
         })
     }
 
@@ -159,6 +345,20 @@ impl Cartridge {
         }
     }
 
+    pub(crate) fn step(&mut self, cycles: u32) {
+        if let Some(clock) = self.sram_clock.as_mut() {
+            clock.step(cycles);
+        }
+    }
+
+    /// Returns the offset into save memory that `addr` (in the save memory region) maps to.
+    fn save_offset(&self, addr: u32) -> u32 {
+        match self.backup {
+            Some(Backup::Sram(_)) => addr & 0x7fff,
+            _ => addr & 0xffff,
+        }
+    }
+
     pub(crate) fn is_eeprom_offset(&self, offset: u32) -> bool {
         matches!(
             self.backup,
@@ -176,12 +376,14 @@ impl Cartridge {
         match blocks {
             // 6-bit addr read or write: 512B.
             9 | 73 => {
-                info!("guessing 512B EEPROM size");
+                self.notices
+                    .push(Notice::EepromSizeDetected(BackupType::Eeprom512B));
                 self.backup = Some(Backup::Eeprom(Eeprom::new(false)));
             }
             // 14-bit addr read or write: 8KiB.
             17 | 81 => {
-                info!("guessing 8KiB EEPROM size");
+                self.notices
+                    .push(Notice::EepromSizeDetected(BackupType::Eeprom8KiB));
                 self.backup = Some(Backup::Eeprom(Eeprom::new(true)));
             }
             _ => {}
@@ -202,22 +404,37 @@ impl Bus for Cartridge {
                         // the EEPROM yet, so we can assume it's in the ready state.
                         Some(Backup::EepromUnknownSize) if addr % 2 == 0 => 1,
                         Some(Backup::EepromUnknownSize) => 0,
+                        // is_eeprom_offset is only true if the backup is an EEPROM.
                         _ => unreachable!(),
                     }
                 } else {
+                    let offset = addr & 0x1ff_ffff;
                     self.rom
                         .bytes()
-                        .get(usize::try_from(addr).unwrap() & 0x1ff_ffff)
+                        .get(usize::try_from(offset).unwrap())
                         .copied()
-                        .unwrap_or(0)
+                        .unwrap_or_else(|| {
+                            // Open bus: the ROM's data lines are left holding the lower bits of
+                            // the hword address.
+                            let hword = (offset / 2).to_le_bytes();
+                            hword[usize::try_from(offset % 2).unwrap()]
+                        })
                 }
             }
-            0x600_0000..=0x7ff_ffff => match self.backup.as_mut() {
-                Some(Backup::Sram(sram)) => sram.read_byte(addr & 0x7fff),
-                Some(Backup::Flash(flash)) => flash.read_byte(addr & 0xffff),
-                _ => 0xff,
-            },
-            _ => panic!("cartridge address OOB"),
+            0x600_0000..=0x7ff_ffff => {
+                let offset = self.save_offset(addr);
+                if let Some(value) = self.sram_clock.and_then(|clock| clock.read_byte(offset)) {
+                    return value;
+                }
+
+                match self.backup.as_mut() {
+                    Some(Backup::Sram(sram)) => sram.read_byte(offset),
+                    Some(Backup::Flash(flash)) => flash.read_byte(offset),
+                    _ => 0xff,
+                }
+            }
+            // Unused
+            _ => 0xff,
         }
     }
 
@@ -225,26 +442,208 @@ impl Bus for Cartridge {
         match addr {
             // TODO: WAITCNT with wait states 0, 1 and 2
             #[expect(clippy::manual_range_patterns)]
-            0x000_0000..=0x1ff_ffff | 0x200_0000..=0x3ff_ffff | 0x400_0000..=0x5ff_ffff => {
-                if self.is_eeprom_offset(addr) {
-                    if let Some(Backup::EepromUnknownSize) = self.backup {
-                        warn!("could not guess EEPROM size; falling back to 512B!");
-                        self.backup = Some(Backup::Eeprom(Eeprom::new(false)));
-                    }
+            0x000_0000..=0x1ff_ffff | 0x200_0000..=0x3ff_ffff | 0x400_0000..=0x5ff_ffff
+                if self.is_eeprom_offset(addr) =>
+            {
+                if let Some(Backup::EepromUnknownSize) = self.backup {
+                    self.notices.push(Notice::EepromSizeUnknown);
+                    self.backup = Some(Backup::Eeprom(Eeprom::new(false)));
+                }
 
-                    if let Some(Backup::Eeprom(eeprom)) = self.backup.as_mut() {
-                        eeprom.write_byte(addr, value);
-                    } else {
-                        unreachable!();
-                    }
+                if let Some(Backup::Eeprom(eeprom)) = self.backup.as_mut() {
+                    eeprom.write_byte(addr, value);
+                } else {
+                    // Like in read_byte, it was an EEPROM already, or it was just sized above.
+                    unreachable!();
                 }
             }
+            0x600_0000..=0x7ff_ffff
+                if self
+                    .sram_clock
+                    .is_some_and(|clock| clock.contains(self.save_offset(addr))) => {}
             0x600_0000..=0x7ff_ffff => match self.backup.as_mut() {
                 Some(Backup::Sram(sram)) => sram.write_byte(addr & 0x7fff, value),
                 Some(Backup::Flash(flash)) => flash.write_byte(addr & 0xffff, value),
                 _ => {}
             },
-            _ => panic!("cartridge address OOB"),
+            // ROM, Unused
+            _ => {}
+        }
+    }
+
+    // Save memory is only wired to the lower 8 data lines, so wider reads see its byte in every
+    // lane, and wider writes only store the byte of the value in the lane of the address; some
+    // games accidentally rely on this.
+    fn read_hword(&mut self, addr: u32) -> u16 {
+        match addr {
+            0x600_0000..=0x7ff_ffff => u16::from(self.read_byte(addr)) * 0x0101,
+            _ => bus::read_hword_as_bytes(self, addr),
+        }
+    }
+
+    fn read_word(&mut self, addr: u32) -> u32 {
+        match addr {
+            0x600_0000..=0x7ff_ffff => u32::from(self.read_byte(addr)) * 0x0101_0101,
+            _ => bus::read_word_as_hwords(self, addr),
+        }
+    }
+
+    fn write_hword(&mut self, addr: u32, value: u16) {
+        match addr {
+            0x600_0000..=0x7ff_ffff => {
+                self.write_byte(
+                    addr,
+                    value.to_le_bytes()[usize::try_from(addr % 2).unwrap()],
+                );
+            }
+            _ => bus::write_hword_as_bytes(self, addr, value),
+        }
+    }
+
+    fn write_word(&mut self, addr: u32, value: u32) {
+        match addr {
+            0x600_0000..=0x7ff_ffff => {
+                self.write_byte(
+                    addr,
+                    value.to_le_bytes()[usize::try_from(addr % 4).unwrap()],
+                );
+            }
+            _ => bus::write_word_as_hwords(self, addr, value),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_header() {
+        let mut buf = vec![0; 0xc0];
+        buf[0xa0..0xac].copy_from_slice(b"MEME GAME\0\0\0");
+        buf[0xac..0xb0].copy_from_slice(b"AMEE");
+        buf[0xb0..0xb2].copy_from_slice(b"01");
+        buf[0xbc] = 2;
+        buf[0xbd] = buf[0xa0..0xbd]
+            .iter()
+            .fold(0u8, |acc, &b| acc.wrapping_sub(b))
+            .wrapping_sub(0x19);
+
+        let header = Rom::new(Rc::from(buf.clone())).unwrap().header().unwrap();
+        assert_eq!(header.title, "MEME GAME");
+        assert_eq!(header.game_code.as_deref(), Some("AMEE"));
+        assert_eq!(header.maker_code.as_deref(), Some("01"));
+        assert_eq!(header.version, 2);
+        assert!(header.checksum_valid);
+        assert_eq!(header.to_string(), "MEME GAME (AMEE)");
+
+        buf[0xbd] ^= 1;
+        buf[0xac..0xb2].fill(0);
+        let header = Rom::new(Rc::from(buf)).unwrap().header().unwrap();
+        assert!(!header.checksum_valid);
+        assert_eq!(header.to_string(), "MEME GAME");
+        assert_eq!((header.game_code, header.maker_code), (None, None));
+
+        assert!(Rom::new(Rc::from(vec![0; 0xbd]))
+            .unwrap()
+            .header()
+            .is_none());
+    }
+
+    #[test]
+    fn parses_backup_type() {
+        let rom = |ids: &[&[u8]]| {
+            let mut buf = vec![0; 0x200];
+            for (i, id) in ids.iter().enumerate() {
+                buf[0xc0 + 0x10 * i..][..id.len()].copy_from_slice(id);
+            }
+            Rom::new(Rc::from(buf)).unwrap()
+        };
+
+        assert_eq!(rom(&[]).parse_backup_type(), BackupType::None);
+        assert_eq!(
+            rom(&[b"EEPROM_V124"]).parse_backup_type(),
+            BackupType::EepromUnknownSize
+        );
+        assert_eq!(
+            rom(&[b"FLASH512_V131"]).parse_backup_type(),
+            BackupType::Flash64KiB
+        );
+        assert_eq!(
+            rom(&[b"SRAM_F_V103", b"FLASH1M_V103"]).parse_backup_type(),
+            BackupType::Sram32KiB
+        );
+        assert_eq!(rom(&[b"FLASH1M_V103"]).parse_unsupported_backup(), None);
+
+        // DACS carts are treated as ROM, even if they have IDs for supported backup types.
+        let dacs_rom = rom(&[b"FLASH1M_V103", b"DACS_V100"]);
+        assert_eq!(dacs_rom.parse_unsupported_backup(), Some("DACS"));
+        assert_eq!(dacs_rom.parse_backup_type(), BackupType::None);
+    }
+
+    #[test]
+    fn rejects_unknown_backup_sizes() {
+        let rom = Rom::new(Rc::from(vec![0; 0xc0])).unwrap();
+        assert!(Cartridge::try_from_backup(&rom, Some(vec![0; 32 * 1024].into())).is_ok());
+        assert_eq!(
+            Cartridge::try_from_backup(&rom, Some(vec![0; 1234].into())).err(),
+            Some(InvalidBackup { len: 1234 })
+        );
+    }
+
+    #[test]
+    fn reads_open_bus_past_rom_end() {
+        let mut cart = Cartridge::from(Rom::new(Rc::from(vec![0xaa; 0xc0])).unwrap());
+        assert_eq!(cart.read_byte(0xbf), 0xaa);
+        assert_eq!(cart.read_hword(0x1234), 0x091a);
+        assert_eq!(cart.read_word(0x1_2340), 0x91a1_91a0);
+
+        cart.write_byte(0x800_0000, 0xff); // Out of range; ignored.
+        assert_eq!(cart.read_byte(0x800_0000), 0xff);
+    }
+
+    #[test]
+    fn shadows_save_memory_with_sram_clock() {
+        let rom = Rom::new(Rc::from(vec![0; 0xc0])).unwrap();
+        let mut cart = Cartridge::new(rom, BackupType::Sram32KiB);
+        cart.write_byte(0x600_7ff0, 0x42);
+        cart.sram_clock = Some(SramClock::new(0x7ff0, 946_684_800)); // 2000-01-01, a Saturday.
+
+        assert_eq!(cart.read_byte(0x600_7ff2), 0x01);
+        assert_eq!(cart.read_byte(0x600_fff3), 6); // Mirrored like the rest of SRAM.
+        assert_eq!(cart.read_byte(0x600_7ff7), 0xff);
+        cart.write_byte(0x600_7ff0, 0x99); // Ignored.
+        assert_eq!(cart.read_byte(0x600_7ff0), 0x00);
+
+        cart.sram_clock = None;
+        assert_eq!(cart.read_byte(0x600_7ff0), 0x42);
+    }
+
+    #[test]
+    fn accesses_save_memory_as_bytes() {
+        let rom = Rom::new(Rc::from(vec![0; 0xc0])).unwrap();
+        let mut cart = Cartridge::new(rom, BackupType::Sram32KiB);
+        cart.write_byte(0x600_0000, 0x12);
+        cart.write_byte(0x600_0001, 0x34);
+        assert_eq!(cart.read_hword(0x600_0000), 0x1212);
+        assert_eq!(cart.read_hword(0x600_0001), 0x3434);
+        assert_eq!(cart.read_word(0x600_0001), 0x3434_3434);
+
+        cart.write_hword(0x600_0010, 0xbbaa);
+        cart.write_hword(0x600_0021, 0xbbaa);
+        cart.write_word(0x600_0032, 0xddcc_bbaa);
+        assert_eq!(cart.read_byte(0x600_0010), 0xaa);
+        assert_eq!(cart.read_byte(0x600_0011), 0xff);
+        assert_eq!(cart.read_byte(0x600_0021), 0xbb);
+        assert_eq!(cart.read_byte(0x600_0020), 0xff);
+        assert_eq!(cart.read_byte(0x600_0032), 0xcc);
+        assert_eq!(cart.read_hword(0x600_0030), 0xffff);
+        assert_eq!(cart.read_byte(0x600_0033), 0xff);
+
+        // Flash behaves the same, and ROM is still read as hwords.
+        let rom = Rom::new(Rc::from(vec![0x55; 0xc0])).unwrap();
+        let mut cart = Cartridge::new(rom, BackupType::Flash64KiB);
+        assert_eq!(cart.read_word(0x600_0000), 0xffff_ffff);
+        assert_eq!(cart.read_word(0), 0x5555_5555);
+    }
+}
@@ -0,0 +1,212 @@
+//! Per-game overrides for settings that can't be reliably detected from the ROM, keyed by the game
+//! code in the ROM header.
+
+use serde::Deserialize;
+
+use super::BackupType;
+
+/// Overrides for a single game. Unset fields are left to detection or their defaults.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Overrides {
+    /// Backup type to use instead of `Rom::parse_backup_type`'s. `BackupType::None` treats the
+    /// backup region as ROM, like for carts with unsupported backup hardware (see
+    /// `Rom::parse_unsupported_backup`) that isn't detected.
+    pub backup_type: Option<BackupType>,
+    /// Whether the cartridge has a real-time clock.
+    pub rtc: Option<bool>,
+    /// Whether the cartridge has a rumble motor.
+    pub rumble: Option<bool>,
+    /// Whether the cartridge has a solar sensor.
+    pub solar_sensor: Option<bool>,
+    /// Whether colors should be corrected to resemble the GBA's LCD.
+    pub color_correction: Option<bool>,
+    /// Offset into save memory to keep a clock at, for ROM hacks that read the time from there
+    /// (see `cart::sram_clock`). Such hacks share their game codes with the games they're based
+    /// on, so this is never set by the built-in overrides.
+    pub sram_clock: Option<u16>,
+}
+
+/// Built-in overrides for games whose hardware isn't detected correctly.
+const BUILTIN: &[(&str, Overrides)] = &[
+    // Pokémon Ruby, Sapphire and Emerald
+    ("AXVE", POKEMON_RTC),
+    ("AXPE", POKEMON_RTC),
+    ("BPEE", POKEMON_RTC),
+    // Pokémon FireRed and LeafGreen
+    ("BPRE", POKEMON),
+    ("BPGE", POKEMON),
+    // Boktai: The Sun Is in Your Hand
+    (
+        "U3IE",
+        Overrides {
+            backup_type: Some(BackupType::Eeprom8KiB),
+            rtc: Some(true),
+            solar_sensor: Some(true),
+            ..Overrides::NONE
+        },
+    ),
+    // Drill Dozer
+    (
+        "V49E",
+        Overrides {
+            backup_type: Some(BackupType::Sram32KiB),
+            rumble: Some(true),
+            ..Overrides::NONE
+        },
+    ),
+];
+
+const POKEMON: Overrides = Overrides {
+    backup_type: Some(BackupType::Flash128KiB),
+    ..Overrides::NONE
+};
+
+const POKEMON_RTC: Overrides = Overrides {
+    rtc: Some(true),
+    ..POKEMON
+};
+
+impl Overrides {
+    const NONE: Self = Self {
+        backup_type: None,
+        rtc: None,
+        rumble: None,
+        solar_sensor: None,
+        color_correction: None,
+        sram_clock: None,
+    };
+
+    /// Returns the built-in overrides for the game with the given code, if any.
+    #[must_use]
+    pub fn builtin(game_code: &str) -> Option<Self> {
+        BUILTIN
+            .iter()
+            .find(|&&(code, _)| code == game_code)
+            .map(|&(_, overrides)| overrides)
+    }
+
+    /// Returns these overrides with the fields that are set in `other` replaced.
+    #[must_use]
+    pub fn merge(self, other: Self) -> Self {
+        Self {
+            backup_type: other.backup_type.or(self.backup_type),
+            rtc: other.rtc.or(self.rtc),
+            rumble: other.rumble.or(self.rumble),
+            solar_sensor: other.solar_sensor.or(self.solar_sensor),
+            color_correction: other.color_correction.or(self.color_correction),
+            sram_clock: other.sram_clock.or(self.sram_clock),
+        }
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        *self == Self::NONE
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, rc::Rc};
+
+    use crate::cart::Rom;
+
+    use super::*;
+
+    #[test]
+    fn merge_prefers_other() {
+        let builtin = Overrides::builtin("AXVE").unwrap();
+        let user = Overrides {
+            backup_type: Some(BackupType::Sram32KiB),
+            sram_clock: Some(0x7ff0),
+            ..Overrides::NONE
+        };
+
+        let merged = builtin.merge(user);
+        assert_eq!(merged.backup_type, Some(BackupType::Sram32KiB));
+        assert_eq!(merged.rtc, Some(true));
+        assert_eq!(merged.sram_clock, Some(0x7ff0));
+        assert_eq!(merged.rumble, None);
+
+        assert_eq!(builtin.merge(Overrides::NONE), builtin);
+        assert_eq!(Overrides::NONE.merge(user), user);
+        assert!(Overrides::NONE.merge(Overrides::NONE).is_empty());
+    }
+
+    #[test]
+    fn builtin_lookup() {
+        assert_eq!(Overrides::builtin("BPRE"), Some(POKEMON));
+        assert_eq!(Overrides::builtin("BPEE"), Some(POKEMON_RTC));
+        let drill_dozer = Overrides::builtin("V49E").unwrap();
+        assert_eq!(drill_dozer.backup_type, Some(BackupType::Sram32KiB));
+        assert_eq!(drill_dozer.rumble, Some(true));
+
+        assert_eq!(Overrides::builtin("AMEE"), None);
+        assert_eq!(Overrides::builtin("axve"), None);
+        assert_eq!(Overrides::builtin(""), None);
+        assert!(BUILTIN
+            .iter()
+            .all(|(_, overrides)| overrides.sram_clock.is_none()));
+    }
+
+    #[test]
+    fn game_code_from_rom() {
+        let rom = |code: &[u8]| {
+            let mut buf = vec![0; 0xc0];
+            buf[0xac..0xb0].copy_from_slice(code);
+            Rom::new(Rc::from(buf)).unwrap()
+        };
+
+        assert_eq!(rom(b"AXVE").game_code(), Some("AXVE"));
+        assert_eq!(
+            Overrides::builtin(rom(b"U3IE").game_code().unwrap())
+                .unwrap()
+                .solar_sensor,
+            Some(true)
+        );
+        assert_eq!(rom(b"AX E").game_code(), None);
+        assert_eq!(rom(b"\0\0\0\0").game_code(), None);
+        assert_eq!(Rom::new(Rc::from(vec![0; 0xaf])).unwrap().game_code(), None);
+    }
+
+    #[test]
+    fn parses_user_overrides() {
+        let overrides: HashMap<String, Overrides> = toml::from_str(
+            r#"
+            [AXVE]
+            backup-type = "flash-128k"
+            rtc = true
+
+            [BPRE]
+            backup-type = "none"
+            sram-clock = 0x7ff0
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            overrides["AXVE"],
+            Overrides {
+                backup_type: Some(BackupType::Flash128KiB),
+                rtc: Some(true),
+                ..Overrides::NONE
+            }
+        );
+        assert_eq!(
+            overrides["BPRE"],
+            Overrides {
+                backup_type: Some(BackupType::None),
+                sram_clock: Some(0x7ff0),
+                ..Overrides::NONE
+            }
+        );
+
+        let parse = toml::from_str::<Overrides>;
+        assert!(parse("").unwrap().is_empty());
+        assert!(parse("backup-type = \"flash-256k\"").is_err());
+        assert!(parse("rtc = 1").is_err());
+        assert!(parse("sram-clock = 0x10000").is_err());
+        assert!(parse("sram-clock = -1").is_err());
+        assert!(parse("backup_type = \"none\"").is_err());
+        assert!(parse("turbo = true").is_err());
+    }
+}
@@ -0,0 +1,158 @@
+//! Real-time clock shim for ROM hacks that read the time from save memory, rather than from a
+//! real-time clock on the cartridge's GPIO port.
+//!
+//! Such hacks (e.g: of Pokémon games that lack an RTC) expect the emulator to keep the current
+//! time in 7 bytes of save memory at an offset of their choosing. The bytes use the same BCD
+//! date-time format as the GBA's RTC (the Seiko S-3511A): year (since 2000), month, day, day of
+//! the week (0 being Sunday), hour (24-hour), minute and second.
+
+use serde::{Deserialize, Serialize};
+
+/// Number of CPU cycles per second.
+const CYCLES_PER_SECOND: u32 = 1 << 24;
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct SramClock {
+    offset: u16,
+    seconds: u64,
+    cycles: u32,
+}
+
+impl SramClock {
+    /// Number of bytes of save memory the clock occupies.
+    pub const LEN: u32 = 7;
+
+    /// Creates a clock at `offset` in save memory (e.g: 0x7ff0 for address `0x0e00_7ff0`), starting at
+    /// `seconds` since the Unix epoch (e.g: `DeterminismConfig::rtc_epoch`).
+    #[must_use]
+    pub fn new(offset: u16, seconds: u64) -> Self {
+        Self {
+            offset,
+            seconds,
+            cycles: 0,
+        }
+    }
+
+    #[must_use]
+    pub fn offset(&self) -> u16 {
+        self.offset
+    }
+
+    /// Current time of the clock, in seconds since the Unix epoch.
+    #[must_use]
+    pub fn seconds(&self) -> u64 {
+        self.seconds
+    }
+
+    pub fn set_seconds(&mut self, seconds: u64) {
+        self.seconds = seconds;
+        self.cycles = 0;
+    }
+
+    /// Whether the state is in range, which is always the case unless it was loaded from a
+    /// malformed save state; such state can panic when stepped.
+    pub(crate) fn is_valid(&self) -> bool {
+        // Far enough from overflowing that it never will while running.
+        self.cycles < CYCLES_PER_SECOND && self.seconds < 1 << 62
+    }
+
+    pub(super) fn step(&mut self, cycles: u32) {
+        self.cycles += cycles;
+        while self.cycles >= CYCLES_PER_SECOND {
+            self.cycles -= CYCLES_PER_SECOND;
+            self.seconds += 1;
+        }
+    }
+
+    /// Reads the byte of the date-time at `offset` in save memory, or returns `None` if `offset`
+    /// isn't part of the clock.
+    pub(super) fn read_byte(&self, offset: u32) -> Option<u8> {
+        if !self.contains(offset) {
+            return None;
+        }
+        let i = offset - u32::from(self.offset);
+
+        Some(self.date_time()[usize::try_from(i).unwrap()])
+    }
+
+    /// Whether `offset` in save memory is part of the clock. The clock can't be set by the game,
+    /// so writes to it are ignored.
+    pub(super) fn contains(&self, offset: u32) -> bool {
+        offset
+            .checked_sub(self.offset.into())
+            .is_some_and(|i| i < Self::LEN)
+    }
+
+    /// Returns the date-time in the format described by the module's documentation.
+    // Each field is under 100, so this doesn't panic.
+    #[expect(clippy::missing_panics_doc)]
+    #[must_use]
+    pub fn date_time(&self) -> [u8; 7] {
+        let days = self.seconds / SECONDS_PER_DAY;
+        let time = self.seconds % SECONDS_PER_DAY;
+        let (year, month, day) = civil_from_days(days);
+        // 1970-01-01 was a Thursday.
+        let weekday = (days + 4) % 7;
+
+        [
+            year % 100,
+            month,
+            day,
+            weekday,
+            time / 3600,
+            time / 60 % 60,
+            time % 60,
+        ]
+        .map(|value| {
+            let value = u8::try_from(value).unwrap();
+            ((value / 10) << 4) | (value % 10)
+        })
+    }
+}
+
+/// Converts days since the Unix epoch to a (year, month, day) date in the proleptic Gregorian
+/// calendar. See <https://howardhinnant.github.io/date_algorithms.html#civil_from_days>.
+fn civil_from_days(days: u64) -> (u64, u64, u64) {
+    let days = days + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    // Months counted from March, so that the leap day is at the end of the year.
+    let month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month + 2) / 5 + 1;
+    let month = if month < 10 { month + 3 } else { month - 9 };
+    let year = era * 400 + year_of_era + u64::from(month <= 2);
+
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_time() {
+        // 2004-02-29 23:59:59 UTC, a Sunday.
+        let mut clock = SramClock::new(0x7ff0, 1_078_099_199);
+        assert_eq!(clock.date_time(), [0x04, 0x02, 0x29, 0, 0x23, 0x59, 0x59]);
+        assert_eq!(clock.read_byte(0x7ff0), Some(0x04));
+        assert_eq!(clock.read_byte(0x7ff6), Some(0x59));
+        assert_eq!(clock.read_byte(0x7ff7), None);
+        assert_eq!(clock.read_byte(0x7fef), None);
+
+        clock.step(CYCLES_PER_SECOND - 1);
+        assert_eq!(clock.seconds(), 1_078_099_199);
+        clock.step(1);
+        assert_eq!(clock.date_time(), [0x04, 0x03, 0x01, 1, 0x00, 0x00, 0x00]);
+
+        // 2099-12-31 23:59:59 UTC wraps around to 2000.
+        clock.set_seconds(4_102_444_799);
+        assert_eq!(clock.date_time(), [0x99, 0x12, 0x31, 4, 0x23, 0x59, 0x59]);
+        clock.step(CYCLES_PER_SECOND);
+        assert_eq!(clock.date_time(), [0x00, 0x01, 0x01, 5, 0x00, 0x00, 0x00]);
+    }
+}
@@ -0,0 +1,283 @@
+//! Cheat searching: finding the addresses of in-game values (like lives or money) in EWRAM and
+//! IWRAM by repeatedly filtering candidate addresses based on how their values changed. The values
+//! found can then be held with `patch::Patches`.
+
+pub mod patch;
+
+use std::{
+    error::Error,
+    fmt::{self, Display, Formatter},
+};
+
+use crate::gba::Gba;
+
+const EWRAM_START: u32 = 0x0200_0000;
+const IWRAM_START: u32 = 0x0300_0000;
+
+/// Width of the values being searched for.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub enum Width {
+    #[default]
+    Byte,
+    Hword,
+    Word,
+}
+
+impl Width {
+    /// Size of a value of this width in bytes.
+    #[must_use]
+    pub fn bytes(self) -> u32 {
+        match self {
+            Self::Byte => 1,
+            Self::Hword => 2,
+            Self::Word => 4,
+        }
+    }
+
+    fn mask(self) -> u32 {
+        u32::MAX >> (32 - 8 * self.bytes())
+    }
+}
+
+/// Condition that the value at a candidate address must satisfy to remain a candidate. Changes
+/// are relative to the value in the previous snapshot, and are compared as unsigned integers.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Filter {
+    Equal(u32),
+    NotEqual(u32),
+    Changed,
+    Unchanged,
+    Increased,
+    Decreased,
+    /// Increased by exactly the given amount (wrapping).
+    IncreasedBy(u32),
+    /// Decreased by exactly the given amount (wrapping).
+    DecreasedBy(u32),
+}
+
+impl Filter {
+    /// Checks that the value compared against fits in `width`, as a wider one could never (or
+    /// would always) match.
+    fn check(self, width: Width) -> Result<Self, InvalidFilter> {
+        match self {
+            Self::Equal(value) | Self::NotEqual(value) if value & !width.mask() != 0 => {
+                Err(InvalidFilter)
+            }
+            _ => Ok(self),
+        }
+    }
+
+    fn matches(self, old: u32, new: u32, width: Width) -> bool {
+        let mask = width.mask();
+        match self {
+            Self::Equal(value) => new == value,
+            Self::NotEqual(value) => new != value,
+            Self::Changed => new != old,
+            Self::Unchanged => new == old,
+            Self::Increased => new > old,
+            Self::Decreased => new < old,
+            Self::IncreasedBy(amount) => new == old.wrapping_add(amount) & mask,
+            Self::DecreasedBy(amount) => new == old.wrapping_sub(amount) & mask,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct InvalidFilter;
+
+impl Display for InvalidFilter {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "Value is wider than the values being searched for")
+    }
+}
+
+impl Error for InvalidFilter {}
+
+/// Copy of EWRAM and IWRAM at some point in time.
+#[derive(Debug, Clone)]
+struct Snapshot {
+    ewram: Box<[u8]>,
+    iwram: Box<[u8]>,
+}
+
+impl Snapshot {
+    fn new(gba: &Gba) -> Self {
+        Self {
+            ewram: gba.ewram.clone(),
+            iwram: gba.iwram.clone(),
+        }
+    }
+
+    fn read(&self, addr: u32, width: Width) -> Option<u32> {
+        let (mem, start) = match addr >> 24 {
+            2 => (&self.ewram, EWRAM_START),
+            3 => (&self.iwram, IWRAM_START),
+            _ => return None,
+        };
+        let offset = usize::try_from(addr - start).unwrap();
+        let bytes = mem.get(offset..offset + usize::try_from(width.bytes()).unwrap())?;
+
+        Some(
+            bytes
+                .iter()
+                .rev()
+                .fold(0, |value, &byte| value << 8 | u32::from(byte)),
+        )
+    }
+
+    fn addrs(&self, width: Width) -> impl Iterator<Item = u32> {
+        let ewram_end = EWRAM_START + u32::try_from(self.ewram.len()).unwrap();
+        let iwram_end = IWRAM_START + u32::try_from(self.iwram.len()).unwrap();
+        let step = usize::try_from(width.bytes()).unwrap();
+
+        (EWRAM_START..ewram_end)
+            .step_by(step)
+            .chain((IWRAM_START..iwram_end).step_by(step))
+    }
+}
+
+/// A cheat search over EWRAM and IWRAM.
+///
+/// A search starts with every aligned address as a candidate, which is the "unknown initial
+/// value" search. Each call to `Self::filter` then removes candidates whose values don't pass the
+/// filter, and takes a new snapshot of memory for the next call to compare against.
+#[derive(Debug, Clone)]
+pub struct Search {
+    width: Width,
+    snapshot: Snapshot,
+    candidates: Vec<u32>,
+}
+
+impl Search {
+    /// Starts a new search for values of the given `width`, taking an initial snapshot of `gba`'s
+    /// memory.
+    #[must_use]
+    pub fn new(gba: &Gba, width: Width) -> Self {
+        let snapshot = Snapshot::new(gba);
+        let candidates = snapshot.addrs(width).collect();
+
+        Self {
+            width,
+            snapshot,
+            candidates,
+        }
+    }
+
+    /// Removes candidates whose current values in `gba` don't pass `filter`, then takes a new
+    /// snapshot of memory.
+    ///
+    /// # Errors
+    /// Returns an error, leaving the search unchanged, if `filter` compares against a value wider
+    /// than the search's width.
+    // Panic is impossible, as candidates are always addresses within the snapshots.
+    #[expect(clippy::missing_panics_doc)]
+    pub fn filter(&mut self, gba: &Gba, filter: Filter) -> Result<(), InvalidFilter> {
+        let filter = filter.check(self.width)?;
+        let snapshot = Snapshot::new(gba);
+        self.candidates.retain(|&addr| {
+            let old = self.snapshot.read(addr, self.width).unwrap();
+            let new = snapshot.read(addr, self.width).unwrap();
+            filter.matches(old, new, self.width)
+        });
+        self.snapshot = snapshot;
+
+        Ok(())
+    }
+
+    #[must_use]
+    pub fn width(&self) -> Width {
+        self.width
+    }
+
+    /// Addresses that passed every filter so far, in ascending order.
+    #[must_use]
+    pub fn candidates(&self) -> &[u32] {
+        &self.candidates
+    }
+
+    /// Returns the remaining candidates along with their values in the latest snapshot.
+    #[expect(clippy::missing_panics_doc)] // see Self::filter
+    pub fn results(&self) -> impl Iterator<Item = (u32, u32)> + '_ {
+        self.candidates
+            .iter()
+            .map(|&addr| (addr, self.snapshot.read(addr, self.width).unwrap()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use crate::{bios, cart};
+
+    use super::*;
+
+    fn new_gba() -> Gba {
+        let bios_rom = bios::Rom::new(Rc::from(vec![0; 0x4000])).unwrap();
+        let cart_rom = cart::Rom::new(Rc::from(vec![0; 0x200])).unwrap();
+
+        Gba::new(bios_rom, cart::Cartridge::from(cart_rom))
+    }
+
+    #[test]
+    fn search_filters() {
+        let mut gba = new_gba();
+        gba.ewram[0x100] = 3;
+        gba.iwram[0x20] = 3;
+        gba.iwram[0x40] = 3;
+
+        let mut search = Search::new(&gba, Width::Byte);
+        assert_eq!(search.candidates().len(), 0x4_8000);
+
+        search.filter(&gba, Filter::Equal(3)).unwrap();
+        assert_eq!(search.candidates(), [0x0200_0100, 0x0300_0020, 0x0300_0040]);
+
+        gba.ewram[0x100] = 2;
+        gba.iwram[0x20] = 2;
+        gba.iwram[0x40] = 4;
+        search.filter(&gba, Filter::Decreased).unwrap();
+        assert_eq!(search.candidates(), [0x0200_0100, 0x0300_0020]);
+
+        gba.iwram[0x20] = 1;
+        search.filter(&gba, Filter::Unchanged).unwrap();
+        assert_eq!(search.results().collect::<Vec<_>>(), [(0x0200_0100, 2)]);
+    }
+
+    #[test]
+    fn search_wider_values() {
+        let mut gba = new_gba();
+        gba.iwram[0x10..0x14].copy_from_slice(&0xffff_fffe_u32.to_le_bytes());
+
+        let mut search = Search::new(&gba, Width::Word);
+        assert_eq!(search.candidates().len(), 0x4_8000 / 4);
+
+        gba.iwram[0x10..0x14].copy_from_slice(&3_u32.to_le_bytes());
+        search.filter(&gba, Filter::IncreasedBy(5)).unwrap();
+        assert_eq!(search.results().collect::<Vec<_>>(), [(0x0300_0010, 3)]);
+
+        let mut search = Search::new(&gba, Width::Hword);
+        search.filter(&gba, Filter::Equal(3)).unwrap();
+        assert_eq!(search.candidates(), [0x0300_0010]);
+    }
+
+    #[test]
+    fn search_rejects_wide_values() {
+        let gba = new_gba();
+        let mut search = Search::new(&gba, Width::Byte);
+        assert_eq!(
+            search.filter(&gba, Filter::Equal(0x100)),
+            Err(InvalidFilter)
+        );
+        assert_eq!(
+            search.filter(&gba, Filter::NotEqual(0x1ff)),
+            Err(InvalidFilter)
+        );
+        assert_eq!(search.candidates().len(), 0x4_8000);
+
+        search.filter(&gba, Filter::NotEqual(0xff)).unwrap();
+        assert_eq!(search.candidates().len(), 0x4_8000);
+        // Only the values compared against are checked; amounts wrap around.
+        search.filter(&gba, Filter::IncreasedBy(0x100)).unwrap();
+        assert_eq!(search.candidates().len(), 0x4_8000);
+    }
+}
@@ -0,0 +1,307 @@
+//! Raw patches: lists of values to write to addresses, like those found by a cheat `Search`.
+//!
+//! A patch list has one `ADDRESS:VALUE` pair per line in hexadecimal (e.g: `02001234:63`), where
+//! the width of the value is given by its number of digits: up to 2 for a byte, 4 for a half-word
+//! and 8 for a word. Blank lines and lines starting with `#` are ignored.
+//!
+//! Patches to cartridge ROM are applied once, before the ROM is loaded. Patches to RAM (EWRAM,
+//! IWRAM, palette RAM, VRAM or OAM) are "freezes", written every frame to hold a value (e.g: a
+//! number of lives). Patches to anywhere else, like IO registers or save memory, are rejected, as
+//! writing to them every frame has side effects.
+
+use std::{
+    error::Error,
+    fmt::{self, Display, Formatter},
+    ops::Range,
+    str::FromStr,
+};
+
+use crate::gba::Gba;
+
+use super::{Search, Width};
+
+const ROM_START: u32 = 0x0800_0000;
+const ROM_END: u32 = 0x0e00_0000;
+/// EWRAM and IWRAM, then palette RAM, VRAM and OAM, including their mirrors.
+const RAM_RANGES: [Range<u32>; 2] = [0x0200_0000..0x0400_0000, 0x0500_0000..0x0800_0000];
+
+/// A value to write to an address.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Patch {
+    pub addr: u32,
+    pub value: u32,
+    pub width: Width,
+}
+
+impl Patch {
+    /// Whether the patch is to cartridge ROM (or one of its mirrors), rather than a RAM freeze.
+    #[must_use]
+    pub fn is_rom(&self) -> bool {
+        (ROM_START..ROM_END).contains(&self.addr)
+    }
+
+    /// Whether the patch is a freeze to RAM (or one of its mirrors).
+    #[must_use]
+    pub fn is_freeze(&self) -> bool {
+        RAM_RANGES.iter().any(|range| range.contains(&self.addr))
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct InvalidPatch(&'static str);
+
+impl Display for InvalidPatch {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid patch: {}", self.0)
+    }
+}
+
+impl Error for InvalidPatch {}
+
+impl FromStr for Patch {
+    type Err = InvalidPatch;
+
+    /// Parses an `ADDRESS:VALUE` pair, like a line of a patch list.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (addr, value) = s
+            .split_once(':')
+            .ok_or(InvalidPatch("expected ADDRESS:VALUE"))?;
+        let (addr, value) = (addr.trim(), value.trim());
+        let addr = u32::from_str_radix(addr, 16).map_err(|_| InvalidPatch("bad address"))?;
+        let width = match value.len() {
+            1..=2 => Width::Byte,
+            3..=4 => Width::Hword,
+            5..=8 => Width::Word,
+            _ => return Err(InvalidPatch("bad value")),
+        };
+        let value = u32::from_str_radix(value, 16).map_err(|_| InvalidPatch("bad value"))?;
+        if addr % width.bytes() != 0 {
+            return Err(InvalidPatch("misaligned address"));
+        }
+
+        let patch = Self { addr, value, width };
+        if !patch.is_rom() && !patch.is_freeze() {
+            return Err(InvalidPatch("address isn't in ROM or RAM"));
+        }
+
+        Ok(patch)
+    }
+}
+
+impl Display for Patch {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let digits = 2 * usize::try_from(self.width.bytes()).unwrap();
+        write!(f, "{:08x}:{:0digits$x}", self.addr, self.value)
+    }
+}
+
+/// Error from parsing a patch list, with the 1-based number of the offending line.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct InvalidPatches {
+    pub line: usize,
+    pub error: InvalidPatch,
+}
+
+impl Display for InvalidPatches {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (line {})", self.error, self.line)
+    }
+}
+
+impl Error for InvalidPatches {}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Patches(pub Vec<Patch>);
+
+impl Patches {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parses a patch list.
+    ///
+    /// # Errors
+    /// Returns an error if a line isn't a valid patch, blank, or a comment.
+    pub fn parse(text: &str) -> Result<Self, InvalidPatches> {
+        text.lines()
+            .enumerate()
+            .map(|(i, line)| (i + 1, line.trim()))
+            .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+            .map(|(line, s)| s.parse().map_err(|error| InvalidPatches { line, error }))
+            .collect::<Result<_, _>>()
+            .map(Self)
+    }
+
+    /// Freezes the remaining candidates of `search` at their latest values.
+    #[must_use]
+    pub fn from_search(search: &Search) -> Self {
+        Self(
+            search
+                .results()
+                .map(|(addr, value)| Patch {
+                    addr,
+                    value,
+                    width: search.width(),
+                })
+                .collect(),
+        )
+    }
+
+    #[must_use]
+    pub fn has_rom_patches(&self) -> bool {
+        self.0.iter().any(Patch::is_rom)
+    }
+
+    /// Applies the patches to cartridge ROM to `rom`, the contents of a ROM file, before it's
+    /// loaded. Bytes past the end of `rom` are left unpatched, as the ROM can't grow.
+    #[expect(clippy::missing_panics_doc)] // offsets and widths always fit a usize
+    pub fn patch_rom(&self, rom: &mut [u8]) {
+        for patch in self.0.iter().filter(|patch| patch.is_rom()) {
+            // Cartridge ROM is mirrored every 32 MiB.
+            let offset = (patch.addr - ROM_START) & 0x1ff_ffff;
+            let bytes = &patch.value.to_le_bytes()[..usize::try_from(patch.width.bytes()).unwrap()];
+            for (i, &byte) in bytes.iter().enumerate() {
+                if let Some(rom_byte) = rom.get_mut(usize::try_from(offset).unwrap() + i) {
+                    *rom_byte = byte;
+                }
+            }
+        }
+    }
+
+    /// Writes the RAM freezes via the bus, as the CPU would. Call once per frame to hold their
+    /// values. Patches that are neither to ROM nor RAM are ignored.
+    #[expect(clippy::missing_panics_doc)] // values are truncated to their width before converting
+    pub fn freeze(&self, gba: &mut Gba) {
+        for patch in self.0.iter().filter(|patch| patch.is_freeze()) {
+            let value = patch.value & patch.width.mask();
+            match patch.width {
+                Width::Byte => gba.write_byte(patch.addr, value.try_into().unwrap()),
+                Width::Hword => gba.write_hword(patch.addr, value.try_into().unwrap()),
+                Width::Word => gba.write_word(patch.addr, value),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use crate::{bios, cart, cheat::Filter};
+
+    use super::*;
+
+    fn new_gba(rom: Vec<u8>) -> Gba {
+        let bios_rom = bios::Rom::new(Rc::from(vec![0; 0x4000])).unwrap();
+        let cart_rom = cart::Rom::new(Rc::from(rom)).unwrap();
+
+        Gba::new(bios_rom, cart::Cartridge::from(cart_rom))
+    }
+
+    #[test]
+    fn parses_patch_lists() {
+        let patches = Patches::parse(
+            "# Infinite lives\n\
+             02001234:63\n\
+             \n\
+             03000010 : 03e7\n\
+             080000c0:deadbeef\n",
+        )
+        .unwrap();
+        assert_eq!(
+            patches.0,
+            [
+                Patch {
+                    addr: 0x0200_1234,
+                    value: 0x63,
+                    width: Width::Byte
+                },
+                Patch {
+                    addr: 0x0300_0010,
+                    value: 0x3e7,
+                    width: Width::Hword
+                },
+                Patch {
+                    addr: 0x0800_00c0,
+                    value: 0xdead_beef,
+                    width: Width::Word
+                },
+            ]
+        );
+        assert_eq!(patches.0[1].to_string(), "03000010:03e7");
+        assert!(patches.has_rom_patches());
+
+        for (text, error) in [
+            ("02001234", "expected ADDRESS:VALUE"),
+            ("0200123g:1", "bad address"),
+            ("02001234:", "bad value"),
+            ("02001234:123456789", "bad value"),
+            ("02001235:1234", "misaligned address"),
+            ("00000000:0", "address isn't in ROM or RAM"),
+            ("04000000:0080", "address isn't in ROM or RAM"),
+            ("0e000000:ff", "address isn't in ROM or RAM"),
+        ] {
+            assert_eq!(
+                Patches::parse(&format!("# ok\n02000000:1\n{text}")),
+                Err(InvalidPatches {
+                    line: 3,
+                    error: InvalidPatch(error)
+                }),
+                "{text}"
+            );
+        }
+    }
+
+    #[test]
+    fn patches_rom() {
+        let patches =
+            Patches::parse("08000001:ab\n0a000002:cdef\n080001fc:12345678\n02000000:ff").unwrap();
+        let mut rom = vec![0; 0x1fe];
+        patches.patch_rom(&mut rom);
+
+        // Mirrors are patched, RAM freezes are ignored and bytes past the end are dropped.
+        assert_eq!(rom[..5], [0, 0xab, 0xef, 0xcd, 0]);
+        assert_eq!(rom[0x1fc..], [0x78, 0x56]);
+        assert_eq!(rom.iter().filter(|&&byte| byte != 0).count(), 5);
+    }
+
+    #[test]
+    fn freezes_ram() {
+        let mut patches =
+            Patches::parse("02000010:7f\n03000020:12345678\n05000002:7fff\n08000000:ff").unwrap();
+        // Not accepted by Patches::parse, but may still be constructed directly.
+        patches.0.push(Patch {
+            addr: 0x0400_0000,
+            value: 0x80,
+            width: Width::Hword,
+        });
+        let mut gba = new_gba(vec![0; 0x200]);
+        gba.ewram[0x10] = 1;
+        patches.freeze(&mut gba);
+        assert_eq!(gba.ewram[0x10], 0x7f);
+        assert_eq!(gba.iwram[0x20..0x24], 0x1234_5678_u32.to_le_bytes());
+        assert_eq!(gba.read_hword(0x0500_0002), 0x7fff);
+        assert_eq!(gba.read_byte(0x0800_0000), 0);
+        assert_eq!(gba.read_hword(0x0400_0000), 0);
+
+        gba.ewram[0x10] = 0;
+        patches.freeze(&mut gba);
+        assert_eq!(gba.ewram[0x10], 0x7f);
+    }
+
+    #[test]
+    fn freezes_search_results() {
+        let mut gba = new_gba(vec![0; 0x200]);
+        gba.iwram[0x40] = 9;
+        let mut search = Search::new(&gba, Width::Byte);
+        search.filter(&gba, Filter::Equal(9)).unwrap();
+
+        let patches = Patches::from_search(&search);
+        let patches_text: Vec<_> = patches.0.iter().map(ToString::to_string).collect();
+        assert_eq!(patches_text, ["03000040:09"]);
+        gba.iwram[0x40] = 8;
+        patches.freeze(&mut gba);
+        assert_eq!(gba.iwram[0x40], 9);
+    }
+}
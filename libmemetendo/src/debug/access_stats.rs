@@ -0,0 +1,216 @@
+use std::{
+    collections::BTreeMap,
+    io::{self, Write},
+};
+
+use super::{io::Register, trace::AccessKind};
+
+/// Size of pages by default: 256 bytes.
+const DEFAULT_PAGE_BITS: u32 = 8;
+
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub struct PageCounts {
+    pub reads: u64,
+    pub writes: u64,
+}
+
+/// Counts accesses made via the bus per page of memory, such as for finding hot IO registers or
+/// regions of memory. Accesses by the CPU (including instruction fetches) and DMA are counted,
+/// with wider accesses counted once at their address.
+///
+/// Disabled by default, as counting slows down every access.
+#[derive(Debug)]
+pub struct AccessStats {
+    enabled: bool,
+    page_bits: u32,
+    pages: BTreeMap<u32, PageCounts>,
+    suppressed: bool,
+}
+
+impl Default for AccessStats {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            page_bits: DEFAULT_PAGE_BITS,
+            pages: BTreeMap::new(),
+            suppressed: false,
+        }
+    }
+}
+
+impl AccessStats {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[must_use]
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Enables or disables counting. Counts are kept when disabled.
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    #[must_use]
+    pub fn page_size(&self) -> u32 {
+        1 << self.page_bits
+    }
+
+    /// Sets the size of pages to `2^bits` bytes (e.g: 1 to count accesses to each IO register
+    /// half-word separately), clearing the counts.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bits` is greater than 31.
+    pub fn set_page_bits(&mut self, bits: u32) {
+        assert!(bits < 32, "page size too large");
+        self.page_bits = bits;
+        self.clear();
+    }
+
+    pub fn clear(&mut self) {
+        self.pages.clear();
+    }
+
+    /// Returns the address and counts of each accessed page, in order of address.
+    pub fn pages(&self) -> impl Iterator<Item = (u32, &PageCounts)> {
+        self.pages
+            .iter()
+            .map(|(&page, counts)| (page << self.page_bits, counts))
+    }
+
+    /// Writes the counts as CSV, with a row per accessed page. Pages are labelled with the name of
+    /// the IO register or memory region that they start in.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if writing to `w` fails.
+    pub fn write_csv(&self, mut w: impl Write) -> io::Result<()> {
+        writeln!(w, "addr,label,reads,writes")?;
+        for (addr, counts) in self.pages() {
+            writeln!(
+                w,
+                "{addr:#010x},{},{},{}",
+                label(addr),
+                counts.reads,
+                counts.writes
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes the counts as a JSON array, with an object per accessed page; see `Self::write_csv`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if writing to `w` fails.
+    pub fn write_json(&self, mut w: impl Write) -> io::Result<()> {
+        write!(w, "[")?;
+        for (i, (addr, counts)) in self.pages().enumerate() {
+            let sep = if i == 0 { "" } else { "," };
+            write!(
+                w,
+                "{sep}\n  {{\"addr\":\"{addr:#010x}\",\"label\":\"{}\",",
+                label(addr)
+            )?;
+            write!(
+                w,
+                "\"reads\":{},\"writes\":{}}}",
+                counts.reads, counts.writes
+            )?;
+        }
+        writeln!(w, "\n]")
+    }
+
+    #[inline]
+    pub(crate) fn is_counting(&self) -> bool {
+        self.enabled && !self.suppressed
+    }
+
+    /// Suppresses counting while a wider access is split into smaller ones, so that it's only
+    /// counted once.
+    pub(crate) fn set_suppressed(&mut self, suppressed: bool) {
+        self.suppressed = suppressed;
+    }
+
+    pub(crate) fn record(&mut self, addr: u32, kind: AccessKind) {
+        let counts = self.pages.entry(addr >> self.page_bits).or_default();
+        match kind {
+            AccessKind::Read => counts.reads += 1,
+            AccessKind::Write => counts.writes += 1,
+        }
+    }
+}
+
+fn label(addr: u32) -> &'static str {
+    if let Some(reg) = Register::find(addr) {
+        return reg.name;
+    }
+
+    match addr {
+        0x0000_0000..=0x0000_3fff => "BIOS",
+        0x0200_0000..=0x02ff_ffff => "EWRAM",
+        0x0300_0000..=0x03ff_ffff => "IWRAM",
+        0x0400_0000..=0x04ff_ffff => "IO",
+        0x0500_0000..=0x05ff_ffff => "Palette RAM",
+        0x0600_0000..=0x06ff_ffff => "VRAM",
+        0x0700_0000..=0x07ff_ffff => "OAM",
+        0x0800_0000..=0x0dff_ffff => "ROM",
+        0x0e00_0000..=0x0fff_ffff => "SRAM",
+        _ => "Unused",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_per_page() {
+        let mut stats = AccessStats::new();
+        stats.record(0x0300_0000, AccessKind::Read);
+        stats.record(0x0300_00ff, AccessKind::Write);
+        stats.record(0x0300_0100, AccessKind::Read);
+        stats.record(0x0300_0104, AccessKind::Read);
+
+        let pages: Vec<_> = stats
+            .pages()
+            .map(|(addr, &PageCounts { reads, writes })| (addr, reads, writes))
+            .collect();
+        assert_eq!(pages, [(0x0300_0000, 1, 1), (0x0300_0100, 2, 0)]);
+
+        stats.set_page_bits(1);
+        assert_eq!(stats.page_size(), 2);
+        assert_eq!(stats.pages().count(), 0);
+    }
+
+    #[test]
+    fn export_formats() {
+        let mut stats = AccessStats::new();
+        stats.set_page_bits(1);
+        stats.record(0x0400_0004, AccessKind::Read);
+        stats.record(0x0400_0004, AccessKind::Write);
+        stats.record(0x0800_0000, AccessKind::Read);
+
+        let mut csv = Vec::new();
+        stats.write_csv(&mut csv).unwrap();
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            "addr,label,reads,writes\n\
+             0x04000004,DISPSTAT,1,1\n\
+             0x08000000,ROM,1,0\n"
+        );
+
+        let mut json = Vec::new();
+        stats.write_json(&mut json).unwrap();
+        assert_eq!(
+            String::from_utf8(json).unwrap(),
+            "[\n  {\"addr\":\"0x04000004\",\"label\":\"DISPSTAT\",\"reads\":1,\"writes\":1},\n  \
+             {\"addr\":\"0x08000000\",\"label\":\"ROM\",\"reads\":1,\"writes\":0}\n]\n"
+        );
+    }
+}
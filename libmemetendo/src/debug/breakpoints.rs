@@ -0,0 +1,67 @@
+//! Breakpoints that stop the CPU before it executes the instruction at an address, optionally only
+//! if a condition holds.
+
+use super::expr::Expr;
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Breakpoint {
+    pub addr: u32,
+    /// Expression that must evaluate to non-zero for the breakpoint to be hit, if any.
+    pub condition: Option<Expr>,
+}
+
+/// Breakpoints checked by `Gba::step` before each instruction. When one is hit, the CPU stops
+/// stepping for the rest of the current system step, and `Self::take_hit` returns its address.
+#[derive(Debug, Default, Clone)]
+pub struct Breakpoints {
+    list: Vec<Breakpoint>,
+    hit: Option<u32>,
+    /// Address of the breakpoint that was last hit, which isn't checked again for the next
+    /// instruction, so that execution can resume past it.
+    resume_addr: Option<u32>,
+}
+
+impl Breakpoints {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, breakpoint: Breakpoint) {
+        self.list.push(breakpoint);
+    }
+
+    /// Removes and returns the breakpoint at `idx` in `Self::iter`'s order, if any.
+    pub fn remove(&mut self, idx: usize) -> Option<Breakpoint> {
+        (idx < self.list.len()).then(|| self.list.remove(idx))
+    }
+
+    pub fn clear(&mut self) {
+        self.list.clear();
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.list.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Breakpoint> {
+        self.list.iter()
+    }
+
+    /// Returns the address of the breakpoint hit since the last call, if any.
+    pub fn take_hit(&mut self) -> Option<u32> {
+        self.hit.take()
+    }
+
+    /// Whether a breakpoint is set at `addr`, ignoring conditions. Returns false for the address
+    /// execution is resuming from after a hit, once.
+    pub(crate) fn check_addr(&mut self, addr: u32) -> bool {
+        self.resume_addr.take() != Some(addr) && self.list.iter().any(|bp| bp.addr == addr)
+    }
+
+    pub(crate) fn set_hit(&mut self, addr: u32) {
+        self.hit = Some(addr);
+        self.resume_addr = Some(addr);
+    }
+}
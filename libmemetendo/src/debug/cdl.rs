@@ -0,0 +1,123 @@
+//! Code/data logging: recording how each byte of the cartridge ROM was accessed, such as for
+//! telling code apart from data when disassembling a ROM.
+//!
+//! Logs are written as raw files the same size as the ROM, with a byte of flags per ROM byte:
+//!
+//! | Bit | Meaning                                              |
+//! |-----|------------------------------------------------------|
+//! | 0   | Executed by the CPU as (part of) an ARM instruction  |
+//! | 1   | Executed by the CPU as (part of) a Thumb instruction |
+//! | 2   | Read by the CPU as data (e.g: via LDR)               |
+//! | 3   | Read by a DMA transfer                               |
+//!
+//! Other bits are reserved, and are zero.
+
+use std::io::{self, Write};
+
+/// Executed by the CPU as (part of) an ARM instruction.
+pub const ARM: u8 = 1 << 0;
+/// Executed by the CPU as (part of) a Thumb instruction.
+pub const THUMB: u8 = 1 << 1;
+/// Read by the CPU as data.
+pub const DATA: u8 = 1 << 2;
+/// Read by a DMA transfer.
+pub const DMA: u8 = 1 << 3;
+
+/// Code/data logger for the cartridge ROM; see the module documentation.
+///
+/// Disabled by default, as logging slows down every instruction and ROM access.
+#[derive(Debug, Default, Clone)]
+pub struct CodeDataLog {
+    enabled: bool,
+    flags: Vec<u8>,
+    /// Whether the CPU is fetching instructions, rather than reading data.
+    fetching: bool,
+    /// Whether a DMA transfer is in progress.
+    dma: bool,
+}
+
+impl CodeDataLog {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[must_use]
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Enables or disables logging. The log is kept when disabled.
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    pub fn clear(&mut self) {
+        self.flags.clear();
+    }
+
+    /// Returns the flags of each ROM byte. May be shorter than the ROM if nothing has been logged
+    /// yet, in which case missing bytes have no flags set.
+    #[must_use]
+    pub fn flags(&self) -> &[u8] {
+        &self.flags
+    }
+
+    /// Merges the flags from a previously written log into this one, such as to accumulate
+    /// coverage across sessions.
+    pub fn merge(&mut self, buf: &[u8]) {
+        if self.flags.len() < buf.len() {
+            self.flags.resize(buf.len(), 0);
+        }
+        for (flags, &other) in self.flags.iter_mut().zip(buf) {
+            *flags |= other;
+        }
+    }
+
+    /// Writes the log for a ROM of `rom_len` bytes; see the module documentation for the format.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if writing to `w` fails.
+    pub fn write(&self, mut w: impl Write, rom_len: usize) -> io::Result<()> {
+        let len = self.flags.len().min(rom_len);
+        w.write_all(&self.flags[..len])?;
+        w.write_all(&vec![0; rom_len - len])
+    }
+
+    pub(crate) fn set_fetching(&mut self, fetching: bool) {
+        self.fetching = fetching;
+    }
+
+    pub(crate) fn set_dma(&mut self, dma: bool) {
+        self.dma = dma;
+    }
+
+    /// Records the execution of an instruction at the ROM `offset`, if within the ROM.
+    pub(crate) fn record_execute(&mut self, offset: u32, thumb: bool, rom_len: usize) {
+        let (len, flag) = if thumb { (2, THUMB) } else { (4, ARM) };
+        for i in 0..len {
+            self.record(offset + i, flag, rom_len);
+        }
+    }
+
+    /// Records a read of the ROM byte at `offset`, if within the ROM.
+    pub(crate) fn record_read(&mut self, offset: u32, rom_len: usize) {
+        if self.dma {
+            self.record(offset, DMA, rom_len);
+        } else if !self.fetching {
+            self.record(offset, DATA, rom_len);
+        }
+    }
+
+    fn record(&mut self, offset: u32, flag: u8, rom_len: usize) {
+        let offset = usize::try_from(offset).unwrap();
+        if offset >= rom_len {
+            return;
+        }
+        if self.flags.len() < rom_len {
+            self.flags.resize(rom_len, 0);
+        }
+        self.flags[offset] |= flag;
+    }
+}
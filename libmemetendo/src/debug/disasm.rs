@@ -0,0 +1,599 @@
+//! Disassembler for the ARM and Thumb instruction sets, naming the targets of branches and
+//! PC-relative loads after the loaded symbols.
+
+use std::fmt::Write;
+
+use bitmatch::bitmatch;
+use intbits::Bits;
+
+use crate::{arbitrary_sign_extend, gba::Gba};
+
+use super::symbols::Symbols;
+
+/// A disassembled instruction.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Instr {
+    pub addr: u32,
+    /// Encoding of the instruction. For a Thumb long branch with link, the second half is in the
+    /// high half-word.
+    pub raw: u32,
+    /// Size of the instruction in bytes. This is 4 for both halves of a Thumb long branch with link
+    /// disassembled together.
+    pub len: u32,
+    pub text: String,
+}
+
+const CONDITIONS: [&str; 16] = [
+    "eq", "ne", "cs", "cc", "mi", "pl", "vs", "vc", "hi", "ls", "ge", "lt", "gt", "le", "", "nv",
+];
+
+const SHIFTS: [&str; 4] = ["lsl", "lsr", "asr", "ror"];
+
+fn reg_name(idx: u32) -> &'static str {
+    const NAMES: [&str; 16] = [
+        "r0", "r1", "r2", "r3", "r4", "r5", "r6", "r7", "r8", "r9", "r10", "r11", "r12", "sp",
+        "lr", "pc",
+    ];
+
+    NAMES[usize::try_from(idx.bits(..4)).unwrap()]
+}
+
+fn r(instr: u32, pos: u8) -> &'static str {
+    reg_name(instr.bits(pos..pos + 4))
+}
+
+fn low_r(instr: u16, pos: u8) -> &'static str {
+    reg_name(instr.bits(pos..pos + 3).into())
+}
+
+fn reg_list(list: u32) -> String {
+    let names: Vec<_> = (0..16).filter(|&i| list.bit(i)).map(reg_name).collect();
+
+    format!("{{{}}}", names.join(", "))
+}
+
+/// Formats an immediate offset, which is subtracted rather than added if `up` is false.
+fn offset(up: bool, value: u32) -> String {
+    let sign = if up { "" } else { "-" };
+    format!("#{sign}{value:#x}")
+}
+
+/// Formats a PC-relative `target` address as a comment, described by `symbols`.
+fn target_comment(symbols: &Symbols, target: u32) -> String {
+    format!(" ; {}", symbols.describe(target))
+}
+
+/// Disassembles the ARM instruction `instr`, located at `addr`.
+#[must_use]
+pub fn disassemble_arm(addr: u32, instr: u32, symbols: &Symbols) -> Instr {
+    Instr {
+        addr,
+        raw: instr,
+        len: 4,
+        text: arm_text(addr, instr, symbols),
+    }
+}
+
+#[bitmatch]
+fn arm_text(addr: u32, instr: u32, symbols: &Symbols) -> String {
+    let cond = CONDITIONS[usize::try_from(instr.bits(28..)).unwrap()];
+    // Adjust for pipelining, which has the PC two instructions ahead.
+    let pc = addr.wrapping_add(8);
+
+    #[bitmatch]
+    match instr.bits(..28) {
+        "0001_0010_1111_1111_1111_????_????" => format!("bx{cond} {}", r(instr, 0)),
+        "0001_0?00_????_????_0000_1001_????" => {
+            let b = if instr.bit(22) { "b" } else { "" };
+            let (rn, rd, rm) = (r(instr, 16), r(instr, 12), r(instr, 0));
+            format!("swp{cond}{b} {rd}, {rm}, [{rn}]")
+        }
+        "0000_????_????_????_????_1001_????" => arm_multiply(instr, cond),
+        "000?_????_????_????_????_1??1_????" => {
+            arm_hword_and_signed_transfer(instr, cond, pc, symbols)
+        }
+        "00?1_0??0_????_????_????_????_????" => arm_psr_transfer(instr, cond),
+        "1111_????_????_????_????_????_????" => format!("swi{cond} #{:#x}", instr.bits(..24)),
+        "011?_????_????_????_????_???1_????" => "undefined".to_string(),
+        "100?_????_????_????_????_????_????" => {
+            let op = if instr.bit(20) { "ldm" } else { "stm" };
+            let mode = match (instr.bit(24), instr.bit(23)) {
+                (false, false) => "da",
+                (false, true) => "ia",
+                (true, false) => "db",
+                (true, true) => "ib",
+            };
+            let writeback = if instr.bit(21) { "!" } else { "" };
+            let user = if instr.bit(22) { "^" } else { "" };
+            let rn = r(instr, 16);
+            let list = reg_list(instr.bits(..16));
+            format!("{op}{cond}{mode} {rn}{writeback}, {list}{user}")
+        }
+        "101?_????_????_????_????_????_????" => {
+            let l = if instr.bit(24) { "l" } else { "" };
+            let addr_offset = 4 * arbitrary_sign_extend!(i32, instr.bits(..24), 24);
+            let target = pc.wrapping_add_signed(addr_offset);
+            format!("b{l}{cond} {}", symbols.describe(target))
+        }
+        "00??_????_????_????_????_????_????" => arm_data_processing(instr, cond, pc, symbols),
+        "01??_????_????_????_????_????_????" => arm_single_transfer(instr, cond, pc, symbols),
+        _ => "undefined".to_string(),
+    }
+}
+
+fn arm_multiply(instr: u32, cond: &str) -> String {
+    let s = if instr.bit(20) { "s" } else { "" };
+    let (rd, rn, rs, rm) = (r(instr, 16), r(instr, 12), r(instr, 8), r(instr, 0));
+    match instr.bits(21..24) {
+        0b000 => format!("mul{cond}{s} {rd}, {rm}, {rs}"),
+        0b001 => format!("mla{cond}{s} {rd}, {rm}, {rs}, {rn}"),
+        op @ 0b100..=0b111 => {
+            let op = ["umull", "umlal", "smull", "smlal"][usize::try_from(op - 0b100).unwrap()];
+            format!("{op}{cond}{s} {rn}, {rd}, {rm}, {rs}")
+        }
+        _ => "undefined".to_string(),
+    }
+}
+
+fn arm_hword_and_signed_transfer(instr: u32, cond: &str, pc: u32, symbols: &Symbols) -> String {
+    let load = instr.bit(20);
+    let op = match (load, instr.bits(5..7)) {
+        (false, 0b01) => "strh",
+        (true, 0b01) => "ldrh",
+        (true, 0b10) => "ldrsb",
+        (true, 0b11) => "ldrsh",
+        _ => return "undefined".to_string(),
+    };
+
+    let (rn, rd) = (r(instr, 16), r(instr, 12));
+    let (pre_index, up, writeback) = (instr.bit(24), instr.bit(23), instr.bit(21));
+    let (offset_text, imm_offset) = if instr.bit(22) {
+        let value = (instr.bits(8..12) << 4) | instr.bits(..4);
+        (offset(up, value), Some(value))
+    } else {
+        let sign = if up { "" } else { "-" };
+        (format!("{sign}{}", r(instr, 0)), None)
+    };
+
+    let mut text = if pre_index {
+        let writeback = if writeback { "!" } else { "" };
+        format!("{op}{cond} {rd}, [{rn}, {offset_text}]{writeback}")
+    } else {
+        format!("{op}{cond} {rd}, [{rn}], {offset_text}")
+    };
+    if let (true, Some(value), 15) = (pre_index, imm_offset, instr.bits(16..20)) {
+        let target = if up {
+            pc.wrapping_add(value)
+        } else {
+            pc.wrapping_sub(value)
+        };
+        text += &target_comment(symbols, target);
+    }
+
+    text
+}
+
+fn psr_name(instr: u32) -> &'static str {
+    if instr.bit(22) {
+        "spsr"
+    } else {
+        "cpsr"
+    }
+}
+
+fn arm_psr_transfer(instr: u32, cond: &str) -> String {
+    let psr = psr_name(instr);
+    if !instr.bit(21) {
+        // MRS{cond} Rd,Psr
+        return format!("mrs{cond} {}, {psr}", r(instr, 12));
+    }
+
+    let mut fields = String::new();
+    for (bit, name) in [(19, 'f'), (18, 's'), (17, 'x'), (16, 'c')] {
+        if instr.bit(bit) {
+            fields.push(name);
+        }
+    }
+    let operand = if instr.bit(25) {
+        let value = instr.bits(..8).rotate_right(2 * instr.bits(8..12));
+        format!("#{value:#x}")
+    } else {
+        r(instr, 0).to_string()
+    };
+
+    // MSR{cond} Psr{_field},Op
+    format!("msr{cond} {psr}_{fields}, {operand}")
+}
+
+/// Formats the second operand of a data processing instruction, with its immediate value if it
+/// has one.
+fn arm_operand2(instr: u32) -> (String, Option<u32>) {
+    if instr.bit(25) {
+        let value = instr.bits(..8).rotate_right(2 * instr.bits(8..12));
+        return (format!("#{value:#x}"), Some(value));
+    }
+
+    (arm_shifted_reg(instr), None)
+}
+
+/// Formats the shifted register operand in the low 12 bits of `instr`.
+fn arm_shifted_reg(instr: u32) -> String {
+    let rm = r(instr, 0);
+    let shift_type = instr.bits(5..7);
+    let shift = SHIFTS[usize::try_from(shift_type).unwrap()];
+    if instr.bit(4) {
+        return format!("{rm}, {shift} {}", r(instr, 8));
+    }
+
+    match (shift_type, instr.bits(7..12)) {
+        (0, 0) => rm.to_string(),
+        // LSR #0 and ASR #0 encode shifts by 32, while ROR #0 encodes RRX.
+        (1 | 2, 0) => format!("{rm}, {shift} #32"),
+        (3, 0) => format!("{rm}, rrx"),
+        (_, amount) => format!("{rm}, {shift} #{amount}"),
+    }
+}
+
+fn arm_data_processing(instr: u32, cond: &str, pc: u32, symbols: &Symbols) -> String {
+    const OPS: [&str; 16] = [
+        "and", "eor", "sub", "rsb", "add", "adc", "sbc", "rsc", "tst", "teq", "cmp", "cmn", "orr",
+        "mov", "bic", "mvn",
+    ];
+
+    let op_idx = instr.bits(21..25);
+    let op = OPS[usize::try_from(op_idx).unwrap()];
+    let s = if instr.bit(20) { "s" } else { "" };
+    let (rn, rd) = (r(instr, 16), r(instr, 12));
+    let (operand2, imm) = arm_operand2(instr);
+
+    match op_idx {
+        // TST, TEQ, CMP and CMN always set the condition flags.
+        8..=11 => format!("{op}{cond} {rn}, {operand2}"),
+        13 | 15 => format!("{op}{cond}{s} {rd}, {operand2}"),
+        _ => {
+            let mut text = format!("{op}{cond}{s} {rd}, {rn}, {operand2}");
+            // ADD/SUB Rd,PC,#imm computes a PC-relative address.
+            if let (2 | 4, 15, Some(value)) = (op_idx, instr.bits(16..20), imm) {
+                let target = if op_idx == 4 {
+                    pc.wrapping_add(value)
+                } else {
+                    pc.wrapping_sub(value)
+                };
+                text += &target_comment(symbols, target);
+            }
+
+            text
+        }
+    }
+}
+
+fn arm_single_transfer(instr: u32, cond: &str, pc: u32, symbols: &Symbols) -> String {
+    let op = if instr.bit(20) { "ldr" } else { "str" };
+    let b = if instr.bit(22) { "b" } else { "" };
+    let (pre_index, up, writeback) = (instr.bit(24), instr.bit(23), instr.bit(21));
+    // Post-indexed transfers with the W bit set force user mode access.
+    let t = if !pre_index && writeback { "t" } else { "" };
+    let (rn, rd) = (r(instr, 16), r(instr, 12));
+
+    let imm_offset = (!instr.bit(25)).then(|| instr.bits(..12));
+    let offset_text = if let Some(value) = imm_offset {
+        offset(up, value)
+    } else {
+        let sign = if up { "" } else { "-" };
+        format!("{sign}{}", arm_shifted_reg(instr))
+    };
+
+    let mut text = if pre_index {
+        let writeback = if writeback { "!" } else { "" };
+        format!("{op}{cond}{b} {rd}, [{rn}, {offset_text}]{writeback}")
+    } else {
+        format!("{op}{cond}{b}{t} {rd}, [{rn}], {offset_text}")
+    };
+    if let (true, Some(value), 15) = (pre_index, imm_offset, instr.bits(16..20)) {
+        let target = if up {
+            pc.wrapping_add(value)
+        } else {
+            pc.wrapping_sub(value)
+        };
+        text += &target_comment(symbols, target);
+    }
+
+    text
+}
+
+/// Disassembles the Thumb instruction in the low half-word of `instrs`, located at `addr`. The high
+/// half-word is the instruction after it, which is disassembled together with the first if both
+/// are halves of a long branch with link.
+#[must_use]
+#[expect(clippy::missing_panics_doc)] // each half-word fits a u16
+pub fn disassemble_thumb(addr: u32, instrs: u32, symbols: &Symbols) -> Instr {
+    let instr: u16 = instrs.bits(..16).try_into().unwrap();
+    let next: u16 = instrs.bits(16..).try_into().unwrap();
+    if instr.bits(11..) == 0b11110 && next.bits(11..) == 0b11111 {
+        let hi_offset = arbitrary_sign_extend!(i32, instr.bits(..11), 11) << 12;
+        let lo_offset = i32::from(next.bits(..11)) << 1;
+        let target = addr
+            .wrapping_add(4)
+            .wrapping_add_signed(hi_offset + lo_offset);
+
+        return Instr {
+            addr,
+            raw: instrs,
+            len: 4,
+            text: format!("bl {}", symbols.describe(target)),
+        };
+    }
+
+    Instr {
+        addr,
+        raw: instr.into(),
+        len: 2,
+        text: thumb_text(addr, instr, symbols),
+    }
+}
+
+#[bitmatch]
+fn thumb_text(addr: u32, instr: u16, symbols: &Symbols) -> String {
+    // Adjust for pipelining, which has the PC two instructions ahead.
+    let pc = addr.wrapping_add(4);
+    let (rd, rs) = (low_r(instr, 0), low_r(instr, 3));
+    let imm8 = u32::from(instr.bits(..8));
+
+    #[bitmatch]
+    match instr.bits(8..) {
+        "1011_0000" => {
+            let sign = if instr.bit(7) { "-" } else { "" };
+            format!("add sp, #{sign}{:#x}", 4 * instr.bits(..7))
+        }
+        "1101_1111" => format!("swi #{imm8:#x}"),
+        "1101_1110" => "undefined".to_string(),
+        "0100_00??" => thumb_alu(instr),
+        "0100_01??" => thumb_hi_reg(instr),
+        "0001_1???" => {
+            let op = if instr.bit(9) { "sub" } else { "add" };
+            let operand = if instr.bit(10) {
+                format!("#{:#x}", instr.bits(6..9))
+            } else {
+                low_r(instr, 6).to_string()
+            };
+            format!("{op} {rd}, {rs}, {operand}")
+        }
+        "0100_1???" => {
+            let target = (pc & !2).wrapping_add(4 * imm8);
+            let rd = low_r(instr, 8);
+            format!("ldr {rd}, [pc, #{:#x}]", 4 * imm8) + &target_comment(symbols, target)
+        }
+        "1110_0???" => {
+            let addr_offset = 2 * arbitrary_sign_extend!(i32, instr.bits(..11), 11);
+            format!(
+                "b {}",
+                symbols.describe(pc.wrapping_add_signed(addr_offset))
+            )
+        }
+        "0101_????" => thumb_reg_offset_transfer(instr),
+        "1000_????" => {
+            let op = if instr.bit(11) { "ldrh" } else { "strh" };
+            format!("{op} {rd}, [{rs}, #{:#x}]", 2 * instr.bits(6..11))
+        }
+        "1001_????" => {
+            let op = if instr.bit(11) { "ldr" } else { "str" };
+            format!("{op} {}, [sp, #{:#x}]", low_r(instr, 8), 4 * imm8)
+        }
+        "1010_????" => {
+            let rd = low_r(instr, 8);
+            if instr.bit(11) {
+                format!("add {rd}, sp, #{:#x}", 4 * imm8)
+            } else {
+                let target = (pc & !2).wrapping_add(4 * imm8);
+                format!("add {rd}, pc, #{:#x}", 4 * imm8) + &target_comment(symbols, target)
+            }
+        }
+        "1011_?10?" => {
+            let (op, extra_reg) = if instr.bit(11) {
+                ("pop", 15)
+            } else {
+                ("push", 14)
+            };
+            let mut list = u32::from(instr.bits(..8));
+            if instr.bit(8) {
+                list.set_bit(extra_reg, true);
+            }
+            format!("{op} {}", reg_list(list))
+        }
+        "1100_????" => {
+            let op = if instr.bit(11) { "ldmia" } else { "stmia" };
+            let rb = low_r(instr, 8);
+            format!("{op} {rb}!, {}", reg_list(imm8))
+        }
+        "1101_????" => {
+            let cond = CONDITIONS[usize::from(instr.bits(8..12))];
+            let addr_offset = 2 * arbitrary_sign_extend!(i32, imm8, 8);
+            format!(
+                "b{cond} {}",
+                symbols.describe(pc.wrapping_add_signed(addr_offset))
+            )
+        }
+        "1111_????" => {
+            // A half of a long branch with link, without the other half after it.
+            let half = if instr.bit(11) { "lo" } else { "hi" };
+            format!("bl.{half} #{:#x}", instr.bits(..11))
+        }
+        "000?_????" => {
+            let op = SHIFTS[usize::from(instr.bits(11..13))];
+            let mut amount = instr.bits(6..11);
+            // LSR #0 and ASR #0 encode shifts by 32.
+            if amount == 0 && op != "lsl" {
+                amount = 32;
+            }
+            format!("{op} {rd}, {rs}, #{amount}")
+        }
+        "001?_????" => {
+            let op = ["mov", "cmp", "add", "sub"][usize::from(instr.bits(11..13))];
+            format!("{op} {}, #{imm8:#x}", low_r(instr, 8))
+        }
+        "011?_????" => {
+            let op = ["str", "ldr", "strb", "ldrb"][usize::from(instr.bits(11..13))];
+            let scale = if instr.bit(12) { 1 } else { 4 };
+            format!("{op} {rd}, [{rs}, #{:#x}]", scale * instr.bits(6..11))
+        }
+        _ => "undefined".to_string(),
+    }
+}
+
+fn thumb_alu(instr: u16) -> String {
+    const OPS: [&str; 16] = [
+        "and", "eor", "lsl", "lsr", "asr", "adc", "sbc", "ror", "tst", "neg", "cmp", "cmn", "orr",
+        "mul", "bic", "mvn",
+    ];
+
+    let (rd, rs) = (low_r(instr, 0), low_r(instr, 3));
+    format!("{} {rd}, {rs}", OPS[usize::from(instr.bits(6..10))])
+}
+
+/// Operations on high registers, and branch exchange.
+fn thumb_hi_reg(instr: u16) -> String {
+    let rd = reg_name((instr.bits(..3) | (instr.bits(7..8) << 3)).into());
+    let rs = reg_name(instr.bits(3..7).into());
+    match instr.bits(8..10) {
+        0 => format!("add {rd}, {rs}"),
+        1 => format!("cmp {rd}, {rs}"),
+        2 => format!("mov {rd}, {rs}"),
+        _ => format!("bx {rs}"),
+    }
+}
+
+/// Transfers with register offsets.
+fn thumb_reg_offset_transfer(instr: u16) -> String {
+    let (rd, rb) = (low_r(instr, 0), low_r(instr, 3));
+    let ro = low_r(instr, 6);
+    let op = if instr.bit(9) {
+        ["strh", "ldsb", "ldrh", "ldsh"][usize::from(instr.bits(10..12))]
+    } else {
+        ["str", "strb", "ldr", "ldrb"][usize::from(instr.bits(10..12))]
+    };
+    format!("{op} {rd}, [{rb}, {ro}]")
+}
+
+/// Disassembles the instruction at `addr` in the memory of `gba`, as Thumb if `thumb` is set, or
+/// ARM otherwise. Memory is read without triggering debug hooks.
+#[must_use]
+pub fn disassemble(gba: &mut Gba, addr: u32, thumb: bool) -> Instr {
+    let mut buf = [0; 4];
+    gba.peek_mem(addr, &mut buf);
+    let instrs = u32::from_le_bytes(buf);
+    if thumb {
+        disassemble_thumb(addr, instrs, &gba.debug.symbols)
+    } else {
+        disassemble_arm(addr, instrs, &gba.debug.symbols)
+    }
+}
+
+/// Formats `instr` on its own line, preceded by a label line if a symbol starts at its address.
+#[must_use]
+#[expect(clippy::missing_panics_doc)] // writing to a String can't fail
+pub fn listing_line(instr: &Instr, symbols: &Symbols) -> String {
+    let mut line = String::new();
+    if let Some(symbol) = symbols.lookup(instr.addr).filter(|s| s.offset == 0) {
+        writeln!(line, "{}:", symbol.symbol.name).unwrap();
+    }
+    let raw_width = 2 * usize::try_from(instr.len).unwrap();
+    write!(
+        line,
+        "  {:#010x}: {:0raw_width$x}  {}",
+        instr.addr, instr.raw, instr.text
+    )
+    .unwrap();
+
+    line
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::debug::symbols::Symbol;
+
+    fn symbols() -> Symbols {
+        let mut symbols = Symbols::new();
+        symbols.insert(Symbol {
+            name: "main".to_owned(),
+            addr: 0x0800_0100,
+            size: None,
+        });
+        symbols
+    }
+
+    fn arm(addr: u32, instr: u32) -> String {
+        disassemble_arm(addr, instr, &symbols()).text
+    }
+
+    fn thumb(addr: u32, instrs: u32) -> String {
+        disassemble_thumb(addr, instrs, &symbols()).text
+    }
+
+    #[test]
+    fn disassembles_arm() {
+        assert_eq!(arm(0x0800_0000, 0xe3a0_3403), "mov r3, #0x3000000");
+        assert_eq!(arm(0x0800_0000, 0xe280_0001), "add r0, r0, #0x1");
+        assert_eq!(arm(0x0800_0000, 0x1090_1182), "addnes r1, r0, r2, lsl #3");
+        assert_eq!(arm(0x0800_0000, 0xe783_0101), "str r0, [r3, r1, lsl #2]");
+        assert_eq!(arm(0x0800_0000, 0xe1c2_00b0), "strh r0, [r2, #0x0]");
+        assert_eq!(arm(0x0800_0000, 0xe12f_ff1e), "bx lr");
+        assert_eq!(arm(0x0800_0000, 0xe92d_4010), "stmdb sp!, {r4, lr}");
+        assert_eq!(arm(0x0800_0000, 0xe10f_0000), "mrs r0, cpsr");
+        assert_eq!(arm(0x0800_0000, 0xe129_f000), "msr cpsr_fc, r0");
+        assert_eq!(arm(0x0800_0000, 0xe002_0391), "mul r2, r1, r3");
+        assert_eq!(arm(0x0800_0000, 0xef00_0005), "swi #0x5");
+        assert_eq!(arm(0x0800_0000, 0xe600_0010), "undefined");
+    }
+
+    #[test]
+    fn names_arm_targets_after_symbols() {
+        // Branches are relative to the PC, 8 bytes ahead.
+        assert_eq!(arm(0x0800_00f8, 0xeb00_0000), "bl 0x08000100 (main)");
+        assert_eq!(arm(0x0800_0110, 0xeaff_fffa), "b 0x08000100 (main)");
+        assert_eq!(
+            arm(0x0800_00f0, 0xe59f_0008),
+            "ldr r0, [pc, #0x8] ; 0x08000100 (main)"
+        );
+        assert_eq!(arm(0x0800_0000, 0xea00_0000), "b 0x08000008");
+    }
+
+    #[test]
+    fn disassembles_thumb() {
+        assert_eq!(thumb(0x0800_0000, 0x2001), "mov r0, #0x1");
+        assert_eq!(thumb(0x0800_0000, 0x1889), "add r1, r1, r2");
+        assert_eq!(thumb(0x0800_0000, 0x0848), "lsr r0, r1, #1");
+        assert_eq!(thumb(0x0800_0000, 0x4770), "bx lr");
+        assert_eq!(thumb(0x0800_0000, 0xb510), "push {r4, lr}");
+        assert_eq!(thumb(0x0800_0000, 0xbd10), "pop {r4, pc}");
+        assert_eq!(thumb(0x0800_0000, 0x8848), "ldrh r0, [r1, #0x2]");
+        assert_eq!(thumb(0x0800_0000, 0x5688), "ldsb r0, [r1, r2]");
+        assert_eq!(thumb(0x0800_0000, 0x5a88), "ldrh r0, [r1, r2]");
+        assert_eq!(thumb(0x0800_0000, 0xdf05), "swi #0x5");
+        assert_eq!(thumb(0x0800_0000, 0xde00), "undefined");
+    }
+
+    #[test]
+    fn names_thumb_targets_after_symbols() {
+        assert_eq!(thumb(0x0800_00fc, 0xe000), "b 0x08000100 (main)");
+        assert_eq!(thumb(0x0800_0104, 0xd0fc), "beq 0x08000100 (main)");
+        // PC-relative loads are from the word-aligned PC.
+        assert_eq!(
+            thumb(0x0800_00f6, 0x4802),
+            "ldr r0, [pc, #0x8] ; 0x08000100 (main)"
+        );
+
+        let bl = disassemble_thumb(0x0800_00f0, 0xf80a_f000, &symbols());
+        assert_eq!((bl.len, bl.text.as_str()), (4, "bl 0x08000108 (main+0x8)"));
+        assert_eq!(thumb(0x0800_00f0, 0x2000_f000), "bl.hi #0x0");
+    }
+
+    #[test]
+    fn lists_labels() {
+        let instr = disassemble_thumb(0x0800_0100, 0x2001, &symbols());
+        assert_eq!(
+            listing_line(&instr, &symbols()),
+            "main:\n  0x08000100: 2001  mov r0, #0x1"
+        );
+    }
+}
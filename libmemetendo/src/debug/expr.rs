@@ -0,0 +1,418 @@
+//! A tiny expression language for conditional breakpoints and watches, such as
+//! `r0 == 0xcafe && [0x03001234]:u16 != 0`.
+//!
+//! Values are unsigned 32-bit integers, and arithmetic wraps. Comparisons and logical operators
+//! evaluate to 1 if true and 0 if false; any non-zero value is true. Operands may be:
+//!
+//! - Decimal or "0x"-prefixed hexadecimal numbers.
+//! - Registers: `r0` to `r15`, `sp`, `lr`, `pc`, `cpsr` and `spsr`. `pc` (and `r15`) is the
+//!   address of the next instruction to execute, rather than the pipelined value of r15.
+//! - Symbol names, which evaluate to the symbol's address, and IO register names (e.g:
+//!   `DISPCNT`; see `debug::io::REGISTERS`), which evaluate to the register's address.
+//! - Memory reads: `[ADDR]` reads a word, and `[ADDR]:u8`, `[ADDR]:u16` or `[ADDR]:u32` read a
+//!   value of that width. Reads are unaligned and don't trigger debug hooks.
+//!
+//! Operators, from highest to lowest precedence, are unary `!`, `~` and `-`, then `*`, `+ -`,
+//! `<< >>`, `< <= > >=`, `== !=`, `&`, `^`, `|`, `&&` and `||`. Parentheses may be used for
+//! grouping.
+
+use std::{
+    error::Error,
+    fmt::{self, Display, Formatter},
+};
+
+use crate::{cheat::Width, gba::Gba};
+
+use super::{io, symbols::Symbols};
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct InvalidExpr(&'static str);
+
+impl Display for InvalidExpr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid expression: {}", self.0)
+    }
+}
+
+impl Error for InvalidExpr {}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum UnaryOp {
+    Not,
+    BitNot,
+    Neg,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum BinaryOp {
+    Or,
+    And,
+    BitOr,
+    BitXor,
+    BitAnd,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Shl,
+    Shr,
+    Add,
+    Sub,
+    Mul,
+}
+
+/// Binary operators grouped by precedence, from lowest to highest.
+const BINARY_OPS: [&[(&str, BinaryOp)]; 10] = [
+    &[("||", BinaryOp::Or)],
+    &[("&&", BinaryOp::And)],
+    &[("|", BinaryOp::BitOr)],
+    &[("^", BinaryOp::BitXor)],
+    &[("&", BinaryOp::BitAnd)],
+    &[("==", BinaryOp::Eq), ("!=", BinaryOp::Ne)],
+    &[
+        ("<", BinaryOp::Lt),
+        ("<=", BinaryOp::Le),
+        (">", BinaryOp::Gt),
+        (">=", BinaryOp::Ge),
+    ],
+    &[("<<", BinaryOp::Shl), (">>", BinaryOp::Shr)],
+    &[("+", BinaryOp::Add), ("-", BinaryOp::Sub)],
+    &[("*", BinaryOp::Mul)],
+];
+
+/// Punctuation recognized by the tokenizer, longest first so that e.g: `<<` isn't read as `<`.
+const PUNCTUATION: [&str; 23] = [
+    "||", "&&", "==", "!=", "<=", ">=", "<<", ">>", "|", "^", "&", "<", ">", "+", "-", "*", "!",
+    "~", "(", ")", "[", "]", ":",
+];
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+enum Node {
+    Const(u32),
+    /// Register index; 15 is the address of the next instruction.
+    Reg(usize),
+    Cpsr,
+    Spsr,
+    Mem(Box<Node>, Width),
+    Unary(UnaryOp, Box<Node>),
+    Binary(BinaryOp, Box<Node>, Box<Node>),
+}
+
+/// A parsed expression; see the module documentation for the syntax.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Expr(Node);
+
+impl Expr {
+    /// Parses `s`, resolving symbol names to addresses using `symbols`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `s` isn't a valid expression, or names an unknown symbol.
+    pub fn parse(s: &str, symbols: &Symbols) -> Result<Self, InvalidExpr> {
+        let tokens = tokenize(s)?;
+        let mut parser = Parser {
+            tokens: &tokens,
+            symbols,
+        };
+        let node = parser.parse_binary(0)?;
+        if !parser.tokens.is_empty() {
+            return Err(InvalidExpr("unexpected trailing input"));
+        }
+
+        Ok(Self(node))
+    }
+
+    /// Evaluates the expression against the current state of `gba`.
+    #[must_use]
+    pub fn eval(&self, gba: &mut Gba) -> u32 {
+        eval(&self.0, gba)
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+enum Token<'a> {
+    Num(u32),
+    Ident(&'a str),
+    Punct(&'static str),
+}
+
+fn tokenize(s: &str) -> Result<Vec<Token<'_>>, InvalidExpr> {
+    let is_ident_char = |c: char| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '$');
+
+    let mut tokens = Vec::new();
+    let mut rest = s.trim_start();
+    while !rest.is_empty() {
+        let (token, len) = if let Some(&punct) = PUNCTUATION.iter().find(|&&p| rest.starts_with(p))
+        {
+            (Token::Punct(punct), punct.len())
+        } else {
+            let len = rest.find(|c| !is_ident_char(c)).unwrap_or(rest.len());
+            let word = &rest[..len];
+            if word.is_empty() {
+                return Err(InvalidExpr("unexpected character"));
+            }
+
+            let token = if word.starts_with(|c: char| c.is_ascii_digit()) {
+                let value = if let Some(hex) = word.strip_prefix("0x") {
+                    u32::from_str_radix(hex, 16)
+                } else {
+                    word.parse()
+                };
+                Token::Num(value.map_err(|_| InvalidExpr("invalid number"))?)
+            } else {
+                Token::Ident(word)
+            };
+            (token, len)
+        };
+
+        tokens.push(token);
+        rest = rest[len..].trim_start();
+    }
+
+    Ok(tokens)
+}
+
+struct Parser<'a, 'b> {
+    tokens: &'a [Token<'b>],
+    symbols: &'a Symbols,
+}
+
+impl<'a, 'b> Parser<'a, 'b> {
+    fn next(&mut self) -> Option<&'a Token<'b>> {
+        let (token, rest) = self.tokens.split_first()?;
+        self.tokens = rest;
+        Some(token)
+    }
+
+    fn eat(&mut self, punct: &'static str) -> bool {
+        if self.tokens.first() == Some(&Token::Punct(punct)) {
+            self.tokens = &self.tokens[1..];
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, punct: &'static str, error: &'static str) -> Result<(), InvalidExpr> {
+        if self.eat(punct) {
+            Ok(())
+        } else {
+            Err(InvalidExpr(error))
+        }
+    }
+
+    fn parse_binary(&mut self, level: usize) -> Result<Node, InvalidExpr> {
+        let Some(ops) = BINARY_OPS.get(level) else {
+            return self.parse_unary();
+        };
+
+        let mut lhs = self.parse_binary(level + 1)?;
+        'outer: loop {
+            for &(punct, op) in *ops {
+                if self.eat(punct) {
+                    let rhs = self.parse_binary(level + 1)?;
+                    lhs = Node::Binary(op, Box::new(lhs), Box::new(rhs));
+                    continue 'outer;
+                }
+            }
+
+            return Ok(lhs);
+        }
+    }
+
+    fn parse_unary(&mut self) -> Result<Node, InvalidExpr> {
+        for (punct, op) in [
+            ("!", UnaryOp::Not),
+            ("~", UnaryOp::BitNot),
+            ("-", UnaryOp::Neg),
+        ] {
+            if self.eat(punct) {
+                return Ok(Node::Unary(op, Box::new(self.parse_unary()?)));
+            }
+        }
+
+        self.parse_operand()
+    }
+
+    fn parse_operand(&mut self) -> Result<Node, InvalidExpr> {
+        match self.next() {
+            Some(&Token::Num(value)) => Ok(Node::Const(value)),
+            Some(&Token::Ident(name)) => parse_ident(name, self.symbols),
+            Some(Token::Punct("(")) => {
+                let node = self.parse_binary(0)?;
+                self.expect(")", "expected \")\"")?;
+                Ok(node)
+            }
+            Some(Token::Punct("[")) => {
+                let addr = self.parse_binary(0)?;
+                self.expect("]", "expected \"]\"")?;
+                let width = if self.eat(":") {
+                    match self.next() {
+                        Some(Token::Ident("u8")) => Width::Byte,
+                        Some(Token::Ident("u16")) => Width::Hword,
+                        Some(Token::Ident("u32")) => Width::Word,
+                        _ => return Err(InvalidExpr("expected \"u8\", \"u16\" or \"u32\"")),
+                    }
+                } else {
+                    Width::Word
+                };
+                Ok(Node::Mem(Box::new(addr), width))
+            }
+            Some(Token::Punct(_)) => Err(InvalidExpr("unexpected operator")),
+            None => Err(InvalidExpr("unexpected end of input")),
+        }
+    }
+}
+
+fn parse_ident(name: &str, symbols: &Symbols) -> Result<Node, InvalidExpr> {
+    let reg = match name.to_ascii_lowercase().as_str() {
+        "sp" => Some(13),
+        "lr" => Some(14),
+        "pc" => Some(15),
+        "cpsr" => return Ok(Node::Cpsr),
+        "spsr" => return Ok(Node::Spsr),
+        lower => lower
+            .strip_prefix('r')
+            .and_then(|idx| idx.parse().ok())
+            .filter(|&idx| idx < 16),
+    };
+    if let Some(idx) = reg {
+        return Ok(Node::Reg(idx));
+    }
+
+    symbols
+        .find_by_name(name)
+        .map(|symbol| symbol.addr)
+        .or_else(|| io::Register::find_by_name(name).map(io::Register::addr))
+        .map(Node::Const)
+        .ok_or(InvalidExpr("unknown register or symbol"))
+}
+
+fn eval(node: &Node, gba: &mut Gba) -> u32 {
+    match node {
+        &Node::Const(value) => value,
+        &Node::Reg(15) => gba.cpu.next_instr_addr(),
+        &Node::Reg(idx) => gba.cpu.reg.r[idx],
+        Node::Cpsr => gba.cpu.reg.cpsr().bits(),
+        Node::Spsr => gba.cpu.reg.spsr(),
+        Node::Mem(addr, width) => {
+            let addr = eval(addr, gba);
+            let mut buf = [0; 4];
+            let len = usize::try_from(width.bytes()).unwrap();
+            gba.peek_mem(addr, &mut buf[..len]);
+            u32::from_le_bytes(buf)
+        }
+        Node::Unary(op, operand) => {
+            let value = eval(operand, gba);
+            match op {
+                UnaryOp::Not => u32::from(value == 0),
+                UnaryOp::BitNot => !value,
+                UnaryOp::Neg => value.wrapping_neg(),
+            }
+        }
+        Node::Binary(BinaryOp::Or, lhs, rhs) => {
+            u32::from(eval(lhs, gba) != 0 || eval(rhs, gba) != 0)
+        }
+        Node::Binary(BinaryOp::And, lhs, rhs) => {
+            u32::from(eval(lhs, gba) != 0 && eval(rhs, gba) != 0)
+        }
+        Node::Binary(op, lhs, rhs) => {
+            let (lhs, rhs) = (eval(lhs, gba), eval(rhs, gba));
+            match op {
+                BinaryOp::Or | BinaryOp::And => unreachable!(),
+                BinaryOp::BitOr => lhs | rhs,
+                BinaryOp::BitXor => lhs ^ rhs,
+                BinaryOp::BitAnd => lhs & rhs,
+                BinaryOp::Eq => u32::from(lhs == rhs),
+                BinaryOp::Ne => u32::from(lhs != rhs),
+                BinaryOp::Lt => u32::from(lhs < rhs),
+                BinaryOp::Le => u32::from(lhs <= rhs),
+                BinaryOp::Gt => u32::from(lhs > rhs),
+                BinaryOp::Ge => u32::from(lhs >= rhs),
+                BinaryOp::Shl => lhs.checked_shl(rhs).unwrap_or(0),
+                BinaryOp::Shr => lhs.checked_shr(rhs).unwrap_or(0),
+                BinaryOp::Add => lhs.wrapping_add(rhs),
+                BinaryOp::Sub => lhs.wrapping_sub(rhs),
+                BinaryOp::Mul => lhs.wrapping_mul(rhs),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use crate::{bios, cart, debug::symbols::Symbol};
+
+    use super::*;
+
+    fn new_gba() -> Gba {
+        let bios_rom = bios::Rom::new(Rc::from(vec![0; 0x4000])).unwrap();
+        let cart_rom = cart::Rom::new(Rc::from(vec![0; 0x200])).unwrap();
+
+        Gba::new(bios_rom, cart::Cartridge::from(cart_rom))
+    }
+
+    fn eval_str(gba: &mut Gba, s: &str) -> u32 {
+        Expr::parse(s, &gba.debug.symbols.clone())
+            .unwrap_or_else(|e| panic!("{s}: {e}"))
+            .eval(gba)
+    }
+
+    #[test]
+    fn operators() {
+        let mut gba = new_gba();
+        for (s, expected) in [
+            ("1 + 2 * 3", 7),
+            ("(1 + 2) * 3", 9),
+            ("0 - 1", u32::MAX),
+            ("-1 == 0xffffffff", 1),
+            ("~0 >> 28", 0xf),
+            ("1 << 32", 0),
+            ("0xf0 | 0x0f ^ 0xff & 0x3c", 0xf0 | (0x0f ^ (0xff & 0x3c))),
+            ("1 < 2 && 2 <= 2 && 3 > 2 && 2 >= 3", 0),
+            ("0 || 5", 1),
+            ("!0 + !7", 1),
+            ("1 == 1 != 0", 1),
+        ] {
+            assert_eq!(eval_str(&mut gba, s), expected, "{s}");
+        }
+    }
+
+    #[test]
+    fn operands() {
+        let mut gba = new_gba();
+        gba.cpu.reg.r[0] = 0xcafe;
+        gba.cpu.reg.r[13] = 0x0300_7f00;
+        gba.iwram[0x1234..0x1238].copy_from_slice(&[0x78, 0x56, 0x34, 0x12]);
+        gba.debug.symbols.insert(Symbol {
+            name: "counter".to_string(),
+            addr: 0x0300_1234,
+            size: None,
+        });
+
+        assert_eq!(eval_str(&mut gba, "R0"), 0xcafe);
+        assert_eq!(eval_str(&mut gba, "sp == r13"), 1);
+        assert_eq!(eval_str(&mut gba, "[0x03001234]"), 0x1234_5678);
+        assert_eq!(eval_str(&mut gba, "[0x03001235]:u16"), 0x3456);
+        assert_eq!(eval_str(&mut gba, "[counter]:u8"), 0x78);
+        assert_eq!(eval_str(&mut gba, "dispcnt + 4"), 0x0400_0004);
+        assert_eq!(
+            eval_str(&mut gba, "r0 == 0xCAFE && [0x03001234]:u16 != 0"),
+            1
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_expressions() {
+        let symbols = Symbols::new();
+        for s in [
+            "", "1 +", "(1", "[1", "[1]:u64", "r16", "unknown", "1 2", "0xg", "@",
+        ] {
+            assert!(Expr::parse(s, &symbols).is_err(), "{s}");
+        }
+    }
+}
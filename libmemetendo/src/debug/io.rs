@@ -0,0 +1,290 @@
+use std::ops::RangeInclusive;
+
+/// An IO register mapped at `0x0400_0000 + offset`, named as in GBATEK.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Register {
+    pub name: &'static str,
+    pub offset: u32,
+    pub len: u32,
+}
+
+impl Register {
+    #[must_use]
+    pub const fn addr(&self) -> u32 {
+        0x0400_0000 + self.offset
+    }
+
+    #[must_use]
+    pub const fn addr_range(&self) -> RangeInclusive<u32> {
+        self.addr()..=self.addr() + self.len - 1
+    }
+
+    /// Finds the register containing the byte at `addr`.
+    #[must_use]
+    pub fn find(addr: u32) -> Option<&'static Register> {
+        REGISTERS
+            .iter()
+            .find(|reg| reg.addr_range().contains(&addr))
+    }
+
+    /// Finds the register with the name `name`, ignoring ASCII case.
+    #[must_use]
+    pub fn find_by_name(name: &str) -> Option<&'static Register> {
+        REGISTERS
+            .iter()
+            .find(|reg| reg.name.eq_ignore_ascii_case(name))
+    }
+}
+
+/// Parses an address range from `s`, which is either a register name (e.g: `DISPCNT`), a group
+/// of registers sharing a name prefix before an underscore (e.g: `TM0CNT` for `TM0CNT_L` and
+/// `TM0CNT_H`), a hexadecimal address (e.g: `0x4000000`), or two hexadecimal addresses separated
+/// by a hyphen (e.g: `0x4000100-0x400010f`).
+#[must_use]
+pub fn parse_addr_range(s: &str) -> Option<RangeInclusive<u32>> {
+    let s = s.trim();
+    if let Some(reg) = Register::find_by_name(s) {
+        return Some(reg.addr_range());
+    }
+
+    let mut group = REGISTERS.iter().filter(|reg| {
+        reg.name
+            .split_once('_')
+            .is_some_and(|(prefix, _)| prefix.eq_ignore_ascii_case(s))
+    });
+    if let Some(first) = group.next() {
+        let last = group.next_back().unwrap_or(first);
+        return Some(first.addr()..=*last.addr_range().end());
+    }
+
+    let parse_hex = |s: &str| {
+        let s = s.trim();
+        let digits = s
+            .strip_prefix("0x")
+            .or_else(|| s.strip_prefix("0X"))
+            .unwrap_or(s);
+        // from_str_radix also accepts a leading sign, which isn't a hexadecimal digit.
+        if digits.starts_with('+') {
+            return None;
+        }
+        u32::from_str_radix(digits, 16).ok()
+    };
+    match s.split_once('-') {
+        Some((start, end)) => {
+            let (start, end) = (parse_hex(start)?, parse_hex(end)?);
+            (start <= end).then_some(start..=end)
+        }
+        None => parse_hex(s).map(|addr| addr..=addr),
+    }
+}
+
+/// Parses address ranges from `s`, which is either a pattern matching register names (e.g:
+/// `TM?CNT_L` for every timer's reload register, or `DMA*`), where `?` matches any character and
+/// `*` matches any number of characters (ignoring ASCII case), or any format accepted by
+/// `parse_addr_range`. Returns `None` if nothing was matched.
+#[must_use]
+pub fn parse_addr_ranges(s: &str) -> Option<Vec<RangeInclusive<u32>>> {
+    let s = s.trim();
+    if !s.contains(['*', '?']) {
+        return parse_addr_range(s).map(|range| vec![range]);
+    }
+
+    let ranges: Vec<_> = REGISTERS
+        .iter()
+        .filter(|reg| matches_pattern(reg.name.as_bytes(), s.as_bytes()))
+        .map(Register::addr_range)
+        .collect();
+    (!ranges.is_empty()).then_some(ranges)
+}
+
+fn matches_pattern(name: &[u8], pattern: &[u8]) -> bool {
+    match (pattern.split_first(), name.split_first()) {
+        (None, _) => name.is_empty(),
+        (Some((b'*', rest)), _) => {
+            (0..=name.len()).any(|skip| matches_pattern(&name[skip..], rest))
+        }
+        (Some((&p, rest)), Some((&c, name))) => {
+            (p == b'?' || p.eq_ignore_ascii_case(&c)) && matches_pattern(name, rest)
+        }
+        (Some(_), None) => false,
+    }
+}
+
+macro_rules! registers {
+    ($(($name:literal, $offset:literal, $len:literal)),* $(,)?) => {
+        &[$(Register { name: $name, offset: $offset, len: $len }),*]
+    };
+}
+
+/// All known IO registers, sorted by address.
+pub const REGISTERS: &[Register] = registers![
+    // LCD
+    ("DISPCNT", 0x000, 2),
+    ("GREENSWP", 0x002, 2),
+    ("DISPSTAT", 0x004, 2),
+    ("VCOUNT", 0x006, 2),
+    ("BG0CNT", 0x008, 2),
+    ("BG1CNT", 0x00a, 2),
+    ("BG2CNT", 0x00c, 2),
+    ("BG3CNT", 0x00e, 2),
+    ("BG0HOFS", 0x010, 2),
+    ("BG0VOFS", 0x012, 2),
+    ("BG1HOFS", 0x014, 2),
+    ("BG1VOFS", 0x016, 2),
+    ("BG2HOFS", 0x018, 2),
+    ("BG2VOFS", 0x01a, 2),
+    ("BG3HOFS", 0x01c, 2),
+    ("BG3VOFS", 0x01e, 2),
+    ("BG2PA", 0x020, 2),
+    ("BG2PB", 0x022, 2),
+    ("BG2PC", 0x024, 2),
+    ("BG2PD", 0x026, 2),
+    ("BG2X", 0x028, 4),
+    ("BG2Y", 0x02c, 4),
+    ("BG3PA", 0x030, 2),
+    ("BG3PB", 0x032, 2),
+    ("BG3PC", 0x034, 2),
+    ("BG3PD", 0x036, 2),
+    ("BG3X", 0x038, 4),
+    ("BG3Y", 0x03c, 4),
+    ("WIN0H", 0x040, 2),
+    ("WIN1H", 0x042, 2),
+    ("WIN0V", 0x044, 2),
+    ("WIN1V", 0x046, 2),
+    ("WININ", 0x048, 2),
+    ("WINOUT", 0x04a, 2),
+    ("MOSAIC", 0x04c, 2),
+    ("BLDCNT", 0x050, 2),
+    ("BLDALPHA", 0x052, 2),
+    ("BLDY", 0x054, 2),
+    // Sound
+    ("SOUND1CNT_L", 0x060, 2),
+    ("SOUND1CNT_H", 0x062, 2),
+    ("SOUND1CNT_X", 0x064, 2),
+    ("SOUND2CNT_L", 0x068, 2),
+    ("SOUND2CNT_H", 0x06c, 2),
+    ("SOUND3CNT_L", 0x070, 2),
+    ("SOUND3CNT_H", 0x072, 2),
+    ("SOUND3CNT_X", 0x074, 2),
+    ("SOUND4CNT_L", 0x078, 2),
+    ("SOUND4CNT_H", 0x07c, 2),
+    ("SOUNDCNT_L", 0x080, 2),
+    ("SOUNDCNT_H", 0x082, 2),
+    ("SOUNDCNT_X", 0x084, 2),
+    ("SOUNDBIAS", 0x088, 2),
+    ("WAVE_RAM", 0x090, 16),
+    ("FIFO_A", 0x0a0, 4),
+    ("FIFO_B", 0x0a4, 4),
+    // DMA
+    ("DMA0SAD", 0x0b0, 4),
+    ("DMA0DAD", 0x0b4, 4),
+    ("DMA0CNT_L", 0x0b8, 2),
+    ("DMA0CNT_H", 0x0ba, 2),
+    ("DMA1SAD", 0x0bc, 4),
+    ("DMA1DAD", 0x0c0, 4),
+    ("DMA1CNT_L", 0x0c4, 2),
+    ("DMA1CNT_H", 0x0c6, 2),
+    ("DMA2SAD", 0x0c8, 4),
+    ("DMA2DAD", 0x0cc, 4),
+    ("DMA2CNT_L", 0x0d0, 2),
+    ("DMA2CNT_H", 0x0d2, 2),
+    ("DMA3SAD", 0x0d4, 4),
+    ("DMA3DAD", 0x0d8, 4),
+    ("DMA3CNT_L", 0x0dc, 2),
+    ("DMA3CNT_H", 0x0de, 2),
+    // Timers
+    ("TM0CNT_L", 0x100, 2),
+    ("TM0CNT_H", 0x102, 2),
+    ("TM1CNT_L", 0x104, 2),
+    ("TM1CNT_H", 0x106, 2),
+    ("TM2CNT_L", 0x108, 2),
+    ("TM2CNT_H", 0x10a, 2),
+    ("TM3CNT_L", 0x10c, 2),
+    ("TM3CNT_H", 0x10e, 2),
+    // Serial Communication
+    ("SIOMULTI0", 0x120, 2),
+    ("SIOMULTI1", 0x122, 2),
+    ("SIOMULTI2", 0x124, 2),
+    ("SIOMULTI3", 0x126, 2),
+    ("SIOCNT", 0x128, 2),
+    ("SIOMLT_SEND", 0x12a, 2),
+    // Keypad
+    ("KEYINPUT", 0x130, 2),
+    ("KEYCNT", 0x132, 2),
+    // Serial Communication
+    ("RCNT", 0x134, 2),
+    ("JOYCNT", 0x140, 2),
+    ("JOY_RECV", 0x150, 4),
+    ("JOY_TRANS", 0x154, 4),
+    ("JOYSTAT", 0x158, 2),
+    // Interrupt, Waitstate, and Power-Down Control
+    ("IE", 0x200, 2),
+    ("IF", 0x202, 2),
+    ("WAITCNT", 0x204, 2),
+    ("IME", 0x208, 2),
+    ("POSTFLG", 0x300, 1),
+    ("HALTCNT", 0x301, 1),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_addr_range_names_and_groups() {
+        assert_eq!(parse_addr_range("DISPCNT"), Some(0x400_0000..=0x400_0001));
+        assert_eq!(parse_addr_range(" dispcnt "), Some(0x400_0000..=0x400_0001));
+        assert_eq!(parse_addr_range("TM0CNT"), Some(0x400_0100..=0x400_0103));
+        assert_eq!(parse_addr_range("HALTCNT"), Some(0x400_0301..=0x400_0301));
+    }
+
+    #[test]
+    fn parse_addr_range_addresses() {
+        assert_eq!(parse_addr_range("0x4000004"), Some(0x400_0004..=0x400_0004));
+        assert_eq!(parse_addr_range("4000004"), Some(0x400_0004..=0x400_0004));
+        assert_eq!(
+            parse_addr_range("0X4000100-0x400010f"),
+            Some(0x400_0100..=0x400_010f)
+        );
+        assert_eq!(
+            parse_addr_range("0x4000100 - 0x4000100"),
+            Some(0x400_0100..=0x400_0100)
+        );
+        assert_eq!(parse_addr_range("0-0xffffffff"), Some(0..=u32::MAX));
+    }
+
+    #[test]
+    fn parse_addr_range_rejects_invalid() {
+        assert_eq!(parse_addr_range(""), None);
+        assert_eq!(parse_addr_range("   "), None);
+        assert_eq!(parse_addr_range("-"), None);
+        assert_eq!(parse_addr_range("0x"), None);
+        assert_eq!(parse_addr_range("NOTAREG"), None);
+        assert_eq!(parse_addr_range("0x4000100-"), None);
+        assert_eq!(parse_addr_range("-0x4000100"), None);
+        assert_eq!(parse_addr_range("+4000100"), None);
+        assert_eq!(parse_addr_range("0x400010f-0x4000100"), None);
+        assert_eq!(parse_addr_range("0x100000000"), None);
+        assert_eq!(parse_addr_range("0x4000000-0x100000000"), None);
+        assert_eq!(parse_addr_range("0x1-0x2-0x3"), None);
+    }
+
+    #[test]
+    fn parse_addr_ranges_patterns() {
+        assert_eq!(
+            parse_addr_ranges("tm?cnt_l"),
+            Some(vec![
+                0x400_0100..=0x400_0101,
+                0x400_0104..=0x400_0105,
+                0x400_0108..=0x400_0109,
+                0x400_010c..=0x400_010d,
+            ])
+        );
+        assert_eq!(
+            parse_addr_ranges("*").map(|r| r.len()),
+            Some(REGISTERS.len())
+        );
+        assert_eq!(parse_addr_ranges("NOPE*"), None);
+        assert_eq!(parse_addr_ranges("IE"), Some(vec![0x400_0200..=0x400_0201]));
+    }
+}
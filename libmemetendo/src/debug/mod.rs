@@ -0,0 +1,35 @@
+pub mod access_stats;
+pub mod breakpoints;
+pub mod cdl;
+pub mod disasm;
+pub mod expr;
+pub mod io;
+pub mod profile;
+pub mod symbols;
+pub mod trace;
+pub mod verify;
+pub mod watchpoints;
+
+use self::{
+    access_stats::AccessStats, breakpoints::Breakpoints, cdl::CodeDataLog, profile::Profiler,
+    symbols::Symbols, trace::IoTrace, watchpoints::Watchpoints,
+};
+
+/// Debugging facilities that hook into the emulated system's bus.
+#[derive(Default)]
+pub struct Hooks {
+    pub io_trace: IoTrace,
+    pub access_stats: AccessStats,
+    pub symbols: Symbols,
+    pub breakpoints: Breakpoints,
+    pub watchpoints: Watchpoints,
+    pub cdl: CodeDataLog,
+    pub profiler: Profiler,
+}
+
+impl Hooks {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
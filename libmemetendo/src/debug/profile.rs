@@ -0,0 +1,235 @@
+//! Profiling: attributing executed instructions to the guest functions that executed them, using
+//! the loaded symbols.
+//!
+//! Calls are tracked with a shadow call stack: a call is an instruction entering the start of a
+//! symbol with the link register pointing just past the previous instruction (like after a `BL`,
+//! or `MOV LR, PC` followed by `BX`), and returns from it are detected when execution reaches the
+//! link register's address from the time of the call. Tail calls and other jumps replace the
+//! innermost function of the call stack with the function being jumped to.
+//!
+//! As the CPU's instruction timings aren't emulated, each executed instruction is counted as one
+//! sample, rather than by the cycles it took.
+
+use std::{
+    collections::HashMap,
+    io::{self, Write},
+};
+
+use super::symbols::Symbols;
+
+/// Function "address" for instructions not within any symbol.
+const UNKNOWN: u32 = u32::MAX;
+
+/// Maximum depth of the shadow call stack; deeper calls are attributed to the deepest frame, which
+/// avoids unbounded growth if returns are missed (like for code that never returns to its caller).
+const MAX_DEPTH: usize = 64;
+
+#[derive(Debug, Copy, Clone)]
+struct Frame {
+    /// Start address of the function executing in this frame; updated on jumps and tail calls.
+    func: u32,
+    /// Address the call returns to, or `None` for the outermost frame.
+    return_addr: Option<u32>,
+}
+
+/// Profiler for guest code; see the module documentation.
+///
+/// Disabled by default, as profiling slows down every instruction.
+#[derive(Debug, Default, Clone)]
+pub struct Profiler {
+    enabled: bool,
+    stack: Vec<Frame>,
+    /// Address just past the previously executed instruction.
+    prev_end_addr: Option<u32>,
+    /// Sample counts for each call stack, as the addresses of its functions from outermost to
+    /// innermost.
+    counts: HashMap<Vec<u32>, u64>,
+    key: Vec<u32>,
+}
+
+impl Profiler {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[must_use]
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Enables or disables profiling. Samples are kept when disabled, but the call stack is
+    /// forgotten, as calls and returns made while disabled are missed.
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+        self.stack.clear();
+        self.prev_end_addr = None;
+    }
+
+    /// Forgets all samples and the current call stack.
+    pub fn clear(&mut self) {
+        self.stack.clear();
+        self.prev_end_addr = None;
+        self.counts.clear();
+    }
+
+    /// Total number of samples (executed instructions) recorded.
+    #[must_use]
+    pub fn total_samples(&self) -> u64 {
+        self.counts.values().sum()
+    }
+
+    /// Number of samples recorded for the function starting at `addr`, including its callees.
+    #[must_use]
+    pub fn inclusive_samples(&self, addr: u32) -> u64 {
+        self.counts
+            .iter()
+            .filter(|(stack, _)| stack.contains(&addr))
+            .map(|(_, &count)| count)
+            .sum()
+    }
+
+    /// Number of samples recorded for the function starting at `addr`, excluding its callees.
+    #[must_use]
+    pub fn exclusive_samples(&self, addr: u32) -> u64 {
+        self.counts
+            .iter()
+            .filter(|(stack, _)| stack.last() == Some(&addr))
+            .map(|(_, &count)| count)
+            .sum()
+    }
+
+    /// Writes the samples in the "folded stacks" format accepted by flamegraph tools (like
+    /// `flamegraph.pl` and inferno): one line per call stack, with its functions' names separated
+    /// by ';' from outermost to innermost, followed by a space and its sample count. Lines are
+    /// sorted for stable output.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if writing to `w` fails.
+    pub fn write_folded(&self, mut w: impl Write, symbols: &Symbols) -> io::Result<()> {
+        let name = |addr| {
+            symbols
+                .lookup(addr)
+                .filter(|_| addr != UNKNOWN)
+                .map_or_else(|| "[unknown]".to_string(), |s| s.symbol.name.clone())
+        };
+
+        let mut lines: Vec<_> = self
+            .counts
+            .iter()
+            .map(|(stack, count)| {
+                let names: Vec<_> = stack.iter().map(|&addr| name(addr)).collect();
+                (names.join(";"), count)
+            })
+            .collect();
+        lines.sort_unstable();
+        for (stack, count) in lines {
+            writeln!(w, "{stack} {count}")?;
+        }
+
+        Ok(())
+    }
+
+    /// Records a sample for the instruction about to be executed at `addr`, where `lr` is the
+    /// current value of the link register.
+    pub(crate) fn record(&mut self, addr: u32, thumb: bool, lr: u32, symbols: &Symbols) {
+        while self
+            .stack
+            .last()
+            .is_some_and(|f| f.return_addr == Some(addr))
+        {
+            self.stack.pop();
+        }
+
+        let func = symbols.lookup(addr).map_or(UNKNOWN, |s| s.symbol.addr);
+        let is_call = func == addr && self.prev_end_addr == Some(lr & !1);
+        let depth = self.stack.len();
+        match self.stack.last_mut() {
+            Some(frame) if !is_call || depth >= MAX_DEPTH => frame.func = func,
+            _ => self.stack.push(Frame {
+                func,
+                return_addr: (depth > 0).then_some(lr & !1),
+            }),
+        }
+        self.prev_end_addr = Some(addr.wrapping_add(if thumb { 2 } else { 4 }));
+
+        self.key.clear();
+        self.key.extend(self.stack.iter().map(|f| f.func));
+        if let Some(count) = self.counts.get_mut(&self.key) {
+            *count += 1;
+        } else {
+            self.counts.insert(self.key.clone(), 1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::debug::symbols::Symbol;
+
+    fn symbols() -> Symbols {
+        let mut symbols = Symbols::new();
+        for (name, addr) in [("main", 0x100), ("foo", 0x200), ("bar", 0x300)] {
+            symbols.insert(Symbol {
+                name: name.to_string(),
+                addr,
+                size: None,
+            });
+        }
+        symbols
+    }
+
+    #[test]
+    fn tracks_calls_and_returns() {
+        let symbols = symbols();
+        let mut profiler = Profiler::new();
+        profiler.set_enabled(true);
+
+        profiler.record(0x100, false, 0, &symbols);
+        // main: BL foo
+        profiler.record(0x104, false, 0, &symbols);
+        profiler.record(0x200, false, 0x108, &symbols);
+        // foo: calls bar, a Thumb function
+        profiler.record(0x204, false, 0x108, &symbols);
+        profiler.record(0x300, true, 0x209, &symbols);
+        profiler.record(0x302, true, 0x209, &symbols);
+        // bar returns to foo, which returns to main.
+        profiler.record(0x208, false, 0x209, &symbols);
+        profiler.record(0x108, false, 0x209, &symbols);
+
+        assert_eq!(profiler.total_samples(), 8);
+        assert_eq!(profiler.inclusive_samples(0x100), 8);
+        assert_eq!(profiler.exclusive_samples(0x100), 3);
+        assert_eq!(profiler.inclusive_samples(0x200), 5);
+        assert_eq!(profiler.exclusive_samples(0x200), 3);
+        assert_eq!(profiler.inclusive_samples(0x300), 2);
+
+        let mut buf = Vec::new();
+        profiler.write_folded(&mut buf, &symbols).unwrap();
+        assert_eq!(
+            String::from_utf8(buf).unwrap(),
+            "main 3\nmain;foo 3\nmain;foo;bar 2\n"
+        );
+    }
+
+    #[test]
+    fn jumps_are_not_calls() {
+        let symbols = symbols();
+        let mut profiler = Profiler::new();
+        profiler.set_enabled(true);
+
+        // main: B foo, with a stale LR.
+        profiler.record(0x100, false, 0x50, &symbols);
+        profiler.record(0x200, false, 0x50, &symbols);
+        profiler.record(0x10, false, 0x50, &symbols);
+
+        let mut buf = Vec::new();
+        profiler.write_folded(&mut buf, &symbols).unwrap();
+        assert_eq!(
+            String::from_utf8(buf).unwrap(),
+            "[unknown] 1\nfoo 1\nmain 1\n"
+        );
+    }
+}
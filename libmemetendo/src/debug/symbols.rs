@@ -0,0 +1,370 @@
+use std::{
+    collections::BTreeMap,
+    error::Error,
+    fmt::{self, Display, Formatter},
+};
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Symbol {
+    pub name: String,
+    pub addr: u32,
+    /// Size of the symbol in bytes, if known.
+    pub size: Option<u32>,
+}
+
+/// A symbol found by `Symbols::lookup`, along with the offset of the looked-up address from the
+/// start of the symbol.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct SymbolOffset<'a> {
+    pub symbol: &'a Symbol,
+    pub offset: u32,
+}
+
+impl Display for SymbolOffset<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.symbol.name)?;
+        if self.offset > 0 {
+            write!(f, "+{:#x}", self.offset)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct InvalidElf(&'static str);
+
+impl Display for InvalidElf {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid ELF file: {}", self.0)
+    }
+}
+
+impl Error for InvalidElf {}
+
+/// Table of symbol names for ROM (or RAM) addresses, used to make debug output more readable.
+#[derive(Debug, Default, Clone)]
+pub struct Symbols(BTreeMap<u32, Symbol>);
+
+impl Symbols {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parses a no$gba-style symbol file, where each line is a hexadecimal address followed by a
+    /// name. Comments (starting with ';'), directives (names starting with '.', like `.thumb`)
+    /// and malformed lines are ignored.
+    #[must_use]
+    pub fn parse_sym(s: &str) -> Self {
+        let mut symbols = Self::new();
+        for line in s.lines() {
+            let line = line.split_once(';').map_or(line, |(line, _)| line);
+            let mut columns = line.split_whitespace();
+            let (Some(addr), Some(name)) = (columns.next(), columns.next()) else {
+                continue;
+            };
+            if name.starts_with('.') {
+                continue;
+            }
+            if let Ok(addr) = u32::from_str_radix(addr, 16) {
+                symbols.insert(Symbol {
+                    name: name.to_owned(),
+                    addr,
+                    size: None,
+                });
+            }
+        }
+
+        symbols
+    }
+
+    /// Parses the symbol table of a little-endian 32-bit ELF file, like those produced by devkitARM
+    /// alongside a GBA ROM. Only function, object and untyped (label) symbols are used.
+    ///
+    /// # Errors
+    /// Returns an error if the ELF file or its symbol table is malformed or unsupported.
+    // Panics are impossible, as the slices converted to arrays always have the correct length, and
+    // usize is at least 32 bits wide on the platforms we support.
+    #[expect(clippy::missing_panics_doc)]
+    pub fn parse_elf(buf: &[u8]) -> Result<Self, InvalidElf> {
+        const SHT_SYMTAB: u32 = 2;
+        const STT_NOTYPE: u8 = 0;
+        const STT_OBJECT: u8 = 1;
+        const STT_FUNC: u8 = 2;
+
+        // Offsets come from the file, so may overflow on targets with a 32-bit usize (e.g: WASM).
+        let offset_by = |offset: usize, delta: usize| {
+            offset
+                .checked_add(delta)
+                .ok_or(InvalidElf("offset out of range"))
+        };
+        let bytes_at = |offset: usize, len: usize| {
+            buf.get(offset..offset_by(offset, len)?)
+                .ok_or(InvalidElf("unexpected end of file"))
+        };
+        let hword_at =
+            |offset: usize| bytes_at(offset, 2).map(|b| u16::from_le_bytes(b.try_into().unwrap()));
+        let word_at =
+            |offset: usize| bytes_at(offset, 4).map(|b| u32::from_le_bytes(b.try_into().unwrap()));
+        let to_usize = |value: u32| usize::try_from(value).unwrap();
+
+        if !buf.starts_with(b"\x7fELF") {
+            return Err(InvalidElf("bad magic number"));
+        }
+        if buf.get(4..6) != Some(&[1, 1]) {
+            return Err(InvalidElf("not a little-endian 32-bit ELF"));
+        }
+
+        let sh_offset = to_usize(word_at(0x20)?);
+        let sh_entry_len = usize::from(hword_at(0x2e)?);
+        let sh_count = usize::from(hword_at(0x30)?);
+        let section = |idx: usize| {
+            let offset = idx
+                .checked_mul(sh_entry_len)
+                .ok_or(InvalidElf("offset out of range"))
+                .and_then(|offset| offset_by(sh_offset, offset))?;
+            Ok::<_, InvalidElf>((
+                word_at(offset_by(offset, 4)?)?,            // sh_type
+                to_usize(word_at(offset_by(offset, 16)?)?), // sh_offset
+                to_usize(word_at(offset_by(offset, 20)?)?), // sh_size
+                to_usize(word_at(offset_by(offset, 24)?)?), // sh_link
+                to_usize(word_at(offset_by(offset, 36)?)?), // sh_entsize
+            ))
+        };
+
+        let mut symbols = Self::new();
+        for idx in 0..sh_count {
+            let (kind, offset, len, link, entry_len) = section(idx)?;
+            if kind != SHT_SYMTAB {
+                continue;
+            }
+            if entry_len < 16 || link >= sh_count {
+                return Err(InvalidElf("bad symbol table"));
+            }
+            let (_, str_offset, str_len, _, _) = section(link)?;
+            let strtab = bytes_at(str_offset, str_len)?;
+
+            for sym_offset in (offset..offset_by(offset, len)?).step_by(entry_len) {
+                let sym = bytes_at(sym_offset, 16)?;
+                let field = |i: usize| u32::from_le_bytes(sym[i..i + 4].try_into().unwrap());
+                let name_offset = to_usize(field(0));
+                let addr = field(4);
+                let size = field(8);
+                let kind = sym[12] & 0xf;
+                let section_idx = u16::from_le_bytes([sym[14], sym[15]]);
+                if section_idx == 0 || ![STT_NOTYPE, STT_OBJECT, STT_FUNC].contains(&kind) {
+                    continue;
+                }
+
+                let name = strtab
+                    .get(name_offset..)
+                    .and_then(|s| s.split(|&b| b == 0).next())
+                    .ok_or(InvalidElf("bad symbol name"))?;
+                // Skip unnamed symbols and ARM mapping symbols like "$a", "$t" and "$d".
+                if name.is_empty() || name[0] == b'$' {
+                    continue;
+                }
+
+                symbols.insert(Symbol {
+                    name: String::from_utf8_lossy(name).into_owned(),
+                    // Bit 0 of a function's address is set if it contains Thumb code.
+                    addr: if kind == STT_FUNC { addr & !1 } else { addr },
+                    size: (size > 0).then_some(size),
+                });
+            }
+        }
+
+        Ok(symbols)
+    }
+
+    /// Adds `symbol`, unless a symbol already exists at the same address.
+    pub fn insert(&mut self, symbol: Symbol) {
+        self.0.entry(symbol.addr).or_insert(symbol);
+    }
+
+    pub fn clear(&mut self) {
+        self.0.clear();
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Symbol> {
+        self.0.values()
+    }
+
+    /// Finds the symbol containing `addr`; that is, the closest symbol at or before `addr`.
+    /// Symbols with a known size are only matched if `addr` is within their bounds.
+    #[must_use]
+    pub fn lookup(&self, addr: u32) -> Option<SymbolOffset<'_>> {
+        let (_, symbol) = self.0.range(..=addr).next_back()?;
+        let offset = addr - symbol.addr;
+        if symbol.size.is_some_and(|size| offset >= size) {
+            return None;
+        }
+
+        Some(SymbolOffset { symbol, offset })
+    }
+
+    #[must_use]
+    pub fn find_by_name(&self, name: &str) -> Option<&Symbol> {
+        self.0.values().find(|symbol| symbol.name == name)
+    }
+
+    /// Returns a `Display`able address, followed by the symbol containing it (if any).
+    #[must_use]
+    pub fn describe(&self, addr: u32) -> impl Display + '_ {
+        DescribeAddr {
+            addr,
+            symbol: self.lookup(addr),
+        }
+    }
+}
+
+struct DescribeAddr<'a> {
+    addr: u32,
+    symbol: Option<SymbolOffset<'a>>,
+}
+
+impl Display for DescribeAddr<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{:#010x}", self.addr)?;
+        if let Some(symbol) = self.symbol {
+            write!(f, " ({symbol})")?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_sym_and_lookup() {
+        let symbols = Symbols::parse_sym(
+            "; no$gba symbols\n\
+             08000000 .arm\n\
+             08000000 start\n\
+             080000c0 main ; entry point\n\
+             08000200 .thumb\n\
+             bogus\n\
+             03000000 irq_handler\n",
+        );
+        assert_eq!(symbols.len(), 3);
+
+        let lookup = symbols.lookup(0x0800_00c4).unwrap();
+        assert_eq!(lookup.symbol.name, "main");
+        assert_eq!(lookup.offset, 4);
+        assert_eq!(lookup.to_string(), "main+0x4");
+        assert_eq!(symbols.lookup(0x0800_0000).unwrap().to_string(), "start");
+        assert!(symbols.lookup(0x0200_0000).is_none());
+
+        assert_eq!(
+            symbols.describe(0x0300_0010).to_string(),
+            "0x03000010 (irq_handler+0x10)"
+        );
+        assert_eq!(symbols.find_by_name("main").unwrap().addr, 0x0800_00c0);
+    }
+
+    #[test]
+    fn lookup_respects_size() {
+        let mut symbols = Symbols::new();
+        symbols.insert(Symbol {
+            name: "func".to_owned(),
+            addr: 0x0800_0100,
+            size: Some(0x10),
+        });
+        assert!(symbols.lookup(0x0800_010f).is_some());
+        assert!(symbols.lookup(0x0800_0110).is_none());
+    }
+
+    #[test]
+    fn parse_elf_rejects_garbage() {
+        assert!(Symbols::parse_elf(b"not an elf").is_err());
+        assert!(Symbols::parse_elf(b"\x7fELF\x02\x01").is_err());
+        assert!(Symbols::parse_elf(b"\x7fELF\x01\x01").is_err());
+    }
+
+    /// Returns the header of a little-endian 32-bit ELF with `sh_count` section headers of
+    /// `sh_entry_len` bytes at `sh_offset`.
+    fn elf_header(sh_offset: u32, sh_entry_len: u16, sh_count: u16) -> Vec<u8> {
+        let mut buf = vec![0; 0x34];
+        buf[..6].copy_from_slice(b"\x7fELF\x01\x01");
+        buf[0x20..0x24].copy_from_slice(&sh_offset.to_le_bytes());
+        buf[0x2e..0x30].copy_from_slice(&sh_entry_len.to_le_bytes());
+        buf[0x30..0x32].copy_from_slice(&sh_count.to_le_bytes());
+        buf
+    }
+
+    /// Returns a section header with the given type, offset, size, link and entry size.
+    fn section_header(kind: u32, offset: u32, len: u32, link: u32, entry_len: u32) -> [u8; 40] {
+        let mut header = [0; 40];
+        for (i, value) in [
+            (4, kind),
+            (16, offset),
+            (20, len),
+            (24, link),
+            (36, entry_len),
+        ] {
+            header[i..i + 4].copy_from_slice(&value.to_le_bytes());
+        }
+        header
+    }
+
+    #[test]
+    fn parse_elf_rejects_out_of_range_offsets() {
+        assert!(Symbols::parse_elf(&elf_header(u32::MAX, 40, 1)).is_err());
+        assert!(Symbols::parse_elf(&elf_header(0x34, u16::MAX, u16::MAX)).is_err());
+
+        // Symbol table and string table extending past the end of the file.
+        for (symtab, strtab) in [
+            (
+                section_header(2, u32::MAX - 4, 32, 1, 16),
+                section_header(3, 0, 0, 0, 0),
+            ),
+            (
+                section_header(2, 0, u32::MAX, 1, 16),
+                section_header(3, 0, 0, 0, 0),
+            ),
+            (
+                section_header(2, 0, 0, 1, 16),
+                section_header(3, u32::MAX, 2, 0, 0),
+            ),
+        ] {
+            let mut buf = elf_header(0x34, 40, 2);
+            buf.extend_from_slice(&symtab);
+            buf.extend_from_slice(&strtab);
+            assert!(Symbols::parse_elf(&buf).is_err());
+        }
+    }
+
+    #[test]
+    fn parse_elf_symbols() {
+        let mut buf = elf_header(0x34, 40, 2);
+        let (symtab_offset, strtab_offset) = (0x84, 0xa4);
+        buf.extend_from_slice(&section_header(2, symtab_offset, 32, 1, 16));
+        buf.extend_from_slice(&section_header(3, strtab_offset, 6, 0, 0));
+        for (name_offset, addr, kind) in [(0, 0, 0), (1u32, 0x0800_0101u32, 2u8)] {
+            buf.extend_from_slice(&name_offset.to_le_bytes());
+            buf.extend_from_slice(&addr.to_le_bytes());
+            buf.extend_from_slice(&8u32.to_le_bytes());
+            buf.extend_from_slice(&[kind, 0, 1, 0]);
+        }
+        buf.extend_from_slice(b"\0main\0");
+
+        let symbols = Symbols::parse_elf(&buf).unwrap();
+        assert_eq!(symbols.len(), 1);
+        let lookup = symbols.lookup(0x0800_0104).unwrap();
+        assert_eq!((lookup.symbol.name.as_str(), lookup.offset), ("main", 4));
+    }
+}
@@ -0,0 +1,190 @@
+use std::{
+    fmt::{self, Display, Formatter},
+    ops::RangeInclusive,
+};
+
+use log::info;
+
+use super::{io::Register, symbols::Symbols};
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum AccessKind {
+    Read,
+    Write,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct IoAccess {
+    pub kind: AccessKind,
+    pub addr: u32,
+    /// Size of the access in bytes (1, 2 or 4).
+    pub len: u8,
+    pub value: u32,
+    /// Address of the CPU instruction that was executing when the access happened. Accesses made
+    /// by DMA report the last instruction executed by the CPU instead.
+    pub instr_addr: u32,
+}
+
+impl IoAccess {
+    #[must_use]
+    pub fn register(&self) -> Option<&'static Register> {
+        Register::find(self.addr)
+    }
+}
+
+impl Display for IoAccess {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let kind = match self.kind {
+            AccessKind::Read => "read",
+            AccessKind::Write => "write",
+        };
+        write!(f, "{kind} {:#010x}", self.addr)?;
+        if let Some(reg) = self.register() {
+            write!(f, " ({}", reg.name)?;
+            if self.addr != reg.addr() {
+                write!(f, "+{}", self.addr - reg.addr())?;
+            }
+            write!(f, ")")?;
+        }
+
+        let width = 2 * usize::from(self.len);
+        write!(f, " = {:#0w$x}", self.value, w = width + 2)
+    }
+}
+
+pub type IoTraceCallback = Box<dyn FnMut(&IoAccess)>;
+
+/// Traces accesses to selected IO register address ranges.
+///
+/// Accesses are logged at the info level, unless a callback is set via `set_callback`.
+#[derive(Default)]
+pub struct IoTrace {
+    ranges: Vec<RangeInclusive<u32>>,
+    callback: Option<IoTraceCallback>,
+    suppressed: bool,
+    instr_addr: u32,
+}
+
+impl IoTrace {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_range(&mut self, range: RangeInclusive<u32>) {
+        self.ranges.push(range);
+    }
+
+    /// Traces the registers named by `name`; see `debug::io::parse_addr_ranges` for the accepted
+    /// formats. Returns `false` if `name` could not be parsed.
+    pub fn add_register(&mut self, name: &str) -> bool {
+        if let Some(ranges) = super::io::parse_addr_ranges(name) {
+            self.ranges.extend(ranges);
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.ranges.clear();
+    }
+
+    pub fn set_callback(&mut self, callback: Option<IoTraceCallback>) {
+        self.callback = callback;
+    }
+
+    #[must_use]
+    pub fn is_enabled(&self) -> bool {
+        !self.ranges.is_empty()
+    }
+
+    #[inline]
+    pub(crate) fn is_tracing(&self, addr: u32, len: u8) -> bool {
+        if self.ranges.is_empty() || self.suppressed || addr >> 24 != 0x04 {
+            return false;
+        }
+
+        let end_addr = addr.wrapping_add(u32::from(len) - 1);
+        self.ranges
+            .iter()
+            .any(|range| addr <= *range.end() && end_addr >= *range.start())
+    }
+
+    /// Suppresses tracing while a wider access is split into smaller ones, so that it's only
+    /// traced once.
+    pub(crate) fn set_suppressed(&mut self, suppressed: bool) {
+        self.suppressed = suppressed;
+    }
+
+    pub(crate) fn instr_addr(&self) -> u32 {
+        self.instr_addr
+    }
+
+    pub(crate) fn set_instr_addr(&mut self, addr: u32) {
+        self.instr_addr = addr;
+    }
+
+    pub(crate) fn record(&mut self, access: &IoAccess, symbols: &Symbols) {
+        if let Some(ref mut callback) = self.callback {
+            callback(access);
+        } else {
+            info!(target: "io_trace", "{access} from {}", symbols.describe(access.instr_addr));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, rc::Rc};
+
+    use super::*;
+
+    #[test]
+    fn traces_overlapping_io_accesses() {
+        let mut trace = IoTrace::new();
+        assert!(!trace.is_enabled());
+        assert!(!trace.is_tracing(0x400_0000, 2));
+
+        assert!(trace.add_register("DISPSTAT"));
+        assert!(!trace.add_register("NOTAREG"));
+        assert!(trace.is_enabled());
+        assert!(trace.is_tracing(0x400_0004, 2));
+        assert!(trace.is_tracing(0x400_0005, 1));
+        assert!(trace.is_tracing(0x400_0000, 8));
+        assert!(!trace.is_tracing(0x400_0000, 4));
+        assert!(!trace.is_tracing(0x400_0006, 2));
+        // Mirrors of the IO region outside of 0x4xxxxxx aren't IO.
+        assert!(!trace.is_tracing(0x500_0004, 2));
+
+        trace.set_suppressed(true);
+        assert!(!trace.is_tracing(0x400_0004, 2));
+        trace.set_suppressed(false);
+
+        trace.clear();
+        assert!(!trace.is_enabled());
+        assert!(!trace.is_tracing(0x400_0004, 2));
+    }
+
+    #[test]
+    fn records_to_callback() {
+        let accesses = Rc::new(RefCell::new(Vec::new()));
+        let mut trace = IoTrace::new();
+        let recorded = Rc::clone(&accesses);
+        trace.set_callback(Some(Box::new(move |access| {
+            recorded.borrow_mut().push(*access);
+        })));
+
+        let access = IoAccess {
+            kind: AccessKind::Write,
+            addr: 0x400_0101,
+            len: 1,
+            value: 0x12,
+            instr_addr: 0x800_0000,
+        };
+        trace.record(&access, &Symbols::new());
+        assert_eq!(*accesses.borrow(), [access]);
+        assert_eq!(access.register().unwrap().name, "TM0CNT_L");
+        assert_eq!(access.to_string(), "write 0x04000101 (TM0CNT_L+1) = 0x12");
+    }
+}
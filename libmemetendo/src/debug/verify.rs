@@ -0,0 +1,113 @@
+use std::{
+    collections::VecDeque,
+    error::Error,
+    fmt::{self, Display, Formatter},
+};
+
+use crate::gba::Gba;
+
+/// Hashes of the state of each component of the system (e.g: "cpu", "video", "iwram"), for
+/// cheaply comparing states; see `Gba::state_hashes`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StateHashes(Vec<(&'static str, u64)>);
+
+impl StateHashes {
+    pub(crate) fn new(hashes: Vec<(&'static str, u64)>) -> Self {
+        Self(hashes)
+    }
+
+    /// Returns the name and state hash of each component.
+    pub fn iter(&self) -> impl Iterator<Item = (&'static str, u64)> + '_ {
+        self.0.iter().copied()
+    }
+
+    /// Returns the names of the components whose states differ from those in `other`.
+    #[must_use]
+    pub fn diff(&self, other: &Self) -> Vec<&'static str> {
+        self.iter()
+            .filter(|&(name, hash)| !other.iter().any(|other| other == (name, hash)))
+            .map(|(name, _)| name)
+            .collect()
+    }
+}
+
+/// The state of the system after re-simulating a frame differed from its recorded state.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Divergence {
+    pub frame: u64,
+    /// Names of the components whose states differed.
+    pub components: Vec<&'static str>,
+}
+
+impl Display for Divergence {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "State diverged at frame {}: {}",
+            self.frame,
+            self.components.join(", ")
+        )
+    }
+}
+
+impl Error for Divergence {}
+
+/// Detects nondeterminism when re-simulating frames after loading a save state, such as when
+/// rolling back for netplay or rewinding. Nondeterminism makes such features drift out of sync.
+///
+/// While simulating normally, `Self::record` the state after each frame. After loading a state and
+/// re-simulating frames with the same inputs, `Self::verify` the state after each of them; the
+/// first frame to fail verification is where the simulation first diverged.
+#[derive(Debug, Clone)]
+pub struct StateVerifier {
+    history: VecDeque<(u64, StateHashes)>,
+    capacity: usize,
+}
+
+impl StateVerifier {
+    /// Creates a verifier that remembers the states of the last `capacity` recorded frames.
+    #[must_use]
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            history: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    /// Records the state of `gba` after `frame`. Previously recorded states for `frame` and later
+    /// frames are forgotten, as they're from a different timeline.
+    pub fn record(&mut self, frame: u64, gba: &Gba) {
+        while self.history.back().is_some_and(|&(f, _)| f >= frame) {
+            self.history.pop_back();
+        }
+        if self.history.len() == self.capacity {
+            self.history.pop_front();
+        }
+        if self.capacity > 0 {
+            self.history.push_back((frame, gba.state_hashes()));
+        }
+    }
+
+    /// Checks that the state of `gba` after re-simulating `frame` matches its recorded state, if
+    /// any.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error with the components whose states differ if they don't match.
+    pub fn verify(&self, frame: u64, gba: &Gba) -> Result<(), Divergence> {
+        let Some((_, expected)) = self.history.iter().find(|&&(f, _)| f == frame) else {
+            return Ok(());
+        };
+
+        let components = gba.state_hashes().diff(expected);
+        if components.is_empty() {
+            Ok(())
+        } else {
+            Err(Divergence { frame, components })
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.history.clear();
+    }
+}
@@ -0,0 +1,100 @@
+//! Watchpoints that stop the CPU after IO registers are read or written.
+
+use std::ops::RangeInclusive;
+
+use super::trace::{AccessKind, IoAccess};
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Watchpoint {
+    /// IO addresses watched; see `debug::io::parse_addr_ranges` for selecting them by register
+    /// name.
+    pub range: RangeInclusive<u32>,
+    pub on_read: bool,
+    pub on_write: bool,
+}
+
+impl Watchpoint {
+    fn is_watching(&self, addr: u32, end_addr: u32, kind: AccessKind) -> bool {
+        let watched_kind = match kind {
+            AccessKind::Read => self.on_read,
+            AccessKind::Write => self.on_write,
+        };
+
+        watched_kind && addr <= *self.range.end() && end_addr >= *self.range.start()
+    }
+}
+
+/// IO watchpoints checked by `Gba::step` for every access to the IO registers, including accesses
+/// made by DMA. When one is hit, the CPU stops stepping for the rest of the current system step
+/// after finishing the instruction that made the access, and `Self::take_hit` returns the access.
+#[derive(Debug, Default, Clone)]
+pub struct Watchpoints {
+    list: Vec<Watchpoint>,
+    hit: Option<IoAccess>,
+    /// Whether the CPU should stop, as a watchpoint was hit since it last stopped.
+    stopping: bool,
+    suppressed: bool,
+}
+
+impl Watchpoints {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, watchpoint: Watchpoint) {
+        self.list.push(watchpoint);
+    }
+
+    /// Removes and returns the watchpoint at `idx` in `Self::iter`'s order, if any.
+    pub fn remove(&mut self, idx: usize) -> Option<Watchpoint> {
+        (idx < self.list.len()).then(|| self.list.remove(idx))
+    }
+
+    pub fn clear(&mut self) {
+        self.list.clear();
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.list.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Watchpoint> {
+        self.list.iter()
+    }
+
+    /// Returns the first access that hit a watchpoint since the last call, if any.
+    pub fn take_hit(&mut self) -> Option<IoAccess> {
+        self.stopping = false;
+        self.hit.take()
+    }
+
+    #[inline]
+    pub(crate) fn is_watching(&self, addr: u32, len: u8, kind: AccessKind) -> bool {
+        if self.list.is_empty() || self.suppressed || addr >> 24 != 0x04 {
+            return false;
+        }
+
+        let end_addr = addr.wrapping_add(u32::from(len) - 1);
+        self.list
+            .iter()
+            .any(|wp| wp.is_watching(addr, end_addr, kind))
+    }
+
+    /// Suppresses checks while a wider access is split into smaller ones, so that it's only
+    /// checked once.
+    pub(crate) fn set_suppressed(&mut self, suppressed: bool) {
+        self.suppressed = suppressed;
+    }
+
+    pub(crate) fn set_hit(&mut self, access: IoAccess) {
+        self.hit.get_or_insert(access);
+        self.stopping = true;
+    }
+
+    /// Whether the CPU should stop, as a watchpoint was hit. Only returns true once per hit.
+    pub(crate) fn take_stopping(&mut self) -> bool {
+        std::mem::take(&mut self.stopping)
+    }
+}
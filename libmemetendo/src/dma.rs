@@ -1,6 +1,7 @@
 use std::mem::replace;
 
 use intbits::Bits;
+use serde::{Deserialize, Serialize};
 use strum_macros::FromRepr;
 
 use crate::{
@@ -9,7 +10,7 @@ use crate::{
     irq::{Interrupt, Irq},
 };
 
-#[derive(Debug, Default, Eq, PartialEq)]
+#[derive(Debug, Clone, Default, Eq, PartialEq, Serialize, Deserialize)]
 enum State {
     #[default]
     None,
@@ -17,7 +18,7 @@ enum State {
     Transferring,
 }
 
-#[derive(Debug, Default, Copy, Clone, Eq, PartialEq, FromRepr)]
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq, FromRepr, Serialize, Deserialize)]
 #[repr(u8)]
 enum AddressControl {
     #[default]
@@ -27,7 +28,7 @@ enum AddressControl {
     IncrementAndReload,
 }
 
-#[derive(Debug, Default, Copy, Clone, Eq, PartialEq, FromRepr)]
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq, FromRepr, Serialize, Deserialize)]
 #[repr(u8)]
 enum TimingMode {
     #[default]
@@ -38,7 +39,7 @@ enum TimingMode {
 }
 
 #[expect(clippy::struct_excessive_bools)]
-#[derive(Debug, Default)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 struct Channel {
     initial_src_addr: u32,
     initial_dst_addr: u32,
@@ -59,8 +60,138 @@ struct Channel {
     state: State,
 }
 
-#[derive(Debug, Default)]
-pub struct Dma([Channel; 4]);
+/// Snapshot of a DMA channel's registers and transfer progress, for debuggers; see
+/// `Dma::channel_state`.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct ChannelState {
+    /// Value written to `DMAxSAD`.
+    pub src_addr: u32,
+    /// Value written to `DMAxDAD`.
+    pub dst_addr: u32,
+    /// Value written to `DMAxCNT_L`.
+    pub count: u16,
+    /// Value written to `DMAxCNT_H`, with the enable bit (15) cleared if the channel has since
+    /// disabled itself.
+    pub control: u16,
+    /// Whether a transfer is pending or in progress.
+    pub transferring: bool,
+    /// Address the current (or last) transfer will read from next.
+    pub current_src_addr: u32,
+    /// Address the current (or last) transfer will write to next.
+    pub current_dst_addr: u32,
+    /// Units (half-words or words) left to transfer in the current transfer.
+    pub remaining: u32,
+}
+
+/// The DMA controller. Only one channel transfers at a time, with lower-indexed channels having
+/// priority over (and pausing) higher-indexed ones, and the CPU is stalled while any transfer is in
+/// progress.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Dma {
+    chans: [Channel; 4],
+    /// Bus cycles given to transfers that haven't been spent yet, as a unit is only transferred
+    /// once enough cycles were given for all of its accesses.
+    cycles: u32,
+    /// Index of the channel that last accessed the bus; the first access of a channel that takes
+    /// over the bus is non-sequential.
+    bus_owner: Option<usize>,
+}
+
+/// DMA state as saved by version 1 of its save state chunk, before bus arbitration was emulated.
+#[derive(Serialize, Deserialize)]
+pub(crate) struct DmaV1([Channel; 4]);
+
+impl From<DmaV1> for Dma {
+    fn from(v1: DmaV1) -> Self {
+        Self {
+            chans: v1.0,
+            ..Self::default()
+        }
+    }
+}
+
+#[cfg(test)]
+impl From<&Dma> for DmaV1 {
+    fn from(dma: &Dma) -> Self {
+        Self(dma.chans.clone())
+    }
+}
+
+/// A run of units transferred by a channel during a call to `Dma::step`.
+#[derive(Debug, Copy, Clone)]
+struct Segment {
+    src_addr: u32,
+    dst_addr: u32,
+    src_addr_ctrl: AddressControl,
+    dst_addr_ctrl: AddressControl,
+    transfer_word: bool,
+    blocks: u32,
+}
+
+impl Segment {
+    fn transfer(&self, bus: &mut impl Bus) {
+        let (mut src_addr, mut dst_addr) = (self.src_addr, self.dst_addr);
+        let stride = if self.transfer_word { 4 } else { 2 };
+        for _ in 0..self.blocks {
+            if self.transfer_word {
+                let value = bus.read_word_aligned(src_addr);
+                bus.write_word_aligned(dst_addr, value);
+            } else {
+                let value = bus.read_hword_aligned(src_addr);
+                bus.write_hword_aligned(dst_addr, value);
+            }
+            update_addr(&mut src_addr, self.src_addr_ctrl, stride);
+            update_addr(&mut dst_addr, self.dst_addr_ctrl, stride);
+        }
+    }
+}
+
+fn update_addr(addr: &mut u32, ctrl: AddressControl, offset: u32) {
+    match ctrl {
+        AddressControl::Increment | AddressControl::IncrementAndReload => {
+            *addr = addr.wrapping_add(offset);
+        }
+        AddressControl::Decrement => *addr = addr.wrapping_sub(offset),
+        AddressControl::Fixed => {}
+    }
+}
+
+fn is_cart_addr(addr: u32) -> bool {
+    (0x0800_0000..=0x0fff_ffff).contains(&addr)
+}
+
+/// Returns the bus cycles taken by an access to `addr`, with the wait states configured by
+/// `waitcnt` (the value of the `WAITCNT` register). `sequential` is whether the access follows an
+/// access to the previous address in the same memory region.
+fn access_cycles(addr: u32, word: bool, sequential: bool, waitcnt: u16) -> u32 {
+    let wait = |bits: u16, table: [u32; 4]| table[usize::from(bits)];
+    match addr >> 24 {
+        // External WRAM (16-bit bus with 2 wait states)
+        0x02 if word => 6,
+        0x02 => 3,
+        // Palette RAM and VRAM (16-bit bus)
+        0x05 | 0x06 if word => 2,
+        // Cartridge ROM (16-bit bus), in wait state regions 0, 1 and 2
+        0x08..=0x0d => {
+            let (n, s) = match (addr >> 25) & 3 {
+                0 => (waitcnt.bits(2..4), [2, 1][usize::from(waitcnt.bit(4))]),
+                1 => (waitcnt.bits(5..7), [4, 1][usize::from(waitcnt.bit(7))]),
+                _ => (waitcnt.bits(8..10), [8, 1][usize::from(waitcnt.bit(10))]),
+            };
+            let n = wait(n, [4, 3, 2, 8]);
+            let first = 1 + if sequential { s } else { n };
+            if word {
+                first + 1 + s
+            } else {
+                first
+            }
+        }
+        // Cartridge SRAM (8-bit bus)
+        0x0e | 0x0f => 1 + wait(waitcnt.bits(..2), [4, 3, 2, 8]),
+        // BIOS, internal WRAM, IO and OAM (32-bit bus)
+        _ => 1,
+    }
+}
 
 impl Dma {
     #[must_use]
@@ -69,12 +200,12 @@ impl Dma {
     }
 
     fn in_audio_fifo_mode(&self, chan_idx: usize) -> bool {
-        (1..=2).contains(&chan_idx) && self.0[chan_idx].timing_mode == TimingMode::Special
+        (1..=2).contains(&chan_idx) && self.chans[chan_idx].timing_mode == TimingMode::Special
     }
 
     fn start_transfer(&mut self, chan_idx: usize) {
         let audio_fifo = self.in_audio_fifo_mode(chan_idx);
-        let chan = &mut self.0[chan_idx];
+        let chan = &mut self.chans[chan_idx];
         if !chan.enabled || chan.state != State::None {
             return;
         }
@@ -90,101 +221,170 @@ impl Dma {
         chan.state = State::StartingTransfer;
     }
 
+    /// Gives `cycles` bus cycles to the transferring channels, in order of priority, for them to
+    /// transfer as many units as the memory they access allows; `waitcnt` is the value of the
+    /// `WAITCNT` register. Returns a function that does the transfers on the bus, if any.
     #[must_use]
     pub fn step<B: Bus>(
         &mut self,
         irq: &mut Irq,
         cart: &mut Cartridge,
+        waitcnt: u16,
         cycles: u8,
     ) -> Option<impl Fn(&mut B)> {
-        // TODO: proper cycle transfer timings, cart DRQ, special timing modes
-        for chan_idx in 0..self.0.len() {
-            if !self.0[chan_idx].enabled || self.0[chan_idx].state == State::None {
+        // TODO: cart DRQ, special timing modes
+        if !self.transfer_in_progress() {
+            return None;
+        }
+
+        self.cycles += u32::from(cycles);
+        let mut segments = [None; 4];
+        for chan_idx in 0..self.chans.len() {
+            if !self.chans[chan_idx].enabled || self.chans[chan_idx].state == State::None {
                 continue;
             }
 
             let audio_fifo = self.in_audio_fifo_mode(chan_idx);
-            let chan = &mut self.0[chan_idx];
+            let sequential = self.bus_owner == Some(chan_idx);
+            let chan = &mut self.chans[chan_idx];
+
+            if chan.state == State::StartingTransfer {
+                // Internal processing before the first access; longer when accessing the cart.
+                let dst_addr = chan.dst_addr;
+                let both_cart = is_cart_addr(chan.src_addr) && is_cart_addr(dst_addr);
+                let start_cycles = if both_cart { 4 } else { 2 };
+                if self.cycles < start_cycles {
+                    break;
+                }
+                self.cycles -= start_cycles;
 
-            let dst_addr = chan.dst_addr;
-            if chan.state == State::StartingTransfer
-                && dst_addr >= 0x0800_0000
-                && cart.is_eeprom_offset(dst_addr - 0x0800_0000)
-            {
-                cart.notify_eeprom_dma(chan.rem_blocks);
+                if dst_addr >= 0x0800_0000 && cart.is_eeprom_offset(dst_addr - 0x0800_0000) {
+                    cart.notify_eeprom_dma(chan.rem_blocks);
+                }
+                chan.state = State::Transferring;
             }
-            chan.state = State::Transferring;
 
-            let src_addr = chan.src_addr;
-            let src_addr_ctrl = chan.src_addr_ctrl;
-            let dst_addr_ctrl = if audio_fifo {
-                AddressControl::Fixed
-            } else {
-                chan.dst_addr_ctrl
+            let segment = Segment {
+                src_addr: chan.src_addr,
+                dst_addr: chan.dst_addr,
+                src_addr_ctrl: chan.src_addr_ctrl,
+                dst_addr_ctrl: if audio_fifo {
+                    AddressControl::Fixed
+                } else {
+                    chan.dst_addr_ctrl
+                },
+                transfer_word: audio_fifo || chan.transfer_word,
+                blocks: 0,
             };
-            let blocks = chan.rem_blocks.min(cycles.into());
-            let transfer_word = audio_fifo || chan.transfer_word;
-            let stride = if transfer_word { 4 } else { 2 };
-
-            let update_addr = |addr: &mut u32, ctrl, offset| {
-                match ctrl {
-                    AddressControl::Increment | AddressControl::IncrementAndReload => {
-                        *addr = addr.wrapping_add(offset);
-                    }
-                    AddressControl::Decrement => *addr = addr.wrapping_sub(offset),
-                    AddressControl::Fixed => {}
-                };
+            let unit_cycles = |sequential| {
+                access_cycles(segment.src_addr, segment.transfer_word, sequential, waitcnt)
+                    + access_cycles(segment.dst_addr, segment.transfer_word, sequential, waitcnt)
             };
-            update_addr(&mut chan.src_addr, src_addr_ctrl, stride * blocks);
-            update_addr(&mut chan.dst_addr, dst_addr_ctrl, stride * blocks);
+            let (first_cycles, next_cycles) = (unit_cycles(sequential), unit_cycles(true));
+
+            let mut blocks = 0;
+            while blocks < chan.rem_blocks {
+                let unit_cycles = if blocks == 0 {
+                    first_cycles
+                } else {
+                    next_cycles
+                };
+                if self.cycles < unit_cycles {
+                    break;
+                }
+                self.cycles -= unit_cycles;
+                blocks += 1;
+            }
+            if blocks == 0 {
+                break;
+            }
+            self.bus_owner = Some(chan_idx);
+
+            let stride = if segment.transfer_word { 4 } else { 2 };
+            update_addr(&mut chan.src_addr, segment.src_addr_ctrl, stride * blocks);
+            update_addr(&mut chan.dst_addr, segment.dst_addr_ctrl, stride * blocks);
+            segments[chan_idx] = Some(Segment { blocks, ..segment });
 
             chan.rem_blocks -= blocks;
-            if chan.rem_blocks == 0 {
-                chan.state = State::None;
-                chan.enabled = chan.repeat;
-                if chan.repeat {
-                    if chan.dst_addr_ctrl == AddressControl::IncrementAndReload {
-                        chan.dst_addr = chan.initial_dst_addr;
-                    }
-                    chan.rem_blocks = chan.initial_blocks;
-                }
+            if chan.rem_blocks > 0 {
+                // Lower priority channels must wait for this transfer to finish.
+                break;
+            }
 
-                if chan.irq_enabled {
-                    irq.request(
-                        [
-                            Interrupt::Dma0,
-                            Interrupt::Dma1,
-                            Interrupt::Dma2,
-                            Interrupt::Dma3,
-                        ][chan_idx],
-                    );
+            chan.state = State::None;
+            chan.enabled = chan.repeat;
+            if chan.repeat {
+                if chan.dst_addr_ctrl == AddressControl::IncrementAndReload {
+                    chan.dst_addr = chan.initial_dst_addr;
                 }
+                chan.rem_blocks = chan.initial_blocks;
             }
 
-            return Some(move |bus: &mut B| {
-                let mut src_addr = src_addr;
-                let mut dst_addr = dst_addr;
-
-                for _ in 0..blocks {
-                    if transfer_word {
-                        let value = bus.read_word_aligned(src_addr);
-                        bus.write_word_aligned(dst_addr, value);
-                    } else {
-                        let value = bus.read_hword_aligned(src_addr);
-                        bus.write_hword_aligned(dst_addr, value);
-                    }
-                    update_addr(&mut src_addr, src_addr_ctrl, stride);
-                    update_addr(&mut dst_addr, dst_addr_ctrl, stride);
-                }
-            });
+            if chan.irq_enabled {
+                irq.request(
+                    [
+                        Interrupt::Dma0,
+                        Interrupt::Dma1,
+                        Interrupt::Dma2,
+                        Interrupt::Dma3,
+                    ][chan_idx],
+                );
+            }
+        }
+
+        if !self.transfer_in_progress() {
+            // The bus is handed back to the CPU; leftover cycles aren't kept for later transfers.
+            self.cycles = 0;
+            self.bus_owner = None;
         }
 
-        None
+        segments
+            .iter()
+            .any(Option::is_some)
+            .then_some(move |bus: &mut B| {
+                for segment in segments.iter().flatten() {
+                    segment.transfer(bus);
+                }
+            })
+    }
+
+    /// Whether the state is in range, which is always the case unless it was loaded from a
+    /// malformed save state; such state can panic when stepped.
+    pub(crate) fn is_valid(&self) -> bool {
+        // At most 0x1_0000 units are transferred at a time, and cycles are only left over if
+        // there are too few for a unit, so the bound on those is a loose one.
+        self.chans
+            .iter()
+            .all(|chan| chan.initial_blocks <= u16::MAX.into() && chan.rem_blocks <= 0x1_0000)
+            && self.cycles <= u16::MAX.into()
+            && self
+                .bus_owner
+                .map_or(true, |chan_idx| chan_idx < self.chans.len())
     }
 
     #[must_use]
     pub fn transfer_in_progress(&self) -> bool {
-        self.0.iter().any(|chan| chan.state != State::None)
+        self.chans.iter().any(|chan| chan.state != State::None)
+    }
+
+    /// Returns the state of the channel with the index `chan_idx`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `chan_idx` isn't between 0 and 3.
+    #[must_use]
+    pub fn channel_state(&self, chan_idx: usize) -> ChannelState {
+        let chan = &self.chans[chan_idx];
+        ChannelState {
+            src_addr: chan.initial_src_addr,
+            dst_addr: chan.initial_dst_addr,
+            count: chan.initial_blocks.try_into().unwrap(),
+            control: chan.cached_dmacnt_hi_bits.with_bit(15, chan.enabled),
+            transferring: chan.state != State::None,
+            current_src_addr: chan.src_addr,
+            current_dst_addr: chan.dst_addr,
+            remaining: chan.rem_blocks,
+        }
     }
 }
 
@@ -204,8 +404,10 @@ impl Dma {
             Event::AudioFifoA | Event::AudioFifoB => TimingMode::Special,
         };
 
-        for chan_idx in 0..self.0.len() {
-            if !self.0[chan_idx].enabled || self.0[chan_idx].timing_mode != event_timing_mode {
+        for chan_idx in 0..self.chans.len() {
+            if !self.chans[chan_idx].enabled
+                || self.chans[chan_idx].timing_mode != event_timing_mode
+            {
                 continue;
             }
 
@@ -216,7 +418,7 @@ impl Dma {
             };
             if let Some(fifo_addr) = fifo_addr {
                 if !self.in_audio_fifo_mode(chan_idx)
-                    || self.0[chan_idx].initial_dst_addr != fifo_addr
+                    || self.chans[chan_idx].initial_dst_addr != fifo_addr
                 {
                     continue;
                 }
@@ -229,27 +431,35 @@ impl Dma {
 
 impl Bus for Dma {
     fn read_byte(&mut self, addr: u32) -> u8 {
-        assert!((0xb0..0xe0).contains(&addr), "IO register address OOB");
+        if !(0xb0..0xe0).contains(&addr) {
+            return 0;
+        }
 
-        let chan = &mut self.0[usize::try_from(addr - 0xb0).unwrap() / 12];
+        let chan_idx = usize::try_from(addr - 0xb0).unwrap() / 12;
+        let chan = &mut self.chans[chan_idx];
         match (addr - 0xb0) % 12 {
-            // DMAXCNT
-            10 => chan.cached_dmacnt_hi_bits.bits(..8).try_into().unwrap(),
-            11 => chan
-                .cached_dmacnt_hi_bits
-                .with_bit(15, chan.enabled)
-                .bits(8..)
-                .try_into()
-                .unwrap(),
+            // DMAXCNT (game pak DRQ is only available for DMA3)
+            10 => u8::try_from(chan.cached_dmacnt_hi_bits.bits(..8)).unwrap() & 0xe0,
+            11 => u8::try_from(
+                chan.cached_dmacnt_hi_bits
+                    .with_bit(11, chan_idx == 3 && chan.cached_dmacnt_hi_bits.bit(11))
+                    .with_bit(15, chan.enabled)
+                    .bits(8..),
+            )
+            .unwrap(),
+            // The word count is write-only; so are DMAXSAD and DMAXDAD, but the GBA's bus reads
+            // those as open bus instead
             _ => 0,
         }
     }
 
     fn write_byte(&mut self, addr: u32, value: u8) {
-        assert!((0xb0..0xe0).contains(&addr), "IO register address OOB");
+        if !(0xb0..0xe0).contains(&addr) {
+            return;
+        }
 
         let chan_idx = usize::try_from(addr - 0xb0).unwrap() / 12;
-        let chan = &mut self.0[chan_idx];
+        let chan = &mut self.chans[chan_idx];
         let offset = usize::try_from(addr - 0xb0).unwrap() % 12;
 
         let set_addr_byte = |addr: &mut u32, i, value: u8| match i {
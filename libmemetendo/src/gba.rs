@@ -1,19 +1,46 @@
+use std::{
+    error::Error,
+    fmt::{self, Display, Formatter, Write as _},
+    iter,
+    mem::{size_of, take},
+};
+
 use intbits::Bits;
+use log::warn;
+use serde::{Deserialize, Serialize};
 
 use crate::{
-    arm7tdmi::Cpu,
+    arm7tdmi::{reg::OperationState, Cpu},
     audio::{self, Audio},
     bios::{self, Bios},
     bus,
-    cart::Cartridge,
+    bus::{AlignedExt, Bus as _},
+    cart::{sram_clock::SramClock, Cartridge},
+    debug::{
+        self, io,
+        trace::{AccessKind, IoAccess},
+        verify::StateHashes,
+    },
     dma::Dma,
     irq::Irq,
-    keypad::Keypad,
+    keypad::{Keypad, PollRate},
+    sio::Sio,
     timer::Timers,
-    video::{self, Video},
+    video::{self, Video, HBLANK_DOT, VBLANK_DOT},
+    InvalidConfig,
 };
 
-#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+mod events;
+mod open_bus;
+mod reset;
+mod state;
+
+pub use events::{EventSink, Events, Notice, VideoHooks};
+pub use open_bus::OpenBus;
+pub use reset::RamResetFlags;
+pub use state::Thumbnail;
+
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub enum State {
     #[default]
     Running,
@@ -21,7 +48,7 @@ pub enum State {
     Stopped,
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct HaltControl(pub State);
 
 impl HaltControl {
@@ -32,14 +59,14 @@ impl HaltControl {
 }
 
 impl bus::Bus for HaltControl {
-    fn read_byte(&mut self, addr: u32) -> u8 {
-        assert_eq!(addr, 0x301, "IO register address OOB");
-
+    fn read_byte(&mut self, _addr: u32) -> u8 {
         0
     }
 
     fn write_byte(&mut self, addr: u32, value: u8) {
-        assert_eq!(addr, 0x301, "IO register address OOB");
+        if addr != 0x301 {
+            return;
+        }
 
         self.0 = if value.bit(7) {
             State::Stopped
@@ -49,6 +76,257 @@ impl bus::Bus for HaltControl {
     }
 }
 
+/// Options controlling state that would otherwise depend on the host, so that emulation behaves
+/// identically across runs and platforms (e.g: for replays, netplay or golden tests). The core has
+/// no other sources of nondeterminism, such as the host's clock or an unseeded RNG; the initial
+/// contents of RAM are controlled by `BootState`, as they don't depend on the host.
+///
+/// Only applies when creating the system via `Builder::determinism`; the `Gba` doesn't keep it, so
+/// it can't be changed afterwards.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct DeterminismConfig {
+    /// Time (in seconds since the Unix epoch) that real-time clocks start counting from, rather
+    /// than the host's clock. Only used by the SRAM clock (see `Peripherals::sram_clock`), as no
+    /// cartridge RTC is currently emulated.
+    pub rtc_epoch: u64,
+}
+
+impl Default for DeterminismConfig {
+    fn default() -> Self {
+        Self {
+            rtc_epoch: 946_684_800, // 2000-01-01 00:00:00 UTC
+        }
+    }
+}
+
+impl DeterminismConfig {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+/// Pattern used to fill memory.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub enum MemoryFill {
+    #[default]
+    Zero,
+    /// Every byte is 0xff.
+    Ones,
+    /// Pseudo-random bytes, which is closer to the state real hardware leaves memory in at power on.
+    /// The same seed always produces the same bytes.
+    Random { seed: u64 },
+}
+
+impl MemoryFill {
+    fn bytes(self) -> impl Iterator<Item = u8> {
+        let mut state = match self {
+            Self::Random { seed } => seed,
+            Self::Zero | Self::Ones => 0,
+        };
+
+        iter::repeat_with(move || match self {
+            Self::Zero => 0,
+            Self::Ones => 0xff,
+            Self::Random { .. } => {
+                // SplitMix64.
+                state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+                let mut z = state;
+                z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+                z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+                (z ^ (z >> 31)).to_le_bytes()[0]
+            }
+        })
+    }
+}
+
+/// State of the system when it is reset.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub struct BootState {
+    /// Fill pattern of all RAM regions (EWRAM, IWRAM, palette RAM, VRAM and OAM).
+    pub ram_fill: MemoryFill,
+}
+
+impl BootState {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+/// Optional hardware on the cartridge besides its ROM and backup. Besides `Self::sram_clock`, none
+/// of it is currently emulated, so this is reserved for such hardware; frontends may still use it
+/// (e.g: to show that a game expects a rumble motor).
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub struct Peripherals {
+    /// Whether the cartridge has a real-time clock.
+    pub rtc: bool,
+    /// Whether the cartridge has a rumble motor.
+    pub rumble: bool,
+    /// Whether the cartridge has a solar sensor.
+    pub solar_sensor: bool,
+    /// Offset into save memory to keep a clock at, for ROM hacks that read the time from there;
+    /// see `cart::sram_clock`. It starts at `DeterminismConfig::rtc_epoch`.
+    pub sram_clock: Option<u16>,
+}
+
+impl Peripherals {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+/// Configures a `Gba` before creating it. Unset options take the same defaults as `Gba::new`.
+#[expect(clippy::struct_excessive_bools)]
+#[must_use]
+pub struct Builder {
+    bios_rom: bios::Rom,
+    cart: Cartridge,
+    peripherals: Peripherals,
+    boot_state: BootState,
+    skip_bios: bool,
+    determinism: DeterminismConfig,
+    cpu_multiplier: f32,
+    skip_idle_loops: bool,
+    audio_filter: bool,
+    keypad_poll_rate: PollRate,
+    restrict_oam_access: bool,
+}
+
+impl Builder {
+    /// The BIOS is always executed from `bios_rom`, as it isn't high-level emulated.
+    pub fn new(bios_rom: bios::Rom, cart: Cartridge) -> Self {
+        Self {
+            bios_rom,
+            cart,
+            peripherals: Peripherals::new(),
+            boot_state: BootState::new(),
+            skip_bios: false,
+            determinism: DeterminismConfig::new(),
+            cpu_multiplier: 1.0,
+            skip_idle_loops: false,
+            audio_filter: false,
+            keypad_poll_rate: PollRate::default(),
+            restrict_oam_access: false,
+        }
+    }
+
+    pub fn peripherals(mut self, peripherals: Peripherals) -> Self {
+        self.peripherals = peripherals;
+        self
+    }
+
+    pub fn boot_state(mut self, boot_state: BootState) -> Self {
+        self.boot_state = boot_state;
+        self
+    }
+
+    /// Whether to start executing the cartridge straight away, rather than the BIOS's boot
+    /// animation; see `Gba::reset`.
+    pub fn skip_bios(mut self, skip_bios: bool) -> Self {
+        self.skip_bios = skip_bios;
+        self
+    }
+
+    /// Sets the time the SRAM clock starts at, if there's one; see `Peripherals::sram_clock`.
+    pub fn determinism(mut self, determinism: DeterminismConfig) -> Self {
+        self.determinism = determinism;
+        self
+    }
+
+    /// See `Gba::set_cpu_multiplier`.
+    pub fn cpu_multiplier(mut self, multiplier: f32) -> Self {
+        self.cpu_multiplier = multiplier;
+        self
+    }
+
+    /// Whether to skip idle loops detected in the CPU; see `arm7tdmi::idle::Detector`.
+    pub fn skip_idle_loops(mut self, skip_idle_loops: bool) -> Self {
+        self.skip_idle_loops = skip_idle_loops;
+        self
+    }
+
+    /// Whether to filter audio output like the GBA's analog circuitry; see
+    /// `audio::filter::OutputFilter`.
+    pub fn audio_filter(mut self, audio_filter: bool) -> Self {
+        self.audio_filter = audio_filter;
+        self
+    }
+
+    /// How often the frontend is asked to poll input; see `keypad::PollRate`.
+    pub fn keypad_poll_rate(mut self, poll_rate: PollRate) -> Self {
+        self.keypad_poll_rate = poll_rate;
+        self
+    }
+
+    /// Whether to ignore writes to OAM while it's being read to draw objects, to catch games that
+    /// only show the right objects by luck; see `video::Video::restrict_oam_access`.
+    pub fn restrict_oam_access(mut self, restrict_oam_access: bool) -> Self {
+        self.restrict_oam_access = restrict_oam_access;
+        self
+    }
+
+    /// Creates the `Gba` and resets it, so that it's ready to be stepped.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an option is invalid (e.g: a CPU multiplier that isn't positive).
+    pub fn build(self) -> Result<Gba, InvalidConfig> {
+        let mut gba = Gba::new(self.bios_rom, self.cart);
+        gba.set_cpu_multiplier(self.cpu_multiplier)?;
+        gba.peripherals = self.peripherals;
+        gba.boot_state = self.boot_state;
+        gba.cart.sram_clock = self
+            .peripherals
+            .sram_clock
+            .map(|offset| SramClock::new(offset, self.determinism.rtc_epoch));
+        gba.cpu.idle_loop.enabled = self.skip_idle_loops;
+        gba.audio.output_filter.enabled = self.audio_filter;
+        gba.keypad.poll_rate = self.keypad_poll_rate;
+        gba.video.restrict_oam_access = self.restrict_oam_access;
+        gba.reset(self.skip_bios);
+
+        Ok(gba)
+    }
+}
+
+/// A point in time to step the system until; see `Gba::step_until`.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Event {
+    /// The start of the horizontal blanking period of a scanline.
+    HBlank,
+    /// The start of the vertical blanking period.
+    VBlank,
+    /// The start of the next scanline.
+    Scanline,
+    /// The start of the scanline with the given VCOUNT.
+    VCount(u8),
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct InvalidState(&'static str);
+
+impl Display for InvalidState {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid save state: {}", self.0)
+    }
+}
+
+impl Error for InvalidState {}
+
+impl InvalidState {
+    const ROM_MISMATCH: Self = Self("state is for a different ROM");
+
+    /// Whether the state was refused only because it was saved for a different cartridge ROM
+    /// (e.g: another game, or another revision of the same game), so frontends can offer to load
+    /// it anyway via `Gba::load_state_for_any_rom`.
+    #[must_use]
+    pub fn is_rom_mismatch(&self) -> bool {
+        *self == Self::ROM_MISMATCH
+    }
+}
+
 pub struct Gba {
     pub cpu: Cpu,
     pub irq: Irq,
@@ -60,12 +338,25 @@ pub struct Gba {
     pub video: Video,
     pub audio: Audio,
     pub keypad: Keypad,
+    pub sio: Sio,
     pub bios: Bios,
     pub cart: Cartridge,
+    pub debug: debug::Hooks,
+    /// Where notable events are reported, like the cartridge's backup type being guessed or the
+    /// start of a scanline.
+    pub events: Events,
+    pub peripherals: Peripherals,
+    pub boot_state: BootState,
+    open_bus: OpenBus,
     io_todo: Box<[u8]>,
+    cpu_multiplier: f32,
+    /// Fractional number of CPU steps owed to the CPU; see `Self::set_cpu_multiplier`.
+    cpu_budget: f32,
 }
 
 impl Gba {
+    /// Creates the system with default options; use `Builder` to configure it instead. It must be
+    /// reset before it's stepped.
     #[must_use]
     pub fn new(bios_rom: bios::Rom, cart: Cartridge) -> Self {
         Self {
@@ -79,14 +370,23 @@ impl Gba {
             video: Video::new(),
             audio: Audio::new(),
             keypad: Keypad::new(),
+            sio: Sio::new(),
             bios: Bios::new(bios_rom),
             cart,
+            debug: debug::Hooks::new(),
+            events: Events::default(),
+            peripherals: Peripherals::new(),
+            boot_state: BootState::new(),
+            open_bus: OpenBus::new(),
             io_todo: vec![0; 0x801].into_boxed_slice(),
+            cpu_multiplier: 1.0,
+            cpu_budget: 0.0,
         }
     }
 
     pub fn reset(&mut self, skip_bios: bool) {
         // TODO: reset other hardware components
+        self.fill_ram();
         self.bios.reset();
         self.cpu.reset(&mut bus!(self), skip_bios);
         self.audio.reset(skip_bios);
@@ -97,27 +397,428 @@ impl Gba {
         }
     }
 
+    fn fill_ram(&mut self) {
+        let mut fill = self.boot_state.ram_fill.bytes();
+        for byte in self.iwram.iter_mut().chain(self.ewram.iter_mut()) {
+            *byte = fill.next().unwrap();
+        }
+
+        // Write video memory via the bus, so that caches (e.g: for OAM) are kept up to date. OAM is
+        // filled even if it's busy.
+        let restrict_oam_access = take(&mut self.video.restrict_oam_access);
+        let mut bus = bus!(self);
+        let video_mem = (0x0500_0000..0x0500_0400)
+            .chain(0x0600_0000..0x0601_8000)
+            .chain(0x0700_0000..0x0700_0400);
+        for addr in video_mem.step_by(2) {
+            let value = u16::from_le_bytes([fill.next().unwrap(), fill.next().unwrap()]);
+            bus.write_hword(addr, value);
+        }
+        self.video.restrict_oam_access = restrict_oam_access;
+    }
+
     pub fn step(
         &mut self,
         video_cb: &mut impl video::Callback,
         audio_cb: &mut impl audio::Callback,
     ) {
+        let requested_irqs = self.irq.requested();
+        let (_, vcount) = self.video.position();
         self.keypad.step(&mut self.irq);
 
-        if self.haltcnt.0 == State::Running && !self.dma.transfer_in_progress() {
-            self.cpu.step(&mut bus!(self));
-        }
-        if self.haltcnt.0 != State::Stopped {
+        self.step_cpu();
+        self.cart.step(3);
+        let stopped = self.haltcnt.0 == State::Stopped;
+        if stopped {
+            self.video.step_stopped(video_cb, 3);
+        } else {
             // TODO: actual cycle counting
             self.video.step(video_cb, &mut self.irq, &mut self.dma, 3);
             self.timers.step(&mut self.irq, &mut self.audio, 3);
-            if let Some(do_transfer) = self.dma.step(&mut self.irq, &mut self.cart, 3) {
+            self.sio.step(&mut self.irq, 3);
+            let waitcnt = u16::from_le_bytes([self.io_todo[0x204], self.io_todo[0x205]]);
+            if let Some(do_transfer) = self.dma.step(&mut self.irq, &mut self.cart, waitcnt, 3) {
+                self.debug.cdl.set_dma(true);
                 do_transfer(&mut bus!(self));
+                self.cpu.idle_loop.wake(); // The transfer may have written to polled memory.
             }
+            self.debug.cdl.set_dma(false);
             self.audio.step(audio_cb, &mut self.dma, 3);
         }
+        let frame_ended = self.video.take_frame_ended();
+        if frame_ended {
+            self.audio.flush_samples(audio_cb);
+        }
+        // No scanlines are drawn while stopped, but input is still needed to wake up via the
+        // keypad interrupt.
+        let (_, new_vcount) = self.video.position();
+        if new_vcount != vcount {
+            self.events.start_scanline(&self.video);
+        }
+        if (stopped && frame_ended)
+            || (new_vcount != vcount && self.keypad.poll_rate.polls_at(new_vcount))
+        {
+            self.keypad.request_poll();
+        }
 
+        // Idle loops may poll IF, or memory written by interrupt handlers.
+        if self.irq.requested() != requested_irqs {
+            self.cpu.idle_loop.wake();
+        }
         self.irq.step(&mut self.cpu, &mut self.haltcnt);
+
+        for notice in self.cart.notices.drain(..) {
+            self.events.report(notice);
+        }
+    }
+
+    fn step_cpu(&mut self) {
+        self.cpu_budget += self.cpu_multiplier;
+        while self.cpu_budget >= 1.0 {
+            if self.haltcnt.0 != State::Running || self.dma.transfer_in_progress() {
+                // The CPU can't catch up on steps it missed while stalled.
+                self.cpu_budget = 0.0;
+                break;
+            }
+
+            if self.debug.watchpoints.take_stopping()
+                || (!self.debug.breakpoints.is_empty() && self.is_breakpoint_hit())
+            {
+                self.cpu_budget = 0.0;
+                break;
+            }
+            if self.debug.io_trace.is_enabled() || !self.debug.watchpoints.is_empty() {
+                self.debug
+                    .io_trace
+                    .set_instr_addr(self.cpu.next_instr_addr());
+            }
+            if self.debug.profiler.is_enabled() {
+                self.debug.profiler.record(
+                    self.cpu.next_instr_addr(),
+                    self.cpu.reg.cpsr().state() == OperationState::Thumb,
+                    self.cpu.reg.r[14],
+                    &self.debug.symbols,
+                );
+            }
+            self.cpu.step(&mut bus!(self));
+            self.cpu_budget -= 1.0;
+        }
+    }
+
+    fn is_breakpoint_hit(&mut self) -> bool {
+        let addr = self.cpu.next_instr_addr();
+        if !self.debug.breakpoints.check_addr(addr) {
+            return false;
+        }
+
+        // Conditions are evaluated against the whole system, which owns the breakpoints.
+        let breakpoints = take(&mut self.debug.breakpoints);
+        let hit = breakpoints.iter().filter(|bp| bp.addr == addr).any(|bp| {
+            bp.condition
+                .as_ref()
+                .map_or(true, |cond| cond.eval(self) != 0)
+        });
+        self.debug.breakpoints = breakpoints;
+        if hit {
+            self.debug.breakpoints.set_hit(addr);
+        }
+
+        hit
+    }
+
+    /// Returns how many times faster the CPU runs than on real hardware; see
+    /// `Self::set_cpu_multiplier`.
+    #[must_use]
+    pub fn cpu_multiplier(&self) -> f32 {
+        self.cpu_multiplier
+    }
+
+    /// Sets how many times faster than on real hardware to run the CPU (e.g: 2.0 to overclock it
+    /// to double speed, or 0.5 to underclock it to half speed), while the rest of the system runs
+    /// at its usual speed. Overclocking can reduce lag in demanding games, but may break games that
+    /// depend on the CPU's timing. Defaults to 1.0.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `multiplier` isn't a positive, finite number.
+    pub fn set_cpu_multiplier(&mut self, multiplier: f32) -> Result<(), InvalidConfig> {
+        if !multiplier.is_finite() || multiplier <= 0.0 {
+            return Err(InvalidConfig("CPU multiplier must be positive and finite"));
+        }
+
+        self.cpu_multiplier = multiplier;
+        Ok(())
+    }
+
+    /// Steps the system until `event` happens. Returns false if it didn't happen within two frames'
+    /// worth of steps, which is only possible if the system is stopped or `event` is impossible
+    /// (e.g: a VCOUNT that is out of range).
+    pub fn step_until(
+        &mut self,
+        event: Event,
+        video_cb: &mut impl video::Callback,
+        audio_cb: &mut impl audio::Callback,
+    ) -> bool {
+        // TODO: Gba::step currently always steps 3 cycles.
+        const MAX_STEPS: u32 = 2 * 4 * video::HORIZ_DOTS as u32 * video::VERT_DOTS as u32 / 3;
+
+        for _ in 0..MAX_STEPS {
+            let (old_x, old_y) = self.video.position();
+            self.step(video_cb, audio_cb);
+            let (x, y) = self.video.position();
+
+            let reached = match event {
+                Event::HBlank => old_x < HBLANK_DOT.into() && x >= HBLANK_DOT.into(),
+                Event::VBlank => old_y != y && y == VBLANK_DOT,
+                Event::Scanline => old_y != y,
+                Event::VCount(vcount) => old_y != y && y == vcount,
+            };
+            if reached {
+                return true;
+            }
+        }
+
+        false
+    }
+
+    /// Steps the system until the start of the next scanline; see `Self::step_until`.
+    pub fn step_scanline(
+        &mut self,
+        video_cb: &mut impl video::Callback,
+        audio_cb: &mut impl audio::Callback,
+    ) -> bool {
+        self.step_until(Event::Scanline, video_cb, audio_cb)
+    }
+
+    /// Reads `buf.len()` bytes starting at `addr` via the bus, one byte at a time, as the CPU would
+    /// via LDRB. Reads may have side effects, such as when reading from some IO registers.
+    pub fn read_mem(&mut self, addr: u32, buf: &mut [u8]) {
+        let mut bus = bus!(self);
+        let mut addr = addr;
+        for value in buf {
+            *value = bus.read_byte(addr);
+            addr = addr.wrapping_add(1);
+        }
+    }
+
+    /// Writes `buf` starting at `addr` via the bus, one byte at a time, as the CPU would via STRB.
+    /// Note that 8-bit writes to video memory have special behaviour.
+    pub fn write_mem(&mut self, addr: u32, buf: &[u8]) {
+        self.cpu.idle_loop.wake();
+        let mut bus = bus!(self);
+        let mut addr = addr;
+        for &value in buf {
+            bus.write_byte(addr, value);
+            addr = addr.wrapping_add(1);
+        }
+    }
+
+    pub fn read_byte(&mut self, addr: u32) -> u8 {
+        bus!(self).read_byte(addr)
+    }
+
+    /// Reads a half-word from `addr` with its lowest bit cleared, like the CPU's bus accesses.
+    pub fn read_hword(&mut self, addr: u32) -> u16 {
+        bus!(self).read_hword_aligned(addr)
+    }
+
+    /// Reads a word from `addr` with its lowest 2 bits cleared, like the CPU's bus accesses.
+    pub fn read_word(&mut self, addr: u32) -> u32 {
+        bus!(self).read_word_aligned(addr)
+    }
+
+    /// Like `Self::read_mem`, but without triggering debug hooks (such as IO tracing).
+    pub(crate) fn peek_mem(&mut self, addr: u32, buf: &mut [u8]) {
+        let mut bus = bus!(self);
+        let mut addr = addr;
+        for value in buf {
+            *value = bus.read_byte_untraced(addr);
+            addr = addr.wrapping_add(1);
+        }
+    }
+
+    pub fn write_byte(&mut self, addr: u32, value: u8) {
+        self.cpu.idle_loop.wake();
+        bus!(self).write_byte(addr, value);
+    }
+
+    /// Writes a half-word to `addr` with its lowest bit cleared, like the CPU's bus accesses.
+    pub fn write_hword(&mut self, addr: u32, value: u16) {
+        self.cpu.idle_loop.wake();
+        bus!(self).write_hword_aligned(addr, value);
+    }
+
+    /// Writes a word to `addr` with its lowest 2 bits cleared, like the CPU's bus accesses.
+    pub fn write_word(&mut self, addr: u32, value: u32) {
+        self.cpu.idle_loop.wake();
+        bus!(self).write_word_aligned(addr, value);
+    }
+
+    /// Renders the value of every IO register, with its address and name, one per line. Values are
+    /// read as the CPU would read them, so write-only bits read as zero, but without triggering
+    /// debug hooks.
+    #[must_use]
+    #[expect(clippy::missing_panics_doc)] // writing to a String can't fail
+    pub fn dump_io_map(&mut self) -> String {
+        let mut dump = String::new();
+        for reg in io::REGISTERS {
+            let mut bytes = vec![0; usize::try_from(reg.len).unwrap()];
+            self.peek_mem(reg.addr(), &mut bytes);
+            write!(dump, "{:#010x} {:<11} ", reg.addr(), reg.name).unwrap();
+            match *bytes.as_slice() {
+                [b0] => write!(dump, "{b0:#04x}"),
+                [b0, b1] => write!(dump, "{:#06x}", u16::from_le_bytes([b0, b1])),
+                [b0, b1, b2, b3] => write!(dump, "{:#010x}", u32::from_le_bytes([b0, b1, b2, b3])),
+                _ => bytes.iter().try_for_each(|b| write!(dump, "{b:02x}")),
+            }
+            .unwrap();
+            dump.push('\n');
+        }
+
+        dump
+    }
+
+    /// Saves the state of the emulated hardware, including the cartridge's backup memory. ROMs,
+    /// debug hooks and configuration (like `Self::boot_state`) are not included.
+    #[must_use]
+    pub fn save_state(&self) -> Vec<u8> {
+        self.state_writer().finish()
+    }
+
+    /// Like `Self::save_state`, but also saves `thumbnail` (e.g: of the current frame), which
+    /// `Thumbnail::from_state` reads back without loading the state.
+    #[must_use]
+    pub fn save_state_with_thumbnail(&self, thumbnail: &Thumbnail) -> Vec<u8> {
+        let mut w = self.state_writer();
+        w.chunk(state::THUMBNAIL, thumbnail);
+
+        w.finish()
+    }
+
+    fn state_writer(&self) -> state::Writer {
+        let mut w = state::Writer::new();
+        w.chunk(state::CPU, &self.cpu);
+        w.chunk(state::IRQ, &self.irq);
+        w.chunk(state::HALTCNT, &self.haltcnt);
+        w.chunk(state::TIMERS, &self.timers);
+        w.chunk(state::DMA, &self.dma);
+        w.chunk(state::IWRAM, &self.iwram);
+        w.chunk(state::EWRAM, &self.ewram);
+        w.chunk(state::VIDEO, &self.video.to_saved());
+        w.chunk(state::AUDIO, &self.audio);
+        w.chunk(state::KEYPAD, &self.keypad);
+        w.chunk(state::SIO, &self.sio);
+        w.chunk(state::BIOS_PROTECTION, &self.bios.protection);
+        w.chunk(state::CART_BACKUP, &self.cart.backup);
+        w.chunk(state::IO_TODO, &self.io_todo);
+        w.chunk(state::ROM_INFO, &state::RomInfo::new(self.cart.rom()));
+        w.chunk(
+            state::GBA,
+            &state::CpuSpeed {
+                multiplier: self.cpu_multiplier,
+                budget: self.cpu_budget,
+            },
+        );
+        if let Some(clock) = &self.cart.sram_clock {
+            w.chunk(state::SRAM_CLOCK, clock);
+        }
+
+        w
+    }
+
+    /// Returns hashes of the state of each component saved by `Self::save_state`, such as for
+    /// finding which components' states differ between two points in time.
+    #[must_use]
+    pub fn state_hashes(&self) -> StateHashes {
+        StateHashes::new(state::chunk_hashes(&self.save_state()))
+    }
+
+    /// Loads a state saved by `Self::save_state`, including states saved by older versions. The
+    /// state is expected to be for the currently loaded ROMs; states saved for a different
+    /// cartridge ROM are refused, as loading them would likely crash the game or corrupt its save.
+    /// On failure, the current state is left untouched.
+    ///
+    /// # Errors
+    /// Returns an error if the save state is malformed, from an unsupported version, or for a
+    /// different cartridge ROM (see `InvalidState::is_rom_mismatch`).
+    pub fn load_state(&mut self, buf: &[u8]) -> Result<(), InvalidState> {
+        self.load_state_impl(buf, true)
+    }
+
+    /// Like `Self::load_state`, but loads states saved for a different cartridge ROM, with a
+    /// warning. Useful for states of a game's other revisions or of ROM hacks, which may work.
+    ///
+    /// # Errors
+    /// Returns an error if the save state is malformed or from an unsupported version.
+    pub fn load_state_for_any_rom(&mut self, buf: &[u8]) -> Result<(), InvalidState> {
+        self.load_state_impl(buf, false)
+    }
+
+    fn load_state_impl(&mut self, buf: &[u8], check_rom: bool) -> Result<(), InvalidState> {
+        let state = state::Components::load(buf)?;
+        if let Some(rom_info) = &state.rom_info {
+            let current = state::RomInfo::new(self.cart.rom());
+            if !rom_info.same_rom(&current) {
+                if check_rom {
+                    return Err(InvalidState::ROM_MISMATCH);
+                }
+                warn!(
+                    "loading a save state for a different ROM (CRC32 {:08x}, loaded ROM is CRC32 \
+                     {:08x})",
+                    rom_info.crc32, current.crc32
+                );
+            }
+            if rom_info.core_version != current.core_version {
+                warn!(
+                    "loading a save state from core version {} (current version is {})",
+                    rom_info.core_version, current.core_version
+                );
+            }
+        }
+        if state.iwram.len() != self.iwram.len()
+            || state.ewram.len() != self.ewram.len()
+            || state.io_todo.len() != self.io_todo.len()
+        {
+            return Err(InvalidState("bad memory size"));
+        }
+
+        let idle_loop_enabled = self.cpu.idle_loop.enabled;
+        self.cpu = state.cpu;
+        self.cpu.idle_loop.enabled = idle_loop_enabled;
+        self.irq = state.irq;
+        self.haltcnt = state.haltcnt;
+        self.timers = state.timers;
+        self.dma = state.dma;
+        self.iwram = state.iwram.into_boxed_slice();
+        self.ewram = state.ewram.into_boxed_slice();
+        let restrict_oam_access = self.video.restrict_oam_access;
+        self.video = state.video;
+        self.video.restrict_oam_access = restrict_oam_access;
+        let output_filter = self.audio.output_filter;
+        self.audio = state.audio;
+        self.audio.output_filter = output_filter;
+        self.audio.output_filter.reset();
+        let poll_rate = self.keypad.poll_rate;
+        self.keypad = state.keypad;
+        self.keypad.poll_rate = poll_rate;
+        let sio_device = self.sio.device.take();
+        self.sio = state.sio;
+        self.sio.device = sio_device;
+        self.bios.protection = state.bios_protection;
+        self.cart.backup = state.cart_backup;
+        if let Some(clock) = state.sram_clock {
+            self.cart.sram_clock = Some(clock);
+        }
+        self.io_todo = state.io_todo.into_boxed_slice();
+        if let Some(speed) = state.cpu_speed {
+            self.cpu_multiplier = speed.multiplier;
+            self.cpu_budget = speed.budget;
+        } else {
+            self.cpu_budget = 0.0;
+        }
+        self.cpu.notify_prefetched(&mut bus!(self));
+
+        Ok(())
     }
 }
 
@@ -131,8 +832,12 @@ pub struct Bus<'a> {
     pub video: &'a mut Video,
     pub audio: &'a mut Audio,
     pub keypad: &'a mut Keypad,
+    pub sio: &'a mut Sio,
     pub bios: &'a mut Bios,
     pub cart: &'a mut Cartridge,
+    pub debug: &'a mut debug::Hooks,
+    pub events: &'a mut Events,
+    pub open_bus: &'a mut OpenBus,
     pub io_todo: &'a mut Box<[u8]>,
 }
 
@@ -151,15 +856,247 @@ macro_rules! bus {
             video: &mut $gba.video,
             audio: &mut $gba.audio,
             keypad: &mut $gba.keypad,
+            sio: &mut $gba.sio,
             cart: &mut $gba.cart,
             bios: &mut $gba.bios,
+            debug: &mut $gba.debug,
+            events: &mut $gba.events,
+            open_bus: &mut $gba.open_bus,
             io_todo: &mut $gba.io_todo,
         }
     }};
 }
 
+impl Bus<'_> {
+    fn count_access<T>(
+        &mut self,
+        addr: u32,
+        kind: AccessKind,
+        access: impl FnOnce(&mut Self) -> T,
+    ) -> T {
+        if !self.debug.access_stats.is_counting() {
+            return access(self);
+        }
+
+        self.debug.access_stats.record(addr, kind);
+        self.debug.access_stats.set_suppressed(true);
+        let result = access(self);
+        self.debug.access_stats.set_suppressed(false);
+
+        result
+    }
+
+    #[expect(clippy::cast_possible_truncation)]
+    fn trace_io_read<T: Into<u32> + Copy>(
+        &mut self,
+        addr: u32,
+        read: impl FnOnce(&mut Self) -> T,
+    ) -> T {
+        let len = size_of::<T>() as u8;
+        let traced = self.debug.io_trace.is_tracing(addr, len);
+        let watched = self
+            .debug
+            .watchpoints
+            .is_watching(addr, len, AccessKind::Read);
+        if !traced && !watched {
+            return read(self);
+        }
+
+        self.set_io_debug_suppressed(true);
+        let value = read(self);
+        self.set_io_debug_suppressed(false);
+        let access = IoAccess {
+            kind: AccessKind::Read,
+            addr,
+            len,
+            value: value.into(),
+            instr_addr: self.debug.io_trace.instr_addr(),
+        };
+        if traced {
+            self.debug.io_trace.record(&access, &self.debug.symbols);
+        }
+        if watched {
+            self.debug.watchpoints.set_hit(access);
+        }
+
+        value
+    }
+
+    fn trace_io_write<T: Into<u32> + Copy>(
+        &mut self,
+        addr: u32,
+        value: T,
+        write: impl FnOnce(&mut Self, T),
+    ) {
+        #[expect(clippy::cast_possible_truncation)]
+        let len = size_of::<T>() as u8;
+        let traced = self.debug.io_trace.is_tracing(addr, len);
+        let watched = self
+            .debug
+            .watchpoints
+            .is_watching(addr, len, AccessKind::Write);
+        if !traced && !watched {
+            write(self, value);
+            return;
+        }
+
+        let access = IoAccess {
+            kind: AccessKind::Write,
+            addr,
+            len,
+            value: value.into(),
+            instr_addr: self.debug.io_trace.instr_addr(),
+        };
+        if traced {
+            self.debug.io_trace.record(&access, &self.debug.symbols);
+        }
+        if watched {
+            self.debug.watchpoints.set_hit(access);
+        }
+        self.set_io_debug_suppressed(true);
+        write(self, value);
+        self.set_io_debug_suppressed(false);
+    }
+
+    /// Suppresses IO tracing and watchpoints while a wider access is split into smaller ones.
+    fn set_io_debug_suppressed(&mut self, suppressed: bool) {
+        self.debug.io_trace.set_suppressed(suppressed);
+        self.debug.watchpoints.set_suppressed(suppressed);
+    }
+}
+
 impl bus::Bus for Bus<'_> {
     fn read_byte(&mut self, addr: u32) -> u8 {
+        if self.debug.cdl.is_enabled() && (0x0800_0000..=0x0dff_ffff).contains(&addr) {
+            let rom_len = self.cart.rom().bytes().len();
+            self.debug.cdl.record_read(addr & 0x1ff_ffff, rom_len);
+        }
+        if (0x0400_03ff..=0x04ff_ffff).contains(&addr) {
+            let notice = Notice::InvalidIoAccess { addr, write: false };
+            self.events.report(notice);
+        }
+
+        self.count_access(addr, AccessKind::Read, |bus| {
+            bus.trace_io_read(addr, |bus| bus.read_byte_untraced(addr))
+        })
+    }
+
+    fn read_hword(&mut self, addr: u32) -> u16 {
+        self.count_access(addr, AccessKind::Read, |bus| {
+            bus.trace_io_read(addr, |bus| match addr {
+                // Unmapped I/O; not split into bytes, so it's only reported once.
+                0x0400_03ff..=0x04ff_ffff => {
+                    let notice = Notice::InvalidIoAccess { addr, write: false };
+                    bus.events.report(notice);
+                    bus.open_bus.read_hword(addr)
+                }
+                // Cartridge save memory has an 8-bit bus, which it handles wider accesses to itself.
+                0x0e00_0000..=0x0fff_ffff => bus.cart.read_hword(addr & 0x7ff_ffff),
+                _ => bus::read_hword_as_bytes(bus, addr),
+            })
+        })
+    }
+
+    fn read_word(&mut self, addr: u32) -> u32 {
+        self.count_access(addr, AccessKind::Read, |bus| {
+            bus.trace_io_read(addr, |bus| match addr {
+                0x0400_03ff..=0x04ff_ffff => {
+                    let notice = Notice::InvalidIoAccess { addr, write: false };
+                    bus.events.report(notice);
+                    bus.open_bus.read_word(addr)
+                }
+                0x0e00_0000..=0x0fff_ffff => bus.cart.read_word(addr & 0x7ff_ffff),
+                _ => bus::read_word_as_hwords(bus, addr),
+            })
+        })
+    }
+
+    fn write_byte(&mut self, addr: u32, value: u8) {
+        self.count_access(addr, AccessKind::Write, |bus| {
+            bus.trace_io_write(addr, value, |bus, value| {
+                bus.write_byte_untraced(addr, value);
+            });
+        });
+    }
+
+    fn write_hword(&mut self, addr: u32, value: u16) {
+        self.count_access(addr, AccessKind::Write, |bus| {
+            // Video memory has weird behaviour when writing 8-bit values, so we can't simply
+            // delegate such writes to write_hword_as_bytes.
+            match addr {
+                // Palette RAM
+                0x0500_0000..=0x05ff_ffff => bus.video.palette_ram.write_hword(addr & 0x3ff, value),
+                // VRAM
+                0x0600_0000..=0x06ff_ffff => bus.video.vram().write_hword(addr & 0x1_ffff, value),
+                // OAM
+                0x0700_0000..=0x07ff_ffff => {
+                    if !bus.video.restrict_oam_access || bus.video.is_oam_accessible() {
+                        bus.video.oam.write_hword(addr & 0x3ff, value);
+                    }
+                }
+                _ => bus.trace_io_write(addr, value, |bus, value| match addr {
+                    // Unmapped I/O; see read_hword.
+                    0x0400_03ff..=0x04ff_ffff => {
+                        let notice = Notice::InvalidIoAccess { addr, write: true };
+                        bus.events.report(notice);
+                    }
+                    // Cartridge save memory; see read_hword.
+                    0x0e00_0000..=0x0fff_ffff => bus.cart.write_hword(addr & 0x7ff_ffff, value),
+                    _ => bus::write_hword_as_bytes(bus, addr, value),
+                }),
+            }
+        });
+    }
+
+    fn write_word(&mut self, addr: u32, value: u32) {
+        self.count_access(addr, AccessKind::Write, |bus| {
+            bus.trace_io_write(addr, value, |bus, value| match addr {
+                0x0400_03ff..=0x04ff_ffff => {
+                    let notice = Notice::InvalidIoAccess { addr, write: true };
+                    bus.events.report(notice);
+                }
+                0x0e00_0000..=0x0fff_ffff => bus.cart.write_word(addr & 0x7ff_ffff, value),
+                _ => bus::write_word_as_hwords(bus, addr, value),
+            });
+        });
+    }
+
+    fn prefetch_instr(&mut self, addr: u32) {
+        self.bios.update_protection(addr);
+        self.debug.cdl.set_fetching(true);
+    }
+
+    fn notify_prefetched(&mut self, addr: u32, pipeline: [u32; 2], thumb: bool) {
+        self.open_bus.update(addr, pipeline, thumb);
+    }
+
+    fn notify_execute(&mut self, addr: u32, thumb: bool) {
+        if !self.debug.cdl.is_enabled() {
+            return;
+        }
+
+        self.debug.cdl.set_fetching(false);
+        if (0x0800_0000..=0x0dff_ffff).contains(&addr) {
+            let rom_len = self.cart.rom().bytes().len();
+            self.debug
+                .cdl
+                .record_execute(addr & 0x1ff_ffff, thumb, rom_len);
+        }
+    }
+
+    fn is_volatile(&self, addr: u32) -> bool {
+        match addr {
+            // IE, IF and IME only change when written or when an interrupt is requested.
+            0x0400_0200..=0x0400_0203 | 0x0400_0208..=0x0400_020b => false,
+            // I/O Registers, Cartridge EEPROM, SRAM and Flash
+            0x0400_0000..=0x04ff_ffff | 0x0d00_0000..=0x0fff_ffff => true,
+            _ => false,
+        }
+    }
+}
+
+impl Bus<'_> {
+    fn read_byte_untraced(&mut self, addr: u32) -> u8 {
         match addr {
             // BIOS
             0x0000_0000..=0x0000_3fff => self.bios.read_byte(addr),
@@ -172,10 +1109,12 @@ impl bus::Bus for Bus<'_> {
                 let addr = addr & 0x3ff;
                 #[expect(clippy::match_overlapping_arm)]
                 match addr {
+                    _ if is_io_open_bus(addr) => self.open_bus.read_byte(addr),
                     0x000..=0x056 => self.video.read_byte(addr),
                     0x060..=0x0a7 => self.audio.read_byte(addr),
                     0x0b0..=0x0df => self.dma.read_byte(addr),
                     0x100..=0x10f => self.timers.read_byte(addr),
+                    0x120..=0x12b | 0x134..=0x135 => self.sio.read_byte(addr),
                     0x130..=0x133 => self.keypad.read_byte(addr),
                     0x200..=0x203 | 0x208..=0x20b => self.irq.read_byte(addr),
                     0x301 => self.haltcnt.read_byte(addr),
@@ -191,12 +1130,12 @@ impl bus::Bus for Bus<'_> {
             0x0700_0000..=0x07ff_ffff => self.video.oam.read_byte(addr & 0x3ff),
             // Cartridge
             0x0800_0000..=0x0fff_ffff => self.cart.read_byte(addr & 0x7ff_ffff),
-            // Unused
-            _ => 0xff,
+            // Unused, Unmapped I/O
+            _ => self.open_bus.read_byte(addr),
         }
     }
 
-    fn write_byte(&mut self, addr: u32, value: u8) {
+    fn write_byte_untraced(&mut self, addr: u32, value: u8) {
         match addr {
             // External WRAM
             0x0200_0000..=0x02ff_ffff => self.ewram.write_byte(addr & 0x3_ffff, value),
@@ -211,9 +1150,17 @@ impl bus::Bus for Bus<'_> {
                     0x060..=0x0a7 => self.audio.write_byte(addr, value),
                     0x0b0..=0x0df => self.dma.write_byte(addr, value),
                     0x100..=0x10f => self.timers.write_byte(addr, value),
+                    0x120..=0x12b | 0x134..=0x135 => self.sio.write_byte(addr, value),
                     0x130..=0x133 => self.keypad.write_byte(addr, value),
                     0x200..=0x203 | 0x208..=0x20b => self.irq.write_byte(addr, value),
-                    0x301 => self.haltcnt.write_byte(addr, value),
+                    0x301 => {
+                        self.haltcnt.write_byte(addr, value);
+                        if self.haltcnt.0 == State::Stopped
+                            && (!self.video.is_forced_blank() || self.audio.is_enabled())
+                        {
+                            self.events.report(Notice::StopWithVideoOrSoundOn);
+                        }
+                    }
                     0x000..=0x800 => self.io_todo[usize::try_from(addr).unwrap()] = value, // TODO
                     _ => {}
                 }
@@ -224,28 +1171,156 @@ impl bus::Bus for Bus<'_> {
             0x0600_0000..=0x06ff_ffff => {
                 self.video.vram().write_byte(addr & 0x1_ffff, value);
             }
+            // Unmapped I/O
+            0x0400_03ff..=0x04ff_ffff => {
+                let notice = Notice::InvalidIoAccess { addr, write: true };
+                self.events.report(notice);
+            }
             // Cartridge
             0x0800_0000..=0x0fff_ffff => self.cart.write_byte(addr & 0x7ff_ffff, value),
             // Read-only, Unused, Ignored 8-bit writes to OAM/VRAM
             _ => {}
         }
     }
+}
 
-    fn write_hword(&mut self, addr: u32, value: u16) {
-        // Video memory has weird behaviour when writing 8-bit values, so we can't simply delegate
-        // such writes to write_hword_as_bytes.
-        match addr {
-            // Palette RAM
-            0x0500_0000..=0x05ff_ffff => self.video.palette_ram.write_hword(addr & 0x3ff, value),
-            // VRAM
-            0x0600_0000..=0x06ff_ffff => self.video.vram().write_hword(addr & 0x1_ffff, value),
-            // OAM
-            0x0700_0000..=0x07ff_ffff => self.video.oam.write_hword(addr & 0x3ff, value),
-            _ => bus::write_hword_as_bytes(self, addr, value),
+/// Returns true if `addr` (an offset into the I/O registers) is in a word that's unused or only has
+/// write-only registers, which reads open bus. Other unused or write-only registers read 0.
+fn is_io_open_bus(addr: u32) -> bool {
+    matches!(
+        addr,
+        // BGxHOFS/VOFS, BGxPA-PD/X/Y, WINxH/V, MOSAIC, BLDY, unused
+        0x010..=0x047 | 0x04c..=0x04f | 0x054..=0x05f
+        // Unused, FIFO_A/B, unused
+        | 0x08c..=0x08f | 0x0a0..=0x0af
+        // DMAxSAD/DAD
+        | 0x0b0..=0x0b7 | 0x0bc..=0x0c3 | 0x0c8..=0x0cf | 0x0d4..=0x0db
+        // Unused
+        | 0x0e0..=0x0ff | 0x110..=0x11f | 0x138..=0x13f | 0x144..=0x14f | 0x15c..=0x1ff
+        | 0x20c..=0x2ff | 0x304..
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use crate::{
+        arm7tdmi::{reg::PC_INDEX, Instr},
+        cart,
+    };
+
+    use super::*;
+
+    fn new_gba(ram_fill: MemoryFill) -> Gba {
+        let bios_rom = bios::Rom::new(Rc::from(vec![0; 0x4000])).unwrap();
+        let cart_rom = cart::Rom::new(Rc::from(vec![0; 0x200])).unwrap();
+        let mut gba = Gba::new(bios_rom, Cartridge::from(cart_rom));
+        gba.boot_state = BootState { ram_fill };
+        gba.reset(false);
+
+        gba
+    }
+
+    #[test]
+    fn random_memory_fill_depends_only_on_seed() {
+        let bytes = |seed| -> Vec<_> { MemoryFill::Random { seed }.bytes().take(64).collect() };
+        assert_eq!(bytes(1), bytes(1));
+        assert_ne!(bytes(1), bytes(2));
+        assert!(bytes(1).iter().any(|&b| b != bytes(1)[0]));
+    }
+
+    #[test]
+    fn reset_fills_all_ram() {
+        let mut gba = new_gba(MemoryFill::Ones);
+        assert!(gba.iwram.iter().chain(gba.ewram.iter()).all(|&b| b == 0xff));
+        for addr in [
+            0x0500_0000,
+            0x0500_03fe,
+            0x0600_0000,
+            0x0601_7ffe,
+            0x0700_0000,
+        ] {
+            assert_eq!(gba.read_hword(addr), 0xffff, "{addr:#010x}");
         }
+
+        // Resetting again refills it, including OAM while it's busy.
+        gba.video.restrict_oam_access = true;
+        gba.boot_state.ram_fill = MemoryFill::Zero;
+        gba.reset(false);
+        assert!(gba.iwram.iter().chain(gba.ewram.iter()).all(|&b| b == 0));
+        gba.video.restrict_oam_access = false;
+        assert_eq!(gba.read_hword(0x0700_03fe), 0);
     }
 
-    fn prefetch_instr(&mut self, addr: u32) {
-        self.bios.update_protection(addr);
+    #[test]
+    fn random_fill_continues_across_regions() {
+        let seed = 42;
+        let gba = new_gba(MemoryFill::Random { seed });
+        let expected: Vec<_> = MemoryFill::Random { seed }
+            .bytes()
+            .take(gba.iwram.len() + gba.ewram.len())
+            .collect();
+        let (iwram, ewram) = expected.split_at(gba.iwram.len());
+        assert_eq!(*gba.iwram, *iwram);
+        assert_eq!(*gba.ewram, *ewram);
+    }
+
+    /// Returns a system that has just booted a cartridge ROM of bytes counting up from 0.
+    fn new_booted_gba() -> Gba {
+        let bios_rom = bios::Rom::new(Rc::from(vec![0; 0x4000])).unwrap();
+        let cart_rom: Vec<_> = (0..=u8::MAX).cycle().take(0x200).collect();
+        let cart_rom = cart::Rom::new(Rc::from(cart_rom)).unwrap();
+        let mut gba = Gba::new(bios_rom, Cartridge::from(cart_rom));
+        gba.reset(true);
+
+        gba
+    }
+
+    #[test]
+    fn reads_prefetched_arm_instr_as_open_bus() {
+        let mut gba = new_booted_gba();
+        // Before the instruction at 0x0800_0000 runs, the one at 0x0800_0004 was prefetched last.
+        assert_eq!(gba.read_word(0x1000_0000), 0x0706_0504);
+        assert_eq!(gba.read_byte(0x0000_4001), 0x05);
+        assert_eq!(gba.read_hword(0x0400_0402), 0x0706); // Unmapped I/O
+        assert_eq!(gba.read_word(0x0400_00e0), 0x0706_0504); // Unused I/O
+        assert_eq!(gba.read_word(0x0400_00d4), 0x0706_0504); // DMA3SAD (write-only)
+        assert_eq!(gba.read_hword(0x0400_00dc), 0); // DMA3CNT_L (write-only, but not DMA3CNT_H)
+
+        // While it runs, the one at 0x0800_0008 was.
+        gba.cpu.reg.r[1] = 0x1000_0000;
+        gba.cpu.execute_one(&mut bus!(gba), Instr::Arm(0xe591_0000)); // ldr r0, [r1]
+        assert_eq!(gba.cpu.reg.r[0], 0x0b0a_0908);
+    }
+
+    #[test]
+    fn reads_prefetched_thumb_instrs_as_open_bus() {
+        let mut gba = new_booted_gba();
+        gba.cpu.execute_one(&mut bus!(gba), Instr::Thumb(0x46c0)); // nop
+        assert_eq!(gba.read_word(0x1000_0000), 0x0504_0504);
+
+        // IWRAM has a 32-bit bus, so it's left holding the last 2 prefetched instructions, in an
+        // order depending on their alignment.
+        for i in 0..4 {
+            gba.write_hword(0x0300_0000 + 2 * i, 0x1000 + u16::try_from(i).unwrap());
+        }
+        gba.cpu.reg.r[PC_INDEX] = 0x0300_0000;
+        gba.cpu.reload_pipeline(&mut bus!(gba));
+        gba.cpu.execute_one(&mut bus!(gba), Instr::Thumb(0x46c0)); // nop at 0x0300_0000
+        assert_eq!(gba.read_word(0x1000_0000), 0x1001_1002);
+        gba.cpu.execute_one(&mut bus!(gba), Instr::Thumb(0x46c0)); // nop at 0x0300_0002
+        assert_eq!(gba.read_word(0x1000_0000), 0x1003_1002);
+    }
+
+    #[test]
+    fn loading_state_restores_open_bus() {
+        let mut gba = new_booted_gba();
+        let state = gba.save_state();
+        gba.cpu.execute_one(&mut bus!(gba), Instr::Arm(0xe1a0_0000)); // nop
+        assert_eq!(gba.read_word(0x1000_0000), 0x0b0a_0908);
+
+        gba.load_state(&state).unwrap();
+        assert_eq!(gba.read_word(0x1000_0000), 0x0706_0504);
     }
 }
@@ -0,0 +1,123 @@
+use std::fmt::{self, Display, Formatter};
+
+use log::{log, Level};
+
+use crate::{
+    cart::BackupType,
+    video::{Video, VBLANK_DOT},
+};
+
+/// Something notable that happened in the core, which frontends may want to show the user (e.g:
+/// in a status line) or tests may want to check for; see `EventSink`.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum Notice {
+    /// The cartridge's backup type was guessed from the IDs in its ROM, as it isn't in the header;
+    /// see `cart::Rom::parse_backup_type`.
+    BackupTypeGuessed(BackupType),
+    /// The size of the cartridge's EEPROM was detected from the first DMA transfer to it; either
+    /// `BackupType::Eeprom512B` or `BackupType::Eeprom8KiB`.
+    EepromSizeDetected(BackupType),
+    /// The cartridge's EEPROM was written to before its size could be detected, so it's assumed to
+    /// be 512B.
+    EepromSizeUnknown,
+    /// An address in the IO region that isn't mapped to any register was read or written.
+    /// Reported once per access, at the address the access was made to.
+    InvalidIoAccess { addr: u32, write: bool },
+    /// STOP mode was entered without turning off video (via forced blank) and sound first, as
+    /// Nintendo requires.
+    StopWithVideoOrSoundOn,
+    /// Something the game relies on isn't emulated, so it may misbehave; e.g: backup hardware
+    /// named by `cart::Rom::parse_unsupported_backup`, which is treated as ROM.
+    Unimplemented(&'static str),
+}
+
+impl Display for Notice {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::BackupTypeGuessed(backup_type) => {
+                write!(f, "guessed backup type: {backup_type:?}")
+            }
+            Self::EepromSizeDetected(BackupType::Eeprom8KiB) => write!(f, "detected 8KiB EEPROM"),
+            Self::EepromSizeDetected(_) => write!(f, "detected 512B EEPROM"),
+            Self::EepromSizeUnknown => write!(f, "could not detect EEPROM size; assuming 512B"),
+            Self::InvalidIoAccess { addr, write: false } => {
+                write!(f, "read from unmapped IO address {addr:#010x}")
+            }
+            Self::InvalidIoAccess { addr, write: true } => {
+                write!(f, "write to unmapped IO address {addr:#010x}")
+            }
+            Self::StopWithVideoOrSoundOn => {
+                write!(
+                    f,
+                    "entered STOP mode without disabling video and sound first"
+                )
+            }
+            Self::Unimplemented(feature) => write!(f, "{feature} isn't emulated"),
+        }
+    }
+}
+
+impl Notice {
+    /// The level `Events` logs the notice at if there's no sink.
+    fn level(self) -> Level {
+        match self {
+            Self::BackupTypeGuessed(_) | Self::EepromSizeDetected(_) => Level::Info,
+            Self::EepromSizeUnknown | Self::StopWithVideoOrSoundOn | Self::Unimplemented(_) => {
+                Level::Warn
+            }
+            // Games commonly do this harmlessly, so it would flood the log otherwise.
+            Self::InvalidIoAccess { .. } => Level::Debug,
+        }
+    }
+}
+
+/// Receives the `Notice`s reported by the core, as they happen.
+pub trait EventSink {
+    fn report(&mut self, notice: Notice);
+}
+
+/// Called at video boundaries, so that work can be done at precise points in a frame (e.g: ripping
+/// palettes, or capturing each scanline) without polling the system's state.
+pub trait VideoHooks {
+    /// Called at the start of scanline `y` (as in `VCOUNT`), including those in V-Blank; the
+    /// scanlines above it have been drawn.
+    fn on_scanline(&mut self, _video: &Video, _y: u8) {}
+
+    /// Called at the start of V-Blank, once the frame has been drawn; after `Self::on_scanline`
+    /// for its first scanline.
+    fn on_vblank(&mut self, _video: &Video) {}
+}
+
+/// Where a `Gba` reports its `Notice`s and video boundaries; see `Gba::events`.
+#[derive(Default)]
+pub struct Events {
+    /// Receives the notices. If there's none, they're logged instead.
+    pub sink: Option<Box<dyn EventSink>>,
+    /// Called at video boundaries, if set.
+    pub video_hooks: Option<Box<dyn VideoHooks>>,
+}
+
+impl Events {
+    pub(crate) fn report(&mut self, notice: Notice) {
+        if let Some(sink) = &mut self.sink {
+            sink.report(notice);
+            return;
+        }
+
+        log!(notice.level(), "{notice}");
+    }
+
+    /// Calls the video hooks for the start of the scanline `video` is now on.
+    pub(crate) fn start_scanline(&mut self, video: &Video) {
+        let Some(hooks) = &mut self.video_hooks else {
+            return;
+        };
+
+        let (_, y) = video.position();
+        hooks.on_scanline(video, y);
+        if y == VBLANK_DOT {
+            hooks.on_vblank(video);
+        }
+    }
+}
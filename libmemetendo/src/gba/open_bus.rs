@@ -0,0 +1,48 @@
+use intbits::Bits;
+
+use crate::bus::Bus;
+
+/// What's read from unused memory, and from IO registers that are unused or write-only: nothing
+/// drives the bus for those reads, so it still holds the instruction the CPU prefetched last.
+///
+/// The cartridge drives its own bus, so it doesn't read this value; see `Cartridge`'s `read_byte`.
+/// NOTE: DMA transfers from unused memory also read this value, though they should read the last
+/// value the DMA transferred instead.
+#[derive(Default, Copy, Clone, Debug)]
+pub struct OpenBus(u32);
+
+impl OpenBus {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Updates the value after the CPU prefetched the instruction at `addr`; see
+    /// `Bus::notify_prefetched`.
+    pub fn update(&mut self, addr: u32, pipeline: [u32; 2], thumb: bool) {
+        if !thumb {
+            self.0 = pipeline[1];
+            return;
+        }
+
+        // Thumb instructions are fetched a hword at a time; which ones are left on the 32-bit bus
+        // depends on the width of the memory they were fetched from, and on their alignment.
+        let [prev, latest] = pipeline.map(|instr| instr.bits(..16));
+        let aligned = addr & 0b10 == 0;
+        self.0 = match addr >> 24 {
+            // IWRAM
+            0x03 if aligned => latest.with_bits(16.., prev),
+            // BIOS, IWRAM, OAM
+            0x00 | 0x03 | 0x07 if !aligned => prev.with_bits(16.., latest),
+            // NOTE: for BIOS and OAM, the upper hword should be the instruction after the latest,
+            // which isn't prefetched yet; repeat the latest instead, like the 16-bit memories do.
+            _ => latest.with_bits(16.., latest),
+        };
+    }
+}
+
+impl Bus for OpenBus {
+    fn read_byte(&mut self, addr: u32) -> u8 {
+        self.0.to_le_bytes()[usize::try_from(addr & 0b11).unwrap()]
+    }
+}
@@ -0,0 +1,130 @@
+use std::ops::{BitOr, Range};
+
+use crate::{bus, bus::Bus};
+
+use super::{Gba, HaltControl};
+
+/// What the BIOS's `RegisterRamReset` function (SWI 0x01) clears or resets, as the bits of its
+/// argument; see `Gba::register_ram_reset`.
+#[derive(Default, Copy, Clone, Debug, PartialEq, Eq)]
+pub struct RamResetFlags(u8);
+
+impl RamResetFlags {
+    pub const NONE: Self = Self(0);
+    pub const EWRAM: Self = Self(1 << 0);
+    /// All of IWRAM but its last 0x200 bytes.
+    pub const IWRAM: Self = Self(1 << 1);
+    pub const PALETTE_RAM: Self = Self(1 << 2);
+    pub const VRAM: Self = Self(1 << 3);
+    pub const OAM: Self = Self(1 << 4);
+    /// Also switches the serial port to general-purpose mode.
+    pub const SIO_REGS: Self = Self(1 << 5);
+    pub const SOUND_REGS: Self = Self(1 << 6);
+    /// All IO registers other than the serial and sound registers.
+    pub const OTHER_REGS: Self = Self(1 << 7);
+    pub const ALL: Self = Self(u8::MAX);
+
+    #[must_use]
+    pub const fn from_bits(bits: u8) -> Self {
+        Self(bits)
+    }
+
+    #[must_use]
+    pub const fn bits(self) -> u8 {
+        self.0
+    }
+
+    #[must_use]
+    pub const fn contains(self, flags: Self) -> bool {
+        self.0 & flags.0 == flags.0
+    }
+}
+
+impl BitOr for RamResetFlags {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl Gba {
+    /// Clears memory and resets IO registers according to `flags`, like the BIOS's
+    /// `RegisterRamReset` function (SWI 0x01). Like that function, this always enables forced
+    /// blank, and never clears the last 0x200 bytes of IWRAM, which hold the BIOS's stacks and
+    /// interrupt vector.
+    pub fn register_ram_reset(&mut self, flags: RamResetFlags) {
+        if flags.contains(RamResetFlags::EWRAM) {
+            self.ewram.fill(0);
+        }
+        if flags.contains(RamResetFlags::IWRAM) {
+            self.iwram[..0x7e00].fill(0);
+        }
+
+        // Write via the bus, so that caches (e.g: for OAM) are kept up to date. OAM is accessible
+        // as forced blank is enabled first.
+        let mut bus = bus!(self);
+        bus.write_hword(0x0400_0000, 0x0080); // DISPCNT
+        if flags.contains(RamResetFlags::PALETTE_RAM) {
+            fill(&mut bus, 0x0500_0000..0x0500_0400, 0);
+        }
+        if flags.contains(RamResetFlags::VRAM) {
+            fill(&mut bus, 0x0600_0000..0x0601_8000, 0);
+        }
+        if flags.contains(RamResetFlags::OAM) {
+            fill(&mut bus, 0x0700_0000..0x0700_0400, 0);
+        }
+        if flags.contains(RamResetFlags::SIO_REGS) {
+            fill(&mut bus, 0x0400_0120..0x0400_012c, 0); // SIODATA, SIOCNT, SIOMLT_SEND
+            bus.write_hword(0x0400_0134, 0x8000); // RCNT: general-purpose mode
+            fill(&mut bus, 0x0400_0140..0x0400_015a, 0); // JOYCNT, JOY_RECV, JOY_TRANS, JOYSTAT
+        }
+        if flags.contains(RamResetFlags::SOUND_REGS) {
+            fill(&mut bus, 0x0400_0060..0x0400_0084, 0); // Channels, SOUNDCNT_L, SOUNDCNT_H
+            fill(&mut bus, 0x0400_0090..0x0400_00a0, 0); // Wave RAM
+            bus.write_hword(0x0400_0084, 0); // SOUNDCNT_X
+        }
+        if flags.contains(RamResetFlags::OTHER_REGS) {
+            fill(&mut bus, 0x0400_0004..0x0400_0056, 0); // DISPSTAT, BG, window and blending
+            for addr in [0x0400_0020, 0x0400_0026, 0x0400_0030, 0x0400_0036] {
+                bus.write_hword(addr, 0x100); // BGxPA, BGxPD: identity matrices
+            }
+            fill(&mut bus, 0x0400_00b0..0x0400_00e0, 0); // DMA
+            fill(&mut bus, 0x0400_0100..0x0400_0110, 0); // Timers
+            bus.write_hword(0x0400_0132, 0); // KEYCNT
+            bus.write_hword(0x0400_0200, 0); // IE
+            bus.write_hword(0x0400_0202, 0xffff); // IF: acknowledge all interrupts
+            bus.write_hword(0x0400_0204, 0); // WAITCNT
+            bus.write_hword(0x0400_0208, 0); // IME
+        }
+    }
+
+    /// Resets the system like games do when A, B, Select and Start are held together: via the
+    /// BIOS's `RegisterRamReset` function (SWI 0x01) with `flags`, then its `SoftReset` function
+    /// (SWI 0x00), which restarts the game from the cartridge ROM, or from EWRAM if the byte at
+    /// 0x3007ffa is non-zero (e.g: for multiboot programs), without running the BIOS's boot
+    /// sequence.
+    ///
+    /// Unlike `Self::reset`, hardware not reset by `flags` (e.g: the cartridge's RTC) keeps its
+    /// state.
+    pub fn soft_reset(&mut self, flags: RamResetFlags) {
+        self.register_ram_reset(flags);
+        let entry_addr = if self.iwram[0x7ffa] == 0 {
+            0x0800_0000
+        } else {
+            0x0200_0000
+        };
+        self.iwram[0x7e00..].fill(0);
+
+        self.haltcnt = HaltControl::new();
+        self.cpu.soft_reset(&mut bus!(self), entry_addr);
+        self.cpu.idle_loop.wake();
+    }
+}
+
+/// Writes `value` to every half-word in `range`.
+fn fill(bus: &mut impl Bus, range: Range<u32>, value: u16) {
+    for addr in range.step_by(2) {
+        bus.write_hword(addr, value);
+    }
+}
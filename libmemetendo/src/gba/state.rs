@@ -0,0 +1,984 @@
+//! Save state format.
+//!
+//! A save state starts with a magic number and the version of the format, both 4 bytes. Since
+//! version 2, the rest is a sequence of chunks, each holding the state of a single component. A
+//! chunk has a header of a 4 byte tag, the 4 byte version of its contents' format and the 4 byte
+//! length of its contents (all integers are little-endian), followed by its contents serialized
+//! with bincode.
+//!
+//! Unknown chunks are skipped when loading, so newer versions can add optional chunks without
+//! breaking older versions. When the format of a chunk's contents changes, its version should be
+//! bumped, with older versions migrated when loading. Version 1 of the format had no chunks; it's
+//! migrated as a whole.
+
+use std::collections::HashMap;
+
+use serde::{de::DeserializeOwned, Deserialize, Deserializer, Serialize};
+
+use crate::{
+    arm7tdmi::Cpu,
+    audio::Audio,
+    bios, cart,
+    dma::{Dma, DmaV1},
+    irq::Irq,
+    keypad::Keypad,
+    sio::Sio,
+    timer::{Timers, TimersV1},
+    util::video::FrameBuffer,
+    video::{Video, HBLANK_DOT, VBLANK_DOT},
+};
+
+use super::{HaltControl, InvalidState};
+
+const MAGIC: &[u8; 4] = b"MUBA";
+const VERSION: u32 = 2;
+
+/// Tag, current version and name of a kind of chunk.
+#[derive(Debug, Copy, Clone)]
+pub struct ChunkKind {
+    tag: [u8; 4],
+    version: u32,
+    /// Name of the component whose state the chunk holds.
+    name: &'static str,
+}
+
+pub const CPU: ChunkKind = ChunkKind::new(*b"CPU ", "cpu");
+pub const IRQ: ChunkKind = ChunkKind::new(*b"IRQ ", "irq");
+pub const HALTCNT: ChunkKind = ChunkKind::new(*b"HALT", "haltcnt");
+pub const TIMERS: ChunkKind = ChunkKind::new(*b"TMR ", "timers").with_version(2);
+pub const DMA: ChunkKind = ChunkKind::new(*b"DMA ", "dma").with_version(2);
+pub const IWRAM: ChunkKind = ChunkKind::new(*b"IWRM", "iwram");
+pub const EWRAM: ChunkKind = ChunkKind::new(*b"EWRM", "ewram");
+/// Since version 2, `Video` is followed by the state it doesn't serialize itself; see
+/// `Video::to_saved`.
+pub const VIDEO: ChunkKind = ChunkKind::new(*b"VID ", "video").with_version(3);
+pub const AUDIO: ChunkKind = ChunkKind::new(*b"AUD ", "audio").with_version(2);
+pub const KEYPAD: ChunkKind = ChunkKind::new(*b"KEYP", "keypad");
+/// Optional; states saved before it was added are loaded with the serial I/O registers reset.
+pub const SIO: ChunkKind = ChunkKind::new(*b"SIO ", "sio");
+pub const BIOS_PROTECTION: ChunkKind = ChunkKind::new(*b"BIOS", "bios_protection");
+pub const CART_BACKUP: ChunkKind = ChunkKind::new(*b"BKUP", "cart_backup");
+pub const IO_TODO: ChunkKind = ChunkKind::new(*b"IOTD", "io_todo");
+/// Optional; only saved if the cartridge has an SRAM clock. States without it leave the clock as
+/// is when loaded.
+pub const SRAM_CLOCK: ChunkKind = ChunkKind::new(*b"SCLK", "sram_clock");
+/// Optional; only read by `Thumbnail::from_state`, never when loading.
+pub const THUMBNAIL: ChunkKind = ChunkKind::new(*b"THMB", "thumbnail");
+/// Optional; states saved before it was added are assumed to be for the loaded ROM.
+pub const ROM_INFO: ChunkKind = ChunkKind::new(*b"ROM ", "rom_info");
+/// Optional; states saved before it was added keep the current CPU multiplier when loaded, and owe
+/// the CPU no steps.
+pub const GBA: ChunkKind = ChunkKind::new(*b"GBA ", "gba");
+
+const KINDS: [ChunkKind; 18] = [
+    CPU,
+    IRQ,
+    HALTCNT,
+    TIMERS,
+    DMA,
+    IWRAM,
+    EWRAM,
+    VIDEO,
+    AUDIO,
+    KEYPAD,
+    SIO,
+    BIOS_PROTECTION,
+    CART_BACKUP,
+    IO_TODO,
+    SRAM_CLOCK,
+    THUMBNAIL,
+    ROM_INFO,
+    GBA,
+];
+
+impl ChunkKind {
+    const fn new(tag: [u8; 4], name: &'static str) -> Self {
+        Self {
+            tag,
+            version: 1,
+            name,
+        }
+    }
+
+    const fn with_version(self, version: u32) -> Self {
+        Self { version, ..self }
+    }
+}
+
+pub struct Writer(Vec<u8>);
+
+impl Writer {
+    pub fn new() -> Self {
+        let mut buf = Vec::with_capacity(0x6_0000);
+        buf.extend_from_slice(MAGIC);
+        buf.extend_from_slice(&VERSION.to_le_bytes());
+
+        Self(buf)
+    }
+
+    // Serializing to a Vec only fails if the state contains types unsupported by bincode, which is
+    // impossible.
+    pub fn chunk(&mut self, kind: ChunkKind, value: &(impl Serialize + ?Sized)) {
+        let len = bincode::serialized_size(value).unwrap();
+        self.0.extend_from_slice(&kind.tag);
+        self.0.extend_from_slice(&kind.version.to_le_bytes());
+        self.0
+            .extend_from_slice(&u32::try_from(len).unwrap().to_le_bytes());
+        bincode::serialize_into(&mut self.0, value).unwrap();
+    }
+
+    pub fn finish(self) -> Vec<u8> {
+        self.0
+    }
+}
+
+/// Owned state of each component, as loaded from a save state of any supported version. Its
+/// deserialized form is version 1 of the format.
+#[derive(Deserialize)]
+pub struct Components {
+    pub cpu: Cpu,
+    pub irq: Irq,
+    pub haltcnt: HaltControl,
+    #[serde(deserialize_with = "deserialize_timers_v1")]
+    pub timers: Timers,
+    #[serde(deserialize_with = "deserialize_dma_v1")]
+    pub dma: Dma,
+    pub iwram: Vec<u8>,
+    pub ewram: Vec<u8>,
+    #[serde(deserialize_with = "deserialize_video_v1")]
+    pub video: Video,
+    #[serde(deserialize_with = "deserialize_audio_v1")]
+    pub audio: Audio,
+    pub keypad: Keypad,
+    /// Not in version 1.
+    #[serde(skip)]
+    pub sio: Sio,
+    pub bios_protection: bios::Protection,
+    pub cart_backup: Option<cart::Backup>,
+    pub io_todo: Vec<u8>,
+    /// Not in version 1.
+    #[serde(skip)]
+    pub sram_clock: Option<cart::sram_clock::SramClock>,
+    /// Not in version 1.
+    #[serde(skip)]
+    pub rom_info: Option<RomInfo>,
+    /// Not in version 1.
+    #[serde(skip)]
+    pub cpu_speed: Option<CpuSpeed>,
+}
+
+impl Components {
+    pub fn load(buf: &[u8]) -> Result<Self, InvalidState> {
+        let (version, buf) = split_header(buf)?;
+        let components: Self = match version {
+            // Version 1 serialized the components in this order without chunks.
+            1 => bincode::deserialize(buf).map_err(|_| InvalidState("malformed data"))?,
+            VERSION => Self::load_chunks(buf)?,
+            _ => return Err(InvalidState("unsupported version")),
+        };
+        components.validate()?;
+
+        Ok(components)
+    }
+
+    /// Checks that the state of each component is in range, so that a malformed save state is
+    /// rejected rather than panicking once it's loaded.
+    fn validate(&self) -> Result<(), InvalidState> {
+        let checks = [
+            (self.timers.is_valid(), "bad timers state"),
+            (self.dma.is_valid(), "bad DMA state"),
+            (self.video.is_valid(), "bad video state"),
+            (self.audio.is_valid(), "bad audio state"),
+            (self.sio.is_valid(), "bad serial I/O state"),
+            (
+                self.cart_backup
+                    .as_ref()
+                    .map_or(true, cart::Backup::is_valid),
+                "bad cartridge backup state",
+            ),
+            (
+                self.sram_clock.map_or(true, |clock| clock.is_valid()),
+                "bad SRAM clock state",
+            ),
+            (
+                self.cpu_speed.map_or(true, CpuSpeed::is_valid),
+                "bad CPU speed",
+            ),
+        ];
+        match checks.into_iter().find(|&(valid, _)| !valid) {
+            Some((_, reason)) => Err(InvalidState(reason)),
+            None => Ok(()),
+        }
+    }
+
+    fn load_chunks(mut buf: &[u8]) -> Result<Self, InvalidState> {
+        let mut chunks = Chunks(HashMap::new());
+        while !buf.is_empty() {
+            let chunk;
+            (chunk, buf) = split_chunk(buf)?;
+            if chunks.0.insert(chunk.tag, chunk).is_some() {
+                return Err(InvalidState("duplicate chunk"));
+            }
+        }
+
+        Ok(Self {
+            cpu: chunks.take(CPU)?,
+            irq: chunks.take(IRQ)?,
+            haltcnt: chunks.take(HALTCNT)?,
+            timers: chunks.take_or_migrate(TIMERS, |v1: TimersV1| Timers::from(v1))?,
+            dma: chunks.take_or_migrate(DMA, |v1: DmaV1| Dma::from(v1))?,
+            iwram: chunks.take(IWRAM)?,
+            ewram: chunks.take(EWRAM)?,
+            video: chunks.take_versioned(VIDEO, load_video)?,
+            audio: chunks.take_or_migrate(AUDIO, Audio::migrate_v1)?,
+            keypad: chunks.take(KEYPAD)?,
+            sio: chunks.take_or_default(SIO)?,
+            bios_protection: chunks.take(BIOS_PROTECTION)?,
+            cart_backup: chunks.take(CART_BACKUP)?,
+            io_todo: chunks.take(IO_TODO)?,
+            sram_clock: chunks.take_optional(SRAM_CLOCK)?,
+            rom_info: chunks.take_optional(ROM_INFO)?,
+            cpu_speed: chunks.take_optional(GBA)?,
+        })
+    }
+}
+
+/// How fast the CPU runs relative to the rest of the system; see `Gba::set_cpu_multiplier`.
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CpuSpeed {
+    pub multiplier: f32,
+    /// Fractional number of CPU steps owed to the CPU, which is always less than one between
+    /// steps.
+    pub budget: f32,
+}
+
+impl CpuSpeed {
+    fn is_valid(self) -> bool {
+        self.multiplier.is_finite() && self.multiplier > 0.0 && (0.0..1.0).contains(&self.budget)
+    }
+}
+
+/// Identifies the cartridge ROM and version of the core a state was saved with, so states for a
+/// different game (or revision of it) aren't loaded by mistake.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RomInfo {
+    pub crc32: u32,
+    pub sha1: [u8; 20],
+    pub core_version: String,
+}
+
+impl RomInfo {
+    pub fn new(rom: &cart::Rom) -> Self {
+        let hashes = rom.hashes();
+        Self {
+            crc32: hashes.crc32,
+            sha1: hashes.sha1,
+            core_version: env!("CARGO_PKG_VERSION").to_string(),
+        }
+    }
+
+    pub fn same_rom(&self, other: &Self) -> bool {
+        self.crc32 == other.crc32 && self.sha1 == other.sha1
+    }
+}
+
+/// Downscaled copy of the screen, saved in a state by `Gba::save_state_with_thumbnail`, so
+/// frontends can preview states (e.g: in their load state menus) without loading them.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Thumbnail(Box<[u8]>);
+
+impl Default for Thumbnail {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Thumbnail {
+    /// Width in dots; half the screen's.
+    pub const WIDTH: u8 = HBLANK_DOT / 2;
+    /// Height in dots; half the screen's.
+    pub const HEIGHT: u8 = VBLANK_DOT / 2;
+
+    const LEN: usize = 3 * Self::WIDTH as usize * Self::HEIGHT as usize;
+
+    /// Creates a black thumbnail.
+    #[must_use]
+    pub fn new() -> Self {
+        Self(vec![0; Self::LEN].into_boxed_slice())
+    }
+
+    #[must_use]
+    pub fn from_frame<const STRIDE: usize>(frame: &FrameBuffer<STRIDE>) -> Self {
+        let mut thumbnail = Self::new();
+        thumbnail.capture(frame);
+
+        thumbnail
+    }
+
+    /// Replaces the thumbnail with a downscaled copy of `frame`, where each dot is the average of a
+    /// 2x2 block of the frame's dots.
+    // The average of 4 bytes always fits in a byte, so this doesn't panic.
+    #[expect(clippy::missing_panics_doc)]
+    pub fn capture<const STRIDE: usize>(&mut self, frame: &FrameBuffer<STRIDE>) {
+        let frame_dot = |x: usize, y: usize, i: usize| {
+            u16::from(frame.0[STRIDE * (y * usize::from(HBLANK_DOT) + x) + i])
+        };
+        for (i, rgb) in self.0.chunks_exact_mut(3).enumerate() {
+            let (x, y) = (
+                2 * (i % usize::from(Self::WIDTH)),
+                2 * (i / usize::from(Self::WIDTH)),
+            );
+            for (j, value) in rgb.iter_mut().enumerate() {
+                let sum = frame_dot(x, y, j)
+                    + frame_dot(x + 1, y, j)
+                    + frame_dot(x, y + 1, j)
+                    + frame_dot(x + 1, y + 1, j);
+                *value = u8::try_from(sum / 4).unwrap();
+            }
+        }
+    }
+
+    /// Dots of the thumbnail as RGB bytes, in rows from the top-left, like a `FrameBuffer`.
+    #[must_use]
+    pub fn rgb(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// Reads the thumbnail of a state saved by `Gba::save_state_with_thumbnail`, if it has one.
+    ///
+    /// # Errors
+    /// Returns an error if the state or its thumbnail is malformed, or if the state is from an
+    /// unsupported version.
+    pub fn from_state(buf: &[u8]) -> Result<Option<Self>, InvalidState> {
+        let (version, mut buf) = split_header(buf)?;
+        match version {
+            1 => return Ok(None),
+            VERSION => {}
+            _ => return Err(InvalidState("unsupported version")),
+        }
+
+        while !buf.is_empty() {
+            let chunk;
+            (chunk, buf) = split_chunk(buf)?;
+            if chunk.tag != THUMBNAIL.tag {
+                continue;
+            }
+            if chunk.version != THUMBNAIL.version {
+                return Err(InvalidState("unsupported chunk version"));
+            }
+
+            let thumbnail: Self = deserialize(chunk.data)?;
+            if thumbnail.0.len() != Self::LEN {
+                return Err(InvalidState("malformed data"));
+            }
+            return Ok(Some(thumbnail));
+        }
+
+        Ok(None)
+    }
+}
+
+/// Splits the magic number and version from the start of a state, returning the version and the
+/// rest of `buf`.
+fn split_header(buf: &[u8]) -> Result<(u32, &[u8]), InvalidState> {
+    let Some(buf) = buf.strip_prefix(MAGIC) else {
+        return Err(InvalidState("bad magic number"));
+    };
+    let (version, buf) = buf
+        .split_first_chunk()
+        .ok_or(InvalidState("unexpected end of data"))?;
+
+    Ok((u32::from_le_bytes(*version), buf))
+}
+
+struct Chunk<'a> {
+    tag: [u8; 4],
+    version: u32,
+    data: &'a [u8],
+}
+
+/// Splits the first chunk from `buf`, returning it and the rest of `buf`.
+fn split_chunk(buf: &[u8]) -> Result<(Chunk<'_>, &[u8]), InvalidState> {
+    let (header, rest) = buf
+        .split_first_chunk::<12>()
+        .ok_or(InvalidState("unexpected end of data"))?;
+    let len = u32::from_le_bytes(header[8..].try_into().unwrap());
+    let len = usize::try_from(len).map_err(|_| InvalidState("unexpected end of data"))?;
+    if rest.len() < len {
+        return Err(InvalidState("unexpected end of data"));
+    }
+
+    let (data, rest) = rest.split_at(len);
+    let chunk = Chunk {
+        tag: header[..4].try_into().unwrap(),
+        version: u32::from_le_bytes(header[4..8].try_into().unwrap()),
+        data,
+    };
+
+    Ok((chunk, rest))
+}
+
+/// Hashes the contents of each chunk of a state saved by `Writer`, in order, with the names of
+/// their components.
+pub fn chunk_hashes(buf: &[u8]) -> Vec<(&'static str, u64)> {
+    let mut buf = &buf[MAGIC.len() + 4..];
+    let mut hashes = Vec::with_capacity(KINDS.len());
+    while !buf.is_empty() {
+        let chunk;
+        (chunk, buf) = split_chunk(buf).unwrap();
+        let kind = KINDS.iter().find(|kind| kind.tag == chunk.tag).unwrap();
+        hashes.push((kind.name, fnv1a(chunk.data)));
+    }
+
+    hashes
+}
+
+/// 64-bit FNV-1a hash, which is fast and stable across platforms and Rust versions.
+fn fnv1a(data: &[u8]) -> u64 {
+    data.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+fn deserialize_timers_v1<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Timers, D::Error> {
+    TimersV1::deserialize(deserializer).map(Timers::from)
+}
+
+fn deserialize_dma_v1<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Dma, D::Error> {
+    DmaV1::deserialize(deserializer).map(Dma::from)
+}
+
+fn load_video(version: u32, data: &[u8]) -> Result<Video, InvalidState> {
+    match version {
+        1 => deserialize(data).map(Video::migrate_v1),
+        2 => deserialize(data).map(Video::migrate_v2),
+        _ => deserialize(data).map(Video::from_saved),
+    }
+}
+
+fn deserialize_video_v1<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Video, D::Error> {
+    Video::deserialize(deserializer).map(Video::migrate_v1)
+}
+
+fn deserialize_audio_v1<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Audio, D::Error> {
+    Audio::deserialize(deserializer).map(Audio::migrate_v1)
+}
+
+fn deserialize<T: DeserializeOwned>(data: &[u8]) -> Result<T, InvalidState> {
+    bincode::deserialize(data).map_err(|_| InvalidState("malformed data"))
+}
+
+/// Chunks by tag.
+struct Chunks<'a>(HashMap<[u8; 4], Chunk<'a>>);
+
+impl<'a> Chunks<'a> {
+    fn take_chunk(&mut self, kind: ChunkKind) -> Result<Chunk<'a>, InvalidState> {
+        self.0
+            .remove(&kind.tag)
+            .ok_or(InvalidState("missing chunk"))
+    }
+
+    /// Deserializes the contents of a required chunk.
+    fn take<T: DeserializeOwned>(&mut self, kind: ChunkKind) -> Result<T, InvalidState> {
+        let chunk = self.take_chunk(kind)?;
+        if chunk.version != kind.version {
+            return Err(InvalidState("unsupported chunk version"));
+        }
+
+        deserialize(chunk.data)
+    }
+
+    /// Deserializes the contents of an optional chunk, or returns the default value if it's
+    /// missing.
+    fn take_or_default<T: DeserializeOwned + Default>(
+        &mut self,
+        kind: ChunkKind,
+    ) -> Result<T, InvalidState> {
+        if self.0.contains_key(&kind.tag) {
+            self.take(kind)
+        } else {
+            Ok(T::default())
+        }
+    }
+
+    /// Deserializes the contents of an optional chunk, or returns `None` if it's missing.
+    fn take_optional<T: DeserializeOwned>(
+        &mut self,
+        kind: ChunkKind,
+    ) -> Result<Option<T>, InvalidState> {
+        if self.0.contains_key(&kind.tag) {
+            self.take(kind).map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Deserializes the contents of a required chunk via `load`, given the version of its format;
+    /// for chunks with more than one older version to migrate.
+    fn take_versioned<T>(
+        &mut self,
+        kind: ChunkKind,
+        load: impl FnOnce(u32, &[u8]) -> Result<T, InvalidState>,
+    ) -> Result<T, InvalidState> {
+        let chunk = self.take_chunk(kind)?;
+        if chunk.version == 0 || chunk.version > kind.version {
+            return Err(InvalidState("unsupported chunk version"));
+        }
+
+        load(chunk.version, chunk.data)
+    }
+
+    /// Deserializes the contents of a required chunk, migrating the contents of the previous
+    /// version of its format (deserialized as `U`) via `migrate`.
+    fn take_or_migrate<T: DeserializeOwned, U: DeserializeOwned>(
+        &mut self,
+        kind: ChunkKind,
+        migrate: impl FnOnce(U) -> T,
+    ) -> Result<T, InvalidState> {
+        let chunk = self.take_chunk(kind)?;
+        if chunk.version == kind.version {
+            deserialize(chunk.data)
+        } else if chunk.version.checked_add(1) == Some(kind.version) {
+            deserialize(chunk.data).map(migrate)
+        } else {
+            Err(InvalidState("unsupported chunk version"))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use crate::{
+        cart::{BackupType, Cartridge},
+        gba::{Event, Gba},
+        util,
+    };
+
+    use super::*;
+
+    /// Borrowed state of each component, serialized like version 1 of the format.
+    #[derive(Serialize)]
+    struct ComponentsV1<'a> {
+        cpu: &'a Cpu,
+        irq: &'a Irq,
+        haltcnt: &'a HaltControl,
+        timers: TimersV1,
+        dma: DmaV1,
+        iwram: &'a [u8],
+        ewram: &'a [u8],
+        video: &'a Video,
+        audio: Audio,
+        keypad: &'a Keypad,
+        bios_protection: &'a bios::Protection,
+        cart_backup: &'a Option<cart::Backup>,
+        io_todo: &'a [u8],
+    }
+
+    fn save_state_v1(gba: &Gba) -> Vec<u8> {
+        let mut buf = MAGIC.to_vec();
+        buf.extend_from_slice(&1u32.to_le_bytes());
+        let components = ComponentsV1 {
+            cpu: &gba.cpu,
+            irq: &gba.irq,
+            haltcnt: &gba.haltcnt,
+            timers: TimersV1::from(&gba.timers),
+            dma: DmaV1::from(&gba.dma),
+            iwram: &gba.iwram,
+            ewram: &gba.ewram,
+            video: &gba.video,
+            audio: gba.audio.to_v1(),
+            keypad: &gba.keypad,
+            bios_protection: &gba.bios.protection,
+            cart_backup: &gba.cart.backup,
+            io_todo: &gba.io_todo,
+        };
+        bincode::serialize_into(&mut buf, &components).unwrap();
+
+        buf
+    }
+
+    fn new_gba() -> Gba {
+        let bios_rom = bios::Rom::new(Rc::from(vec![0; 0x4000])).unwrap();
+        // loop:
+        //     add r0, r0, #1
+        //     str r0, [r1], #4
+        //     b   loop
+        let program = [0xe280_0001_u32, 0xe481_0004, 0xeaff_fffc];
+        let cart_rom =
+            cart::Rom::new(program.iter().flat_map(|i| i.to_le_bytes()).collect()).unwrap();
+
+        let mut gba = Gba::new(bios_rom, Cartridge::new(cart_rom, BackupType::Sram32KiB));
+        gba.reset(true);
+        gba.cpu.reg.r[1] = 0x0300_0000;
+        gba.write_byte(0x0e00_0000, 0x42);
+        for _ in 0..1000 {
+            gba.step(
+                &mut util::video::NullCallback,
+                &mut util::audio::NullCallback,
+            );
+        }
+
+        gba
+    }
+
+    /// Returns the chunks of a save state as (tag, version, contents).
+    fn chunks(buf: &[u8]) -> Vec<([u8; 4], u32, &[u8])> {
+        let mut buf = &buf[8..];
+        let mut chunks = Vec::new();
+        while !buf.is_empty() {
+            let tag = buf[..4].try_into().unwrap();
+            let version = u32::from_le_bytes(buf[4..8].try_into().unwrap());
+            let len = usize::try_from(u32::from_le_bytes(buf[8..12].try_into().unwrap())).unwrap();
+            chunks.push((tag, version, &buf[12..12 + len]));
+            buf = &buf[12 + len..];
+        }
+
+        chunks
+    }
+
+    fn encode(chunks: &[([u8; 4], u32, &[u8])]) -> Vec<u8> {
+        let mut buf = MAGIC.to_vec();
+        buf.extend_from_slice(&VERSION.to_le_bytes());
+        for &(tag, version, data) in chunks {
+            buf.extend_from_slice(&tag);
+            buf.extend_from_slice(&version.to_le_bytes());
+            buf.extend_from_slice(&u32::try_from(data.len()).unwrap().to_le_bytes());
+            buf.extend_from_slice(data);
+        }
+
+        buf
+    }
+
+    #[test]
+    fn migrates_version_1() {
+        let mut gba = new_gba();
+        // Version 1 didn't save the phase of the timers' prescaler, so it's reset when migrated.
+        gba.timers = Timers::from(TimersV1::from(&gba.timers));
+        let state = gba.save_state();
+
+        let mut other_gba = new_gba();
+        other_gba.write_byte(0x0e00_0000, 0);
+        other_gba.load_state(&save_state_v1(&gba)).unwrap();
+        assert_eq!(other_gba.save_state(), state);
+        assert_eq!(other_gba.read_byte(0x0e00_0000), 0x42);
+    }
+
+    #[test]
+    fn migrates_dma_chunk_version_1() {
+        let mut gba = new_gba();
+        let state = gba.save_state();
+        let mut state_chunks = chunks(&state);
+        let dma_v1 = bincode::serialize(&DmaV1::from(&gba.dma)).unwrap();
+        let dma_chunk = state_chunks
+            .iter_mut()
+            .find(|(tag, _, _)| *tag == DMA.tag)
+            .unwrap();
+        *dma_chunk = (DMA.tag, 1, &dma_v1);
+
+        gba.load_state(&encode(&state_chunks)).unwrap();
+        assert_eq!(gba.save_state(), state);
+    }
+
+    #[test]
+    fn migrates_timers_chunk_version_1() {
+        let mut gba = new_gba();
+        gba.write_hword(0x0400_0102, 0x0083); // TM0CNT_H: start, divide by 1024
+        gba.timers = Timers::from(TimersV1::from(&gba.timers));
+        let state = gba.save_state();
+        let mut state_chunks = chunks(&state);
+        let timers_v1 = bincode::serialize(&TimersV1::from(&gba.timers)).unwrap();
+        let timers_chunk = state_chunks
+            .iter_mut()
+            .find(|(tag, _, _)| *tag == TIMERS.tag)
+            .unwrap();
+        *timers_chunk = (TIMERS.tag, 1, &timers_v1);
+
+        gba.load_state(&encode(&state_chunks)).unwrap();
+        assert_eq!(gba.save_state(), state);
+    }
+
+    #[test]
+    fn migrates_audio_chunk_version_1() {
+        let mut gba = new_gba();
+        gba.write_hword(0x0400_0088, 0x4222); // SOUNDBIAS: bias 0x222, 8-bit resolution
+        let state = gba.save_state();
+        let mut state_chunks = chunks(&state);
+        let audio_v1 = bincode::serialize(&gba.audio.to_v1()).unwrap();
+        let audio_chunk = state_chunks
+            .iter_mut()
+            .find(|(tag, _, _)| *tag == AUDIO.tag)
+            .unwrap();
+        *audio_chunk = (AUDIO.tag, 1, &audio_v1);
+
+        gba.load_state(&encode(&state_chunks)).unwrap();
+        assert_eq!(gba.save_state(), state);
+    }
+
+    #[test]
+    fn migrates_video_chunk_version_1() {
+        let mut gba = new_gba();
+        let state = gba.save_state();
+        let mut state_chunks = chunks(&state);
+        let video_v1 = bincode::serialize(&gba.video).unwrap();
+        let video_chunk = state_chunks
+            .iter_mut()
+            .find(|(tag, _, _)| *tag == VIDEO.tag)
+            .unwrap();
+        *video_chunk = (VIDEO.tag, 1, &video_v1);
+
+        gba.load_state(&encode(&state_chunks)).unwrap();
+        assert_eq!(gba.save_state(), state);
+    }
+
+    #[test]
+    fn migrates_video_chunk_version_2() {
+        let mut gba = new_gba();
+        let state = gba.save_state();
+        let mut state_chunks = chunks(&state);
+        let (video, line, _) = gba.video.to_saved();
+        let video_v2 = bincode::serialize(&(video, line)).unwrap();
+        let video_chunk = state_chunks
+            .iter_mut()
+            .find(|(tag, _, _)| *tag == VIDEO.tag)
+            .unwrap();
+        *video_chunk = (VIDEO.tag, 2, &video_v2);
+
+        gba.load_state(&encode(&state_chunks)).unwrap();
+        assert_eq!(gba.save_state(), state);
+    }
+
+    #[test]
+    fn keeps_reference_points_written_mid_frame() {
+        let mut gba = new_gba();
+        gba.write_word(0x0400_0028, 0x100); // BG2X: copied to the internal register next scanline
+        let state = gba.save_state();
+
+        let mut other_gba = new_gba_with_rom(&[0xe280_0001, 0xe481_0004, 0xeaff_fffc]);
+        other_gba.load_state(&state).unwrap();
+        for gba in [&mut gba, &mut other_gba] {
+            for event in [Event::Scanline, Event::HBlank] {
+                assert!(gba.step_until(
+                    event,
+                    &mut util::video::NullCallback,
+                    &mut util::audio::NullCallback,
+                ));
+            }
+        }
+        assert_eq!(other_gba.state_hashes(), gba.state_hashes());
+    }
+
+    #[test]
+    fn keeps_line_registers_latched_mid_line() {
+        let mut gba = new_gba();
+        let (x, y) = gba.video.position();
+        assert!(x > 0 && x < HBLANK_DOT.into() && y < VBLANK_DOT);
+        gba.write_hword(0x0400_0000, 0x0403); // DISPCNT: mode 3, BG2 on; latched next scanline
+        let state = gba.save_state();
+
+        let mut other_gba = new_gba_with_rom(&[0xe280_0001, 0xe481_0004, 0xeaff_fffc]);
+        other_gba.load_state(&state).unwrap();
+        assert_eq!(other_gba.save_state(), state);
+    }
+
+    #[test]
+    fn keeps_cpu_budget() {
+        let mut gba = new_gba();
+        gba.set_cpu_multiplier(1.5).unwrap();
+        gba.step(
+            &mut util::video::NullCallback,
+            &mut util::audio::NullCallback,
+        );
+        let state = gba.save_state();
+
+        let mut other_gba = new_gba_with_rom(&[0xe280_0001, 0xe481_0004, 0xeaff_fffc]);
+        other_gba.load_state(&state).unwrap();
+        assert!((other_gba.cpu_multiplier() - 1.5).abs() < f32::EPSILON);
+        for _ in 0..1000 {
+            gba.step(
+                &mut util::video::NullCallback,
+                &mut util::audio::NullCallback,
+            );
+            other_gba.step(
+                &mut util::video::NullCallback,
+                &mut util::audio::NullCallback,
+            );
+        }
+        assert_eq!(other_gba.state_hashes(), gba.state_hashes());
+    }
+
+    #[test]
+    fn rejects_bad_cpu_speed() {
+        let mut gba = new_gba();
+        let state = gba.save_state();
+        let mut state_chunks = chunks(&state);
+        let speed = bincode::serialize(&CpuSpeed {
+            multiplier: 1.0,
+            budget: 2.0,
+        })
+        .unwrap();
+        let chunk = state_chunks.iter_mut().find(|(tag, _, _)| *tag == GBA.tag);
+        chunk.unwrap().2 = &speed;
+        assert_eq!(
+            gba.load_state(&encode(&state_chunks)),
+            Err(InvalidState("bad CPU speed"))
+        );
+    }
+
+    #[test]
+    fn rejects_out_of_range_state() {
+        let mut gba = new_gba();
+        let state = gba.save_state();
+        let mut state_chunks = chunks(&state);
+        let chunk = state_chunks
+            .iter_mut()
+            .find(|(tag, _, _)| *tag == VIDEO.tag);
+        let chunk = chunk.unwrap();
+        let mut video = chunk.2.to_vec();
+        video[..3].copy_from_slice(&[0xff, 0xff, 250]); // x: 0xffff, y: 250
+        chunk.2 = &video;
+        assert_eq!(
+            gba.load_state(&encode(&state_chunks)),
+            Err(InvalidState("bad video state"))
+        );
+        assert_eq!(gba.save_state(), state);
+    }
+
+    #[test]
+    fn skips_unknown_chunks() {
+        let mut gba = new_gba();
+        let state = gba.save_state();
+        let mut state_chunks = chunks(&state);
+        assert_eq!(state_chunks.len(), 16);
+
+        state_chunks.insert(3, (*b"NEW!", 7, b"from the future"));
+        gba.load_state(&encode(&state_chunks)).unwrap();
+        assert_eq!(gba.save_state(), state);
+    }
+
+    #[test]
+    fn defaults_missing_optional_chunks() {
+        let mut gba = new_gba();
+        let state = gba.save_state();
+        let mut state_chunks = chunks(&state);
+        state_chunks.retain(|(tag, _, _)| *tag != SIO.tag);
+
+        gba.write_hword(0x0400_0134, 0x8000); // RCNT: general-purpose mode
+        gba.load_state(&encode(&state_chunks)).unwrap();
+        assert_eq!(gba.save_state(), state);
+    }
+
+    #[test]
+    fn saves_thumbnails() {
+        let mut gba = new_gba();
+        let mut frame = FrameBuffer::<3>::new(0);
+        frame.0[..6].copy_from_slice(&[0x10, 0x20, 0x30, 0x30, 0x40, 0x50]);
+        let thumbnail = Thumbnail::from_frame(&frame);
+        // Averaged with the dot to the right, and the black dots below.
+        assert_eq!(thumbnail.rgb()[..6], [0x10, 0x18, 0x20, 0, 0, 0]);
+
+        let state = gba.save_state_with_thumbnail(&thumbnail);
+        assert_eq!(Thumbnail::from_state(&state), Ok(Some(thumbnail)));
+        assert_eq!(Thumbnail::from_state(&gba.save_state()), Ok(None));
+        assert_eq!(Thumbnail::from_state(&save_state_v1(&gba)), Ok(None));
+
+        // It's ignored when loading.
+        let plain_state = gba.save_state();
+        gba.load_state(&state).unwrap();
+        assert_eq!(gba.save_state(), plain_state);
+    }
+
+    #[test]
+    fn rejects_bad_chunks() {
+        let mut gba = new_gba();
+        let state = gba.save_state();
+        let state_chunks = chunks(&state);
+
+        let mut missing = state_chunks.clone();
+        missing.remove(0);
+        assert_eq!(
+            gba.load_state(&encode(&missing)),
+            Err(InvalidState("missing chunk"))
+        );
+
+        let mut duplicate = state_chunks.clone();
+        duplicate.push(duplicate[0]);
+        assert_eq!(
+            gba.load_state(&encode(&duplicate)),
+            Err(InvalidState("duplicate chunk"))
+        );
+
+        let mut new_version = state_chunks.clone();
+        new_version[0].1 += 1;
+        assert_eq!(
+            gba.load_state(&encode(&new_version)),
+            Err(InvalidState("unsupported chunk version"))
+        );
+
+        let mut malformed = state_chunks;
+        malformed[0].2 = &[1, 2, 3];
+        assert_eq!(
+            gba.load_state(&encode(&malformed)),
+            Err(InvalidState("malformed data"))
+        );
+
+        assert_eq!(gba.save_state(), state);
+    }
+
+    fn new_gba_with_rom(program: &[u32]) -> Gba {
+        let bios_rom = bios::Rom::new(Rc::from(vec![0; 0x4000])).unwrap();
+        let cart_rom =
+            cart::Rom::new(program.iter().flat_map(|i| i.to_le_bytes()).collect()).unwrap();
+        let mut gba = Gba::new(bios_rom, Cartridge::new(cart_rom, BackupType::Sram32KiB));
+        gba.reset(true);
+
+        gba
+    }
+
+    #[test]
+    fn rejects_states_for_other_roms() {
+        let mut gba = new_gba();
+        let state = gba.save_state();
+
+        // b   . (instead of the add)
+        let mut other_gba = new_gba_with_rom(&[0xeaff_fffe, 0xe481_0004, 0xeaff_fffc]);
+        let other_state = other_gba.save_state();
+        let err = other_gba.load_state(&state).unwrap_err();
+        assert_eq!(err, InvalidState("state is for a different ROM"));
+        assert!(err.is_rom_mismatch());
+        assert_eq!(other_gba.save_state(), other_state);
+
+        other_gba.load_state_for_any_rom(&state).unwrap();
+        assert_eq!(other_gba.iwram, gba.iwram);
+        assert_eq!(other_gba.cpu.reg.r, gba.cpu.reg.r);
+
+        // States from before the ROM was saved are assumed to be for it.
+        let mut state_chunks = chunks(&state);
+        state_chunks.retain(|(tag, _, _)| *tag != ROM_INFO.tag);
+        let mut other_gba = new_gba_with_rom(&[0xe280_0001, 0xe481_0004, 0xeaff_fffc]);
+        other_gba.load_state(&encode(&state_chunks)).unwrap();
+        other_gba.load_state(&save_state_v1(&gba)).unwrap();
+
+        gba.load_state(&state).unwrap();
+        assert_eq!(gba.save_state(), state);
+    }
+
+    #[test]
+    fn loads_states_from_other_core_versions() {
+        let mut gba = new_gba();
+        let state = gba.save_state();
+        let mut state_chunks = chunks(&state);
+        let mut rom_info = RomInfo::new(gba.cart.rom());
+        rom_info.core_version = "0.0.0".to_string();
+        let rom_info = bincode::serialize(&rom_info).unwrap();
+        let rom_chunk = state_chunks
+            .iter_mut()
+            .find(|(tag, _, _)| *tag == ROM_INFO.tag)
+            .unwrap();
+        *rom_chunk = (ROM_INFO.tag, 1, &rom_info);
+
+        gba.load_state(&encode(&state_chunks)).unwrap();
+        assert_eq!(gba.save_state(), state);
+    }
+}
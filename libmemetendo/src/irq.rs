@@ -1,4 +1,5 @@
 use intbits::Bits;
+use serde::{Deserialize, Serialize};
 
 use crate::{
     arm7tdmi::{Cpu, Exception},
@@ -24,7 +25,7 @@ pub enum Interrupt {
     GamePak,
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Irq {
     intme: u32,
     inte: u16,
@@ -38,19 +39,38 @@ impl Irq {
     }
 
     pub fn step(&mut self, cpu: &mut Cpu, haltcnt: &mut HaltControl) {
-        if (self.inte.bits(..14) & self.intf) == 0 {
-            return;
+        // In STOP mode, the system clock is stopped, so only interrupts from external sources can
+        // wake the system up.
+        const STOP_WAKE_MASK: u16 = 1 << Interrupt::Serial as u16
+            | 1 << Interrupt::Keypad as u16
+            | 1 << Interrupt::GamePak as u16;
+
+        let mut pending = self.inte.bits(..14) & self.intf;
+        if haltcnt.0 == State::Stopped {
+            pending &= STOP_WAKE_MASK;
+        }
+        if pending != 0 {
+            haltcnt.0 = State::Running;
         }
 
-        haltcnt.0 = State::Running;
-        if self.intme.bit(0) {
+        // The interrupt request is level-triggered, so it's withdrawn if the CPU didn't service it
+        // before it was acknowledged or disabled, such as by a DMA transfer stalling the CPU.
+        if pending != 0 && self.intme.bit(0) {
             cpu.raise_exception(Exception::Interrupt);
+        } else {
+            cpu.cancel_exception(Exception::Interrupt);
         }
     }
 
     pub fn request(&mut self, interrupt: Interrupt) {
         self.intf.set_bit(interrupt as usize, true);
     }
+
+    /// Returns the value of IF: the interrupts that have been requested.
+    #[must_use]
+    pub fn requested(&self) -> u16 {
+        self.intf
+    }
 }
 
 impl Bus for Irq {
@@ -58,16 +78,13 @@ impl Bus for Irq {
         match addr {
             // IE
             0x200 => self.inte.bits(..8).try_into().unwrap(),
-            0x201 => self.inte.bits(8..).try_into().unwrap(),
+            0x201 => self.inte.bits(8..14).try_into().unwrap(),
             // IF
             0x202 => self.intf.bits(..8).try_into().unwrap(),
             0x203 => self.intf.bits(8..).try_into().unwrap(),
             // IME
-            0x208 => self.intme.bits(..8).try_into().unwrap(),
-            0x209 => self.intme.bits(8..16).try_into().unwrap(),
-            0x20a => self.intme.bits(16..24).try_into().unwrap(),
-            0x20b => self.intme.bits(24..).try_into().unwrap(),
-            _ => panic!("IO register address OOB"),
+            0x208 => self.intme.bits(..1).try_into().unwrap(),
+            _ => 0,
         }
     }
 
@@ -88,7 +105,7 @@ impl Bus for Irq {
             0x209 => self.intme.set_bits(8..16, value.into()),
             0x20a => self.intme.set_bits(16..24, value.into()),
             0x20b => self.intme.set_bits(24.., value.into()),
-            _ => panic!("IO register address OOB"),
+            _ => {}
         }
     }
 }
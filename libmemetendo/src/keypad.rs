@@ -1,12 +1,22 @@
+use std::{
+    error::Error,
+    fmt::{self, Display, Formatter},
+    ops::{BitAnd, BitOr, Not},
+    str::FromStr,
+};
+
 use intbits::Bits;
-use strum_macros::EnumCount;
+use serde::{Deserialize, Serialize};
+use strum::{EnumCount, IntoEnumIterator};
+use strum_macros::{EnumCount, EnumIter};
 
 use crate::{
     bus::Bus,
     irq::{Interrupt, Irq},
+    video::{VBLANK_DOT, VERT_DOTS},
 };
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq, EnumCount)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, EnumCount, EnumIter)]
 pub enum Key {
     A,
     B,
@@ -20,17 +30,186 @@ pub enum Key {
     L,
 }
 
-#[derive(Default, Copy, Clone, Debug)]
+/// Set of keys, such as the keys pressed on a `Keypad`. Bit n of `Self::bits` is set for the key
+/// with the discriminant n; unlike `KEYINPUT`, set bits are pressed.
+#[derive(Default, Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct KeyState(u16);
+
+impl KeyState {
+    pub const NONE: Self = Self(0);
+    pub const ALL: Self = Self((1 << Key::COUNT) - 1);
+
+    /// Creates a set from its bits, ignoring bits that don't correspond to keys.
+    #[must_use]
+    pub const fn from_bits_truncate(bits: u16) -> Self {
+        Self(bits & Self::ALL.0)
+    }
+
+    #[must_use]
+    pub const fn bits(self) -> u16 {
+        self.0
+    }
+
+    #[must_use]
+    pub const fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    #[must_use]
+    pub fn contains(self, key: Key) -> bool {
+        self.0.bit(key as usize)
+    }
+
+    pub fn set(&mut self, key: Key, pressed: bool) {
+        self.0.set_bit(key as usize, pressed);
+    }
+
+    /// Returns the keys in the set, in the order of their discriminants.
+    pub fn keys(self) -> impl Iterator<Item = Key> {
+        Key::iter().filter(move |&key| self.contains(key))
+    }
+}
+
+impl From<Key> for KeyState {
+    fn from(key: Key) -> Self {
+        Self(1 << key as u16)
+    }
+}
+
+impl FromIterator<Key> for KeyState {
+    fn from_iter<T: IntoIterator<Item = Key>>(iter: T) -> Self {
+        iter.into_iter()
+            .fold(Self::NONE, |state, key| state | key.into())
+    }
+}
+
+impl BitOr for KeyState {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl BitAnd for KeyState {
+    type Output = Self;
+
+    fn bitand(self, rhs: Self) -> Self {
+        Self(self.0 & rhs.0)
+    }
+}
+
+impl Not for KeyState {
+    type Output = Self;
+
+    fn not(self) -> Self {
+        Self(!self.0 & Self::ALL.0)
+    }
+}
+
+/// Notified of keys pressed or released by `Keypad::set_state_with_callback`.
+pub trait Callback {
+    fn key_changed(&mut self, key: Key, pressed: bool);
+}
+
+impl<F: FnMut(Key, bool)> Callback for F {
+    fn key_changed(&mut self, key: Key, pressed: bool) {
+        self(key, pressed);
+    }
+}
+
+/// How often a `Keypad` asks the frontend to sample the host's input into it; see
+/// `Keypad::take_poll`.
+///
+/// Games usually read `KEYINPUT` once a frame, but not at the same point in it. Polling more often
+/// shortens the time between a key being pressed on the host and the game being able to see it,
+/// which matters most for games with tight timing (e.g: fighting or rhythm games), but it can't
+/// make a game react faster than it reads its input. The cost is polling the host more often, and
+/// for frontends that emulate on another thread, synchronizing with it more often. The input seen
+/// by the game also depends on when it was polled, so input is only reproducible (e.g: for
+/// recordings) when polled at the same rate.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum PollRate {
+    /// At the start of the vertical blanking period (VCOUNT 160).
+    #[default]
+    Frame,
+    /// At the start of the vertical blanking period and of the next frame (VCOUNT 160 and 0).
+    HalfFrame,
+    /// At the start of every scanline.
+    Scanline,
+}
+
+impl PollRate {
+    /// Whether input is polled at the start of the scanline with the given VCOUNT.
+    #[must_use]
+    pub fn polls_at(self, vcount: u8) -> bool {
+        match self {
+            Self::Frame => vcount == VBLANK_DOT,
+            Self::HalfFrame => vcount == VBLANK_DOT || vcount == 0,
+            Self::Scanline => true,
+        }
+    }
+
+    #[must_use]
+    pub fn polls_per_frame(self) -> u32 {
+        match self {
+            Self::Frame => 1,
+            Self::HalfFrame => 2,
+            Self::Scanline => VERT_DOTS.into(),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct InvalidPollRate;
+
+impl Display for InvalidPollRate {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "Expected \"frame\", \"half-frame\" or \"scanline\"")
+    }
+}
+
+impl Error for InvalidPollRate {}
+
+impl FromStr for PollRate {
+    type Err = InvalidPollRate;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "frame" => Ok(Self::Frame),
+            "half-frame" => Ok(Self::HalfFrame),
+            "scanline" => Ok(Self::Scanline),
+            _ => Err(InvalidPollRate),
+        }
+    }
+}
+
+impl Display for PollRate {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Frame => write!(f, "frame"),
+            Self::HalfFrame => write!(f, "half-frame"),
+            Self::Scanline => write!(f, "scanline"),
+        }
+    }
+}
+
+#[derive(Default, Copy, Clone, Debug, Serialize, Deserialize)]
 struct IrqControl {
     keys: u16,
     enabled: bool,
     all_pressed: bool,
 }
 
-#[derive(Default, Copy, Clone, Debug)]
+#[derive(Default, Copy, Clone, Debug, Serialize, Deserialize)]
 pub struct Keypad {
     pressed: u16,
     keycnt: IrqControl,
+    /// Not saved in save states, as it's configured by the frontend.
+    #[serde(skip)]
+    pub poll_rate: PollRate,
+    #[serde(skip)]
+    poll_requested: bool,
 }
 
 impl Keypad {
@@ -45,7 +224,7 @@ impl Keypad {
         }
 
         let do_irq = if self.keycnt.all_pressed {
-            self.pressed != 0 && self.keycnt.keys & self.pressed == self.pressed
+            self.keycnt.keys != 0 && self.keycnt.keys & self.pressed == self.keycnt.keys
         } else {
             self.keycnt.keys & self.pressed != 0
         };
@@ -54,9 +233,204 @@ impl Keypad {
         }
     }
 
+    pub(crate) fn request_poll(&mut self) {
+        self.poll_requested = true;
+    }
+
+    /// Whether the frontend should sample the host's input into the keypad now, which is requested
+    /// at the points given by `Self::poll_rate`, and once a frame while the system is stopped.
+    /// Clears the request.
+    pub fn take_poll(&mut self) -> bool {
+        std::mem::take(&mut self.poll_requested)
+    }
+
     pub fn set_pressed(&mut self, key: Key, pressed: bool) {
         self.pressed.set_bit(key as usize, pressed);
     }
+
+    #[must_use]
+    pub fn is_pressed(&self, key: Key) -> bool {
+        self.pressed.bit(key as usize)
+    }
+
+    pub fn pressed_keys(&self) -> impl Iterator<Item = Key> {
+        self.state().keys()
+    }
+
+    /// Releases every key, then presses `keys`.
+    pub fn set_pressed_keys(&mut self, keys: impl IntoIterator<Item = Key>) {
+        self.set_state(keys.into_iter().collect());
+    }
+
+    /// Returns the set of pressed keys.
+    #[must_use]
+    pub fn state(&self) -> KeyState {
+        KeyState(self.pressed)
+    }
+
+    /// Presses exactly the keys in `state`, such as for injecting input recorded by `Self::state`.
+    pub fn set_state(&mut self, state: KeyState) {
+        self.pressed = state.bits();
+    }
+
+    /// Like `Self::set_state`, but notifies `cb` of each key that was pressed or released as a
+    /// result, in the order of their discriminants.
+    pub fn set_state_with_callback(&mut self, state: KeyState, cb: &mut impl Callback) {
+        let changed = KeyState(self.pressed ^ state.bits());
+        self.set_state(state);
+        for key in changed.keys() {
+            cb.key_changed(key, state.contains(key));
+        }
+    }
+}
+
+/// Helper for turbo (autofire) buttons, which repeatedly press and release keys while held.
+#[derive(Copy, Clone, Debug)]
+pub struct Turbo {
+    held: u16,
+    interval: u32,
+    frame: u32,
+}
+
+impl Default for Turbo {
+    fn default() -> Self {
+        Self::new(2)
+    }
+}
+
+impl Turbo {
+    /// Largest interval; longer ones are clamped to it, so that a whole cycle's length fits a u32.
+    pub const MAX_INTERVAL: u32 = u32::MAX / 2;
+
+    /// Creates a helper that toggles its held keys every `interval` frames (clamped to between 1
+    /// and `Self::MAX_INTERVAL`).
+    #[must_use]
+    pub fn new(interval: u32) -> Self {
+        Self {
+            held: 0,
+            interval: interval.clamp(1, Self::MAX_INTERVAL),
+            frame: 0,
+        }
+    }
+
+    #[must_use]
+    pub fn interval(&self) -> u32 {
+        self.interval
+    }
+
+    pub fn set_interval(&mut self, interval: u32) {
+        self.interval = interval.clamp(1, Self::MAX_INTERVAL);
+        self.frame %= 2 * self.interval;
+    }
+
+    /// Sets whether the turbo button for `key` is held. The first frame a turbo button is held, its
+    /// key is pressed.
+    pub fn set_held(&mut self, key: Key, held: bool) {
+        if self.held == 0 && held {
+            self.frame = 0;
+        }
+        self.held.set_bit(key as usize, held);
+    }
+
+    #[must_use]
+    pub fn is_held(&self, key: Key) -> bool {
+        self.held.bit(key as usize)
+    }
+
+    /// Advances the press-and-release cycle by `frames` frames.
+    // Panic is impossible as the frame is less than the cycle's length, which fits a u32.
+    #[expect(clippy::missing_panics_doc)]
+    pub fn step(&mut self, frames: u32) {
+        let frame = (u64::from(self.frame) + u64::from(frames)) % (2 * u64::from(self.interval));
+        self.frame = frame.try_into().unwrap();
+    }
+
+    /// Presses the keys of held turbo buttons on `keypad` during the pressed part of the cycle.
+    /// Keys pressed by other means are unaffected.
+    pub fn apply(&self, keypad: &mut Keypad) {
+        if self.frame < self.interval {
+            keypad.pressed |= self.held;
+        }
+    }
+}
+
+/// Helper for input macros: the keys pressed over a short run of consecutive frames, recorded from
+/// a keypad and then replayed on demand (e.g: for repetitive in-game operations).
+#[derive(Clone, Debug, Default)]
+pub struct Macro {
+    frames: Vec<KeyState>,
+    /// Index of the frame being replayed, if playing.
+    playing: Option<usize>,
+    recording: bool,
+}
+
+impl Macro {
+    /// Recording stops after this many frames (a minute).
+    pub const MAX_FRAMES: usize = 3600;
+
+    /// Creates a macro that replays `frames`, truncated to `Self::MAX_FRAMES`.
+    #[must_use]
+    pub fn new(mut frames: Vec<KeyState>) -> Self {
+        frames.truncate(Self::MAX_FRAMES);
+        Self {
+            frames,
+            playing: None,
+            recording: false,
+        }
+    }
+
+    /// The keys pressed on each frame of the macro.
+    #[must_use]
+    pub fn frames(&self) -> &[KeyState] {
+        &self.frames
+    }
+
+    /// Discards the macro's frames and starts recording new ones, stopping any replay.
+    pub fn start_recording(&mut self) {
+        self.frames.clear();
+        self.playing = None;
+        self.recording = true;
+    }
+
+    pub fn stop_recording(&mut self) {
+        self.recording = false;
+    }
+
+    #[must_use]
+    pub fn is_recording(&self) -> bool {
+        self.recording
+    }
+
+    /// Replays the macro from its first frame. Does nothing while recording.
+    pub fn play(&mut self) {
+        if !self.recording && !self.frames.is_empty() {
+            self.playing = Some(0);
+        }
+    }
+
+    #[must_use]
+    pub fn is_playing(&self) -> bool {
+        self.playing.is_some()
+    }
+
+    /// Advances by a frame: while recording, the keys pressed on `keypad` during the frame are
+    /// recorded as its keys; while playing, moves on to the next frame of the macro.
+    pub fn step(&mut self, keypad: &Keypad) {
+        if self.recording {
+            self.frames.push(keypad.state());
+            self.recording = self.frames.len() < Self::MAX_FRAMES;
+        } else if let Some(frame) = self.playing {
+            self.playing = Some(frame + 1).filter(|&frame| frame < self.frames.len());
+        }
+    }
+
+    /// Presses the keys of the frame being replayed on `keypad`. Keys pressed by other means are
+    /// unaffected.
+    pub fn apply(&self, keypad: &mut Keypad) {
+        if let Some(frame) = self.playing {
+            keypad.pressed |= self.frames[frame].bits();
+        }
+    }
 }
 
 impl Bus for Keypad {
@@ -64,14 +438,14 @@ impl Bus for Keypad {
         match addr {
             // KEYINPUT
             0x130 => (!self.pressed).bits(..8).try_into().unwrap(),
-            0x131 => (!self.pressed).bits(8..).try_into().unwrap(),
+            0x131 => (!self.pressed).bits(8..10).try_into().unwrap(),
             // KEYCNT
             0x132 => self.keycnt.keys.bits(..8).try_into().unwrap(),
             0x133 => u8::try_from(self.keycnt.keys.bits(8..))
                 .unwrap()
                 .with_bit(6, self.keycnt.enabled)
                 .with_bit(7, self.keycnt.all_pressed),
-            _ => panic!("IO register address OOB"),
+            _ => 0,
         }
     }
 
@@ -79,9 +453,162 @@ impl Bus for Keypad {
         match addr {
             // KEYCNT
             0x132 => self.keycnt.keys.set_bits(..8, value.into()),
-            0x133 => self.keycnt.keys.set_bits(8.., value.into()),
-            0x130 | 0x131 => {}
-            _ => panic!("IO register address OOB"),
+            0x133 => {
+                self.keycnt.keys.set_bits(8..10, value.bits(..2).into());
+                self.keycnt.enabled = value.bit(6);
+                self.keycnt.all_pressed = value.bit(7);
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns whether `turbo` presses A on each of the next `frames` frames.
+    fn turbo_presses(turbo: &mut Turbo, frames: usize) -> Vec<bool> {
+        (0..frames)
+            .map(|_| {
+                let mut keypad = Keypad::new();
+                turbo.apply(&mut keypad);
+                turbo.step(1);
+                keypad.is_pressed(Key::A)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn turbo_presses_and_releases_every_interval() {
+        let mut turbo = Turbo::new(2);
+        assert_eq!(turbo_presses(&mut turbo, 4), [false; 4]);
+
+        turbo.set_held(Key::A, true);
+        assert_eq!(
+            turbo_presses(&mut turbo, 6),
+            [true, true, false, false, true, true]
+        );
+
+        // Holding another turbo button doesn't restart the cycle.
+        turbo.set_held(Key::B, true);
+        assert_eq!(turbo_presses(&mut turbo, 2), [false, false]);
+
+        // Releasing all of them does.
+        turbo.set_held(Key::A, false);
+        turbo.set_held(Key::B, false);
+        turbo.set_held(Key::A, true);
+        assert_eq!(turbo_presses(&mut turbo, 2), [true, true]);
+
+        turbo.set_interval(0);
+        assert_eq!(turbo.interval(), 1);
+        assert_eq!(turbo_presses(&mut turbo, 4), [true, false, true, false]);
+    }
+
+    #[test]
+    fn turbo_leaves_other_keys_pressed() {
+        let mut turbo = Turbo::new(1);
+        turbo.set_held(Key::A, true);
+        turbo.step(1);
+        let mut keypad = Keypad::new();
+        keypad.set_pressed(Key::A, true);
+        keypad.set_pressed(Key::Start, true);
+        turbo.apply(&mut keypad);
+        assert!(keypad.is_pressed(Key::A) && keypad.is_pressed(Key::Start));
+    }
+
+    #[test]
+    fn turbo_clamps_long_intervals() {
+        let mut turbo = Turbo::new(u32::MAX);
+        assert_eq!(turbo.interval(), Turbo::MAX_INTERVAL);
+        turbo.set_held(Key::A, true);
+        turbo.step(u32::MAX);
+        turbo.step(u32::MAX);
+        // 2 * (u32::MAX - 1) frames into a cycle of u32::MAX - 1 frames.
+        assert_eq!(turbo_presses(&mut turbo, 1), [true]);
+
+        turbo.set_interval(u32::MAX);
+        assert_eq!(turbo.interval(), Turbo::MAX_INTERVAL);
+    }
+
+    #[test]
+    fn key_state() {
+        let state: KeyState = [Key::A, Key::Start, Key::L].into_iter().collect();
+        assert_eq!(state.bits(), 0x209);
+        assert!(state.contains(Key::Start) && !state.contains(Key::B));
+        assert_eq!(
+            state.keys().collect::<Vec<_>>(),
+            [Key::A, Key::Start, Key::L]
+        );
+        assert_eq!(KeyState::from_bits_truncate(0xfe09), KeyState(0x209));
+        assert_eq!((!state).bits(), 0x1f6);
+        assert_eq!(state & !state, KeyState::NONE);
+        assert_eq!(state | !state, KeyState::ALL);
+
+        let mut keypad = Keypad::new();
+        keypad.set_state(state);
+        assert_eq!(keypad.state(), state);
+        assert!(keypad.is_pressed(Key::L));
+        // KEYINPUT has bits cleared for pressed keys.
+        assert_eq!(keypad.read_hword(0x130), 0x1f6);
+    }
+
+    #[test]
+    fn set_state_notifies_edges() {
+        let mut keypad = Keypad::new();
+        keypad.set_pressed(Key::A, true);
+        keypad.set_pressed(Key::B, true);
+
+        let mut edges = Vec::new();
+        let state = KeyState::from(Key::B) | Key::Up.into();
+        keypad.set_state_with_callback(state, &mut |key, pressed| edges.push((key, pressed)));
+        assert_eq!(edges, [(Key::A, false), (Key::Up, true)]);
+        assert_eq!(keypad.state(), state);
+
+        edges.clear();
+        keypad.set_state_with_callback(state, &mut |key, pressed| edges.push((key, pressed)));
+        assert!(edges.is_empty());
+    }
+
+    #[test]
+    fn macro_records_and_replays() {
+        let mut keypad = Keypad::new();
+        let mut input_macro = Macro::default();
+        input_macro.start_recording();
+        for keys in [[Key::A].as_slice(), &[], &[Key::Up, Key::B]] {
+            keypad.set_pressed_keys(keys.iter().copied());
+            input_macro.step(&keypad);
+        }
+        input_macro.stop_recording();
+        assert_eq!(input_macro.frames().len(), 3);
+
+        keypad.set_pressed_keys([Key::L]);
+        input_macro.play();
+        let mut replayed = Vec::new();
+        while input_macro.is_playing() {
+            let mut frame_keypad = keypad;
+            input_macro.apply(&mut frame_keypad);
+            replayed.push(frame_keypad.state());
+            input_macro.step(&keypad);
+        }
+        assert_eq!(
+            replayed,
+            [
+                KeyState::from(Key::A) | Key::L.into(),
+                Key::L.into(),
+                [Key::Up, Key::B, Key::L].into_iter().collect(),
+            ]
+        );
+    }
+
+    #[test]
+    fn macro_stops_recording_at_max_frames() {
+        let mut input_macro = Macro::default();
+        input_macro.start_recording();
+        for _ in 0..=Macro::MAX_FRAMES {
+            input_macro.step(&Keypad::new());
         }
+        assert!(!input_macro.is_recording());
+        assert_eq!(input_macro.frames().len(), Macro::MAX_FRAMES);
     }
 }
@@ -1,20 +1,41 @@
+//! Library for emulating the Game Boy Advance.
+//!
+//! A ROM shouldn't be able to make the emulator panic, no matter what it executes or which memory
+//! and IO registers it accesses; this is relied on by frontends that run untrusted ROMs, like Web
+//! Memetendo, where a panic takes down the whole page. This is only as certain as the `run` fuzz
+//! target in `fuzz`, which runs random ROMs that also make random memory accesses; any panic it
+//! finds is a bug. The same goes for save states: `Gba::load_state` rejects malformed ones rather
+//! than loading state that panics later, as checked by the `save_state` fuzz target, which loads
+//! random states and runs them, and by `run`, which saves and reloads the state it ends up in.
+//!
+//! Systems share no state (there are no statics), so any number of `Gba`s can run in one process,
+//! each on its own thread if needed; `sio::link` connects two of them with a link cable.
+
 #![warn(clippy::pedantic)]
 
 use core::fmt;
 use std::{
-    error::Error,
+    error,
     fmt::{Display, Formatter},
 };
 
+use cart::{dat::InvalidDat, InvalidBackup};
+use debug::{expr::InvalidExpr, symbols::InvalidElf};
+use gba::InvalidState;
+
 pub mod arm7tdmi;
 pub mod audio;
 pub mod bios;
 pub mod bus;
 pub mod cart;
+pub mod cheat;
+pub mod debug;
 pub mod dma;
 pub mod gba;
 pub mod irq;
 pub mod keypad;
+pub mod sio;
+pub mod storage;
 pub mod timer;
 pub mod util;
 pub mod video;
@@ -28,4 +49,90 @@ impl Display for InvalidRomSize {
     }
 }
 
-impl Error for InvalidRomSize {}
+impl error::Error for InvalidRomSize {}
+
+/// A setting was given a value it doesn't support.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct InvalidConfig(&'static str);
+
+impl Display for InvalidConfig {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid configuration: {}", self.0)
+    }
+}
+
+impl error::Error for InvalidConfig {}
+
+/// Any of the errors returned by the crate, for frontends that don't need to tell them apart but
+/// want to show the user why something (e.g: loading a file) failed.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum Error {
+    InvalidRomSize(InvalidRomSize),
+    InvalidBackup(InvalidBackup),
+    InvalidState(InvalidState),
+    InvalidElf(InvalidElf),
+    InvalidConfig(InvalidConfig),
+    InvalidExpr(InvalidExpr),
+    InvalidDat(InvalidDat),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidRomSize(e) => e.fmt(f),
+            Self::InvalidBackup(e) => e.fmt(f),
+            Self::InvalidState(e) => e.fmt(f),
+            Self::InvalidElf(e) => e.fmt(f),
+            Self::InvalidConfig(e) => e.fmt(f),
+            Self::InvalidExpr(e) => e.fmt(f),
+            Self::InvalidDat(e) => e.fmt(f),
+        }
+    }
+}
+
+// `Display` forwards to the wrapped error, so it's not also returned as the source; error reporters
+// would show its message twice otherwise.
+impl error::Error for Error {}
+
+impl From<InvalidRomSize> for Error {
+    fn from(e: InvalidRomSize) -> Self {
+        Self::InvalidRomSize(e)
+    }
+}
+
+impl From<InvalidBackup> for Error {
+    fn from(e: InvalidBackup) -> Self {
+        Self::InvalidBackup(e)
+    }
+}
+
+impl From<InvalidState> for Error {
+    fn from(e: InvalidState) -> Self {
+        Self::InvalidState(e)
+    }
+}
+
+impl From<InvalidElf> for Error {
+    fn from(e: InvalidElf) -> Self {
+        Self::InvalidElf(e)
+    }
+}
+
+impl From<InvalidConfig> for Error {
+    fn from(e: InvalidConfig) -> Self {
+        Self::InvalidConfig(e)
+    }
+}
+
+impl From<InvalidExpr> for Error {
+    fn from(e: InvalidExpr) -> Self {
+        Self::InvalidExpr(e)
+    }
+}
+
+impl From<InvalidDat> for Error {
+    fn from(e: InvalidDat) -> Self {
+        Self::InvalidDat(e)
+    }
+}
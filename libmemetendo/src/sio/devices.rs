@@ -0,0 +1,140 @@
+//! Simple devices for the link port, useful for testing games' serial code, and as examples for
+//! writing custom devices.
+
+use super::{Device, TransferLength};
+
+/// Connects the link port's SO line back to its SI line, so each transfer receives what it sent.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct Loopback;
+
+impl Device for Loopback {
+    fn transfer(&mut self, value: u32, _len: TransferLength) -> u32 {
+        value
+    }
+}
+
+/// Always ready for a transfer, which it replies to with `Self::reply`, ignoring what was sent.
+/// Useful for getting past games waiting for a device to respond. By default, it replies with all
+/// 0s, as if its SO line was held low.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct Dummy {
+    pub reply: u32,
+}
+
+impl Dummy {
+    #[must_use]
+    pub fn new(reply: u32) -> Self {
+        Self { reply }
+    }
+}
+
+impl Device for Dummy {
+    fn transfer(&mut self, _value: u32, _len: TransferLength) -> u32 {
+        self.reply
+    }
+}
+
+/// A transfer recorded by `Logged`.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Transfer {
+    pub len: TransferLength,
+    pub sent: u32,
+    pub received: u32,
+}
+
+/// Records the transfers made with a device, passing them through unchanged.
+#[derive(Debug, Clone)]
+pub struct Logged<D> {
+    pub device: D,
+    transfers: Vec<Transfer>,
+}
+
+impl<D: Device> Logged<D> {
+    #[must_use]
+    pub fn new(device: D) -> Self {
+        Self {
+            device,
+            transfers: Vec::new(),
+        }
+    }
+
+    /// Transfers recorded since the last call to `Self::take_transfers`, in order.
+    #[must_use]
+    pub fn transfers(&self) -> &[Transfer] {
+        &self.transfers
+    }
+
+    pub fn take_transfers(&mut self) -> Vec<Transfer> {
+        std::mem::take(&mut self.transfers)
+    }
+}
+
+impl<D: Device> Device for Logged<D> {
+    fn transfer(&mut self, value: u32, len: TransferLength) -> u32 {
+        let received = self.device.transfer(value, len);
+        self.transfers.push(Transfer {
+            len,
+            sent: value,
+            received,
+        });
+
+        received
+    }
+
+    fn is_ready(&self) -> bool {
+        self.device.is_ready()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{bus::Bus, irq::Irq, sio::Sio};
+
+    use super::*;
+
+    /// Does a 32-bit transfer at 2 MHz, returning what was received.
+    fn transfer32(sio: &mut Sio, value: u32) -> u32 {
+        let mut irq = Irq::new();
+        sio.write_word(0x120, value); // SIODATA32
+        sio.write_hword(0x128, 0x1083); // SIOCNT: 32-bit, 2 MHz internal clock, start
+        while sio.read_hword(0x128) & 0x80 != 0 {
+            sio.step(&mut irq, 1);
+        }
+
+        sio.read_word(0x120)
+    }
+
+    #[test]
+    fn loopback_and_dummy() {
+        let mut sio = Sio::new();
+        sio.device = Some(Box::new(Loopback));
+        assert_eq!(transfer32(&mut sio, 0x1234_5678), 0x1234_5678);
+
+        sio.device = Some(Box::new(Dummy::default()));
+        assert_eq!(sio.read_hword(0x128) & 4, 0); // Ready
+        assert_eq!(transfer32(&mut sio, 0x1234_5678), 0);
+    }
+
+    #[test]
+    fn logged_records_transfers() {
+        let mut device = Logged::new(Dummy::new(0xaaaa_aaaa));
+        assert_eq!(device.transfer(0x42, TransferLength::Bits8), 0xaaaa_aaaa);
+        device.transfer(0x1234_5678, TransferLength::Bits32);
+        assert_eq!(
+            device.take_transfers(),
+            [
+                Transfer {
+                    len: TransferLength::Bits8,
+                    sent: 0x42,
+                    received: 0xaaaa_aaaa,
+                },
+                Transfer {
+                    len: TransferLength::Bits32,
+                    sent: 0x1234_5678,
+                    received: 0xaaaa_aaaa,
+                },
+            ]
+        );
+        assert!(device.transfers().is_empty());
+    }
+}
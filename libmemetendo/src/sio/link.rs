@@ -0,0 +1,155 @@
+//! An in-process link cable, connecting two systems' link ports for local multiplayer testing.
+//!
+//! Each end is a `LinkPort`, which is `Send`, so the systems can run on different threads (each
+//! `Gba` must be created on the thread that runs it). Only normal mode is supported: the system
+//! supplying the clock exchanges data with the other as soon as it has started a transfer
+//! clocked externally, which makes its `SI` line read low (ready). If the other system isn't
+//! waiting on a transfer, all 1s are received, as if nothing were connected.
+
+use std::sync::{
+    atomic::{AtomicU32, AtomicU64, Ordering},
+    Arc,
+};
+
+use super::{Device, TransferLength};
+
+/// The end isn't waiting on a transfer.
+const IDLE: u64 = 0;
+/// The end is waiting on a transfer clocked by the other end.
+const WAITING: u64 = 1 << 32;
+/// The other end clocked the transfer, sending the value in the low 32 bits.
+const DONE: u64 = 1 << 33;
+
+#[derive(Default)]
+struct End {
+    /// `IDLE`, `WAITING` or `DONE`. Only the end itself moves out of `IDLE` and `DONE`, and only
+    /// the other end moves out of `WAITING` (with a compare-and-swap), so no transition is missed.
+    state: AtomicU64,
+    /// What the end is sending in the transfer it's waiting on.
+    sending: AtomicU32,
+}
+
+/// One end of a link cable; see `LinkPort::pair`.
+pub struct LinkPort {
+    ends: Arc<[End; 2]>,
+    idx: usize,
+}
+
+impl LinkPort {
+    /// Creates both ends of a link cable.
+    #[must_use]
+    pub fn pair() -> (Self, Self) {
+        let ends = Arc::new([End::default(), End::default()]);
+        let port = |idx| Self {
+            ends: Arc::clone(&ends),
+            idx,
+        };
+
+        (port(0), port(1))
+    }
+
+    fn this(&self) -> &End {
+        &self.ends[self.idx]
+    }
+
+    fn other(&self) -> &End {
+        &self.ends[1 - self.idx]
+    }
+}
+
+impl Device for LinkPort {
+    fn transfer(&mut self, value: u32, _len: TransferLength) -> u32 {
+        let other = self.other();
+        // Read what the other end is sending before handing over the transfer, after which it may
+        // start another.
+        let reply = other.sending.load(Ordering::Acquire);
+        let done = DONE | u64::from(value);
+        match other
+            .state
+            .compare_exchange(WAITING, done, Ordering::AcqRel, Ordering::Acquire)
+        {
+            Ok(_) => reply,
+            Err(_) => u32::MAX,
+        }
+    }
+
+    fn is_ready(&self) -> bool {
+        self.other().state.load(Ordering::Acquire) == WAITING
+    }
+
+    #[expect(clippy::cast_possible_truncation)] // the value is in the low 32 bits
+    fn poll_external_transfer(&mut self, value: u32, _len: TransferLength) -> Option<u32> {
+        let this = self.this();
+        match this.state.load(Ordering::Acquire) {
+            IDLE => {
+                this.sending.store(value, Ordering::Release);
+                this.state.store(WAITING, Ordering::Release);
+                None
+            }
+            WAITING => None,
+            done => {
+                this.state.store(IDLE, Ordering::Release);
+                Some(done as u32)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use crate::{bus::Bus, irq::Irq, sio::Sio};
+
+    use super::*;
+
+    fn wait_for_transfer(sio: &mut Sio, irq: &mut Irq) {
+        while sio.read_hword(0x128) & 0x80 != 0 {
+            sio.step(irq, 1);
+        }
+    }
+
+    #[test]
+    fn exchanges_between_threads() {
+        let (port0, port1) = LinkPort::pair();
+        let child = thread::spawn(move || {
+            let mut sio = Sio::new();
+            let mut irq = Irq::new();
+            sio.device = Some(Box::new(port1));
+            let mut received = Vec::new();
+            for i in 0..3 {
+                sio.write_word(0x120, 0x1000 + i);
+                sio.write_hword(0x128, 0x1080); // 32-bit, external clock, start
+                wait_for_transfer(&mut sio, &mut irq);
+                received.push(sio.read_word(0x120));
+            }
+
+            received
+        });
+
+        let mut sio = Sio::new();
+        let mut irq = Irq::new();
+        sio.device = Some(Box::new(port0));
+        let mut received = Vec::new();
+        for i in 0..3 {
+            // Wait for the other end to be ready (SI low).
+            while sio.read_hword(0x128) & 4 != 0 {
+                thread::yield_now();
+            }
+            sio.write_word(0x120, 0x2000 + i);
+            sio.write_hword(0x128, 0x1083); // 32-bit, 2 MHz internal clock, start
+            wait_for_transfer(&mut sio, &mut irq);
+            received.push(sio.read_word(0x120));
+        }
+
+        assert_eq!(received, [0x1000, 0x1001, 0x1002]);
+        assert_eq!(child.join().unwrap(), [0x2000, 0x2001, 0x2002]);
+    }
+
+    #[test]
+    fn receives_ones_if_other_end_is_not_waiting() {
+        let (mut port0, _port1) = LinkPort::pair();
+        assert!(!port0.is_ready());
+        assert_eq!(port0.transfer(0x12, TransferLength::Bits8), u32::MAX);
+    }
+}
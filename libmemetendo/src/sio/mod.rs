@@ -0,0 +1,300 @@
+//! Serial I/O (SIO) via the link port, which connects the system to other systems and peripherals
+//! like the GBA Wireless Adapter.
+//!
+//! Only normal mode transfers are emulated, and only when the GBA supplies the clock, or when a
+//! device that can supply it (like a `link::LinkPort`) is connected; in the other modes
+//! (multi-player, UART, JOY bus and general-purpose), the registers are just stored as written.
+
+pub mod devices;
+pub mod link;
+pub mod wireless;
+
+use intbits::Bits;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bus::Bus,
+    irq::{Interrupt, Irq},
+};
+
+pub use wireless::WirelessAdapter;
+
+/// Length of a normal mode transfer.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TransferLength {
+    Bits8,
+    Bits32,
+}
+
+/// A device connected to the link port, such as another system or a peripheral. See `devices` for
+/// some simple examples.
+///
+/// For example, a device that collects the bytes sent to it, in the style of a printer:
+///
+/// ```
+/// use std::{cell::RefCell, rc::Rc};
+///
+/// use libmemetendo::{
+///     bus::Bus,
+///     irq::Irq,
+///     sio::{Device, Sio, TransferLength},
+/// };
+///
+/// struct Printer(Rc<RefCell<Vec<u8>>>);
+///
+/// impl Device for Printer {
+///     fn transfer(&mut self, value: u32, len: TransferLength) -> u32 {
+///         if len == TransferLength::Bits8 {
+///             self.0.borrow_mut().push(value.to_le_bytes()[0]);
+///         }
+///
+///         0
+///     }
+/// }
+///
+/// let printed = Rc::new(RefCell::new(Vec::new()));
+/// let mut sio = Sio::new();
+/// sio.device = Some(Box::new(Printer(Rc::clone(&printed))));
+///
+/// let mut irq = Irq::new();
+/// for &byte in b"hi!" {
+///     sio.write_byte(0x12a, byte); // SIODATA8
+///     sio.write_hword(0x128, 0x0083); // SIOCNT: 8-bit, 2 MHz internal clock, start
+///     while sio.read_hword(0x128) & 0x80 != 0 {
+///         sio.step(&mut irq, 1);
+///     }
+///     assert_eq!(sio.read_byte(0x12a), 0);
+/// }
+/// assert_eq!(*printed.borrow(), b"hi!");
+/// ```
+///
+/// On a `Gba`, devices are connected via `Gba::sio`, where the same registers are at their usual
+/// addresses (e.g: `SIOCNT` at `0x0400_0128`).
+pub trait Device {
+    /// Exchanges data in a normal mode transfer clocked by the GBA: `value` is sent to the device,
+    /// and what it sent back is returned. For 8-bit transfers, only the low byte of each is used.
+    fn transfer(&mut self, value: u32, len: TransferLength) -> u32;
+
+    /// Whether the device is ready for a transfer, which the GBA reads from its SI line in normal
+    /// mode. Devices are always ready by default.
+    fn is_ready(&self) -> bool {
+        true
+    }
+
+    /// Polled while the GBA waits on a normal mode transfer clocked by the device, with what the
+    /// GBA is sending. Returns what the device sent back once it has clocked the transfer, or
+    /// `None` if it hasn't yet. By default, devices never supply the clock.
+    fn poll_external_transfer(&mut self, _value: u32, _len: TransferLength) -> Option<u32> {
+        None
+    }
+}
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct Sio {
+    /// `SIODATA32` (hwords 0 and 1), or `SIOMULTI0` to 3.
+    multi: [u16; 4],
+    siocnt: u16,
+    /// `SIODATA8` (low byte), or `SIOMLT_SEND`.
+    send: u16,
+    rcnt: u16,
+    /// Cycles elapsed since the transfer in progress started.
+    transfer_cycles: u32,
+    /// Device connected to the link port, if any. It isn't included in save states, so devices
+    /// with state (like `WirelessAdapter`) may be out of sync with games after loading one.
+    #[serde(skip)]
+    pub device: Option<Box<dyn Device>>,
+}
+
+impl Sio {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn step(&mut self, irq: &mut Irq, cycles: u8) {
+        if !self.is_normal_mode() || !self.siocnt.bit(7) {
+            return;
+        }
+
+        if !self.siocnt.bit(0) {
+            let (value, len) = (self.send_value(), self.transfer_len());
+            let received = self
+                .device
+                .as_mut()
+                .and_then(|device| device.poll_external_transfer(value, len));
+            if let Some(received) = received {
+                self.finish_transfer(irq, received);
+            }
+            return;
+        }
+
+        // The internal clock runs at 256 KHz or 2 MHz.
+        let bit_cycles = if self.siocnt.bit(1) { 8 } else { 64 };
+        self.transfer_cycles += u32::from(cycles);
+        if self.transfer_cycles >= u32::from(self.transfer_len_bits()) * bit_cycles {
+            let (value, len) = (self.send_value(), self.transfer_len());
+            // With nothing connected, SI is pulled high, so all 1s are received.
+            let received = self
+                .device
+                .as_mut()
+                .map_or(u32::MAX, |device| device.transfer(value, len));
+            self.finish_transfer(irq, received);
+        }
+    }
+
+    /// Whether the state is in range, which is always the case unless it was loaded from a
+    /// malformed save state; such state can panic when stepped.
+    pub(crate) fn is_valid(&self) -> bool {
+        // Transfers finish after at most 32 bits of 64 cycles each, so this bound is a loose one.
+        self.transfer_cycles <= u16::MAX.into()
+    }
+
+    fn is_normal_mode(&self) -> bool {
+        !self.rcnt.bit(15) && !self.siocnt.bit(13)
+    }
+
+    fn transfer_len(&self) -> TransferLength {
+        if self.siocnt.bit(12) {
+            TransferLength::Bits32
+        } else {
+            TransferLength::Bits8
+        }
+    }
+
+    fn transfer_len_bits(&self) -> u8 {
+        match self.transfer_len() {
+            TransferLength::Bits8 => 8,
+            TransferLength::Bits32 => 32,
+        }
+    }
+
+    fn send_value(&self) -> u32 {
+        match self.transfer_len() {
+            TransferLength::Bits8 => self.send.bits(..8).into(),
+            TransferLength::Bits32 => u32::from(self.multi[0]) | (u32::from(self.multi[1]) << 16),
+        }
+    }
+
+    fn finish_transfer(&mut self, irq: &mut Irq, received: u32) {
+        match self.transfer_len() {
+            TransferLength::Bits8 => self
+                .send
+                .set_bits(..8, received.bits(..8).try_into().unwrap()),
+            TransferLength::Bits32 => {
+                self.multi[0] = received.bits(..16).try_into().unwrap();
+                self.multi[1] = received.bits(16..).try_into().unwrap();
+            }
+        }
+
+        self.siocnt.set_bit(7, false);
+        if self.siocnt.bit(14) {
+            irq.request(Interrupt::Serial);
+        }
+    }
+
+    fn siocnt(&self) -> u16 {
+        if !self.is_normal_mode() {
+            return self.siocnt;
+        }
+
+        // SI reads low if the other side is ready; it's pulled high if nothing is connected.
+        let ready = self.device.as_ref().is_some_and(|device| device.is_ready());
+        self.siocnt.with_bit(2, !ready)
+    }
+
+    fn reg_mut(&mut self, addr: u32) -> Option<&mut u16> {
+        match addr & !1 {
+            0x120..=0x127 => Some(&mut self.multi[usize::try_from((addr - 0x120) / 2).unwrap()]),
+            0x128 => Some(&mut self.siocnt),
+            0x12a => Some(&mut self.send),
+            0x134 => Some(&mut self.rcnt),
+            _ => None,
+        }
+    }
+}
+
+impl Bus for Sio {
+    fn read_byte(&mut self, addr: u32) -> u8 {
+        let value = if addr & !1 == 0x128 {
+            self.siocnt()
+        } else {
+            self.reg_mut(addr).map_or(0, |reg| *reg)
+        };
+
+        value.to_le_bytes()[usize::try_from(addr & 1).unwrap()]
+    }
+
+    fn write_byte(&mut self, addr: u32, value: u8) {
+        let was_started = self.siocnt.bit(7);
+        let Some(reg) = self.reg_mut(addr) else {
+            return;
+        };
+        if addr & 1 == 0 {
+            reg.set_bits(..8, value.into());
+        } else {
+            reg.set_bits(8.., value.into());
+        }
+
+        if !was_started && self.siocnt.bit(7) {
+            self.transfer_cycles = 0;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Replies to each transfer with what was sent, plus one.
+    struct Increment;
+
+    impl Device for Increment {
+        fn transfer(&mut self, value: u32, _len: TransferLength) -> u32 {
+            value.wrapping_add(1)
+        }
+    }
+
+    fn step_cycles(sio: &mut Sio, irq: &mut Irq, cycles: u32) {
+        for _ in 0..cycles {
+            sio.step(irq, 1);
+        }
+    }
+
+    #[test]
+    fn normal_transfer_without_device() {
+        let mut sio = Sio::new();
+        let mut irq = Irq::new();
+        assert_eq!(sio.read_hword(0x128) & 4, 4); // SI pulled high
+
+        sio.write_byte(0x12a, 0x42);
+        sio.write_hword(0x128, 0x4083); // 8-bit, 2 MHz internal clock, IRQ, start
+        step_cycles(&mut sio, &mut irq, 8 * 8 - 1);
+        assert_eq!(sio.read_hword(0x128) & 0x80, 0x80);
+        assert_eq!(irq.requested(), 0);
+
+        step_cycles(&mut sio, &mut irq, 1);
+        assert_eq!(sio.read_hword(0x128) & 0x80, 0);
+        assert_eq!(sio.read_byte(0x12a), 0xff);
+        assert_eq!(irq.requested(), 1 << Interrupt::Serial as u16);
+    }
+
+    #[test]
+    fn normal_transfer_with_device() {
+        let mut sio = Sio::new();
+        let mut irq = Irq::new();
+        sio.device = Some(Box::new(Increment));
+        assert_eq!(sio.read_hword(0x128) & 4, 0);
+
+        sio.write_word(0x120, 0x1234_ffff);
+        sio.write_hword(0x128, 0x1081); // 32-bit, 256 KHz internal clock, start
+        step_cycles(&mut sio, &mut irq, 32 * 64);
+        assert_eq!(sio.read_hword(0x128) & 0x80, 0);
+        assert_eq!(sio.read_word(0x120), 0x1235_0000);
+        assert_eq!(irq.requested(), 0);
+
+        // Transfers clocked by the other side never finish if the device can't supply the clock.
+        sio.write_hword(0x128, 0x1080);
+        step_cycles(&mut sio, &mut irq, 32 * 64);
+        assert_eq!(sio.read_hword(0x128) & 0x80, 0x80);
+    }
+}
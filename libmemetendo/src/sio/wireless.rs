@@ -0,0 +1,229 @@
+//! The GBA Wireless Adapter (RFU), which games like the Pokémon Fire Red, Leaf Green and Emerald
+//! versions probe for before offering their wireless features.
+//!
+//! Only enough of its protocol is emulated for games to detect it: the login handshake, and
+//! commands answered as if no other adapters are in range. Exchanging data with other systems can
+//! build on this later.
+
+use std::collections::VecDeque;
+
+use intbits::Bits;
+use log::warn;
+
+use super::{Device, TransferLength};
+
+/// Low hword of the last word sent by the GBA to log in, after "NINTENDO" is sent (as pairs of
+/// ASCII characters, each pair sent twice).
+const LOGIN_END: u16 = 0x8001;
+/// Low hword of the first word sent to log in.
+const LOGIN_START: u16 = 0x494e;
+
+/// High hword of the command headers sent by the GBA, and of the response headers sent back.
+const COMMAND_MAGIC: u32 = 0x9966;
+/// Sent while the other side has nothing to send.
+const ACK: u32 = 0x8000_0000;
+
+#[derive(Debug, Clone, Default)]
+enum State {
+    /// Echoing the words sent by the GBA to log in, which ends with `LOGIN_END`.
+    #[default]
+    LoggingIn,
+    /// Waiting for a command.
+    Idle,
+    /// Receiving the parameters of a command.
+    ReceivingParams { command: u8, remaining: u8 },
+    /// Sending the words of a response.
+    Responding(VecDeque<u32>),
+}
+
+/// GBA Wireless Adapter, connected to the link port. It's only communicated with by 32-bit normal
+/// mode transfers.
+#[derive(Debug, Clone, Default)]
+pub struct WirelessAdapter {
+    state: State,
+    /// Low hword of the previous word sent by the GBA while logging in.
+    prev_login_hword: u16,
+}
+
+impl WirelessAdapter {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether the GBA has finished logging in to the adapter.
+    #[must_use]
+    pub fn is_logged_in(&self) -> bool {
+        !matches!(self.state, State::LoggingIn)
+    }
+
+    fn log_in(&mut self, value: u32) -> u32 {
+        // The adapter echoes what was sent in its high hword, and the complement of what was sent
+        // previously in its low hword.
+        let hword: u16 = value.bits(..16).try_into().unwrap();
+        let reply = (u32::from(hword) << 16) | u32::from(!self.prev_login_hword);
+        self.prev_login_hword = hword;
+        if hword == LOGIN_END {
+            self.state = State::Idle;
+        }
+
+        reply
+    }
+
+    fn receive_command(&mut self, value: u32) -> u32 {
+        if value.bits(16..) != COMMAND_MAGIC {
+            // Logging in again, such as after the game resets the adapter.
+            if value.bits(..16) == LOGIN_START.into() {
+                self.state = State::LoggingIn;
+                self.prev_login_hword = 0;
+                return self.log_in(value);
+            }
+
+            return ACK;
+        }
+
+        let command = value.bits(..8).try_into().unwrap();
+        let param_count = value.bits(8..16).try_into().unwrap();
+        self.state = if param_count == 0 {
+            State::Responding(response(command))
+        } else {
+            State::ReceivingParams {
+                command,
+                remaining: param_count,
+            }
+        };
+
+        ACK
+    }
+}
+
+impl Device for WirelessAdapter {
+    fn transfer(&mut self, value: u32, len: TransferLength) -> u32 {
+        if len != TransferLength::Bits32 {
+            return u32::MAX;
+        }
+
+        match &mut self.state {
+            State::LoggingIn => self.log_in(value),
+            State::Idle => self.receive_command(value),
+            &mut State::ReceivingParams { command, remaining } => {
+                self.state = if remaining > 1 {
+                    State::ReceivingParams {
+                        command,
+                        remaining: remaining - 1,
+                    }
+                } else {
+                    State::Responding(response(command))
+                };
+
+                ACK
+            }
+            State::Responding(words) => {
+                let word = words.pop_front().unwrap();
+                if words.is_empty() {
+                    self.state = State::Idle;
+                }
+
+                word
+            }
+        }
+    }
+}
+
+/// Returns the words of the response to `command`: a header with the command's ID (plus 0x80) and
+/// the length of the data, followed by the data.
+fn response(command: u8) -> VecDeque<u32> {
+    let data: &[u32] = match command {
+        // Version.
+        0x12 => &[0x0083_0117],
+        // Signal level, system status and slot status: not hosting or connected, so there are no
+        // connections or assigned device IDs to report.
+        0x11 | 0x13 | 0x14 => &[0],
+        // Hello, broadcast, setup, host, stop host, broadcast read start, send data, disconnect and
+        // sleep, which just acknowledge the command; also accept connections, broadcast read poll
+        // and end, and receive data, which have nothing to report as no other adapters are in
+        // range.
+        0x10 | 0x16 | 0x17 | 0x19 | 0x1b | 0x1c | 0x24 | 0x30 | 0x3d | 0x1a | 0x1d | 0x1e
+        | 0x26 => &[],
+        _ => {
+            warn!("unsupported wireless adapter command {command:#04x}; ignoring");
+            &[]
+        }
+    };
+
+    let len = u32::try_from(data.len()).unwrap();
+    let header = (COMMAND_MAGIC << 16) | (len << 8) | u32::from(command | 0x80);
+
+    [header].into_iter().chain(data.iter().copied()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transfer(adapter: &mut WirelessAdapter, value: u32) -> u32 {
+        adapter.transfer(value, TransferLength::Bits32)
+    }
+
+    fn log_in(adapter: &mut WirelessAdapter) {
+        let mut prev: Option<(u32, u32)> = None;
+        for hword in [
+            LOGIN_START,
+            LOGIN_START,
+            0x544e,
+            0x544e,
+            0x4e45,
+            0x4e45,
+            0x4f44,
+            0x4f44,
+            LOGIN_END,
+        ] {
+            // The GBA sends the complement of the adapter's previous high hword in its high hword.
+            let high = prev.map_or(0, |(_, reply)| !reply.bits(16..) & 0xffff);
+            let value = (high << 16) | u32::from(hword);
+            let reply = transfer(adapter, value);
+            assert_eq!(reply.bits(16..), hword.into());
+            if let Some((prev_value, _)) = prev {
+                assert_eq!(reply.bits(..16), !prev_value & 0xffff);
+            }
+            prev = Some((value, reply));
+        }
+    }
+
+    #[test]
+    fn logs_in() {
+        let mut adapter = WirelessAdapter::new();
+        log_in(&mut adapter);
+        assert!(adapter.is_logged_in());
+
+        // The GBA can log in again after the adapter is reset.
+        log_in(&mut adapter);
+        assert!(adapter.is_logged_in());
+    }
+
+    #[test]
+    fn responds_to_commands() {
+        let mut adapter = WirelessAdapter::new();
+        log_in(&mut adapter);
+
+        // Hello.
+        assert_eq!(transfer(&mut adapter, 0x9966_0010), ACK);
+        assert_eq!(transfer(&mut adapter, ACK), 0x9966_0090);
+
+        // Setup, with 1 parameter.
+        assert_eq!(transfer(&mut adapter, 0x9966_0117), ACK);
+        assert_eq!(transfer(&mut adapter, 0x003c_0420), ACK);
+        assert_eq!(transfer(&mut adapter, ACK), 0x9966_0097);
+
+        // System status.
+        assert_eq!(transfer(&mut adapter, 0x9966_0013), ACK);
+        assert_eq!(transfer(&mut adapter, ACK), 0x9966_0193);
+        assert_eq!(transfer(&mut adapter, ACK), 0);
+
+        // Broadcast read: no hosts found.
+        for command in [0x1c, 0x1d, 0x1e] {
+            assert_eq!(transfer(&mut adapter, 0x9966_0000 | command), ACK);
+            assert_eq!(transfer(&mut adapter, ACK), 0x9966_0080 | command);
+        }
+    }
+}
@@ -0,0 +1,173 @@
+//! Persistence of a game's saves (its cartridge backup and save state) via a `Backend`, so
+//! frontends can store them however suits their platform (e.g: as files, or in `IndexedDB` on the
+//! web), and tests can keep them in memory.
+
+use std::{
+    collections::HashMap,
+    io::{self, ErrorKind},
+};
+
+use crate::gba::{Gba, Thumbnail};
+
+/// Name of the cartridge backup (the game's own save data), as written by `flush_backup`.
+pub const BACKUP: &str = "backup";
+/// Name of the save state, as written by `save_state`.
+pub const STATE: &str = "state";
+
+/// Stores named blobs of data for the game being played.
+pub trait Backend {
+    /// Returns the blob called `name`, or `None` if there isn't one.
+    ///
+    /// # Errors
+    /// Returns an error if the blob exists, but couldn't be read.
+    fn read(&mut self, name: &str) -> io::Result<Option<Vec<u8>>>;
+
+    /// Stores `data` as the blob called `name`, replacing any existing one.
+    ///
+    /// # Errors
+    /// Returns an error if the blob couldn't be written.
+    fn write(&mut self, name: &str, data: &[u8]) -> io::Result<()>;
+}
+
+impl<B: Backend + ?Sized> Backend for &mut B {
+    fn read(&mut self, name: &str) -> io::Result<Option<Vec<u8>>> {
+        (**self).read(name)
+    }
+
+    fn write(&mut self, name: &str, data: &[u8]) -> io::Result<()> {
+        (**self).write(name, data)
+    }
+}
+
+/// Keeps blobs in memory, such as for tests.
+#[derive(Debug, Default, Clone)]
+pub struct Memory {
+    pub blobs: HashMap<String, Vec<u8>>,
+}
+
+impl Memory {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Backend for Memory {
+    fn read(&mut self, name: &str) -> io::Result<Option<Vec<u8>>> {
+        Ok(self.blobs.get(name).cloned())
+    }
+
+    fn write(&mut self, name: &str, data: &[u8]) -> io::Result<()> {
+        self.blobs.insert(name.to_string(), data.to_vec());
+        Ok(())
+    }
+}
+
+/// Writes the cartridge backup as `BACKUP`. Returns false if the cartridge has no backup, in
+/// which case nothing is written.
+///
+/// # Errors
+/// Returns an error if the backend couldn't write the backup.
+pub fn flush_backup(gba: &Gba, mut backend: impl Backend) -> io::Result<bool> {
+    let Some(buf) = gba.cart.backup_buffer() else {
+        return Ok(false);
+    };
+    backend.write(BACKUP, buf)?;
+
+    Ok(true)
+}
+
+/// Reads the cartridge backup written by `flush_backup`, if any, for passing to
+/// `Cartridge::try_from_backup`.
+///
+/// # Errors
+/// Returns an error if the backend couldn't read the backup.
+pub fn read_backup(mut backend: impl Backend) -> io::Result<Option<Box<[u8]>>> {
+    Ok(backend.read(BACKUP)?.map(Vec::into_boxed_slice))
+}
+
+/// Writes a save state as `STATE`, including `thumbnail` if given.
+///
+/// # Errors
+/// Returns an error if the backend couldn't write the state.
+pub fn save_state(
+    gba: &Gba,
+    mut backend: impl Backend,
+    thumbnail: Option<&Thumbnail>,
+) -> io::Result<()> {
+    let buf = match thumbnail {
+        Some(thumbnail) => gba.save_state_with_thumbnail(thumbnail),
+        None => gba.save_state(),
+    };
+
+    backend.write(STATE, &buf)
+}
+
+/// Loads the save state written by `save_state`. Returns false if there isn't one.
+///
+/// # Errors
+/// Returns an error if the backend couldn't read the state, or an error of kind
+/// `ErrorKind::InvalidData` (wrapping an `InvalidState`) if the state couldn't be loaded.
+pub fn load_state(gba: &mut Gba, mut backend: impl Backend) -> io::Result<bool> {
+    let Some(buf) = backend.read(STATE)? else {
+        return Ok(false);
+    };
+    gba.load_state(&buf)
+        .map_err(|e| io::Error::new(ErrorKind::InvalidData, e))?;
+
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use crate::{
+        bios,
+        bus::Bus,
+        cart::{self, BackupType, Cartridge},
+    };
+
+    use super::*;
+
+    fn new_gba(backup: Option<Box<[u8]>>) -> Gba {
+        let bios_rom = bios::Rom::new(Rc::from(vec![0; 0x4000])).unwrap();
+        let cart_rom = cart::Rom::new(Rc::from(vec![0; 0x200])).unwrap();
+        let cart = match backup {
+            Some(buf) => Cartridge::try_from_backup(&cart_rom, Some(buf)).unwrap(),
+            None => Cartridge::new(cart_rom, BackupType::Sram32KiB),
+        };
+
+        Gba::new(bios_rom, cart)
+    }
+
+    #[test]
+    fn persists_backups() {
+        let mut storage = Memory::new();
+        assert_eq!(read_backup(&mut storage).unwrap(), None);
+
+        let mut gba = new_gba(None);
+        gba.cart.write_byte(0x600_0010, 0x42);
+        assert!(flush_backup(&gba, &mut storage).unwrap());
+
+        let mut gba = new_gba(read_backup(&mut storage).unwrap());
+        assert_eq!(gba.cart.read_byte(0x600_0010), 0x42);
+    }
+
+    #[test]
+    fn persists_states() {
+        let mut storage = Memory::new();
+        let mut gba = new_gba(None);
+        assert!(!load_state(&mut gba, &mut storage).unwrap());
+
+        gba.iwram[0] = 0x42;
+        save_state(&gba, &mut storage, None).unwrap();
+        gba.iwram[0] = 0;
+        assert!(load_state(&mut gba, &mut storage).unwrap());
+        assert_eq!(gba.iwram[0], 0x42);
+
+        storage.write(STATE, b"nope").unwrap();
+        let e = load_state(&mut gba, &mut storage).unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::InvalidData);
+    }
+}
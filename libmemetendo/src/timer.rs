@@ -1,6 +1,7 @@
 use std::mem::{replace, take};
 
 use intbits::Bits;
+use serde::{Deserialize, Serialize};
 use strum_macros::FromRepr;
 
 use crate::{
@@ -9,7 +10,7 @@ use crate::{
     irq::{Interrupt, Irq},
 };
 
-#[derive(Debug, Default, FromRepr)]
+#[derive(Debug, Clone, Default, FromRepr, Serialize, Deserialize)]
 #[repr(u8)]
 enum PrescalarSelect {
     #[default]
@@ -19,9 +20,21 @@ enum PrescalarSelect {
     Div1024,
 }
 
-#[derive(Debug, Default)]
+impl PrescalarSelect {
+    fn divider(&self) -> u16 {
+        match self {
+            Self::Div1 => 1,
+            Self::Div64 => 64,
+            Self::Div256 => 256,
+            Self::Div1024 => MAX_DIV,
+        }
+    }
+}
+
+const MAX_DIV: u16 = 1024;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 struct Control {
-    accum: u32,
     initial: u16,
     counter: u16,
     prescalar_select: PrescalarSelect,
@@ -31,8 +44,35 @@ struct Control {
     cached_bits: u16,
 }
 
-#[derive(Debug, Default)]
-pub struct Timers([Control; 4]);
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Timers {
+    timers: [Control; 4],
+    /// Cycles counted by the prescaler shared by all timers, modulo its largest divider. Timers
+    /// tick whenever it passes a multiple of their divider, rather than counting from when they're
+    /// started, so their first tick may come early.
+    prescaler: u16,
+}
+
+/// Timer state as saved by version 1 of its save state chunk, when each timer had its own
+/// prescaler (accumulating 1024ths of a tick).
+#[derive(Serialize, Deserialize)]
+pub(crate) struct TimersV1([(u32, Control); 4]);
+
+impl From<TimersV1> for Timers {
+    fn from(v1: TimersV1) -> Self {
+        Self {
+            timers: v1.0.map(|(_, timer)| timer),
+            prescaler: 0,
+        }
+    }
+}
+
+#[cfg(test)]
+impl From<&Timers> for TimersV1 {
+    fn from(timers: &Timers) -> Self {
+        Self(timers.timers.clone().map(|timer| (0, timer)))
+    }
+}
 
 impl Timers {
     #[must_use]
@@ -40,11 +80,20 @@ impl Timers {
         Self::default()
     }
 
+    /// Whether the state is in range, which is always the case unless it was loaded from a
+    /// malformed save state; such state can panic when stepped.
+    pub(crate) fn is_valid(&self) -> bool {
+        self.prescaler < MAX_DIV
+    }
+
     // Panics if the ticks calculation overflows u16, but that shouldn't be possible.
     #[expect(clippy::missing_panics_doc)]
     pub fn step(&mut self, irq: &mut Irq, audio: &mut Audio, cycles: u8) {
+        let prescaler = self.prescaler;
+        self.prescaler = (prescaler + u16::from(cycles)) % MAX_DIV;
+
         let mut prev_overflow_count = 0;
-        for (i, timer) in self.0.iter_mut().enumerate() {
+        for (i, timer) in self.timers.iter_mut().enumerate() {
             let ticks = {
                 let prev_overflow_count = take(&mut prev_overflow_count);
                 if !timer.start || (timer.cascade && prev_overflow_count == 0) {
@@ -54,22 +103,12 @@ impl Timers {
                 if timer.cascade {
                     prev_overflow_count
                 } else {
-                    const MAX_DIV: u32 = 1024;
-
-                    let shift = match timer.prescalar_select {
-                        PrescalarSelect::Div1 => 10,
-                        PrescalarSelect::Div64 => 4,
-                        PrescalarSelect::Div256 => 2,
-                        PrescalarSelect::Div1024 => 0,
-                    };
-                    timer.accum += u32::from(cycles) << shift;
-                    if timer.accum < MAX_DIV {
+                    let div = timer.prescalar_select.divider();
+                    let ticks = (prescaler % div + u16::from(cycles)) / div;
+                    if ticks == 0 {
                         continue;
                     }
 
-                    let ticks = u16::try_from(timer.accum / MAX_DIV).unwrap();
-                    timer.accum %= MAX_DIV;
-
                     ticks
                 }
             };
@@ -105,29 +144,38 @@ impl Timers {
 
 impl Bus for Timers {
     fn read_byte(&mut self, addr: u32) -> u8 {
-        assert!((0x100..0x110).contains(&addr), "IO register address OOB");
+        if !(0x100..0x110).contains(&addr) {
+            return 0;
+        }
 
-        let tmcnt = &mut self.0[usize::try_from(addr & 0xf).unwrap() / 4];
+        let tmcnt = &mut self.timers[usize::try_from(addr & 0xf).unwrap() / 4];
         match addr & 3 {
             0 => tmcnt.counter.bits(..8).try_into().unwrap(),
             1 => tmcnt.counter.bits(8..).try_into().unwrap(),
-            2 => tmcnt.cached_bits.bits(..8).try_into().unwrap(),
-            3 => tmcnt.cached_bits.bits(8..).try_into().unwrap(),
+            2 => u8::try_from(tmcnt.cached_bits.bits(..8)).unwrap() & 0xc7,
+            3 => 0,
             _ => unreachable!(),
         }
     }
 
     fn write_byte(&mut self, addr: u32, value: u8) {
-        assert!((0x100..0x110).contains(&addr), "IO register address OOB");
+        if !(0x100..0x110).contains(&addr) {
+            return;
+        }
 
-        let tmcnt = &mut self.0[usize::try_from(addr & 0xf).unwrap() / 4];
+        let idx = usize::try_from(addr & 0xf).unwrap() / 4;
+        let tmcnt = &mut self.timers[idx];
         match addr & 3 {
+            // Only the reload value is written, even while running; the counter is loaded with it
+            // when the timer is started or overflows.
             0 => tmcnt.initial.set_bits(..8, value.into()),
             1 => tmcnt.initial.set_bits(8.., value.into()),
             2 => {
                 tmcnt.cached_bits.set_bits(..8, value.into());
                 tmcnt.prescalar_select = PrescalarSelect::from_repr(value.bits(..2)).unwrap();
-                tmcnt.cascade = value.bit(2);
+                // Timer 0 has no previous timer to count up with, so it ignores the count-up bit
+                // and uses its prescaler (the bit still reads back as written).
+                tmcnt.cascade = idx != 0 && value.bit(2);
                 tmcnt.irq_enabled = value.bit(6);
 
                 if !replace(&mut tmcnt.start, value.bit(7)) && tmcnt.start {
@@ -136,6 +184,110 @@ impl Bus for Timers {
             }
             3 => tmcnt.cached_bits.set_bits(8.., value.into()),
             _ => unreachable!(),
-        };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn step(timers: &mut Timers, irq: &mut Irq, cycles: u32) {
+        let mut audio = Audio::new();
+        for _ in 0..cycles {
+            timers.step(irq, &mut audio, 1);
+        }
+    }
+
+    #[test]
+    fn reload_writes_only_take_effect_on_start_or_overflow() {
+        let (mut timers, mut irq) = (Timers::new(), Irq::new());
+        timers.write_hword(0x100, 0xfff0); // TM0CNT_L
+        timers.write_hword(0x102, 0x0080); // TM0CNT_H: start
+        step(&mut timers, &mut irq, 4);
+        assert_eq!(timers.read_hword(0x100), 0xfff4);
+
+        // While running, the counter keeps counting from where it was until it overflows.
+        timers.write_hword(0x100, 0xff00);
+        assert_eq!(timers.read_hword(0x100), 0xfff4);
+        step(&mut timers, &mut irq, 11);
+        assert_eq!(timers.read_hword(0x100), 0xffff);
+        step(&mut timers, &mut irq, 1);
+        assert_eq!(timers.read_hword(0x100), 0xff00);
+
+        // While stopped, the counter holds its value until it's started again.
+        timers.write_hword(0x102, 0);
+        timers.write_hword(0x100, 0x1234);
+        step(&mut timers, &mut irq, 8);
+        assert_eq!(timers.read_hword(0x100), 0xff00);
+        timers.write_hword(0x102, 0x0080);
+        assert_eq!(timers.read_hword(0x100), 0x1234);
+
+        // Rewriting the control register while running doesn't reload the counter.
+        step(&mut timers, &mut irq, 2);
+        timers.write_hword(0x102, 0x0080);
+        assert_eq!(timers.read_hword(0x100), 0x1236);
+    }
+
+    #[test]
+    fn timer_0_ignores_count_up() {
+        let (mut timers, mut irq) = (Timers::new(), Irq::new());
+        timers.write_hword(0x102, 0x0084); // TM0CNT_H: start, count-up
+        timers.write_hword(0x106, 0x0084); // TM1CNT_H: start, count-up
+        assert_eq!(timers.read_hword(0x102), 0x0084);
+
+        // Timer 0 counts every cycle instead, so timer 1 still counts its overflows.
+        step(&mut timers, &mut irq, 0x1_0003);
+        assert_eq!(timers.read_hword(0x100), 3);
+        assert_eq!(timers.read_hword(0x104), 1);
+    }
+
+    #[test]
+    fn prescaler_is_shared_and_free_running() {
+        let (mut timers, mut irq) = (Timers::new(), Irq::new());
+        step(&mut timers, &mut irq, 60);
+        timers.write_hword(0x102, 0x0081); // TM0CNT_H: start, divide by 64
+
+        // The first tick comes when the shared prescaler reaches 64, not 64 cycles after starting.
+        step(&mut timers, &mut irq, 3);
+        assert_eq!(timers.read_hword(0x100), 0);
+        step(&mut timers, &mut irq, 1);
+        assert_eq!(timers.read_hword(0x100), 1);
+        step(&mut timers, &mut irq, 63);
+        assert_eq!(timers.read_hword(0x100), 1);
+        step(&mut timers, &mut irq, 1);
+        assert_eq!(timers.read_hword(0x100), 2);
+
+        // Changing the divider keeps the counter, with the next tick when the prescaler reaches the
+        // next multiple of the new divider: 256, rather than 256 cycles after the change at 192.
+        step(&mut timers, &mut irq, 64);
+        timers.write_hword(0x102, 0x0082); // TM0CNT_H: start, divide by 256
+        assert_eq!(timers.read_hword(0x100), 3);
+        step(&mut timers, &mut irq, 63);
+        assert_eq!(timers.read_hword(0x100), 3);
+        step(&mut timers, &mut irq, 1);
+        assert_eq!(timers.read_hword(0x100), 4);
+    }
+
+    #[test]
+    fn steps_of_many_cycles_match_single_cycles() {
+        let (mut timers, mut irq) = (Timers::new(), Irq::new());
+        timers.write_hword(0x100, 0xfffe); // TM0CNT_L
+        timers.write_hword(0x102, 0x00c0); // TM0CNT_H: start, IRQ
+        timers.write_hword(0x106, 0x0084); // TM1CNT_H: start, count-up
+        timers.write_hword(0x10a, 0x0083); // TM2CNT_H: start, divide by 1024
+        let (mut single_timers, mut single_irq) = (timers.clone(), irq.clone());
+
+        let mut audio = Audio::new();
+        for _ in 0..1000 {
+            timers.step(&mut irq, &mut audio, 3);
+        }
+        step(&mut single_timers, &mut single_irq, 3000);
+        for addr in [0x100, 0x104, 0x108] {
+            assert_eq!(timers.read_hword(addr), single_timers.read_hword(addr));
+        }
+        assert_eq!(timers.read_hword(0x104), 1500);
+        assert_eq!(timers.read_hword(0x108), 2);
+        assert_eq!(irq.requested(), single_irq.requested());
     }
 }
@@ -15,7 +15,18 @@ macro_rules! arbitrary_sign_extend {
 }
 
 pub mod video {
-    use crate::video::{Callback, Dot, HBLANK_DOT, VBLANK_DOT};
+    use std::{
+        error::Error,
+        fmt::{self, Display, Formatter},
+        hash::{DefaultHasher, Hash, Hasher},
+        mem::replace,
+        str::FromStr,
+    };
+
+    use crate::{
+        keypad::Key,
+        video::{Callback, Dot, Layer, HBLANK_DOT, VBLANK_DOT},
+    };
 
     #[derive(Clone, Debug)]
     pub struct FrameBuffer<const STRIDE: usize = 3>(pub Box<[u8]>);
@@ -48,6 +59,290 @@ pub mod video {
                 self.0.swap(i + 1, i + STRIDE + 1);
             }
         }
+
+        /// Draws every key as a box near the bottom-left of the screen, where the boxes of keys in
+        /// `pressed` are highlighted. Useful for showing the keypad state (e.g: via
+        /// `Keypad::pressed_keys`) when streaming or debugging input problems.
+        pub fn draw_keypad_overlay(&mut self, pressed: impl IntoIterator<Item = Key>) {
+            // (key, (x, y, width, height)), relative to the top-left of the overlay.
+            const LAYOUT: [(Key, (u8, u8, u8, u8)); 10] = [
+                (Key::L, (0, 0, 10, 3)),
+                (Key::R, (48, 0, 10, 3)),
+                (Key::Up, (5, 5, 4, 4)),
+                (Key::Left, (1, 9, 4, 4)),
+                (Key::Right, (9, 9, 4, 4)),
+                (Key::Down, (5, 13, 4, 4)),
+                (Key::Select, (18, 14, 7, 3)),
+                (Key::Start, (27, 14, 7, 3)),
+                (Key::B, (40, 10, 5, 5)),
+                (Key::A, (48, 6, 5, 5)),
+            ];
+            const ORIGIN: (u8, u8) = (4, VBLANK_DOT - 4 - 17);
+
+            let mut pressed_bits = 0u16;
+            for key in pressed {
+                pressed_bits |= 1 << key as u16;
+            }
+
+            for (key, (x, y, width, height)) in LAYOUT {
+                let rgb = if pressed_bits & (1 << key as u16) != 0 {
+                    [0xff, 0xff, 0xff]
+                } else {
+                    [0x40, 0x40, 0x40]
+                };
+                self.fill_rect((ORIGIN.0 + x, ORIGIN.1 + y), (width, height), rgb);
+            }
+        }
+
+        fn fill_rect(&mut self, (x, y): (u8, u8), (width, height): (u8, u8), rgb: [u8; 3]) {
+            for y in y..y + height {
+                for x in x..x + width {
+                    let i = STRIDE * (usize::from(y) * usize::from(HBLANK_DOT) + usize::from(x));
+                    self.0[i..i + 3].copy_from_slice(&rgb);
+                }
+            }
+        }
+    }
+
+    /// Detects dots where objects are only shown every other frame (a common trick for
+    /// transparency, or for showing more objects than fit on a line), and shows them at half
+    /// alpha instead, which is closer to how they look on the GBA's slow LCD.
+    ///
+    /// Meant to be called from `Callback::put_dot_with_layer`; keeps the history of every dot.
+    #[derive(Clone, Debug)]
+    pub struct FlickerFilter(Box<[DotHistory]>);
+
+    #[derive(Copy, Clone, Debug)]
+    struct DotHistory {
+        prev_dot: Dot,
+        /// Bits set for the recent frames where an object was at the top of the dot, with the
+        /// latest frame in the lowest bit.
+        obj_frames: u8,
+    }
+
+    /// Number of frames an object must alternate between shown and hidden for before it's
+    /// composited.
+    const FLICKER_FRAMES: u32 = 4;
+
+    impl Default for FlickerFilter {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
+    impl FlickerFilter {
+        #[must_use]
+        pub fn new() -> Self {
+            let history = DotHistory {
+                prev_dot: Dot::from(0),
+                // Can't look like flicker until every bit is replaced by a recorded frame.
+                obj_frames: u8::MAX,
+            };
+            Self(vec![history; usize::from(HBLANK_DOT) * usize::from(VBLANK_DOT)].into())
+        }
+
+        /// Records the dot drawn at `(x, y)` this frame, returning the dot to show instead.
+        ///
+        /// # Panics
+        ///
+        /// Panics if `(x, y)` is off-screen.
+        pub fn apply(&mut self, x: u8, y: u8, dot: Dot, top_layer: Option<Layer>) -> Dot {
+            const MASK: u8 = (1 << FLICKER_FRAMES) - 1;
+            const ALTERNATING: u8 = 0b1010_1010 & MASK;
+
+            assert!(x < HBLANK_DOT && y < VBLANK_DOT);
+            let history = &mut self.0[usize::from(y) * usize::from(HBLANK_DOT) + usize::from(x)];
+            history.obj_frames =
+                (history.obj_frames << 1 | u8::from(top_layer == Some(Layer::Obj))) & MASK;
+            let prev_dot = replace(&mut history.prev_dot, dot);
+
+            if history.obj_frames == ALTERNATING || history.obj_frames == !ALTERNATING & MASK {
+                dot.average(prev_dot)
+            } else {
+                dot
+            }
+        }
+    }
+
+    /// How `FrameBlender` combines skipped frames with the next shown frame.
+    #[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+    pub enum BlendMode {
+        /// Average of each component; shows motion as a blur.
+        #[default]
+        Average,
+        /// Minimum of each component; keeps dark things (e.g: text on a light background) that
+        /// were only drawn in some frames visible.
+        Min,
+        /// Maximum of each component; likewise for light things.
+        Max,
+    }
+
+    #[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+    pub struct InvalidBlendMode;
+
+    impl Display for InvalidBlendMode {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            write!(f, "Expected \"average\", \"min\" or \"max\"")
+        }
+    }
+
+    impl Error for InvalidBlendMode {}
+
+    impl FromStr for BlendMode {
+        type Err = InvalidBlendMode;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            match s.to_ascii_lowercase().as_str() {
+                "average" => Ok(Self::Average),
+                "min" => Ok(Self::Min),
+                "max" => Ok(Self::Max),
+                _ => Err(InvalidBlendMode),
+            }
+        }
+    }
+
+    impl Display for BlendMode {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            match self {
+                Self::Average => write!(f, "average"),
+                Self::Min => write!(f, "min"),
+                Self::Max => write!(f, "max"),
+            }
+        }
+    }
+
+    /// Blends the frames skipped since the last shown frame into the next, so that skipping
+    /// frames (e.g: when fast-forwarding) doesn't make the picture jump as much.
+    ///
+    /// Meant to be called from `Callback::put_skipped_dot` and `Callback::put_dot`, while
+    /// `Callback::is_receiving_skipped_dots` returns true.
+    #[derive(Clone, Debug)]
+    pub struct FrameBlender {
+        pub mode: BlendMode,
+        /// Per dot, the red, green and blue components accumulated from the skipped frames: their
+        /// sums for `BlendMode::Average`, otherwise their minimums or maximums.
+        accum: Box<[[u16; 3]]>,
+        skipped_frames: u16,
+    }
+
+    impl FrameBlender {
+        #[must_use]
+        pub fn new(mode: BlendMode) -> Self {
+            Self {
+                mode,
+                accum: vec![[0; 3]; usize::from(HBLANK_DOT) * usize::from(VBLANK_DOT)].into(),
+                skipped_frames: 0,
+            }
+        }
+
+        /// Number of skipped frames that will be blended into the next shown frame.
+        #[must_use]
+        pub fn skipped_frames(&self) -> u16 {
+            self.skipped_frames
+        }
+
+        /// Records the dot drawn at `(x, y)` in a skipped frame.
+        ///
+        /// # Panics
+        ///
+        /// Panics if `(x, y)` is off-screen.
+        pub fn put_skipped(&mut self, x: u8, y: u8, dot: Dot) {
+            assert!(x < HBLANK_DOT && y < VBLANK_DOT);
+            let accum = &mut self.accum[usize::from(y) * usize::from(HBLANK_DOT) + usize::from(x)];
+            let components = [dot.red(), dot.green(), dot.blue()].map(u16::from);
+            if self.skipped_frames == 0 {
+                *accum = components;
+                return;
+            }
+
+            for (accum, c) in accum.iter_mut().zip(components) {
+                *accum = match self.mode {
+                    BlendMode::Average => accum.saturating_add(c),
+                    BlendMode::Min => (*accum).min(c),
+                    BlendMode::Max => (*accum).max(c),
+                };
+            }
+        }
+
+        /// Called at the end of each skipped frame.
+        pub fn end_skipped_frame(&mut self) {
+            // Averaging more frames than this could overflow the sums (and would just be a blur).
+            self.skipped_frames = (self.skipped_frames + 1).min(u16::MAX / 32);
+        }
+
+        /// Returns the dot to show at `(x, y)` in a shown frame, blended with the skipped frames'.
+        ///
+        /// # Panics
+        ///
+        /// Panics if `(x, y)` is off-screen.
+        #[must_use]
+        pub fn apply(&self, x: u8, y: u8, dot: Dot) -> Dot {
+            assert!(x < HBLANK_DOT && y < VBLANK_DOT);
+            if self.skipped_frames == 0 {
+                return dot;
+            }
+
+            let accum = self.accum[usize::from(y) * usize::from(HBLANK_DOT) + usize::from(x)];
+            let components = [dot.red(), dot.green(), dot.blue()].map(u16::from);
+            let bgr = (0..3).fold(0, |bgr, i| {
+                let c = match self.mode {
+                    BlendMode::Average => (accum[i] + components[i]) / (self.skipped_frames + 1),
+                    BlendMode::Min => accum[i].min(components[i]),
+                    BlendMode::Max => accum[i].max(components[i]),
+                };
+                bgr | (c << (5 * i))
+            });
+
+            Dot::from(bgr)
+        }
+
+        /// Called at the end of each shown frame, discarding the skipped frames blended into it.
+        pub fn end_frame(&mut self) {
+            self.skipped_frames = 0;
+        }
+    }
+
+    /// Presents frames less often while the system seems idle (e.g: in a menu), with the screen
+    /// unchanged and no sound for a while, so that frontends can save power.
+    #[derive(Debug, Default)]
+    pub struct PowerSaver {
+        /// Of the previous frame's screen.
+        screen_hash: u64,
+        /// Consecutive frames without a change to the screen or any sound.
+        idle_frames: u32,
+    }
+
+    impl PowerSaver {
+        /// Idle frames after which fewer frames are presented; about 1 second.
+        const IDLE_FRAMES: u32 = 60;
+        /// While idle, only one in this many frames is presented.
+        const IDLE_PRESENT_INTERVAL: u32 = 15;
+
+        /// Called at the end of each shown frame, with whether sound was output during it (e.g:
+        /// from `util::audio::SoundDetector`). Returns whether the frame should be presented.
+        pub fn end_frame<const STRIDE: usize>(
+            &mut self,
+            screen: &FrameBuffer<STRIDE>,
+            audible: bool,
+        ) -> bool {
+            let mut hasher = DefaultHasher::new();
+            screen.0.hash(&mut hasher);
+            let screen_hash = hasher.finish();
+            if replace(&mut self.screen_hash, screen_hash) != screen_hash || audible {
+                self.idle_frames = 0;
+                return true;
+            }
+
+            self.idle_frames = self.idle_frames.saturating_add(1);
+            !self.is_idle()
+                || (self.idle_frames - Self::IDLE_FRAMES) % Self::IDLE_PRESENT_INTERVAL == 0
+        }
+
+        /// Whether fewer frames are being presented.
+        #[must_use]
+        pub fn is_idle(&self) -> bool {
+            self.idle_frames >= Self::IDLE_FRAMES
+        }
     }
 
     pub struct NullCallback;
@@ -61,9 +356,109 @@ pub mod video {
             false
         }
     }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn components(dot: Dot) -> (u8, u8, u8) {
+            (dot.red(), dot.green(), dot.blue())
+        }
+
+        #[test]
+        fn flicker_filter_composites_alternating_objects() {
+            let (obj, bg) = (Dot::WHITE, Dot::from(0));
+            let mut filter = FlickerFilter::new();
+            for frame in 0..FLICKER_FRAMES - 1 {
+                let (dot, layer) = if frame % 2 == 0 {
+                    (obj, Some(Layer::Obj))
+                } else {
+                    (bg, Some(Layer::Bg0))
+                };
+                assert_eq!(
+                    components(filter.apply(10, 20, dot, layer)),
+                    components(dot)
+                );
+            }
+
+            let half = components(obj.average(bg));
+            assert_eq!(components(filter.apply(10, 20, bg, Some(Layer::Bg0))), half);
+            assert_eq!(
+                components(filter.apply(10, 20, obj, Some(Layer::Obj))),
+                half
+            );
+
+            // Other dots have their own history.
+            assert_eq!(
+                components(filter.apply(11, 20, obj, Some(Layer::Obj))),
+                (31, 31, 31)
+            );
+        }
+
+        #[test]
+        fn frame_blender_blends_skipped_frames() {
+            let (dark, light) = (Dot::from(0x0c63), Dot::from(0x7fff)); // (3, 3, 3), white
+            for (mode, expected) in [
+                (BlendMode::Average, (17, 17, 17)),
+                (BlendMode::Min, (3, 3, 3)),
+                (BlendMode::Max, (31, 31, 31)),
+            ] {
+                let mut blender = FrameBlender::new(mode);
+                assert_eq!(components(blender.apply(0, 0, dark)), (3, 3, 3));
+
+                blender.put_skipped(5, 6, light);
+                blender.end_skipped_frame();
+                assert_eq!(blender.skipped_frames(), 1);
+                assert_eq!(components(blender.apply(5, 6, dark)), expected);
+
+                // Skipped frames are only blended into the next shown frame.
+                blender.end_frame();
+                assert_eq!(components(blender.apply(5, 6, dark)), (3, 3, 3));
+            }
+
+            assert_eq!("MAX".parse(), Ok(BlendMode::Max));
+            assert_eq!("blur".parse::<BlendMode>(), Err(InvalidBlendMode));
+        }
+
+        #[test]
+        fn power_saver_presents_less_while_idle() {
+            let mut saver = PowerSaver::default();
+            let mut screen = FrameBuffer::<3>::default();
+            let presented = |saver: &mut PowerSaver, screen, frames, audible| {
+                (0..frames)
+                    .filter(|_| saver.end_frame(screen, audible))
+                    .count()
+            };
+
+            assert_eq!(presented(&mut saver, &screen, 60, false), 60);
+            assert!(!saver.is_idle());
+            assert_eq!(presented(&mut saver, &screen, 60, false), 4);
+            assert!(saver.is_idle());
+
+            // Sound or a change to the screen ends idling.
+            assert!(saver.end_frame(&screen, true));
+            assert!(!saver.is_idle());
+            assert_eq!(presented(&mut saver, &screen, 60, false), 60);
+            screen.put_dot(1, 2, Dot::WHITE);
+            assert!(saver.end_frame(&screen, false));
+            assert!(!saver.is_idle());
+        }
+
+        #[test]
+        fn flicker_filter_ignores_steady_objects() {
+            let mut filter = FlickerFilter::new();
+            for frame in 0..10 {
+                let dot = Dot::from(frame * 0x421);
+                let out = filter.apply(0, 0, dot, Some(Layer::Obj));
+                assert_eq!(components(out), components(dot));
+            }
+        }
+    }
 }
 
 pub mod audio {
+    use std::mem::{replace, take};
+
     use crate::audio::Callback;
 
     pub struct NullCallback;
@@ -71,4 +466,452 @@ pub mod audio {
     impl Callback for NullCallback {
         fn push_sample(&mut self, _: (i16, i16)) {}
     }
+
+    /// Detects whether any sound is output, which is when the samples pushed to it aren't all at
+    /// the same level; silence isn't necessarily at zero, due to the sound bias.
+    #[derive(Debug, Default)]
+    pub struct SoundDetector {
+        last_sample: (i16, i16),
+        audible: bool,
+    }
+
+    impl SoundDetector {
+        pub fn push_sample(&mut self, sample: (i16, i16)) {
+            self.audible |= replace(&mut self.last_sample, sample) != sample;
+        }
+
+        /// Returns whether sound was output since the last call.
+        pub fn take_audible(&mut self) -> bool {
+            take(&mut self.audible)
+        }
+    }
+}
+
+pub mod frame_skip {
+    use std::{
+        error::Error,
+        fmt::{self, Display, Formatter},
+        str::FromStr,
+        time::Duration,
+    };
+
+    /// How frames are skipped when emulation falls behind real-time.
+    #[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+    pub enum Mode {
+        /// Skip up to this many frames in a row whenever behind schedule.
+        Fixed(u32),
+        /// Skip frames only while emulating and rendering frames is consistently slower than
+        /// real-time, as measured by `Controller`.
+        #[default]
+        Auto,
+    }
+
+    #[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+    pub struct InvalidMode;
+
+    impl Display for InvalidMode {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            write!(f, "Expected \"auto\" or a number of frames")
+        }
+    }
+
+    impl Error for InvalidMode {}
+
+    impl FromStr for Mode {
+        type Err = InvalidMode;
+
+        /// Parses "auto" or a maximum number of frames to skip.
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            if s.eq_ignore_ascii_case("auto") {
+                Ok(Self::Auto)
+            } else {
+                s.parse().map(Self::Fixed).map_err(|_| InvalidMode)
+            }
+        }
+    }
+
+    impl Display for Mode {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            match self {
+                Self::Fixed(max_skip) => write!(f, "{max_skip}"),
+                Self::Auto => write!(f, "auto"),
+            }
+        }
+    }
+
+    /// Decides how many frames may be skipped in a row.
+    ///
+    /// In `Mode::Auto`, this keeps a moving average of the time taken to emulate and render a
+    /// frame, relative to the frame's duration on hardware (the "load"). Frames are only skipped
+    /// while the load stays above 1, so a transient spike (e.g: from the host being busy) doesn't
+    /// cause a burst of skipped frames; instead, emulation briefly runs slower than real-time.
+    #[derive(Debug, Clone)]
+    pub struct Controller {
+        pub mode: Mode,
+        frame_duration: Duration,
+        load: f64,
+        skipping: bool,
+    }
+
+    /// Weight of the latest frame time in the load's moving average.
+    const LOAD_SMOOTHING: f64 = 0.05;
+    /// Load above which to start skipping frames, and below which to stop (to avoid rapidly
+    /// toggling frame skipping when the load is close to 1).
+    const START_SKIP_LOAD: f64 = 1.05;
+    const STOP_SKIP_LOAD: f64 = 0.95;
+    /// Maximum frames to skip in a row in `Mode::Auto`.
+    const MAX_AUTO_SKIP: u32 = 4;
+
+    impl Controller {
+        /// Creates a controller for frames lasting `frame_duration` in real-time.
+        #[must_use]
+        pub fn new(mode: Mode, frame_duration: Duration) -> Self {
+            Self {
+                mode,
+                frame_duration,
+                load: 0.0,
+                skipping: false,
+            }
+        }
+
+        /// Records the time taken to emulate and render a frame that wasn't skipped. Time spent
+        /// on skipped frames shouldn't be recorded, as they're cheaper to emulate.
+        pub fn push_frame_time(&mut self, time: Duration) {
+            let frame_load = time.as_secs_f64() / self.frame_duration.as_secs_f64();
+            self.load += LOAD_SMOOTHING * (frame_load - self.load);
+            self.skipping = if self.skipping {
+                self.load > STOP_SKIP_LOAD
+            } else {
+                self.load > START_SKIP_LOAD
+            };
+        }
+
+        /// Returns the moving average of the load, as described in the type's documentation.
+        #[must_use]
+        pub fn load(&self) -> f64 {
+            self.load
+        }
+
+        /// Returns the maximum number of frames that may currently be skipped in a row when
+        /// behind schedule.
+        #[must_use]
+        pub fn max_skip(&self) -> u32 {
+            match self.mode {
+                Mode::Fixed(max_skip) => max_skip,
+                Mode::Auto if self.skipping => {
+                    // To keep up with a load of n, n - 1 of every n frames need to be skipped.
+                    #[expect(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+                    let max_skip = (self.load.ceil() as u32).saturating_sub(1);
+                    max_skip.clamp(1, MAX_AUTO_SKIP)
+                }
+                Mode::Auto => 0,
+            }
+        }
+
+        /// Forgets the measured load, such as after emulation was paused.
+        pub fn reset(&mut self) {
+            self.load = 0.0;
+            self.skipping = false;
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        const FRAME_DURATION: Duration = Duration::from_millis(16);
+
+        #[test]
+        fn auto_ignores_spikes() {
+            let mut ctrl = Controller::new(Mode::Auto, FRAME_DURATION);
+            for _ in 0..100 {
+                ctrl.push_frame_time(FRAME_DURATION / 2);
+            }
+            ctrl.push_frame_time(FRAME_DURATION * 5);
+            assert_eq!(ctrl.max_skip(), 0);
+        }
+
+        #[test]
+        fn auto_skips_when_consistently_behind() {
+            let mut ctrl = Controller::new(Mode::Auto, FRAME_DURATION);
+            for _ in 0..200 {
+                ctrl.push_frame_time(FRAME_DURATION * 5 / 2);
+            }
+            assert_eq!(ctrl.max_skip(), 2);
+
+            for _ in 0..200 {
+                ctrl.push_frame_time(FRAME_DURATION / 2);
+            }
+            assert_eq!(ctrl.max_skip(), 0);
+        }
+
+        #[test]
+        fn parse_mode() {
+            assert_eq!("auto".parse(), Ok(Mode::Auto));
+            assert_eq!("3".parse(), Ok(Mode::Fixed(3)));
+            assert_eq!("-1".parse::<Mode>(), Err(InvalidMode));
+            assert_eq!(Mode::Fixed(0).to_string(), "0");
+        }
+    }
+}
+
+pub use self::frame_limiter::FrameLimiter;
+
+pub mod frame_limiter {
+    use std::{
+        fmt::{self, Display, Formatter},
+        mem::take,
+        time::Duration,
+    };
+
+    use super::frame_skip::{self, Controller as FrameSkipController};
+
+    /// Paces emulation to a frame rate, deciding when each frame is due and which frames skip
+    /// rendering when emulation falls behind (via a `frame_skip::Controller`).
+    ///
+    /// Times are durations since an arbitrary epoch of the frontend's choosing (e.g: an `Instant`,
+    /// or a timestamp from `requestAnimationFrame`), so the limiter doesn't depend on a particular
+    /// clock. Frames are scheduled relative to the previous frame's scheduled time rather than when
+    /// it finished, so the frame rate doesn't drift from sleeping imprecisely.
+    ///
+    /// For each frame, call `Self::start_frame`, emulate the frame, then call `Self::end_frame`.
+    #[derive(Debug, Clone)]
+    pub struct FrameLimiter {
+        pub frame_skip: FrameSkipController,
+        frame_duration: Duration,
+        next_frame_time: Option<Duration>,
+        skipped_frames: u32,
+        fps: FpsCounter,
+    }
+
+    impl FrameLimiter {
+        /// Creates a limiter for frames lasting `frame_duration` in real-time.
+        #[must_use]
+        pub fn new(frame_skip_mode: frame_skip::Mode, frame_duration: Duration) -> Self {
+            Self {
+                frame_skip: FrameSkipController::new(frame_skip_mode, frame_duration),
+                frame_duration,
+                next_frame_time: None,
+                skipped_frames: 0,
+                fps: FpsCounter::new(),
+            }
+        }
+
+        /// Whether the next frame is due at `now`.
+        #[must_use]
+        pub fn is_frame_due(&self, now: Duration) -> bool {
+            self.next_frame_time.map_or(true, |time| now >= time)
+        }
+
+        /// Returns how long to wait from `now` until the next frame is due.
+        #[must_use]
+        pub fn time_until_next_frame(&self, now: Duration) -> Duration {
+            self.next_frame_time
+                .map_or(Duration::ZERO, |time| time.saturating_sub(now))
+        }
+
+        /// Starts emulating a frame at `now`, returning whether its rendering should be skipped.
+        pub fn start_frame(&mut self, now: Duration) -> bool {
+            self.next_frame_time.get_or_insert(now);
+            self.skipped_frames > 0
+        }
+
+        /// Finishes emulating the frame started by `Self::start_frame`, which took `frame_time`
+        /// to emulate (and render, if it wasn't skipped), at `now`.
+        ///
+        /// Returns whether emulation is behind schedule, in which case the next frame should be
+        /// emulated straight away, and its rendering skipped. Otherwise, the next frame is due
+        /// after `Self::time_until_next_frame`. If too many frames in a row would be skipped,
+        /// pacing resumes from when the next frame starts, rather than trying to catch up.
+        pub fn end_frame(&mut self, now: Duration, frame_time: Duration) -> bool {
+            let skipped = self.skipped_frames > 0;
+            if !skipped {
+                self.frame_skip.push_frame_time(frame_time);
+            }
+            self.fps.push_frames(u32::from(!skipped), 1);
+
+            // Never wait for longer than a frame, in case the clock went backwards.
+            let next_frame_time = (self.next_frame_time.unwrap_or(now) + self.frame_duration)
+                .min(now + self.frame_duration);
+            if next_frame_time > now {
+                self.next_frame_time = Some(next_frame_time);
+                self.skipped_frames = 0;
+                false
+            } else if self.skipped_frames >= self.frame_skip.max_skip() {
+                // Too far behind; reschedule from the next frame.
+                self.next_frame_time = None;
+                self.skipped_frames = 0;
+                false
+            } else {
+                self.next_frame_time = Some(next_frame_time);
+                self.skipped_frames += 1;
+                true
+            }
+        }
+
+        /// Resumes pacing from the next frame, such as after fast-forwarding or pausing.
+        pub fn reset(&mut self) {
+            self.next_frame_time = None;
+            self.skipped_frames = 0;
+        }
+
+        /// Counts of the frames finished via `Self::end_frame`; see `FpsCounter::poll`.
+        pub fn poll_fps(&mut self, now: Duration) -> Option<Fps> {
+            self.fps.poll(now)
+        }
+    }
+
+    /// Numbers of frames shown over a second.
+    #[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+    pub struct Fps {
+        /// Frames that were rendered.
+        pub rendered: u32,
+        /// Frames that were emulated, including those with skipped rendering.
+        pub emulated: u32,
+    }
+
+    impl Display for Fps {
+        /// Formats as "FPS: rendered", followed by " (emulated)" if any frames were skipped.
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            write!(f, "FPS: {}", self.rendered)?;
+            if self.emulated != self.rendered {
+                write!(f, " ({})", self.emulated)?;
+            }
+
+            Ok(())
+        }
+    }
+
+    /// Counts frames over each second, for showing the frame rate.
+    #[derive(Debug, Default, Clone)]
+    pub struct FpsCounter {
+        next_second_time: Option<Duration>,
+        frames: Fps,
+    }
+
+    impl FpsCounter {
+        #[must_use]
+        pub fn new() -> Self {
+            Self::default()
+        }
+
+        pub fn push_frames(&mut self, rendered: u32, emulated: u32) {
+            self.frames.rendered += rendered;
+            self.frames.emulated += emulated;
+        }
+
+        /// Returns the frames counted since the last result, if a second has passed since then (or
+        /// since the first call), and starts counting the next second.
+        pub fn poll(&mut self, now: Duration) -> Option<Fps> {
+            const SECOND: Duration = Duration::from_secs(1);
+
+            let next_second_time = *self.next_second_time.get_or_insert(now + SECOND);
+            if now < next_second_time {
+                return None;
+            }
+            self.next_second_time = Some(now + SECOND);
+
+            Some(take(&mut self.frames))
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        const FRAME_DURATION: Duration = Duration::from_millis(16);
+
+        fn ms(ms: u64) -> Duration {
+            Duration::from_millis(ms)
+        }
+
+        #[test]
+        fn paces_without_drift() {
+            let mut limiter = FrameLimiter::new(frame_skip::Mode::Fixed(2), FRAME_DURATION);
+            assert!(limiter.is_frame_due(ms(1000)));
+            let mut now = ms(1000);
+            for frame in 1..=100 {
+                assert!(!limiter.start_frame(now));
+                now += ms(5);
+                assert!(!limiter.end_frame(now, ms(5)));
+                assert!(!limiter.is_frame_due(now));
+                // Oversleep a little; the next frame is still due on schedule.
+                now += limiter.time_until_next_frame(now) + ms(1);
+                assert!(limiter.is_frame_due(now));
+                assert_eq!(now, ms(1000) + FRAME_DURATION * frame + ms(1));
+            }
+        }
+
+        #[test]
+        fn skips_frames_when_behind() {
+            let mut limiter = FrameLimiter::new(frame_skip::Mode::Fixed(2), FRAME_DURATION);
+            let mut now = Duration::ZERO;
+            let mut skips = Vec::new();
+            for _ in 0..6 {
+                skips.push(limiter.start_frame(now));
+                now += FRAME_DURATION * 3;
+                limiter.end_frame(now, FRAME_DURATION * 3);
+                now += limiter.time_until_next_frame(now);
+            }
+            // Gives up catching up after skipping 2 frames in a row.
+            assert_eq!(skips, [false, true, true, false, true, true]);
+
+            // Catches back up when frames are quick again.
+            let mut limiter = FrameLimiter::new(frame_skip::Mode::Fixed(2), FRAME_DURATION);
+            limiter.start_frame(Duration::ZERO);
+            assert!(limiter.end_frame(ms(20), ms(20)));
+            assert!(limiter.start_frame(ms(20)));
+            assert!(!limiter.end_frame(ms(21), ms(1)));
+            assert_eq!(limiter.time_until_next_frame(ms(21)), ms(11));
+            assert!(!limiter.start_frame(ms(32)));
+        }
+
+        #[test]
+        fn auto_frame_skip_ignores_spikes() {
+            let mut limiter = FrameLimiter::new(frame_skip::Mode::Auto, FRAME_DURATION);
+            limiter.start_frame(Duration::ZERO);
+            assert!(!limiter.end_frame(ms(100), ms(100)));
+            assert!(limiter.is_frame_due(ms(100)));
+            assert!(!limiter.start_frame(ms(100)));
+            assert!(!limiter.end_frame(ms(101), ms(1)));
+            assert_eq!(limiter.time_until_next_frame(ms(101)), ms(15));
+        }
+
+        #[test]
+        fn reset_resumes_pacing() {
+            let mut limiter = FrameLimiter::new(frame_skip::Mode::Fixed(2), FRAME_DURATION);
+            limiter.start_frame(Duration::ZERO);
+            limiter.end_frame(ms(1), ms(1));
+            assert!(!limiter.is_frame_due(ms(10)));
+            limiter.reset();
+            assert!(limiter.is_frame_due(ms(10)));
+            assert_eq!(limiter.time_until_next_frame(ms(10)), Duration::ZERO);
+        }
+
+        #[test]
+        fn counts_fps() {
+            let mut limiter = FrameLimiter::new(frame_skip::Mode::Fixed(1), ms(100));
+            assert_eq!(limiter.poll_fps(Duration::ZERO), None);
+            let mut now = Duration::ZERO;
+            for _ in 0..10 {
+                limiter.start_frame(now);
+                // Every other frame is skipped.
+                now += ms(150);
+                limiter.end_frame(now, ms(150));
+            }
+            let fps = limiter.poll_fps(now).unwrap();
+            assert_eq!(
+                fps,
+                Fps {
+                    rendered: 5,
+                    emulated: 10
+                }
+            );
+            assert_eq!(fps.to_string(), "FPS: 5 (10)");
+            assert_eq!(limiter.poll_fps(now), None);
+            assert_eq!(limiter.poll_fps(now + ms(999)), None);
+            assert_eq!(limiter.poll_fps(now + ms(1000)), Some(Fps::default()));
+            assert_eq!(Fps::default().to_string(), "FPS: 0");
+        }
+    }
 }
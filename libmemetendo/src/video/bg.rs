@@ -1,3 +1,5 @@
+use std::cell::RefCell;
+
 use intbits::Bits;
 
 use crate::bus::Bus;
@@ -21,6 +23,191 @@ impl DotInfo {
     }
 }
 
+/// Bytes of VRAM whose writes are tracked together by `TileCache`; the size of a 4bpp tile.
+const BLOCK_LEN: usize = 32;
+const BLOCK_COUNT: usize = 0x1_8000 / BLOCK_LEN;
+
+/// Where a BG's dot is drawn from, as looked up from its screen map.
+#[derive(Debug, Copy, Clone)]
+pub(super) struct TileRef {
+    /// VRAM offset of the tile's dots.
+    pub offset: usize,
+    /// Palette for 4bpp tiles, or `None` for 8bpp tiles.
+    pub palette_idx: Option<u16>,
+    pub flip: (bool, bool),
+}
+
+#[derive(Copy, Clone)]
+struct DecodedTile {
+    /// VRAM generation the tile was decoded at, or 0 if it never was.
+    generation: u32,
+    /// Color index of each dot, in rows.
+    color_idxs: [u8; 64],
+}
+
+#[derive(Copy, Clone)]
+struct DrawnTile {
+    /// Index of the tile in `Tiles::decoded`.
+    idx: usize,
+    palette_idx: Option<u16>,
+    flip: (bool, bool),
+}
+
+/// The tile a BG last drew a dot from.
+#[derive(Copy, Clone)]
+struct LastTile {
+    /// Generation it was looked up at, or 0 if it never was.
+    generation: u32,
+    /// Position in tiles it was looked up for.
+    pos: (i32, i32),
+    /// `None` if no tile is drawn at the position (e.g: outside a BG without wraparound).
+    tile: Option<DrawnTile>,
+}
+
+#[derive(Clone)]
+struct Tiles {
+    /// 4bpp tiles by VRAM offset divided by 32, then 8bpp tiles by VRAM offset divided by 64.
+    decoded: Box<[DecodedTile]>,
+    /// By BG.
+    last: [LastTile; 4],
+}
+
+/// Cache of BG tiles decoded from VRAM, so that tiles are decoded once while unchanged, rather
+/// than for every dot they're drawn to on every scanline (e.g: static screens). The tile each BG
+/// last drew from is also remembered, so that consecutive dots from the same tile (e.g: along a
+/// scanline) skip looking it up from the screen map.
+///
+/// Invalidated lazily via a generation counter, bumped by each VRAM write and by changes to what
+/// screen map lookups depend on (e.g: `BGxCNT`). VRAM writes also record the generation for the
+/// block written, and tiles decoded from a block since written to are decoded again when next
+/// drawn. Palette RAM isn't cached, so writes to it don't invalidate anything.
+#[derive(Clone)]
+pub(super) struct TileCache {
+    generation: u32,
+    /// Generation of the latest write to each block of VRAM.
+    block_generations: Box<[u32]>,
+    /// Updated while drawing, which only borrows the `Video`.
+    tiles: RefCell<Box<Tiles>>,
+}
+
+impl Default for TileCache {
+    fn default() -> Self {
+        let decoded = DecodedTile {
+            generation: 0,
+            color_idxs: [0; 64],
+        };
+        let last = LastTile {
+            generation: 0,
+            pos: (0, 0),
+            tile: None,
+        };
+
+        Self {
+            generation: 1,
+            block_generations: vec![0; BLOCK_COUNT].into_boxed_slice(),
+            tiles: RefCell::new(Box::new(Tiles {
+                decoded: vec![decoded; BLOCK_COUNT + BLOCK_COUNT / 2].into_boxed_slice(),
+                last: [last; 4],
+            })),
+        }
+    }
+}
+
+impl TileCache {
+    /// Invalidates screen map lookups, such as after a write to `BGxCNT`.
+    pub fn invalidate_lookups(&mut self) {
+        if self.generation == u32::MAX {
+            // Start again before the counter wraps, so stale tiles can't look up to date.
+            *self = Self::default();
+        }
+        self.generation += 1;
+    }
+
+    /// Invalidates screen map lookups and tiles decoded from the VRAM at `offset`; called for
+    /// every write to VRAM.
+    pub fn invalidate_vram(&mut self, offset: usize) {
+        self.invalidate_lookups();
+        if let Some(block_generation) = self.block_generations.get_mut(offset / BLOCK_LEN) {
+            *block_generation = self.generation;
+        }
+    }
+
+    /// Returns the color index and palette of the dot at `(dot_x, dot_y)` of the tile at
+    /// `tile_pos` of BG `bg_idx`. If it's not the tile the BG last drew from, it's looked up with
+    /// `look_up`, and decoded from `vram` if it isn't cached. Returns `None` if `look_up` found no
+    /// tile.
+    pub fn dot(
+        &self,
+        vram: &[u8],
+        bg_idx: usize,
+        tile_pos: (i32, i32),
+        (dot_x, dot_y): (u8, u8),
+        look_up: impl FnOnce() -> Option<TileRef>,
+    ) -> Option<(u8, Option<u16>)> {
+        let mut tiles = self.tiles.borrow_mut();
+        let last = tiles.last[bg_idx];
+        let tile = if last.generation == self.generation && last.pos == tile_pos {
+            last.tile?
+        } else {
+            let tile =
+                look_up().map(|tile_ref| self.decode_tile(&mut tiles.decoded, vram, tile_ref));
+            tiles.last[bg_idx] = LastTile {
+                generation: self.generation,
+                pos: tile_pos,
+                tile,
+            };
+            tile?
+        };
+
+        let (dot_x, dot_y) = Video::flip_tile_dot_pos(tile.flip, (1, 1), (dot_x, dot_y));
+        let color_idx =
+            tiles.decoded[tile.idx].color_idxs[8 * usize::from(dot_y) + usize::from(dot_x)];
+
+        Some((color_idx, tile.palette_idx))
+    }
+
+    /// Decodes the tile from `vram` if it isn't cached in `decoded`.
+    fn decode_tile(
+        &self,
+        decoded: &mut [DecodedTile],
+        vram: &[u8],
+        tile_ref: TileRef,
+    ) -> DrawnTile {
+        let color256 = tile_ref.palette_idx.is_none();
+        let (tile_len, tile_idx) = if color256 {
+            (64, BLOCK_COUNT + tile_ref.offset / 64)
+        } else {
+            (32, tile_ref.offset / 32)
+        };
+        let first_block = tile_ref.offset / BLOCK_LEN;
+        let written = self.block_generations[first_block..first_block + tile_len / BLOCK_LEN]
+            .iter()
+            .max()
+            .copied()
+            .unwrap_or(0);
+
+        let tile = &mut decoded[tile_idx];
+        if tile.generation == 0 || tile.generation < written {
+            let bytes = &vram[tile_ref.offset..tile_ref.offset + tile_len];
+            if color256 {
+                tile.color_idxs.copy_from_slice(bytes);
+            } else {
+                for (i, &byte) in bytes.iter().enumerate() {
+                    tile.color_idxs[2 * i] = byte.bits(..4);
+                    tile.color_idxs[2 * i + 1] = byte.bits(4..);
+                }
+            }
+            tile.generation = self.generation;
+        }
+
+        DrawnTile {
+            idx: tile_idx,
+            palette_idx: tile_ref.palette_idx,
+            flip: tile_ref.flip,
+        }
+    }
+}
+
 impl Video {
     pub(super) fn priority_sort_tile_mode_bgs(&mut self) {
         // If many BGs share the same priority, the one with the smallest index wins.
@@ -36,17 +223,17 @@ impl Video {
         &self,
         win: Window,
     ) -> impl Iterator<Item = DotInfo> + '_ {
-        self.tile_mode_bg_order
+        self.line_tile_mode_bg_order
             .iter()
             .filter(move |&&i| {
-                self.dispcnt.display_bg[i]
+                self.line_dispcnt.display_bg[i]
                     && self.window_control(win).map_or(true, |w| w.display_bg[i])
             })
             .filter_map(|&i| self.compute_bg_tile_mode_dot(i))
     }
 
-    fn compute_bg_tile_mode_dot(&self, bg_idx: usize) -> Option<DotInfo> {
-        let text_mode = self.dispcnt.mode == 0 || bg_idx < 2;
+    pub(super) fn compute_bg_tile_mode_dot(&self, bg_idx: usize) -> Option<DotInfo> {
+        let text_mode = self.line_dispcnt.mode == 0 || bg_idx < 2;
 
         let (mut x, mut y) = self.mosaic_transform_pos(bg_idx, (self.x.into(), self.y.into()));
         (x, y) = if text_mode {
@@ -61,6 +248,32 @@ impl Video {
             x.div_euclid(TILE_DOT_LEN.into()),
             y.div_euclid(TILE_DOT_LEN.into()),
         );
+        let (dot_x, dot_y) = (
+            u8::try_from(x.rem_euclid(TILE_DOT_LEN.into()).bits(..8)).unwrap(),
+            u8::try_from(y.rem_euclid(TILE_DOT_LEN.into()).bits(..8)).unwrap(),
+        );
+        let (color_idx, palette_idx) =
+            self.tile_cache
+                .dot(&self.vram, bg_idx, (tile_x, tile_y), (dot_x, dot_y), || {
+                    self.look_up_bg_tile(bg_idx, text_mode, (tile_x, tile_y))
+                })?;
+
+        (color_idx != 0).then_some(DotInfo::TileMode {
+            idx: bg_idx,
+            palette: DotPaletteInfo {
+                idx: palette_idx,
+                color_idx: color_idx.into(),
+            },
+        })
+    }
+
+    /// Looks up the tile at `(tile_x, tile_y)` of a tile mode BG from its screen map.
+    fn look_up_bg_tile(
+        &self,
+        bg_idx: usize,
+        text_mode: bool,
+        (tile_x, tile_y): (i32, i32),
+    ) -> Option<TileRef> {
         let screen_tile_len = self.bgcnt[bg_idx].screen_tile_len(text_mode);
         let screen_idx = if text_mode {
             self.bgcnt[bg_idx].text_mode_screen_index((
@@ -90,53 +303,50 @@ impl Video {
         };
         let screen_tile_idx = screen_tile_y * u32::from(screen_tile_len) + screen_tile_x;
 
-        let (mut dot_x, mut dot_y) = (
-            u8::try_from(x.rem_euclid(TILE_DOT_LEN.into()).bits(..8)).unwrap(),
-            u8::try_from(y.rem_euclid(TILE_DOT_LEN.into()).bits(..8)).unwrap(),
-        );
-        let (dots_idx, palette_idx) = if text_mode {
+        let (dots_idx, palette_idx, flip) = if text_mode {
             let tile_info_offset = u32::try_from(screen_base_offset).unwrap() + 2 * screen_tile_idx;
             let tile_info = self.vram.as_ref().read_hword(tile_info_offset);
             let dots_idx = usize::from(tile_info.bits(..10));
 
-            if self.dispcnt.mode == 0 || (self.dispcnt.mode == 1 && bg_idx < 2) {
-                (dot_x, dot_y) = Self::flip_tile_dot_pos(
-                    (tile_info.bit(10), tile_info.bit(11)),
-                    (1, 1),
-                    (dot_x, dot_y),
-                );
-            }
+            let flip = if self.line_dispcnt.mode == 0 || (self.line_dispcnt.mode == 1 && bg_idx < 2)
+            {
+                (tile_info.bit(10), tile_info.bit(11))
+            } else {
+                (false, false)
+            };
             let color256 = self.bgcnt[bg_idx].color256
-                || (self.dispcnt.mode == 1 && bg_idx == 2)
-                || self.dispcnt.mode == 2;
+                || (self.line_dispcnt.mode == 1 && bg_idx == 2)
+                || self.line_dispcnt.mode == 2;
 
-            (dots_idx, (!color256).then_some(tile_info.bits(12..)))
+            (dots_idx, (!color256).then_some(tile_info.bits(12..)), flip)
         } else {
             let dots_idx_offset = screen_base_offset + usize::try_from(screen_tile_idx).unwrap();
             if dots_idx_offset >= self.vram.len() {
                 return None;
             }
 
-            (usize::from(self.vram[dots_idx_offset]), None)
+            (
+                usize::from(self.vram[dots_idx_offset]),
+                None,
+                (false, false),
+            )
         };
 
         let color256 = palette_idx.is_none();
-        let dot_offset = self.bgcnt[bg_idx].dots_vram_offset()
-            + if color256 { 64 } else { 32 } * dots_idx
-            + (8 * usize::from(dot_y) + usize::from(dot_x)) / if color256 { 1 } else { 2 };
-        if dot_offset >= self.vram.len() {
-            return None;
-        }
+        // Tiles are aligned to their size, so either all of one is in VRAM or none of it is.
+        let offset =
+            self.bgcnt[bg_idx].dots_vram_offset() + if color256 { 64 } else { 32 } * dots_idx;
 
-        self.read_tile_dot_palette(palette_idx, dot_offset, dot_x)
-            .map(|palette| DotInfo::TileMode {
-                idx: bg_idx,
-                palette,
-            })
+        (offset < self.vram.len()).then_some(TileRef {
+            offset,
+            palette_idx,
+            flip,
+        })
     }
 
     pub(super) fn compute_bg_bitmap_mode_dot(&self, win: Window) -> Option<DotInfo> {
-        if !self.dispcnt.display_bg[2] || self.window_control(win).is_some_and(|w| !w.display_bg[2])
+        if !self.line_dispcnt.display_bg[2]
+            || self.window_control(win).is_some_and(|w| !w.display_bg[2])
         {
             return None;
         }
@@ -149,12 +359,12 @@ impl Video {
         #[expect(clippy::cast_sign_loss)]
         let (x, y) = (x as u32, y as u32);
 
-        match self.dispcnt.mode {
+        match self.line_dispcnt.mode {
             _ if x >= HBLANK_DOT.into() || y >= VBLANK_DOT.into() => None,
             3 => Some(DotInfo::Mode3 { pos: (x, y) }),
             4 => {
                 let (x, y) = (usize::try_from(x).unwrap(), usize::try_from(y).unwrap());
-                let frame_offset = self.dispcnt.frame_vram_offset();
+                let frame_offset = self.line_dispcnt.frame_vram_offset();
                 let color_idx = self.vram[frame_offset + y * usize::from(HBLANK_DOT) + x];
 
                 (color_idx > 0).then_some(DotInfo::Mode4 { color_idx })
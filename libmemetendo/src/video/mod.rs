@@ -5,6 +5,10 @@ mod reg;
 use std::iter;
 
 use intbits::Bits;
+use serde::{Deserialize, Serialize};
+use serde_big_array::BigArray;
+use strum::IntoEnumIterator;
+use strum_macros::EnumIter;
 use tinyvec::{array_vec, ArrayVec};
 
 use crate::{
@@ -15,6 +19,7 @@ use crate::{
 };
 
 use self::{
+    bg::TileCache,
     obj::Oam,
     reg::{
         BackgroundAffine, BackgroundControl, BackgroundOffset, BlendCoefficient, BlendControl,
@@ -23,8 +28,19 @@ use self::{
     },
 };
 
-#[derive(Copy, Clone)]
-pub struct PaletteRam([u8; 0x400]);
+/// The registers latched for the current scanline. Not serialized as part of `Video`, as version 1
+/// of its save state chunk didn't save them; later versions save them after it.
+#[derive(Clone, Serialize, Deserialize)]
+pub(crate) struct LineRegisters(DisplayControl, ArrayVec<[usize; 4]>);
+
+/// Whether each BG reference point's coordinates were written since they were last copied to its
+/// internal registers. Not serialized as part of `Video`, as versions 1 and 2 of its save state
+/// chunk didn't save them; later versions save them after its `LineRegisters`.
+#[derive(Default, Clone, Serialize, Deserialize)]
+pub(crate) struct ReferencePointsWritten([(bool, bool); 2]);
+
+#[derive(Copy, Clone, Serialize, Deserialize)]
+pub struct PaletteRam(#[serde(with = "BigArray")] [u8; 0x400]);
 
 impl Default for PaletteRam {
     fn default() -> Self {
@@ -38,8 +54,9 @@ impl Bus for PaletteRam {
     }
 
     fn write_byte(&mut self, addr: u32, value: u8) {
-        // 8-bit writes act weird; write as a hword instead.
-        self.0.write_hword(addr, u16::from_le_bytes([value, value]));
+        // 8-bit writes act weird; write as a hword to the aligned address instead.
+        self.0
+            .write_hword(addr & !1, u16::from_le_bytes([value, value]));
     }
 
     fn write_hword(&mut self, addr: u32, value: u16) {
@@ -70,25 +87,53 @@ impl Bus for Vram<'_> {
         if usize::try_from(addr).unwrap() < self.0.dispcnt.obj_vram_offset() {
             self.0
                 .vram
-                .write_hword(addr, u16::from_le_bytes([value, value]));
+                .write_hword(addr & !1, u16::from_le_bytes([value, value]));
+            self.0
+                .tile_cache
+                .invalidate_vram(usize::try_from(addr).unwrap());
         }
     }
 
     fn write_hword(&mut self, addr: u32, value: u16) {
-        self.0.vram.write_hword(Self::offset(addr), value);
+        let addr = Self::offset(addr);
+        self.0.vram.write_hword(addr, value);
+        self.0
+            .tile_cache
+            .invalidate_vram(usize::try_from(addr).unwrap());
     }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Video {
     x: u16,
     y: u8,
     cycle_accum: u16,
+    /// Cycles since the last blank frame was presented while the system is stopped.
+    #[serde(skip)]
+    stopped_cycle_accum: u32,
+    /// Whether `Callback::end_frame` was called since the last `Self::take_frame_ended`.
+    #[serde(skip)]
+    frame_ended: bool,
     tile_mode_bg_order: ArrayVec<[usize; 4]>,
+    /// `DISPCNT` and the BG drawing order as latched at the start of the current scanline, which
+    /// are what it's drawn with; changes (e.g: mid-frame video mode switches for split-screen
+    /// effects) take effect from the next scanline. Saved after the other fields; see
+    /// `LineRegisters`.
+    #[serde(skip)]
+    line_dispcnt: DisplayControl,
+    #[serde(skip)]
+    line_tile_mode_bg_order: ArrayVec<[usize; 4]>,
 
     vram: Box<[u8]>,
     pub palette_ram: PaletteRam,
     pub oam: Oam,
+    /// Whether writes to OAM are ignored while it's being read to draw objects; see
+    /// `Self::is_oam_accessible`. Disabled by default, as writes are never lost like this on
+    /// hardware (the CPU is stalled instead, which isn't emulated), but games that rely on such
+    /// writes landing at just the right time can show corrupted objects; enabling it makes them
+    /// show up in testing. Not included in save states.
+    #[serde(skip)]
+    pub restrict_oam_access: bool,
 
     dispcnt: DisplayControl,
     dispstat: DisplayStatus,
@@ -106,6 +151,10 @@ pub struct Video {
     bldcnt: BlendControl,
     bldalpha: (BlendCoefficient, BlendCoefficient),
     bldy: BlendCoefficient,
+
+    /// Rebuilt from VRAM as it's drawn, so not included in save states.
+    #[serde(skip)]
+    tile_cache: TileCache,
 }
 
 impl Default for Video {
@@ -120,6 +169,13 @@ pub const VERT_DOTS: u8 = 228;
 pub const HBLANK_DOT: u8 = 240;
 pub const VBLANK_DOT: u8 = 160;
 
+/// Cycles per dot.
+pub const DOT_CYCLES: u16 = 4;
+/// Cycle of a scanline from which `DISPSTAT`'s horizontal blanking flag is set. This is later than
+/// the start of the horizontal blanking period at `HBLANK_DOT` (cycle 960), when its interrupt
+/// and DMA transfers happen.
+pub const HBLANK_FLAG_CYCLE: u16 = 1006;
+
 impl Video {
     #[must_use]
     pub fn new() -> Self {
@@ -127,10 +183,15 @@ impl Video {
             x: 0,
             y: 0,
             cycle_accum: 0,
+            stopped_cycle_accum: 0,
+            frame_ended: false,
             tile_mode_bg_order: array_vec![0, 1, 2, 3],
+            line_dispcnt: DisplayControl::default(),
+            line_tile_mode_bg_order: array_vec![0, 1, 2, 3],
             vram: vec![0; 0x1_8000].into_boxed_slice(),
             palette_ram: PaletteRam::default(),
             oam: Oam::default(),
+            restrict_oam_access: false,
             dispcnt: DisplayControl::default(),
             dispstat: DisplayStatus::default(),
             greenswp: 0,
@@ -147,6 +208,7 @@ impl Video {
             bldcnt: BlendControl::default(),
             bldalpha: (BlendCoefficient::default(), BlendCoefficient::default()),
             bldy: BlendCoefficient::default(),
+            tile_cache: TileCache::default(),
         }
     }
 
@@ -155,11 +217,25 @@ impl Video {
     #[expect(clippy::missing_panics_doc)]
     pub fn step(&mut self, cb: &mut impl Callback, irq: &mut Irq, dma: &mut Dma, cycles: u8) {
         self.cycle_accum += u16::from(cycles);
-        while self.cycle_accum >= 4 {
-            self.cycle_accum -= 4;
+        while self.cycle_accum >= DOT_CYCLES {
+            self.cycle_accum -= DOT_CYCLES;
 
-            if self.x < HBLANK_DOT.into() && self.y < VBLANK_DOT && !cb.is_frame_skipping() {
-                cb.put_dot(self.x.try_into().unwrap(), self.y, self.compute_dot());
+            if self.x == 0 && self.y < VBLANK_DOT {
+                self.latch_line_registers();
+            }
+            if self.x < HBLANK_DOT.into() && self.y < VBLANK_DOT {
+                let x = self.x.try_into().unwrap();
+                if !cb.is_frame_skipping() {
+                    let (dot, top_layer) = self.compute_dot();
+                    cb.put_dot_with_layer(x, self.y, dot, top_layer);
+                    if cb.is_capturing_layers() {
+                        for layer in Layer::iter() {
+                            cb.put_layer_dot(layer, x, self.y, self.compute_layer_dot(layer));
+                        }
+                    }
+                } else if cb.is_receiving_skipped_dots() {
+                    cb.put_skipped_dot(x, self.y, self.compute_dot().0);
+                }
             }
 
             self.x += 1;
@@ -179,6 +255,7 @@ impl Video {
                 }
                 if self.y == VBLANK_DOT - 1 {
                     cb.end_frame(self.greenswp.bit(0));
+                    self.frame_ended = true;
                 }
             }
 
@@ -194,7 +271,7 @@ impl Video {
                     dma.notify(dma::Event::VBlank);
 
                     for bg_ref in &mut self.bgref {
-                        bg_ref.internal = bg_ref.external;
+                        bg_ref.reload();
                     }
                 }
 
@@ -205,8 +282,142 @@ impl Video {
         }
     }
 
+    /// Steps the video unit while the system is in STOP mode. The LCD controller is halted, so no
+    /// dots are drawn and no interrupts or DMA transfers are triggered, but a blank (black) frame is
+    /// still presented every frame period, so that frontends waiting on `Callback::end_frame` (e.g:
+    /// to poll input for a keypad wake-up interrupt) don't hang.
+    pub fn step_stopped(&mut self, cb: &mut impl Callback, cycles: u8) {
+        const CYCLES_PER_FRAME: u32 = DOT_CYCLES as u32 * HORIZ_DOTS as u32 * VERT_DOTS as u32;
+
+        self.stopped_cycle_accum += u32::from(cycles);
+        if self.stopped_cycle_accum < CYCLES_PER_FRAME {
+            return;
+        }
+        self.stopped_cycle_accum -= CYCLES_PER_FRAME;
+
+        let skipping = cb.is_frame_skipping();
+        if !skipping || cb.is_receiving_skipped_dots() {
+            for y in 0..VBLANK_DOT {
+                for x in 0..HBLANK_DOT {
+                    if skipping {
+                        cb.put_skipped_dot(x, y, Dot::new(0, 0, 0));
+                    } else {
+                        cb.put_dot(x, y, Dot::new(0, 0, 0));
+                    }
+                }
+            }
+        }
+        cb.end_frame(false);
+        self.frame_ended = true;
+    }
+
+    /// Whether `DISPCNT`'s forced blank bit is set, which turns off the display.
+    #[must_use]
+    pub fn is_forced_blank(&self) -> bool {
+        self.dispcnt.forced_blank
+    }
+
+    /// Whether OAM is free for the CPU and DMA to access, rather than being read to draw objects.
+    /// It's busy while visible scanlines are drawn, and during the H-Blank before each of them
+    /// unless `DISPCNT`'s "H-Blank interval free" bit is set. It's always free in forced blank.
+    #[must_use]
+    pub fn is_oam_accessible(&self) -> bool {
+        if self.dispcnt.forced_blank {
+            return true;
+        }
+
+        if self.x < HBLANK_DOT.into() {
+            self.y >= VBLANK_DOT
+        } else {
+            let next_y = (self.y + 1) % VERT_DOTS;
+            next_y >= VBLANK_DOT || self.dispcnt.hblank_oam_access
+        }
+    }
+
+    /// Returns the (x, y) position of the dot currently being drawn, including those in the
+    /// blanking periods; y is the same as `VCOUNT`.
+    #[must_use]
+    pub fn position(&self) -> (u16, u8) {
+        (self.x, self.y)
+    }
+
+    /// Returns the number of cycles elapsed since the start of the current scanline, from 0 to
+    /// `HORIZ_DOTS * DOT_CYCLES - 1`.
+    #[must_use]
+    pub fn line_cycle(&self) -> u16 {
+        self.x * DOT_CYCLES + self.cycle_accum
+    }
+
+    /// Latches the registers that the current scanline is drawn with.
+    fn latch_line_registers(&mut self) {
+        if self.line_dispcnt.mode != self.dispcnt.mode {
+            self.tile_cache.invalidate_lookups();
+        }
+        self.line_dispcnt = self.dispcnt;
+        self.line_tile_mode_bg_order = self.tile_mode_bg_order;
+        for bg_ref in &mut self.bgref {
+            bg_ref.reload_written();
+        }
+    }
+
+    /// Returns the state saved by a save state's video chunk: `Video` itself, then the state it
+    /// doesn't serialize itself (see `LineRegisters` and `ReferencePointsWritten`).
+    pub(crate) fn to_saved(&self) -> (&Self, LineRegisters, ReferencePointsWritten) {
+        let line = LineRegisters(self.line_dispcnt, self.line_tile_mode_bg_order);
+        let written = ReferencePointsWritten(self.bgref.map(|bg_ref| bg_ref.written));
+        (self, line, written)
+    }
+
+    /// Restores the state returned by `Self::to_saved`.
+    pub(crate) fn from_saved(
+        (mut video, line, written): (Self, LineRegisters, ReferencePointsWritten),
+    ) -> Self {
+        LineRegisters(video.line_dispcnt, video.line_tile_mode_bg_order) = line;
+        for (bg_ref, written) in video.bgref.iter_mut().zip(written.0) {
+            bg_ref.written = written;
+        }
+        video
+    }
+
+    /// Migrates state loaded from version 1 of its save state chunk, which didn't save the line
+    /// registers, by latching them from the current registers.
+    pub(crate) fn migrate_v1(mut self) -> Self {
+        self.latch_line_registers();
+        self
+    }
+
+    /// Migrates state loaded from version 2 of its save state chunk, which didn't save whether the
+    /// BG reference points were written; they're assumed not to be.
+    pub(crate) fn migrate_v2((video, line): (Self, LineRegisters)) -> Self {
+        Self::from_saved((video, line, ReferencePointsWritten::default()))
+    }
+
+    /// Whether the state is in range, which is always the case unless it was loaded from a
+    /// malformed save state; such state can panic when stepped or drawn.
+    pub(crate) fn is_valid(&self) -> bool {
+        let bg_order_valid = |order: &ArrayVec<[usize; 4]>| order.iter().all(|&i| i < 4);
+
+        self.x < HORIZ_DOTS
+            && self.y < VERT_DOTS
+            && self.cycle_accum < DOT_CYCLES
+            && bg_order_valid(&self.tile_mode_bg_order)
+            && bg_order_valid(&self.line_tile_mode_bg_order)
+            && self.dispcnt.is_valid()
+            && self.line_dispcnt.is_valid()
+            && self.vram.len() == 0x1_8000
+            && self.bgcnt.iter().all(|bgcnt| bgcnt.is_valid())
+            && self.bgref.iter().all(ReferencePoint::is_valid)
+            && self.mosaic_bg.is_valid()
+            && self.mosaic_obj.is_valid()
+    }
+
+    /// Whether a frame ended since the last call.
+    pub(crate) fn take_frame_ended(&mut self) -> bool {
+        std::mem::take(&mut self.frame_ended)
+    }
+
     #[must_use]
-    pub fn vram(&mut self) -> Vram {
+    pub fn vram(&mut self) -> Vram<'_> {
         Vram(self)
     }
 }
@@ -243,6 +454,16 @@ impl Dot {
         Dot { r, g, b }
     }
 
+    /// Returns the dot halfway between this one and `other`.
+    #[must_use]
+    pub const fn average(self, other: Dot) -> Dot {
+        Dot::new(
+            (self.r + other.r) / 2,
+            (self.g + other.g) / 2,
+            (self.b + other.b) / 2,
+        )
+    }
+
     #[must_use]
     pub const fn red(self) -> u8 {
         self.r
@@ -259,10 +480,45 @@ impl Dot {
     }
 }
 
+/// A layer that can be rendered on its own via `Callback::put_layer_dot`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, EnumIter)]
+pub enum Layer {
+    Bg0,
+    Bg1,
+    Bg2,
+    Bg3,
+    Obj,
+}
+
 pub trait Callback {
     fn put_dot(&mut self, x: u8, y: u8, dot: Dot);
     fn end_frame(&mut self, green_swap: bool);
     fn is_frame_skipping(&self) -> bool;
+
+    /// Like `put_dot`, but also receives the layer at the top of the dot, or `None` for the
+    /// backdrop and forced blank. Calls `put_dot` by default.
+    fn put_dot_with_layer(&mut self, x: u8, y: u8, dot: Dot, _top_layer: Option<Layer>) {
+        self.put_dot(x, y, dot);
+    }
+
+    /// If true, `put_layer_dot` is called for every layer alongside each `put_dot`.
+    fn is_capturing_layers(&self) -> bool {
+        false
+    }
+
+    /// Receives the dot of a single layer, ignoring windows, priorities and blending effects.
+    /// `dot` is `None` if the layer is transparent or not displayed at this position.
+    fn put_layer_dot(&mut self, _layer: Layer, _x: u8, _y: u8, _dot: Option<Dot>) {}
+
+    /// If true, dots are still computed while frames are skipped, and passed to
+    /// `put_skipped_dot` (e.g: to blend skipped frames into the next shown frame). Computing dots is
+    /// most of the cost of a frame, so skipping frames then saves much less time.
+    fn is_receiving_skipped_dots(&self) -> bool {
+        false
+    }
+
+    /// Receives a dot of a skipped frame; only called if `is_receiving_skipped_dots`.
+    fn put_skipped_dot(&mut self, _x: u8, _y: u8, _dot: Dot) {}
 }
 
 #[derive(Debug, Copy, Clone)]
@@ -272,17 +528,44 @@ enum DotInfo {
     Backdrop,
 }
 
+impl DotInfo {
+    fn layer(self) -> Option<Layer> {
+        match self {
+            DotInfo::Object(_) => Some(Layer::Obj),
+            DotInfo::Background(info) => Layer::iter().nth(info.index()),
+            DotInfo::Backdrop => None,
+        }
+    }
+}
+
 impl Video {
-    fn compute_dot(&mut self) -> Dot {
-        if self.dispcnt.forced_blank {
-            return Dot::WHITE;
+    fn compute_dot(&mut self) -> (Dot, Option<Layer>) {
+        if self.line_dispcnt.forced_blank {
+            return (Dot::WHITE, None);
         }
 
         let top_win = self.find_top_window();
         let mut top_iter = self.compute_top_dots_iter(top_win).peekable();
         let top_info = top_iter.next().unwrap();
         let top_dot = self.read_dot(top_info);
+        let top_layer = top_info.layer();
+
+        // Windows with color special effects disabled also disable semi-transparent objects.
+        if self
+            .window_control(top_win)
+            .is_some_and(|w| !w.blendfx_enabled)
+        {
+            return (top_dot, top_layer);
+        }
 
+        let is_target = |info: &DotInfo, dot_idx: usize| match info {
+            DotInfo::Object(_) => self.bldcnt.obj_target[dot_idx],
+            DotInfo::Background(bg) => self.bldcnt.bg_target[dot_idx][bg.index()],
+            DotInfo::Backdrop => self.bldcnt.backdrop_target[dot_idx],
+        };
+        // Semi-transparent objects are always alpha blended with 2nd targets below them, whatever
+        // the blend mode and 1st targets. Otherwise, they're affected by the blend mode like
+        // other objects.
         let obj_alpha_mode = matches!(
             top_info,
             DotInfo::Object(obj::DotInfo {
@@ -290,31 +573,47 @@ impl Video {
                 ..
             })
         );
-        let is_target = |info: &DotInfo, dot_idx: usize| {
-            let targeted = match info {
-                DotInfo::Object(_) => self.bldcnt.obj_target[dot_idx] || obj_alpha_mode,
-                DotInfo::Background(bg) => self.bldcnt.bg_target[dot_idx][bg.index()],
-                DotInfo::Backdrop => self.bldcnt.backdrop_target[dot_idx],
-            };
-            let win_blendfx = self
-                .window_control(top_win)
-                .map_or(true, |w| w.blendfx_enabled);
-
-            targeted && win_blendfx
+        let alpha_blend =
+            obj_alpha_mode || (self.bldcnt.mode == BlendMode::Alpha && is_target(&top_info, 0));
+
+        let dot = if alpha_blend && is_target(top_iter.peek().unwrap(), 1) {
+            let bot_dot = self.read_dot(top_iter.next().unwrap());
+            self.alpha_blend_dots(top_dot, bot_dot)
+        } else if is_target(&top_info, 0) {
+            match self.bldcnt.mode {
+                BlendMode::Brighten => self.adjust_dot_brightness(false, top_dot),
+                BlendMode::Dim => self.adjust_dot_brightness(true, top_dot),
+                _ => top_dot,
+            }
+        } else {
+            top_dot
         };
 
-        match self.bldcnt.mode {
-            _ if !is_target(&top_info, 0) => top_dot,
-            mode if (mode == BlendMode::Alpha || obj_alpha_mode)
-                && is_target(top_iter.peek().unwrap(), 1) =>
-            {
-                let bot_dot = self.read_dot(top_iter.next().unwrap());
-                self.alpha_blend_dots(top_dot, bot_dot)
-            }
-            BlendMode::Brighten => self.adjust_dot_brightness(false, top_dot),
-            BlendMode::Dim => self.adjust_dot_brightness(true, top_dot),
-            _ => top_dot,
+        (dot, top_layer)
+    }
+
+    fn compute_layer_dot(&self, layer: Layer) -> Option<Dot> {
+        if self.line_dispcnt.forced_blank {
+            return None;
         }
+
+        let info = match (layer, self.line_dispcnt.mode()) {
+            (Layer::Obj, _) => self.compute_top_obj_dot(Window::None).map(DotInfo::Object),
+            (Layer::Bg2, BackgroundMode::Bitmap) => self
+                .compute_bg_bitmap_mode_dot(Window::None)
+                .map(DotInfo::Background),
+            (_, BackgroundMode::Tile) => {
+                let bg_idx = layer as usize;
+                (self.line_dispcnt.display_bg[bg_idx]
+                    && self.line_tile_mode_bg_order.contains(&bg_idx))
+                .then(|| self.compute_bg_tile_mode_dot(bg_idx))
+                .flatten()
+                .map(DotInfo::Background)
+            }
+            _ => None,
+        };
+
+        info.map(|info| self.read_dot(info))
     }
 
     fn read_dot(&self, info: DotInfo) -> Dot {
@@ -328,7 +627,8 @@ impl Video {
                 bg::DotInfo::Mode3 { pos: (x, y) } => vram(2 * (y * u32::from(HBLANK_DOT) + x)),
                 bg::DotInfo::Mode4 { color_idx } => palette_ram(2 * u32::from(color_idx)),
                 bg::DotInfo::Mode5 { pos: (x, y) } => vram(
-                    u32::try_from(self.dispcnt.frame_vram_offset()).unwrap() + 2 * (y * 160 + x),
+                    u32::try_from(self.line_dispcnt.frame_vram_offset()).unwrap()
+                        + 2 * (y * 160 + x),
                 ),
             },
             DotInfo::Backdrop => palette_ram(0),
@@ -339,7 +639,7 @@ impl Video {
         let mut obj_info = self.compute_top_obj_dot(top_win);
         let mut bg_tile_mode_iter = self.compute_bg_tile_mode_dot_iter(top_win).peekable();
 
-        iter::from_fn(move || match self.dispcnt.mode() {
+        iter::from_fn(move || match self.line_dispcnt.mode() {
             BackgroundMode::Tile => match (obj_info, bg_tile_mode_iter.peek()) {
                 (Some(obj), Some(bg)) if obj.priority <= self.bgcnt[bg.index()].priority => {
                     Some(DotInfo::Object(obj_info.take().unwrap()))
@@ -418,12 +718,14 @@ impl Video {
     }
 
     fn find_top_window(&self) -> Window {
-        if self.dispcnt.display_bg_window == [false; 2] && !self.dispcnt.display_obj_window {
+        if self.line_dispcnt.display_bg_window == [false; 2]
+            && !self.line_dispcnt.display_obj_window
+        {
             return Window::None;
         }
 
         for win_idx in 0..2 {
-            if !self.dispcnt.display_bg_window[win_idx] {
+            if !self.line_dispcnt.display_bg_window[win_idx] {
                 continue;
             }
 
@@ -444,7 +746,7 @@ impl Video {
             }
         }
 
-        if self.dispcnt.display_obj_window && self.check_inside_obj_window() {
+        if self.line_dispcnt.display_obj_window && self.check_inside_obj_window() {
             Window::Object
         } else {
             Window::Outside
@@ -473,7 +775,6 @@ impl DotPaletteInfo {
 const TILE_DOT_LEN: u8 = 8;
 
 impl Video {
-    #[expect(clippy::similar_names)]
     fn affine_transform_pos(
         (ref_x, ref_y): (i32, i32),
         (dmx, dmy): (i32, i32),
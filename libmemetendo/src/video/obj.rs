@@ -1,3 +1,5 @@
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_big_array::BigArray;
 use strum_macros::FromRepr;
 use tinyvec::ArrayVec;
 
@@ -199,8 +201,27 @@ const REGIONS_SIZE: (usize, usize) = (
 
 impl Default for Oam {
     fn default() -> Self {
+        Self::from_buf(&[0; 0x400])
+    }
+}
+
+// Only the contents of OAM are saved; the caches are rebuilt from them when loaded.
+impl Serialize for Oam {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        BigArray::serialize(&self.buf, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Oam {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(Self::from_buf(&BigArray::deserialize(deserializer)?))
+    }
+}
+
+impl Oam {
+    fn from_buf(buf: &[u8; 0x400]) -> Self {
         let mut oam = Self {
-            buf: [0; 0x400],
+            buf: *buf,
             attrs: [Attributes::default(); 128],
             regions: vec![ArrayVec::new(); REGIONS_SIZE.0 * REGIONS_SIZE.1].into_boxed_slice(),
         };
@@ -210,9 +231,7 @@ impl Default for Oam {
 
         oam
     }
-}
 
-impl Oam {
     fn region_pos((x, y): (u16, u16)) -> (u16, u16) {
         (x / u16::from(TILE_DOT_LEN), y / u16::from(TILE_DOT_LEN))
     }
@@ -228,51 +247,55 @@ impl Oam {
             }
 
             let (clip_width, clip_height) = attrs.clip_dots_size();
-            let (start_x, start_y) = attrs.pos();
-            let (end_x, end_y) = (
-                start_x + i16::from(clip_width) - 1,
-                start_y + i16::from(clip_height) - 1,
-            );
-            if start_x >= HBLANK_DOT.into()
-                || start_y >= VBLANK_DOT.into()
-                || end_x < 0
-                || end_y < 0
-            {
-                return; // Fully outside of the drawable area; no region.
-            }
+            let (start_x, y) = attrs.pos();
+            // Y wraps at 8 bits, so sprites extending past the bottom of the 256 line area are
+            // also drawn at the top of the screen.
+            for start_y in [y, y - 256] {
+                let (end_x, end_y) = (
+                    start_x + i16::from(clip_width) - 1,
+                    start_y + i16::from(clip_height) - 1,
+                );
+                if start_x >= HBLANK_DOT.into()
+                    || start_y >= VBLANK_DOT.into()
+                    || end_x < 0
+                    || end_y < 0
+                {
+                    continue; // Fully outside of the drawable area; no region.
+                }
 
-            #[expect(clippy::cast_sign_loss)]
-            let (start_region_x, start_region_y) =
-                Self::region_pos((start_x.max(0) as u16, start_y.max(0) as u16));
-            #[expect(clippy::cast_sign_loss)]
-            let (end_region_x, end_region_y) = Self::region_pos((
-                end_x.min(i16::from(HBLANK_DOT) - 1) as u16,
-                end_y.min(i16::from(VBLANK_DOT) - 1) as u16,
-            ));
-
-            let cmp = |&i: &u8| {
-                self.attrs[usize::from(i)]
-                    .priority()
-                    .cmp(&attrs.priority())
-                    .then_with(|| i.cmp(&idx))
-            };
+                #[expect(clippy::cast_sign_loss)]
+                let (start_region_x, start_region_y) =
+                    Self::region_pos((start_x.max(0) as u16, start_y.max(0) as u16));
+                #[expect(clippy::cast_sign_loss)]
+                let (end_region_x, end_region_y) = Self::region_pos((
+                    end_x.min(i16::from(HBLANK_DOT) - 1) as u16,
+                    end_y.min(i16::from(VBLANK_DOT) - 1) as u16,
+                ));
+
+                let cmp = |&i: &u8| {
+                    self.attrs[usize::from(i)]
+                        .priority()
+                        .cmp(&attrs.priority())
+                        .then_with(|| i.cmp(&idx))
+                };
 
-            let mut region_y = start_region_y;
-            while region_y <= end_region_y {
-                let mut region_x = start_region_x;
-                while region_x <= end_region_x {
-                    let region_idxs = &mut regions[Self::region_index((region_x, region_y))];
-                    if remove {
-                        if let Ok(i) = region_idxs.binary_search_by(cmp) {
-                            region_idxs.remove(i);
+                let mut region_y = start_region_y;
+                while region_y <= end_region_y {
+                    let mut region_x = start_region_x;
+                    while region_x <= end_region_x {
+                        let region_idxs = &mut regions[Self::region_index((region_x, region_y))];
+                        if remove {
+                            if let Ok(i) = region_idxs.binary_search_by(cmp) {
+                                region_idxs.remove(i);
+                            }
+                        } else if let Err(i) = region_idxs.binary_search_by(cmp) {
+                            region_idxs.insert(i, idx);
                         }
-                    } else if let Err(i) = region_idxs.binary_search_by(cmp) {
-                        region_idxs.insert(i, idx);
-                    }
 
-                    region_x += 1;
+                        region_x += 1;
+                    }
+                    region_y += 1;
                 }
-                region_y += 1;
             }
         };
 
@@ -349,7 +372,7 @@ impl Video {
     }
 
     pub(super) fn check_inside_obj_window(&self) -> bool {
-        self.dispcnt.display_obj
+        self.line_dispcnt.display_obj
             && self
                 .region_attrs_iter()
                 .filter(|&attrs| attrs.mode() == Some(Mode::WindowMask))
@@ -358,7 +381,9 @@ impl Video {
     }
 
     pub(super) fn compute_top_obj_dot(&self, win: Window) -> Option<DotInfo> {
-        if !self.dispcnt.display_obj || self.window_control(win).is_some_and(|w| !w.display_obj) {
+        if !self.line_dispcnt.display_obj
+            || self.window_control(win).is_some_and(|w| !w.display_obj)
+        {
             return None;
         }
 
@@ -375,15 +400,18 @@ impl Video {
         let (x, y) = (self.x as i16, i16::from(self.y));
         let (obj_x, obj_y) = attrs.pos();
         let (clip_width, clip_height) = attrs.clip_dots_size();
+        // X wraps at 9 bits, so no sprite can span both edges of the screen, but Y wraps at 8
+        // bits, so tall sprites near the bottom can also be drawn at the top.
+        let obj_dot_actual_y = (y - obj_y).rem_euclid(256);
         if !(obj_x..obj_x + i16::from(clip_width)).contains(&x)
-            || !(obj_y..obj_y + i16::from(clip_height)).contains(&y)
+            || obj_dot_actual_y >= i16::from(clip_height)
         {
             return None; // Clipped
         }
 
         let (obj_dot_actual_x, obj_dot_actual_y) = (
             u8::try_from(x - obj_x).unwrap(),
-            u8::try_from(y - obj_y).unwrap(),
+            u8::try_from(obj_dot_actual_y).unwrap(),
         );
         let (mut obj_dot_x, mut obj_dot_y) = (obj_dot_actual_x, obj_dot_actual_y);
         let mosaic = attrs.mosaic() && self.mosaic_obj.get() != (1, 1);
@@ -448,7 +476,7 @@ impl Video {
 
         let (tile_x, tile_y) = (obj_dot_x / TILE_DOT_LEN, obj_dot_y / TILE_DOT_LEN);
         let color256 = attrs.palette_idx().is_none();
-        let dots_row_stride = if self.dispcnt.obj_1d {
+        let dots_row_stride = if self.line_dispcnt.obj_1d {
             usize::from(tile_width) * if color256 { 2 } else { 1 }
         } else {
             32 // 2D mapping always uses 32x32 tile maps
@@ -461,7 +489,7 @@ impl Video {
         let (dot_x, dot_y) = (obj_dot_x % TILE_DOT_LEN, obj_dot_y % TILE_DOT_LEN);
         let dot_offset = dots_offset
             + (8 * usize::from(dot_y) + usize::from(dot_x)) / if color256 { 1 } else { 2 };
-        if dot_offset < self.dispcnt.obj_vram_offset() || dot_offset >= self.vram.len() {
+        if dot_offset < self.line_dispcnt.obj_vram_offset() || dot_offset >= self.vram.len() {
             return None; // Outside of obj VRAM
         }
 
@@ -473,7 +501,6 @@ impl Video {
             })
     }
 
-    #[expect(clippy::similar_names)]
     fn obj_affine_transform_pos(
         &self,
         params_idx: u32,
@@ -1,12 +1,15 @@
+use std::mem::take;
+
 use intbits::Bits;
+use serde::{Deserialize, Serialize};
 use strum_macros::FromRepr;
 use tinyvec::array_vec;
 
 use crate::{arbitrary_sign_extend, bus::Bus};
 
-use super::{Video, HBLANK_DOT, VBLANK_DOT};
+use super::{Video, HBLANK_FLAG_CYCLE, VBLANK_DOT, VERT_DOTS};
 
-#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Serialize, Deserialize)]
 pub enum BackgroundMode {
     Tile,
     Bitmap,
@@ -14,11 +17,11 @@ pub enum BackgroundMode {
 }
 
 #[expect(clippy::struct_excessive_bools)]
-#[derive(Default, Copy, Clone, Debug)]
+#[derive(Default, Copy, Clone, Debug, Serialize, Deserialize)]
 pub(super) struct DisplayControl {
     pub mode: u8,
     frame_select: u8,
-    hblank_oam_access: bool,
+    pub hblank_oam_access: bool,
     pub obj_1d: bool,
     pub forced_blank: bool,
     pub display_bg: [bool; 4],
@@ -51,6 +54,10 @@ impl DisplayControl {
         self.display_obj_window = bits.bit(7);
     }
 
+    pub fn is_valid(&self) -> bool {
+        self.mode < 8 && self.frame_select < 2
+    }
+
     pub fn mode(&self) -> BackgroundMode {
         match self.mode {
             0..=2 => BackgroundMode::Tile,
@@ -71,7 +78,7 @@ impl DisplayControl {
     }
 }
 
-#[derive(Default, Copy, Clone, Debug)]
+#[derive(Default, Copy, Clone, Debug, Serialize, Deserialize)]
 pub(super) struct DisplayStatus {
     pub vblank_irq_enabled: bool,
     pub hblank_irq_enabled: bool,
@@ -82,7 +89,7 @@ pub(super) struct DisplayStatus {
 
 impl DisplayStatus {
     fn lo_bits(self, vblanking: bool, hblanking: bool, vcount: u8) -> u8 {
-        self.cached_bits
+        (self.cached_bits & 0x38)
             .with_bit(0, vblanking)
             .with_bit(1, hblanking)
             .with_bit(2, vcount == self.vcount_target)
@@ -96,7 +103,7 @@ impl DisplayStatus {
     }
 }
 
-#[derive(Copy, Clone, Default, Debug, FromRepr)]
+#[derive(Copy, Clone, Default, Debug, FromRepr, Serialize, Deserialize)]
 #[repr(u8)]
 pub(super) enum ScreenAreas {
     #[default]
@@ -106,7 +113,7 @@ pub(super) enum ScreenAreas {
     Four,
 }
 
-#[derive(Copy, Clone, Default, Debug)]
+#[derive(Copy, Clone, Default, Debug, Serialize, Deserialize)]
 pub(super) struct BackgroundControl {
     pub priority: u8,
     dots_base_block: u8,
@@ -126,6 +133,12 @@ impl Video {
         if old_priority != self.bgcnt[bg_idx].priority {
             self.priority_sort_tile_mode_bgs();
         }
+        self.tile_cache.invalidate_lookups();
+    }
+
+    fn set_bgcnt_hi_bits(&mut self, bg_idx: usize, bits: u8) {
+        self.bgcnt[bg_idx].set_hi_bits(bits);
+        self.tile_cache.invalidate_lookups();
     }
 }
 
@@ -145,6 +158,10 @@ impl BackgroundControl {
         self.screen_config = ScreenAreas::from_repr(bits.bits(6..)).unwrap();
     }
 
+    pub fn is_valid(self) -> bool {
+        self.priority < 4 && self.dots_base_block < 4 && self.screen_base_block < 32
+    }
+
     pub fn dots_vram_offset(self) -> usize {
         0x4000 * usize::from(self.dots_base_block)
     }
@@ -174,7 +191,7 @@ impl BackgroundControl {
     }
 }
 
-#[derive(Copy, Clone, Default, Debug)]
+#[derive(Copy, Clone, Default, Debug, Serialize, Deserialize)]
 pub(super) struct BackgroundOffset(u16, u16);
 
 impl BackgroundOffset {
@@ -183,10 +200,14 @@ impl BackgroundOffset {
     }
 }
 
-#[derive(Copy, Clone, Default, Debug)]
+#[derive(Copy, Clone, Default, Debug, Serialize, Deserialize)]
 pub(super) struct ReferencePoint {
     pub external: (i32, i32),
     pub internal: (i32, i32),
+    /// Whether the X and Y coordinates were written since they were last copied to the internal
+    /// registers. Saved after `Video`; see `ReferencePointsWritten`.
+    #[serde(skip)]
+    pub written: (bool, bool),
 }
 
 impl ReferencePoint {
@@ -204,16 +225,45 @@ impl ReferencePoint {
 
     fn set_x_byte(&mut self, idx: usize, bits: u8) {
         Self::set_byte(&mut self.external.0, idx, bits);
-        self.internal.0 = self.external.0;
+        self.written.0 = true;
     }
 
     fn set_y_byte(&mut self, idx: usize, bits: u8) {
         Self::set_byte(&mut self.external.1, idx, bits);
-        self.internal.1 = self.external.1;
+        self.written.1 = true;
+    }
+
+    /// Whether the coordinates are in range: the external ones are 28 bits, and the internal ones
+    /// drift at most a frame's worth of increments further.
+    pub fn is_valid(&self) -> bool {
+        let in_range =
+            |coord: i32, bits: u32| (-(1 << (bits - 1))..1 << (bits - 1)).contains(&coord);
+        in_range(self.external.0, 28)
+            && in_range(self.external.1, 28)
+            && in_range(self.internal.0, 29)
+            && in_range(self.internal.1, 29)
+    }
+
+    /// Copies both coordinates to the internal registers, as done at the start of `VBlank`.
+    pub fn reload(&mut self) {
+        self.internal = self.external;
+        self.written = (false, false);
+    }
+
+    /// Copies the coordinates written since the last copy to the internal registers, replacing the
+    /// per-scanline increment. Done at the start of each scanline, so writes in the middle of one
+    /// (e.g: from a `HBlank` IRQ or DMA) don't affect the rest of it.
+    pub fn reload_written(&mut self) {
+        if take(&mut self.written.0) {
+            self.internal.0 = self.external.0;
+        }
+        if take(&mut self.written.1) {
+            self.internal.1 = self.external.1;
+        }
     }
 }
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, Serialize, Deserialize)]
 pub(super) struct BackgroundAffine {
     pub a: i16,
     pub b: i16,
@@ -232,13 +282,13 @@ impl Default for BackgroundAffine {
     }
 }
 
-#[derive(Default, Copy, Clone, Debug)]
+#[derive(Default, Copy, Clone, Debug, Serialize, Deserialize)]
 pub(super) struct WindowDimensions {
     pub horiz: (u8, u8),
     pub vert: (u8, u8),
 }
 
-#[derive(Copy, Clone, Default, Debug)]
+#[derive(Copy, Clone, Default, Debug, Serialize, Deserialize)]
 pub(super) struct WindowControl {
     pub display_bg: [bool; 4],
     pub display_obj: bool,
@@ -258,9 +308,16 @@ impl WindowControl {
     }
 }
 
-#[derive(Copy, Clone, Default, Debug)]
+#[derive(Copy, Clone, Debug, Serialize, Deserialize)]
 pub(super) struct MosaicSize(u8, u8);
 
+impl Default for MosaicSize {
+    fn default() -> Self {
+        // MOSAIC is initially 0, which is a size of 1x1 (no mosaic).
+        Self(1, 1)
+    }
+}
+
 impl MosaicSize {
     fn set_bits(&mut self, bits: u8) {
         self.0 = bits.bits(..4) + 1;
@@ -270,9 +327,13 @@ impl MosaicSize {
     pub fn get(self) -> (u8, u8) {
         (self.0, self.1)
     }
+
+    pub fn is_valid(self) -> bool {
+        (1..=16).contains(&self.0) && (1..=16).contains(&self.1)
+    }
 }
 
-#[derive(Copy, Clone, Eq, PartialEq, FromRepr, Default, Debug)]
+#[derive(Copy, Clone, Eq, PartialEq, FromRepr, Default, Debug, Serialize, Deserialize)]
 #[repr(u8)]
 pub(super) enum BlendMode {
     #[default]
@@ -282,7 +343,7 @@ pub(super) enum BlendMode {
     Dim,
 }
 
-#[derive(Copy, Clone, Default, Debug)]
+#[derive(Copy, Clone, Default, Debug, Serialize, Deserialize)]
 pub(super) struct BlendControl {
     pub bg_target: [[bool; 4]; 2],
     pub obj_target: [bool; 2],
@@ -314,7 +375,7 @@ impl BlendControl {
     }
 }
 
-#[derive(Copy, Clone, Default, Debug)]
+#[derive(Copy, Clone, Default, Debug, Serialize, Deserialize)]
 pub(super) struct BlendCoefficient(u8);
 
 impl BlendCoefficient {
@@ -326,16 +387,15 @@ impl BlendCoefficient {
 impl Bus for Video {
     fn read_byte(&mut self, addr: u32) -> u8 {
         match addr {
-            // DISPCNT
-            0x00 => self.dispcnt.cached_bits.bits(..8).try_into().unwrap(),
+            // DISPCNT (bit 3 can only be set by BIOS opcodes)
+            0x00 => u8::try_from(self.dispcnt.cached_bits.bits(..8)).unwrap() & 0xf7,
             0x01 => self.dispcnt.cached_bits.bits(8..).try_into().unwrap(),
             // GREENSWP (undocumented)
-            0x02 => self.greenswp.bits(..8).try_into().unwrap(),
-            0x03 => self.greenswp.bits(8..).try_into().unwrap(),
-            // DISPSTAT
+            0x02 => self.greenswp.bits(..1).try_into().unwrap(),
+            // DISPSTAT (the VBlank flag isn't set for the last scanline)
             0x04 => self.dispstat.lo_bits(
-                self.y >= VBLANK_DOT && self.y != 227,
-                self.x >= HBLANK_DOT.into(),
+                self.y >= VBLANK_DOT && self.y != VERT_DOTS - 1,
+                self.line_cycle() >= HBLANK_FLAG_CYCLE,
                 self.y,
             ),
             0x05 => self.dispstat.vcount_target,
@@ -343,10 +403,10 @@ impl Bus for Video {
             0x06 => self.y,
             // BG0CNT
             0x08 => self.bgcnt[0].cached_bits.bits(..8).try_into().unwrap(),
-            0x09 => self.bgcnt[0].cached_bits.bits(8..).try_into().unwrap(),
+            0x09 => u8::try_from(self.bgcnt[0].cached_bits.bits(8..)).unwrap() & 0xdf,
             // BG1CNT
             0x0a => self.bgcnt[1].cached_bits.bits(..8).try_into().unwrap(),
-            0x0b => self.bgcnt[1].cached_bits.bits(8..).try_into().unwrap(),
+            0x0b => u8::try_from(self.bgcnt[1].cached_bits.bits(8..)).unwrap() & 0xdf,
             // BG2CNT
             0x0c => self.bgcnt[2].cached_bits.bits(..8).try_into().unwrap(),
             0x0d => self.bgcnt[2].cached_bits.bits(8..).try_into().unwrap(),
@@ -354,18 +414,17 @@ impl Bus for Video {
             0x0e => self.bgcnt[3].cached_bits.bits(..8).try_into().unwrap(),
             0x0f => self.bgcnt[3].cached_bits.bits(8..).try_into().unwrap(),
             // WININ
-            0x48 => self.winin[0].cached_bits,
-            0x49 => self.winin[1].cached_bits,
+            0x48 => self.winin[0].cached_bits & 0x3f,
+            0x49 => self.winin[1].cached_bits & 0x3f,
             // WINOUT
-            0x4a => self.winout.cached_bits,
-            0x4b => self.winobj.cached_bits,
+            0x4a => self.winout.cached_bits & 0x3f,
+            0x4b => self.winobj.cached_bits & 0x3f,
             // BLDCNT
             0x50 => self.bldcnt.cached_bits.bits(..8).try_into().unwrap(),
-            0x51 => self.bldcnt.cached_bits.bits(8..).try_into().unwrap(),
+            0x51 => u8::try_from(self.bldcnt.cached_bits.bits(8..)).unwrap() & 0x3f,
             // BLDALPHA
-            0x52 => self.bldalpha.0 .0,
-            0x53 => self.bldalpha.1 .0,
-            0x57.. => panic!("IO register address OOB"),
+            0x52 => self.bldalpha.0 .0 & 0x1f,
+            0x53 => self.bldalpha.1 .0 & 0x1f,
             _ => 0,
         }
     }
@@ -396,16 +455,16 @@ impl Bus for Video {
             0x05 => self.dispstat.vcount_target = value,
             // BG0CNT
             0x08 => self.set_bgcnt_lo_bits(0, value),
-            0x09 => self.bgcnt[0].set_hi_bits(value),
+            0x09 => self.set_bgcnt_hi_bits(0, value),
             // BG1CNT
             0x0a => self.set_bgcnt_lo_bits(1, value),
-            0x0b => self.bgcnt[1].set_hi_bits(value),
+            0x0b => self.set_bgcnt_hi_bits(1, value),
             // BG2CNT
             0x0c => self.set_bgcnt_lo_bits(2, value),
-            0x0d => self.bgcnt[2].set_hi_bits(value),
+            0x0d => self.set_bgcnt_hi_bits(2, value),
             // BG3CNT
             0x0e => self.set_bgcnt_lo_bits(3, value),
-            0x0f => self.bgcnt[3].set_hi_bits(value),
+            0x0f => self.set_bgcnt_hi_bits(3, value),
             // BG0HOFS
             0x10 => self.bgofs[0].0.set_bits(..8, value.into()),
             0x11 => self.bgofs[0].0.set_bit(8, value.bit(0)),
@@ -491,7 +550,7 @@ impl Bus for Video {
             0x53 => self.bldalpha.1 .0 = value,
             // BLDY
             0x54 => self.bldy.0 = value,
-            0x57.. => panic!("IO register address OOB"),
+            // Read-only registers (e.g: VCOUNT) and unused addresses
             _ => {}
         }
     }
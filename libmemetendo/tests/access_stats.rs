@@ -0,0 +1,42 @@
+//! Tests for counting memory accesses via `debug::access_stats::AccessStats`.
+
+mod util;
+
+use libmemetendo::debug::access_stats::PageCounts;
+
+/// ```text
+///     b    .
+/// ```
+const PROGRAM: [u32; 1] = [0xeaff_fffe];
+
+#[test]
+fn bus_accesses_are_counted_once() {
+    let mut gba = util::test_gba(&PROGRAM);
+
+    gba.debug.access_stats.set_page_bits(2);
+    gba.debug.access_stats.set_enabled(true);
+    gba.write_word(0x0300_0000, 0xdead_beef);
+    gba.write_hword(0x0500_0000, 0x7fff);
+    assert_eq!(gba.read_word(0x0300_0000), 0xdead_beef);
+    gba.read_hword(0x0400_0004);
+    util::step_n(&mut gba, 10);
+    gba.debug.access_stats.set_enabled(false);
+    gba.read_word(0x0300_0000);
+
+    let pages: Vec<_> = gba
+        .debug
+        .access_stats
+        .pages()
+        .map(|(addr, &PageCounts { reads, writes })| (addr, reads, writes))
+        .collect();
+    assert_eq!(pages.len(), 6);
+    assert_eq!(pages[0], (0x0300_0000, 1, 1));
+    assert_eq!(pages[1], (0x0400_0004, 1, 0));
+    assert_eq!(pages[2], (0x0500_0000, 0, 1));
+
+    // Instruction fetches of the branch and the 2 words after it.
+    assert_eq!(pages[3].0, 0x0800_0000);
+    assert!(pages[3].1 >= 10);
+    assert_eq!(pages[4].0, 0x0800_0004);
+    assert_eq!(pages[5].0, 0x0800_0008);
+}
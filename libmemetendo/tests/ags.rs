@@ -0,0 +1,104 @@
+//! Test runner for the AGS aging cartridge
+//!
+//! The cartridge's ROM isn't freely distributable, so this is ignored by default and skipped if
+//! `tests/ags.gba` doesn't exist. Compares the result screen with `tests/ags_pass.png` (its result
+//! screen on hardware, where every test passes), reporting the lines of text that differ, which
+//! name the subsystems whose tests failed.
+
+mod runner;
+mod util;
+
+use std::{collections::HashSet, path::Path};
+
+use image::RgbImage;
+use libmemetendo::{keypad::Key, video::VBLANK_DOT};
+use runner::Runner;
+use util::{hash_image, read_cart_rom, read_image};
+
+const ROM_PATH: &str = "tests/ags.gba";
+const PASS_SCREEN_PATH: &str = "tests/ags_pass.png";
+
+/// Frames to wait for the cartridge's menu after booting.
+const STARTUP_FRAMES: u32 = 120;
+/// Selects the highlighted (first) entry of the menu, which runs every test.
+const INPUTS: [(u32, Key, bool); 2] = [
+    (STARTUP_FRAMES, Key::A, true),
+    (STARTUP_FRAMES + 5, Key::A, false),
+];
+/// Frames the screen must stay the same for to be considered the result screen.
+const STABLE_FRAMES: u32 = 120;
+/// Frames to wait for the result screen before giving up; five minutes.
+const MAX_FRAMES: u32 = 60 * 60 * 5;
+/// Height of a line of text on the result screen.
+const LINE_HEIGHT: u32 = 8;
+
+/// Runs the tests, returning the screen once it stops changing (or after `MAX_FRAMES`).
+fn run_to_result_screen(runner: &mut Runner) -> RgbImage {
+    runner.step_frames_with_input(STARTUP_FRAMES + 6, &INPUTS);
+
+    let (mut prev_hash, mut stable_frames) = (0, 0);
+    let mut seen_hashes = HashSet::new();
+    for _ in 0..MAX_FRAMES {
+        runner.step_frame();
+        let hash = hash_image(&runner.screen.image);
+        seen_hashes.insert(hash);
+        stable_frames = if hash == prev_hash {
+            stable_frames + 1
+        } else {
+            0
+        };
+        if stable_frames == STABLE_FRAMES {
+            break;
+        }
+        prev_hash = hash;
+    }
+
+    println!(
+        "result screen hash: {prev_hash:016x} ({} distinct frames seen)",
+        seen_hashes.len()
+    );
+    runner.screen.image.clone()
+}
+
+/// Returns the indices of the lines of text that differ between the screens.
+fn failed_lines(screen: &RgbImage, pass_screen: &RgbImage) -> Vec<u32> {
+    (0..u32::from(VBLANK_DOT) / LINE_HEIGHT)
+        .filter(|&line| {
+            (line * LINE_HEIGHT..(line + 1) * LINE_HEIGHT).any(|y| {
+                (0..screen.width()).any(|x| screen.get_pixel(x, y) != pass_screen.get_pixel(x, y))
+            })
+        })
+        .collect()
+}
+
+#[test]
+#[ignore = "slow, and needs the AGS aging cartridge ROM"]
+fn aging() {
+    if !Path::new(ROM_PATH).is_file() {
+        eprintln!("skipping: \"{ROM_PATH}\" not found");
+        return;
+    }
+
+    let mut runner = Runner::new(read_cart_rom(ROM_PATH));
+    let screen = run_to_result_screen(&mut runner);
+    let screen_path = Path::new(env!("CARGO_TARGET_TMPDIR")).join("ags_result.png");
+    screen
+        .save(&screen_path)
+        .expect("failed to save result screen");
+    println!("result screen saved to {}", screen_path.display());
+
+    if !Path::new(PASS_SCREEN_PATH).is_file() {
+        eprintln!("no \"{PASS_SCREEN_PATH}\" to compare the result screen with");
+        return;
+    }
+    let failed_lines = failed_lines(&screen, &read_image(PASS_SCREEN_PATH));
+    for &line in &failed_lines {
+        println!("line {line:2} (y = {}): FAIL", line * LINE_HEIGHT);
+    }
+    assert!(
+        failed_lines.is_empty(),
+        "{} lines of the result screen differ; see {}",
+        failed_lines.len(),
+        screen_path.display()
+    );
+}
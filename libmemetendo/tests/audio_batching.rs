@@ -0,0 +1,59 @@
+//! Tests for how audio samples are batched before they're pushed to the audio callback.
+
+mod util;
+
+use libmemetendo::{
+    audio::{self, SAMPLE_BATCH_LEN},
+    gba::Event,
+    util::video::NullCallback,
+};
+
+/// ```text
+///     b    .
+/// ```
+const PROGRAM: [u32; 1] = [0xeaff_fffe];
+
+#[derive(Default)]
+struct Batches {
+    lens: Vec<usize>,
+    single_samples: usize,
+}
+
+impl audio::Callback for Batches {
+    fn push_sample(&mut self, _sample: (i16, i16)) {
+        self.single_samples += 1;
+    }
+
+    fn push_samples(&mut self, samples: &[(i16, i16)]) {
+        self.lens.push(samples.len());
+    }
+}
+
+#[test]
+fn samples_are_batched_and_flushed_each_frame() {
+    let mut gba = util::test_gba(&PROGRAM);
+    gba.write_hword(0x0400_0084, 0x0080); // SOUNDCNT_X: enable sound
+
+    let mut batches = Batches::default();
+    assert!(gba.step_until(Event::VBlank, &mut NullCallback, &mut batches));
+    batches = Batches::default();
+    assert!(gba.step_until(Event::VBlank, &mut NullCallback, &mut batches));
+    assert_eq!(batches.single_samples, 0);
+    assert!(batches
+        .lens
+        .iter()
+        .all(|&len| len > 0 && len <= SAMPLE_BATCH_LEN));
+    // A frame lasts 280,896 cycles, and a sample is output every 8 cycles.
+    let frame_samples: usize = batches.lens.iter().sum();
+    assert!(
+        (35_111..=35_113).contains(&frame_samples),
+        "{frame_samples}"
+    );
+
+    // Samples output since the frame ended are still pending until they're flushed.
+    batches = Batches::default();
+    gba.audio.flush_samples(&mut batches);
+    assert_eq!(batches.lens.len(), 1);
+    gba.audio.flush_samples(&mut batches);
+    assert_eq!(batches.lens.len(), 1);
+}
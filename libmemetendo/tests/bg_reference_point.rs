@@ -0,0 +1,100 @@
+//! Tests that writes to the affine background reference points in the middle of a frame (e.g: for
+//! per-scanline "Mode 7" effects) take effect from the next scanline, replacing its increment.
+
+mod util;
+
+use libmemetendo::{
+    gba::{Event, Gba},
+    util::{audio::NullCallback, video::FrameBuffer},
+    video::{self, Dot, HBLANK_DOT, VBLANK_DOT},
+};
+
+/// ```text
+///     b    .
+/// ```
+const PROGRAM: [u32; 1] = [0xeaff_fffe];
+
+struct Screen(FrameBuffer);
+
+impl video::Callback for Screen {
+    fn put_dot(&mut self, x: u8, y: u8, dot: Dot) {
+        self.0.put_dot(x, y, dot);
+    }
+
+    fn end_frame(&mut self, _green_swap: bool) {}
+
+    fn is_frame_skipping(&self) -> bool {
+        false
+    }
+}
+
+impl Screen {
+    /// Row of the bitmap drawn at (`x`, `y`).
+    fn bitmap_row(&self, x: u32, y: u32) -> u32 {
+        let i = 3 * usize::try_from(y * u32::from(HBLANK_DOT) + x).unwrap();
+        let [r, _, b] = self.0 .0[i..i + 3] else {
+            unreachable!();
+        };
+        u32::from(r / 8) | (u32::from(b / 8) << 5)
+    }
+}
+
+/// Creates a system displaying a mode 3 bitmap whose colours encode the row they're in.
+fn new_gba() -> Gba {
+    let mut gba = util::test_gba(&PROGRAM);
+
+    gba.write_hword(0x0400_0000, 0x0403); // DISPCNT: mode 3, BG2 displayed
+    gba.write_hword(0x0400_0020, 0x100); // BG2PA: identity
+    gba.write_hword(0x0400_0026, 0x100); // BG2PD: identity
+    for y in 0..u32::from(VBLANK_DOT) {
+        for x in 0..u32::from(HBLANK_DOT) {
+            let color = u16::try_from((y % 32) | ((y / 32) << 10)).unwrap();
+            gba.write_hword(0x0600_0000 + 2 * (y * u32::from(HBLANK_DOT) + x), color);
+        }
+    }
+
+    gba
+}
+
+#[test]
+fn reference_point_write_takes_effect_from_next_scanline() {
+    let mut gba = new_gba();
+    let mut screen = Screen(FrameBuffer::new(0));
+
+    // Move BG2Y back to the top of the bitmap in the middle of scanline 80.
+    assert!(gba.step_until(Event::VBlank, &mut screen, &mut NullCallback));
+    assert!(gba.step_until(Event::VCount(80), &mut screen, &mut NullCallback));
+    while gba.video.position().0 < 120 {
+        gba.step(&mut screen, &mut NullCallback);
+    }
+    gba.write_word(0x0400_002c, 0); // BG2Y
+    assert!(gba.step_until(Event::VBlank, &mut screen, &mut NullCallback));
+
+    assert_eq!(screen.bitmap_row(0, 79), 79);
+    assert_eq!(screen.bitmap_row(119, 80), 80);
+    assert_eq!(screen.bitmap_row(239, 80), 80);
+    assert_eq!(screen.bitmap_row(0, 81), 0);
+    assert_eq!(screen.bitmap_row(0, 82), 1);
+    assert_eq!(screen.bitmap_row(239, 159), 78);
+}
+
+#[test]
+fn reference_points_reload_at_vblank() {
+    let mut gba = new_gba();
+    let mut screen = Screen(FrameBuffer::new(0));
+
+    // A write in the middle of the frame is reloaded at the start of the next, rather than the
+    // frame continuing from where the previous one left off.
+    assert!(gba.step_until(Event::VBlank, &mut screen, &mut NullCallback));
+    assert!(gba.step_until(Event::VCount(40), &mut screen, &mut NullCallback));
+    while gba.video.position().0 < 120 {
+        gba.step(&mut screen, &mut NullCallback);
+    }
+    gba.write_word(0x0400_002c, 100 << 8); // BG2Y
+    assert!(gba.step_until(Event::VBlank, &mut screen, &mut NullCallback));
+    assert_eq!(screen.bitmap_row(0, 41), 100);
+
+    assert!(gba.step_until(Event::VBlank, &mut screen, &mut NullCallback));
+    assert_eq!(screen.bitmap_row(0, 0), 100);
+    assert_eq!(screen.bitmap_row(0, 59), 159);
+}
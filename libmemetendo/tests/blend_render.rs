@@ -0,0 +1,167 @@
+//! Pixel-exact tests for how color special effects (blending) interact with semi-transparent
+//! sprites (objects) and windows.
+
+mod util;
+
+use libmemetendo::{
+    gba::Gba,
+    video::{self, Dot, HBLANK_DOT, VBLANK_DOT},
+};
+
+/// ```text
+///     b    .
+/// ```
+const PROGRAM: [u32; 1] = [0xeaff_fffe];
+
+const RED: (u8, u8, u8) = (31, 0, 0);
+const BLUE: (u8, u8, u8) = (0, 0, 31);
+/// Red blended half-and-half with blue by BLDALPHA.
+const RED_BLUE: (u8, u8, u8) = (15, 0, 15);
+/// Red brightened halfway towards white by BLDY.
+const BRIGHT_RED: (u8, u8, u8) = (31, 15, 15);
+/// Blue brightened halfway towards white by BLDY.
+const BRIGHT_BLUE: (u8, u8, u8) = (15, 15, 31);
+
+/// Records the color of every dot of the last frame.
+struct Colors {
+    dots: Vec<(u8, u8, u8)>,
+    frames: u32,
+}
+
+impl video::Callback for Colors {
+    fn put_dot(&mut self, x: u8, y: u8, dot: Dot) {
+        let i = usize::from(y) * usize::from(HBLANK_DOT) + usize::from(x);
+        self.dots[i] = (dot.red(), dot.green(), dot.blue());
+    }
+
+    fn end_frame(&mut self, _green_swap: bool) {
+        self.frames += 1;
+    }
+
+    fn is_frame_skipping(&self) -> bool {
+        false
+    }
+}
+
+/// Creates a system displaying only a red 8x8 sprite at (16, 16) over a blue backdrop, with
+/// BLDALPHA blending both colors equally, and BLDY brightening (or darkening) colors by half.
+fn new_gba(obj_attr0: u16) -> Gba {
+    let mut gba = util::test_gba(&PROGRAM);
+
+    gba.write_hword(0x0400_0000, 0x1040); // DISPCNT: mode 0, 1D obj mapping, display obj
+    gba.write_hword(0x0400_0052, 0x0808); // BLDALPHA: EVA = EVB = 8/16
+    gba.write_hword(0x0400_0054, 8); // BLDY: EY = 8/16
+    gba.write_hword(0x0500_0000, 0x7c00); // Backdrop: blue
+    gba.write_hword(0x0500_0202, 0x001f); // Obj palette 0, color 1: red
+    for addr in (0x0601_0000..0x0601_0020).step_by(4) {
+        gba.write_word(addr, 0x1111_1111);
+    }
+    for i in 1..128 {
+        gba.write_hword(0x0700_0000 + 8 * i, 0x0200); // Hidden
+    }
+    for (i, attr) in (0..).zip([obj_attr0 | 16, 16, 0]) {
+        gba.write_hword(0x0700_0000 + 2 * i, attr);
+    }
+
+    gba
+}
+
+/// Renders a frame with the given BLDCNT, returning the colors of a sprite dot and a backdrop dot.
+fn render(gba: &mut Gba, bldcnt: u16) -> ((u8, u8, u8), (u8, u8, u8)) {
+    let dots = render_frame(gba, bldcnt);
+    (dot_at(&dots, 20, 20), dot_at(&dots, 0, 0))
+}
+
+fn render_frame(gba: &mut Gba, bldcnt: u16) -> Vec<(u8, u8, u8)> {
+    gba.write_hword(0x0400_0050, bldcnt);
+    let mut video_cb = Colors {
+        dots: vec![(0, 0, 0); usize::from(HBLANK_DOT) * usize::from(VBLANK_DOT)],
+        frames: 0,
+    };
+    while video_cb.frames < 2 {
+        gba.step(&mut video_cb, &mut libmemetendo::util::audio::NullCallback);
+    }
+
+    video_cb.dots
+}
+
+fn dot_at(dots: &[(u8, u8, u8)], x: usize, y: usize) -> (u8, u8, u8) {
+    dots[y * usize::from(HBLANK_DOT) + x]
+}
+
+// BLDCNT bits.
+const OBJ_1ST: u16 = 1 << 4;
+const BD_1ST: u16 = 1 << 5;
+const ALPHA: u16 = 1 << 6;
+const BRIGHTEN: u16 = 2 << 6;
+const DIM: u16 = 3 << 6;
+const BD_2ND: u16 = 1 << 13;
+
+#[test]
+fn normal_obj_effects() {
+    let mut gba = new_gba(0);
+    assert_eq!(render(&mut gba, ALPHA | BD_2ND), (RED, BLUE));
+    assert_eq!(render(&mut gba, ALPHA | OBJ_1ST | BD_2ND), (RED_BLUE, BLUE));
+    assert_eq!(render(&mut gba, ALPHA | OBJ_1ST), (RED, BLUE));
+    assert_eq!(render(&mut gba, BRIGHTEN | BD_2ND), (RED, BLUE));
+    assert_eq!(
+        render(&mut gba, BRIGHTEN | OBJ_1ST | BD_1ST),
+        (BRIGHT_RED, BRIGHT_BLUE)
+    );
+    assert_eq!(render(&mut gba, DIM | OBJ_1ST | BD_2ND), ((15, 0, 0), BLUE));
+}
+
+#[test]
+fn semi_transparent_obj_forces_alpha_blending() {
+    let mut gba = new_gba(0x0400);
+    // Semi-transparent sprites are alpha blended with 2nd targets below them, even if they aren't
+    // 1st targets, and whatever the blend mode.
+    for mode in [0, ALPHA, BRIGHTEN, DIM] {
+        assert_eq!(render(&mut gba, mode | BD_2ND), (RED_BLUE, BLUE));
+        assert_eq!(render(&mut gba, mode | OBJ_1ST | BD_2ND), (RED_BLUE, BLUE));
+    }
+    // The brightness effects still apply to the backdrop as a 1st target.
+    assert_eq!(
+        render(&mut gba, BRIGHTEN | BD_1ST | BD_2ND),
+        (RED_BLUE, BRIGHT_BLUE)
+    );
+}
+
+#[test]
+fn semi_transparent_obj_without_2nd_target() {
+    let mut gba = new_gba(0x0400);
+    // Without a 2nd target below, semi-transparent sprites are only affected by the blend mode if
+    // they're 1st targets, like other sprites.
+    for mode in [0, ALPHA, BRIGHTEN, DIM] {
+        assert_eq!(render(&mut gba, mode), (RED, BLUE));
+    }
+    assert_eq!(render(&mut gba, ALPHA | OBJ_1ST), (RED, BLUE));
+    assert_eq!(render(&mut gba, BRIGHTEN | OBJ_1ST), (BRIGHT_RED, BLUE));
+    assert_eq!(render(&mut gba, DIM | OBJ_1ST), ((15, 0, 0), BLUE));
+}
+
+#[test]
+fn window_disables_effects_for_semi_transparent_obj() {
+    let mut gba = new_gba(0x0400);
+    // Window 0 covers the left half of the sprite and the backdrop at x 0 to 19, displaying obj,
+    // with color special effects disabled; outside of it, they're enabled.
+    gba.write_hword(0x0400_0000, 0x3040); // DISPCNT: also enable window 0
+    gba.write_hword(0x0400_0040, 20); // WIN0H: x 0 to 19
+    gba.write_hword(0x0400_0044, 160); // WIN0V: y 0 to 159
+    gba.write_hword(0x0400_0048, 0x0010); // WININ: obj, no effects
+    gba.write_hword(0x0400_004a, 0x0030); // WINOUT: obj, effects
+
+    for bldcnt in [
+        BRIGHTEN | BD_1ST | BD_2ND,
+        ALPHA | OBJ_1ST | BD_1ST | BD_2ND,
+    ] {
+        let dots = render_frame(&mut gba, bldcnt);
+        assert_eq!(dot_at(&dots, 16, 16), RED);
+        assert_eq!(dot_at(&dots, 19, 23), RED);
+        assert_eq!(dot_at(&dots, 20, 16), RED_BLUE);
+        assert_eq!(dot_at(&dots, 23, 23), RED_BLUE);
+        assert_eq!(dot_at(&dots, 0, 0), BLUE);
+    }
+    let dots = render_frame(&mut gba, BRIGHTEN | BD_1ST | BD_2ND);
+    assert_eq!(dot_at(&dots, 30, 0), BRIGHT_BLUE);
+}
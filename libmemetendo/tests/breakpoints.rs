@@ -0,0 +1,65 @@
+//! Tests for stopping the CPU at breakpoints via `debug::breakpoints::Breakpoints`.
+
+mod util;
+
+use libmemetendo::{
+    debug::{breakpoints::Breakpoint, expr::Expr, symbols::Symbols},
+    gba::Gba,
+};
+
+/// ```text
+///     mov  r4, #0x03000000
+/// loop:
+///     add  r2, r2, #1
+///     str  r2, [r4]
+///     b    loop
+/// ```
+const PROGRAM: [u32; 4] = [0xe3a0_4403, 0xe282_2001, 0xe584_2000, 0xeaff_fffc];
+const STR_ADDR: u32 = 0x0800_0008;
+
+/// Steps until a breakpoint is hit, returning its address, or `None` if none were hit within
+/// `max_steps` steps.
+fn step_until_hit(gba: &mut Gba, max_steps: u32) -> Option<u32> {
+    for _ in 0..max_steps {
+        util::step_n(gba, 1);
+        if let Some(addr) = gba.debug.breakpoints.take_hit() {
+            return Some(addr);
+        }
+    }
+
+    None
+}
+
+#[test]
+fn stops_before_instruction() {
+    let mut gba = util::test_gba(&PROGRAM);
+    gba.debug.breakpoints.add(Breakpoint {
+        addr: STR_ADDR,
+        condition: None,
+    });
+
+    for i in 1..=3 {
+        assert_eq!(step_until_hit(&mut gba, 100), Some(STR_ADDR));
+        assert_eq!(gba.cpu.next_instr_addr(), STR_ADDR);
+        assert_eq!(gba.cpu.reg.r[2], i);
+        // The store hasn't executed yet.
+        assert_eq!(gba.read_word(0x0300_0000), i - 1);
+    }
+
+    assert!(gba.debug.breakpoints.remove(0).is_some());
+    assert_eq!(step_until_hit(&mut gba, 100), None);
+}
+
+#[test]
+fn conditional_breakpoint() {
+    let mut gba = util::test_gba(&PROGRAM);
+    let condition = Expr::parse("r2 == 5 && [0x03000000]:u16 == 4", &Symbols::new()).unwrap();
+    gba.debug.breakpoints.add(Breakpoint {
+        addr: STR_ADDR,
+        condition: Some(condition),
+    });
+
+    assert_eq!(step_until_hit(&mut gba, 1000), Some(STR_ADDR));
+    assert_eq!(gba.cpu.reg.r[2], 5);
+    assert_eq!(step_until_hit(&mut gba, 1000), None);
+}
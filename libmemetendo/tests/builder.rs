@@ -0,0 +1,69 @@
+//! Tests for configuring the system via `gba::Builder`.
+
+mod util;
+
+use libmemetendo::{
+    cart::Cartridge,
+    gba::{self, BootState, DeterminismConfig, MemoryFill, Peripherals},
+    keypad::PollRate,
+};
+
+fn builder() -> gba::Builder {
+    let cart = Cartridge::from(util::program_rom(&[0; 0x40]));
+    gba::Builder::new(util::zero_bios_rom(), cart)
+}
+
+#[test]
+fn defaults_match_new() {
+    let gba = builder().build().unwrap();
+    assert!((gba.cpu_multiplier() - 1.0).abs() < f32::EPSILON);
+    assert!(!gba.cpu.idle_loop.enabled);
+    assert!(!gba.audio.output_filter.enabled);
+    assert_eq!(gba.keypad.poll_rate, PollRate::Frame);
+    assert_eq!(gba.peripherals, Peripherals::new());
+    assert_eq!(gba.boot_state, BootState::new());
+    // Starts at the BIOS's reset vector.
+    assert_eq!(gba.cpu.next_instr_addr(), 0);
+}
+
+#[test]
+fn applies_options() {
+    let peripherals = Peripherals {
+        rtc: true,
+        rumble: false,
+        solar_sensor: true,
+        sram_clock: Some(0x7ff0),
+    };
+    let boot_state = BootState {
+        ram_fill: MemoryFill::Ones,
+    };
+    let mut gba = builder()
+        .peripherals(peripherals)
+        .boot_state(boot_state)
+        .determinism(DeterminismConfig { rtc_epoch: 1234 })
+        .skip_bios(true)
+        .cpu_multiplier(2.0)
+        .skip_idle_loops(true)
+        .audio_filter(true)
+        .keypad_poll_rate(PollRate::Scanline)
+        .build()
+        .unwrap();
+
+    assert!((gba.cpu_multiplier() - 2.0).abs() < f32::EPSILON);
+    assert!(gba.cpu.idle_loop.enabled);
+    assert!(gba.audio.output_filter.enabled);
+    assert_eq!(gba.keypad.poll_rate, PollRate::Scanline);
+    assert_eq!(gba.peripherals, peripherals);
+    assert_eq!(gba.boot_state, boot_state);
+    let clock = gba.cart.sram_clock.unwrap();
+    assert_eq!((clock.offset(), clock.seconds()), (0x7ff0, 1234));
+    // Reset with the boot state and skipped the BIOS.
+    assert_eq!(gba.read_byte(0x0200_0000), 0xff);
+    assert_eq!(gba.cpu.next_instr_addr(), 0x0800_0000);
+}
+
+#[test]
+fn rejects_invalid_options() {
+    assert!(builder().cpu_multiplier(0.0).build().is_err());
+    assert!(builder().cpu_multiplier(f32::NAN).build().is_err());
+}
@@ -0,0 +1,63 @@
+//! Tests for logging how the cartridge ROM is accessed via `debug::cdl::CodeDataLog`.
+
+mod util;
+
+use libmemetendo::debug::cdl::{CodeDataLog, ARM, DATA, DMA, THUMB};
+
+/// ```text
+///     ldr  r1, =0xdeadbeef
+///     add  r0, pc, #1
+///     bx   r0
+/// .thumb
+///     b    .
+/// ```
+///
+/// Followed by the literal pool at 0x18, and 16 bytes at 0x1c for DMA to copy.
+const PROGRAM: [u32; 12] = [
+    0xe59f_1010,
+    0xe28f_0001,
+    0xe12f_ff10,
+    0x0000_e7fe,
+    0,
+    0,
+    0xdead_beef,
+    1,
+    2,
+    3,
+    4,
+    0,
+];
+
+#[test]
+fn logs_rom_accesses() {
+    let mut gba = util::test_gba(&PROGRAM);
+    gba.write_word(0x0400_00d4, 0x0800_001c); // DMA3SAD
+    gba.write_word(0x0400_00d8, 0x0300_0000); // DMA3DAD
+    gba.write_hword(0x0400_00dc, 4); // DMA3CNT_L
+    gba.write_hword(0x0400_00de, 0x8400); // DMA3CNT_H: enabled, 32-bit, immediate
+    gba.debug.cdl.set_enabled(true);
+
+    util::step_n(&mut gba, 100);
+    assert_eq!(gba.read_word(0x0300_0004), 2, "DMA should have copied");
+
+    let mut log = Vec::new();
+    gba.debug.cdl.write(&mut log, 4 * PROGRAM.len()).unwrap();
+    let mut expected = vec![0; 4 * PROGRAM.len()];
+    expected[..0xc].fill(ARM);
+    expected[0xc..0xe].fill(THUMB);
+    expected[0x18..0x1c].fill(DATA);
+    expected[0x1c..0x2c].fill(DMA);
+    assert_eq!(log, expected);
+}
+
+#[test]
+fn merges_logs() {
+    let mut cdl = CodeDataLog::new();
+    cdl.merge(&[ARM, 0, DATA]);
+    cdl.merge(&[DATA, THUMB]);
+    assert_eq!(cdl.flags(), [ARM | DATA, THUMB, DATA]);
+
+    let mut log = Vec::new();
+    cdl.write(&mut log, 4).unwrap();
+    assert_eq!(log, [ARM | DATA, THUMB, DATA, 0]);
+}
@@ -0,0 +1,42 @@
+//! Tests for overclocking and underclocking the CPU via `Gba::set_cpu_multiplier`.
+
+mod util;
+
+/// ```text
+///     mov  r4, #0x03000000
+/// loop:
+///     add  r2, r2, #1
+///     str  r2, [r4]
+///     b    loop
+/// ```
+const PROGRAM: [u32; 4] = [0xe3a0_4403, 0xe282_2001, 0xe584_2000, 0xeaff_fffc];
+
+/// Runs the program for `steps` steps with the given CPU multiplier, returning the number of loop
+/// iterations completed and the final video position.
+fn run(multiplier: f32, steps: u32) -> (u32, (u16, u8)) {
+    let mut gba = util::test_gba(&PROGRAM);
+    gba.set_cpu_multiplier(multiplier).unwrap();
+    util::step_n(&mut gba, steps);
+
+    (gba.read_word(0x0300_0000), gba.video.position())
+}
+
+#[test]
+fn multiplier_scales_cpu_speed_only() {
+    let (iters, pos) = run(1.0, 3000);
+    assert!(iters > 900);
+
+    let (overclocked_iters, overclocked_pos) = run(2.0, 3000);
+    assert!(overclocked_iters.abs_diff(2 * iters) <= 1);
+    assert_eq!(overclocked_pos, pos);
+
+    let (underclocked_iters, underclocked_pos) = run(0.5, 3000);
+    assert!(underclocked_iters.abs_diff(iters / 2) <= 1);
+    assert_eq!(underclocked_pos, pos);
+}
+
+#[test]
+#[should_panic(expected = "positive")]
+fn multiplier_must_be_positive() {
+    run(0.0, 0);
+}
@@ -0,0 +1,139 @@
+//! Tests that emulation is deterministic.
+
+mod util;
+
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+};
+
+use libmemetendo::{
+    cart::{BackupType, Cartridge},
+    gba::{BootState, Builder, DeterminismConfig, Gba, MemoryFill, Peripherals},
+    util::{audio, video::FrameBuffer},
+    video::{self, Dot},
+};
+
+/// ```text
+///     mov  r3, #0x03000000
+///     mov  r2, #0x05000000
+/// loop:
+///     add  r0, r0, #1
+///     and  r1, r0, #0xff
+///     str  r0, [r3, r1, lsl #2]
+///     strh r0, [r2]
+///     b    loop
+/// ```
+const PROGRAM: [u32; 7] = [
+    0xe3a0_3403,
+    0xe3a0_2405,
+    0xe280_0001,
+    0xe200_10ff,
+    0xe783_0101,
+    0xe1c2_00b0,
+    0xeaff_fffa,
+];
+
+/// Save memory offset of the SRAM clock.
+const SRAM_CLOCK_OFFSET: u16 = 0x7ff0;
+
+/// Records the last frame drawn.
+#[derive(Default)]
+struct LastFrame {
+    buf: FrameBuffer,
+    frames: u32,
+}
+
+impl video::Callback for LastFrame {
+    fn put_dot(&mut self, x: u8, y: u8, dot: Dot) {
+        self.buf.put_dot(x, y, dot);
+    }
+
+    fn end_frame(&mut self, _green_swap: bool) {
+        self.frames += 1;
+    }
+
+    fn is_frame_skipping(&self) -> bool {
+        false
+    }
+}
+
+/// Creates a system whose state depends on everything `DeterminismConfig` and `BootState` control:
+/// it keeps a clock in SRAM starting at `rtc_epoch`, and its RAM is filled with pseudo-random bytes.
+fn build_gba(rtc_epoch: u64) -> Gba {
+    let cart = Cartridge::new(util::program_rom(&PROGRAM), BackupType::Sram32KiB);
+    Builder::new(util::zero_bios_rom(), cart)
+        .skip_bios(true)
+        .peripherals(Peripherals {
+            sram_clock: Some(SRAM_CLOCK_OFFSET),
+            ..Peripherals::new()
+        })
+        .boot_state(BootState {
+            ram_fill: MemoryFill::Random { seed: 0xcafe },
+        })
+        .determinism(DeterminismConfig { rtc_epoch })
+        .build()
+        .unwrap()
+}
+
+/// Reads the date-time of the SRAM clock; see `cart::sram_clock`.
+fn sram_clock_date_time(gba: &mut Gba) -> Vec<u8> {
+    let addr = 0x0e00_0000 + u32::from(SRAM_CLOCK_OFFSET);
+    (addr..addr + 7).map(|addr| gba.read_byte(addr)).collect()
+}
+
+/// Runs `gba` for 2 frames, returning a hash of the last frame and the contents of work RAM.
+fn run(gba: &mut Gba) -> u64 {
+    let mut frame = LastFrame::default();
+    while frame.frames < 2 {
+        gba.step(&mut frame, &mut audio::NullCallback);
+    }
+
+    let mut hasher = DefaultHasher::new();
+    frame.buf.0.hash(&mut hasher);
+    gba.iwram.hash(&mut hasher);
+    gba.ewram.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[test]
+fn runs_are_identical() {
+    assert_eq!(
+        run(&mut util::test_gba(&PROGRAM)),
+        run(&mut util::test_gba(&PROGRAM))
+    );
+}
+
+#[test]
+fn configured_runs_are_identical() {
+    // 2001-09-09 01:46:40 UTC, a Sunday.
+    let (mut gba, mut other_gba) = (build_gba(1_000_000_000), build_gba(1_000_000_000));
+    assert_eq!(run(&mut gba), run(&mut other_gba));
+    assert_eq!(
+        sram_clock_date_time(&mut gba),
+        [0x01, 0x09, 0x09, 0x00, 0x01, 0x46, 0x40]
+    );
+    assert_eq!(
+        sram_clock_date_time(&mut gba),
+        sram_clock_date_time(&mut other_gba)
+    );
+
+    // Only the clock should differ when starting it a day later.
+    let mut later_gba = build_gba(1_000_086_400);
+    assert_eq!(run(&mut later_gba), run(&mut build_gba(1_000_000_000)));
+    assert_eq!(
+        sram_clock_date_time(&mut later_gba),
+        [0x01, 0x09, 0x10, 0x01, 0x01, 0x46, 0x40]
+    );
+}
+
+#[test]
+fn reset_clears_work_ram() {
+    let mut gba = util::test_gba(&PROGRAM);
+    util::step_n(&mut gba, 1000);
+    gba.write_word(0x0200_0000, 0xdead_beef);
+    assert!(gba.iwram.iter().any(|&b| b != 0));
+
+    gba.reset(true);
+    assert!(gba.iwram.iter().chain(gba.ewram.iter()).all(|&b| b == 0));
+}
@@ -0,0 +1,89 @@
+//! Tests for how long DMA transfers take, and how they share the bus with the CPU and each other.
+
+mod util;
+
+use libmemetendo::gba::Gba;
+
+/// ```text
+/// loop:
+///     add  r0, r0, #1
+///     b    loop
+/// ```
+const PROGRAM: [u32; 2] = [0xe280_0001, 0xeaff_fffd];
+
+/// Starts an immediate transfer of `count` units on the DMA channel `chan_idx`.
+fn start_dma(gba: &mut Gba, chan_idx: u32, src_addr: u32, dst_addr: u32, count: u16, word: bool) {
+    let base = 0x0400_00b0 + 12 * chan_idx;
+    gba.write_word(base, src_addr); // DMAxSAD
+    gba.write_word(base + 4, dst_addr); // DMAxDAD
+    gba.write_hword(base + 8, count); // DMAxCNT_L
+    gba.write_hword(base + 10, if word { 0x8400 } else { 0x8000 }); // DMAxCNT_H: enable
+}
+
+/// Steps until no DMA transfer is in progress, returning the number of steps taken.
+fn steps_until_done(gba: &mut Gba) -> u32 {
+    let mut steps = 0;
+    while (0..4).any(|i| gba.dma.channel_state(i).transferring) {
+        util::step_n(gba, 1);
+        steps += 1;
+        assert!(steps < 10_000, "transfer never finished");
+    }
+
+    steps
+}
+
+#[test]
+fn stalls_cpu_until_done() {
+    let mut gba = util::test_gba(&PROGRAM);
+    util::step_n(&mut gba, 1);
+    let count = gba.cpu.reg.r[0];
+
+    // 2 cycles to start, then 2 cycles (1 for the read and 1 for the write) for each unit.
+    start_dma(&mut gba, 3, 0x0300_0000, 0x0300_1000, 30, false);
+    assert_eq!(steps_until_done(&mut gba), (2 + 2 * 30_u32).div_ceil(3));
+    assert_eq!(gba.cpu.reg.r[0], count, "CPU should have been stalled");
+
+    util::step_n(&mut gba, 10);
+    assert!(gba.cpu.reg.r[0] > count, "CPU should have resumed");
+}
+
+#[test]
+fn cart_wait_states_slow_transfers() {
+    // With the default wait states of WAITCNT, the first word read from the cart takes 8 cycles,
+    // then 6 cycles for the next ones. Writing to internal WRAM takes 1 cycle.
+    let mut gba = util::test_gba(&PROGRAM);
+    start_dma(&mut gba, 3, 0x0800_0000, 0x0300_0000, 4, true);
+    assert_eq!(steps_until_done(&mut gba), (2 + 9 + 3 * 7_u32).div_ceil(3));
+    assert_eq!(gba.read_word(0x0300_0004), PROGRAM[1]);
+
+    // With the fastest wait states (3 for the first access, 1 for the next ones), the first word
+    // takes 4 + 2 = 6 cycles, then 4 cycles for the next ones.
+    let mut gba = util::test_gba(&PROGRAM);
+    gba.write_hword(0x0400_0204, 0x0014); // WAITCNT
+    start_dma(&mut gba, 3, 0x0800_0000, 0x0300_0000, 4, true);
+    assert_eq!(steps_until_done(&mut gba), (2 + 7 + 3 * 5_u32).div_ceil(3));
+}
+
+#[test]
+fn higher_priority_channel_pauses_lower_one() {
+    let mut gba = util::test_gba(&PROGRAM);
+    start_dma(&mut gba, 3, 0x0300_0000, 0x0300_1000, 100, false);
+    util::step_n(&mut gba, 5);
+    let remaining = gba.dma.channel_state(3).remaining;
+    assert!(remaining < 100);
+
+    start_dma(&mut gba, 0, 0x0300_0000, 0x0300_2000, 10, false);
+    let mut steps = 0;
+    while gba.dma.channel_state(0).transferring {
+        assert_eq!(gba.dma.channel_state(3).remaining, remaining);
+        util::step_n(&mut gba, 1);
+        steps += 1;
+    }
+    // DMA3 used 14 of the 15 cycles it was given, so DMA0 has the remaining cycle to use.
+    assert_eq!(steps, (2 + 2 * 10 - 1_u32).div_ceil(3));
+    assert_eq!(gba.dma.channel_state(3).remaining, remaining);
+    assert!(gba.dma.channel_state(3).transferring);
+
+    steps_until_done(&mut gba);
+    assert_eq!(gba.dma.channel_state(3).remaining, 0);
+}
@@ -0,0 +1,124 @@
+//! Tests for the notices reported to a `gba::EventSink`, and the calls to `gba::VideoHooks`.
+
+mod util;
+
+use std::{cell::RefCell, rc::Rc};
+
+use libmemetendo::{
+    cart::{self, BackupType, Cartridge},
+    gba::{Event, EventSink, Gba, Notice, VideoHooks},
+    video::{Video, VERT_DOTS},
+};
+
+/// ```text
+///     mov  r0, #0x04000000
+///     add  r0, r0, #0x400
+///     strb r0, [r0]
+///     b    .
+/// ```
+const PROGRAM: [u32; 4] = [0xe3a0_0301, 0xe280_0b01, 0xe5c0_0000, 0xeaff_fffe];
+
+#[derive(Clone, Default)]
+struct Recorder(Rc<RefCell<Vec<Notice>>>);
+
+impl EventSink for Recorder {
+    fn report(&mut self, notice: Notice) {
+        self.0.borrow_mut().push(notice);
+    }
+}
+
+/// Records the scanlines started, and the number of V-Blanks.
+#[derive(Clone, Default)]
+struct ScanlineRecorder(Rc<RefCell<(Vec<u8>, u32)>>);
+
+impl VideoHooks for ScanlineRecorder {
+    fn on_scanline(&mut self, video: &Video, y: u8) {
+        assert_eq!(video.position(), (0, y));
+        self.0.borrow_mut().0.push(y);
+    }
+
+    fn on_vblank(&mut self, _video: &Video) {
+        self.0.borrow_mut().1 += 1;
+    }
+}
+
+/// Creates a system whose cartridge ROM has `backup_id` after the program, recording its notices.
+fn new_gba(backup_id: &[u8]) -> (Gba, Recorder) {
+    let mut rom: Vec<_> = PROGRAM.iter().flat_map(|i| i.to_le_bytes()).collect();
+    rom.extend_from_slice(backup_id);
+    rom.resize(rom.len().next_multiple_of(4), 0);
+    let cart_rom = cart::Rom::new(Rc::from(rom)).unwrap();
+
+    let mut gba = util::test_gba_with_cart(Cartridge::from(cart_rom));
+    let recorder = Recorder::default();
+    gba.events.sink = Some(Box::new(recorder.clone()));
+
+    (gba, recorder)
+}
+
+#[test]
+fn reports_guessed_backup_type_and_invalid_io_access() {
+    let (mut gba, recorder) = new_gba(b"SRAM_V113");
+    util::step_n(&mut gba, 10);
+    assert_eq!(
+        *recorder.0.borrow(),
+        [
+            Notice::BackupTypeGuessed(BackupType::Sram32KiB),
+            Notice::InvalidIoAccess {
+                addr: 0x0400_0400,
+                write: true,
+            },
+        ]
+    );
+}
+
+#[test]
+fn reports_invalid_io_access_once_per_access() {
+    let (mut gba, recorder) = new_gba(&[]);
+    gba.write_word(0x0400_0400, 0);
+    gba.read_word(0x0400_0800);
+    gba.write_hword(0x0400_1000, 0);
+    gba.read_hword(0x0400_2000);
+    assert_eq!(
+        *recorder.0.borrow(),
+        [
+            Notice::InvalidIoAccess {
+                addr: 0x0400_0400,
+                write: true,
+            },
+            Notice::InvalidIoAccess {
+                addr: 0x0400_0800,
+                write: false,
+            },
+            Notice::InvalidIoAccess {
+                addr: 0x0400_1000,
+                write: true,
+            },
+            Notice::InvalidIoAccess {
+                addr: 0x0400_2000,
+                write: false,
+            },
+        ]
+    );
+}
+
+#[test]
+fn reports_unsupported_backup_hardware() {
+    let (mut gba, recorder) = new_gba(b"DACS_V100");
+    util::step_n(&mut gba, 1);
+    assert_eq!(recorder.0.borrow()[0], Notice::Unimplemented("DACS"));
+}
+
+#[test]
+fn calls_video_hooks_at_each_scanline() {
+    let (mut gba, _) = new_gba(&[]);
+    let recorder = ScanlineRecorder::default();
+    gba.events.video_hooks = Some(Box::new(recorder.clone()));
+
+    util::step_until(&mut gba, Event::VCount(0));
+    *recorder.0.borrow_mut() = (Vec::new(), 0);
+    util::step_until(&mut gba, Event::VCount(0));
+    let (lines, vblanks) = recorder.0.take();
+    assert!(lines.into_iter().eq((1..VERT_DOTS).chain([0])));
+    assert_eq!(vblanks, 1);
+}
@@ -0,0 +1,65 @@
+//! Tests for driving the CPU one instruction at a time with `Cpu::execute_one`, as external tools
+//! do, with a plain buffer of memory as the bus rather than a whole `Gba`.
+
+use libmemetendo::arm7tdmi::{
+    reg::{OperationMode, OperationState, PC_INDEX},
+    Cpu, Instr,
+};
+
+fn new_cpu(mem: &mut [u8]) -> Cpu {
+    let mut cpu = Cpu::new();
+    cpu.reset(&mut &mut *mem, false);
+    assert_eq!(cpu.next_instr_addr(), 0);
+
+    cpu
+}
+
+#[test]
+fn executes_arm_instrs() {
+    let mut mem = vec![0; 0x100];
+    let mut cpu = new_cpu(&mut mem);
+    let mut bus = &mut mem[..];
+
+    cpu.execute_one(&mut bus, Instr::Arm(0xe3a0_0005)); // mov r0, #5
+    cpu.execute_one(&mut bus, Instr::Arm(0xe080_1080)); // add r1, r0, r0, lsl #1
+    assert_eq!(cpu.reg.r[..2], [5, 15]);
+    assert_eq!(cpu.next_instr_addr(), 8);
+
+    cpu.reg.r[2] = 0x40;
+    cpu.execute_one(&mut bus, Instr::Arm(0xe582_1004)); // str r1, [r2, #4]
+    assert_eq!(mem[0x44..0x48], 15u32.to_le_bytes());
+}
+
+#[test]
+fn switches_to_thumb_state() {
+    let mut mem = vec![0; 0x100];
+    let mut cpu = new_cpu(&mut mem);
+    let mut bus = &mut mem[..];
+
+    cpu.execute_one(&mut bus, Instr::Thumb(0x2000)); // movs r0, #0
+    assert_eq!(cpu.reg.cpsr.state(), OperationState::Thumb);
+    assert!(cpu.reg.cpsr.zero);
+    assert_eq!(cpu.next_instr_addr(), 2);
+    assert_eq!(cpu.reg.r[PC_INDEX], 6);
+
+    // Switching back to ARM realigns PC.
+    cpu.execute_one(&mut bus, Instr::Arm(0xe3a0_0007)); // mov r0, #7
+    assert_eq!(cpu.reg.cpsr.state(), OperationState::Arm);
+    assert_eq!(cpu.reg.r[0], 7);
+    assert_eq!(cpu.next_instr_addr(), 4);
+}
+
+#[test]
+fn executes_branches_and_swis() {
+    let mut mem = vec![0; 0x100];
+    let mut cpu = new_cpu(&mut mem);
+    let mut bus = &mut mem[..];
+
+    cpu.execute_one(&mut bus, Instr::Arm(0xea00_000e)); // b 0x40
+    assert_eq!(cpu.next_instr_addr(), 0x40);
+
+    cpu.execute_one(&mut bus, Instr::Arm(0xef00_0000)); // swi #0
+    assert_eq!(cpu.reg.cpsr.mode(), OperationMode::Supervisor);
+    assert_eq!(cpu.next_instr_addr(), 0x08);
+    assert_eq!(cpu.reg.r[14], 0x44);
+}
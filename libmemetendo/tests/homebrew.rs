@@ -0,0 +1,112 @@
+//! Boots homebrew ROMs headlessly, comparing hashes of their frames with known-good hashes, which
+//! covers much more of the emulator (BG modes, sprites, sound initialization, etc.) than the other
+//! tests.
+//!
+//! ROMs and their expected hashes are listed in `tests/homebrew/frames.txt`; see its comments for
+//! the format. ROMs that don't exist are skipped, so this is ignored by default.
+
+mod runner;
+mod util;
+
+use std::{collections::BTreeMap, fs, path::Path};
+
+use runner::Runner;
+use util::{hash_image, read_cart_rom};
+
+const DIR: &str = "tests/homebrew";
+
+/// Expected hash of a frame, or `None` if it should only be printed.
+type Frames = BTreeMap<u32, Option<u64>>;
+
+/// Parses the manifest into the frames to check for each ROM.
+fn parse_manifest(manifest: &str) -> BTreeMap<&str, Frames> {
+    let mut roms = BTreeMap::<_, Frames>::new();
+    for (i, line) in manifest.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let fields: Vec<_> = line.split_whitespace().collect();
+        let [rom, frame, hash] = fields[..] else {
+            panic!("line {}: expected \"<ROM file> <frame> <hash>\"", i + 1);
+        };
+        let frame = frame
+            .parse()
+            .unwrap_or_else(|_| panic!("line {}: bad frame \"{frame}\"", i + 1));
+        let hash = (hash != "?").then(|| {
+            u64::from_str_radix(hash, 16)
+                .unwrap_or_else(|_| panic!("line {}: bad hash \"{hash}\"", i + 1))
+        });
+        roms.entry(rom).or_default().insert(frame, hash);
+    }
+
+    roms
+}
+
+/// Runs the ROM to each frame in `frames`, returning descriptions of the frames that didn't match.
+fn check_rom(rom: &str, frames: &Frames) -> Vec<String> {
+    let mut runner = Runner::new(read_cart_rom(Path::new(DIR).join(rom)));
+    let mut mismatches = Vec::new();
+    let mut frame = 0;
+    for (&target_frame, &expected_hash) in frames {
+        runner.step_frames(target_frame - frame);
+        frame = target_frame;
+
+        let hash = hash_image(&runner.screen.image);
+        println!("{rom} {frame} {hash:016x}");
+        if expected_hash.is_some_and(|expected_hash| expected_hash != hash) {
+            let screen_path = Path::new(env!("CARGO_TARGET_TMPDIR"))
+                .join(format!("homebrew_{}_{frame}.png", rom.replace('/', "_")));
+            runner
+                .screen
+                .image
+                .save(&screen_path)
+                .expect("failed to save frame");
+            mismatches.push(format!(
+                "{rom} frame {frame}: expected hash {:016x}, got {hash:016x}; see {}",
+                expected_hash.unwrap(),
+                screen_path.display()
+            ));
+        }
+    }
+
+    mismatches
+}
+
+#[test]
+#[ignore = "slow, and needs homebrew ROMs in tests/homebrew"]
+fn boot_homebrew() {
+    let manifest_path = Path::new(DIR).join("frames.txt");
+    let manifest = fs::read_to_string(&manifest_path).expect("failed to read manifest");
+
+    let mut mismatches = Vec::new();
+    for (rom, frames) in parse_manifest(&manifest) {
+        if !Path::new(DIR).join(rom).is_file() {
+            eprintln!("skipping: \"{rom}\" not found in \"{DIR}\"");
+            continue;
+        }
+        mismatches.extend(check_rom(rom, &frames));
+    }
+
+    assert!(mismatches.is_empty(), "{}", mismatches.join("\n"));
+}
+
+#[test]
+fn parses_manifest() {
+    let manifest = "# comment\n\
+                    \n\
+                    a.gba 60 00000000deadbeef\n\
+                    b.gba 10 ?\n\
+                    a.gba 30 ?\n";
+    let roms = parse_manifest(manifest);
+    assert_eq!(roms.len(), 2);
+    assert_eq!(
+        roms["a.gba"].iter().collect::<Vec<_>>(),
+        [(&30, &None), (&60, &Some(0xdead_beef))]
+    );
+    assert_eq!(roms["b.gba"][&10], None);
+
+    // The committed manifest should always parse.
+    parse_manifest(&fs::read_to_string(Path::new(DIR).join("frames.txt")).unwrap());
+}
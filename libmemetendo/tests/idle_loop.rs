@@ -0,0 +1,68 @@
+//! Tests for skipping idle loops detected by `arm7tdmi::idle::Detector`.
+
+mod util;
+
+use libmemetendo::gba::Gba;
+
+/// ```text
+///     mov  r3, #0x04000000
+///     mov  r0, #8
+///     strh r0, [r3, #4]         @ DISPSTAT: VBlank IRQ
+///     add  r1, r3, #0x200
+///     mov  r0, #1
+///     strh r0, [r1]             @ IE: VBlank
+///     mov  r4, #0x03000000
+/// loop:
+///     ldrh r0, [r1, #2]         @ IF
+///     tst  r0, #1
+///     beq  loop
+///     strh r0, [r1, #2]         @ acknowledge VBlank
+///     ldr  r2, [r4]
+///     add  r2, r2, #1
+///     str  r2, [r4]
+///     b    loop
+/// ```
+const PROGRAM: [u32; 15] = [
+    0xe3a0_3301,
+    0xe3a0_0008,
+    0xe1c3_00b4,
+    0xe283_1c02,
+    0xe3a0_0001,
+    0xe1c1_00b0,
+    0xe3a0_4403,
+    0xe1d1_00b2,
+    0xe310_0001,
+    0x0aff_fffc,
+    0xe1c1_00b2,
+    0xe594_2000,
+    0xe282_2001,
+    0xe584_2000,
+    0xeaff_fff7,
+];
+
+fn new_gba(skip_idle_loops: bool) -> Gba {
+    let mut gba = util::test_gba(&PROGRAM);
+    gba.cpu.idle_loop.enabled = skip_idle_loops;
+
+    gba
+}
+
+#[test]
+fn skipping_idle_loops_preserves_behaviour() {
+    let mut gba = new_gba(false);
+    let mut idle_gba = new_gba(true);
+    let mut idle_steps = 0;
+    for _ in 0..300_000 {
+        util::step_n(&mut gba, 1);
+        util::step_n(&mut idle_gba, 1);
+        if idle_gba.cpu.idle_loop.is_idle() {
+            idle_steps += 1;
+        }
+    }
+
+    // Waiting for VBlank should be detected as idle for most of the time.
+    assert!(idle_steps > 200_000);
+    let vblanks = gba.read_word(0x0300_0000);
+    assert_eq!(vblanks, 3);
+    assert_eq!(idle_gba.read_word(0x0300_0000), vblanks);
+}
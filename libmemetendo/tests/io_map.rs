@@ -0,0 +1,56 @@
+//! Tests for inspecting IO registers and DMA channels, as a debugger would.
+
+mod util;
+
+use libmemetendo::gba::Event;
+
+#[test]
+fn dma_channel_state() {
+    let mut gba = util::test_gba(&[0; 0x80]);
+    gba.write_word(0x0400_00d4, 0x0300_0000); // DMA3SAD
+    gba.write_word(0x0400_00d8, 0x0200_0000); // DMA3DAD
+    gba.write_hword(0x0400_00dc, 0x10); // DMA3CNT_L
+    gba.write_hword(0x0400_00de, 0x9400); // DMA3CNT_H: enabled, 32-bit, VBlank
+
+    let state = gba.dma.channel_state(3);
+    assert_eq!(state.src_addr, 0x0300_0000);
+    assert_eq!(state.dst_addr, 0x0200_0000);
+    assert_eq!(state.count, 0x10);
+    assert_eq!(state.control, 0x9400);
+    assert!(!state.transferring);
+    assert_eq!(state.current_src_addr, 0x0300_0000);
+    assert_eq!(state.remaining, 0x10);
+
+    // Not repeating, so the channel disables itself after the transfer.
+    for event in [Event::VBlank, Event::Scanline] {
+        util::step_until(&mut gba, event);
+    }
+    let state = gba.dma.channel_state(3);
+    assert_eq!(state.control, 0x1400);
+    assert_eq!(state.current_src_addr, 0x0300_0040);
+    assert_eq!(state.current_dst_addr, 0x0200_0040);
+    assert_eq!(state.remaining, 0);
+
+    assert!(!gba.dma.channel_state(0).transferring);
+}
+
+#[test]
+fn dump_io_map() {
+    let mut gba = util::test_gba(&[0; 0x80]);
+    gba.write_hword(0x0400_0000, 0x0403); // DISPCNT
+    gba.write_hword(0x0400_0200, 0x0001); // IE
+
+    let dump = gba.dump_io_map();
+    let line = |name| {
+        dump.lines()
+            .find(|line| line.split_whitespace().nth(1) == Some(name))
+            .unwrap_or_else(|| panic!("{name} missing from dump:\n{dump}"))
+    };
+    assert_eq!(line("DISPCNT"), "0x04000000 DISPCNT     0x0403");
+    assert_eq!(line("IE"), "0x04000200 IE          0x0001");
+    assert_eq!(line("HALTCNT"), "0x04000301 HALTCNT     0x00");
+    assert_eq!(
+        line("WAVE_RAM"),
+        "0x04000090 WAVE_RAM    00000000000000000000000000000000"
+    );
+}
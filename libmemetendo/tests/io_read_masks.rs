@@ -0,0 +1,94 @@
+//! Tests that write-only and unused bits of IO registers read back as they do on hardware.
+
+mod util;
+
+use libmemetendo::gba::Gba;
+
+fn new_gba() -> Gba {
+    let mut gba = util::test_gba(&[0; 0x80]);
+    gba.write_hword(0x0400_0084, 0x0080); // SOUNDCNT_X: enable sound, so writes aren't ignored
+
+    gba
+}
+
+/// Writes `value` to the hword register at `addr`, and asserts that it reads back as `expected`.
+fn assert_read_back(gba: &mut Gba, name: &str, addr: u32, value: u16, expected: u16) {
+    gba.write_hword(addr, value);
+    assert_eq!(
+        gba.read_hword(addr),
+        expected,
+        "{name} ({addr:#x}) should read back {expected:#06x} after writing {value:#06x}"
+    );
+}
+
+#[test]
+fn video_registers() {
+    let mut gba = new_gba();
+    assert_read_back(&mut gba, "DISPCNT", 0x0400_0000, 0xffff, 0xfff7);
+    assert_read_back(&mut gba, "GREENSWP", 0x0400_0002, 0xffff, 0x0001);
+    gba.write_hword(0x0400_0004, 0xffff);
+    assert_eq!(gba.read_hword(0x0400_0004) & !7, 0xff38, "DISPSTAT");
+    for (i, addr) in (0x0400_0008..0x0400_0010).step_by(2).enumerate() {
+        let expected = if i < 2 { 0xdfff } else { 0xffff };
+        assert_read_back(&mut gba, "BGxCNT", addr, 0xffff, expected);
+    }
+    for addr in (0x0400_0010..0x0400_0048).step_by(2) {
+        assert_read_back(&mut gba, "BG offset/affine/window", addr, 0xffff, 0);
+    }
+    assert_read_back(&mut gba, "WININ", 0x0400_0048, 0xffff, 0x3f3f);
+    assert_read_back(&mut gba, "WINOUT", 0x0400_004a, 0xffff, 0x3f3f);
+    assert_read_back(&mut gba, "MOSAIC", 0x0400_004c, 0xffff, 0);
+    assert_read_back(&mut gba, "BLDCNT", 0x0400_0050, 0xffff, 0x3fff);
+    assert_read_back(&mut gba, "BLDALPHA", 0x0400_0052, 0xffff, 0x1f1f);
+    assert_read_back(&mut gba, "BLDY", 0x0400_0054, 0xffff, 0);
+}
+
+#[test]
+fn audio_registers() {
+    let mut gba = new_gba();
+    assert_read_back(&mut gba, "SOUND1CNT_L", 0x0400_0060, 0xffff, 0x007f);
+    assert_read_back(&mut gba, "SOUND1CNT_X", 0x0400_0064, 0xffff, 0x4000);
+    assert_read_back(&mut gba, "SOUNDCNT_H", 0x0400_0082, 0xffff, 0x770f);
+    assert_read_back(&mut gba, "SOUNDBIAS", 0x0400_0088, 0xffff, 0xc3fe);
+    assert_read_back(&mut gba, "FIFO_A", 0x0400_00a0, 0xffff, 0);
+}
+
+#[test]
+fn dma_registers() {
+    let mut gba = new_gba();
+    for chan_idx in 0..4 {
+        let base = 0x0400_00b0 + 12 * chan_idx;
+        assert_read_back(&mut gba, "DMAxSAD", base, 0xffff, 0);
+        assert_read_back(&mut gba, "DMAxDAD", base + 4, 0xffff, 0);
+        assert_read_back(&mut gba, "DMAxCNT_L", base + 8, 0xffff, 0);
+
+        // Don't set the enable bit, or a transfer will start.
+        let expected = if chan_idx == 3 { 0x7fe0 } else { 0x77e0 };
+        assert_read_back(&mut gba, "DMAxCNT_H", base + 10, 0x7fff, expected);
+    }
+}
+
+#[test]
+fn timer_registers() {
+    let mut gba = new_gba();
+    for timer_idx in 0..4 {
+        let base = 0x0400_0100 + 4 * timer_idx;
+        assert_read_back(&mut gba, "TMxCNT_L", base, 0x1234, 0);
+        assert_read_back(&mut gba, "TMxCNT_H", base + 2, 0xff7f, 0x0047);
+    }
+}
+
+#[test]
+fn keypad_registers() {
+    let mut gba = new_gba();
+    assert_read_back(&mut gba, "KEYINPUT", 0x0400_0130, 0, 0x03ff);
+    assert_read_back(&mut gba, "KEYCNT", 0x0400_0132, 0xffff, 0xc3ff);
+}
+
+#[test]
+fn irq_registers() {
+    let mut gba = new_gba();
+    assert_read_back(&mut gba, "IE", 0x0400_0200, 0xffff, 0x3fff);
+    assert_read_back(&mut gba, "IME", 0x0400_0208, 0xffff, 0x0001);
+    assert_read_back(&mut gba, "IME", 0x0400_020a, 0xffff, 0);
+}
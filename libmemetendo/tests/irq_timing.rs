@@ -0,0 +1,55 @@
+//! Tests for when interrupt requests are serviced relative to DMA transfers stalling the CPU.
+
+mod util;
+
+use libmemetendo::{
+    arm7tdmi::reg::{OperationMode, LR_INDEX},
+    gba::Gba,
+    keypad::Key,
+};
+
+/// ```text
+///     b    .
+/// ```
+const PROGRAM: [u32; 1] = [0xeaff_fffe];
+
+/// Creates a system with a keypad interrupt requested and IME set, then starts a DMA transfer that
+/// enables the interrupt in IE, before setting IME to `ime`. The transfer takes 4 steps: 2 cycles
+/// to start, then 2 cycles for each of its 5 units.
+fn new_gba_with_dma(ime: u16) -> Gba {
+    let mut gba = util::test_gba(&PROGRAM);
+
+    gba.keypad.set_pressed(Key::A, true);
+    gba.write_hword(0x0400_0132, 0x4001); // KEYCNT: IRQ on A
+    gba.write_word(0x0400_0208, 1); // IME
+
+    // Values for IE, IF, WAITCNT, (unused) and IME.
+    for (i, value) in [0x1000, 0, 0, 0, ime].into_iter().enumerate() {
+        gba.write_hword(0x0300_0000 + 2 * u32::try_from(i).unwrap(), value);
+    }
+    gba.write_word(0x0400_00d4, 0x0300_0000); // DMA3SAD
+    gba.write_word(0x0400_00d8, 0x0400_0200); // DMA3DAD
+    gba.write_hword(0x0400_00dc, 5); // DMA3CNT_L
+    gba.write_hword(0x0400_00de, 0x8000); // DMA3CNT_H: enable, immediate, 16-bit
+
+    gba
+}
+
+#[test]
+fn irq_requested_during_dma_is_serviced_after_it() {
+    let mut gba = new_gba_with_dma(1);
+    util::step_n(&mut gba, 4);
+    assert_eq!(gba.cpu.reg.cpsr.mode(), OperationMode::System);
+
+    // The interrupt should be serviced before the CPU executes another instruction.
+    util::step_n(&mut gba, 1);
+    assert_eq!(gba.cpu.reg.cpsr.mode(), OperationMode::Interrupt);
+    assert_eq!(gba.cpu.reg.r[LR_INDEX], 0x0800_0004);
+}
+
+#[test]
+fn irq_withdrawn_during_dma_is_not_serviced() {
+    let mut gba = new_gba_with_dma(0);
+    util::step_n(&mut gba, 10);
+    assert_eq!(gba.cpu.reg.cpsr.mode(), OperationMode::System);
+}
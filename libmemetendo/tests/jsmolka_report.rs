@@ -0,0 +1,144 @@
+//! Runs every jsmolka/gba-tests ROM and prints a matrix of results, rather than stopping at the
+//! first failure like the tests in `jsmolka.rs`. Useful for measuring accuracy progress; run it
+//! with `cargo test --test jsmolka_report -- --ignored --nocapture`.
+
+mod runner;
+mod util;
+
+use std::{
+    fmt::{self, Display, Formatter},
+    panic::{catch_unwind, AssertUnwindSafe},
+    path::Path,
+};
+
+use runner::Runner;
+use util::{read_cart_rom, read_image};
+
+/// Frames to run each test ROM for before giving up on seeing the pass screen.
+const MAX_FRAMES: u32 = 10;
+
+struct Case {
+    category: &'static str,
+    rom_path: &'static str,
+    pass_screen_path: &'static str,
+    /// Whether the ROM stores the number of the failed test in r12, as most of them do; the PPU
+    /// tests are only checked visually.
+    reports_failed_test: bool,
+}
+
+const fn case(category: &'static str, rom_path: &'static str) -> Case {
+    Case {
+        category,
+        rom_path,
+        pass_screen_path: "tests/jsmolka/ok.png",
+        reports_failed_test: true,
+    }
+}
+
+const fn ppu_case(
+    category: &'static str,
+    rom_path: &'static str,
+    pass_screen_path: &'static str,
+) -> Case {
+    Case {
+        category,
+        rom_path,
+        pass_screen_path,
+        reports_failed_test: false,
+    }
+}
+
+const CASES: [Case; 11] = [
+    case("arm", "tests/jsmolka/gba-tests/arm/arm.gba"),
+    case("thumb", "tests/jsmolka/gba-tests/thumb/thumb.gba"),
+    case("memory", "tests/jsmolka/gba-tests/memory/memory.gba"),
+    case("bios", "tests/jsmolka/gba-tests/bios/bios.gba"),
+    case("nes", "tests/jsmolka/gba-tests/nes/nes.gba"),
+    case("save/none", "tests/jsmolka/gba-tests/save/none.gba"),
+    case("save/sram", "tests/jsmolka/gba-tests/save/sram.gba"),
+    case("save/flash64", "tests/jsmolka/gba-tests/save/flash64.gba"),
+    case("save/flash128", "tests/jsmolka/gba-tests/save/flash128.gba"),
+    ppu_case(
+        "ppu/stripes",
+        "tests/jsmolka/gba-tests/ppu/stripes.gba",
+        "tests/jsmolka/ppu_stripes_ok.png",
+    ),
+    ppu_case(
+        "ppu/shades",
+        "tests/jsmolka/gba-tests/ppu/shades.gba",
+        "tests/jsmolka/ppu_shades_ok.png",
+    ),
+];
+
+enum Outcome {
+    Passed,
+    /// The pass screen wasn't shown; holds the number of the failed test, if reported.
+    Failed(Option<u32>),
+    /// The test ROM wasn't found; the submodules probably weren't fetched.
+    Missing,
+    /// The emulator panicked.
+    Panicked(String),
+}
+
+impl Display for Outcome {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Passed => write!(f, "pass"),
+            Self::Failed(Some(test)) => write!(f, "FAIL (test {test})"),
+            Self::Failed(None) => write!(f, "FAIL"),
+            Self::Missing => write!(f, "missing"),
+            Self::Panicked(msg) => write!(f, "PANIC ({msg})"),
+        }
+    }
+}
+
+fn run_case(case: &Case) -> Outcome {
+    if !Path::new(case.rom_path).is_file() {
+        return Outcome::Missing;
+    }
+
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        let pass_screen = read_image(case.pass_screen_path);
+        let mut runner = Runner::new(read_cart_rom(case.rom_path));
+        for _ in 0..MAX_FRAMES {
+            runner.step_frame();
+            if runner.screen.image == pass_screen {
+                return Outcome::Passed;
+            }
+        }
+
+        // r12 is 0 if the ROM didn't get as far as reporting a failed test.
+        let failed_test = runner.gba.cpu.reg.r[12];
+        Outcome::Failed((case.reports_failed_test && failed_test != 0).then_some(failed_test))
+    }));
+
+    result.unwrap_or_else(|payload| {
+        let msg = payload
+            .downcast_ref::<&str>()
+            .map(ToString::to_string)
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_default();
+        Outcome::Panicked(msg)
+    })
+}
+
+#[test]
+#[ignore = "slow; prints a report of all jsmolka/gba-tests ROMs"]
+fn report() {
+    let width = CASES.iter().map(|case| case.category.len()).max().unwrap();
+    let mut failures = 0;
+    println!();
+    for case in &CASES {
+        let outcome = run_case(case);
+        if matches!(outcome, Outcome::Failed(_) | Outcome::Panicked(_)) {
+            failures += 1;
+        }
+        println!("{:width$}  {outcome}", case.category);
+    }
+
+    assert!(
+        failures == 0,
+        "{failures} of {} test ROMs failed",
+        CASES.len()
+    );
+}
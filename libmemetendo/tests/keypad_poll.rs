@@ -0,0 +1,58 @@
+//! Tests for when the frontend is asked to poll input, per `keypad::PollRate`.
+
+mod util;
+
+use libmemetendo::{
+    gba::Event,
+    keypad::PollRate,
+    video::{HORIZ_DOTS, VERT_DOTS},
+};
+
+/// ```text
+///     b    .
+/// ```
+const PROGRAM: [u32; 1] = [0xeaff_fffe];
+
+/// Steps a frame from the start of VBlank, returning the VCOUNTs at which polls were requested.
+fn poll_vcounts(poll_rate: PollRate) -> Vec<u8> {
+    let mut gba = util::test_gba(&PROGRAM);
+    gba.keypad.poll_rate = poll_rate;
+
+    let (video_cb, audio_cb) = (
+        &mut libmemetendo::util::video::NullCallback,
+        &mut libmemetendo::util::audio::NullCallback,
+    );
+    assert!(gba.step_until(Event::VBlank, video_cb, audio_cb));
+    assert!(gba.keypad.take_poll());
+
+    // Each step is 3 cycles.
+    let frame_steps = 4 * u32::from(HORIZ_DOTS) * u32::from(VERT_DOTS) / 3;
+    let mut vcounts = Vec::new();
+    for _ in 0..frame_steps {
+        gba.step(video_cb, audio_cb);
+        if gba.keypad.take_poll() {
+            vcounts.push(gba.video.position().1);
+        }
+    }
+
+    vcounts
+}
+
+#[test]
+fn polls_at_rate() {
+    assert_eq!(poll_vcounts(PollRate::Frame), [160]);
+    assert_eq!(poll_vcounts(PollRate::HalfFrame), [0, 160]);
+    assert_eq!(
+        poll_vcounts(PollRate::Scanline),
+        (161..228).chain(0..=160).collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn parses_poll_rate() {
+    for rate in [PollRate::Frame, PollRate::HalfFrame, PollRate::Scanline] {
+        assert_eq!(rate.to_string().parse(), Ok(rate));
+    }
+    assert_eq!("Half-Frame".parse(), Ok(PollRate::HalfFrame));
+    assert!("vblank".parse::<PollRate>().is_err());
+}
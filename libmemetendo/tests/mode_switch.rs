@@ -0,0 +1,96 @@
+//! Tests that video mode switches in the middle of a frame (e.g: for split-screen effects) take
+//! effect from the next scanline, rather than in the middle of the current one.
+
+mod util;
+
+use image::RgbImage;
+use libmemetendo::{
+    gba::{Event, Gba},
+    util::{audio::NullCallback, video::FrameBuffer},
+    video::{self, Dot, HBLANK_DOT, VBLANK_DOT},
+};
+use util::hash_image;
+
+/// ```text
+///     b    .
+/// ```
+const PROGRAM: [u32; 1] = [0xeaff_fffe];
+
+/// Mode 3 with BG2 displayed.
+const MODE_3: u16 = 0x0403;
+/// Mode 0 with BG0 displayed.
+const MODE_0: u16 = 0x0100;
+
+struct Screen(FrameBuffer);
+
+impl video::Callback for Screen {
+    fn put_dot(&mut self, x: u8, y: u8, dot: Dot) {
+        self.0.put_dot(x, y, dot);
+    }
+
+    fn end_frame(&mut self, _green_swap: bool) {}
+
+    fn is_frame_skipping(&self) -> bool {
+        false
+    }
+}
+
+impl Screen {
+    fn dot(&self, x: u32, y: u32) -> [u8; 3] {
+        let i = 3 * usize::try_from(y * u32::from(HBLANK_DOT) + x).unwrap();
+        self.0 .0[i..i + 3].try_into().unwrap()
+    }
+}
+
+/// Creates a system with a red and blue gradient in the mode 3 bitmap, and BG0 entirely green in
+/// mode 0, over a black backdrop.
+fn new_gba() -> Gba {
+    let mut gba = util::test_gba(&PROGRAM);
+
+    gba.write_hword(0x0400_0020, 0x100); // BG2PA: identity
+    gba.write_hword(0x0400_0026, 0x100); // BG2PD: identity
+    for y in 0..u32::from(VBLANK_DOT) {
+        for x in 0..u32::from(HBLANK_DOT) {
+            let color = u16::try_from((x / 8) | ((y / 8) << 10)).unwrap();
+            gba.write_hword(0x0600_0000 + 2 * (y * u32::from(HBLANK_DOT) + x), color);
+        }
+    }
+
+    gba.write_hword(0x0400_0008, 0x1000); // BG0CNT: screen base block 16
+    gba.write_hword(0x0500_0000, 0); // Backdrop: black
+    gba.write_hword(0x0500_0002, 0x03e0); // BG palette 0, color 1: green
+    for addr in (0x0600_0020..0x0600_0040).step_by(4) {
+        gba.write_word(addr, 0x1111_1111); // Tile 1
+    }
+    for addr in (0x0600_8000..0x0600_8800).step_by(2) {
+        gba.write_hword(addr, 1);
+    }
+
+    gba
+}
+
+#[test]
+fn mode_switch_takes_effect_from_next_scanline() {
+    let mut gba = new_gba();
+    let mut screen = Screen(FrameBuffer::new(0));
+
+    // Draw the top of the frame in mode 3, switching to mode 0 in the middle of scanline 80.
+    assert!(gba.step_until(Event::VBlank, &mut screen, &mut NullCallback));
+    gba.write_hword(0x0400_0000, MODE_3);
+    assert!(gba.step_until(Event::VCount(80), &mut screen, &mut NullCallback));
+    while gba.video.position().0 < 120 {
+        gba.step(&mut screen, &mut NullCallback);
+    }
+    gba.write_hword(0x0400_0000, MODE_0);
+    assert!(gba.step_until(Event::VBlank, &mut screen, &mut NullCallback));
+
+    assert_eq!(screen.dot(8, 8), [8, 0, 8]);
+    assert_eq!(screen.dot(239, 79), [29 * 8, 0, 9 * 8]);
+    assert_eq!(screen.dot(239, 80), [29 * 8, 0, 10 * 8]);
+    assert_eq!(screen.dot(0, 81), [0, 31 * 8, 0]);
+    assert_eq!(screen.dot(239, 159), [0, 31 * 8, 0]);
+
+    let image =
+        RgbImage::from_raw(HBLANK_DOT.into(), VBLANK_DOT.into(), screen.0 .0.into()).unwrap();
+    assert_eq!(hash_image(&image), 0x6fd3_d40b_6e0b_5ea5);
+}
@@ -0,0 +1,78 @@
+//! Tests for rendering mosaic backgrounds.
+
+mod util;
+
+use libmemetendo::{
+    gba::Gba,
+    util::audio,
+    video::{self, Dot, HBLANK_DOT},
+};
+
+/// ```text
+///     b .
+/// ```
+const PROGRAM: [u32; 1] = [0xeaff_fffe];
+
+/// Records the first scanline of the first frame.
+#[derive(Default)]
+struct FirstLine {
+    dots: Vec<Dot>,
+    done: bool,
+}
+
+impl video::Callback for FirstLine {
+    fn put_dot(&mut self, _x: u8, y: u8, dot: Dot) {
+        if y == 0 && !self.done {
+            self.dots.push(dot);
+        }
+    }
+
+    fn end_frame(&mut self, _green_swap: bool) {
+        self.done = true;
+    }
+
+    fn is_frame_skipping(&self) -> bool {
+        false
+    }
+}
+
+/// Creates a system displaying BG0 with mosaic enabled, whose tiles have red dots in even columns
+/// and transparent dots in odd ones.
+fn new_gba() -> Gba {
+    let mut gba = util::test_gba(&PROGRAM);
+
+    gba.write_hword(0x0400_0000, 0x0100); // DISPCNT: mode 0, BG0
+    gba.write_hword(0x0400_0008, 0x0840); // BG0CNT: mosaic, screen base block 8
+    gba.write_hword(0x0500_0002, 0x001f); // Palette colour 1: red
+    for addr in (0x0600_0000..0x0600_0020).step_by(2) {
+        gba.write_hword(addr, 0x0101); // Tile 0: dots with colour 1, then 0
+    }
+
+    gba
+}
+
+/// Returns which dots of the first scanline of the next frame are red.
+fn red_dots(gba: &mut Gba) -> Vec<bool> {
+    let mut line = FirstLine::default();
+    while !line.done {
+        gba.step(&mut line, &mut audio::NullCallback);
+    }
+    assert_eq!(line.dots.len(), HBLANK_DOT.into());
+
+    line.dots.iter().map(|dot| dot.red() == 31).collect()
+}
+
+#[test]
+fn renders_without_mosaic_before_mosaic_is_written() {
+    let mut gba = new_gba();
+    let dots = red_dots(&mut gba);
+    assert!(dots.iter().step_by(2).all(|&red| red));
+    assert!(dots.iter().skip(1).step_by(2).all(|&red| !red));
+}
+
+#[test]
+fn renders_mosaic_size() {
+    let mut gba = new_gba();
+    gba.write_hword(0x0400_004c, 0x0001); // MOSAIC: BG width of 2
+    assert!(red_dots(&mut gba).into_iter().all(|red| red));
+}
@@ -0,0 +1,107 @@
+//! Tests that writes to OAM are ignored while it's being read to draw objects, when
+//! `gba::Builder::restrict_oam_access` is enabled.
+
+mod util;
+
+use libmemetendo::{
+    cart::Cartridge,
+    gba::{Builder, Event, Gba},
+};
+
+/// Writes an increasing counter to OAM and IWRAM, in that order.
+///
+/// ```text
+///     mov  r3, #0x03000000
+///     mov  r4, #0x07000000
+/// loop:
+///     add  r0, r0, #1
+///     strh r0, [r4]
+///     str  r0, [r3]
+///     b    loop
+/// ```
+const PROGRAM: [u32; 6] = [
+    0xe3a0_3403,
+    0xe3a0_4407,
+    0xe280_0001,
+    0xe1c4_00b0,
+    0xe583_0000,
+    0xeaff_fffb,
+];
+
+fn new_gba(restrict_oam_access: bool) -> Gba {
+    let cart = Cartridge::from(util::program_rom(&PROGRAM));
+    Builder::new(util::zero_bios_rom(), cart)
+        .skip_bios(true)
+        .restrict_oam_access(restrict_oam_access)
+        .build()
+        .unwrap()
+}
+
+/// Returns the counter as last written to OAM, and as last written to IWRAM.
+fn counters(gba: &mut Gba) -> (u16, u16) {
+    (gba.read_hword(0x0700_0000), gba.read_hword(0x0300_0000))
+}
+
+/// Whether the last counter written to OAM landed (the program may be between the two writes).
+fn is_oam_up_to_date(gba: &mut Gba) -> bool {
+    let (oam, iwram) = counters(gba);
+    oam.wrapping_sub(iwram) <= 1
+}
+
+#[test]
+fn ignores_writes_while_drawing() {
+    let mut gba = new_gba(true);
+    util::step_until(&mut gba, Event::HBlank);
+    let (oam, iwram) = counters(&mut gba);
+    assert_eq!(oam, 0);
+    assert_ne!(iwram, 0);
+
+    // Without the "H-Blank interval free" bit, OAM is busy preparing the next scanline.
+    util::step_n(&mut gba, 10);
+    assert_eq!(counters(&mut gba).0, 0);
+    util::step_until(&mut gba, Event::VCount(100));
+    util::step_until(&mut gba, Event::HBlank);
+    assert_eq!(counters(&mut gba).0, 0);
+
+    // Free from the H-Blank before the first invisible scanline.
+    util::step_until(&mut gba, Event::VBlank);
+    assert!(is_oam_up_to_date(&mut gba));
+    util::step_until(&mut gba, Event::VCount(0));
+    let (oam, _) = counters(&mut gba);
+    util::step_until(&mut gba, Event::HBlank);
+    assert_eq!(counters(&mut gba).0, oam);
+}
+
+#[test]
+fn allows_writes_in_hblank_if_interval_free() {
+    let mut gba = new_gba(true);
+    gba.write_hword(0x0400_0000, 0x0020); // DISPCNT: H-Blank interval free
+    util::step_until(&mut gba, Event::HBlank);
+    assert_eq!(counters(&mut gba).0, 0);
+
+    util::step_n(&mut gba, 10);
+    assert!(is_oam_up_to_date(&mut gba));
+    util::step_until(&mut gba, Event::Scanline);
+    let (oam, _) = counters(&mut gba);
+    util::step_n(&mut gba, 10);
+    let (new_oam, iwram) = counters(&mut gba);
+    assert_eq!(new_oam, oam);
+    assert_ne!(iwram, oam);
+}
+
+#[test]
+fn allows_writes_in_forced_blank() {
+    let mut gba = new_gba(true);
+    gba.write_hword(0x0400_0000, 0x0080); // DISPCNT: forced blank
+    util::step_n(&mut gba, 10);
+    assert!(is_oam_up_to_date(&mut gba));
+    assert_ne!(counters(&mut gba).0, 0);
+}
+
+#[test]
+fn allows_writes_while_drawing_if_unrestricted() {
+    let mut gba = new_gba(false);
+    util::step_n(&mut gba, 10);
+    assert!(is_oam_up_to_date(&mut gba));
+    assert_ne!(counters(&mut gba).0, 0);
+}
@@ -0,0 +1,162 @@
+//! Pixel-exact tests for how sprites (objects) are positioned and clipped.
+
+mod util;
+
+use libmemetendo::{
+    gba::Gba,
+    video::{self, Dot, HBLANK_DOT, VBLANK_DOT},
+};
+
+/// ```text
+///     b    .
+/// ```
+const PROGRAM: [u32; 1] = [0xeaff_fffe];
+
+/// Records which dots of the last frame were drawn with the sprites' (red) color.
+struct RedDots {
+    dots: Vec<bool>,
+    frames: u32,
+}
+
+impl video::Callback for RedDots {
+    fn put_dot(&mut self, x: u8, y: u8, dot: Dot) {
+        let i = usize::from(y) * usize::from(HBLANK_DOT) + usize::from(x);
+        self.dots[i] = (dot.red(), dot.green(), dot.blue()) == (Dot::MAX_COMPONENT, 0, 0);
+    }
+
+    fn end_frame(&mut self, _green_swap: bool) {
+        self.frames += 1;
+    }
+
+    fn is_frame_skipping(&self) -> bool {
+        false
+    }
+}
+
+/// Creates a system displaying only sprites, where every sprite dot (with 1D mapping) is red, and
+/// the backdrop is black. Every sprite is disabled.
+fn new_gba() -> Gba {
+    let mut gba = util::test_gba(&PROGRAM);
+
+    gba.write_hword(0x0400_0000, 0x1040); // DISPCNT: mode 0, 1D obj mapping, display obj
+    gba.write_hword(0x0500_0000, 0); // Backdrop: black
+    gba.write_hword(0x0500_0202, 0x001f); // Obj palette 0, color 1: red
+    for addr in (0x0601_0000..0x0601_8000).step_by(4) {
+        gba.write_word(addr, 0x1111_1111);
+    }
+    for i in 0..128 {
+        gba.write_hword(0x0700_0000 + 8 * i, 0x0200); // Hidden
+    }
+
+    gba
+}
+
+/// Sets the attributes of sprite `idx`.
+fn set_obj(gba: &mut Gba, idx: u32, attrs: [u16; 3]) {
+    for (i, attr) in (0..).zip(attrs) {
+        gba.write_hword(0x0700_0000 + 8 * idx + 2 * i, attr);
+    }
+}
+
+/// Sets the affine parameters at `idx` to scale sprites by the reciprocal of `scale` (8.8 fixed
+/// point), so that 0x100 is the identity, and 0x80 doubles their size.
+fn set_affine_scale(gba: &mut Gba, idx: u32, scale: u16) {
+    let base = 0x0700_0006 + 32 * idx;
+    for (i, value) in (0..).zip([scale, 0, 0, scale]) {
+        gba.write_hword(base + 8 * i, value);
+    }
+}
+
+/// Renders a frame, returning which of its dots are red.
+fn render(gba: &mut Gba) -> Vec<bool> {
+    let mut video_cb = RedDots {
+        dots: vec![false; usize::from(HBLANK_DOT) * usize::from(VBLANK_DOT)],
+        frames: 0,
+    };
+    while video_cb.frames < 2 {
+        gba.step(&mut video_cb, &mut libmemetendo::util::audio::NullCallback);
+    }
+
+    video_cb.dots
+}
+
+/// Asserts that exactly the dots for which `expected` returns true are red.
+fn assert_red_dots(dots: &[bool], expected: impl Fn(i32, i32) -> bool) {
+    for y in 0..i32::from(VBLANK_DOT) {
+        for x in 0..i32::from(HBLANK_DOT) {
+            let i = usize::try_from(y * i32::from(HBLANK_DOT) + x).unwrap();
+            assert_eq!(dots[i], expected(x, y), "dot at ({x}, {y})");
+        }
+    }
+}
+
+#[test]
+fn double_size_affine_bounding_area() {
+    // 8x8 affine sprite at (16, 16) with the identity transform. With double-size, its 16x16
+    // bounding area is centered on the sprite, which is drawn in the middle 8x8 dots.
+    let mut gba = new_gba();
+    set_affine_scale(&mut gba, 0, 0x100);
+    set_obj(&mut gba, 0, [0x0310 | 16, 16, 0]);
+    assert_red_dots(&render(&mut gba), |x, y| {
+        (20..28).contains(&x) && (20..28).contains(&y)
+    });
+
+    // Scaled to twice its size, it fills its bounding area exactly.
+    set_affine_scale(&mut gba, 0, 0x80);
+    assert_red_dots(&render(&mut gba), |x, y| {
+        (16..32).contains(&x) && (16..32).contains(&y)
+    });
+
+    // Without double-size, the same sprite is clipped to its 8x8 bounding area.
+    set_obj(&mut gba, 0, [0x0110 | 16, 16, 0]);
+    assert_red_dots(&render(&mut gba), |x, y| {
+        (16..24).contains(&x) && (16..24).contains(&y)
+    });
+}
+
+#[test]
+fn x_wraps_at_9_bits() {
+    let mut gba = new_gba();
+    // X = 508 (-4): the rightmost 4 columns are drawn at the left edge.
+    set_obj(&mut gba, 0, [10, 508, 0]);
+    // X = 236: the leftmost 4 columns are drawn at the right edge.
+    set_obj(&mut gba, 1, [30, 236, 0]);
+    // X = 240 to 255 are off-screen, and don't wrap around to the left edge.
+    set_obj(&mut gba, 2, [50, 240, 0]);
+    set_obj(&mut gba, 3, [70, 255, 0]);
+    assert_red_dots(&render(&mut gba), |x, y| {
+        ((10..18).contains(&y) && (0..4).contains(&x))
+            || ((30..38).contains(&y) && (236..240).contains(&x))
+    });
+}
+
+#[test]
+fn y_wraps_at_8_bits() {
+    let mut gba = new_gba();
+    // Y = 252: the bottom 4 rows are drawn at the top edge.
+    set_obj(&mut gba, 0, [252, 10, 0]);
+    // Y = 150: a 128x128 (64x64 double-size) sprite covers rows 150 to 277, so it wraps around to
+    // draw rows 0 to 21 at the top.
+    set_affine_scale(&mut gba, 0, 0x80);
+    set_obj(&mut gba, 1, [0x0300 | 150, 0xc000 | 100, 0]);
+    assert_red_dots(&render(&mut gba), |x, y| {
+        ((0..4).contains(&y) && (10..18).contains(&x))
+            || ((150..160).contains(&y) || (0..22).contains(&y)) && (100..228).contains(&x)
+    });
+}
+
+#[test]
+fn hidden_sprites_are_not_drawn() {
+    let mut gba = new_gba();
+    set_affine_scale(&mut gba, 0, 0x100);
+    // Bit 9 of attribute 0 hides non-affine sprites, whatever their other attributes. The affine
+    // parameter bits in attribute 1 are flips for non-affine sprites.
+    set_obj(&mut gba, 0, [0x0200 | 16, 0x3e00 | 16, 0]);
+    // Affine sprites use bit 9 as the double-size flag instead, so are still drawn.
+    set_obj(&mut gba, 1, [0x0300 | 40, 40, 0]);
+    // OBJ mode 3 is prohibited, and isn't drawn.
+    set_obj(&mut gba, 2, [0x0c00 | 80, 80, 0]);
+    assert_red_dots(&render(&mut gba), |x, y| {
+        (44..52).contains(&x) && (44..52).contains(&y)
+    });
+}
@@ -0,0 +1,50 @@
+//! Tests for running independent systems in parallel, as multi-ROM sessions (like `memetendo
+//! --link`) do. Nothing is shared between `Gba` instances, so each must behave exactly as it would
+//! if it were the only one.
+
+mod util;
+
+use std::thread;
+
+use libmemetendo::gba::Event;
+
+/// ```text
+///     mov r3, #0x03000000
+/// loop:
+///     add r0, r0, #1
+///     and r1, r0, #0xff
+///     str r0, [r3, r1, lsl #2]
+///     b   loop
+/// ```
+const PROGRAM: [u32; 5] = [
+    0xe3a0_3403,
+    0xe280_0001,
+    0xe200_10ff,
+    0xe783_0101,
+    0xeaff_fffb,
+];
+
+/// Runs a system for `frames` frames, returning its save state. Each instance gets a differently
+/// sized ROM, so their states differ.
+fn run(frames: usize) -> Vec<u8> {
+    let mut program = PROGRAM.to_vec();
+    program.resize(0x40 * frames, 0);
+    let mut gba = util::test_gba(&program);
+    for _ in 0..frames {
+        util::step_until(&mut gba, Event::VBlank);
+    }
+
+    gba.save_state()
+}
+
+#[test]
+fn runs_instances_in_parallel() {
+    let frame_counts = [5, 10, 15, 20];
+    let threads = frame_counts.map(|frames| thread::spawn(move || run(frames)));
+    let states = threads.map(|thread| thread.join().unwrap());
+
+    for (state, frames) in states.iter().zip(frame_counts) {
+        assert_eq!(*state, run(frames), "{frames} frames");
+    }
+    assert!(states.windows(2).all(|pair| pair[0] != pair[1]));
+}
@@ -0,0 +1,53 @@
+//! Tests for profiling guest functions via `debug::profile::Profiler`.
+
+mod util;
+
+use libmemetendo::debug::symbols::Symbol;
+
+/// ```text
+/// main:
+///     bl   foo
+///     b    .
+///     nop
+/// foo:
+///     mov  r0, #1
+///     bx   lr
+/// ```
+const PROGRAM: [u32; 5] = [
+    0xeb00_0001,
+    0xeaff_fffe,
+    0xe1a0_0000,
+    0xe3a0_0001,
+    0xe12f_ff1e,
+];
+
+#[test]
+fn attributes_samples_to_functions() {
+    let mut gba = util::test_gba(&PROGRAM);
+    for (name, addr) in [("main", 0x0800_0000), ("foo", 0x0800_000c)] {
+        gba.debug.symbols.insert(Symbol {
+            name: name.to_string(),
+            addr,
+            size: None,
+        });
+    }
+    gba.debug.profiler.set_enabled(true);
+
+    util::step_n(&mut gba, 100);
+
+    let profiler = &gba.debug.profiler;
+    let total = profiler.total_samples();
+    assert!(total > 3);
+    assert_eq!(profiler.inclusive_samples(0x0800_0000), total);
+    assert_eq!(profiler.exclusive_samples(0x0800_0000), total - 2);
+    assert_eq!(profiler.exclusive_samples(0x0800_000c), 2);
+
+    let mut folded = Vec::new();
+    profiler
+        .write_folded(&mut folded, &gba.debug.symbols)
+        .unwrap();
+    assert_eq!(
+        String::from_utf8(folded).unwrap(),
+        format!("main {}\nmain;foo 2\n", total - 2)
+    );
+}
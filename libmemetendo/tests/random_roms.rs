@@ -0,0 +1,98 @@
+//! Tests that running garbage, or accessing memory and IO registers with garbage, doesn't crash the
+//! emulator, as no ROM should be able to.
+
+mod util;
+
+use std::rc::Rc;
+
+use libmemetendo::{
+    cart::{self, Cartridge},
+    video::{self, Dot},
+};
+
+struct RenderingCallback;
+
+impl video::Callback for RenderingCallback {
+    fn put_dot(&mut self, _x: u8, _y: u8, _dot: Dot) {}
+
+    fn end_frame(&mut self, _green_swap: bool) {}
+
+    fn is_frame_skipping(&self) -> bool {
+        false
+    }
+}
+
+/// Returns pseudo-random numbers from xorshift64, so failures are reproducible.
+fn random_numbers(seed: u64) -> impl Iterator<Item = u64> {
+    let mut state = seed;
+    std::iter::repeat_with(move || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state
+    })
+}
+
+fn random_bytes(seed: u64, len: usize) -> Vec<u8> {
+    random_numbers(seed)
+        .map(|n| n.to_le_bytes()[0])
+        .take(len)
+        .collect()
+}
+
+#[test]
+fn random_roms_do_not_panic() {
+    for seed in 1..=64 {
+        let cart_rom = cart::Rom::new(Rc::from(random_bytes(seed, 0x1_0000))).unwrap();
+        let mut gba = util::test_gba_with_cart(Cartridge::from(cart_rom));
+
+        for _ in 0..100_000 {
+            gba.step(
+                &mut RenderingCallback,
+                &mut libmemetendo::util::audio::NullCallback,
+            );
+        }
+    }
+}
+
+#[test]
+fn random_bus_accesses_do_not_panic() {
+    const REGIONS: [u32; 8] = [
+        0x0000_0000, // BIOS
+        0x0200_0000, // EWRAM
+        0x0400_0000, // IO registers
+        0x0500_0000, // Palette RAM
+        0x0600_0000, // VRAM
+        0x0700_0000, // OAM
+        0x0800_0000, // Cartridge ROM
+        0x0d00_0000, // Cartridge backup (e.g: EEPROM, flash, SRAM)
+    ];
+
+    for seed in 1..=32 {
+        let mut gba = util::test_gba(&[0xeaff_fffe]); // b .
+
+        let mut rng = random_numbers(seed);
+        let mut next = || rng.next().unwrap();
+        for _ in 0..10_000 {
+            let region = REGIONS[usize::try_from(next() % 8).unwrap()];
+            let addr = region + u32::try_from(next() % 0x200_0000).unwrap();
+            let value = u32::try_from(next() & 0xffff_ffff).unwrap();
+            match next() % 7 {
+                0 => gba.write_byte(addr, value.to_le_bytes()[0]),
+                1 => gba.write_hword(addr, u16::try_from(value & 0xffff).unwrap()),
+                2 => gba.write_word(addr, value),
+                3 => _ = gba.read_byte(addr),
+                4 => _ = gba.read_hword(addr),
+                5 => _ = gba.read_word(addr),
+                _ => {
+                    for _ in 0..next() % 500 {
+                        gba.step(
+                            &mut RenderingCallback,
+                            &mut libmemetendo::util::audio::NullCallback,
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
@@ -5,6 +5,7 @@ use libmemetendo::{
     bios,
     cart::{self, Cartridge},
     gba::Gba,
+    keypad::Key,
     util::{self, video::FrameBuffer},
     video::{self, HBLANK_DOT, VBLANK_DOT},
 };
@@ -62,6 +63,19 @@ impl Runner {
             self.step_frame();
         }
     }
+
+    /// Like `step_frames`, but presses (or releases) keys before the frames given by `inputs`, as
+    /// `(frame, key, pressed)` tuples sorted by frame.
+    #[allow(unused)]
+    pub fn step_frames_with_input(&mut self, frames: u32, inputs: &[(u32, Key, bool)]) {
+        let mut inputs = inputs.iter().peekable();
+        for frame in 0..frames {
+            while let Some(&(_, key, pressed)) = inputs.next_if(|&&(f, ..)| f <= frame) {
+                self.gba.keypad.set_pressed(key, pressed);
+            }
+            self.step_frame();
+        }
+    }
 }
 
 pub struct VideoCallback {
@@ -0,0 +1,27 @@
+//! Tests for wide accesses to cartridge save memory through the system bus, which only has 8 data
+//! lines.
+
+mod util;
+
+use libmemetendo::{
+    cart::{BackupType, Cartridge},
+    gba::Gba,
+};
+
+fn new_gba(backup_type: BackupType) -> Gba {
+    util::test_gba_with_cart(Cartridge::new(util::program_rom(&[0; 0x30]), backup_type))
+}
+
+#[test]
+fn wide_sram_accesses_use_one_byte() {
+    let mut gba = new_gba(BackupType::Sram32KiB);
+    gba.write_word(0x0e00_0100, 0x4433_2211);
+    gba.write_hword(0x0e00_0200, 0x6655);
+    assert_eq!(gba.read_byte(0x0e00_0100), 0x11);
+    assert_eq!(gba.read_byte(0x0e00_0101), 0xff);
+    assert_eq!(gba.read_byte(0x0e00_0200), 0x55);
+    assert_eq!(gba.read_byte(0x0e00_0201), 0xff);
+
+    assert_eq!(gba.read_word(0x0e00_0100), 0x1111_1111);
+    assert_eq!(gba.read_hword(0x0f00_0200), 0x5555); // Mirrored.
+}
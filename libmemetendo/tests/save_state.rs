@@ -0,0 +1,102 @@
+//! Tests for `Gba::save_state` and `Gba::load_state`.
+
+mod util;
+
+use libmemetendo::{
+    cart::{BackupType, Cartridge},
+    gba::Gba,
+};
+
+/// ```text
+///     mov r3, #0x03000000
+/// loop:
+///     add r0, r0, #1
+///     and r1, r0, #0xff
+///     str r0, [r3, r1, lsl #2]
+///     b   loop
+/// ```
+const PROGRAM: [u32; 5] = [
+    0xe3a0_3403,
+    0xe280_0001,
+    0xe200_10ff,
+    0xe783_0101,
+    0xeaff_fffb,
+];
+
+fn new_gba() -> Gba {
+    let cart = Cartridge::new(util::program_rom(&PROGRAM), BackupType::Sram32KiB);
+    let mut gba = util::test_gba_with_cart(cart);
+    gba.write_hword(0x0400_0000, 0x1f40); // DISPCNT: mode 0, BG0-3 & OBJ on
+    gba.write_hword(0x0700_0000, 0x0010); // OBJ 0 attr 0: y = 16
+    gba.write_byte(0x0e00_0000, 0x42); // SRAM
+
+    gba
+}
+
+#[test]
+fn load_restores_saved_state() {
+    let mut gba = new_gba();
+    util::step_n(&mut gba, 10_000);
+    let state = gba.save_state();
+
+    util::step_n(&mut gba, 100_000);
+    let expected_state = gba.save_state();
+    assert_ne!(state, expected_state);
+
+    gba.load_state(&state).unwrap();
+    assert_eq!(gba.save_state(), state);
+    util::step_n(&mut gba, 100_000);
+    assert_eq!(gba.save_state(), expected_state);
+
+    // Loading into a different instance should work the same.
+    let mut other_gba = new_gba();
+    other_gba.load_state(&state).unwrap();
+    util::step_n(&mut other_gba, 100_000);
+    assert_eq!(other_gba.save_state(), expected_state);
+    assert_eq!(other_gba.read_byte(0x0e00_0000), 0x42);
+}
+
+#[test]
+fn load_rejects_invalid_state() {
+    let mut gba = new_gba();
+    let state = gba.save_state();
+
+    assert!(gba.load_state(b"").is_err());
+    assert!(gba.load_state(b"not a save state").is_err());
+    assert!(gba.load_state(&state[..state.len() / 2]).is_err());
+
+    let mut bad_version = state.clone();
+    bad_version[4] ^= 0xff;
+    assert!(gba.load_state(&bad_version).is_err());
+
+    assert_eq!(gba.save_state(), state);
+}
+
+/// A state saved by version 1 of the format, before it was split into versioned chunks. It was
+/// saved by the last core version to use that format, after 10,000 steps from `new_gba`.
+const STATE_V1: &[u8] = include_bytes!("save_state/v1.state");
+
+#[test]
+fn load_migrates_version_1_state() {
+    let mut gba = new_gba();
+    gba.write_byte(0x0e00_0000, 0);
+    gba.load_state(STATE_V1).unwrap();
+    assert_eq!(gba.read_byte(0x0e00_0000), 0x42);
+    assert_eq!(gba.read_hword(0x0400_0000), 0x1f40);
+    assert_eq!(gba.read_hword(0x0700_0000), 0x0010);
+    // SOUNDBIAS; version 1 saved its level halved.
+    assert_eq!(gba.read_hword(0x0400_0088), 0x0200);
+
+    // The program may not have stored r0 yet, but it stored the value before it.
+    let r0 = gba.cpu.reg.r[0];
+    assert!(r0 > 0);
+    assert_eq!(gba.read_word(0x0300_0000 + 4 * ((r0 - 1) & 0xff)), r0 - 1);
+    util::step_n(&mut gba, 10_000);
+    assert!(gba.cpu.reg.r[0] > r0);
+
+    // Once saved again, it's in the current version of the format.
+    let state = gba.save_state();
+    assert_ne!(&state[4..8], &STATE_V1[4..8]);
+    gba.load_state(&state).unwrap();
+    assert_eq!(gba.save_state(), state);
+}
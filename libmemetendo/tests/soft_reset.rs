@@ -0,0 +1,92 @@
+//! Tests for `Gba::soft_reset`, which resets the system like a game's soft reset key combo, and
+//! `Gba::register_ram_reset`.
+
+mod util;
+
+use libmemetendo::{
+    arm7tdmi::reg::{OperationMode, OperationState, SP_INDEX},
+    gba::{Gba, RamResetFlags},
+};
+
+/// ```text
+///     add r0, pc, #1
+///     bx  r0       ; Switch to Thumb state.
+/// .thumb
+///     b   .
+///     b   .
+/// ```
+const PROGRAM: [u32; 3] = [0xe28f_0001, 0xe12f_ff10, 0xe7fe_e7fe];
+
+fn new_gba() -> Gba {
+    let mut gba = util::test_gba(&PROGRAM);
+    util::step_n(&mut gba, 20);
+    assert_eq!(gba.cpu.reg.cpsr.state(), OperationState::Thumb);
+
+    gba
+}
+
+#[test]
+fn resets_flagged_memory_and_registers() {
+    let mut gba = new_gba();
+    gba.write_word(0x0200_0000, 0x1234_5678); // EWRAM
+    gba.write_word(0x0300_0000, 0x1234_5678); // IWRAM
+    gba.write_word(0x0300_7f00, 0x1234_5678); // IWRAM used by the BIOS
+    gba.write_hword(0x0500_0000, 0x7fff); // Palette RAM
+    gba.write_hword(0x0400_0000, 0x0403); // DISPCNT
+    gba.write_hword(0x0400_0200, 0x0001); // IE
+
+    gba.soft_reset(RamResetFlags::EWRAM | RamResetFlags::PALETTE_RAM | RamResetFlags::OTHER_REGS);
+    assert_eq!(gba.read_word(0x0200_0000), 0);
+    assert_eq!(gba.read_word(0x0300_0000), 0x1234_5678);
+    assert_eq!(gba.read_word(0x0300_7f00), 0);
+    assert_eq!(gba.read_hword(0x0500_0000), 0);
+    assert_eq!(gba.read_hword(0x0400_0000), 0x0080); // Forced blank
+    assert_eq!(gba.read_hword(0x0400_0200), 0);
+
+    assert_eq!(gba.cpu.next_instr_addr(), 0x0800_0000);
+    assert_eq!(gba.cpu.reg.cpsr.state(), OperationState::Arm);
+    assert_eq!(gba.cpu.reg.cpsr.mode(), OperationMode::System);
+    assert_eq!(gba.cpu.reg.r[0], 0);
+    assert_eq!(gba.cpu.reg.r[SP_INDEX], 0x0300_7f00);
+}
+
+#[test]
+fn restarts_from_ewram_if_flagged() {
+    let mut gba = new_gba();
+    gba.write_word(0x0200_0000, 0x1234_5678);
+    gba.write_byte(0x0300_7ffa, 1);
+
+    gba.soft_reset(RamResetFlags::NONE);
+    assert_eq!(gba.cpu.next_instr_addr(), 0x0200_0000);
+    assert_eq!(gba.read_word(0x0200_0000), 0x1234_5678);
+    assert_eq!(gba.read_byte(0x0300_7ffa), 0);
+}
+
+#[test]
+fn register_ram_reset_only_clears_flagged_regions() {
+    let mut gba = new_gba();
+    for addr in [
+        0x0200_0000,
+        0x0300_7dfc,
+        0x0300_7e00,
+        0x0600_0000,
+        0x0700_0000,
+    ] {
+        gba.write_word(addr, 0x1234_5678);
+    }
+    gba.write_hword(0x0400_0084, 0x0080); // SOUNDCNT_X: sound enabled
+    gba.write_hword(0x0400_0200, 0x0001); // IE
+
+    gba.register_ram_reset(RamResetFlags::from_bits(0b0101_1010)); // IWRAM, VRAM, OAM, sound
+    assert_eq!(gba.read_word(0x0200_0000), 0x1234_5678);
+    assert_eq!(gba.read_word(0x0300_7dfc), 0);
+    assert_eq!(gba.read_word(0x0300_7e00), 0x1234_5678);
+    assert_eq!(gba.read_word(0x0600_0000), 0);
+    assert_eq!(gba.read_word(0x0700_0000), 0);
+    assert_eq!(gba.read_hword(0x0400_0084) & 0x80, 0);
+    assert_eq!(gba.read_hword(0x0400_0200), 0x0001);
+    assert_eq!(gba.read_hword(0x0400_0000), 0x0080);
+
+    // The CPU is left as it was.
+    assert_eq!(gba.cpu.reg.cpsr.state(), OperationState::Thumb);
+}
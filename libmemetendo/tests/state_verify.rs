@@ -0,0 +1,64 @@
+//! Tests for detecting nondeterminism via `debug::verify::StateVerifier`.
+
+mod util;
+
+use libmemetendo::{
+    debug::verify::{Divergence, StateVerifier},
+    gba::Event,
+};
+
+/// ```text
+///     mov r3, #0x03000000
+/// loop:
+///     add r0, r0, #1
+///     and r1, r0, #0xff
+///     str r0, [r3, r1, lsl #2]
+///     b   loop
+/// ```
+const PROGRAM: [u32; 5] = [
+    0xe3a0_3403,
+    0xe280_0001,
+    0xe200_10ff,
+    0xe783_0101,
+    0xeaff_fffb,
+];
+
+#[test]
+fn detects_divergence_after_load() {
+    let mut gba = util::test_gba(&PROGRAM);
+
+    let mut verifier = StateVerifier::new(60);
+    let mut state = Vec::new();
+    for frame in 0..4 {
+        util::step_until(&mut gba, Event::VBlank);
+        verifier.record(frame, &gba);
+        if frame == 1 {
+            state = gba.save_state();
+        }
+    }
+
+    // Re-simulating deterministically should reproduce the recorded states.
+    gba.load_state(&state).unwrap();
+    for frame in 2..4 {
+        util::step_until(&mut gba, Event::VBlank);
+        verifier.verify(frame, &gba).unwrap();
+    }
+
+    // Simulate nondeterminism by changing memory that the program doesn't touch.
+    gba.load_state(&state).unwrap();
+    gba.write_word(0x0300_7000, 1);
+    util::step_until(&mut gba, Event::VBlank);
+    assert_eq!(
+        verifier.verify(2, &gba),
+        Err(Divergence {
+            frame: 2,
+            components: vec!["iwram"]
+        })
+    );
+
+    // Recording a frame forgets the recorded states of later frames.
+    verifier.record(2, &gba);
+    verifier.verify(2, &gba).unwrap();
+    util::step_until(&mut gba, Event::VBlank);
+    verifier.verify(3, &gba).unwrap();
+}
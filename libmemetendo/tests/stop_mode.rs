@@ -0,0 +1,98 @@
+//! Tests for the STOP low-power mode entered via `HALTCNT`.
+
+mod util;
+
+use libmemetendo::{
+    gba::{Gba, State},
+    keypad::Key,
+    video::{self, Dot},
+};
+
+/// ```text
+///     mov  r3, #0x04000000
+///     mov  r4, #0x03000000
+///     mov  r0, #0x80
+///     strh r0, [r3]             @ DISPCNT: forced blank
+///     mov  r0, #0x4000
+///     orr  r0, r0, #1
+///     add  r1, r3, #0x100
+///     strh r0, [r1, #0x32]      @ KEYCNT: IRQ on A
+///     mov  r0, #0x1000
+///     add  r1, r3, #0x200
+///     strh r0, [r1]             @ IE: keypad
+///     mov  r0, #0x80
+///     add  r1, r3, #0x300
+///     strb r0, [r1, #1]         @ HALTCNT: stop
+/// loop:
+///     add  r2, r2, #1
+///     str  r2, [r4]
+///     b    loop
+/// ```
+const PROGRAM: [u32; 17] = [
+    0xe3a0_3301,
+    0xe3a0_4403,
+    0xe3a0_0080,
+    0xe1c3_00b0,
+    0xe3a0_0901,
+    0xe380_0001,
+    0xe283_1c01,
+    0xe1c1_03b2,
+    0xe3a0_0a01,
+    0xe283_1c02,
+    0xe1c1_00b0,
+    0xe3a0_0080,
+    0xe283_1c03,
+    0xe5c1_0001,
+    0xe282_2001,
+    0xe584_2000,
+    0xeaff_fffc,
+];
+
+#[derive(Default)]
+struct FrameCounter(u32);
+
+impl video::Callback for FrameCounter {
+    fn put_dot(&mut self, _x: u8, _y: u8, _dot: Dot) {}
+
+    fn end_frame(&mut self, _green_swap: bool) {
+        self.0 += 1;
+    }
+
+    fn is_frame_skipping(&self) -> bool {
+        true
+    }
+}
+
+fn step(gba: &mut Gba, video_cb: &mut FrameCounter, steps: u32) {
+    for _ in 0..steps {
+        gba.step(video_cb, &mut libmemetendo::util::audio::NullCallback);
+    }
+}
+
+#[test]
+fn keypad_irq_wakes_from_stop() {
+    let mut gba = util::test_gba(&PROGRAM);
+
+    let mut video_cb = FrameCounter::default();
+    step(&mut gba, &mut video_cb, 100);
+    assert_eq!(gba.haltcnt.0, State::Stopped);
+    let counter = gba.read_word(0x0300_0000);
+
+    // Frames should still be presented while stopped, but the CPU shouldn't run.
+    step(&mut gba, &mut video_cb, 300_000);
+    assert_eq!(gba.haltcnt.0, State::Stopped);
+    assert_eq!(gba.read_word(0x0300_0000), counter);
+    assert!(video_cb.0 > 0);
+    // Input should still be polled, so that keys can wake the system.
+    assert!(gba.keypad.take_poll());
+
+    // Keys that don't satisfy KEYCNT shouldn't wake the system.
+    gba.keypad.set_pressed(Key::B, true);
+    step(&mut gba, &mut video_cb, 100);
+    assert_eq!(gba.haltcnt.0, State::Stopped);
+
+    gba.keypad.set_pressed(Key::A, true);
+    step(&mut gba, &mut video_cb, 100);
+    assert_eq!(gba.haltcnt.0, State::Running);
+    assert_ne!(gba.read_word(0x0300_0000), counter);
+}
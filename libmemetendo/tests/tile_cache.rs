@@ -0,0 +1,133 @@
+//! Tests that BGs are redrawn after writes to what their cached tiles were decoded or looked up
+//! from (VRAM and `BGxCNT`), rather than from stale tiles.
+
+mod util;
+
+use libmemetendo::{
+    gba::{Event, Gba},
+    util::{audio::NullCallback, video::FrameBuffer},
+    video::{self, Dot, HBLANK_DOT},
+};
+
+/// ```text
+///     b    .
+/// ```
+const PROGRAM: [u32; 1] = [0xeaff_fffe];
+
+const RED: u16 = 0x001f;
+const GREEN: u16 = 0x03e0;
+const BLUE: u16 = 0x7c00;
+const BLACK: u16 = 0;
+
+struct Screen(FrameBuffer);
+
+impl video::Callback for Screen {
+    fn put_dot(&mut self, x: u8, y: u8, dot: Dot) {
+        self.0.put_dot(x, y, dot);
+    }
+
+    fn end_frame(&mut self, _green_swap: bool) {}
+
+    fn is_frame_skipping(&self) -> bool {
+        false
+    }
+}
+
+/// The color as put in a `FrameBuffer`.
+fn rgb(color: u16) -> [u8; 3] {
+    let dot = Dot::from(color);
+    [dot.red(), dot.green(), dot.blue()].map(|c| c * 8)
+}
+
+/// Steps to the end of the next frame, returning the colors of the dots at `xs` on the first
+/// scanline.
+fn draw_frame<const N: usize>(gba: &mut Gba, xs: [u8; N]) -> [[u8; 3]; N] {
+    let mut screen = Screen(FrameBuffer::new(0));
+    assert!(gba.step_until(Event::VBlank, &mut screen, &mut NullCallback));
+
+    xs.map(|x| {
+        let i = 3 * usize::from(x);
+        assert!(x < HBLANK_DOT);
+        screen.0 .0[i..i + 3].try_into().unwrap()
+    })
+}
+
+/// Creates a system displaying BG0 in mode 0, with its screen map at `0x0600_0800` filled with
+/// tile 1: red in its left half and green in its right half, with 4bpp colors.
+fn new_gba() -> Gba {
+    let mut gba = util::test_gba(&PROGRAM);
+
+    gba.write_hword(0x0500_0002, RED); // Palette 0, color 1
+    gba.write_hword(0x0500_0004, GREEN); // Palette 0, color 2
+    gba.write_hword(0x0500_0022, BLUE); // Palette 1, color 1
+    for row in 0..8 {
+        gba.write_word(0x0600_0020 + 4 * row, 0x2222_1111);
+    }
+    for entry in 0..0x400 {
+        gba.write_hword(0x0600_0800 + 2 * entry, 1);
+    }
+    gba.write_hword(0x0400_0008, 0x0100); // BG0CNT: screen base block 1
+    gba.write_hword(0x0400_0000, 0x0100); // DISPCNT: mode 0, BG0 on
+
+    gba
+}
+
+#[test]
+fn redraws_after_writes() {
+    let mut gba = new_gba();
+    let xs = [0, 7, 8, 15];
+    assert_eq!(
+        draw_frame(&mut gba, xs),
+        [rgb(RED), rgb(GREEN), rgb(RED), rgb(GREEN)]
+    );
+    // Drawn from the cache.
+    assert_eq!(
+        draw_frame(&mut gba, xs),
+        [rgb(RED), rgb(GREEN), rgb(RED), rgb(GREEN)]
+    );
+
+    // Flip the first tile horizontally, then also use palette 1.
+    gba.write_hword(0x0600_0800, 0x0401);
+    assert_eq!(
+        draw_frame(&mut gba, xs),
+        [rgb(GREEN), rgb(RED), rgb(RED), rgb(GREEN)]
+    );
+    gba.write_hword(0x0600_0800, 0x1401);
+    assert_eq!(draw_frame(&mut gba, xs)[1], rgb(BLUE));
+
+    // Overwrite the tile's dots; every tile using it changes.
+    for row in 0..8 {
+        gba.write_word(0x0600_0020 + 4 * row, 0x2222_2222);
+    }
+    assert_eq!(
+        draw_frame(&mut gba, xs),
+        [rgb(BLACK), rgb(BLACK), rgb(GREEN), rgb(GREEN)]
+    );
+
+    // Palette RAM isn't cached.
+    gba.write_hword(0x0500_0004, BLUE);
+    assert_eq!(draw_frame(&mut gba, xs)[3], rgb(BLUE));
+
+    // Move the screen map to an empty block, leaving only the backdrop.
+    gba.write_hword(0x0400_0008, 0x0200);
+    assert_eq!(draw_frame(&mut gba, xs), [rgb(BLACK); 4]);
+}
+
+#[test]
+fn redraws_after_color_mode_changes() {
+    let mut gba = new_gba();
+    assert_eq!(draw_frame(&mut gba, [0, 7]), [rgb(RED), rgb(GREEN)]);
+
+    // As 8bpp, tile 1 is where 4bpp tiles 2 and 3 are, and its colors index all of palette RAM.
+    gba.write_hword(0x0500_0022, RED); // color 0x11
+    gba.write_hword(0x0500_0044, GREEN); // color 0x22
+    for row in 0..8 {
+        gba.write_word(0x0600_0040 + 4 * row, 0x2222_1111);
+        gba.write_word(0x0600_0060 + 4 * row, 0x2222_1111);
+    }
+    gba.write_hword(0x0400_0008, 0x0180); // BG0CNT: screen base block 1, 8bpp
+    assert_eq!(draw_frame(&mut gba, [0, 1, 2, 3, 4]), {
+        let [red, green] = [rgb(RED), rgb(GREEN)];
+        [red, red, green, green, red]
+    });
+}
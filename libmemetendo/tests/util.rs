@@ -1,8 +1,14 @@
 use std::{fs, path::Path, rc::Rc};
 
 use image::RgbImage;
-use libmemetendo::cart;
+use libmemetendo::{
+    bios,
+    cart::{self, Cartridge},
+    gba::{Event, Gba},
+    util::{audio, video},
+};
 
+#[allow(unused)]
 pub fn read_image(path: impl AsRef<Path>) -> RgbImage {
     image::io::Reader::open(path)
         .expect("failed to open image file")
@@ -11,9 +17,63 @@ pub fn read_image(path: impl AsRef<Path>) -> RgbImage {
         .into_rgb8()
 }
 
+#[allow(unused)]
 pub fn read_cart_rom(path: impl AsRef<Path>) -> cart::Rom {
     cart::Rom::new(Rc::from(
         fs::read(path).expect("failed to read test ROM; did you fetch the submodules?"),
     ))
     .expect("bad ROM size")
 }
+
+/// Hashes the pixels of `image` with FNV-1a, which (unlike `DefaultHasher`) is stable across Rust
+/// versions, so the hashes can be stored alongside tests.
+#[allow(unused)]
+pub fn hash_image(image: &RgbImage) -> u64 {
+    image
+        .as_raw()
+        .iter()
+        .fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+        })
+}
+
+/// BIOS ROM of all zeroes, for tests that skip the BIOS and don't call into it.
+#[allow(unused)]
+pub fn zero_bios_rom() -> bios::Rom {
+    bios::Rom::new(Rc::from(vec![0; 0x4000])).unwrap()
+}
+
+/// Cartridge ROM containing `program`, a list of ARM instructions.
+#[allow(unused)]
+pub fn program_rom(program: &[u32]) -> cart::Rom {
+    cart::Rom::new(program.iter().flat_map(|i| i.to_le_bytes()).collect()).unwrap()
+}
+
+/// Creates a system with `cart` and a zeroed BIOS, reset to start executing the cartridge.
+#[allow(unused)]
+pub fn test_gba_with_cart(cart: Cartridge) -> Gba {
+    let mut gba = Gba::new(zero_bios_rom(), cart);
+    gba.reset(true);
+
+    gba
+}
+
+/// Like `test_gba_with_cart`, but the cartridge's ROM contains `program`; see `program_rom`.
+#[allow(unused)]
+pub fn test_gba(program: &[u32]) -> Gba {
+    test_gba_with_cart(Cartridge::from(program_rom(program)))
+}
+
+/// Steps `gba` `steps` times, ignoring video and audio output.
+#[allow(unused)]
+pub fn step_n(gba: &mut Gba, steps: u32) {
+    for _ in 0..steps {
+        gba.step(&mut video::NullCallback, &mut audio::NullCallback);
+    }
+}
+
+/// Steps `gba` until `event` happens, ignoring video and audio output; see `Gba::step_until`.
+#[allow(unused)]
+pub fn step_until(gba: &mut Gba, event: Event) {
+    assert!(gba.step_until(event, &mut video::NullCallback, &mut audio::NullCallback));
+}
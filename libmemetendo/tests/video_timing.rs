@@ -0,0 +1,90 @@
+//! Tests for the timing of the `DISPSTAT` flags and `VCOUNT`.
+
+mod util;
+
+use libmemetendo::{
+    gba::{Event, Gba},
+    video::{DOT_CYCLES, HBLANK_DOT, HBLANK_FLAG_CYCLE, HORIZ_DOTS, VBLANK_DOT, VERT_DOTS},
+};
+
+/// ```text
+///     b    .
+/// ```
+const PROGRAM: [u32; 1] = [0xeaff_fffe];
+
+/// Returns the VBlank, HBlank and VCOUNT match flags of `DISPSTAT`.
+fn dispstat_flags(gba: &mut Gba) -> (bool, bool, bool) {
+    let bits = gba.read_hword(0x0400_0004);
+    (bits & 1 != 0, bits & 2 != 0, bits & 4 != 0)
+}
+
+#[test]
+fn hblank_flag_is_set_after_hblank_starts() {
+    let mut gba = util::test_gba(&PROGRAM);
+    // Check a drawn scanline and a scanline in the VBlank period.
+    for vcount in [1, VBLANK_DOT + 1] {
+        util::step_until(&mut gba, Event::VCount(vcount));
+        let mut seen_flag = false;
+        while gba.video.position().1 == vcount {
+            let cycle = gba.video.line_cycle();
+            assert!(cycle < HORIZ_DOTS * DOT_CYCLES);
+            let (_, hblank, _) = dispstat_flags(&mut gba);
+            assert_eq!(hblank, cycle >= HBLANK_FLAG_CYCLE, "cycle {cycle}");
+            seen_flag |= hblank;
+            util::step_n(&mut gba, 1);
+        }
+        assert!(seen_flag);
+        // Cleared at the start of the next scanline.
+        assert!(!dispstat_flags(&mut gba).1);
+    }
+
+    // The flag is still clear at the start of the horizontal blanking period.
+    util::step_until(&mut gba, Event::HBlank);
+    assert!(gba.video.position().0 >= HBLANK_DOT.into());
+    assert!(gba.video.line_cycle() < HBLANK_FLAG_CYCLE);
+    assert!(!dispstat_flags(&mut gba).1);
+}
+
+#[test]
+fn vblank_flag_is_not_set_for_the_last_scanline() {
+    let mut gba = util::test_gba(&PROGRAM);
+    util::step_until(&mut gba, Event::VCount(0));
+    for _ in 0..VERT_DOTS {
+        let vcount = gba.video.position().1;
+        let (vblank, _, _) = dispstat_flags(&mut gba);
+        assert_eq!(
+            vblank,
+            (VBLANK_DOT..VERT_DOTS - 1).contains(&vcount),
+            "VCOUNT {vcount}"
+        );
+        util::step_until(&mut gba, Event::Scanline);
+    }
+    assert_eq!(gba.video.position().1, 0);
+}
+
+#[test]
+fn vcount_match_flag() {
+    let mut gba = util::test_gba(&PROGRAM);
+    gba.write_byte(0x0400_0005, 100); // DISPSTAT: VCOUNT target 100
+    for vcount in [99, 100, 101] {
+        util::step_until(&mut gba, Event::VCount(vcount));
+        assert_eq!(dispstat_flags(&mut gba).2, vcount == 100, "VCOUNT {vcount}");
+    }
+
+    // The flag follows changes to the target mid-scanline.
+    gba.write_byte(0x0400_0005, 101);
+    assert!(dispstat_flags(&mut gba).2);
+}
+
+#[test]
+fn vcount_is_read_only() {
+    let mut gba = util::test_gba(&PROGRAM);
+    util::step_until(&mut gba, Event::VCount(50));
+    gba.write_hword(0x0400_0006, 100);
+    assert_eq!(gba.read_hword(0x0400_0006), 50);
+    assert_eq!(gba.video.position().1, 50);
+
+    // Scanlines are still counted from where they were.
+    util::step_until(&mut gba, Event::Scanline);
+    assert_eq!(gba.read_hword(0x0400_0006), 51);
+}
@@ -0,0 +1,123 @@
+//! Tests for stopping the CPU on IO register accesses via `debug::watchpoints::Watchpoints`.
+
+mod util;
+
+use libmemetendo::{
+    debug::{
+        io,
+        trace::{AccessKind, IoAccess},
+        watchpoints::Watchpoint,
+    },
+    gba::Gba,
+};
+
+/// ```text
+///     mov  r4, #0x04000000
+///     mov  r0, #0x80
+///     strh r0, [r4]         @ DISPCNT
+///     add  r5, r4, #0x100
+///     strh r0, [r5, #4]     @ TM1CNT_L
+///     ldrh r1, [r4]         @ DISPCNT
+///     b    .
+/// ```
+const PROGRAM: [u32; 7] = [
+    0xe3a0_4301,
+    0xe3a0_0080,
+    0xe1c4_00b0,
+    0xe284_5c01,
+    0xe1c5_00b4,
+    0xe1d4_10b0,
+    0xeaff_fffe,
+];
+
+fn watch(gba: &mut Gba, regs: &str, on_read: bool, on_write: bool) {
+    for range in io::parse_addr_ranges(regs).unwrap() {
+        gba.debug.watchpoints.add(Watchpoint {
+            range,
+            on_read,
+            on_write,
+        });
+    }
+}
+
+/// Steps until a watchpoint is hit, returning the access, or `None` if none were hit within
+/// `max_steps` steps.
+fn step_until_hit(gba: &mut Gba, max_steps: u32) -> Option<IoAccess> {
+    for _ in 0..max_steps {
+        util::step_n(gba, 1);
+        if let Some(access) = gba.debug.watchpoints.take_hit() {
+            return Some(access);
+        }
+    }
+
+    None
+}
+
+#[test]
+fn stops_after_write() {
+    let mut gba = util::test_gba(&PROGRAM);
+    watch(&mut gba, "DISPCNT", false, true);
+
+    let access = step_until_hit(&mut gba, 100).unwrap();
+    assert_eq!(
+        access,
+        IoAccess {
+            kind: AccessKind::Write,
+            addr: 0x0400_0000,
+            len: 2,
+            value: 0x80,
+            instr_addr: 0x0800_0008,
+        }
+    );
+    assert_eq!(access.register().unwrap().name, "DISPCNT");
+    // Stopped right after the store.
+    assert_eq!(gba.cpu.next_instr_addr(), 0x0800_000c);
+
+    // The read of DISPCNT isn't watched.
+    assert_eq!(step_until_hit(&mut gba, 100), None);
+}
+
+#[test]
+fn selects_registers_by_pattern() {
+    let mut gba = util::test_gba(&PROGRAM);
+    watch(&mut gba, "TM?CNT_L", true, true);
+    assert_eq!(gba.debug.watchpoints.iter().count(), 4);
+
+    let access = step_until_hit(&mut gba, 100).unwrap();
+    assert_eq!(access.register().unwrap().name, "TM1CNT_L");
+    assert_eq!(access.instr_addr, 0x0800_0010);
+    assert_eq!(step_until_hit(&mut gba, 100), None);
+}
+
+#[test]
+fn stops_after_read() {
+    let mut gba = util::test_gba(&PROGRAM);
+    watch(&mut gba, "dispcnt", true, false);
+
+    let access = step_until_hit(&mut gba, 100).unwrap();
+    assert_eq!(access.kind, AccessKind::Read);
+    assert_eq!(access.value, 0x80);
+    assert_eq!(access.instr_addr, 0x0800_0014);
+    assert_eq!(gba.cpu.reg.r[1], 0x80);
+}
+
+#[test]
+fn parses_register_patterns() {
+    let ranges = io::parse_addr_ranges("TM*").unwrap();
+    assert_eq!(ranges.len(), 8);
+    assert_eq!(ranges[0], 0x0400_0100..=0x0400_0101);
+    assert_eq!(
+        io::parse_addr_ranges("dma?cnt_h").unwrap(),
+        [
+            0x0400_00ba..=0x0400_00bb,
+            0x0400_00c6..=0x0400_00c7,
+            0x0400_00d2..=0x0400_00d3,
+            0x0400_00de..=0x0400_00df
+        ]
+    );
+    assert_eq!(
+        io::parse_addr_ranges("TM0CNT").unwrap(),
+        [0x0400_0100..=0x0400_0103]
+    );
+    assert_eq!(io::parse_addr_ranges("NOPE*"), None);
+}
@@ -1,27 +1,156 @@
-use std::mem::size_of;
+use std::{
+    mem::size_of,
+    sync::{
+        atomic::{AtomicI16, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+};
 
-use libmemetendo::audio::{self, SAMPLE_FREQUENCY};
+use libmemetendo::{
+    audio::{self, SAMPLE_FREQUENCY},
+    util::audio::SoundDetector,
+};
 use log::info;
 use sdl2::{
-    audio::{AudioQueue, AudioSpec, AudioSpecDesired},
+    audio::{AudioCallback, AudioDevice, AudioQueue, AudioSpec, AudioSpecDesired},
     AudioSubsystem,
 };
 
+/// How samples are given to SDL.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Backend {
+    /// Samples are queued by the main thread after every frame.
+    Queue,
+    /// Samples are pulled by SDL's audio callback from a lock-free ring buffer as they're needed,
+    /// so audio isn't queued in bursts after skipped frames.
+    Callback,
+}
+
+/// Circular buffer of resampled samples waiting to be queued to SDL.
+struct SampleBuffer {
+    samples: Box<[i16]>,
+    start_idx: usize,
+    len: usize,
+}
+
+impl SampleBuffer {
+    fn new(len: usize) -> Self {
+        Self {
+            samples: vec![0; len].into_boxed_slice(),
+            start_idx: 0,
+            len: 0,
+        }
+    }
+
+    fn push(&mut self, value: i16) {
+        if self.len < self.samples.len() {
+            let i = (self.start_idx + self.len) % self.samples.len();
+            self.samples[i] = value;
+            self.len += 1;
+        } else {
+            // Overwrite the oldest value.
+            self.samples[self.start_idx] = value;
+            self.start_idx += 1;
+            self.start_idx %= self.samples.len();
+        }
+    }
+}
+
+/// Lock-free ring buffer of resampled samples, written by the emulation thread and read by SDL's
+/// audio callback. There must only be one writer and one reader.
+struct RingBuffer {
+    samples: Box<[AtomicI16]>,
+    /// Total amount of values ever read; the index of the next value to read is this modulo the
+    /// buffer's length.
+    read_count: AtomicUsize,
+    /// Total amount of values ever written.
+    write_count: AtomicUsize,
+}
+
+impl RingBuffer {
+    fn new(len: usize) -> Self {
+        Self {
+            samples: (0..len).map(|_| AtomicI16::new(0)).collect(),
+            read_count: AtomicUsize::new(0),
+            write_count: AtomicUsize::new(0),
+        }
+    }
+
+    fn len(&self) -> usize {
+        let read_count = self.read_count.load(Ordering::Acquire);
+        self.write_count
+            .load(Ordering::Acquire)
+            .wrapping_sub(read_count)
+    }
+
+    /// Writes as many of `values` as there's room for. Unlike `SampleBuffer`, the newest values
+    /// are dropped if the buffer is full, as the oldest may be being read.
+    fn write(&self, values: &[i16]) {
+        let write_count = self.write_count.load(Ordering::Relaxed);
+        let free_len =
+            self.samples.len() - write_count.wrapping_sub(self.read_count.load(Ordering::Acquire));
+        let count = values.len().min(free_len);
+        for (i, &value) in values[..count].iter().enumerate() {
+            let idx = write_count.wrapping_add(i) % self.samples.len();
+            self.samples[idx].store(value, Ordering::Relaxed);
+        }
+
+        self.write_count
+            .store(write_count.wrapping_add(count), Ordering::Release);
+    }
+
+    /// Reads values into `out` until it's full or the buffer is empty, returning the amount read.
+    fn read(&self, out: &mut [i16]) -> usize {
+        let read_count = self.read_count.load(Ordering::Relaxed);
+        let len = self
+            .write_count
+            .load(Ordering::Acquire)
+            .wrapping_sub(read_count);
+        let count = out.len().min(len);
+        for (i, value) in out[..count].iter_mut().enumerate() {
+            let idx = read_count.wrapping_add(i) % self.samples.len();
+            *value = self.samples[idx].load(Ordering::Relaxed);
+        }
+
+        self.read_count
+            .store(read_count.wrapping_add(count), Ordering::Release);
+        count
+    }
+}
+
+/// SDL audio callback for `Backend::Callback`.
+struct PullCallback(Arc<RingBuffer>);
+
+impl AudioCallback for PullCallback {
+    type Channel = i16;
+
+    fn callback(&mut self, out: &mut [i16]) {
+        let count = self.0.read(out);
+        // Emulation fell behind; output silence for the rest.
+        out[count..].fill(0);
+    }
+}
+
+/// Where resampled samples are written for output.
+enum Output {
+    Queue(Arc<Mutex<SampleBuffer>>),
+    Callback(Arc<RingBuffer>),
+}
+
 struct Callback {
     spec: AudioSpec,
     freq_counter: u32,
     freq_counter_accum: u32,
     sample_accum: (i32, i32),
     accum_extra_sample: bool,
-
-    // Circular sample buffer.
-    samples: Box<[i16]>,
-    samples_start_idx: usize,
-    samples_len: usize,
+    /// Samples not yet moved to `Self::output`, to avoid locking it for every sample.
+    pending: Vec<i16>,
+    output: Output,
+    sound: SoundDetector,
 }
 
 impl Callback {
-    fn new(spec: AudioSpec) -> Result<Self, String> {
+    fn new(spec: AudioSpec, output: Output) -> Result<Self, String> {
         info!("{spec:?}");
 
         if spec.channels > 2 {
@@ -45,21 +174,27 @@ impl Callback {
             freq_counter_accum: 0,
             sample_accum: (0, 0),
             accum_extra_sample: false,
-            // Make the buffer twice the size of SDL's sample buffer. This gives us some leg room
-            // in case we're writing samples slightly quicker than they're consumed.
-            samples: vec![0; 2 * Self::samples_len(&spec)].into_boxed_slice(),
-            samples_start_idx: 0,
-            samples_len: 0,
+            pending: Vec::new(),
+            output,
+            sound: SoundDetector::default(),
         })
     }
 
     fn samples_len(spec: &AudioSpec) -> usize {
         usize::try_from(spec.size).unwrap() / size_of::<i16>()
     }
+
+    /// Length of the buffer for samples waiting to be output.
+    fn output_len(spec: &AudioSpec) -> usize {
+        // Make the buffer twice the size of SDL's sample buffer. This gives us some leg room in
+        // case we're writing samples slightly quicker than they're consumed.
+        2 * Self::samples_len(spec)
+    }
 }
 
 impl audio::Callback for Callback {
     fn push_sample(&mut self, sample: (i16, i16)) {
+        self.sound.push_sample(sample);
         self.sample_accum.0 += i32::from(sample.0);
         self.sample_accum.1 += i32::from(sample.1);
 
@@ -84,94 +219,199 @@ impl audio::Callback for Callback {
             self.freq_counter_accum -= freq;
         }
 
-        let mut push = |value| {
-            if self.samples_len < self.samples.len() {
-                let i = (self.samples_start_idx + self.samples_len) % self.samples.len();
-                self.samples[i] = value;
-                self.samples_len += 1;
-            } else {
-                // Overwrite the oldest value.
-                self.samples[self.samples_start_idx] = value;
-                self.samples_start_idx += 1;
-                self.samples_start_idx %= self.samples.len();
-            }
-        };
-
         if self.spec.channels > 1 {
-            push(sample.0);
-            push(sample.1);
+            self.pending.push(sample.0);
+            self.pending.push(sample.1);
         } else {
-            push(sample.0 / 2 + sample.1 / 2);
+            self.pending.push(sample.0 / 2 + sample.1 / 2);
+        }
+    }
+}
+
+/// Resamples audio for output by `Audio`. Unlike `Audio`, this can be sent to the emulation
+/// thread.
+#[derive(Default)]
+pub struct Resampler(Option<Callback>);
+
+impl Resampler {
+    /// Makes the samples pushed since the last call available to `Audio::queue_samples`, or to
+    /// SDL's audio callback.
+    pub fn flush(&mut self) {
+        let Some(cb) = self.0.as_mut() else {
+            return;
+        };
+
+        match &cb.output {
+            Output::Queue(samples) => {
+                let mut samples = samples.lock().unwrap();
+                for value in cb.pending.drain(..) {
+                    samples.push(value);
+                }
+            }
+            Output::Callback(samples) => {
+                samples.write(&cb.pending);
+                cb.pending.clear();
+            }
+        }
+    }
+
+    /// Returns whether any sound was output since the last call.
+    pub fn take_audible(&mut self) -> bool {
+        self.0.as_mut().is_some_and(|cb| cb.sound.take_audible())
+    }
+}
+
+impl audio::Callback for Resampler {
+    fn push_sample(&mut self, sample: (i16, i16)) {
+        if let Some(cb) = self.0.as_mut() {
+            cb.push_sample(sample);
+        }
+    }
+
+    fn push_samples(&mut self, samples: &[(i16, i16)]) {
+        if let Some(cb) = self.0.as_mut() {
+            cb.push_samples(samples);
+        }
+    }
+}
+
+enum Device {
+    Queue(AudioQueue<i16>, Arc<Mutex<SampleBuffer>>),
+    Callback(AudioDevice<PullCallback>, Arc<RingBuffer>),
+}
+
+impl Device {
+    fn spec(&self) -> &AudioSpec {
+        match self {
+            Self::Queue(queue, _) => queue.spec(),
+            Self::Callback(device, _) => device.spec(),
+        }
+    }
+
+    fn resume(&self) {
+        match self {
+            Self::Queue(queue, _) => queue.resume(),
+            Self::Callback(device, _) => device.resume(),
+        }
+    }
+
+    fn pause(&self) {
+        match self {
+            Self::Queue(queue, _) => {
+                queue.pause();
+                // Don't play stale samples when resumed.
+                queue.clear();
+            }
+            Self::Callback(device, _) => device.pause(),
         }
     }
 }
 
 #[derive(Default)]
-pub struct Audio(Option<(AudioQueue<i16>, Callback)>);
+pub struct Audio(Option<Device>);
 
 impl Audio {
+    /// Opens an SDL audio device using `Backend`, returning it along with the `Resampler` that
+    /// provides its samples.
     #[expect(clippy::result_large_err)]
     pub fn new(
-        params: Option<(&AudioSubsystem, AudioSpecDesired)>,
-    ) -> Result<Self, (String, Self)> {
-        let Some((sdl_audio, spec)) = params else {
-            return Ok(Self(None));
+        params: Option<(&AudioSubsystem, AudioSpecDesired, Backend)>,
+    ) -> Result<(Self, Resampler), (String, Self, Resampler)> {
+        let Some((sdl_audio, spec, backend)) = params else {
+            return Ok((Self(None), Resampler(None)));
         };
+        let fail = |e| (e, Self(None), Resampler(None));
 
-        let queue = sdl_audio.open_queue(None, &spec).map_err(|e| {
-            (
-                format!("failed to create sdl2 audio queue: {e}"),
-                Self(None),
-            )
-        })?;
+        let (device, output) = match backend {
+            Backend::Queue => {
+                let queue = sdl_audio
+                    .open_queue(None, &spec)
+                    .map_err(|e| fail(format!("failed to create sdl2 audio queue: {e}")))?;
+                let samples = Arc::new(Mutex::new(SampleBuffer::new(Callback::output_len(
+                    queue.spec(),
+                ))));
+                let output = Output::Queue(Arc::clone(&samples));
+                (Device::Queue(queue, samples), output)
+            }
+            Backend::Callback => {
+                let mut ring = None;
+                let device = sdl_audio
+                    .open_playback(None, &spec, |spec| {
+                        let samples = Arc::new(RingBuffer::new(Callback::output_len(&spec)));
+                        ring = Some(Arc::clone(&samples));
+                        PullCallback(samples)
+                    })
+                    .map_err(|e| fail(format!("failed to create sdl2 audio device: {e}")))?;
+                let samples = ring.unwrap();
+                let output = Output::Callback(Arc::clone(&samples));
+                (Device::Callback(device, samples), output)
+            }
+        };
 
-        Callback::new(*queue.spec())
-            .map(|cb| {
-                queue.resume();
-                Self(Some((queue, cb)))
-            })
-            .map_err(|e| (format!("failed to create audio callback: {e}"), Self(None)))
+        let cb = Callback::new(*device.spec(), output)
+            .map_err(|e| fail(format!("failed to create audio callback: {e}")))?;
+        device.resume();
+        Ok((Self(Some(device)), Resampler(Some(cb))))
     }
 
+    /// Pauses or resumes audio output, such as while emulation is paused.
+    pub fn set_paused(&self, paused: bool) {
+        if let Some(device) = &self.0 {
+            if paused {
+                device.pause();
+            } else {
+                device.resume();
+            }
+        }
+    }
+
+    /// Returns the amount of buffered audio as a multiple of SDL's audio buffer size, if audio is
+    /// enabled.
+    #[expect(clippy::cast_precision_loss)] // Only used for display purposes.
+    pub fn queue_depth(&self) -> Option<f32> {
+        let depth = match self.0.as_ref()? {
+            Device::Queue(queue, _) => queue.size() as f32 / queue.spec().size as f32,
+            Device::Callback(device, samples) => {
+                samples.len() as f32 / Callback::samples_len(device.spec()) as f32
+            }
+        };
+        Some(depth)
+    }
+
+    /// Queues the samples made available by `Resampler::flush`. Does nothing for
+    /// `Backend::Callback`, where SDL pulls the samples itself.
     pub fn queue_samples(&mut self) -> Result<(), String> {
-        let Some((queue, cb)) = self.0.as_mut() else {
+        let Some(Device::Queue(queue, samples)) = self.0.as_mut() else {
             return Ok(());
         };
+        let mut samples = samples.lock().unwrap();
 
         // Limit the max amount of samples we can have enqueued, otherwise we risk having the
         // audio drift behind if the queue isn't being consumed fast enough.
-        let count = cb
-            .samples_len
-            .min(Callback::samples_len(&cb.spec).saturating_sub(queue.size().try_into().unwrap()));
+        let count = samples.len.min(
+            Callback::samples_len(queue.spec()).saturating_sub(queue.size().try_into().unwrap()),
+        );
         if count == 0 {
             return Ok(());
         }
 
         let try_queue = || {
-            if cb.samples_start_idx + count <= cb.samples.len() {
-                queue.queue_audio(&cb.samples[cb.samples_start_idx..][..count])?;
+            if samples.start_idx + count <= samples.samples.len() {
+                queue.queue_audio(&samples.samples[samples.start_idx..][..count])?;
             } else {
                 // The circular buffer wrapped around, so write in two parts.
-                let first_part = &cb.samples[cb.samples_start_idx..];
+                let first_part = &samples.samples[samples.start_idx..];
                 queue.queue_audio(first_part)?;
-                queue.queue_audio(&cb.samples[..count - first_part.len()])?;
+                queue.queue_audio(&samples.samples[..count - first_part.len()])?;
             }
 
             Ok(())
         };
         let result = try_queue();
-        cb.samples_start_idx += count;
-        cb.samples_start_idx %= cb.samples.len();
-        cb.samples_len -= count;
+        samples.start_idx += count;
+        samples.start_idx %= samples.samples.len();
+        samples.len -= count;
 
         result
     }
 }
-
-impl audio::Callback for Audio {
-    fn push_sample(&mut self, sample: (i16, i16)) {
-        if let Some((_, cb)) = self.0.as_mut() {
-            cb.push_sample(sample);
-        }
-    }
-}
@@ -0,0 +1,330 @@
+use std::{
+    io::{self, BufRead},
+    ops::RangeInclusive,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, RecvTimeoutError},
+    },
+    thread,
+    time::Duration,
+};
+
+use anyhow::{anyhow, bail, Context, Result};
+use libmemetendo::{
+    arm7tdmi::reg::OperationState,
+    cheat::{Filter, Search, Width},
+    debug::{breakpoints::Breakpoint, disasm, expr::Expr, io::Register, watchpoints::Watchpoint},
+    gba::Gba,
+};
+use log::error;
+
+const HELP: &str = "\
+commands:
+  search new [8|16|32]  start a cheat search for values of the given bit width (default: 8)
+  search eq|ne VALUE    keep values equal (or not equal) to VALUE
+  search changed|same   keep values that changed (or didn't) since the last search command
+  search inc|dec [N]    keep values that increased (or decreased), optionally by exactly N
+  search list [COUNT]   list up to COUNT candidates and their values (default: 20)
+  break ADDR [if COND]  break before executing the instruction at ADDR, if COND is non-zero
+  breaks                list breakpoints
+  delete N              delete breakpoint N
+  iobreak read|write|access REGS
+                        break after IO registers REGS are accessed, given as comma-separated
+                        names, patterns or addresses; e.g: DISPCNT, TM?CNT_L or 0x4000000-0x4000003
+  iobreaks              list IO watchpoints
+  iodelete N            delete IO watchpoint N
+  watch EXPR            show the value of EXPR whenever execution breaks
+  unwatch N             delete watch N
+  print EXPR            show the value of EXPR
+  disasm [arm|thumb] [ADDR [COUNT]]
+                        disassemble COUNT instructions (default: 10) from ADDR (default: the next
+                        instruction), in the CPU's current instruction set unless one is given
+  continue              resume execution after a breakpoint or IO watchpoint is hit
+  help                  show this help
+
+expressions may use registers (r0-r15, sp, lr, pc, cpsr, spsr), symbols and IO register names
+(as addresses), memory reads
+([ADDR], [ADDR]:u8, [ADDR]:u16), comparisons, and arithmetic, bitwise and logical operators;
+e.g: break 0x8000100 if r0 == 0xcafe && [0x3001234]:u16 != 0";
+
+/// Debug console that reads commands from stdin, such as for driving cheat searches. Commands are
+/// read on a separate thread, and ran between frames by `Self::run_pending`, or while execution is
+/// stopped at a breakpoint or IO watchpoint by `Self::run_if_stopped`.
+pub struct Console {
+    lines: Receiver<String>,
+    search: Option<Search>,
+    /// Watched expressions, along with their source text.
+    watches: Vec<(String, Expr)>,
+}
+
+impl Console {
+    pub fn spawn() -> Self {
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || {
+            for line in io::stdin().lock().lines() {
+                match line {
+                    Ok(line) => {
+                        if tx.send(line).is_err() {
+                            break;
+                        }
+                    }
+                    Err(e) => {
+                        error!("failed to read console input: {e}");
+                        break;
+                    }
+                }
+            }
+        });
+        println!("debug console enabled; type \"help\" for a list of commands");
+
+        Self {
+            lines: rx,
+            search: None,
+            watches: Vec::new(),
+        }
+    }
+
+    /// Runs the commands entered since the last call.
+    pub fn run_pending(&mut self, gba: &mut Gba) {
+        while let Ok(line) = self.lines.try_recv() {
+            if let Err(e) = self.run(&line, gba) {
+                println!("error: {e:#}");
+            }
+        }
+    }
+
+    /// If a breakpoint or IO watchpoint was hit, reports it, then runs commands until "continue"
+    /// is entered, or `quit` is set.
+    pub fn run_if_stopped(&mut self, gba: &mut Gba, quit: &AtomicBool) {
+        if let Some(addr) = gba.debug.breakpoints.take_hit() {
+            println!("breakpoint hit at {}", gba.debug.symbols.describe(addr));
+        } else if let Some(access) = gba.debug.watchpoints.take_hit() {
+            let instr_addr = gba.debug.symbols.describe(access.instr_addr);
+            println!("IO watchpoint hit by {access} at {instr_addr}");
+        } else {
+            return;
+        }
+        for (i, (src, expr)) in self.watches.iter().enumerate() {
+            let value = expr.eval(gba);
+            println!("  watch {i}: {src} = {value:#x} ({value})");
+        }
+
+        while !quit.load(Ordering::Relaxed) {
+            let line = match self.lines.recv_timeout(Duration::from_millis(100)) {
+                Ok(line) => line,
+                Err(RecvTimeoutError::Timeout) => continue,
+                Err(RecvTimeoutError::Disconnected) => break,
+            };
+            if matches!(line.trim(), "continue" | "c") {
+                break;
+            }
+            if let Err(e) = self.run(&line, gba) {
+                println!("error: {e:#}");
+            }
+        }
+    }
+
+    fn run(&mut self, line: &str, gba: &mut Gba) -> Result<()> {
+        let args: Vec<_> = line.split_whitespace().collect();
+        match args.as_slice() {
+            [] => {}
+            ["help"] => println!("{HELP}"),
+            ["break", ..] => {
+                let args = line.trim_start()["break".len()..].trim();
+                let (addr, condition) = match args.split_once(" if ") {
+                    Some((addr, condition)) => (addr, Some(parse_expr(gba, condition)?)),
+                    None => (args, None),
+                };
+                let addr = parse_expr(gba, addr)?.eval(gba);
+                gba.debug.breakpoints.add(Breakpoint { addr, condition });
+                println!("added breakpoint at {}", gba.debug.symbols.describe(addr));
+            }
+            ["breaks"] => {
+                for (i, bp) in gba.debug.breakpoints.iter().enumerate() {
+                    let conditional = if bp.condition.is_some() {
+                        " (conditional)"
+                    } else {
+                        ""
+                    };
+                    println!("{i}: {}{conditional}", gba.debug.symbols.describe(bp.addr));
+                }
+            }
+            ["delete", idx] => {
+                let idx = idx.parse().context("invalid breakpoint number")?;
+                if gba.debug.breakpoints.remove(idx).is_none() {
+                    bail!("no breakpoint {idx}");
+                }
+            }
+            ["iobreak", kind, regs] => add_io_watchpoints(gba, kind, regs)?,
+            ["iobreaks"] => list_io_watchpoints(gba),
+            ["iodelete", idx] => {
+                let idx = idx.parse().context("invalid IO watchpoint number")?;
+                if gba.debug.watchpoints.remove(idx).is_none() {
+                    bail!("no IO watchpoint {idx}");
+                }
+            }
+            ["watch", ..] => {
+                let src = line.trim_start()["watch".len()..].trim();
+                let expr = parse_expr(gba, src)?;
+                self.watches.push((src.to_string(), expr));
+            }
+            ["unwatch", idx] => {
+                let idx: usize = idx.parse().context("invalid watch number")?;
+                if idx >= self.watches.len() {
+                    bail!("no watch {idx}");
+                }
+                self.watches.remove(idx);
+            }
+            ["print", ..] => {
+                let value = parse_expr(gba, line.trim_start()["print".len()..].trim())?.eval(gba);
+                println!("{value:#x} ({value})");
+            }
+            ["disasm", args @ ..] => disassemble(gba, args)?,
+            ["continue" | "c"] => bail!("not stopped at a breakpoint"),
+            ["search", "new"] => self.new_search(gba, Width::Byte),
+            ["search", "new", bits] => {
+                let width = match *bits {
+                    "8" => Width::Byte,
+                    "16" => Width::Hword,
+                    "32" => Width::Word,
+                    _ => bail!("invalid bit width: {bits}"),
+                };
+                self.new_search(gba, width);
+            }
+            ["search", "list"] => self.list_search(20)?,
+            ["search", "list", count] => {
+                self.list_search(count.parse().context("invalid count")?)?;
+            }
+            ["search", args @ ..] => {
+                let filter = parse_filter(args)?;
+                let search = self.search.as_mut().ok_or_else(no_search_error)?;
+                search.filter(gba, filter)?;
+                println!("{} candidates remaining", search.candidates().len());
+            }
+            _ => bail!("unknown command: {line} (type \"help\" for a list of commands)"),
+        }
+
+        Ok(())
+    }
+
+    fn new_search(&mut self, gba: &Gba, width: Width) {
+        let search = Search::new(gba, width);
+        println!(
+            "started new search with {} candidates",
+            search.candidates().len()
+        );
+        self.search = Some(search);
+    }
+
+    fn list_search(&self, count: usize) -> Result<()> {
+        let search = self.search.as_ref().ok_or_else(no_search_error)?;
+        let hex_digits = 2 * usize::try_from(search.width().bytes()).unwrap();
+        for (addr, value) in search.results().take(count) {
+            println!(
+                "{addr:#010x}: {value:#0width$x} ({value})",
+                width = hex_digits + 2
+            );
+        }
+        if search.candidates().len() > count {
+            println!("... and {} more", search.candidates().len() - count);
+        }
+
+        Ok(())
+    }
+}
+
+fn add_io_watchpoints(gba: &mut Gba, kind: &str, regs: &str) -> Result<()> {
+    let (on_read, on_write) = match kind {
+        "read" => (true, false),
+        "write" => (false, true),
+        "access" => (true, true),
+        _ => bail!("invalid access kind: {kind}"),
+    };
+    for range in crate::parse_io_ranges(regs)? {
+        println!("added IO watchpoint on {}", describe_io_range(&range));
+        gba.debug.watchpoints.add(Watchpoint {
+            range,
+            on_read,
+            on_write,
+        });
+    }
+
+    Ok(())
+}
+
+fn list_io_watchpoints(gba: &Gba) {
+    for (i, wp) in gba.debug.watchpoints.iter().enumerate() {
+        let kind = match (wp.on_read, wp.on_write) {
+            (true, true) => "access",
+            (true, false) => "read",
+            _ => "write",
+        };
+        println!("{i}: {kind} {}", describe_io_range(&wp.range));
+    }
+}
+
+/// Describes `range` by the name of the IO register it covers, if it's exactly one register.
+fn describe_io_range(range: &RangeInclusive<u32>) -> String {
+    match Register::find(*range.start()) {
+        Some(reg) if reg.addr_range() == *range => reg.name.to_string(),
+        _ => format!("{:#010x}-{:#010x}", range.start(), range.end()),
+    }
+}
+
+fn disassemble(gba: &mut Gba, args: &[&str]) -> Result<()> {
+    let (thumb, args) = match args {
+        ["arm", args @ ..] => (false, args),
+        ["thumb", args @ ..] => (true, args),
+        _ => (gba.cpu.reg.cpsr.state() == OperationState::Thumb, args),
+    };
+    let (addr, count) = match args {
+        [] => (gba.cpu.next_instr_addr(), 10),
+        [addr] => (parse_expr(gba, addr)?.eval(gba), 10),
+        [addr, count] => (
+            parse_expr(gba, addr)?.eval(gba),
+            count.parse().context("invalid count")?,
+        ),
+        _ => bail!("invalid disasm command (type \"help\" for a list of commands)"),
+    };
+
+    let mut addr = addr & if thumb { !1 } else { !3 };
+    for _ in 0..count {
+        let instr = disasm::disassemble(gba, addr, thumb);
+        println!("{}", disasm::listing_line(&instr, &gba.debug.symbols));
+        addr = addr.wrapping_add(instr.len);
+    }
+
+    Ok(())
+}
+
+fn parse_expr(gba: &Gba, s: &str) -> Result<Expr> {
+    Ok(Expr::parse(s, &gba.debug.symbols)?)
+}
+
+fn no_search_error() -> anyhow::Error {
+    anyhow!("no search in progress; start one with \"search new\"")
+}
+
+fn parse_filter(args: &[&str]) -> Result<Filter> {
+    Ok(match args {
+        ["eq", value] => Filter::Equal(parse_value(value)?),
+        ["ne", value] => Filter::NotEqual(parse_value(value)?),
+        ["changed"] => Filter::Changed,
+        ["same"] => Filter::Unchanged,
+        ["inc"] => Filter::Increased,
+        ["dec"] => Filter::Decreased,
+        ["inc", amount] => Filter::IncreasedBy(parse_value(amount)?),
+        ["dec", amount] => Filter::DecreasedBy(parse_value(amount)?),
+        _ => bail!("invalid search command (type \"help\" for a list of commands)"),
+    })
+}
+
+/// Parses a decimal or "0x"-prefixed hexadecimal value.
+fn parse_value(s: &str) -> Result<u32> {
+    if let Some(hex) = s.strip_prefix("0x") {
+        u32::from_str_radix(hex, 16)
+    } else {
+        s.parse()
+    }
+    .with_context(|| format!("invalid value: {s}"))
+}
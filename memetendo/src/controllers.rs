@@ -0,0 +1,90 @@
+use log::{error, info};
+use sdl2::{
+    controller::{Axis, Button, GameController},
+    event::Event,
+    GameControllerSubsystem,
+};
+
+/// Stick deflection past which it's treated as pressing the d-pad; about half way.
+const STICK_THRESHOLD: i16 = i16::MAX / 2;
+
+/// Game controllers that are currently connected.
+pub struct Controllers {
+    sdl_controller: Option<GameControllerSubsystem>,
+    open: Vec<GameController>,
+}
+
+impl Controllers {
+    /// Opens the controllers that are already connected.
+    pub fn new(sdl_controller: Option<GameControllerSubsystem>) -> Self {
+        let mut controllers = Self {
+            sdl_controller,
+            open: Vec::new(),
+        };
+        let count = controllers
+            .sdl_controller
+            .as_ref()
+            .map_or(Ok(0), GameControllerSubsystem::num_joysticks)
+            .unwrap_or(0);
+        for joystick_index in 0..count {
+            controllers.open(joystick_index);
+        }
+
+        controllers
+    }
+
+    pub fn handle_event(&mut self, event: &Event) {
+        match *event {
+            Event::ControllerDeviceAdded { which, .. } => self.open(which),
+            Event::ControllerDeviceRemoved { which, .. } => self.open.retain(|controller| {
+                let keep = controller.instance_id() != which;
+                if !keep {
+                    info!("controller disconnected: {}", controller.name());
+                }
+                keep
+            }),
+            _ => {}
+        }
+    }
+
+    fn open(&mut self, joystick_index: u32) {
+        let Some(ref sdl_controller) = self.sdl_controller else {
+            return;
+        };
+        if !sdl_controller.is_game_controller(joystick_index) {
+            return;
+        }
+
+        match sdl_controller.open(joystick_index) {
+            // SDL also reports controllers that were connected before we opened them as added.
+            Ok(controller) if self.is_open(controller.instance_id()) => {}
+            Ok(controller) => {
+                info!("controller connected: {}", controller.name());
+                self.open.push(controller);
+            }
+            Err(e) => error!("failed to open controller: {e}"),
+        }
+    }
+
+    fn is_open(&self, instance_id: u32) -> bool {
+        self.open
+            .iter()
+            .any(|controller| controller.instance_id() == instance_id)
+    }
+
+    /// Returns true if `button` is held on any controller. The left stick also counts as the
+    /// d-pad.
+    pub fn is_held(&self, button: Button) -> bool {
+        let stick = |controller: &GameController| match button {
+            Button::DPadUp => controller.axis(Axis::LeftY) < -STICK_THRESHOLD,
+            Button::DPadDown => controller.axis(Axis::LeftY) > STICK_THRESHOLD,
+            Button::DPadLeft => controller.axis(Axis::LeftX) < -STICK_THRESHOLD,
+            Button::DPadRight => controller.axis(Axis::LeftX) > STICK_THRESHOLD,
+            _ => false,
+        };
+
+        self.open
+            .iter()
+            .any(|controller| controller.button(button) || stick(controller))
+    }
+}
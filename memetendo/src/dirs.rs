@@ -0,0 +1,143 @@
+use std::{
+    env,
+    ffi::OsString,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use libmemetendo::storage;
+use log::info;
+
+/// Directories for files written by the frontend, like cartridge backups, save states and the
+/// list of recently played games.
+pub struct Dirs {
+    data: Option<PathBuf>,
+    save: Option<PathBuf>,
+    state: Option<PathBuf>,
+}
+
+impl Dirs {
+    /// Uses the given directories, defaulting to "saves" and "states" directories within the
+    /// user's data directory (e.g: `$XDG_DATA_HOME/memetendo` or `%APPDATA%\memetendo`). In
+    /// portable mode, the data directory is beside the executable instead.
+    ///
+    /// If no data directory can be found, files are kept beside the cartridge ROM instead.
+    pub fn new(save: Option<PathBuf>, state: Option<PathBuf>, portable: bool) -> Result<Self> {
+        let data = if portable {
+            let exe_path = env::current_exe().context("failed to get executable path")?;
+            exe_path.parent().map(Path::to_path_buf)
+        } else {
+            data_dir()
+        };
+
+        Ok(Self {
+            save: save.or_else(|| data.as_ref().map(|dir| dir.join("saves"))),
+            state: state.or_else(|| data.as_ref().map(|dir| dir.join("states"))),
+            data,
+        })
+    }
+
+    /// Returns the files for the saves of the game in `cart_path`.
+    pub fn game_files(&self, cart_path: &Path) -> GameFiles {
+        GameFiles {
+            backup: cart_file(self.save.as_deref(), cart_path, "sav"),
+            old_backup: cart_path.with_extension("sav"),
+            state: cart_file(self.state.as_deref(), cart_path, "state"),
+        }
+    }
+
+    /// Returns the path of the list of recently played games, if there's a data directory.
+    pub fn recent_file(&self) -> Option<PathBuf> {
+        self.data.as_ref().map(|dir| dir.join("recent.toml"))
+    }
+
+    /// Returns the path of the file keeping each game's input macro, if there's a data directory.
+    pub fn macros_file(&self) -> Option<PathBuf> {
+        self.data.as_ref().map(|dir| dir.join("macros.toml"))
+    }
+}
+
+/// Stores a game's saves as files.
+#[derive(Clone, Debug)]
+pub struct GameFiles {
+    pub backup: PathBuf,
+    /// Backup file beside the cartridge ROM, where they used to always be kept. It's read if
+    /// there's no file at `Self::backup`.
+    pub old_backup: PathBuf,
+    pub state: PathBuf,
+}
+
+impl GameFiles {
+    fn path(&self, name: &str) -> io::Result<&Path> {
+        match name {
+            storage::BACKUP => Ok(&self.backup),
+            storage::STATE => Ok(&self.state),
+            _ => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!("no file for {name}"),
+            )),
+        }
+    }
+}
+
+impl storage::Backend for GameFiles {
+    fn read(&mut self, name: &str) -> io::Result<Option<Vec<u8>>> {
+        let mut path = self.path(name)?;
+        if name == storage::BACKUP && !path.exists() && self.old_backup.is_file() {
+            info!(
+                "using cart backup file beside the ROM: {}",
+                self.old_backup.to_string_lossy()
+            );
+            path = &self.old_backup;
+        }
+
+        info!("reading {name} file: {}", path.to_string_lossy());
+        match fs::read(path) {
+            Ok(buf) => Ok(Some(buf)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn write(&mut self, name: &str, data: &[u8]) -> io::Result<()> {
+        let path = self.path(name)?;
+        info!("writing {name} file: {}", path.to_string_lossy());
+        create_parent(path)?;
+        fs::write(path, data)
+    }
+}
+
+/// Returns the path of a file in `dir` (or beside the cartridge ROM) named after the cartridge
+/// ROM, with extension `ext`.
+fn cart_file(dir: Option<&Path>, cart_path: &Path, ext: &str) -> PathBuf {
+    let dir = dir.unwrap_or_else(|| cart_path.parent().unwrap_or(Path::new("")));
+    let mut name = cart_path.file_stem().unwrap_or_default().to_os_string();
+    name.push(".");
+    name.push(ext);
+    dir.join(name)
+}
+
+/// Creates the parent directory of `path` if it doesn't exist.
+pub fn create_parent(path: &Path) -> io::Result<()> {
+    match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => fs::create_dir_all(dir),
+        _ => Ok(()),
+    }
+}
+
+fn data_dir() -> Option<PathBuf> {
+    let non_empty = |var| env::var_os(var).filter(|dir: &OsString| !dir.is_empty());
+    let dir = if cfg!(windows) {
+        PathBuf::from(non_empty("APPDATA")?)
+    } else if cfg!(target_os = "macos") {
+        PathBuf::from(non_empty("HOME")?).join("Library/Application Support")
+    } else {
+        non_empty("XDG_DATA_HOME").map_or_else(
+            || Some(PathBuf::from(non_empty("HOME")?).join(".local/share")),
+            |dir| Some(PathBuf::from(dir)),
+        )?
+    };
+
+    Some(dir.join("memetendo"))
+}
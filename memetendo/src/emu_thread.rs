@@ -0,0 +1,434 @@
+use std::{
+    mem::take,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, Sender},
+        Arc, Mutex,
+    },
+    thread::{self, sleep, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, Result};
+use libmemetendo::{
+    cart::Header,
+    cheat::patch::Patches,
+    gba::{Gba, RamResetFlags, Thumbnail},
+    keypad::{Keypad, Macro, Turbo},
+    storage,
+    util::{
+        frame_skip,
+        video::{BlendMode, FlickerFilter, FrameBlender, FrameBuffer, PowerSaver},
+        FrameLimiter,
+    },
+    video,
+};
+use log::error;
+
+use crate::{
+    audio::Resampler,
+    console::Console,
+    dirs::GameFiles,
+    layers::LayerBuffers,
+    triple_buffer::{self, Reader, Writer},
+};
+
+pub const FRAME_DURATION: Duration = Duration::from_nanos(1_000_000_000 / 60);
+
+/// Keypad input, set by the main thread.
+pub struct Input {
+    pub keypad: Keypad,
+    /// Held turbo buttons; stepped by the emulation thread.
+    pub turbo: Turbo,
+    /// Input macro being recorded or replayed; stepped by the emulation thread.
+    pub key_macro: Macro,
+    /// Whether to emulate as fast as possible, rather than at the GBA's frame rate.
+    pub fast_forward: bool,
+    /// Whether emulation is paused (e.g: while the window is unfocused).
+    pub paused: bool,
+}
+
+/// Commands for the emulation thread, run before emulating the next frame.
+#[derive(Debug, Copy, Clone)]
+pub enum Command {
+    SaveState,
+    LoadState,
+    SoftReset,
+    Reset { skip_bios: bool },
+}
+
+/// A frame published by the emulation thread.
+#[derive(Clone)]
+pub struct Frame {
+    pub screen: FrameBuffer,
+    /// Dots of each layer, if capturing layers.
+    pub layers: Option<LayerBuffers>,
+    /// Number of frames emulated since the previously published frame, including this one.
+    pub emulated_frames: u32,
+    /// Time spent emulating those frames.
+    pub emulation_time: Duration,
+}
+
+#[expect(clippy::struct_excessive_bools)] // Not a state machine; just settings.
+pub struct Options {
+    pub frame_skip_mode: frame_skip::Mode,
+    pub turbo_interval: u32,
+    pub input_overlay: bool,
+    pub flicker_filter: bool,
+    pub frame_blend: Option<BlendMode>,
+    pub capture_layers: bool,
+    /// Whether to publish fewer frames while the game seems idle (e.g: in a menu), to save power.
+    pub power_save: bool,
+    pub console: Option<Console>,
+    /// Where `Command::SaveState` and `Command::LoadState` keep the save state.
+    pub game_files: GameFiles,
+    /// Patches whose RAM freezes are written at the start of every frame.
+    pub patches: Option<Patches>,
+}
+
+struct VideoCallback {
+    frames: Writer<Frame>,
+    new_frame: bool,
+    frame_skipping: bool,
+    /// Keypad state to draw as an overlay, if enabled.
+    input_overlay: Option<Keypad>,
+    flicker_filter: Option<FlickerFilter>,
+    /// Blends skipped frames into shown frames, if enabled.
+    frame_blender: Option<FrameBlender>,
+    /// Decides which frames are published while idle, if enabled.
+    power_saver: Option<PowerSaver>,
+    /// Of the last rendered frame, for save states.
+    thumbnail: Thumbnail,
+}
+
+impl VideoCallback {
+    /// Whether the frame that just ended should be published, given whether it had any sound.
+    fn should_publish(&mut self, audible: bool) -> bool {
+        let screen = &self.frames.buf().screen;
+        self.power_saver
+            .as_mut()
+            .map_or(true, |saver| saver.end_frame(screen, audible))
+    }
+}
+
+impl video::Callback for VideoCallback {
+    fn put_dot(&mut self, x: u8, y: u8, dot: video::Dot) {
+        let dot = match self.frame_blender {
+            Some(ref blender) => blender.apply(x, y, dot),
+            None => dot,
+        };
+        self.frames.buf_mut().screen.put_dot(x, y, dot);
+    }
+
+    fn put_dot_with_layer(
+        &mut self,
+        x: u8,
+        y: u8,
+        dot: video::Dot,
+        top_layer: Option<video::Layer>,
+    ) {
+        let dot = match self.flicker_filter {
+            Some(ref mut filter) => filter.apply(x, y, dot, top_layer),
+            None => dot,
+        };
+        self.put_dot(x, y, dot);
+    }
+
+    fn end_frame(&mut self, green_swap: bool) {
+        self.new_frame = true;
+        if self.frame_skipping {
+            if let Some(ref mut blender) = self.frame_blender {
+                blender.end_skipped_frame();
+            }
+            return;
+        }
+        if let Some(ref mut blender) = self.frame_blender {
+            blender.end_frame();
+        }
+
+        let frame = self.frames.buf_mut();
+        if green_swap {
+            frame.screen.green_swap();
+            if let Some(ref mut layers) = frame.layers {
+                layers.green_swap();
+            }
+        }
+        self.thumbnail.capture(&frame.screen);
+        if let Some(keypad) = self.input_overlay {
+            frame.screen.draw_keypad_overlay(keypad.pressed_keys());
+        }
+    }
+
+    fn is_frame_skipping(&self) -> bool {
+        self.frame_skipping
+    }
+
+    fn is_receiving_skipped_dots(&self) -> bool {
+        self.frame_blender.is_some()
+    }
+
+    fn put_skipped_dot(&mut self, x: u8, y: u8, dot: video::Dot) {
+        if let Some(ref mut blender) = self.frame_blender {
+            blender.put_skipped(x, y, dot);
+        }
+    }
+
+    fn is_capturing_layers(&self) -> bool {
+        self.frames.buf().layers.is_some()
+    }
+
+    fn put_layer_dot(&mut self, layer: video::Layer, x: u8, y: u8, dot: Option<video::Dot>) {
+        if let Some(ref mut layers) = self.frames.buf_mut().layers {
+            layers.put_dot(layer, x, y, dot);
+        }
+    }
+}
+
+/// Thread running the emulated system, paced to the GBA's frame rate. Frames are published to
+/// `Self::frames` for the main thread to present, so that slow presentation doesn't hold up
+/// emulation (and vice versa).
+pub struct EmuThread {
+    handle: JoinHandle<()>,
+    quit: Arc<AtomicBool>,
+    start: Option<Sender<Resampler>>,
+    commands: Sender<Command>,
+    pub input: Arc<Mutex<Input>>,
+    pub frames: Reader<Frame>,
+    /// Header of the cartridge ROM, if it has one.
+    pub rom_header: Option<Header>,
+    /// Canonical name of the game, if the cartridge ROM was identified from a dat file.
+    pub game_name: Option<String>,
+}
+
+impl EmuThread {
+    /// Spawns the thread, creating the system on it with `init`, as a `Gba` can't be sent between
+    /// threads; `init` also returns the canonical name of the game, if known. Emulation begins
+    /// after `Self::start` is called. When the thread is told to quit, `on_exit` is called with the
+    /// system before the thread exits.
+    pub fn spawn(
+        init: impl FnOnce() -> Result<(Gba, Option<String>)> + Send + 'static,
+        on_exit: impl FnOnce(&Gba) + Send + 'static,
+        options: Options,
+    ) -> Result<Self> {
+        let quit = Arc::new(AtomicBool::new(false));
+        let input = Arc::new(Mutex::new(Input {
+            keypad: Keypad::new(),
+            turbo: Turbo::new(options.turbo_interval),
+            key_macro: Macro::default(),
+            fast_forward: false,
+            paused: false,
+        }));
+        let (frames_writer, frames) = triple_buffer::new(Frame {
+            screen: FrameBuffer::default(),
+            layers: options.capture_layers.then(LayerBuffers::default),
+            emulated_frames: 0,
+            emulation_time: Duration::ZERO,
+        });
+        let (init_tx, init_rx) = mpsc::channel();
+        let (start_tx, start_rx) = mpsc::channel();
+        let (commands_tx, commands_rx) = mpsc::channel();
+
+        let handle = thread::spawn({
+            let quit = Arc::clone(&quit);
+            let input = Arc::clone(&input);
+            move || {
+                let mut gba = match init() {
+                    Ok((gba, game_name)) => {
+                        init_tx
+                            .send(Ok((gba.cart.rom().header(), game_name)))
+                            .unwrap();
+                        gba
+                    }
+                    Err(e) => {
+                        init_tx.send(Err(e)).unwrap();
+                        return;
+                    }
+                };
+                // If the main thread failed to start us, there's nothing to do.
+                let Ok(resampler) = start_rx.recv() else {
+                    return;
+                };
+
+                let mut video_cb = VideoCallback {
+                    frames: frames_writer,
+                    new_frame: false,
+                    frame_skipping: false,
+                    input_overlay: options.input_overlay.then(Keypad::new),
+                    flicker_filter: options.flicker_filter.then(FlickerFilter::new),
+                    frame_blender: options.frame_blend.map(FrameBlender::new),
+                    power_saver: options.power_save.then(PowerSaver::default),
+                    thumbnail: Thumbnail::new(),
+                };
+                run(
+                    &mut gba,
+                    &mut video_cb,
+                    resampler,
+                    &input,
+                    &commands_rx,
+                    options.game_files,
+                    &quit,
+                    FrameLimiter::new(options.frame_skip_mode, FRAME_DURATION),
+                    options.console,
+                    options.patches.as_ref(),
+                );
+                on_exit(&gba);
+            }
+        });
+
+        let (rom_header, game_name) = match init_rx.recv() {
+            Ok(result) => result?,
+            Err(_) => return Err(anyhow!("emulation thread panicked")),
+        };
+
+        Ok(Self {
+            handle,
+            quit,
+            start: Some(start_tx),
+            commands: commands_tx,
+            input,
+            frames,
+            rom_header,
+            game_name,
+        })
+    }
+
+    /// Title to show for the game: its canonical name if known, otherwise its title from the ROM
+    /// header (which may be empty).
+    pub fn game_title(&self) -> &str {
+        self.game_name.as_deref().unwrap_or_else(|| {
+            self.rom_header
+                .as_ref()
+                .map_or("", |header| header.title.as_str())
+        })
+    }
+
+    /// Begins emulation, outputting audio to `resampler`.
+    pub fn start(&mut self, resampler: Resampler) {
+        if let Some(start) = self.start.take() {
+            // If this fails, the thread panicked; that's reported by Self::join.
+            let _ = start.send(resampler);
+        }
+    }
+
+    pub fn send(&self, command: Command) {
+        // If this fails, the thread exited; that's reported by Self::is_finished.
+        let _ = self.commands.send(command);
+    }
+
+    /// Returns true if the thread exited early, such as from a panic.
+    pub fn is_finished(&self) -> bool {
+        self.handle.is_finished()
+    }
+
+    /// Tells the thread to quit and waits for it to exit.
+    pub fn join(self) -> Result<()> {
+        self.quit.store(true, Ordering::Relaxed);
+        drop(self.start);
+        self.handle
+            .join()
+            .map_err(|_| anyhow!("emulation thread panicked"))
+    }
+}
+
+#[expect(clippy::too_many_arguments)] // Only called by EmuThread::spawn.
+fn run(
+    gba: &mut Gba,
+    video_cb: &mut VideoCallback,
+    mut resampler: Resampler,
+    input: &Mutex<Input>,
+    commands: &Receiver<Command>,
+    mut game_files: GameFiles,
+    quit: &AtomicBool,
+    mut limiter: FrameLimiter,
+    mut console: Option<Console>,
+    patches: Option<&Patches>,
+) {
+    let epoch = Instant::now();
+    let (mut emulated_frames, mut emulation_time) = (0, Duration::ZERO);
+
+    while !quit.load(Ordering::Relaxed) {
+        if let Some(ref mut console) = console {
+            console.run_pending(gba);
+        }
+        for command in commands.try_iter() {
+            run_command(gba, command, &mut game_files, &video_cb.thumbnail);
+        }
+        if input.lock().unwrap().paused {
+            // Resume pacing from when emulation is unpaused, rather than catching up.
+            limiter.reset();
+            sleep(FRAME_DURATION);
+            continue;
+        }
+        if let Some(patches) = patches {
+            patches.freeze(gba);
+        }
+        if video_cb.input_overlay.is_some() {
+            video_cb.input_overlay = Some(gba.keypad);
+        }
+
+        video_cb.frame_skipping = limiter.start_frame(epoch.elapsed());
+        let frame_start_time = Instant::now();
+        while !take(&mut video_cb.new_frame) {
+            gba.step(video_cb, &mut resampler);
+            if gba.keypad.take_poll() {
+                poll_input(gba, input);
+            }
+            if let Some(ref mut console) = console {
+                console.run_if_stopped(gba, quit);
+            }
+        }
+        let frame_time = frame_start_time.elapsed();
+        resampler.flush();
+
+        emulated_frames += 1;
+        emulation_time += frame_time;
+        if !video_cb.frame_skipping && video_cb.should_publish(resampler.take_audible()) {
+            let frame = video_cb.frames.buf_mut();
+            frame.emulated_frames = take(&mut emulated_frames);
+            frame.emulation_time = take(&mut emulation_time);
+            video_cb.frames.publish();
+        }
+
+        let fast_forward = {
+            let mut input = input.lock().unwrap();
+            input.turbo.step(1);
+            input.key_macro.step(&gba.keypad);
+            input.fast_forward
+        };
+
+        let now = epoch.elapsed();
+        let behind = limiter.end_frame(now, frame_time);
+        if fast_forward {
+            // Resume pacing from now when fast-forward is disabled.
+            limiter.reset();
+        } else if !behind {
+            sleep(limiter.time_until_next_frame(now));
+        }
+    }
+}
+
+/// Samples the input set by the main thread into the system's keypad.
+fn poll_input(gba: &mut Gba, input: &Mutex<Input>) {
+    let input = input.lock().unwrap();
+    let mut keypad = input.keypad;
+    input.turbo.apply(&mut keypad);
+    input.key_macro.apply(&mut keypad);
+    gba.keypad.set_state(keypad.state());
+}
+
+fn run_command(gba: &mut Gba, command: Command, game_files: &mut GameFiles, thumbnail: &Thumbnail) {
+    match command {
+        Command::SaveState => {
+            if let Err(e) = storage::save_state(gba, game_files, Some(thumbnail)) {
+                error!("failed to write save state: {e}");
+            }
+        }
+        Command::LoadState => match storage::load_state(gba, game_files) {
+            Ok(true) => {}
+            Ok(false) => error!("no save state to load"),
+            Err(e) => error!("failed to load save state: {e}"),
+        },
+        Command::SoftReset => gba.soft_reset(RamResetFlags::ALL),
+        Command::Reset { skip_bios } => gba.reset(skip_bios),
+    }
+}
@@ -0,0 +1,122 @@
+use std::str::FromStr;
+
+use anyhow::{anyhow, Error, Result};
+use libmemetendo::video::{HBLANK_DOT, VBLANK_DOT};
+use log::info;
+use sdl2::{
+    pixels::PixelFormatEnum,
+    rect::Rect,
+    render::WindowCanvas,
+    video::{DisplayMode, FullscreenType},
+    VideoSubsystem,
+};
+
+/// Resolution and optional refresh rate of an exclusive fullscreen display mode, like
+/// "1920x1080" or "1920x1080@60".
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ModeSpec {
+    pub width: i32,
+    pub height: i32,
+    /// Refresh rate in Hz, or 0 for any.
+    pub refresh_rate: i32,
+}
+
+impl FromStr for ModeSpec {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let err = || anyhow!("expected WIDTHxHEIGHT or WIDTHxHEIGHT@HZ");
+        let (size, refresh_rate) = s.split_once('@').unwrap_or((s, "0"));
+        let (width, height) = size.split_once('x').ok_or_else(err)?;
+
+        let parse = |s: &str| s.parse::<i32>().ok().filter(|&n| n >= 0).ok_or_else(err);
+        Ok(Self {
+            width: parse(width)?,
+            height: parse(height)?,
+            refresh_rate: parse(refresh_rate)?,
+        })
+    }
+}
+
+/// Switches the window between windowed and fullscreen. Fullscreen is borderless at the desktop's
+/// resolution, unless an exclusive display mode was given.
+///
+/// While fullscreen, the screen is scaled by the largest integer factor that fits and centred, and
+/// the mouse cursor is hidden.
+pub struct Fullscreen {
+    sdl_video: VideoSubsystem,
+    mode: Option<ModeSpec>,
+    enabled: bool,
+}
+
+impl Fullscreen {
+    pub fn new(sdl_video: VideoSubsystem, mode: Option<ModeSpec>) -> Self {
+        Self {
+            sdl_video,
+            mode,
+            enabled: false,
+        }
+    }
+
+    pub fn set_enabled(&mut self, canvas: &mut WindowCanvas, enabled: bool) -> Result<()> {
+        let fullscreen_type = match (enabled, self.mode) {
+            (false, _) => FullscreenType::Off,
+            (true, None) => FullscreenType::Desktop,
+            (true, Some(spec)) => {
+                let display_index = canvas.window().display_index().map_err(Error::msg)?;
+                let mode = self
+                    .sdl_video
+                    .closest_display_mode(
+                        display_index,
+                        &DisplayMode::new(
+                            PixelFormatEnum::Unknown,
+                            spec.width,
+                            spec.height,
+                            spec.refresh_rate,
+                        ),
+                    )
+                    .map_err(|e| anyhow!("no suitable display mode: {e}"))?;
+                info!(
+                    "using display mode: {}x{}@{}",
+                    mode.w, mode.h, mode.refresh_rate
+                );
+                canvas
+                    .window_mut()
+                    .set_display_mode(mode)
+                    .map_err(Error::msg)?;
+                FullscreenType::True
+            }
+        };
+
+        canvas
+            .window_mut()
+            .set_fullscreen(fullscreen_type)
+            .map_err(Error::msg)?;
+        self.sdl_video.sdl().mouse().show_cursor(!enabled);
+        self.enabled = enabled;
+
+        Ok(())
+    }
+
+    pub fn toggle(&mut self, canvas: &mut WindowCanvas) -> Result<()> {
+        self.set_enabled(canvas, !self.enabled)
+    }
+
+    /// Returns where to draw the screen within the window, or `None` to stretch it over the whole
+    /// window.
+    pub fn screen_rect(&self, canvas: &WindowCanvas) -> Option<Rect> {
+        if !self.enabled {
+            return None;
+        }
+
+        let (width, height) = canvas.output_size().ok()?;
+        let (screen_width, screen_height) = (u32::from(HBLANK_DOT), u32::from(VBLANK_DOT));
+        let scale = (width / screen_width).min(height / screen_height).max(1);
+
+        Some(Rect::from_center(
+            canvas.viewport().center(),
+            scale * screen_width,
+            scale * screen_height,
+        ))
+    }
+}
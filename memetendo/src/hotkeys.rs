@@ -0,0 +1,153 @@
+use std::str::FromStr;
+
+use anyhow::{anyhow, Error, Result};
+use sdl2::controller::Button;
+
+/// Frontend actions that can be triggered by hotkeys.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Action {
+    SaveState,
+    LoadState,
+    FastForward,
+    Screenshot,
+    Fullscreen,
+    /// Starts recording the input macro, or stops and saves it if recording.
+    RecordMacro,
+    PlayMacro,
+    /// Restarts the game like its own soft reset key combo does, without running the BIOS.
+    SoftReset,
+    /// Restarts the system as if it was powered off and on, skipping the BIOS if `--skip-bios`.
+    Reset,
+    /// Like `Self::Reset`, but always runs the BIOS.
+    ResetToBios,
+}
+
+impl FromStr for Action {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "save-state" => Ok(Self::SaveState),
+            "load-state" => Ok(Self::LoadState),
+            "fast-forward" => Ok(Self::FastForward),
+            "screenshot" => Ok(Self::Screenshot),
+            "fullscreen" => Ok(Self::Fullscreen),
+            "record-macro" => Ok(Self::RecordMacro),
+            "play-macro" => Ok(Self::PlayMacro),
+            "soft-reset" => Ok(Self::SoftReset),
+            "reset" => Ok(Self::Reset),
+            "reset-to-bios" => Ok(Self::ResetToBios),
+            _ => Err(anyhow!(
+                "unknown action {s:?} (expected save-state, load-state, fast-forward, screenshot, \
+                 fullscreen, record-macro, play-macro, soft-reset, reset or reset-to-bios)"
+            )),
+        }
+    }
+}
+
+/// Controller buttons that must be held together, like "back+rightshoulder" (Select+R). Buttons
+/// are named as in SDL's game controller mappings.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Combo(Vec<Button>);
+
+impl FromStr for Combo {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        s.split('+')
+            .map(|name| {
+                Button::from_string(name.trim())
+                    .ok_or_else(|| anyhow!("unknown controller button {name:?}"))
+            })
+            .collect::<Result<_>>()
+            .map(Self)
+    }
+}
+
+/// A combo bound to an action, parsed from "ACTION=COMBO".
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Binding {
+    pub action: Action,
+    pub combo: Combo,
+}
+
+impl FromStr for Binding {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (action, combo) = s
+            .split_once('=')
+            .ok_or_else(|| anyhow!("expected ACTION=COMBO"))?;
+
+        Ok(Self {
+            action: action.trim().parse()?,
+            combo: combo.parse()?,
+        })
+    }
+}
+
+const DEFAULT_BINDINGS: [(Action, &str); 4] = [
+    (Action::SaveState, "back+rightshoulder"),
+    (Action::LoadState, "back+leftshoulder"),
+    (Action::FastForward, "back+y"),
+    (Action::Screenshot, "back+x"),
+];
+
+/// Maps controller button combos to frontend actions, for controller-only setups. Actions without
+/// a default binding (like fullscreen and the macro actions) can be bound by the user.
+///
+/// An action is triggered when the last button of its combo is pressed. Until all of the combo's
+/// buttons are released, they're consumed by the hotkey and not passed on to the emulated keypad.
+pub struct Hotkeys {
+    bindings: Vec<Binding>,
+    /// Whether each binding's combo is active (triggered, but not yet fully released).
+    active: Vec<bool>,
+}
+
+impl Hotkeys {
+    /// Creates hotkeys with the default bindings, replaced by any of `bindings` for the same
+    /// action.
+    pub fn new(bindings: impl IntoIterator<Item = Binding>) -> Self {
+        let mut hotkeys: Vec<_> = DEFAULT_BINDINGS
+            .iter()
+            .map(|&(action, combo)| Binding {
+                action,
+                combo: combo.parse().unwrap(),
+            })
+            .collect();
+        for binding in bindings {
+            hotkeys.retain(|b| b.action != binding.action);
+            hotkeys.push(binding);
+        }
+
+        Self {
+            active: vec![false; hotkeys.len()],
+            bindings: hotkeys,
+        }
+    }
+
+    /// Updates the state of the combos from the held controller buttons, returning the actions
+    /// that were triggered.
+    pub fn update(&mut self, is_held: impl Fn(Button) -> bool) -> Vec<Action> {
+        let mut actions = Vec::new();
+        for (binding, active) in self.bindings.iter().zip(&mut self.active) {
+            let buttons = &binding.combo.0;
+            if *active {
+                *active = buttons.iter().any(|&button| is_held(button));
+            } else if buttons.iter().all(|&button| is_held(button)) {
+                *active = true;
+                actions.push(binding.action);
+            }
+        }
+
+        actions
+    }
+
+    /// Returns true if `button` is consumed by an active combo.
+    pub fn is_consumed(&self, button: Button) -> bool {
+        self.bindings
+            .iter()
+            .zip(&self.active)
+            .any(|(binding, &active)| active && binding.combo.0.contains(&button))
+    }
+}
@@ -0,0 +1,45 @@
+use sdl2::{pixels::PixelFormatEnum, surface::Surface};
+
+/// The window icon: a tiny GBA.
+const ICON: [&[u8; 16]; 16] = [
+    b"................",
+    b"................",
+    b"................",
+    b"................",
+    b".##############.",
+    b"###ssssssssss###",
+    b"#d#sggggggggs#a#",
+    b"dddsggggggggsa##",
+    b"#d#sggggggggs###",
+    b"###sggggggggs###",
+    b"###ssssssssss###",
+    b".##############.",
+    b"................",
+    b"................",
+    b"................",
+    b"................",
+];
+
+/// Creates a surface containing the window icon.
+pub fn surface() -> Result<Surface<'static>, String> {
+    let mut surface = Surface::new(16, 16, PixelFormatEnum::RGBA32)?;
+    let pitch = surface.pitch() as usize;
+    surface.with_lock_mut(|buf| {
+        for (y, row) in ICON.iter().enumerate() {
+            for (x, &c) in row.iter().enumerate() {
+                let rgba = match c {
+                    b'#' => [0x5a, 0x4f, 0xcf, 0xff],
+                    b's' => [0x1e, 0x1e, 0x3c, 0xff],
+                    b'g' => [0x9b, 0xbc, 0x0f, 0xff],
+                    b'd' => [0x20, 0x20, 0x20, 0xff],
+                    b'a' => [0xdd, 0xdd, 0xdd, 0xff],
+                    _ => [0; 4],
+                };
+                let i = y * pitch + x * 4;
+                buf[i..i + 4].copy_from_slice(&rgba);
+            }
+        }
+    });
+
+    Ok(surface)
+}
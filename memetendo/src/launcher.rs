@@ -0,0 +1,207 @@
+use std::path::PathBuf;
+
+use libmemetendo::video::{HBLANK_DOT, VBLANK_DOT};
+use log::warn;
+use sdl2::{
+    controller::Button,
+    event::{Event, WindowEvent},
+    keyboard::Scancode,
+    pixels::Color,
+    rect::Rect,
+    render::WindowCanvas,
+    EventPump,
+};
+
+use crate::{
+    controllers::Controllers,
+    recent::{self, Game},
+    text,
+};
+
+const LIST_Y: i32 = 44;
+const ROW_HEIGHT: i32 = 10;
+const MAX_TITLE_LEN: usize = 24;
+/// Maximum number of games listed, leaving room for the selected game's statistics below.
+const MAX_ROWS: usize = 9;
+/// Below the last row of the list.
+const STATS_Y: i32 = 138;
+
+/// Shown in the main window when started without a cartridge ROM. Lists recently played games to
+/// pick from with the keyboard, a controller or the mouse; other ROMs can be dropped onto the
+/// window. Returns the picked cartridge ROM file, or `None` if the user quit.
+pub fn run(
+    event_pump: &mut EventPump,
+    canvas: &mut WindowCanvas,
+    controllers: &mut Controllers,
+    games: &[Game],
+) -> Option<PathBuf> {
+    let games: Vec<_> = games
+        .iter()
+        .filter(|game| game.path.is_file())
+        .take(MAX_ROWS)
+        .collect();
+    let mut selected = 0;
+
+    loop {
+        if let Err(e) = draw(canvas, &games, selected) {
+            warn!("failed to draw launcher: {e}");
+        }
+
+        let Some(event) = event_pump.wait_event_timeout(250) else {
+            continue;
+        };
+        for event in [event].into_iter().chain(event_pump.poll_iter()) {
+            controllers.handle_event(&event);
+            let pick = match event {
+                Event::Quit { .. }
+                | Event::Window {
+                    win_event: WindowEvent::Close,
+                    ..
+                }
+                | Event::KeyDown {
+                    scancode: Some(Scancode::Escape),
+                    ..
+                } => return None,
+                Event::DropFile { filename, .. } => return Some(filename.into()),
+                Event::KeyDown {
+                    scancode: Some(Scancode::Up),
+                    ..
+                }
+                | Event::ControllerButtonDown {
+                    button: Button::DPadUp,
+                    ..
+                } => {
+                    selected = selected.saturating_sub(1);
+                    false
+                }
+                Event::KeyDown {
+                    scancode: Some(Scancode::Down),
+                    ..
+                }
+                | Event::ControllerButtonDown {
+                    button: Button::DPadDown,
+                    ..
+                } => {
+                    selected = (selected + 1).min(games.len().saturating_sub(1));
+                    false
+                }
+                Event::KeyDown {
+                    scancode: Some(Scancode::Return | Scancode::X),
+                    ..
+                }
+                | Event::ControllerButtonDown {
+                    button: Button::A | Button::Start,
+                    ..
+                } => true,
+                Event::MouseButtonDown { x, y, .. } => {
+                    match row_at(canvas, x, y).filter(|&row| row < games.len()) {
+                        Some(row) => {
+                            selected = row;
+                            true
+                        }
+                        None => false,
+                    }
+                }
+                _ => false,
+            };
+            if pick && selected < games.len() {
+                return Some(games[selected].path.clone());
+            }
+        }
+    }
+}
+
+/// Returns the scale and top-left position of the launcher's screen-sized area within the window,
+/// which is scaled by the largest integer factor that fits and centred.
+pub fn layout(canvas: &WindowCanvas) -> (i32, i32, i32) {
+    let (width, height) = canvas.output_size().unwrap_or_default();
+    let (width, height) = (
+        i32::try_from(width).unwrap_or(i32::MAX),
+        i32::try_from(height).unwrap_or(i32::MAX),
+    );
+    let (screen_width, screen_height) = (i32::from(HBLANK_DOT), i32::from(VBLANK_DOT));
+    let scale = (width / screen_width).min(height / screen_height).max(1);
+
+    (
+        scale,
+        (width - scale * screen_width) / 2,
+        (height - scale * screen_height) / 2,
+    )
+}
+
+/// Returns the index of the list row at the window position `(x, y)`, if any.
+fn row_at(canvas: &WindowCanvas, x: i32, y: i32) -> Option<usize> {
+    let (scale, left, top) = layout(canvas);
+    let (x, y) = ((x - left) / scale, (y - top) / scale - (LIST_Y - 2));
+    if y < 0 || !(0..i32::from(HBLANK_DOT)).contains(&x) {
+        return None;
+    }
+
+    usize::try_from(y / ROW_HEIGHT).ok()
+}
+
+fn draw(canvas: &mut WindowCanvas, games: &[&Game], selected: usize) -> Result<(), String> {
+    let (scale, left, top) = layout(canvas);
+    let dot_scale = scale.unsigned_abs();
+    let screen_width = i32::from(HBLANK_DOT);
+    let draw_text = |canvas: &mut WindowCanvas, x, y, s: &str| {
+        text::draw(canvas, left + x * scale, top + y * scale, dot_scale, s)
+    };
+    let text_width = |s: &str| i32::try_from(s.chars().count()).unwrap_or(0) * text::ADVANCE;
+    let centred = |s: &str| (screen_width - text_width(s)) / 2;
+
+    canvas.set_draw_color(Color::BLACK);
+    canvas.clear();
+
+    canvas.set_draw_color(Color::RGB(0x9b, 0x8f, 0xff));
+    let title = "Memetendo Unsafe Boy Advance";
+    draw_text(canvas, centred(title), 6, title)?;
+
+    canvas.set_draw_color(Color::WHITE);
+    let hint = "Drop a ROM file onto this window";
+    draw_text(canvas, centred(hint), 20, hint)?;
+    if !games.is_empty() {
+        let hint = "or pick a recent game:";
+        draw_text(canvas, centred(hint), 30, hint)?;
+    }
+
+    for (i, game) in (0..).zip(games) {
+        let y = LIST_Y + i * ROW_HEIGHT;
+        if usize::try_from(i) == Ok(selected) {
+            canvas.set_draw_color(Color::RGB(0x3c, 0x34, 0x8c));
+            canvas.fill_rect(Rect::new(
+                left + 4 * scale,
+                top + (y - 2) * scale,
+                (screen_width - 8).unsigned_abs() * dot_scale,
+                ROW_HEIGHT.unsigned_abs() * dot_scale,
+            ))?;
+        }
+
+        canvas.set_draw_color(Color::WHITE);
+        let title: String = game.display_title().chars().take(MAX_TITLE_LEN).collect();
+        draw_text(canvas, 8, y, &title)?;
+        let play_time = recent::format_play_time(game.play_time);
+        draw_text(
+            canvas,
+            screen_width - 8 - text_width(&play_time),
+            y,
+            &play_time,
+        )?;
+    }
+
+    if let Some(stats) = games
+        .get(selected)
+        .and_then(|game| recent::format_last_played(game.last_played))
+    {
+        canvas.set_draw_color(Color::GREY);
+        let stats = format!("Last played {stats}");
+        draw_text(canvas, centred(&stats), STATS_Y, &stats)?;
+    }
+
+    canvas.set_draw_color(Color::GREY);
+    let help = "Enter/A: play   Esc: quit";
+    draw_text(canvas, centred(help), 150, help)?;
+
+    canvas.present();
+    Ok(())
+}
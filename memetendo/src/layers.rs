@@ -0,0 +1,125 @@
+use anyhow::{Context, Result};
+use libmemetendo::{
+    util::video::FrameBuffer,
+    video::{Dot, Layer, HBLANK_DOT, VBLANK_DOT},
+};
+use log::warn;
+use sdl2::{
+    pixels::PixelFormatEnum,
+    render::{TextureCreator, WindowCanvas},
+    sys::SDL_WindowFlags,
+    video::WindowContext,
+    VideoSubsystem,
+};
+
+/// Color used for transparent dots; magenta is unlikely to be mistaken for actual graphics.
+const TRANSPARENT_RGB: [u8; 3] = [0xff, 0x00, 0xff];
+
+const LAYERS: [Layer; 5] = [Layer::Bg0, Layer::Bg1, Layer::Bg2, Layer::Bg3, Layer::Obj];
+
+/// Dots of each BG layer and the OBJ layer, captured separately for display by `LayerWindows`.
+#[derive(Clone, Default)]
+pub struct LayerBuffers([FrameBuffer; LAYERS.len()]);
+
+impl LayerBuffers {
+    pub fn put_dot(&mut self, layer: Layer, x: u8, y: u8, dot: Option<Dot>) {
+        let buf = &mut self.0[layer as usize];
+        if let Some(dot) = dot {
+            buf.put_dot(x, y, dot);
+        } else {
+            let i = 3 * (usize::from(y) * usize::from(HBLANK_DOT) + usize::from(x));
+            buf.0[i..i + 3].copy_from_slice(&TRANSPARENT_RGB);
+        }
+    }
+
+    pub fn green_swap(&mut self) {
+        for buf in &mut self.0 {
+            buf.green_swap();
+        }
+    }
+}
+
+struct LayerWindow {
+    layer: Layer,
+    canvas: WindowCanvas,
+    texture_creator: TextureCreator<WindowContext>,
+}
+
+/// Auxiliary windows showing each BG layer and the OBJ layer rendered separately.
+pub struct LayerWindows(Vec<LayerWindow>);
+
+impl LayerWindows {
+    pub fn new(sdl_video: &VideoSubsystem) -> Result<Self> {
+        let mut windows = Vec::new();
+        for layer in LAYERS {
+            let window = sdl_video
+                .window(
+                    &format!("Memetendo Unsafe Boy Advance | {layer:?}"),
+                    HBLANK_DOT.into(),
+                    VBLANK_DOT.into(),
+                )
+                .resizable()
+                .build()
+                .context("failed to create sdl2 layer window")?;
+
+            let canvas = window
+                .into_canvas()
+                .build()
+                .context("failed to get sdl2 layer window canvas")?;
+
+            windows.push(LayerWindow {
+                layer,
+                texture_creator: canvas.texture_creator(),
+                canvas,
+            });
+        }
+
+        Ok(Self(windows))
+    }
+
+    pub fn present(&mut self, bufs: &LayerBuffers) {
+        for window in &mut self.0 {
+            let flags = window.canvas.window().window_flags();
+            if flags & SDL_WindowFlags::SDL_WINDOW_HIDDEN as u32 != 0 {
+                continue;
+            }
+            let buf = &bufs.0[window.layer as usize];
+
+            let mut texture = match window.texture_creator.create_texture_static(
+                PixelFormatEnum::RGB24,
+                HBLANK_DOT.into(),
+                VBLANK_DOT.into(),
+            ) {
+                Ok(texture) => texture,
+                Err(e) => {
+                    warn!("failed to create {:?} layer texture: {e}", window.layer);
+                    continue;
+                }
+            };
+            if let Err(e) = texture.update(None, &buf.0, 3 * usize::from(HBLANK_DOT)) {
+                warn!("failed to update {:?} layer texture: {e}", window.layer);
+                continue;
+            }
+
+            window.canvas.clear();
+            if let Err(e) = window.canvas.copy(&texture, None, None) {
+                warn!("failed to draw {:?} layer texture: {e}", window.layer);
+            }
+            window.canvas.present();
+        }
+    }
+
+    /// Hides the layer window with the given ID, returning true if it was one of ours.
+    pub fn close(&mut self, window_id: u32) -> bool {
+        let Some(window) = self
+            .0
+            .iter_mut()
+            .find(|window| window.canvas.window().id() == window_id)
+        else {
+            return false;
+        };
+
+        window.canvas.window_mut().hide();
+        true
+    }
+}
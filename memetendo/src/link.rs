@@ -0,0 +1,132 @@
+use std::{path::Path, time::Duration};
+
+use anyhow::{Context, Result};
+use clap::ArgMatches;
+use libmemetendo::{
+    sio::link::LinkPort,
+    video::{HBLANK_DOT, VBLANK_DOT},
+};
+use log::warn;
+use sdl2::{
+    event::{Event, WindowEvent},
+    pixels::PixelFormatEnum,
+    render::{TextureCreator, WindowCanvas},
+    video::WindowContext,
+    VideoSubsystem,
+};
+
+use crate::{
+    audio::Resampler, dirs::Dirs, emu_thread::EmuThread, emu_thread_options, load_system,
+    save_cart_backup, system_files,
+};
+
+/// The second system of a `--link` session, connected to the first by an in-process link cable
+/// and shown in its own window, for testing local multiplayer.
+pub struct LinkedSystem {
+    pub emu: EmuThread,
+    canvas: WindowCanvas,
+    texture_creator: TextureCreator<WindowContext>,
+    /// Whether its window has focus, in which case it gets the keypad input instead of the first
+    /// system.
+    pub focused: bool,
+}
+
+/// Spawns the emulation thread of the second system, if a `--link` session was requested. Also
+/// returns the other end of its link cable, for connecting to the first system.
+pub fn spawn(
+    matches: &ArgMatches,
+    dirs: &Dirs,
+    bios_path: &Path,
+) -> Result<Option<(EmuThread, LinkPort)>> {
+    let Some(cart_path) = matches.value_of_os("link").map(Path::new) else {
+        return Ok(None);
+    };
+    let (port, linked_port) = LinkPort::pair();
+    let mut game_files = dirs.game_files(cart_path);
+    let mut files = system_files(matches, bios_path, cart_path, game_files.clone())?;
+    files.link_port = Some(linked_port);
+    // Debug output and patches are for the first system's game only.
+    files.symbols_path = None;
+    files.count_accesses = false;
+    files.cdl_path = None;
+    files.profile = false;
+    files.patches = None;
+
+    let emu = EmuThread::spawn(
+        move || load_system(files),
+        move |gba| save_cart_backup(gba, &mut game_files),
+        emu_thread_options(matches, dirs.game_files(cart_path), None),
+    )?;
+
+    Ok(Some((emu, port)))
+}
+
+impl LinkedSystem {
+    /// Opens the window for the system, and begins emulating it without audio.
+    pub fn new(mut emu: EmuThread, sdl_video: &VideoSubsystem) -> Result<Self> {
+        let title = match emu.game_title() {
+            "" => "Linked | Memetendo Unsafe Boy Advance".to_string(),
+            title => format!("{title} (linked) | Memetendo Unsafe Boy Advance"),
+        };
+        let window = sdl_video
+            .window(&title, HBLANK_DOT.into(), VBLANK_DOT.into())
+            .resizable()
+            .build()
+            .context("failed to create sdl2 linked window")?;
+
+        let canvas = window
+            .into_canvas()
+            .build()
+            .context("failed to get sdl2 linked window canvas")?;
+
+        emu.start(Resampler::default());
+        Ok(Self {
+            emu,
+            texture_creator: canvas.texture_creator(),
+            canvas,
+            focused: false,
+        })
+    }
+
+    /// Tracks which window has focus. Other events for the window (like closing it) are handled
+    /// as they are for the first system's.
+    pub fn handle_event(&mut self, event: &Event) {
+        if let Event::Window {
+            window_id,
+            win_event: WindowEvent::FocusGained,
+            ..
+        } = *event
+        {
+            self.focused = window_id == self.canvas.window().id();
+        }
+    }
+
+    /// Presents the latest frame, if a new one was published.
+    pub fn present(&mut self) {
+        let Some(frame) = self.emu.frames.wait_new(Duration::ZERO) else {
+            return;
+        };
+
+        let mut texture = match self.texture_creator.create_texture_static(
+            PixelFormatEnum::RGB24,
+            HBLANK_DOT.into(),
+            VBLANK_DOT.into(),
+        ) {
+            Ok(texture) => texture,
+            Err(e) => {
+                warn!("failed to create linked screen texture: {e}");
+                return;
+            }
+        };
+        if let Err(e) = texture.update(None, &frame.screen.0, 3 * usize::from(HBLANK_DOT)) {
+            warn!("failed to update linked screen texture: {e}");
+            return;
+        }
+
+        self.canvas.clear();
+        if let Err(e) = self.canvas.copy(&texture, None, None) {
+            warn!("failed to draw linked screen texture: {e}");
+        }
+        self.canvas.present();
+    }
+}
@@ -0,0 +1,84 @@
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use libmemetendo::keypad::{KeyState, Macro};
+use log::warn;
+
+use crate::dirs;
+
+/// Input macros recorded for each game, keyed by game code and persisted as TOML in the data
+/// directory. Each is a list of the keys pressed on each of its frames, with bit n set for the key
+/// with the discriminant n (e.g: `BPRE = [1, 1, 0, 8]` presses A for 2 frames, then Start).
+type Macros = BTreeMap<String, Vec<u16>>;
+
+/// The input macro of the game being played, which is saved whenever a new one is recorded.
+pub struct GameMacro {
+    /// Where macros are saved, if there's a data directory.
+    path: Option<PathBuf>,
+    /// Game code of the game, if it has one; its macro isn't saved otherwise.
+    game_code: Option<String>,
+}
+
+impl GameMacro {
+    pub fn new(path: Option<PathBuf>, game_code: Option<String>) -> Self {
+        Self { path, game_code }
+    }
+
+    /// Loads the game's macro, or returns an empty one if there's none.
+    pub fn load(&self) -> Macro {
+        let (Some(path), Some(game_code)) = (&self.path, &self.game_code) else {
+            return Macro::default();
+        };
+        let frames = load_macros(path)
+            .remove(game_code)
+            .unwrap_or_default()
+            .into_iter()
+            .map(KeyState::from_bits_truncate)
+            .collect();
+
+        Macro::new(frames)
+    }
+
+    /// Saves `input_macro` as the game's macro, replacing the one saved before.
+    pub fn save(&self, input_macro: &Macro) {
+        let (Some(path), Some(game_code)) = (&self.path, &self.game_code) else {
+            return;
+        };
+
+        let mut macros = load_macros(path);
+        let frames = input_macro.frames().iter().map(|keys| keys.bits());
+        macros.insert(game_code.clone(), frames.collect());
+        if let Err(e) = save_macros(path, &macros) {
+            warn!("failed to save macro: {e:#}");
+        }
+    }
+}
+
+/// Loads the macros from `path`, or returns none if it doesn't exist or is invalid.
+fn load_macros(path: &Path) -> Macros {
+    let s = match fs::read_to_string(path) {
+        Ok(s) => s,
+        Err(e) => {
+            if e.kind() != io::ErrorKind::NotFound {
+                warn!("failed to read macros file: {e}");
+            }
+            return Macros::new();
+        }
+    };
+
+    toml::from_str(&s).unwrap_or_else(|e| {
+        warn!("failed to parse macros file: {e}");
+        Macros::new()
+    })
+}
+
+fn save_macros(path: &Path, macros: &Macros) -> Result<()> {
+    let s = toml::to_string(macros).context("failed to serialize macros")?;
+    dirs::create_parent(path)
+        .and_then(|()| fs::write(path, s))
+        .context("failed to write macros file")
+}
@@ -2,41 +2,84 @@
 
 use std::{
     fmt::Write,
-    fs, io,
-    mem::take,
-    path::Path,
+    fs::{self, File},
+    io::{self, BufWriter, Write as _},
+    ops::RangeInclusive,
+    path::{Path, PathBuf},
     rc::Rc,
-    thread::sleep,
-    time::{Duration, Instant},
+    time::{Duration, Instant, SystemTime},
 };
 
 use anyhow::{anyhow, Context, Result};
-use clap::{arg, command, value_parser};
+use clap::{arg, command, value_parser, Arg, ArgMatches, Command};
 use libmemetendo::{
     bios,
-    cart::{self, BackupType, Cartridge},
-    gba::Gba,
-    keypad::{Key, Keypad},
-    util::video::FrameBuffer,
-    video::{self, HBLANK_DOT, VBLANK_DOT},
+    cart::{self, dat, BackupType, Cartridge},
+    cheat::patch::Patches,
+    debug::{self, symbols::Symbols},
+    gba::{self, DeterminismConfig, Gba, Peripherals},
+    keypad::{Key, Keypad, PollRate, Turbo},
+    sio::{self, link::LinkPort},
+    storage,
+    util::{
+        frame_limiter::FpsCounter,
+        frame_skip,
+        video::{BlendMode, FrameBuffer},
+    },
+    video::{HBLANK_DOT, VBLANK_DOT},
 };
 use log::{error, info, warn};
 use sdl2::{
     audio::AudioSpecDesired,
-    event::Event,
+    controller::Button,
+    event::{Event, WindowEvent},
     keyboard::{KeyboardState, Scancode},
     pixels::{Color, PixelFormatEnum},
     render::{Texture, TextureCreator, WindowCanvas},
+    surface::Surface,
     video::WindowContext,
-    AudioSubsystem, EventPump,
+    AudioSubsystem, EventPump, VideoSubsystem,
 };
 
-use crate::audio::Audio;
+use crate::{
+    audio::{Audio, Resampler},
+    console::Console,
+    controllers::Controllers,
+    dirs::{Dirs, GameFiles},
+    emu_thread::{EmuThread, FRAME_DURATION},
+    fullscreen::{Fullscreen, ModeSpec},
+    hotkeys::{Action, Binding, Hotkeys},
+    layers::LayerWindows,
+    link::LinkedSystem,
+    macros::GameMacro,
+    overrides::UserOverrides,
+    perf_hud::PerfHud,
+    recent::{Recent, Session},
+};
 
 mod audio;
+mod console;
+mod controllers;
+mod dirs;
+mod emu_thread;
+mod fullscreen;
+mod hotkeys;
+mod icon;
+mod launcher;
+mod layers;
+mod link;
+mod macros;
+mod overrides;
+mod perf_hud;
+mod recent;
+mod splash;
+mod text;
+mod triple_buffer;
 
 struct SdlContext {
+    sdl_video: VideoSubsystem,
     sdl_audio: Option<AudioSubsystem>,
+    controllers: Controllers,
     win_canvas: WindowCanvas,
     win_texture_creator: TextureCreator<WindowContext>,
     event_pump: EventPump,
@@ -62,7 +105,15 @@ impl SdlContext {
             }
         };
 
-        let window = sdl_video
+        let sdl_controller = match sdl.game_controller() {
+            Ok(controller) => Some(controller),
+            Err(e) => {
+                error!("failed to init sdl2 game controller subsystem: {e}");
+                None
+            }
+        };
+
+        let mut window = sdl_video
             .window(
                 "Memetendo Unsafe Boy Advance",
                 HBLANK_DOT.into(),
@@ -72,6 +123,10 @@ impl SdlContext {
             .resizable()
             .build()
             .context("failed to create sdl2 window")?;
+        match icon::surface() {
+            Ok(icon) => window.set_icon(icon),
+            Err(e) => warn!("failed to create window icon: {e}"),
+        }
 
         let win_canvas = window
             .into_canvas()
@@ -81,7 +136,9 @@ impl SdlContext {
         let win_texture_creator = win_canvas.texture_creator();
 
         Ok(Self {
+            sdl_video,
             sdl_audio,
+            controllers: Controllers::new(sdl_controller),
             win_canvas,
             win_texture_creator,
             event_pump,
@@ -89,101 +146,90 @@ impl SdlContext {
     }
 }
 
-struct VideoCallback<'r> {
-    texture: Texture<'r>,
-    new_frame: bool,
-    frame_skipping: bool,
-    buf: FrameBuffer,
+fn load_cart(
+    rom: cart::Rom,
+    game_files: &mut GameFiles,
+    fallback_backup_type: Option<BackupType>,
+) -> Cartridge {
+    match storage::read_backup(game_files) {
+        Ok(Some(buf)) => match Cartridge::try_from_backup(&rom, Some(buf)) {
+            Ok(cart) => Some(cart),
+            Err(e) => {
+                error!("failed to load cart backup file: {e}");
+                None
+            }
+        },
+        Ok(None) => None,
+        Err(e) => {
+            error!("failed to read cart backup file: {e}");
+            None
+        }
+    }
+    .unwrap_or_else(|| match fallback_backup_type {
+        Some(backup_type) => {
+            info!("using backup type: {backup_type:?}");
+            Cartridge::new(rom, backup_type)
+        }
+        // Reports the guessed backup type.
+        None => Cartridge::from(rom),
+    })
 }
 
-impl<'r> VideoCallback<'r> {
-    fn new<T>(texture_creator: &'r TextureCreator<T>) -> Result<Self> {
-        let texture = texture_creator
-            .create_texture_streaming(PixelFormatEnum::RGB24, HBLANK_DOT.into(), VBLANK_DOT.into())
-            .context("failed to create screen texture")?;
-
-        Ok(Self {
-            texture,
-            new_frame: false,
-            frame_skipping: false,
-            buf: FrameBuffer::default(),
-        })
+fn load_symbols(path: &Path) -> Result<Symbols> {
+    let buf = fs::read(path)?;
+    if buf.starts_with(b"\x7fELF") {
+        Ok(Symbols::parse_elf(&buf)?)
+    } else {
+        Ok(Symbols::parse_sym(&String::from_utf8_lossy(&buf)))
     }
 }
 
-impl video::Callback for VideoCallback<'_> {
-    fn put_dot(&mut self, x: u8, y: u8, dot: video::Dot) {
-        self.buf.put_dot(x, y, dot);
+/// Loads symbols from `path` if given, otherwise from a .sym or .elf file alongside the cartridge
+/// ROM, if any.
+fn load_cart_symbols(cart_path: &Path, path: Option<&Path>) -> Result<Symbols> {
+    if let Some(path) = path {
+        return load_symbols(path).context("failed to load symbols file");
     }
 
-    fn end_frame(&mut self, green_swap: bool) {
-        self.new_frame = true;
-        if self.frame_skipping {
-            return;
-        }
-
-        if green_swap {
-            self.buf.green_swap();
-        }
-
-        if let Err(e) = self.texture.with_lock(None, |texture_buf, _| {
-            texture_buf.copy_from_slice(&self.buf.0);
-        }) {
-            warn!("failed to lock screen texture: {e}");
+    for ext in ["sym", "elf"] {
+        let path = cart_path.with_extension(ext);
+        if !path.is_file() {
+            continue;
         }
-    }
 
-    fn is_frame_skipping(&self) -> bool {
-        self.frame_skipping
-    }
-}
-
-fn load_cart(
-    rom: cart::Rom,
-    backup_path: &impl AsRef<Path>,
-    fallback_backup_type: Option<BackupType>,
-) -> Cartridge {
-    match fs::read(backup_path) {
-        Ok(buf) => {
-            let len = buf.len();
-            let cart = Cartridge::try_from_backup(&rom, Some(buf.into_boxed_slice()));
-            if cart.is_none() {
-                error!(
-                    "failed to determine cart backup type from file {} (len: {len})",
-                    backup_path.as_ref().to_string_lossy()
-                );
-            }
-
-            cart
-        }
-        Err(e) => {
-            if e.kind() != io::ErrorKind::NotFound {
-                error!(
-                    "failed to read cart backup file {}: {e}",
-                    backup_path.as_ref().to_string_lossy()
+        match load_symbols(&path) {
+            Ok(symbols) => {
+                info!(
+                    "loaded {} symbols from {}",
+                    symbols.len(),
+                    path.to_string_lossy()
                 );
+                return Ok(symbols);
             }
-
-            None
+            Err(e) => error!(
+                "failed to load symbols file {}: {e}",
+                path.to_string_lossy()
+            ),
         }
     }
-    .unwrap_or_else(|| {
-        let backup_type = fallback_backup_type.unwrap_or_else(|| rom.parse_backup_type());
-        info!("using backup type: {backup_type:?}");
 
-        Cartridge::new(rom, backup_type)
-    })
+    Ok(Symbols::new())
 }
 
-fn main() -> Result<()> {
-    env_logger::builder()
-        .format_timestamp(None)
-        .parse_env(env_logger::Env::default().default_filter_or("info"))
-        .init();
-
-    let matches = command!()
+#[expect(clippy::too_many_lines)] // Just a list of arguments.
+fn cli() -> Command<'static> {
+    command!()
         .arg(arg!(--"skip-bios" "Skip executing BIOS ROM after boot").required(false))
-        .arg(arg!(-b --bios <FILE> "BIOS ROM file to use").allow_invalid_utf8(true))
+        .arg(
+            arg!(--splash "Show a short splash screen in place of the skipped BIOS boot animation")
+                .requires("skip-bios")
+                .required(false),
+        )
+        .arg(
+            arg!(-b --bios <FILE> "BIOS ROM file to use (defaults to the last one used)")
+                .allow_invalid_utf8(true)
+                .required(false),
+        )
         .arg(
             arg!(--"backup-fallback" <TYPE> "Cartridge backup type to fallback to")
                 .value_parser([
@@ -197,52 +243,295 @@ fn main() -> Result<()> {
                 ])
                 .required(false),
         )
-        .arg(arg!(<ROM_FILE> "Cartridge ROM file to execute").allow_invalid_utf8(true))
         .arg(
-            arg!(--"frame-skip" <FRAMES> "Maximum frames to skip when behind")
-                .value_parser(value_parser!(u32))
-                .default_value("3")
+            arg!([ROM_FILE] "Cartridge ROM file to execute (opens a launcher if not given)")
+                .allow_invalid_utf8(true),
+        )
+        .arg(
+            arg!(--"save-dir" <DIR> "Directory for cartridge backups (battery saves)")
+                .allow_invalid_utf8(true)
+                .required(false),
+        )
+        .arg(
+            arg!(--"state-dir" <DIR> "Directory for save states")
+                .allow_invalid_utf8(true)
                 .required(false),
         )
-        .get_matches();
+        .arg(
+            arg!(--portable "Keep saves and save states beside the executable by default")
+                .required(false),
+        )
+        .arg(
+            arg!(--"frame-skip" <FRAMES> "Maximum frames to skip when behind, or \"auto\"")
+                .value_parser(|s: &str| s.parse::<frame_skip::Mode>())
+                .default_value("auto")
+                .required(false),
+        )
+        .arg(
+            arg!(--"frame-blend" <MODE> "Blend skipped frames into shown ones (average, min or max)")
+                .value_parser(|s: &str| s.parse::<BlendMode>())
+                .required(false),
+        )
+        .arg(
+            arg!(--"layer-windows" "Open windows showing each BG and OBJ layer separately")
+                .required(false),
+        )
+        .arg(arg!(--fullscreen "Start in fullscreen (toggle with F11)").required(false))
+        .arg(
+            arg!(--"display-mode" <MODE> "Use an exclusive fullscreen mode (e.g: 1920x1080@60)")
+                .value_parser(|s: &str| s.parse::<ModeSpec>())
+                .required(false),
+        )
+        .arg(arg!(--"perf-hud" "Show the performance HUD (toggle with F3)").required(false))
+        .arg(arg!(--"pause-on-focus-loss" "Pause while the window is unfocused").required(false))
+        .arg(
+            arg!(--"power-save" "Present fewer frames while the screen is still and silent")
+                .required(false),
+        )
+        .arg(
+            arg!(--"audio-filter" "Filter the audio output to sound closer to real hardware")
+                .required(false),
+        )
+        .arg(
+            arg!(--"audio-backend" <BACKEND> "How audio is given to SDL")
+                .value_parser(["queue", "callback"])
+                .default_value("queue")
+                .required(false),
+        )
+        .arg(arg!(--"input-overlay" "Show the keypad state over the screen").required(false))
+        .arg(
+            arg!(--"flicker-filter" "Show objects that flicker every other frame as translucent")
+                .required(false),
+        )
+        .arg(
+            arg!(--"turbo-interval" <FRAMES> "Frames between presses and releases of turbo buttons")
+                .value_parser(value_parser!(u32).range(1..))
+                .default_value("2")
+                .required(false),
+        )
+        .arg(
+            arg!(--overrides <FILE> "Per-game overrides file (TOML) to use")
+                .allow_invalid_utf8(true)
+                .required(false),
+        )
+        .arg(
+            arg!(--dat <FILE> "Dat file (e.g: from No-Intro) to identify the cartridge ROM with")
+                .allow_invalid_utf8(true)
+                .required(false),
+        )
+        .arg(
+            arg!(--hotkey <BINDING> "Bind a controller combo to an action (e.g: save-state=back+x)")
+                .value_parser(|s: &str| s.parse::<Binding>())
+                .multiple_occurrences(true)
+                .required(false),
+        )
+        .args(system_args())
+        .args(debug_args())
+}
 
-    let skip_bios = matches.is_present("skip-bios");
-    let bios_path = Path::new(matches.value_of_os("bios").unwrap());
-    let cart_fallback_backup_type =
-        matches
-            .get_one::<String>("backup-fallback")
-            .map(|s| match s.as_str() {
-                "none" => BackupType::None,
-                "eeprom-unknown" => BackupType::EepromUnknownSize,
-                "eeprom-512" => BackupType::Eeprom512B,
-                "eeprom-8k" => BackupType::Eeprom8KiB,
-                "sram-32k" => BackupType::Sram32KiB,
-                "flash-64k" => BackupType::Flash64KiB,
-                "flash-128k" => BackupType::Flash128KiB,
-                _ => unreachable!(),
-            });
-    let cart_path = Path::new(matches.value_of_os("ROM_FILE").unwrap());
-    let max_frame_skip = *matches.get_one::<u32>("frame-skip").unwrap();
+/// Arguments configuring the emulated system.
+fn system_args() -> [Arg<'static>; 6] {
+    [
+        arg!(--"skip-idle-loops" "Stop executing loops that busy-wait for an interrupt")
+            .required(false),
+        arg!(--"cpu-multiplier" <FACTOR> "Speed multiplier for the CPU (e.g: 2 to overclock)")
+            .value_parser(parse_cpu_multiplier)
+            .default_value("1")
+            .required(false),
+        arg!(--"wireless-adapter" "Connect a GBA Wireless Adapter to the link port")
+            .required(false),
+        arg!(--link <FILE> "Also run this ROM in a second window, linked by a link cable")
+            .allow_invalid_utf8(true)
+            .conflicts_with("wireless-adapter")
+            .required(false),
+        arg!(--"input-poll" <RATE> "How often to poll input (frame, half-frame or scanline)")
+            .value_parser(|s: &str| s.parse::<PollRate>())
+            .default_value("frame")
+            .required(false),
+        arg!(--patches <FILE> "ADDRESS:VALUE patches to apply to the ROM or hold in RAM")
+            .allow_invalid_utf8(true)
+            .required(false),
+    ]
+}
 
-    let bios_rom_buf = fs::read(bios_path).context("failed to read BIOS ROM file")?;
-    let bios_rom = bios::Rom::new(Rc::from(bios_rom_buf)).context("invalid BIOS ROM size")?;
+fn debug_args() -> [Arg<'static>; 6] {
+    [
+        arg!(--symbols <FILE> "Symbols file (.sym or .elf) to use for debug output")
+            .allow_invalid_utf8(true)
+            .required(false),
+        arg!(--"trace-io" <REGS> "Log accesses to IO registers (comma-separated)").required(false),
+        arg!(--"access-stats" <FILE> "Write memory access counts to a CSV (or .json) file on exit")
+            .allow_invalid_utf8(true)
+            .required(false),
+        arg!(--cdl <FILE> "Log how ROM bytes are used (code or data) to a file on exit")
+            .allow_invalid_utf8(true)
+            .required(false),
+        arg!(--profile <FILE> "Write a flamegraph-compatible profile of guest functions on exit")
+            .allow_invalid_utf8(true)
+            .required(false),
+        arg!(--console "Read debug commands (e.g: cheat searches) from stdin").required(false),
+    ]
+}
 
-    let cart_rom_buf = fs::read(cart_path).context("failed to read cartridge ROM file")?;
-    let cart_rom = cart::Rom::new(Rc::from(cart_rom_buf)).context("invalid cartridge ROM size")?;
-    let mut cart_backup_path = cart_path.to_owned();
-    cart_backup_path.set_extension("sav");
-    let cart = load_cart(cart_rom, &cart_backup_path, cart_fallback_backup_type);
+#[expect(clippy::too_many_lines)] // Just the startup steps, in order.
+fn main() -> Result<()> {
+    env_logger::builder()
+        .format_timestamp(None)
+        .parse_env(env_logger::Env::default().default_filter_or("info"))
+        .init();
+
+    let matches = cli().get_matches();
+    let dirs = Dirs::new(
+        matches.value_of_os("save-dir").map(PathBuf::from),
+        matches.value_of_os("state-dir").map(PathBuf::from),
+        matches.is_present("portable"),
+    )?;
+    let recent_path = dirs.recent_file();
+    let mut recent = recent_path.as_deref().map(Recent::load).unwrap_or_default();
 
-    let mut sdl = SdlContext::init()?;
-    let mut video_cb = VideoCallback::new(&sdl.win_texture_creator)?;
-    sdl.win_canvas.set_draw_color(Color::BLACK);
-    sdl.win_canvas.clear();
-    sdl.win_canvas.present();
+    // If no cartridge ROM was given, let the user pick one from the launcher. Otherwise, SDL is
+    // initialized after loading the system, so that errors loading it don't flash a window.
+    let (cart_path, sdl) = if let Some(path) = matches.value_of_os("ROM_FILE") {
+        (PathBuf::from(path), None)
+    } else {
+        let mut sdl = SdlContext::init()?;
+        let path = launcher::run(
+            &mut sdl.event_pump,
+            &mut sdl.win_canvas,
+            &mut sdl.controllers,
+            &recent.games,
+        );
+        let Some(path) = path else {
+            return Ok(());
+        };
+        (path, Some(sdl))
+    };
+    let bios_path = matches
+        .value_of_os("bios")
+        .map(PathBuf::from)
+        .or_else(|| recent.bios.clone())
+        .ok_or_else(|| anyhow!("no BIOS ROM file given"))?;
+
+    let (linked_emu, link_port) = link::spawn(&matches, &dirs, &bios_path)?.unzip();
+    let mut game_files = dirs.game_files(&cart_path);
+    let mut files = system_files(&matches, &bios_path, &cart_path, game_files.clone())?;
+    files.link_port = link_port;
+    let access_stats_path = matches.value_of_os("access-stats").map(PathBuf::from);
+    let cdl_path = files.cdl_path.clone();
+    let patches = files.patches.clone();
+    let profile_path = matches.value_of_os("profile").map(PathBuf::from);
+    let mut emu = EmuThread::spawn(
+        move || load_system(files),
+        move |gba| {
+            save_cart_backup(gba, &mut game_files);
+            if let Some(path) = access_stats_path {
+                save_access_stats(gba, &path);
+            }
+            if let Some(path) = cdl_path {
+                save_cdl(gba, &path);
+            }
+            if let Some(path) = profile_path {
+                save_profile(gba, &path);
+            }
+        },
+        emu_thread::Options {
+            capture_layers: matches.is_present("layer-windows"),
+            console: matches.is_present("console").then(Console::spawn),
+            ..emu_thread_options(&matches, dirs.game_files(&cart_path), patches)
+        },
+    )?;
+
+    let mut sdl = match sdl {
+        Some(sdl) => sdl,
+        None => SdlContext::init()?,
+    };
+    let mut frontend = Frontend::new(
+        &sdl.win_texture_creator,
+        &sdl.sdl_video,
+        sdl.controllers,
+        &matches,
+        &dirs,
+        cart_path.clone(),
+        &emu,
+        linked_emu,
+    )?;
+    if prepare_window(
+        &mut sdl.event_pump,
+        &mut sdl.win_canvas,
+        &mut frontend,
+        &matches,
+        &emu,
+    ) {
+        let (mut audio, resampler) = init_audio(sdl.sdl_audio.as_ref(), &matches);
+        emu.start(resampler);
+        recent.bios = Some(fs::canonicalize(&bios_path).unwrap_or(bios_path));
+        let mut session =
+            Session::start(recent, recent_path, cart_path, emu.game_title().to_string());
+        main_loop(
+            &mut sdl.event_pump,
+            &mut sdl.win_canvas,
+            &mut audio,
+            &mut emu,
+            &mut frontend,
+            &mut session,
+        );
+
+        session.end();
+    }
+    let linked_result = frontend
+        .linked
+        .take()
+        .map_or(Ok(()), |linked| linked.emu.join());
+    emu.join().and(linked_result)
+}
 
-    let mut gba = Gba::new(bios_rom, cart);
-    gba.reset(skip_bios);
+/// Readies the main window for the game, entering fullscreen and showing the splash if enabled.
+/// Returns false if the user quit.
+fn prepare_window(
+    event_pump: &mut EventPump,
+    canvas: &mut WindowCanvas,
+    frontend: &mut Frontend,
+    matches: &ArgMatches,
+    emu: &EmuThread,
+) -> bool {
+    if matches.is_present("fullscreen") {
+        if let Err(e) = frontend.fullscreen.set_enabled(canvas, true) {
+            error!("failed to enter fullscreen: {e}");
+        }
+    }
+    canvas.set_draw_color(Color::BLACK);
+    canvas.clear();
+    canvas.present();
 
-    let mut audio = Audio::new(sdl.sdl_audio.as_ref().map(|sdl_audio| {
+    !matches.is_present("splash") || splash::run(event_pump, canvas, emu.game_title())
+}
+
+/// Options for an emulation thread that neither captures layers nor reads debug commands.
+fn emu_thread_options(
+    matches: &ArgMatches,
+    game_files: GameFiles,
+    patches: Option<Patches>,
+) -> emu_thread::Options {
+    emu_thread::Options {
+        frame_skip_mode: *matches.get_one::<frame_skip::Mode>("frame-skip").unwrap(),
+        turbo_interval: *matches.get_one::<u32>("turbo-interval").unwrap(),
+        input_overlay: matches.is_present("input-overlay"),
+        flicker_filter: matches.is_present("flicker-filter"),
+        frame_blend: matches.get_one::<BlendMode>("frame-blend").copied(),
+        capture_layers: false,
+        power_save: matches.is_present("power-save"),
+        console: None,
+        game_files,
+        patches,
+    }
+}
+
+fn init_audio(sdl_audio: Option<&AudioSubsystem>, matches: &ArgMatches) -> (Audio, Resampler) {
+    let backend = match matches.get_one::<String>("audio-backend").unwrap().as_str() {
+        "callback" => audio::Backend::Callback,
+        _ => audio::Backend::Queue,
+    };
+    Audio::new(sdl_audio.map(|sdl_audio| {
         (
             sdl_audio,
             AudioSpecDesired {
@@ -250,134 +539,637 @@ fn main() -> Result<()> {
                 channels: Some(2),
                 samples: Some(2048),
             },
+            backend,
         )
     }))
-    .unwrap_or_else(|(e, audio)| {
+    .unwrap_or_else(|(e, audio, resampler)| {
         error!("failed to initialize audio: {e}");
-        audio
-    });
+        (audio, resampler)
+    })
+}
 
-    main_loop(
-        &mut sdl.event_pump,
-        &mut sdl.win_canvas,
-        &mut video_cb,
-        &mut audio,
-        &mut gba,
-        max_frame_skip,
+fn system_files(
+    matches: &ArgMatches,
+    bios_path: &Path,
+    cart_path: &Path,
+    game_files: GameFiles,
+) -> Result<SystemFiles> {
+    Ok(SystemFiles {
+        bios_path: bios_path.to_path_buf(),
+        cart_path: cart_path.to_path_buf(),
+        game_files,
+        cart_fallback_backup_type: matches
+            .get_one::<String>("backup-fallback")
+            .map(|s| parse_backup_type(s)),
+        overrides_path: matches.value_of_os("overrides").map(PathBuf::from),
+        dat_path: matches.value_of_os("dat").map(PathBuf::from),
+        symbols_path: matches.value_of_os("symbols").map(PathBuf::from),
+        trace_io_ranges: matches
+            .get_one::<String>("trace-io")
+            .map_or(Ok(Vec::new()), |regs| parse_io_ranges(regs))?,
+        skip_bios: matches.is_present("skip-bios"),
+        skip_idle_loops: matches.is_present("skip-idle-loops"),
+        count_accesses: matches.is_present("access-stats"),
+        cdl_path: matches.value_of_os("cdl").map(PathBuf::from),
+        profile: matches.is_present("profile"),
+        cpu_multiplier: *matches.get_one::<f32>("cpu-multiplier").unwrap(),
+        audio_filter: matches.is_present("audio-filter"),
+        wireless_adapter: matches.is_present("wireless-adapter"),
+        keypad_poll_rate: *matches.get_one::<PollRate>("input-poll").unwrap(),
+        patches: matches
+            .value_of_os("patches")
+            .map(|path| load_patches(Path::new(path)))
+            .transpose()?,
+        link_port: None,
+    })
+}
+
+/// Files and settings used to create the emulated system.
+#[expect(clippy::struct_excessive_bools)] // Not a state machine; just settings.
+struct SystemFiles {
+    bios_path: PathBuf,
+    cart_path: PathBuf,
+    game_files: GameFiles,
+    cart_fallback_backup_type: Option<BackupType>,
+    overrides_path: Option<PathBuf>,
+    dat_path: Option<PathBuf>,
+    symbols_path: Option<PathBuf>,
+    trace_io_ranges: Vec<RangeInclusive<u32>>,
+    skip_bios: bool,
+    skip_idle_loops: bool,
+    count_accesses: bool,
+    /// Code/data log to continue, if logging.
+    cdl_path: Option<PathBuf>,
+    profile: bool,
+    cpu_multiplier: f32,
+    audio_filter: bool,
+    wireless_adapter: bool,
+    keypad_poll_rate: PollRate,
+    patches: Option<Patches>,
+    /// End of a link cable to connect to the link port, for `--link` sessions.
+    link_port: Option<LinkPort>,
+}
+
+/// Creates the system, also returning the canonical name of its game if it was identified.
+fn load_system(mut files: SystemFiles) -> Result<(Gba, Option<String>)> {
+    let bios_rom_buf = fs::read(files.bios_path).context("failed to read BIOS ROM file")?;
+    let bios_rom = bios::Rom::new(Rc::from(bios_rom_buf)).context("invalid BIOS ROM size")?;
+    match bios_rom.verify() {
+        bios::Verification::Official => info!("using the official BIOS"),
+        verification => warn!("{verification} ({})", bios_rom.hashes()),
+    }
+
+    let cart_rom_buf = fs::read(&files.cart_path).context("failed to read cartridge ROM file")?;
+    let cart_rom = cart::Rom::new(Rc::from(cart_rom_buf)).context("invalid cartridge ROM size")?;
+    let user_overrides = files
+        .overrides_path
+        .as_deref()
+        .map_or(Ok(UserOverrides::new()), overrides::load_user_overrides)?;
+    let cart_overrides = overrides::cart_overrides(&cart_rom, &user_overrides);
+    let game_name = match files.dat_path {
+        Some(path) => identify_cart(&cart_rom, &path)?,
+        None => None,
+    };
+    // Patch after identifying the ROM, so it's still identified and given its overrides.
+    let cart_rom = match files.patches {
+        Some(ref patches) if patches.has_rom_patches() => {
+            let mut buf = cart_rom.bytes().to_vec();
+            patches.patch_rom(&mut buf);
+            cart::Rom::new(Rc::from(buf)).context("invalid cartridge ROM size")?
+        }
+        _ => cart_rom,
+    };
+    let cart = load_cart(
+        cart_rom,
+        &mut files.game_files,
+        files
+            .cart_fallback_backup_type
+            .or(cart_overrides.backup_type),
     );
+    let symbols = load_cart_symbols(&files.cart_path, files.symbols_path.as_deref())?;
 
-    if let Some(cart_backup_buf) = gba.cart.backup_buffer() {
-        info!(
-            "writing to cart backup file: {}",
-            cart_backup_path.to_string_lossy()
-        );
-        if let Err(e) = fs::write(cart_backup_path, cart_backup_buf) {
-            error!("failed to write backup file: {e}");
+    let mut gba = gba::Builder::new(bios_rom, cart)
+        .peripherals(Peripherals {
+            rtc: cart_overrides.rtc.unwrap_or(false),
+            rumble: cart_overrides.rumble.unwrap_or(false),
+            solar_sensor: cart_overrides.solar_sensor.unwrap_or(false),
+            sram_clock: cart_overrides.sram_clock,
+        })
+        .determinism(DeterminismConfig {
+            // Start the clock from the host's, as games expect it to keep counting while off.
+            rtc_epoch: SystemTime::now()
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .map_or(0, |time| time.as_secs()),
+        })
+        .skip_bios(files.skip_bios)
+        .cpu_multiplier(files.cpu_multiplier)
+        .skip_idle_loops(files.skip_idle_loops)
+        .audio_filter(files.audio_filter)
+        .keypad_poll_rate(files.keypad_poll_rate)
+        .build()?;
+    gba.debug.access_stats.set_enabled(files.count_accesses);
+    if let Some(path) = files.cdl_path {
+        gba.debug.cdl.set_enabled(true);
+        // Continue the existing log, if any, to accumulate coverage across sessions.
+        match fs::read(&path) {
+            Ok(buf) => gba.debug.cdl.merge(&buf),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e).context("failed to read code/data log file"),
+        }
+    }
+    if files.wireless_adapter {
+        gba.sio.device = Some(Box::new(sio::WirelessAdapter::new()));
+    }
+    if let Some(port) = files.link_port {
+        gba.sio.device = Some(Box::new(port));
+    }
+    gba.debug.profiler.set_enabled(files.profile);
+    gba.debug.symbols = symbols;
+    for range in files.trace_io_ranges {
+        gba.debug.io_trace.add_range(range);
+    }
+
+    Ok((gba, game_name))
+}
+
+fn load_patches(path: &Path) -> Result<Patches> {
+    let text = fs::read_to_string(path).context("failed to read patches file")?;
+    let patches = Patches::parse(&text).context("failed to parse patches file")?;
+    info!("loaded {} patches", patches.0.len());
+
+    Ok(patches)
+}
+
+/// Looks up the cartridge ROM in the dat file at `path`, returning the game's canonical name.
+fn identify_cart(rom: &cart::Rom, path: &Path) -> Result<Option<String>> {
+    let text = fs::read_to_string(path).context("failed to read dat file")?;
+    let db = dat::Database::parse(&text).context("failed to parse dat file")?;
+    info!("cartridge ROM hashes: {}", rom.hashes());
+
+    let Some(identity) = db.identify(rom) else {
+        warn!("cartridge ROM isn't in the dat file; it may be a bad dump, hack or homebrew");
+        return Ok(None);
+    };
+    let name = &identity.entry.name;
+    match identity.status {
+        dat::Status::Good => info!("identified cartridge ROM: {name}"),
+        dat::Status::BadDump => warn!("cartridge ROM is a known bad dump of {name}"),
+        dat::Status::Overdump => {
+            warn!("cartridge ROM is an overdump of {name}, with extra data at the end");
+        }
+    }
+
+    Ok(Some(name.clone()))
+}
+
+fn save_cart_backup(gba: &Gba, game_files: &mut GameFiles) {
+    if let Err(e) = storage::flush_backup(gba, game_files) {
+        error!("failed to write backup file: {e}");
+    }
+}
+
+fn save_cdl(gba: &Gba, path: &Path) {
+    info!("writing code/data log: {}", path.to_string_lossy());
+    let rom_len = gba.cart.rom().bytes().len();
+    let result = File::create(path).and_then(|file| {
+        let mut w = BufWriter::new(file);
+        gba.debug.cdl.write(&mut w, rom_len)?;
+        w.flush()
+    });
+    if let Err(e) = result {
+        error!("failed to write code/data log: {e}");
+    }
+}
+
+fn save_profile(gba: &Gba, path: &Path) {
+    info!("writing profile: {}", path.to_string_lossy());
+    let result = File::create(path).and_then(|file| {
+        let mut w = BufWriter::new(file);
+        gba.debug
+            .profiler
+            .write_folded(&mut w, &gba.debug.symbols)?;
+        w.flush()
+    });
+    if let Err(e) = result {
+        error!("failed to write profile: {e}");
+    }
+}
+
+fn save_access_stats(gba: &Gba, path: &Path) {
+    info!("writing memory access stats: {}", path.to_string_lossy());
+    let result = File::create(path).and_then(|file| {
+        let mut w = BufWriter::new(file);
+        if path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("json"))
+        {
+            gba.debug.access_stats.write_json(&mut w)?;
+        } else {
+            gba.debug.access_stats.write_csv(&mut w)?;
         }
+        w.flush()
+    });
+    if let Err(e) = result {
+        error!("failed to write memory access stats: {e}");
+    }
+}
+
+/// Parses a backup type accepted by the "backup-fallback" argument.
+fn parse_backup_type(s: &str) -> BackupType {
+    match s {
+        "none" => BackupType::None,
+        "eeprom-unknown" => BackupType::EepromUnknownSize,
+        "eeprom-512" => BackupType::Eeprom512B,
+        "eeprom-8k" => BackupType::Eeprom8KiB,
+        "sram-32k" => BackupType::Sram32KiB,
+        "flash-64k" => BackupType::Flash64KiB,
+        "flash-128k" => BackupType::Flash128KiB,
+        _ => unreachable!(),
+    }
+}
+
+fn parse_cpu_multiplier(s: &str) -> Result<f32> {
+    match s.parse() {
+        Ok(multiplier) if f32::is_finite(multiplier) && multiplier > 0.0 => Ok(multiplier),
+        _ => Err(anyhow!("must be a positive number")),
     }
+}
+
+fn parse_io_ranges(regs: &str) -> Result<Vec<RangeInclusive<u32>>> {
+    let ranges = regs
+        .split(',')
+        .map(|reg| {
+            debug::io::parse_addr_ranges(reg)
+                .ok_or_else(|| anyhow!("unknown IO register or address range: {reg}"))
+        })
+        .collect::<Result<Vec<_>>>()?;
 
-    Ok(())
+    Ok(ranges.into_iter().flatten().collect())
 }
 
-fn update_keypad(kp: &mut Keypad, kb: &KeyboardState) {
+/// Updates the keypad from the keyboard and from the controller buttons for which `pad` returns
+/// true.
+fn update_keypad(
+    kp: &mut Keypad,
+    turbo: &mut Turbo,
+    kb: &KeyboardState,
+    pad: impl Fn(Button) -> bool,
+) {
     let pressed = |scancode| kb.is_scancode_pressed(scancode);
 
-    kp.set_pressed(Key::A, pressed(Scancode::X));
-    kp.set_pressed(Key::B, pressed(Scancode::Z));
+    kp.set_pressed(Key::A, pressed(Scancode::X) || pad(Button::A));
+    kp.set_pressed(Key::B, pressed(Scancode::Z) || pad(Button::B));
 
     kp.set_pressed(
         Key::Select,
-        pressed(Scancode::LShift) || pressed(Scancode::RShift),
+        pressed(Scancode::LShift) || pressed(Scancode::RShift) || pad(Button::Back),
     );
-    kp.set_pressed(Key::Start, pressed(Scancode::Return));
+    kp.set_pressed(Key::Start, pressed(Scancode::Return) || pad(Button::Start));
 
-    kp.set_pressed(Key::Up, pressed(Scancode::Up));
-    kp.set_pressed(Key::Down, pressed(Scancode::Down));
-    kp.set_pressed(Key::Left, pressed(Scancode::Left));
-    kp.set_pressed(Key::Right, pressed(Scancode::Right));
+    kp.set_pressed(Key::Up, pressed(Scancode::Up) || pad(Button::DPadUp));
+    kp.set_pressed(Key::Down, pressed(Scancode::Down) || pad(Button::DPadDown));
+    kp.set_pressed(Key::Left, pressed(Scancode::Left) || pad(Button::DPadLeft));
+    kp.set_pressed(
+        Key::Right,
+        pressed(Scancode::Right) || pad(Button::DPadRight),
+    );
+
+    kp.set_pressed(Key::L, pressed(Scancode::A) || pad(Button::LeftShoulder));
+    kp.set_pressed(Key::R, pressed(Scancode::S) || pad(Button::RightShoulder));
+
+    turbo.set_held(Key::A, pressed(Scancode::C) || pad(Button::X));
+    turbo.set_held(Key::B, pressed(Scancode::V) || pad(Button::Y));
+}
+
+/// Updates the keypad and triggers any hotkeys from the state of the keyboard and controllers.
+fn update_input(
+    event_pump: &EventPump,
+    win_canvas: &mut WindowCanvas,
+    emu: &EmuThread,
+    frontend: &mut Frontend,
+) {
+    let actions = frontend
+        .hotkeys
+        .update(|button| frontend.controllers.is_held(button));
+    for action in actions {
+        run_action(action, win_canvas, emu, frontend);
+    }
+
+    // In `--link` sessions, only the system whose window has focus gets the input.
+    let (emu, unfocused_emu) = match frontend.linked {
+        Some(ref linked) if linked.focused => (&linked.emu, Some(emu)),
+        Some(ref linked) => (emu, Some(&linked.emu)),
+        None => (emu, None),
+    };
+    if let Some(unfocused_emu) = unfocused_emu {
+        let mut input = unfocused_emu.input.lock().unwrap();
+        input.keypad.set_pressed_keys([]);
+        input.turbo = Turbo::new(input.turbo.interval());
+    }
+
+    let mut input = emu.input.lock().unwrap();
+    let emu_thread::Input { keypad, turbo, .. } = &mut *input;
+    update_keypad(keypad, turbo, &event_pump.keyboard_state(), |button| {
+        frontend.controllers.is_held(button) && !frontend.hotkeys.is_consumed(button)
+    });
+}
+
+fn run_action(
+    action: Action,
+    win_canvas: &mut WindowCanvas,
+    emu: &EmuThread,
+    frontend: &mut Frontend,
+) {
+    match action {
+        Action::SaveState => emu.send(emu_thread::Command::SaveState),
+        Action::LoadState => emu.send(emu_thread::Command::LoadState),
+        Action::FastForward => {
+            let mut input = emu.input.lock().unwrap();
+            input.fast_forward = !input.fast_forward;
+            info!(
+                "fast-forward {}",
+                if input.fast_forward { "on" } else { "off" }
+            );
+        }
+        Action::Screenshot => save_screenshot(&frontend.screen, &frontend.cart_path),
+        Action::Fullscreen => {
+            if let Err(e) = frontend.fullscreen.toggle(win_canvas) {
+                error!("failed to toggle fullscreen: {e}");
+            }
+        }
+        Action::RecordMacro => {
+            let mut input = emu.input.lock().unwrap();
+            if input.key_macro.is_recording() {
+                input.key_macro.stop_recording();
+                let frames = input.key_macro.frames().len();
+                info!("recorded a {frames} frame macro");
+                frontend.game_macro.save(&input.key_macro);
+            } else {
+                input.key_macro.start_recording();
+                info!("recording macro");
+            }
+        }
+        Action::PlayMacro => emu.input.lock().unwrap().key_macro.play(),
+        Action::SoftReset => emu.send(emu_thread::Command::SoftReset),
+        Action::Reset => emu.send(emu_thread::Command::Reset {
+            skip_bios: frontend.skip_bios,
+        }),
+        Action::ResetToBios => emu.send(emu_thread::Command::Reset { skip_bios: false }),
+    }
+}
+
+/// Saves `screen` as a BMP file alongside the cartridge ROM, numbered to not overwrite previous
+/// screenshots.
+fn save_screenshot(screen: &FrameBuffer, cart_path: &Path) {
+    let stem = cart_path.file_stem().unwrap_or_default().to_string_lossy();
+    let Some(path) = (1..10_000)
+        .map(|i| cart_path.with_file_name(format!("{stem}-{i}.bmp")))
+        .find(|path| !path.exists())
+    else {
+        return;
+    };
+
+    let mut buf = screen.0.clone();
+    let result = Surface::from_data(
+        &mut buf,
+        HBLANK_DOT.into(),
+        VBLANK_DOT.into(),
+        3 * u32::from(HBLANK_DOT),
+        PixelFormatEnum::RGB24,
+    )
+    .and_then(|surface| surface.save_bmp(&path));
+    match result {
+        Ok(()) => info!("saved screenshot: {}", path.to_string_lossy()),
+        Err(e) => error!("failed to save screenshot: {e}"),
+    }
+}
+
+/// Frontend state used by the main loop, other than that of SDL and the emulated system.
+struct Frontend<'r> {
+    texture: Texture<'r>,
+    layer_windows: Option<LayerWindows>,
+    /// Second system of a `--link` session.
+    linked: Option<LinkedSystem>,
+    perf_hud: PerfHud,
+    controllers: Controllers,
+    hotkeys: Hotkeys,
+    /// Copy of the last presented frame, for screenshots.
+    screen: FrameBuffer,
+    cart_path: PathBuf,
+    /// Whether `Action::Reset` skips the BIOS.
+    skip_bios: bool,
+    game_macro: GameMacro,
+    fullscreen: Fullscreen,
+    /// Longest time to wait for a frame before handling events and updating input again, so that
+    /// input is fresh for each poll by the emulation thread.
+    input_poll_interval: Duration,
+    pause_on_focus_loss: bool,
+}
+
+impl<'r> Frontend<'r> {
+    /// Also loads the game's input macro into `emu`'s input.
+    #[expect(clippy::too_many_arguments)]
+    fn new(
+        texture_creator: &'r TextureCreator<WindowContext>,
+        sdl_video: &VideoSubsystem,
+        controllers: Controllers,
+        matches: &ArgMatches,
+        dirs: &Dirs,
+        cart_path: PathBuf,
+        emu: &EmuThread,
+        linked_emu: Option<EmuThread>,
+    ) -> Result<Self> {
+        let game_code = emu.rom_header.as_ref().and_then(|h| h.game_code.clone());
+        let game_macro = GameMacro::new(dirs.macros_file(), game_code);
+        emu.input.lock().unwrap().key_macro = game_macro.load();
 
-    kp.set_pressed(Key::L, pressed(Scancode::A));
-    kp.set_pressed(Key::R, pressed(Scancode::S));
+        Ok(Self {
+            texture: texture_creator
+                .create_texture_streaming(
+                    PixelFormatEnum::RGB24,
+                    HBLANK_DOT.into(),
+                    VBLANK_DOT.into(),
+                )
+                .context("failed to create screen texture")?,
+            layer_windows: if matches.is_present("layer-windows") {
+                Some(LayerWindows::new(sdl_video)?)
+            } else {
+                None
+            },
+            linked: linked_emu
+                .map(|emu| LinkedSystem::new(emu, sdl_video))
+                .transpose()?,
+            perf_hud: PerfHud::new(matches.is_present("perf-hud")),
+            controllers,
+            hotkeys: Hotkeys::new(
+                matches
+                    .get_many::<Binding>("hotkey")
+                    .unwrap_or_default()
+                    .cloned(),
+            ),
+            screen: FrameBuffer::default(),
+            cart_path,
+            skip_bios: matches.is_present("skip-bios"),
+            game_macro,
+            fullscreen: Fullscreen::new(
+                sdl_video.clone(),
+                matches.get_one::<ModeSpec>("display-mode").copied(),
+            ),
+            // Polling SDL more often than this would mostly burn CPU time.
+            input_poll_interval: (FRAME_DURATION
+                / matches
+                    .get_one::<PollRate>("input-poll")
+                    .unwrap()
+                    .polls_per_frame())
+            .max(Duration::from_millis(1)),
+            pause_on_focus_loss: matches.is_present("pause-on-focus-loss"),
+        })
+    }
 }
 
 fn main_loop(
     event_pump: &mut EventPump,
     win_canvas: &mut WindowCanvas,
-    video_cb: &mut VideoCallback,
     audio: &mut Audio,
-    gba: &mut Gba,
-    max_frame_skip: u32,
+    emu: &mut EmuThread,
+    frontend: &mut Frontend,
+    session: &mut Session,
 ) {
-    const FRAME_DURATION: Duration = Duration::from_nanos(1_000_000_000 / 60);
-
-    let mut next_redraw_time = Instant::now() + FRAME_DURATION;
-    let mut next_second_time = Instant::now() + Duration::from_secs(1);
-    let (mut frame_counter, mut unskipped_frame_counter) = (0u32, 0u32);
+    let epoch = Instant::now();
+    let mut fps_counter = FpsCounter::new();
     let mut title_text_buf = String::new();
+    let app_title = match emu.game_title() {
+        "" => "Memetendo Unsafe Boy Advance".to_string(),
+        title => format!("{title} | Memetendo Unsafe Boy Advance"),
+    };
+    win_canvas.window_mut().set_title(&app_title).unwrap();
 
-    'main_loop: loop {
-        {
-            let now = Instant::now();
-            if now >= next_second_time {
-                title_text_buf.clear();
-                write!(
-                    &mut title_text_buf,
-                    "Memetendo Unsafe Boy Advance | FPS: {unskipped_frame_counter}"
-                )
-                .unwrap();
-                if frame_counter != unskipped_frame_counter {
-                    write!(&mut title_text_buf, " ({frame_counter})").unwrap();
-                }
-
-                win_canvas.window_mut().set_title(&title_text_buf).unwrap();
-                next_second_time = now + Duration::from_secs(1);
-                (frame_counter, unskipped_frame_counter) = (0, 0);
-            }
+    loop {
+        session.update();
+        if let Some(fps) = fps_counter.poll(epoch.elapsed()) {
+            title_text_buf.clear();
+            write!(&mut title_text_buf, "{app_title} | {fps}").unwrap();
+            win_canvas.window_mut().set_title(&title_text_buf).unwrap();
         }
 
-        let mut skipped_frames = 0;
-        loop {
-            video_cb.frame_skipping = skipped_frames > 0;
-            while !take(&mut video_cb.new_frame) {
-                gba.step(video_cb, audio);
-            }
-            if let Err(e) = audio.queue_samples() {
-                warn!("failed to queue audio samples: {e}");
-            }
+        // Wait for the next frame, but not for so long that handling events is delayed if
+        // emulation falls behind, or that input is stale when polled.
+        let mut perf_sample = None;
+        if let Some(frame) = emu.frames.wait_new(frontend.input_poll_interval) {
+            fps_counter.push_frames(1, frame.emulated_frames);
 
-            if skipped_frames == 0 {
-                unskipped_frame_counter += 1;
+            if let Err(e) = frontend.texture.with_lock(None, |texture_buf, _| {
+                texture_buf.copy_from_slice(&frame.screen.0);
+            }) {
+                warn!("failed to lock screen texture: {e}");
             }
-            frame_counter += 1;
-
-            let rem_time = next_redraw_time - Instant::now();
-            next_redraw_time += FRAME_DURATION;
-            if rem_time > Duration::ZERO {
-                sleep(rem_time);
-                break;
+            frontend.screen.0.copy_from_slice(&frame.screen.0);
+            if let (Some(layer_windows), Some(layers)) =
+                (frontend.layer_windows.as_mut(), frame.layers.as_ref())
+            {
+                layer_windows.present(layers);
             }
 
-            if skipped_frames >= max_frame_skip {
+            perf_sample = Some(perf_hud::Sample {
+                emulation: frame.emulation_time,
+                ..perf_hud::Sample::default()
+            });
+        }
+        if let Err(e) = audio.queue_samples() {
+            warn!("failed to queue audio samples: {e}");
+        }
+
+        if let Some(ref mut linked) = frontend.linked {
+            linked.present();
+            if linked.emu.is_finished() {
                 break;
             }
-            skipped_frames += 1;
         }
+        if !handle_events(event_pump, win_canvas, audio, emu, frontend) || emu.is_finished() {
+            break;
+        }
+        update_input(event_pump, win_canvas, emu, frontend);
 
-        for event in event_pump.poll_iter() {
-            if let Event::Quit { .. } = event {
-                break 'main_loop;
+        if let Some(mut perf_sample) = perf_sample {
+            let render_start_time = Instant::now();
+            win_canvas.clear();
+            let screen_rect = frontend.fullscreen.screen_rect(win_canvas);
+            if let Err(e) = win_canvas.copy(&frontend.texture, None, screen_rect) {
+                warn!("failed to draw screen texture: {e}");
             }
+            frontend.perf_hud.draw(win_canvas);
+            win_canvas.present();
+            perf_sample.render = render_start_time.elapsed();
+            perf_sample.audio_queue = audio.queue_depth();
+            frontend.perf_hud.push(perf_sample);
         }
-        update_keypad(&mut gba.keypad, &event_pump.keyboard_state());
+    }
+}
 
-        win_canvas.clear();
-        if let Err(e) = win_canvas.copy(&video_cb.texture, None, None) {
-            warn!("failed to draw screen texture: {e}");
+/// Handles pending SDL events. Returns `false` if the main loop should exit.
+fn handle_events(
+    event_pump: &mut EventPump,
+    win_canvas: &mut WindowCanvas,
+    audio: &Audio,
+    emu: &EmuThread,
+    frontend: &mut Frontend,
+) -> bool {
+    for event in event_pump.poll_iter() {
+        frontend.controllers.handle_event(&event);
+        if let Some(ref mut linked) = frontend.linked {
+            linked.handle_event(&event);
         }
-        win_canvas.present();
-
-        if skipped_frames >= max_frame_skip {
-            next_redraw_time = Instant::now() + FRAME_DURATION;
+        match event {
+            Event::Quit { .. } => return false,
+            // With multiple windows open, closing the main window doesn't cause Event::Quit.
+            Event::Window {
+                window_id,
+                win_event: WindowEvent::Close,
+                ..
+            } => {
+                let layer_windows = frontend.layer_windows.as_mut();
+                if !layer_windows.is_some_and(|windows| windows.close(window_id)) {
+                    return false;
+                }
+            }
+            // Moving focus between our own windows loses and then regains it, so this only stays
+            // paused while another application is focused.
+            Event::Window {
+                win_event: win_event @ (WindowEvent::FocusLost | WindowEvent::FocusGained),
+                ..
+            } if frontend.pause_on_focus_loss => {
+                let paused = win_event == WindowEvent::FocusLost;
+                emu.input.lock().unwrap().paused = paused;
+                if let Some(ref linked) = frontend.linked {
+                    linked.emu.input.lock().unwrap().paused = paused;
+                }
+                audio.set_paused(paused);
+            }
+            Event::KeyDown {
+                scancode: Some(Scancode::F3),
+                repeat: false,
+                ..
+            } => frontend.perf_hud.enabled = !frontend.perf_hud.enabled,
+            Event::KeyDown {
+                scancode: Some(scancode),
+                repeat: false,
+                ..
+            } => {
+                let action = match scancode {
+                    Scancode::F5 => Action::SaveState,
+                    Scancode::F6 => Action::RecordMacro,
+                    Scancode::F7 => Action::PlayMacro,
+                    Scancode::F8 => Action::LoadState,
+                    Scancode::Tab => Action::FastForward,
+                    Scancode::F11 => Action::Fullscreen,
+                    Scancode::F12 => Action::Screenshot,
+                    _ => continue,
+                };
+                run_action(action, win_canvas, emu, frontend);
+            }
+            _ => {}
         }
     }
+
+    true
 }
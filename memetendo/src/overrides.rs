@@ -0,0 +1,67 @@
+use std::{collections::HashMap, fs, path::Path};
+
+use anyhow::{Context, Result};
+use libmemetendo::cart::{self, overrides::Overrides};
+use log::{info, warn};
+
+/// User overrides keyed by game code, parsed from a TOML file like:
+///
+/// ```toml
+/// [AXVE]
+/// backup-type = "flash-128k"
+/// rtc = true
+/// ```
+///
+/// Carts with unsupported backup hardware that isn't detected can be treated as having none with
+/// `backup-type = "none"`. ROM hacks that read the time from save memory can be given a clock at
+/// an offset into it with, for example, `sram-clock = 0x7ff0`.
+pub type UserOverrides = HashMap<String, Overrides>;
+
+pub fn load_user_overrides(path: &Path) -> Result<UserOverrides> {
+    let s = fs::read_to_string(path).context("failed to read overrides file")?;
+    toml::from_str(&s).context("failed to parse overrides file")
+}
+
+/// Returns the overrides for `rom`: the built-in ones merged with those from `user_overrides`,
+/// which take precedence. Logs the overrides that are applied.
+pub fn cart_overrides(rom: &cart::Rom, user_overrides: &UserOverrides) -> Overrides {
+    let Some(game_code) = rom.game_code() else {
+        return Overrides::default();
+    };
+
+    let builtin = Overrides::builtin(game_code).unwrap_or_default();
+    let user = user_overrides.get(game_code).copied().unwrap_or_default();
+    let overrides = builtin.merge(user);
+    if overrides.is_empty() {
+        return overrides;
+    }
+
+    let source = match (builtin.is_empty(), user.is_empty()) {
+        (false, false) => "built-in and user",
+        (true, false) => "user",
+        _ => "built-in",
+    };
+    info!("applying {source} overrides for game code {game_code}");
+    if let Some(backup_type) = overrides.backup_type {
+        info!("overriding backup type: {backup_type:?}");
+    }
+    if let Some(offset) = overrides.sram_clock {
+        info!("keeping a clock in save memory at offset {offset:#06x}");
+    }
+
+    let unsupported: Vec<_> = [
+        ("rtc", overrides.rtc),
+        ("rumble", overrides.rumble),
+        ("solar-sensor", overrides.solar_sensor),
+        ("color-correction", overrides.color_correction),
+    ]
+    .into_iter()
+    .filter(|&(_, value)| value.is_some())
+    .map(|(name, _)| name)
+    .collect();
+    if !unsupported.is_empty() {
+        warn!("ignoring unsupported overrides: {}", unsupported.join(", "));
+    }
+
+    overrides
+}
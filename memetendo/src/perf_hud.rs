@@ -0,0 +1,133 @@
+use std::{collections::VecDeque, time::Duration};
+
+use log::warn;
+use sdl2::{
+    pixels::Color,
+    rect::{Point, Rect},
+    render::{BlendMode, WindowCanvas},
+};
+
+/// Number of samples kept; about 3 seconds worth at 60 FPS.
+const SAMPLE_CAPACITY: usize = 180;
+
+/// Frame time at the top of the graph; twice the duration of a frame at 60 FPS.
+const GRAPH_MAX_TIME: Duration = Duration::from_nanos(2 * 1_000_000_000 / 60);
+
+/// Audio queue depth at the top of the graph, as a multiple of SDL's audio buffer size.
+const GRAPH_MAX_AUDIO_QUEUE: f32 = 2.0;
+
+#[derive(Debug, Default, Copy, Clone)]
+pub struct Sample {
+    /// Time spent emulating, including any skipped frames.
+    pub emulation: Duration,
+    /// Time spent drawing to and presenting the window.
+    pub render: Duration,
+    /// Amount of queued audio as a multiple of SDL's audio buffer size, if audio is enabled.
+    pub audio_queue: Option<f32>,
+}
+
+/// Graphs frame times and audio queue depth over the last few seconds, to help diagnose stutter.
+///
+/// Emulation time is drawn in green, with render time stacked on top in red. The white line marks
+/// the duration of a frame at 60 FPS, which the bars should stay below. Audio queue depth is drawn
+/// as a yellow line; if it reaches the bottom of the graph, audio is likely to crackle.
+pub struct PerfHud {
+    pub enabled: bool,
+    samples: VecDeque<Sample>,
+}
+
+impl PerfHud {
+    pub fn new(enabled: bool) -> Self {
+        Self {
+            enabled,
+            samples: VecDeque::with_capacity(SAMPLE_CAPACITY),
+        }
+    }
+
+    pub fn push(&mut self, sample: Sample) {
+        if self.samples.len() == SAMPLE_CAPACITY {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(sample);
+    }
+
+    pub fn draw(&self, canvas: &mut WindowCanvas) {
+        if !self.enabled {
+            return;
+        }
+        let (old_draw_color, old_blend_mode) = (canvas.draw_color(), canvas.blend_mode());
+        if let Err(e) = self.try_draw(canvas) {
+            warn!("failed to draw performance HUD: {e}");
+        }
+        canvas.set_draw_color(old_draw_color);
+        canvas.set_blend_mode(old_blend_mode);
+    }
+
+    #[expect(
+        clippy::cast_possible_truncation,
+        clippy::cast_possible_wrap,
+        clippy::cast_precision_loss,
+        clippy::cast_sign_loss
+    )]
+    fn try_draw(&self, canvas: &mut WindowCanvas) -> Result<(), String> {
+        let (width, height) = canvas.output_size()?;
+        let graph_height = height / 3;
+        let graph_top = (height - graph_height) as i32;
+        let bar_width = width as f32 / SAMPLE_CAPACITY as f32;
+
+        let time_height = |time: Duration| {
+            let ratio = time.as_secs_f32() / GRAPH_MAX_TIME.as_secs_f32();
+            (ratio.min(1.0) * graph_height as f32) as u32
+        };
+        let bar_rect = |i: usize, bottom: u32, height: u32| {
+            let x = (i as f32 * bar_width) as i32;
+            let next_x = ((i + 1) as f32 * bar_width) as i32;
+            Rect::new(
+                x,
+                graph_top + (graph_height - bottom - height) as i32,
+                (next_x - x).max(1) as u32,
+                height,
+            )
+        };
+
+        canvas.set_blend_mode(BlendMode::Blend);
+        canvas.set_draw_color(Color::RGBA(0, 0, 0, 160));
+        canvas.fill_rect(Rect::new(0, graph_top, width, graph_height))?;
+        canvas.set_blend_mode(BlendMode::None);
+
+        for (i, sample) in self.samples.iter().enumerate() {
+            let emulation_height = time_height(sample.emulation);
+            let render_height = time_height(sample.render).min(graph_height - emulation_height);
+
+            if emulation_height > 0 {
+                canvas.set_draw_color(Color::GREEN);
+                canvas.fill_rect(bar_rect(i, 0, emulation_height))?;
+            }
+            if render_height > 0 {
+                canvas.set_draw_color(Color::RED);
+                canvas.fill_rect(bar_rect(i, emulation_height, render_height))?;
+            }
+        }
+
+        let target_y = graph_top + (graph_height - time_height(GRAPH_MAX_TIME / 2)) as i32;
+        canvas.set_draw_color(Color::WHITE);
+        canvas.draw_line((0, target_y), (width as i32, target_y))?;
+
+        let audio_points: Vec<_> = self
+            .samples
+            .iter()
+            .enumerate()
+            .filter_map(|(i, sample)| {
+                let ratio = (sample.audio_queue? / GRAPH_MAX_AUDIO_QUEUE).min(1.0);
+                Some(Point::new(
+                    ((i as f32 + 0.5) * bar_width) as i32,
+                    graph_top + ((1.0 - ratio) * graph_height as f32) as i32,
+                ))
+            })
+            .collect();
+        canvas.set_draw_color(Color::YELLOW);
+        canvas.draw_lines(&audio_points[..])?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,197 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    time::{Duration, Instant, SystemTime},
+};
+
+use anyhow::{Context, Result};
+use log::{error, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::dirs;
+
+/// How often the play time of the current session is saved, so that little is lost if the
+/// frontend crashes.
+const SAVE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Played games and their statistics, most recently played first, persisted as TOML in the data
+/// directory. Games are never forgotten, so that their statistics are kept.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Recent {
+    /// The BIOS ROM file used last; used if one isn't given.
+    pub bios: Option<PathBuf>,
+    #[serde(default, rename = "game")]
+    pub games: Vec<Game>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Game {
+    pub path: PathBuf,
+    /// Title from the ROM header, if any.
+    #[serde(default)]
+    pub title: String,
+    /// Total time played, in seconds.
+    #[serde(default)]
+    pub play_time: u64,
+    /// When the game was last played, in seconds since the Unix epoch; 0 if unknown.
+    #[serde(default)]
+    pub last_played: u64,
+}
+
+impl Game {
+    /// Returns the title to show for the game: the one from its header, or its file name.
+    pub fn display_title(&self) -> String {
+        if self.title.is_empty() {
+            self.path
+                .file_stem()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .into_owned()
+        } else {
+            self.title.clone()
+        }
+    }
+}
+
+impl Recent {
+    /// Loads the list from `path`, or returns an empty list if it doesn't exist or is invalid.
+    pub fn load(path: &Path) -> Self {
+        let s = match fs::read_to_string(path) {
+            Ok(s) => s,
+            Err(e) => {
+                if e.kind() != io::ErrorKind::NotFound {
+                    warn!("failed to read recent games file: {e}");
+                }
+                return Self::default();
+            }
+        };
+
+        toml::from_str(&s).unwrap_or_else(|e| {
+            warn!("failed to parse recent games file: {e}");
+            Self::default()
+        })
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let s = toml::to_string(self).context("failed to serialize recent games")?;
+        dirs::create_parent(path)
+            .and_then(|()| fs::write(path, s))
+            .context("failed to write recent games file")
+    }
+
+    /// Moves the game at `path` to the front of the list (adding it if needed), adding `time` to
+    /// its play time and updating when it was last played.
+    pub fn played(&mut self, path: &Path, title: &str, time: Duration) {
+        let path = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+        let mut game = match self.games.iter().position(|game| game.path == path) {
+            Some(i) => self.games.remove(i),
+            None => Game {
+                path,
+                title: String::new(),
+                play_time: 0,
+                last_played: 0,
+            },
+        };
+        title.clone_into(&mut game.title);
+        game.play_time += time.as_secs();
+        game.last_played = unix_time();
+
+        self.games.insert(0, game);
+    }
+}
+
+/// Accounts for the time spent playing a game, adding it to the game's statistics in `Recent`
+/// periodically while it's played, and when the session ends.
+pub struct Session {
+    recent: Recent,
+    /// Where to save `Self::recent`, if there's a data directory.
+    recent_path: Option<PathBuf>,
+    cart_path: PathBuf,
+    title: String,
+    last_update: Instant,
+    /// Play time not yet added to `Self::recent`.
+    unsaved: Duration,
+}
+
+impl Session {
+    /// Starts a session for the game at `cart_path`, immediately saving it as the most recently
+    /// played game.
+    pub fn start(
+        recent: Recent,
+        recent_path: Option<PathBuf>,
+        cart_path: PathBuf,
+        title: String,
+    ) -> Self {
+        let mut session = Self {
+            recent,
+            recent_path,
+            cart_path,
+            title,
+            last_update: Instant::now(),
+            unsaved: Duration::ZERO,
+        };
+        session.save();
+
+        session
+    }
+
+    /// Accounts for the time since the last update; called every iteration of the main loop.
+    pub fn update(&mut self) {
+        let now = Instant::now();
+        self.unsaved += now - self.last_update;
+        self.last_update = now;
+        if self.unsaved >= SAVE_INTERVAL {
+            self.save();
+        }
+    }
+
+    /// Ends the session, saving the remaining play time.
+    pub fn end(mut self) {
+        self.update();
+        self.save();
+    }
+
+    fn save(&mut self) {
+        // Play time is counted in whole seconds, so carry over the rest to the next save.
+        let time = Duration::from_secs(self.unsaved.as_secs());
+        self.unsaved -= time;
+        self.recent.played(&self.cart_path, &self.title, time);
+
+        if let Some(ref path) = self.recent_path {
+            if let Err(e) = self.recent.save(path) {
+                error!("{e:#}");
+            }
+        }
+    }
+}
+
+fn unix_time() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |time| time.as_secs())
+}
+
+/// Formats a play time in seconds like "1H 05M".
+pub fn format_play_time(secs: u64) -> String {
+    let (hours, mins) = (secs / 3600, secs / 60 % 60);
+    if hours > 0 {
+        format!("{hours}H {mins:02}M")
+    } else {
+        format!("{mins}M")
+    }
+}
+
+/// Formats when a game was last played (in seconds since the Unix epoch) relative to now, like "3
+/// DAYS AGO", or returns `None` if it's unknown.
+pub fn format_last_played(last_played: u64) -> Option<String> {
+    if last_played == 0 {
+        return None;
+    }
+
+    let days = unix_time().saturating_sub(last_played) / (24 * 60 * 60);
+    Some(match days {
+        0 => "TODAY".to_string(),
+        1 => "YESTERDAY".to_string(),
+        days => format!("{days} DAYS AGO"),
+    })
+}
@@ -0,0 +1,117 @@
+use std::time::{Duration, Instant};
+
+use libmemetendo::video::HBLANK_DOT;
+use log::warn;
+use sdl2::{
+    event::{Event, WindowEvent},
+    pixels::Color,
+    rect::Rect,
+    render::{Texture, WindowCanvas},
+    EventPump,
+};
+
+use crate::{icon, launcher, text};
+
+const DURATION: Duration = Duration::from_millis(1500);
+/// How long the splash takes to fade in, and to fade out.
+const FADE_DURATION: Duration = Duration::from_millis(400);
+const ICON_SIZE: i32 = 64;
+const ICON_Y: i32 = 32;
+const TITLE_Y: i32 = 104;
+/// As many characters as fit within the screen.
+const MAX_TITLE_LEN: usize = 38;
+
+/// Shown in the main window before starting the game when the BIOS is skipped, in place of its boot
+/// animation: the window icon and the game's title, faded in and out. Skipped early by pressing a
+/// key or button. Returns false if the user quit.
+pub fn run(event_pump: &mut EventPump, canvas: &mut WindowCanvas, game_title: &str) -> bool {
+    let texture_creator = canvas.texture_creator();
+    let icon = icon::surface().and_then(|surface| {
+        texture_creator
+            .create_texture_from_surface(surface)
+            .map_err(|e| e.to_string())
+    });
+    let mut icon = match icon {
+        Ok(icon) => icon,
+        Err(e) => {
+            warn!("failed to create splash icon texture: {e}");
+            return true;
+        }
+    };
+
+    let start_time = Instant::now();
+    loop {
+        let elapsed = start_time.elapsed();
+        if elapsed >= DURATION {
+            return true;
+        }
+        if let Err(e) = draw(canvas, &mut icon, game_title, brightness(elapsed)) {
+            warn!("failed to draw splash: {e}");
+            return true;
+        }
+
+        let Some(event) = event_pump.wait_event_timeout(16) else {
+            continue;
+        };
+        for event in [event].into_iter().chain(event_pump.poll_iter()) {
+            match event {
+                Event::Quit { .. }
+                | Event::Window {
+                    win_event: WindowEvent::Close,
+                    ..
+                } => return false,
+                Event::KeyDown { .. }
+                | Event::ControllerButtonDown { .. }
+                | Event::MouseButtonDown { .. } => return true,
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Returns the brightness of the splash `elapsed` into it, from 0 (black) to 255.
+fn brightness(elapsed: Duration) -> u8 {
+    let fade = elapsed
+        .min(DURATION.saturating_sub(elapsed))
+        .min(FADE_DURATION);
+    u8::try_from(fade.as_millis() * 255 / FADE_DURATION.as_millis()).unwrap_or(u8::MAX)
+}
+
+fn draw(
+    canvas: &mut WindowCanvas,
+    icon: &mut Texture,
+    game_title: &str,
+    brightness: u8,
+) -> Result<(), String> {
+    let (scale, left, top) = launcher::layout(canvas);
+    let screen_width = i32::from(HBLANK_DOT);
+    icon.set_color_mod(brightness, brightness, brightness);
+
+    canvas.set_draw_color(Color::BLACK);
+    canvas.clear();
+    let icon_size = (ICON_SIZE * scale).unsigned_abs();
+    canvas.copy(
+        icon,
+        None,
+        Rect::new(
+            left + (screen_width - ICON_SIZE) / 2 * scale,
+            top + ICON_Y * scale,
+            icon_size,
+            icon_size,
+        ),
+    )?;
+
+    let title: String = game_title.chars().take(MAX_TITLE_LEN).collect();
+    let title_width = i32::try_from(title.chars().count()).unwrap_or(0) * text::ADVANCE;
+    canvas.set_draw_color(Color::RGB(brightness, brightness, brightness));
+    text::draw(
+        canvas,
+        left + (screen_width - title_width) / 2 * scale,
+        top + TITLE_Y * scale,
+        scale.unsigned_abs(),
+        &title,
+    )?;
+
+    canvas.present();
+    Ok(())
+}
@@ -0,0 +1,82 @@
+use std::{
+    mem::swap,
+    sync::{Arc, Condvar, Mutex},
+    time::Duration,
+};
+
+struct Shared<T> {
+    /// The most recently published buffer, and whether it's yet to be taken by the reader.
+    middle: Mutex<(T, bool)>,
+    published: Condvar,
+}
+
+/// Creates a triple buffer, which lets a writer publish values (e.g: frames) without waiting for
+/// the reader, and the reader take the latest published value without waiting for the writer.
+/// Unread values are replaced by newer ones.
+pub fn new<T: Clone>(init: T) -> (Writer<T>, Reader<T>) {
+    let shared = Arc::new(Shared {
+        middle: Mutex::new((init.clone(), false)),
+        published: Condvar::new(),
+    });
+
+    (
+        Writer {
+            back: init.clone(),
+            shared: Arc::clone(&shared),
+        },
+        Reader {
+            front: init,
+            shared,
+        },
+    )
+}
+
+pub struct Writer<T> {
+    back: T,
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> Writer<T> {
+    /// Returns the buffer that the next value is written into.
+    pub fn buf(&self) -> &T {
+        &self.back
+    }
+
+    /// Returns the buffer to write the next value into, which holds an older value initially.
+    pub fn buf_mut(&mut self) -> &mut T {
+        &mut self.back
+    }
+
+    /// Publishes the buffer returned by `Self::buf_mut` to the reader.
+    pub fn publish(&mut self) {
+        let mut middle = self.shared.middle.lock().unwrap();
+        swap(&mut middle.0, &mut self.back);
+        middle.1 = true;
+        self.shared.published.notify_one();
+    }
+}
+
+pub struct Reader<T> {
+    front: T,
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> Reader<T> {
+    /// Waits up to `timeout` for a value to be published, then returns the latest one, if any was
+    /// published since the last call.
+    pub fn wait_new(&mut self, timeout: Duration) -> Option<&T> {
+        let middle = self.shared.middle.lock().unwrap();
+        let (mut middle, _) = self
+            .shared
+            .published
+            .wait_timeout_while(middle, timeout, |(_, new)| !*new)
+            .unwrap();
+        if !middle.1 {
+            return None;
+        }
+
+        swap(&mut middle.0, &mut self.front);
+        middle.1 = false;
+        Some(&self.front)
+    }
+}
@@ -0,0 +1,265 @@
+#![warn(clippy::pedantic)]
+
+use std::{mem::take, rc::Rc};
+
+use libmemetendo::{
+    audio, bios,
+    cart::{self, Cartridge},
+    gba,
+    keypad::{Key, KeyState},
+    util::video::FrameBuffer,
+    video::{self, HBLANK_DOT, VBLANK_DOT},
+};
+use numpy::{PyArray1, PyArray2, PyArray3, PyArrayMethods};
+use pyo3::{exceptions::PyValueError, prelude::*, types::PyBytes};
+
+#[derive(Default)]
+struct VideoCallback {
+    buf: FrameBuffer,
+    new_frame: bool,
+}
+
+impl video::Callback for VideoCallback {
+    fn put_dot(&mut self, x: u8, y: u8, dot: video::Dot) {
+        self.buf.put_dot(x, y, dot);
+    }
+
+    fn end_frame(&mut self, green_swap: bool) {
+        if green_swap {
+            self.buf.green_swap();
+        }
+        self.new_frame = true;
+    }
+
+    fn is_frame_skipping(&self) -> bool {
+        false
+    }
+}
+
+#[derive(Default)]
+struct AudioCallback {
+    enabled: bool,
+    samples: Vec<(i16, i16)>,
+}
+
+impl audio::Callback for AudioCallback {
+    fn push_sample(&mut self, sample: (i16, i16)) {
+        if self.enabled {
+            self.samples.push(sample);
+        }
+    }
+
+    fn push_samples(&mut self, samples: &[(i16, i16)]) {
+        if self.enabled {
+            self.samples.extend_from_slice(samples);
+        }
+    }
+}
+
+/// An emulated Game Boy Advance, created from a 16 KiB `bios` ROM and a cartridge `rom`.
+///
+/// `backup` is the initial contents of the cartridge's backup memory (e.g: from a .sav file), if
+/// any. If `skip_bios` is true, the BIOS boot animation is skipped.
+// Gba holds Rcs to its ROMs, so it can't be sent to other threads.
+#[pyclass(name = "Gba", unsendable)]
+struct PyGba {
+    gba: gba::Gba,
+    video_cb: VideoCallback,
+    audio_cb: AudioCallback,
+}
+
+#[pymethods]
+impl PyGba {
+    #[new]
+    #[pyo3(signature = (bios, rom, backup = None, skip_bios = true))]
+    fn new(bios: &[u8], rom: &[u8], backup: Option<&[u8]>, skip_bios: bool) -> PyResult<Self> {
+        let bios_rom = bios::Rom::new(Rc::from(bios))
+            .map_err(|e| PyValueError::new_err(format!("bad BIOS ROM: {e}")))?;
+        let cart_rom = cart::Rom::new(Rc::from(rom))
+            .map_err(|e| PyValueError::new_err(format!("bad cartridge ROM: {e}")))?;
+        let cart = if let Some(backup) = backup {
+            Cartridge::try_from_backup(&cart_rom, Some(backup.into()))
+                .map_err(|e| PyValueError::new_err(e.to_string()))?
+        } else {
+            Cartridge::from(cart_rom)
+        };
+
+        let mut gba = gba::Gba::new(bios_rom, cart);
+        gba.reset(skip_bios);
+
+        Ok(Self {
+            gba,
+            video_cb: VideoCallback::default(),
+            audio_cb: AudioCallback::default(),
+        })
+    }
+
+    #[pyo3(signature = (skip_bios = true))]
+    fn reset(&mut self, skip_bios: bool) {
+        self.gba.reset(skip_bios);
+    }
+
+    /// Steps the system by a single instruction (or a few cycles if halted).
+    fn step(&mut self) {
+        self.gba.step(&mut self.video_cb, &mut self.audio_cb);
+    }
+
+    /// Steps the system until `frames` frames have finished rendering.
+    #[pyo3(signature = (frames = 1))]
+    fn step_frames(&mut self, frames: u32) {
+        for _ in 0..frames {
+            while !take(&mut self.video_cb.new_frame) {
+                self.gba.step(&mut self.video_cb, &mut self.audio_cb);
+            }
+        }
+    }
+
+    /// Returns the last rendered frame as a (160, 240, 3) array of RGB bytes.
+    fn frame_buffer<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyArray3<u8>>> {
+        PyArray1::from_slice(py, &self.video_cb.buf.0).reshape([
+            usize::from(VBLANK_DOT),
+            usize::from(HBLANK_DOT),
+            3,
+        ])
+    }
+
+    /// Whether audio samples are collected for `take_audio_samples`; disabled by default.
+    #[getter]
+    fn audio_enabled(&self) -> bool {
+        self.audio_cb.enabled
+    }
+
+    #[setter]
+    fn set_audio_enabled(&mut self, enabled: bool) {
+        self.audio_cb.enabled = enabled;
+        if !enabled {
+            self.audio_cb.samples.clear();
+        }
+    }
+
+    /// Returns and clears the collected audio samples as an (n, 2) array of signed 16-bit stereo
+    /// samples, at the rate given by `SAMPLE_FREQUENCY`.
+    fn take_audio_samples<'py>(&mut self, py: Python<'py>) -> PyResult<Bound<'py, PyArray2<i16>>> {
+        let samples: Vec<_> = take(&mut self.audio_cb.samples)
+            .into_iter()
+            .flat_map(|(l, r)| [l, r])
+            .collect();
+        let len = samples.len() / 2;
+
+        PyArray1::from_vec(py, samples).reshape([len, 2])
+    }
+
+    /// Presses or releases a key: one of "A", "B", "Select", "Start", "Right", "Left", "Up",
+    /// "Down", "R" or "L".
+    fn set_key(&mut self, key: &str, pressed: bool) -> PyResult<()> {
+        self.gba.keypad.set_pressed(parse_key(key)?, pressed);
+        Ok(())
+    }
+
+    /// Sets exactly the given keys as pressed, releasing all others.
+    // pyo3 can't extract a list into a slice.
+    #[expect(clippy::needless_pass_by_value)]
+    fn set_keys(&mut self, keys: Vec<String>) -> PyResult<()> {
+        let state = keys
+            .iter()
+            .map(|key| parse_key(key))
+            .collect::<PyResult<KeyState>>()?;
+        self.gba.keypad.set_state(state);
+
+        Ok(())
+    }
+
+    fn is_key_pressed(&self, key: &str) -> PyResult<bool> {
+        Ok(self.gba.keypad.is_pressed(parse_key(key)?))
+    }
+
+    /// Reads `len` bytes starting at `addr` via the bus. Reads may have side effects, such as when
+    /// reading from some IO registers.
+    fn read_mem<'py>(&mut self, py: Python<'py>, addr: u32, len: usize) -> Bound<'py, PyBytes> {
+        let mut buf = vec![0; len];
+        self.gba.read_mem(addr, &mut buf);
+
+        PyBytes::new(py, &buf)
+    }
+
+    fn write_mem(&mut self, addr: u32, data: &[u8]) {
+        self.gba.write_mem(addr, data);
+    }
+
+    fn read_byte(&mut self, addr: u32) -> u8 {
+        self.gba.read_byte(addr)
+    }
+
+    fn read_hword(&mut self, addr: u32) -> u16 {
+        self.gba.read_hword(addr)
+    }
+
+    fn read_word(&mut self, addr: u32) -> u32 {
+        self.gba.read_word(addr)
+    }
+
+    fn write_byte(&mut self, addr: u32, value: u8) {
+        self.gba.write_byte(addr, value);
+    }
+
+    fn write_hword(&mut self, addr: u32, value: u16) {
+        self.gba.write_hword(addr, value);
+    }
+
+    fn write_word(&mut self, addr: u32, value: u32) {
+        self.gba.write_word(addr, value);
+    }
+
+    /// Returns the contents of the cartridge's backup memory (e.g: SRAM or flash), if any.
+    fn backup<'py>(&self, py: Python<'py>) -> Option<Bound<'py, PyBytes>> {
+        Some(PyBytes::new(py, self.gba.cart.backup_buffer()?))
+    }
+
+    /// Returns the state of the system, to be restored by `load_state`. ROMs are not included.
+    fn save_state<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+        PyBytes::new(py, &self.gba.save_state())
+    }
+
+    /// Restores a state returned by `save_state`. States saved for a different ROM are refused,
+    /// unless `any_rom` is true.
+    #[pyo3(signature = (state, any_rom = false))]
+    fn load_state(&mut self, state: &[u8], any_rom: bool) -> PyResult<()> {
+        if any_rom {
+            self.gba.load_state_for_any_rom(state)
+        } else {
+            self.gba.load_state(state)
+        }
+        .map_err(|e| PyValueError::new_err(e.to_string()))
+    }
+}
+
+const KEYS: [(&str, Key); 10] = [
+    ("A", Key::A),
+    ("B", Key::B),
+    ("Select", Key::Select),
+    ("Start", Key::Start),
+    ("Right", Key::Right),
+    ("Left", Key::Left),
+    ("Up", Key::Up),
+    ("Down", Key::Down),
+    ("R", Key::R),
+    ("L", Key::L),
+];
+
+fn parse_key(name: &str) -> PyResult<Key> {
+    KEYS.iter()
+        .find(|&&(key_name, _)| key_name == name)
+        .map(|&(_, key)| key)
+        .ok_or_else(|| PyValueError::new_err(format!("unknown key: {name}")))
+}
+
+/// Python bindings for the Memetendo Unsafe Boy Advance GBA emulator.
+#[pymodule]
+fn pymemetendo(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyGba>()?;
+    m.add("SAMPLE_FREQUENCY", audio::SAMPLE_FREQUENCY)?;
+    m.add("SCREEN_WIDTH", HBLANK_DOT)?;
+    m.add("SCREEN_HEIGHT", VBLANK_DOT)?;
+
+    Ok(())
+}
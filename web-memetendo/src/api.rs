@@ -0,0 +1,318 @@
+//! JavaScript API for embedding the emulator into other pages.
+
+use std::{cell::RefCell, rc::Rc};
+
+use js_sys::Promise;
+use libmemetendo::{
+    bios, cart,
+    gba::{Gba, RamResetFlags, Thumbnail},
+    keypad::Key,
+    util::{
+        frame_skip,
+        video::{BlendMode, FrameBlender, PowerSaver},
+    },
+};
+use log::warn;
+use wasm_bindgen::{prelude::*, Clamped};
+use web_sys::{HtmlCanvasElement, ImageData};
+
+use crate::{audio::Audio, runner::WebRunner};
+
+struct Instance {
+    runner: Rc<RefCell<WebRunner>>,
+    bios_rom: Option<bios::Rom>,
+    cart_rom: Option<cart::Rom>,
+}
+
+impl Instance {
+    /// Creates a fresh `Gba` from the loaded ROMs, if both are loaded.
+    fn power_on(&self) {
+        let (Some(bios_rom), Some(cart_rom)) = (&self.bios_rom, &self.cart_rom) else {
+            return;
+        };
+
+        let mut gba = Gba::new(bios_rom.clone(), cart::Cartridge::from(cart_rom.clone()));
+        gba.reset(false);
+        self.runner.borrow_mut().power_on(gba);
+    }
+}
+
+/// An emulator instance drawing to a canvas; see the README for an example.
+#[wasm_bindgen]
+pub struct Memetendo(Rc<RefCell<Instance>>);
+
+#[wasm_bindgen]
+impl Memetendo {
+    /// Creates an emulator drawing to `canvas`, which should be 240x160 in size.
+    ///
+    /// # Errors
+    /// Throws if a 2D rendering context could not be created for `canvas`.
+    #[wasm_bindgen(constructor)]
+    pub fn new(canvas: &HtmlCanvasElement) -> Result<Memetendo, JsError> {
+        let runner = WebRunner::new(&web_sys::window().unwrap(), canvas)
+            .map_err(|e| JsError::new(&e.to_string()))?;
+
+        Ok(Self(Rc::new(RefCell::new(Instance {
+            runner,
+            bios_rom: None,
+            cart_rom: None,
+        }))))
+    }
+
+    /// Loads a 16 KiB BIOS ROM. If a cartridge ROM is also loaded, the system is restarted.
+    /// Returns whether it's the official BIOS; if not, it may be a replacement BIOS or a corrupt
+    /// dump, so games may not work correctly.
+    ///
+    /// # Errors
+    /// Throws if the BIOS ROM has the wrong size.
+    #[wasm_bindgen(js_name = loadBios)]
+    pub fn load_bios(&self, bytes: &[u8]) -> Result<bool, JsError> {
+        let rom = bios::Rom::new(Rc::from(bytes)).map_err(|e| JsError::new(&e.to_string()))?;
+        let verification = rom.verify();
+        if verification != bios::Verification::Official {
+            warn!("{verification}");
+        }
+        let mut instance = self.0.borrow_mut();
+        instance.bios_rom = Some(rom);
+        instance.power_on();
+
+        Ok(verification == bios::Verification::Official)
+    }
+
+    /// Loads a cartridge ROM. If a BIOS ROM is also loaded, the system is restarted.
+    ///
+    /// # Errors
+    /// Throws if the cartridge ROM is too large.
+    #[wasm_bindgen(js_name = loadRom)]
+    pub fn load_rom(&self, bytes: &[u8]) -> Result<(), JsError> {
+        let rom = cart::Rom::new(Rc::from(bytes)).map_err(|e| JsError::new(&e.to_string()))?;
+        let mut instance = self.0.borrow_mut();
+        instance.cart_rom = Some(rom);
+        instance.power_on();
+
+        Ok(())
+    }
+
+    /// Starts or resumes emulation. Returns a promise that rejects if the BIOS and cartridge ROMs
+    /// haven't been loaded yet.
+    ///
+    /// Audio is initialized by the first call, which browsers may only allow in response to user
+    /// input; if initialization fails, sound is muted.
+    pub fn start(&self) -> Promise {
+        let runner = Rc::clone(&self.0.borrow().runner);
+        wasm_bindgen_futures::future_to_promise(async move {
+            if runner.borrow().gba.is_none() {
+                return Err(JsError::new("BIOS and cartridge ROMs must be loaded first").into());
+            }
+
+            if runner.borrow().audio.is_none() {
+                let audio = Audio::new().await.unwrap_or_else(|(e, audio)| {
+                    warn!("audio initialization failed; sound will be muted: {e:?}");
+                    audio
+                });
+                runner.borrow_mut().audio = Some(audio);
+            }
+            runner.borrow_mut().start();
+
+            Ok(JsValue::UNDEFINED)
+        })
+    }
+
+    /// Pauses emulation until `start` is called again.
+    pub fn pause(&self) {
+        self.0.borrow().runner.borrow_mut().pause();
+    }
+
+    #[wasm_bindgen(getter, js_name = isRunning)]
+    pub fn is_running(&self) -> bool {
+        self.0.borrow().runner.borrow().is_running()
+    }
+
+    /// Restarts the system, as if it was powered off and on again.
+    pub fn reset(&self) {
+        self.0.borrow().power_on();
+    }
+
+    /// Restarts the game without running the BIOS, like its own soft reset (e.g: when A, B, Select
+    /// and Start are held) does; memory and IO registers are cleared, but the cartridge keeps its
+    /// state.
+    #[wasm_bindgen(js_name = softReset)]
+    pub fn soft_reset(&self) {
+        let instance = self.0.borrow();
+        let mut runner = instance.runner.borrow_mut();
+        if let Some(ref mut gba) = runner.gba {
+            gba.soft_reset(RamResetFlags::ALL);
+        }
+    }
+
+    /// Sets the maximum number of frames that can be skipped in a row when emulation falls behind.
+    #[wasm_bindgen(js_name = setMaxFrameSkip)]
+    pub fn set_max_frame_skip(&self, max_frame_skip: u32) {
+        self.0.borrow().runner.borrow_mut().frame_skip_mode =
+            frame_skip::Mode::Fixed(max_frame_skip);
+    }
+
+    /// Sets how frames are skipped when emulation falls behind: either `"auto"` (the default) to
+    /// only skip frames while emulation is consistently slower than real-time, or a maximum number
+    /// of frames to skip in a row.
+    ///
+    /// # Errors
+    /// Throws if `mode` is invalid.
+    #[wasm_bindgen(js_name = setFrameSkip)]
+    pub fn set_frame_skip(&self, mode: &str) -> Result<(), JsError> {
+        self.0.borrow().runner.borrow_mut().frame_skip_mode = mode.parse()?;
+        Ok(())
+    }
+
+    /// Sets how skipped frames are blended into the next shown frame, so the picture jumps less
+    /// when frames are skipped: `"average"`, `"min"` or `"max"` of each colour component, or
+    /// `null` (the default) to not blend them. Blending makes skipping frames save less time.
+    ///
+    /// # Errors
+    /// Throws if `mode` is invalid.
+    #[wasm_bindgen(js_name = setFrameBlend)]
+    pub fn set_frame_blend(&self, mode: Option<String>) -> Result<(), JsError> {
+        let mode = mode.map(|mode| mode.parse::<BlendMode>()).transpose()?;
+        self.0.borrow().runner.borrow_mut().video_cb.frame_blender = mode.map(FrameBlender::new);
+        Ok(())
+    }
+
+    /// Enables or disables filtering the audio output to sound closer to real hardware.
+    #[wasm_bindgen(js_name = setAudioFilter)]
+    pub fn set_audio_filter(&self, enabled: bool) {
+        self.0
+            .borrow()
+            .runner
+            .borrow_mut()
+            .set_audio_filter(enabled);
+    }
+
+    /// Enables or disables drawing fewer frames while the screen is still and there's no sound
+    /// (e.g: in a menu), to save battery.
+    #[wasm_bindgen(js_name = setPowerSave)]
+    pub fn set_power_save(&self, enabled: bool) {
+        self.0.borrow().runner.borrow_mut().video_cb.power_saver =
+            enabled.then(PowerSaver::default);
+    }
+
+    /// Returns the state of the system as a `Uint8Array`, to be restored by `loadState`. ROMs are
+    /// not included, but a thumbnail of the screen is; see `stateThumbnail`.
+    ///
+    /// # Errors
+    /// Throws if the system hasn't been started.
+    #[wasm_bindgen(js_name = saveState)]
+    pub fn save_state(&self) -> Result<Vec<u8>, JsError> {
+        let instance = self.0.borrow();
+        let runner = instance.runner.borrow();
+        let gba = runner.gba.as_ref().ok_or_else(not_started_error)?;
+        let thumbnail = Thumbnail::from_frame(&runner.video_cb.buf);
+
+        Ok(gba.save_state_with_thumbnail(&thumbnail))
+    }
+
+    /// Returns the thumbnail of the screen in a state returned by `saveState`, as a 120x80
+    /// `ImageData`, or `undefined` if it has none. Useful for previewing states before loading
+    /// them.
+    ///
+    /// # Errors
+    /// Throws if the state is invalid.
+    #[wasm_bindgen(js_name = stateThumbnail)]
+    pub fn state_thumbnail(bytes: &[u8]) -> Result<Option<ImageData>, JsError> {
+        let Some(thumbnail) = Thumbnail::from_state(bytes)? else {
+            return Ok(None);
+        };
+        let rgba: Vec<u8> = thumbnail
+            .rgb()
+            .chunks_exact(3)
+            .flat_map(|rgb| [rgb[0], rgb[1], rgb[2], 0xff])
+            .collect();
+        let image_data = ImageData::new_with_u8_clamped_array_and_sh(
+            Clamped(&rgba),
+            Thumbnail::WIDTH.into(),
+            Thumbnail::HEIGHT.into(),
+        )
+        .unwrap();
+
+        Ok(Some(image_data))
+    }
+
+    /// Restores a state returned by `saveState` for the same ROMs. States saved for a different
+    /// cartridge ROM are refused, unless `anyRom` is true.
+    ///
+    /// # Errors
+    /// Throws if the system hasn't been started, or if the state is invalid.
+    #[wasm_bindgen(js_name = loadState)]
+    pub fn load_state(&self, bytes: &[u8], any_rom: Option<bool>) -> Result<(), JsError> {
+        let instance = self.0.borrow();
+        let mut runner = instance.runner.borrow_mut();
+        let gba = runner.gba.as_mut().ok_or_else(not_started_error)?;
+        if any_rom.unwrap_or(false) {
+            gba.load_state_for_any_rom(bytes)?;
+        } else {
+            gba.load_state(bytes)?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the contents of the cartridge's backup memory (e.g: SRAM or flash), if any.
+    #[wasm_bindgen(js_name = exportBackup)]
+    pub fn export_backup(&self) -> Option<Vec<u8>> {
+        let instance = self.0.borrow();
+        let runner = instance.runner.borrow();
+        Some(runner.gba.as_ref()?.cart.backup_buffer()?.to_vec())
+    }
+
+    /// Presses or releases a key. `key` is one of "A", "B", "Select", "Start", "Right", "Left",
+    /// "Up", "Down", "R" or "L".
+    ///
+    /// # Errors
+    /// Throws if `key` isn't a valid key name.
+    #[wasm_bindgen(js_name = setKey)]
+    pub fn set_key(&self, key: &str, pressed: bool) -> Result<(), JsError> {
+        let key = parse_key(key).ok_or_else(|| JsError::new(&format!("Unknown key: {key}")))?;
+        if let Some(ref mut gba) = self.0.borrow().runner.borrow_mut().gba {
+            gba.keypad.set_pressed(key, pressed);
+        }
+
+        Ok(())
+    }
+
+    /// Same as `setKey(key, true)`.
+    ///
+    /// # Errors
+    /// Throws if `key` isn't a valid key name.
+    #[wasm_bindgen(js_name = pressKey)]
+    pub fn press_key(&self, key: &str) -> Result<(), JsError> {
+        self.set_key(key, true)
+    }
+
+    /// Same as `setKey(key, false)`.
+    ///
+    /// # Errors
+    /// Throws if `key` isn't a valid key name.
+    #[wasm_bindgen(js_name = releaseKey)]
+    pub fn release_key(&self, key: &str) -> Result<(), JsError> {
+        self.set_key(key, false)
+    }
+}
+
+fn not_started_error() -> JsError {
+    JsError::new("System has not been started; load a BIOS and cartridge ROM first")
+}
+
+fn parse_key(name: &str) -> Option<Key> {
+    Some(match name {
+        "A" => Key::A,
+        "B" => Key::B,
+        "Select" => Key::Select,
+        "Start" => Key::Start,
+        "Right" => Key::Right,
+        "Left" => Key::Left,
+        "Up" => Key::Up,
+        "Down" => Key::Down,
+        "R" => Key::R,
+        "L" => Key::L,
+        _ => return None,
+    })
+}
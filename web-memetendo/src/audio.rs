@@ -1,20 +1,39 @@
-use js_sys::Float32Array;
-use libmemetendo::audio::{self, SAMPLE_FREQUENCY};
-use wasm_bindgen::JsValue;
+use std::{cell::Cell, rc::Rc};
+
+use js_sys::{Array, Float32Array};
+use libmemetendo::{
+    audio::{self, SAMPLE_FREQUENCY},
+    util::audio::SoundDetector,
+};
+use wasm_bindgen::{closure::Closure, JsCast, JsValue};
 use wasm_bindgen_futures::JsFuture;
 use web_sys::{
-    AudioContext, AudioContextOptions, AudioWorkletNode, AudioWorkletNodeOptions, MessagePort,
+    AudioContext, AudioContextOptions, AudioWorkletNode, AudioWorkletNodeOptions, MessageEvent,
+    MessagePort,
 };
 
+/// How well the audio worklet's buffer is being kept fed with samples.
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub struct Health {
+    /// Audio buffered by the worklet when it last reported, in milliseconds.
+    pub buffered_ms: f64,
+    /// Times the worklet ran out of samples to play.
+    pub underruns: u32,
+}
+
 struct Callback {
     ctx: AudioContext,
     port: MessagePort,
+    /// Health reported by the worklet since the last call to `Audio::take_health`.
+    health: Rc<Cell<Health>>,
+    _on_message: Closure<dyn FnMut(MessageEvent)>,
     freq: u32,
     freq_counter: u32,
     freq_counter_accum: u32,
     sample_accum: (i32, i32),
     accum_extra_sample: bool,
     samples: [Vec<i16>; 2],
+    sound: SoundDetector,
 }
 
 impl Callback {
@@ -34,21 +53,44 @@ impl Callback {
         let node = AudioWorkletNode::new_with_options(&ctx, "audio-processor", &node_options)?;
         node.connect_with_audio_node(&ctx.destination())?;
 
+        // The worklet reports its health as [buffered_ms, underruns] after queueing samples.
+        let port = node.port().unwrap();
+        let health = Rc::new(Cell::new(Health::default()));
+        let on_message = Closure::<dyn FnMut(_)>::new({
+            let health = Rc::clone(&health);
+            move |event: MessageEvent| {
+                let Ok(report) = event.data().dyn_into::<Array>() else {
+                    return;
+                };
+                let prev = health.get();
+                #[expect(clippy::cast_sign_loss, clippy::cast_possible_truncation)]
+                health.set(Health {
+                    buffered_ms: report.get(0).as_f64().unwrap_or(0.0),
+                    underruns: prev.underruns + report.get(1).as_f64().unwrap_or(0.0) as u32,
+                });
+            }
+        });
+        port.set_onmessage(Some(on_message.as_ref().unchecked_ref()));
+
         Ok(Self {
             ctx,
             freq,
-            port: node.port().unwrap(),
+            port,
+            health,
+            _on_message: on_message,
             freq_counter: 0,
             freq_counter_accum: 0,
             sample_accum: (0, 0),
             accum_extra_sample: false,
             samples: [Vec::new(), Vec::new()],
+            sound: SoundDetector::default(),
         })
     }
 }
 
 impl audio::Callback for Callback {
     fn push_sample(&mut self, sample: (i16, i16)) {
+        self.sound.push_sample(sample);
         self.sample_accum.0 += i32::from(sample.0);
         self.sample_accum.1 += i32::from(sample.1);
 
@@ -93,6 +135,30 @@ impl Audio {
         }
     }
 
+    pub fn suspend(&self) {
+        if let Some(ref cb) = self.0 {
+            _ = cb.ctx.suspend().unwrap();
+        }
+    }
+
+    /// Returns the health of the audio buffer, if audio is enabled, and resets its count of
+    /// underruns.
+    pub fn take_health(&mut self) -> Option<Health> {
+        let cb = self.0.as_ref()?;
+        let health = cb.health.get();
+        cb.health.set(Health {
+            underruns: 0,
+            ..health
+        });
+
+        Some(health)
+    }
+
+    /// Returns whether any sound was output since the last call.
+    pub fn take_audible(&mut self) -> bool {
+        self.0.as_mut().is_some_and(|cb| cb.sound.take_audible())
+    }
+
     pub fn queue_samples(&mut self) {
         let Some(ref mut cb) = self.0 else {
             return;
@@ -118,4 +184,10 @@ impl audio::Callback for Audio {
             cb.push_sample(sample);
         }
     }
+
+    fn push_samples(&mut self, samples: &[(i16, i16)]) {
+        if let Some(ref mut cb) = self.0 {
+            cb.push_samples(samples);
+        }
+    }
 }
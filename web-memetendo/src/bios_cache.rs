@@ -0,0 +1,71 @@
+//! Caching of the selected BIOS ROM in `IndexedDB`, so returning users only need to pick a
+//! cartridge ROM, and loading of a BIOS ROM provided by the server as a fallback.
+
+use js_sys::Uint8Array;
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{IdbTransactionMode, Response, Window};
+
+use crate::idb::{open_db, wait};
+
+/// Name of the `IndexedDB` object store for the cached BIOS ROM.
+pub const STORE_NAME: &str = "bios";
+const KEY: &str = "bios";
+
+/// Path of a BIOS ROM the server may provide (like Cult-of-GBA's open-source BIOS), relative to
+/// the page.
+const FALLBACK_PATH: &str = "bios.bin";
+
+/// Returns the cached BIOS ROM, if any.
+pub async fn load(window: &Window) -> Result<Option<Vec<u8>>, JsValue> {
+    let db = open_db(window).await?;
+    let store = db
+        .transaction_with_str_and_mode(STORE_NAME, IdbTransactionMode::Readonly)?
+        .object_store(STORE_NAME)?;
+    let value = wait(&store.get(&KEY.into())?).await?;
+    db.close();
+
+    Ok(value
+        .dyn_into::<Uint8Array>()
+        .ok()
+        .map(|array| array.to_vec()))
+}
+
+/// Caches `rom_buf` as the BIOS ROM, replacing any previously cached one.
+pub async fn store(window: &Window, rom_buf: &[u8]) -> Result<(), JsValue> {
+    let db = open_db(window).await?;
+    let store = db
+        .transaction_with_str_and_mode(STORE_NAME, IdbTransactionMode::Readwrite)?
+        .object_store(STORE_NAME)?;
+    wait(&store.put_with_key(&Uint8Array::from(rom_buf), &KEY.into())?).await?;
+    db.close();
+
+    Ok(())
+}
+
+/// Forgets the cached BIOS ROM, if any.
+pub async fn clear(window: &Window) -> Result<(), JsValue> {
+    let db = open_db(window).await?;
+    let store = db
+        .transaction_with_str_and_mode(STORE_NAME, IdbTransactionMode::Readwrite)?
+        .object_store(STORE_NAME)?;
+    wait(&store.delete(&KEY.into())?).await?;
+    db.close();
+
+    Ok(())
+}
+
+/// Fetches the BIOS ROM provided by the server, if it provides one.
+pub async fn fetch_fallback(window: &Window) -> Option<Vec<u8>> {
+    let response = JsFuture::from(window.fetch_with_str(FALLBACK_PATH))
+        .await
+        .ok()?
+        .dyn_into::<Response>()
+        .ok()?;
+    if !response.ok() {
+        return None;
+    }
+    let array_buf = JsFuture::from(response.array_buffer().ok()?).await.ok()?;
+
+    Some(Uint8Array::new(&array_buf).to_vec())
+}
@@ -0,0 +1,53 @@
+//! Helpers for the `IndexedDB` database where the page keeps its data.
+
+use js_sys::Promise;
+use wasm_bindgen::{closure::Closure, JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{IdbDatabase, IdbRequest, Window};
+
+use crate::{bios_cache, saves};
+
+const DB_NAME: &str = "memetendo";
+const DB_VERSION: u32 = 2;
+
+/// Waits for `request` to finish, returning its result.
+pub async fn wait(request: &IdbRequest) -> Result<JsValue, JsValue> {
+    let promise = Promise::new(&mut |resolve, reject| {
+        request.set_onsuccess(Some(&resolve));
+        request.set_onerror(Some(&reject));
+    });
+    let result = JsFuture::from(promise).await;
+    request.set_onsuccess(None);
+    request.set_onerror(None);
+    if result.is_err() {
+        return Err(request.error()?.map_or(JsValue::UNDEFINED, Into::into));
+    }
+
+    request.result()
+}
+
+/// Opens the database, creating any object stores it's missing.
+pub async fn open_db(window: &Window) -> Result<IdbDatabase, JsValue> {
+    let factory = window
+        .indexed_db()?
+        .ok_or_else(|| JsValue::from_str("IndexedDB is unavailable"))?;
+    let request = factory.open_with_u32(DB_NAME, DB_VERSION)?;
+    let on_upgrade_needed = Closure::once({
+        let request = request.clone();
+        move || {
+            let db = request.result()?.dyn_into::<IdbDatabase>()?;
+            for store_name in [bios_cache::STORE_NAME, saves::STORE_NAME] {
+                if !db.object_store_names().contains(store_name) {
+                    db.create_object_store(store_name)?;
+                }
+            }
+
+            Ok::<_, JsValue>(())
+        }
+    });
+    request.set_onupgradeneeded(Some(on_upgrade_needed.as_ref().unchecked_ref()));
+    let db = wait(&request).await;
+    request.set_onupgradeneeded(None);
+
+    db?.dyn_into()
+}
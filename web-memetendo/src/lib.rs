@@ -1,97 +1,54 @@
 #![warn(clippy::pedantic)]
 
-use std::{cell::RefCell, fmt::Write, mem::take, panic, rc::Rc};
+use std::{
+    cell::{Cell, RefCell},
+    fmt::Write,
+    panic,
+    rc::Rc,
+};
 
-use anyhow::{Context, Result};
+use anyhow::Result;
 use audio::Audio;
-use js_sys::{Array, Reflect, Uint8Array};
+use js_sys::{Array, Uint8Array};
 use libmemetendo::{
     bios,
     cart::{self, BackupType, Cartridge},
-    gba::Gba,
-    keypad::Key,
-    util::video::FrameBuffer,
-    video::{self, HBLANK_DOT, VBLANK_DOT},
+    gba::{EventSink, Gba, Notice},
+    keypad::{Key, Keypad},
+    storage,
+    util::video::PowerSaver,
 };
-use log::{info, Level};
-use wasm_bindgen::{prelude::*, Clamped, JsCast};
+use log::{info, warn, Level};
+use wasm_bindgen::{prelude::*, JsCast};
 use web_sys::{
-    Blob, BlobPropertyBag, CanvasRenderingContext2d, Document, Event, FileReader,
-    HtmlAnchorElement, HtmlButtonElement, HtmlCanvasElement, HtmlFieldSetElement, HtmlInputElement,
-    HtmlParagraphElement, ImageData, KeyboardEvent, Url, Window,
+    Blob, BlobPropertyBag, Document, Event, FileReader, HtmlAnchorElement, HtmlButtonElement,
+    HtmlCanvasElement, HtmlElement, HtmlFieldSetElement, HtmlInputElement, HtmlParagraphElement,
+    KeyboardEvent, Url, Window,
 };
 
-mod audio;
-
-struct VideoCallback {
-    canvas_ctx: CanvasRenderingContext2d,
-    new_frame: bool,
-    frame_skipping: bool,
-    buf: FrameBuffer<4>,
-}
-
-impl video::Callback for VideoCallback {
-    fn put_dot(&mut self, x: u8, y: u8, dot: video::Dot) {
-        self.buf.put_dot(x, y, dot);
-    }
+use crate::runner::WebRunner;
 
-    fn end_frame(&mut self, green_swap: bool) {
-        self.new_frame = true;
-        if self.frame_skipping {
+mod api;
+mod audio;
+mod bios_cache;
+mod idb;
+mod runner;
+mod saves;
+mod stats;
+
+/// Keeps the latest notice reported by the core, which is shown in the status line.
+#[derive(Clone, Default)]
+struct LastNotice(Rc<Cell<Option<Notice>>>);
+
+impl EventSink for LastNotice {
+    fn report(&mut self, notice: Notice) {
+        // Too frequent to be worth showing.
+        if let Notice::InvalidIoAccess { .. } = notice {
             return;
         }
-        if green_swap {
-            self.buf.green_swap();
-        }
 
-        let image_data = ImageData::new_with_u8_clamped_array_and_sh(
-            Clamped(&self.buf.0),
-            HBLANK_DOT.into(),
-            VBLANK_DOT.into(),
-        )
-        .unwrap();
-        self.canvas_ctx
-            .put_image_data(&image_data, 0.0, 0.0)
-            .unwrap();
-    }
-
-    fn is_frame_skipping(&self) -> bool {
-        self.frame_skipping
-    }
-}
-
-impl VideoCallback {
-    fn new(window: &Window) -> Result<Self> {
-        let canvas = window
-            .document()
-            .unwrap()
-            .get_element_by_id("memetendo-screen")
-            .unwrap()
-            .dyn_into::<HtmlCanvasElement>()
-            .unwrap();
-
-        let canvas_ctx = canvas
-            .get_context_with_context_options("2d", &*{
-                let options = js_sys::Object::new();
-                Reflect::set(&options, &"alpha".into(), &false.into()).unwrap();
-                Reflect::set(&options, &"desynchronized".into(), &true.into()).unwrap();
-                options
-            })
-            .unwrap()
-            .map(|ctx| ctx.dyn_into::<CanvasRenderingContext2d>().unwrap())
-            .context("failed to get 2D canvas rendering context")?;
-
-        Ok(Self {
-            canvas_ctx,
-            new_frame: false,
-            frame_skipping: false,
-            buf: FrameBuffer::new(0xff),
-        })
-    }
-
-    fn clear(&self) {
-        self.canvas_ctx
-            .clear_rect(0.0, 0.0, HBLANK_DOT.into(), VBLANK_DOT.into());
+        info!("{notice}");
+        self.0.set(Some(notice));
     }
 }
 
@@ -99,15 +56,18 @@ struct State {
     window: Window,
     document: Document,
     status: HtmlParagraphElement,
+    last_notice: LastNotice,
     backup_fields: HtmlFieldSetElement,
     import_backup_field: HtmlInputElement,
-    audio: Audio,
-    video_cb: VideoCallback,
-    gba: Option<Gba>,
-    updater: Option<Closure<dyn FnMut(f64)>>,
-    max_frame_skip: u32,
+    runner: Rc<RefCell<WebRunner>>,
     selected_bios_rom: Option<bios::Rom>,
+    /// Contents of `Self::selected_bios_rom`, for caching it.
+    selected_bios_buf: Option<Rc<[u8]>>,
+    /// Whether the user consented to caching the selected BIOS ROM in `IndexedDB`.
+    cache_bios: bool,
     selected_cart_rom: Option<cart::Rom>,
+    /// Saves of the selected cartridge ROM's game, if they could be loaded.
+    saves: Option<saves::Storage>,
 }
 
 impl State {
@@ -121,15 +81,56 @@ impl State {
         });
 
         let document = window.document().unwrap();
+        let status = document
+            .get_element_by_id("memetendo-status")
+            .unwrap()
+            .dyn_into::<HtmlParagraphElement>()
+            .unwrap();
+        let stats_panel = document
+            .get_element_by_id("memetendo-stats")
+            .unwrap()
+            .dyn_into::<HtmlElement>()
+            .unwrap();
+
+        let runner = WebRunner::new(
+            window,
+            &document
+                .get_element_by_id("memetendo-screen")
+                .unwrap()
+                .dyn_into::<HtmlCanvasElement>()
+                .unwrap(),
+        )?;
+        let last_notice = LastNotice::default();
+        {
+            let mut runner = runner.borrow_mut();
+            runner.audio = Some(audio);
+            runner.on_stats = Some(Box::new({
+                let status = status.clone();
+                let last_notice = last_notice.clone();
+                let mut text_buf = String::new();
+                move |gba, stats| {
+                    text_buf.clear();
+                    if let Some(header) = gba.cart.rom().header() {
+                        write!(&mut text_buf, "{header} | ").unwrap();
+                    }
+                    write!(&mut text_buf, "{}", stats.fps).unwrap();
+                    if let Some(notice) = last_notice.0.get() {
+                        write!(&mut text_buf, " | {notice}").unwrap();
+                    }
+                    status.set_inner_text(&text_buf);
+
+                    text_buf.clear();
+                    write!(&mut text_buf, "{stats}").unwrap();
+                    stats_panel.set_inner_text(&text_buf);
+                }
+            }));
+        }
 
         Ok(Self {
             window: window.clone(),
             document: document.clone(),
-            status: document
-                .get_element_by_id("memetendo-status")
-                .unwrap()
-                .dyn_into::<HtmlParagraphElement>()
-                .unwrap(),
+            status,
+            last_notice,
             backup_fields: document
                 .get_element_by_id("memetendo-backups")
                 .unwrap()
@@ -140,19 +141,27 @@ impl State {
                 .unwrap()
                 .dyn_into::<HtmlInputElement>()
                 .unwrap(),
-            audio,
-            video_cb: VideoCallback::new(window)?,
-            gba: None,
-            updater: None,
-            max_frame_skip: 3,
+            runner,
             selected_bios_rom: None,
+            selected_bios_buf: None,
+            cache_bios: false,
             selected_cart_rom: None,
+            saves: None,
         })
     }
 }
 
 fn maybe_start_emulation(state: &Rc<RefCell<State>>, cart_backup_buf: Option<Box<[u8]>>) -> bool {
     let mut borrowed_state = state.borrow_mut();
+    // Restarting uses the stored backup (unless importing one), so make sure it's up-to-date.
+    flush_backup(&mut borrowed_state);
+    let cart_backup_buf = cart_backup_buf.or_else(|| {
+        let saves = borrowed_state.saves.as_mut()?;
+        storage::read_backup(saves).unwrap_or_else(|e| {
+            warn!("failed to read the stored backup: {e}");
+            None
+        })
+    });
     let Some(ref bios_rom) = borrowed_state.selected_bios_rom else {
         return false;
     };
@@ -161,123 +170,42 @@ fn maybe_start_emulation(state: &Rc<RefCell<State>>, cart_backup_buf: Option<Box
     };
 
     let cart = if let Some(cart_backup_buf) = cart_backup_buf {
-        let len = cart_backup_buf.len();
-        let Some(cart) = Cartridge::try_from_backup(cart_rom, Some(cart_backup_buf)) else {
-            alert(
-                &borrowed_state.window,
-                format!("Failed to determine cartridge backup type from save file! (len: {len} B)"),
-            );
-            return false;
+        let cart = match Cartridge::try_from_backup(cart_rom, Some(cart_backup_buf)) {
+            Ok(cart) => cart,
+            Err(e) => {
+                alert(
+                    &borrowed_state.window,
+                    format!("Failed to load save file! {e}"),
+                );
+                return false;
+            }
         };
         borrowed_state.backup_fields.set_hidden(false);
 
         cart
     } else {
         let backup_type = cart_rom.parse_backup_type();
-        info!("parsed cart backup type: {backup_type:?}");
         borrowed_state
             .backup_fields
             .set_hidden(backup_type == BackupType::None);
         borrowed_state.import_backup_field.set_value("");
 
-        Cartridge::new(cart_rom.clone(), backup_type)
+        // Reports the guessed backup type.
+        Cartridge::from(cart_rom.clone())
     };
 
     borrowed_state.status.set_inner_text("Starting...");
-    borrowed_state.gba = Some(Gba::new(bios_rom.clone(), cart));
-    borrowed_state.video_cb.clear();
-    borrowed_state.audio.resume();
-    drop(borrowed_state);
-
-    init_emulation_updater(state);
-    true
-}
-
-fn init_emulation_updater(state: &Rc<RefCell<State>>) {
-    if state.borrow().updater.is_some() {
-        return;
+    borrowed_state.last_notice.0.set(None);
+    let mut gba = Gba::new(bios_rom.clone(), cart);
+    gba.events.sink = Some(Box::new(borrowed_state.last_notice.clone()));
+    let mut runner = borrowed_state.runner.borrow_mut();
+    runner.power_on(gba);
+    // Keep the page paused if it's hidden; emulation starts when it's shown.
+    if !borrowed_state.document.hidden() {
+        runner.start();
     }
 
-    let schedule_update = |state: &mut State| {
-        state
-            .window
-            .request_animation_frame(state.updater.as_ref().unwrap().as_ref().unchecked_ref())
-            .unwrap();
-    };
-
-    let mut borrowed_state = state.borrow_mut();
-    {
-        let state = Rc::clone(state);
-        let mut next_frame_ms: Option<f64> = None;
-        let mut next_second_ms: Option<f64> = None;
-        let (mut frame_counter, mut unskipped_frame_counter) = (0u32, 0u32);
-        let mut status_text_buf = String::new();
-
-        borrowed_state.updater = Some(Closure::new(move |ms: f64| {
-            const FRAME_DURATION_MS: f64 = 1000.0 / 59.737;
-
-            let mut borrowed_state = state.borrow_mut();
-
-            if let Some(ref mut next_second_ms) = next_second_ms {
-                if ms >= *next_second_ms {
-                    status_text_buf.clear();
-                    write!(&mut status_text_buf, "FPS: {unskipped_frame_counter}").unwrap();
-                    if frame_counter != unskipped_frame_counter {
-                        write!(&mut status_text_buf, " ({frame_counter})").unwrap();
-                    }
-
-                    borrowed_state.status.set_inner_text(&status_text_buf);
-                    *next_second_ms = ms + 1000.0;
-                    (frame_counter, unskipped_frame_counter) = (0, 0);
-                }
-            } else {
-                next_second_ms = Some(ms + 1000.0);
-            }
-
-            let mut next_ms = next_frame_ms.unwrap_or(ms);
-            if ms >= next_ms {
-                let State {
-                    gba: Some(ref mut gba),
-                    ref mut video_cb,
-                    ref mut audio,
-                    max_frame_skip,
-                    ..
-                } = *borrowed_state
-                else {
-                    borrowed_state.updater = None;
-                    return;
-                };
-
-                let mut skipped_frames = 0;
-                next_frame_ms = loop {
-                    video_cb.frame_skipping = skipped_frames > 0;
-                    while !take(&mut video_cb.new_frame) {
-                        gba.step(video_cb, audio);
-                    }
-                    audio.queue_samples();
-
-                    if skipped_frames == 0 {
-                        unskipped_frame_counter += 1;
-                    }
-                    frame_counter += 1;
-
-                    next_ms += FRAME_DURATION_MS;
-                    if next_ms > ms {
-                        break Some(next_ms);
-                    }
-                    if skipped_frames >= max_frame_skip {
-                        // Too far behind; reschedule for the next frame.
-                        break None;
-                    }
-                    skipped_frames += 1;
-                };
-            }
-
-            schedule_update(&mut borrowed_state);
-        }));
-    }
-
-    schedule_update(&mut borrowed_state);
+    true
 }
 
 fn alert(window: &Window, message: impl AsRef<str>) {
@@ -297,11 +225,23 @@ async fn memetendo_main() {
     init_file_input(&state.borrow(), "memetendo-bios-file", {
         let state = Rc::clone(&state);
         move |rom_buf: Vec<u8>| {
-            let Ok(rom) = bios::Rom::new(Rc::from(rom_buf)) else {
+            let rom_buf = Rc::from(rom_buf);
+            let Ok(rom) = bios::Rom::new(Rc::clone(&rom_buf)) else {
                 alert(&state.borrow().window, "Invalid BIOS ROM size!");
                 return;
             };
-            state.borrow_mut().selected_bios_rom = Some(rom);
+            let verification = rom.verify();
+            if verification != bios::Verification::Official {
+                alert(
+                    &state.borrow().window,
+                    format!("Warning: {verification}. Games may not work correctly."),
+                );
+            }
+            let mut borrowed_state = state.borrow_mut();
+            borrowed_state.selected_bios_rom = Some(rom);
+            borrowed_state.selected_bios_buf = Some(rom_buf);
+            update_bios_cache(&borrowed_state);
+            drop(borrowed_state);
             maybe_start_emulation(&state, None);
         }
     });
@@ -312,8 +252,23 @@ async fn memetendo_main() {
                 alert(&state.borrow().window, "Invalid cartridge ROM size!");
                 return;
             };
-            state.borrow_mut().selected_cart_rom = Some(rom);
-            maybe_start_emulation(&state, None);
+            flush_backup(&mut state.borrow_mut());
+
+            let state = Rc::clone(&state);
+            wasm_bindgen_futures::spawn_local(async move {
+                let window = state.borrow().window.clone();
+                let game_key = format!("{:08x}", rom.hashes().crc32);
+                let saves = saves::Storage::load(&window, game_key)
+                    .await
+                    .inspect_err(|e| warn!("failed to load saves: {e:?}"))
+                    .ok();
+
+                let mut borrowed_state = state.borrow_mut();
+                borrowed_state.selected_cart_rom = Some(rom);
+                borrowed_state.saves = saves;
+                drop(borrowed_state);
+                maybe_start_emulation(&state, None);
+            });
         }
     });
     init_file_input(&state.borrow(), "memetendo-import-backup", {
@@ -342,38 +297,241 @@ async fn memetendo_main() {
         )
         .unwrap();
 
-    let frame_skip_input = document
-        .get_element_by_id("memetendo-frame-skip")
+    init_frame_skip_input(&state);
+    init_input_overlay_checkbox(&state);
+    init_audio_filter_checkbox(&state);
+    init_power_save_checkbox(&state);
+    init_cache_bios_checkbox(&state);
+    init_visibility_handler(&state);
+    let initial_bios_source = select_initial_bios(&state).await;
+
+    document
+        .get_element_by_id("memetendo-options")
+        .unwrap()
+        .dyn_into::<HtmlFieldSetElement>()
+        .unwrap()
+        .set_disabled(false);
+    let status_text = if let Some(source) = initial_bios_source {
+        format!("Using the {source} BIOS; select a Cartridge ROM file to start!")
+    } else {
+        "Select a BIOS and Cartridge ROM file to start!".to_string()
+    };
+    state.borrow_mut().status.set_inner_text(&status_text);
+}
+
+/// Selects the cached BIOS ROM, or otherwise the BIOS ROM provided by the server, if any.
+/// Returns a description of where the selected ROM came from.
+async fn select_initial_bios(state: &Rc<RefCell<State>>) -> Option<&'static str> {
+    let window = state.borrow().window.clone();
+    let cached_buf = bios_cache::load(&window).await.unwrap_or_else(|e| {
+        warn!("failed to load the cached BIOS: {e:?}");
+        None
+    });
+    let (rom_buf, source) = if let Some(rom_buf) = cached_buf {
+        (rom_buf, "cached")
+    } else {
+        (
+            bios_cache::fetch_fallback(&window).await?,
+            "server-provided",
+        )
+    };
+
+    let rom_buf = Rc::from(rom_buf);
+    let Ok(rom) = bios::Rom::new(Rc::clone(&rom_buf)) else {
+        warn!("{source} BIOS ROM has an invalid size");
+        return None;
+    };
+    if rom.verify() == bios::Verification::Blank {
+        warn!("{source} BIOS ROM is blank");
+        return None;
+    }
+    let mut state = state.borrow_mut();
+    state.selected_bios_rom = Some(rom);
+    if source == "cached" {
+        // Caching must've been consented to before.
+        state.cache_bios = true;
+        state
+            .document
+            .get_element_by_id("memetendo-cache-bios")
+            .unwrap()
+            .dyn_into::<HtmlInputElement>()
+            .unwrap()
+            .set_checked(true);
+    }
+    state.selected_bios_buf = Some(rom_buf);
+
+    Some(source)
+}
+
+/// Caches the selected BIOS ROM in `IndexedDB` if `State::cache_bios` is set, or forgets any
+/// cached BIOS ROM if not.
+fn update_bios_cache(state: &State) {
+    let window = state.window.clone();
+    let rom_buf = state.selected_bios_buf.clone();
+    let cache_bios = state.cache_bios;
+    wasm_bindgen_futures::spawn_local(async move {
+        let result = match rom_buf {
+            Some(rom_buf) if cache_bios => bios_cache::store(&window, &rom_buf).await,
+            _ if cache_bios => Ok(()),
+            _ => bios_cache::clear(&window).await,
+        };
+        if let Err(e) = result {
+            warn!("failed to update the cached BIOS: {e:?}");
+        }
+    });
+}
+
+fn init_cache_bios_checkbox(state: &Rc<RefCell<State>>) {
+    let input = state
+        .borrow()
+        .document
+        .get_element_by_id("memetendo-cache-bios")
         .unwrap()
         .dyn_into::<HtmlInputElement>()
         .unwrap();
-    frame_skip_input.set_value(&state.borrow().max_frame_skip.to_string());
-    frame_skip_input
+    input.set_checked(false);
+    input
         .add_event_listener_with_callback("change", {
-            let state = Rc::clone(&state);
+            let state = Rc::clone(state);
             Closure::<dyn Fn(_)>::new(move |event: Event| {
                 let input = event
                     .target()
                     .unwrap()
                     .dyn_into::<HtmlInputElement>()
                     .unwrap();
-                state.borrow_mut().max_frame_skip = input.value().parse().unwrap();
+                let mut state = state.borrow_mut();
+                state.cache_bios = input.checked();
+                update_bios_cache(&state);
             })
             .into_js_value()
             .unchecked_ref()
         })
         .unwrap();
+}
 
+/// Stores the cartridge backup of the running game, if any.
+fn flush_backup(state: &mut State) {
+    let runner = state.runner.borrow();
+    let (Some(gba), Some(saves)) = (&runner.gba, &mut state.saves) else {
+        return;
+    };
+    if let Err(e) = storage::flush_backup(gba, saves) {
+        warn!("failed to store the backup: {e}");
+    }
+}
+
+/// Pauses emulation and audio while the page is hidden, where `requestAnimationFrame` callbacks
+/// stop. When it's shown again, pacing resumes from then, rather than trying to catch up on the
+/// frames missed in the meantime. The cartridge backup is stored when it's hidden, as the page
+/// may be closed afterwards.
+fn init_visibility_handler(state: &Rc<RefCell<State>>) {
+    let document = state.borrow().document.clone();
     document
-        .get_element_by_id("memetendo-options")
+        .add_event_listener_with_callback("visibilitychange", {
+            let state = Rc::clone(state);
+            Closure::<dyn Fn()>::new(move || {
+                let mut state = state.borrow_mut();
+                if state.document.hidden() {
+                    flush_backup(&mut state);
+                    state.runner.borrow_mut().pause();
+                } else {
+                    state.runner.borrow_mut().start();
+                }
+            })
+            .into_js_value()
+            .unchecked_ref()
+        })
+        .unwrap();
+}
+
+fn init_frame_skip_input(state: &Rc<RefCell<State>>) {
+    let input = state
+        .borrow()
+        .document
+        .get_element_by_id("memetendo-frame-skip")
         .unwrap()
-        .dyn_into::<HtmlFieldSetElement>()
+        .dyn_into::<HtmlInputElement>()
+        .unwrap();
+    input.set_value(&state.borrow().runner.borrow().frame_skip_mode.to_string());
+    input
+        .add_event_listener_with_callback("change", {
+            let state = Rc::clone(state);
+            Closure::<dyn Fn(_)>::new(move |event: Event| {
+                let input = event
+                    .target()
+                    .unwrap()
+                    .dyn_into::<HtmlInputElement>()
+                    .unwrap();
+                match input.value().parse() {
+                    Ok(mode) => state.borrow().runner.borrow_mut().frame_skip_mode = mode,
+                    Err(e) => {
+                        alert(&state.borrow().window, format!("Invalid frame skip: {e}."));
+                        input
+                            .set_value(&state.borrow().runner.borrow().frame_skip_mode.to_string());
+                    }
+                }
+            })
+            .into_js_value()
+            .unchecked_ref()
+        })
+        .unwrap();
+}
+
+fn init_audio_filter_checkbox(state: &Rc<RefCell<State>>) {
+    let input = state
+        .borrow()
+        .document
+        .get_element_by_id("memetendo-audio-filter")
         .unwrap()
-        .set_disabled(false);
-    state
-        .borrow_mut()
-        .status
-        .set_inner_text("Select a BIOS and Cartridge ROM file to start!");
+        .dyn_into::<HtmlInputElement>()
+        .unwrap();
+    input.set_checked(false);
+    input
+        .add_event_listener_with_callback("change", {
+            let state = Rc::clone(state);
+            Closure::<dyn Fn(_)>::new(move |event: Event| {
+                let input = event
+                    .target()
+                    .unwrap()
+                    .dyn_into::<HtmlInputElement>()
+                    .unwrap();
+                state
+                    .borrow()
+                    .runner
+                    .borrow_mut()
+                    .set_audio_filter(input.checked());
+            })
+            .into_js_value()
+            .unchecked_ref()
+        })
+        .unwrap();
+}
+
+fn init_power_save_checkbox(state: &Rc<RefCell<State>>) {
+    let input = state
+        .borrow()
+        .document
+        .get_element_by_id("memetendo-power-save")
+        .unwrap()
+        .dyn_into::<HtmlInputElement>()
+        .unwrap();
+    input.set_checked(false);
+    input
+        .add_event_listener_with_callback("change", {
+            let state = Rc::clone(state);
+            Closure::<dyn Fn(_)>::new(move |event: Event| {
+                let input = event
+                    .target()
+                    .unwrap()
+                    .dyn_into::<HtmlInputElement>()
+                    .unwrap();
+                state.borrow().runner.borrow_mut().video_cb.power_saver =
+                    input.checked().then(PowerSaver::default);
+            })
+            .into_js_value()
+            .unchecked_ref()
+        })
+        .unwrap();
 }
 
 // TODO: uses event.code(), so we need to have some sort of prompt that shows the actual key if the
@@ -384,7 +542,9 @@ fn create_keypress_handler(
 ) -> Closure<dyn FnMut(KeyboardEvent)> {
     let state = Rc::clone(state);
     Closure::new(move |event: KeyboardEvent| {
-        let Some(ref mut gba) = state.borrow_mut().gba else {
+        let state = state.borrow();
+        let mut runner = state.runner.borrow_mut();
+        let Some(ref mut gba) = runner.gba else {
             return;
         };
         let key = match event.code().as_str() {
@@ -464,6 +624,33 @@ fn init_file_input(state: &State, id: &str, mut callback: impl FnMut(Vec<u8>) +
         .unwrap();
 }
 
+fn init_input_overlay_checkbox(state: &Rc<RefCell<State>>) {
+    let input = state
+        .borrow()
+        .document
+        .get_element_by_id("memetendo-input-overlay")
+        .unwrap()
+        .dyn_into::<HtmlInputElement>()
+        .unwrap();
+    input.set_checked(false);
+    input
+        .add_event_listener_with_callback("change", {
+            let state = Rc::clone(state);
+            Closure::<dyn Fn(_)>::new(move |event: Event| {
+                let input = event
+                    .target()
+                    .unwrap()
+                    .dyn_into::<HtmlInputElement>()
+                    .unwrap();
+                state.borrow().runner.borrow_mut().video_cb.input_overlay =
+                    input.checked().then(Keypad::new);
+            })
+            .into_js_value()
+            .unchecked_ref()
+        })
+        .unwrap();
+}
+
 fn init_export_backup_button(state: &Rc<RefCell<State>>) {
     state
         .borrow()
@@ -476,8 +663,8 @@ fn init_export_backup_button(state: &Rc<RefCell<State>>) {
             let state = Rc::clone(state);
             Closure::<dyn Fn()>::new(move || {
                 let borrowed_state = state.borrow();
-                let Some(backup_buf) = borrowed_state.gba.as_ref().unwrap().cart.backup_buffer()
-                else {
+                let runner = borrowed_state.runner.borrow();
+                let Some(backup_buf) = runner.gba.as_ref().unwrap().cart.backup_buffer() else {
                     // Possible if backup type is EEPROM and its size is currently unknown.
                     alert(
                         &borrowed_state.window,
@@ -517,6 +704,11 @@ pub fn main() {
     panic::set_hook(Box::new(console_error_panic_hook::hook));
     console_log::init_with_level(Level::Info)
         .unwrap_or_else(|e| eprintln!("failed to init console logger: {e}"));
+}
 
+/// Runs the Web Memetendo page (`www/index.html`). Pages embedding the emulator should use the
+/// `Memetendo` class instead.
+#[wasm_bindgen(js_name = runPage)]
+pub fn run_page() {
     wasm_bindgen_futures::spawn_local(memetendo_main());
 }
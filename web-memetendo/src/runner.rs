@@ -0,0 +1,355 @@
+//! Emulation, rendering and audio plumbing shared by the Web Memetendo page and the embedding API.
+//! Nothing here looks up elements of the page; the canvas to draw to and callbacks are given by the
+//! caller instead.
+
+use std::{
+    cell::RefCell,
+    mem::take,
+    rc::{Rc, Weak},
+    time::Duration,
+};
+
+use anyhow::{Context, Result};
+use js_sys::Reflect;
+use libmemetendo::{
+    gba::Gba,
+    keypad::Keypad,
+    util::{
+        frame_skip,
+        video::{FrameBlender, FrameBuffer, PowerSaver},
+        FrameLimiter,
+    },
+    video::{self, HBLANK_DOT, VBLANK_DOT},
+};
+use wasm_bindgen::{prelude::*, Clamped, JsCast};
+use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement, ImageData, Window};
+
+use crate::{
+    audio::Audio,
+    stats::{FrameTimes, Stats},
+};
+
+pub const FRAME_RATE: f64 = 59.737;
+pub const FRAME_DURATION_MS: f64 = 1000.0 / FRAME_RATE;
+
+pub struct VideoCallback {
+    canvas_ctx: CanvasRenderingContext2d,
+    new_frame: bool,
+    frame_skipping: bool,
+    /// Keypad state to draw as an overlay, if enabled.
+    pub input_overlay: Option<Keypad>,
+    /// Blends skipped frames into shown frames, if enabled.
+    pub frame_blender: Option<FrameBlender>,
+    /// Decides which frames are drawn to the canvas while idle, if enabled.
+    pub power_saver: Option<PowerSaver>,
+    pub buf: FrameBuffer<4>,
+}
+
+impl video::Callback for VideoCallback {
+    fn put_dot(&mut self, x: u8, y: u8, dot: video::Dot) {
+        let dot = match self.frame_blender {
+            Some(ref blender) => blender.apply(x, y, dot),
+            None => dot,
+        };
+        self.buf.put_dot(x, y, dot);
+    }
+
+    fn end_frame(&mut self, green_swap: bool) {
+        self.new_frame = true;
+        if self.frame_skipping {
+            if let Some(ref mut blender) = self.frame_blender {
+                blender.end_skipped_frame();
+            }
+            return;
+        }
+        if let Some(ref mut blender) = self.frame_blender {
+            blender.end_frame();
+        }
+        if green_swap {
+            self.buf.green_swap();
+        }
+        if let Some(keypad) = self.input_overlay {
+            self.buf.draw_keypad_overlay(keypad.pressed_keys());
+        }
+    }
+
+    fn is_frame_skipping(&self) -> bool {
+        self.frame_skipping
+    }
+
+    fn is_receiving_skipped_dots(&self) -> bool {
+        self.frame_blender.is_some()
+    }
+
+    fn put_skipped_dot(&mut self, x: u8, y: u8, dot: video::Dot) {
+        if let Some(ref mut blender) = self.frame_blender {
+            blender.put_skipped(x, y, dot);
+        }
+    }
+}
+
+impl VideoCallback {
+    fn new(canvas: &HtmlCanvasElement) -> Result<Self> {
+        let canvas_ctx = canvas
+            .get_context_with_context_options("2d", &*{
+                let options = js_sys::Object::new();
+                Reflect::set(&options, &"alpha".into(), &false.into()).unwrap();
+                Reflect::set(&options, &"desynchronized".into(), &true.into()).unwrap();
+                options
+            })
+            .unwrap()
+            .map(|ctx| ctx.dyn_into::<CanvasRenderingContext2d>().unwrap())
+            .context("failed to get 2D canvas rendering context")?;
+
+        Ok(Self {
+            canvas_ctx,
+            new_frame: false,
+            frame_skipping: false,
+            input_overlay: None,
+            frame_blender: None,
+            power_saver: None,
+            buf: FrameBuffer::new(0xff),
+        })
+    }
+
+    fn clear(&self) {
+        self.canvas_ctx
+            .clear_rect(0.0, 0.0, HBLANK_DOT.into(), VBLANK_DOT.into());
+    }
+
+    /// Draws the frame that just ended to the canvas, unless the power saver decides otherwise
+    /// given whether it had any sound.
+    fn present(&mut self, audible: bool) {
+        if let Some(ref mut saver) = self.power_saver {
+            if !saver.end_frame(&self.buf, audible) {
+                return;
+            }
+        }
+
+        let image_data = ImageData::new_with_u8_clamped_array_and_sh(
+            Clamped(&self.buf.0),
+            HBLANK_DOT.into(),
+            VBLANK_DOT.into(),
+        )
+        .unwrap();
+        self.canvas_ctx
+            .put_image_data(&image_data, 0.0, 0.0)
+            .unwrap();
+    }
+}
+
+/// Paces emulation to the GBA's frame rate when driven by `requestAnimationFrame` callbacks.
+struct FramePacer {
+    limiter: FrameLimiter,
+    frame_times: FrameTimes,
+}
+
+impl Default for FramePacer {
+    fn default() -> Self {
+        Self {
+            limiter: FrameLimiter::new(
+                frame_skip::Mode::default(),
+                Duration::from_secs_f64(FRAME_DURATION_MS / 1000.0),
+            ),
+            frame_times: FrameTimes::default(),
+        }
+    }
+}
+
+impl FramePacer {
+    /// Emulates the frames due at time `ms` (from `requestAnimationFrame`), skipping the rendering
+    /// of some of them according to `frame_skip_mode` if we've fallen behind. Only the first frame
+    /// is rendered.
+    fn run(
+        &mut self,
+        ms: f64,
+        frame_skip_mode: frame_skip::Mode,
+        gba: &mut Gba,
+        video_cb: &mut VideoCallback,
+        audio: &mut Audio,
+    ) {
+        let now = Duration::from_secs_f64(ms.max(0.0) / 1000.0);
+        if !self.limiter.is_frame_due(now) {
+            return;
+        }
+
+        self.limiter.frame_skip.mode = frame_skip_mode;
+        let performance = web_sys::window().unwrap().performance().unwrap();
+        loop {
+            video_cb.frame_skipping = self.limiter.start_frame(now);
+            let start_ms = performance.now();
+            while !take(&mut video_cb.new_frame) {
+                gba.step(video_cb, audio);
+            }
+            if !video_cb.frame_skipping {
+                video_cb.present(audio.take_audible());
+            }
+            audio.queue_samples();
+            // This includes drawing the frame to the canvas.
+            let frame_ms = (performance.now() - start_ms).max(0.0);
+            self.frame_times.push(frame_ms);
+            if !self
+                .limiter
+                .end_frame(now, Duration::from_secs_f64(frame_ms / 1000.0))
+            {
+                break;
+            }
+        }
+    }
+
+    /// Resumes pacing from the next frame, rather than catching up on the frames missed while
+    /// emulation was paused.
+    fn reset(&mut self) {
+        self.limiter.reset();
+    }
+
+    /// Statistics for the frames emulated by time `ms`, if a second has passed since they were
+    /// last returned; see `FrameLimiter::poll_fps`.
+    fn poll_stats(&mut self, ms: f64, audio: &mut Audio) -> Option<Stats> {
+        let fps = self
+            .limiter
+            .poll_fps(Duration::from_secs_f64(ms.max(0.0) / 1000.0))?;
+
+        Some(Stats {
+            fps,
+            avg_frame_ms: self.frame_times.take_average(),
+            audio: audio.take_health(),
+        })
+    }
+}
+
+pub type StatsCallback = Box<dyn FnMut(&Gba, &Stats)>;
+
+/// Runs a `Gba` while started, drawing its frames to a canvas and playing its audio, paced by
+/// `requestAnimationFrame` callbacks.
+pub struct WebRunner {
+    window: Window,
+    pub video_cb: VideoCallback,
+    /// Needed to run, but given by the caller, as browsers only allow audio to start playing after
+    /// user interaction.
+    pub audio: Option<Audio>,
+    pub gba: Option<Gba>,
+    pacer: FramePacer,
+    updater: Option<Closure<dyn FnMut(f64)>>,
+    update_scheduled: bool,
+    running: bool,
+    pub frame_skip_mode: frame_skip::Mode,
+    audio_filter: bool,
+    /// Called with the statistics of each second of emulation, if set.
+    pub on_stats: Option<StatsCallback>,
+}
+
+impl WebRunner {
+    /// Creates a runner drawing to `canvas`, which should be 240x160 in size.
+    pub fn new(window: &Window, canvas: &HtmlCanvasElement) -> Result<Rc<RefCell<Self>>> {
+        let runner = Rc::new(RefCell::new(Self {
+            window: window.clone(),
+            video_cb: VideoCallback::new(canvas)?,
+            audio: None,
+            gba: None,
+            pacer: FramePacer::default(),
+            updater: None,
+            update_scheduled: false,
+            running: false,
+            frame_skip_mode: frame_skip::Mode::default(),
+            audio_filter: false,
+            on_stats: None,
+        }));
+
+        // Hold a weak reference, so the runner can be freed by its owner.
+        let weak_runner = Rc::downgrade(&runner);
+        runner.borrow_mut().updater = Some(Closure::new(move |ms: f64| {
+            if let Some(runner) = Weak::upgrade(&weak_runner) {
+                runner.borrow_mut().update(ms);
+            }
+        }));
+
+        Ok(runner)
+    }
+
+    /// Replaces the system to run with `gba`, clearing the canvas. Emulation continues if started.
+    pub fn power_on(&mut self, mut gba: Gba) {
+        gba.audio.output_filter.enabled = self.audio_filter;
+        self.gba = Some(gba);
+        self.video_cb.clear();
+        self.pacer = FramePacer::default();
+    }
+
+    /// Starts or resumes emulation, if there's a system to run and audio to play it with. Returns
+    /// whether emulation is running.
+    pub fn start(&mut self) -> bool {
+        if self.gba.is_none() {
+            return false;
+        }
+        let Some(ref audio) = self.audio else {
+            return false;
+        };
+
+        audio.resume();
+        self.running = true;
+        self.schedule_update();
+        true
+    }
+
+    /// Pauses emulation and audio until `Self::start` is called again.
+    pub fn pause(&mut self) {
+        self.running = false;
+        if let Some(ref audio) = self.audio {
+            audio.suspend();
+        }
+        self.pacer.reset();
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.running
+    }
+
+    /// Enables or disables filtering the audio output to sound closer to real hardware, also for
+    /// systems given to `Self::power_on` later.
+    pub fn set_audio_filter(&mut self, enabled: bool) {
+        self.audio_filter = enabled;
+        if let Some(ref mut gba) = self.gba {
+            gba.audio.output_filter.enabled = enabled;
+        }
+    }
+
+    fn schedule_update(&mut self) {
+        if self.update_scheduled {
+            return;
+        }
+
+        self.window
+            .request_animation_frame(self.updater.as_ref().unwrap().as_ref().unchecked_ref())
+            .unwrap();
+        self.update_scheduled = true;
+    }
+
+    fn update(&mut self, ms: f64) {
+        self.update_scheduled = false;
+        let Self {
+            gba: Some(ref mut gba),
+            audio: Some(ref mut audio),
+            ref mut video_cb,
+            ref mut pacer,
+            ref mut on_stats,
+            running: true,
+            frame_skip_mode,
+            ..
+        } = *self
+        else {
+            return;
+        };
+
+        if let Some(on_stats) = on_stats {
+            if let Some(stats) = pacer.poll_stats(ms, audio) {
+                on_stats(gba, &stats);
+            }
+        }
+        if video_cb.input_overlay.is_some() {
+            video_cb.input_overlay = Some(gba.keypad);
+        }
+
+        pacer.run(ms, frame_skip_mode, gba, video_cb, audio);
+        self.schedule_update();
+    }
+}
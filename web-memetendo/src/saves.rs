@@ -0,0 +1,88 @@
+//! Persistence of games' saves in `IndexedDB`.
+
+use std::{collections::HashMap, io};
+
+use js_sys::Uint8Array;
+use libmemetendo::storage;
+use log::warn;
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{IdbTransactionMode, Window};
+
+use crate::idb::{open_db, wait};
+
+/// Name of the `IndexedDB` object store for saves.
+pub const STORE_NAME: &str = "saves";
+
+/// Stores a game's saves in `IndexedDB`. As it's asynchronous, the saves are loaded up-front by
+/// `Self::load`, and writes are persisted in the background (failures are only logged).
+pub struct Storage {
+    window: Window,
+    /// Identifies the game the saves are for.
+    game_key: String,
+    cache: HashMap<String, Vec<u8>>,
+}
+
+impl Storage {
+    /// Loads the saves of the game identified by `game_key` (e.g: from a hash of its ROM).
+    pub async fn load(window: &Window, game_key: String) -> Result<Self, JsValue> {
+        let db = open_db(window).await?;
+        let store = db
+            .transaction_with_str_and_mode(STORE_NAME, IdbTransactionMode::Readonly)?
+            .object_store(STORE_NAME)?;
+
+        let mut cache = HashMap::new();
+        for name in [storage::BACKUP, storage::STATE] {
+            let value = wait(&store.get(&key(&game_key, name).into())?).await?;
+            if let Ok(array) = value.dyn_into::<Uint8Array>() {
+                cache.insert(name.to_string(), array.to_vec());
+            }
+        }
+        db.close();
+
+        Ok(Self {
+            window: window.clone(),
+            game_key,
+            cache,
+        })
+    }
+}
+
+impl storage::Backend for Storage {
+    fn read(&mut self, name: &str) -> io::Result<Option<Vec<u8>>> {
+        Ok(self.cache.get(name).cloned())
+    }
+
+    fn write(&mut self, name: &str, data: &[u8]) -> io::Result<()> {
+        // Backups are flushed whenever the page is hidden, but often haven't changed.
+        if self.cache.get(name).is_some_and(|buf| buf == data) {
+            return Ok(());
+        }
+        self.cache.insert(name.to_string(), data.to_vec());
+
+        let window = self.window.clone();
+        let key = key(&self.game_key, name);
+        let array = Uint8Array::from(data);
+        wasm_bindgen_futures::spawn_local(async move {
+            if let Err(e) = put(&window, &key, &array).await {
+                warn!("failed to store {key}: {e:?}");
+            }
+        });
+
+        Ok(())
+    }
+}
+
+fn key(game_key: &str, name: &str) -> String {
+    format!("{game_key}/{name}")
+}
+
+async fn put(window: &Window, key: &str, value: &JsValue) -> Result<(), JsValue> {
+    let db = open_db(window).await?;
+    let store = db
+        .transaction_with_str_and_mode(STORE_NAME, IdbTransactionMode::Readwrite)?
+        .object_store(STORE_NAME)?;
+    wait(&store.put_with_key(value, &key.into())?).await?;
+    db.close();
+
+    Ok(())
+}
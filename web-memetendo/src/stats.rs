@@ -0,0 +1,89 @@
+//! Performance statistics for the stats panel, so users can tell whether their device keeps up.
+
+use std::fmt::{self, Display, Formatter};
+
+use libmemetendo::util::frame_limiter::Fps;
+
+use crate::{
+    audio,
+    runner::{FRAME_DURATION_MS, FRAME_RATE},
+};
+
+/// Accumulates the time taken to emulate frames, for averaging.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct FrameTimes {
+    total_ms: f64,
+    count: u32,
+}
+
+impl FrameTimes {
+    pub fn push(&mut self, ms: f64) {
+        self.total_ms += ms;
+        self.count += 1;
+    }
+
+    /// Returns the average time of the frames pushed since the last call, if any, in milliseconds.
+    pub fn take_average(&mut self) -> Option<f64> {
+        let times = std::mem::take(self);
+        (times.count > 0).then(|| times.total_ms / f64::from(times.count))
+    }
+}
+
+/// Statistics collected over a second.
+#[derive(Debug, Copy, Clone)]
+pub struct Stats {
+    pub fps: Fps,
+    /// Average time taken to emulate (and render) a frame, in milliseconds.
+    pub avg_frame_ms: Option<f64>,
+    pub audio: Option<audio::Health>,
+}
+
+impl Stats {
+    /// Whether emulation ran noticeably slower than real-time.
+    #[must_use]
+    pub fn is_slow(&self) -> bool {
+        // Frames are counted over whole seconds, so allow for one frame falling in the next.
+        f64::from(self.fps.emulated) < FRAME_RATE - 1.0
+    }
+}
+
+impl Display for Stats {
+    /// Formats as one line for each statistic.
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "Emulated FPS: {} / {FRAME_RATE:.1}", self.fps.emulated)?;
+        if self.is_slow() {
+            write!(f, " (slowdown!)")?;
+        }
+        writeln!(f)?;
+        writeln!(
+            f,
+            "Skipped frames: {}",
+            self.fps.emulated.saturating_sub(self.fps.rendered)
+        )?;
+
+        write!(f, "Frame time: ")?;
+        if let Some(ms) = self.avg_frame_ms {
+            write!(
+                f,
+                "{ms:.1} ms / {FRAME_DURATION_MS:.1} ms budget ({:.0}%)",
+                100.0 * ms / FRAME_DURATION_MS
+            )?;
+        } else {
+            write!(f, "-")?;
+        }
+        writeln!(f)?;
+
+        write!(f, "Audio buffer: ")?;
+        if let Some(health) = self.audio {
+            write!(f, "{:.0} ms", health.buffered_ms)?;
+            if health.underruns > 0 {
+                let plural = if health.underruns == 1 { "" } else { "s" };
+                write!(f, " ({} underrun{plural})", health.underruns)?;
+            }
+        } else {
+            write!(f, "muted")?;
+        }
+
+        Ok(())
+    }
+}
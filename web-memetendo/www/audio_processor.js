@@ -13,6 +13,10 @@ registerProcessor("audio-processor",
                 new Float32Array(this.cap),
                 new Float32Array(this.cap)
             ];
+            // Number of times we ran out of samples since the last report,
+            // counted only after the first samples are queued.
+            this.underruns = 0;
+            this.started = false;
         }
 
         // allSamples is a Float32Array of all the samples for each of the two
@@ -56,11 +60,20 @@ registerProcessor("audio-processor",
 
             this.startIdx = (this.startIdx + replaceLen) % this.cap;
             this.len += appendLen;
+
+            // Report the buffer's health: the milliseconds of audio buffered,
+            // and the underruns since the last report.
+            this.started = true;
+            this.port.postMessage([1000 * this.len / sampleRate, this.underruns]);
+            this.underruns = 0;
         }
 
         process(inputs, outputs, params) {
             const chans = outputs[0];
             const samplesCopyLen = Math.min(chans[0].length, this.len);
+            if (this.started && samplesCopyLen < chans[0].length) {
+                ++this.underruns;
+            }
             const copy1Len = Math.min(samplesCopyLen, this.cap - this.startIdx);
 
             for (let i = 0; i < 2; ++i) {